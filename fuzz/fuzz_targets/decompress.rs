@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes to each of the in-tree decompressors, which must
+//! fail cleanly rather than panic, loop or exhaust memory.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nbt::{Blob, Compression, Endianness, ReadOptions};
+
+fuzz_target!(|data: &[u8]| {
+    for &compression in &[Compression::Gzip, Compression::Zlib, Compression::Lz4, Compression::Zstd] {
+        // Limits keep a small input from declaring a huge allocation.
+        let options = ReadOptions::new(Endianness::BigEndian)
+            .compression(compression)
+            .max_string_len(1 << 16)
+            .max_list_len(1 << 16)
+            .max_array_len(1 << 16);
+        let _ = Blob::from_reader_with(&mut &data[..], &options);
+    }
+});
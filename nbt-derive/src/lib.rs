@@ -0,0 +1,439 @@
+//! Derive macros for the `ToNbt` and `FromNbt` traits of the `nbt` crate.
+//! `NbtCompound` derives both at once.
+//!
+//! These are re-exported by `nbt` when its `derive` feature is enabled; see the
+//! documentation of `nbt::mapping` for the supported attributes.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Data, DeriveInput, Fields, GenericArgument, Ident, LitStr, PathArguments, Type};
+
+#[proc_macro_derive(ToNbt, attributes(nbt))]
+pub fn derive_to_nbt(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_to_nbt(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromNbt, attributes(nbt))]
+pub fn derive_from_nbt(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_from_nbt(&input)
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+#[proc_macro_derive(NbtCompound, attributes(nbt))]
+pub fn derive_nbt_compound(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand_to_nbt(&input)
+        .and_then(|to| {
+            let from = expand_from_nbt(&input)?;
+            Ok(quote!(#to #from))
+        })
+        .unwrap_or_else(compile_error)
+        .into()
+}
+
+/// Converts `err` into `compile_error!` invocations.
+///
+/// `syn::Error::into_compile_error()` refers to `::core`, which is not in
+/// scope for crates using the 2015 edition.
+fn compile_error(err: syn::Error) -> TokenStream2 {
+    err.into_iter()
+        .map(|e| {
+            let msg = e.to_string();
+            quote_spanned!(e.span()=> compile_error!(#msg);)
+        })
+        .collect()
+}
+
+/// A struct field, along with its parsed `#[nbt(...)]` attributes.
+struct Field<'a> {
+    ident: &'a Ident,
+    /// The key used in the Compound.
+    name: LitStr,
+    /// The `T` in a field of type `Option<T>`.
+    option: Option<&'a Type>,
+    skip: bool,
+    array: bool,
+    /// The marker type in `nbt::mapping::tag` for a `tag = "..."` override.
+    tag: Option<TokenStream2>,
+    default: Option<TokenStream2>,
+}
+
+impl<'a> Field<'a> {
+    fn parse(field: &'a syn::Field) -> syn::Result<Field<'a>> {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut parsed = Field {
+            ident,
+            name: LitStr::new(&ident.to_string(), ident.span()),
+            option: option_inner(&field.ty),
+            skip: false,
+            array: false,
+            tag: None,
+            default: None,
+        };
+        let mut renamed = false;
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("nbt") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if renamed {
+                        return Err(meta.error("duplicate `rename` attribute"));
+                    }
+                    parsed.name = meta.value()?.parse()?;
+                    renamed = true;
+                } else if meta.path.is_ident("skip") {
+                    parsed.skip = true;
+                } else if meta.path.is_ident("array") {
+                    parsed.array = true;
+                } else if meta.path.is_ident("tag") {
+                    if parsed.tag.is_some() {
+                        return Err(meta.error("duplicate `tag` attribute"));
+                    }
+                    let name: LitStr = meta.value()?.parse()?;
+                    parsed.tag = Some(tag_marker(&name)?);
+                } else if meta.path.is_ident("default") {
+                    if parsed.default.is_some() {
+                        return Err(meta.error("duplicate `default` attribute"));
+                    }
+                    if meta.input.peek(syn::Token![=]) {
+                        let path: LitStr = meta.value()?.parse()?;
+                        let path: syn::ExprPath = path.parse()?;
+                        parsed.default = Some(quote!(#path()));
+                    } else {
+                        parsed.default = Some(quote!(::std::default::Default::default()));
+                    }
+                } else {
+                    return Err(meta.error("unknown `nbt` attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if parsed.skip && (renamed || parsed.array || parsed.tag.is_some() || parsed.default.is_some()) {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`skip` cannot be combined with other `nbt` attributes",
+            ));
+        }
+        if parsed.array && parsed.tag.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`array` cannot be combined with `tag`",
+            ));
+        }
+        if parsed.option.is_some() && parsed.default.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`default` has no effect on an `Option` field",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// An expression for the type ID of the (non-optional) value `v`.
+    fn tag_id(&self, v: &TokenStream2) -> TokenStream2 {
+        if let Some(ref tag) = self.tag {
+            quote!(::nbt::NbtTagged::<#tag>::tagged_id(#v))
+        } else if self.array {
+            quote!(::nbt::NbtArray::array_tag_id(#v))
+        } else {
+            quote!(::nbt::ToNbt::tag_id(#v))
+        }
+    }
+
+    fn to_value(&self, v: &TokenStream2) -> TokenStream2 {
+        if let Some(ref tag) = self.tag {
+            quote!(::nbt::NbtTagged::<#tag>::to_tagged_value(#v))
+        } else if self.array {
+            quote!(::nbt::NbtArray::to_array_value(#v))
+        } else {
+            quote!(::nbt::ToNbt::to_value(#v))
+        }
+    }
+
+    fn to_raw_writer(&self, v: &TokenStream2) -> TokenStream2 {
+        if let Some(ref tag) = self.tag {
+            quote!(::nbt::NbtTagged::<#tag>::to_tagged_raw_writer(#v, dst))
+        } else if self.array {
+            quote!(::nbt::NbtArray::to_array_raw_writer(#v, dst))
+        } else {
+            quote!(::nbt::ToNbt::to_raw_writer(#v, dst))
+        }
+    }
+
+    fn decode_value(&self, v: &TokenStream2) -> TokenStream2 {
+        if let Some(ref tag) = self.tag {
+            quote!(::nbt::NbtTagged::<#tag>::from_tagged_value(#v))
+        } else if self.array {
+            quote!(::nbt::NbtArray::from_array_value(#v))
+        } else {
+            quote!(::nbt::FromNbt::from_value(#v))
+        }
+    }
+
+    fn decode_raw(&self, id: &TokenStream2) -> TokenStream2 {
+        if let Some(ref tag) = self.tag {
+            quote!(::nbt::NbtTagged::<#tag>::from_tagged_raw_reader(#id, src))
+        } else if self.array {
+            quote!(::nbt::NbtArray::from_array_raw_reader(#id, src))
+        } else {
+            quote!(::nbt::FromNbt::from_raw_reader(#id, src))
+        }
+    }
+
+    /// An expression producing the field's value when it is missing.
+    fn missing(&self) -> TokenStream2 {
+        let name = &self.name;
+        match (self.option, &self.default) {
+            (Some(_), _) => quote!(::std::option::Option::None),
+            (None, Some(default)) => default.clone(),
+            (None, None) => quote! {
+                return ::std::result::Result::Err(
+                    ::nbt::Error::MissingField(::std::string::String::from(#name)))
+            },
+        }
+    }
+}
+
+/// Returns the marker type for the tag named by a `tag = "..."` attribute.
+fn tag_marker(name: &LitStr) -> syn::Result<TokenStream2> {
+    let marker = match &name.value()[..] {
+        "byte" => quote!(Byte),
+        "short" => quote!(Short),
+        "int" => quote!(Int),
+        "long" => quote!(Long),
+        "float" => quote!(Float),
+        "double" => quote!(Double),
+        "byte_array" => quote!(ByteArray),
+        "int_array" => quote!(IntArray),
+        "long_array" => quote!(LongArray),
+        _ => return Err(syn::Error::new_spanned(name, "unknown NBT tag")),
+    };
+    Ok(quote!(::nbt::mapping::tag::#marker))
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let path = match *ty {
+        Type::Path(ref p) if p.qself.is_none() => &p.path,
+        _ => return None,
+    };
+    let last = path.segments.last()?;
+    if last.ident != "Option" {
+        return None;
+    }
+    match last.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                GenericArgument::Type(ref inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<Field<'_>>> {
+    for attr in &input.attrs {
+        if attr.path().is_ident("nbt") {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`nbt` attributes are only supported on fields",
+            ));
+        }
+    }
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "NBT derives only support structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "NBT derives only support structs with named fields",
+            ))
+        }
+    };
+    fields.iter().map(Field::parse).collect()
+}
+
+/// Adds `bound` for every type parameter of the input.
+fn bounded_generics(input: &DeriveInput, bound: TokenStream2) -> syn::Generics {
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(#bound));
+    }
+    generics
+}
+
+fn expand_to_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let fields: Vec<&Field> = fields.iter().filter(|f| !f.skip).collect();
+    let ident = &input.ident;
+    let generics = bounded_generics(input, quote!(::nbt::ToNbt));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let v = quote!(v);
+    let inserts = fields.iter().map(|f| {
+        let field = f.ident;
+        let name = &f.name;
+        let to_value = f.to_value(&v);
+        let insert = quote!(map.insert(::std::string::String::from(#name), #to_value););
+        if f.option.is_some() {
+            quote!(if let ::std::option::Option::Some(ref v) = self.#field { #insert })
+        } else {
+            quote!({ let v = &self.#field; #insert })
+        }
+    });
+    let writes = fields.iter().map(|f| {
+        let field = f.ident;
+        let name = &f.name;
+        let tag_id = f.tag_id(&v);
+        let to_raw_writer = f.to_raw_writer(&v);
+        let write = quote! {
+            dst.write_header(#tag_id, #name)?;
+            #to_raw_writer?;
+        };
+        if f.option.is_some() {
+            quote!(if let ::std::option::Option::Some(ref v) = self.#field { #write })
+        } else {
+            quote!({ let v = &self.#field; #write })
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::nbt::ToNbt for #ident #ty_generics #where_clause {
+            fn tag_id(&self) -> i8 { 0x0a }
+
+            fn to_value(&self) -> ::nbt::Value {
+                let mut map = ::nbt::Map::new();
+                #(#inserts)*
+                ::nbt::Value::Compound(map)
+            }
+
+            fn to_raw_writer<W>(&self, dst: &mut ::nbt::raw::RawWriter<W>) -> ::nbt::Result<()>
+                where W: ::std::io::Write,
+            {
+                dst.begin_compound();
+                #(#writes)*
+                dst.close_nbt()
+            }
+        }
+    })
+}
+
+fn expand_from_nbt(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = parse_fields(input)?;
+    let ident = &input.ident;
+    let generics = bounded_generics(input, quote!(::nbt::FromNbt));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let v = quote!(v);
+    let from_map = fields.iter().map(|f| {
+        let field = f.ident;
+        if f.skip {
+            return quote!(#field: ::std::default::Default::default());
+        }
+        let name = &f.name;
+        let from_value = f.decode_value(&v);
+        let missing = f.missing();
+        let found = if f.option.is_some() {
+            quote!(::std::option::Option::Some(#from_value?))
+        } else {
+            quote!(#from_value?)
+        };
+        quote! {
+            #field: match map.get(#name) {
+                ::std::option::Option::Some(v) => #found,
+                ::std::option::Option::None => #missing,
+            }
+        }
+    });
+
+    let read: Vec<&Field> = fields.iter().filter(|f| !f.skip).collect();
+    let slots: Vec<Ident> = read
+        .iter()
+        .map(|f| format_ident!("__field_{}", f.ident))
+        .collect();
+    let tag = quote!(tag);
+    let arms = read.iter().zip(&slots).map(|(f, slot)| {
+        let name = &f.name;
+        let from_raw_reader = f.decode_raw(&tag);
+        quote!(#name => #slot = ::std::option::Option::Some(#from_raw_reader?),)
+    });
+    let from_slots = fields.iter().map(|f| {
+        let field = f.ident;
+        if f.skip {
+            return quote!(#field: ::std::default::Default::default());
+        }
+        let slot = format_ident!("__field_{}", f.ident);
+        if f.option.is_some() {
+            return quote!(#field: #slot);
+        }
+        let missing = f.missing();
+        quote! {
+            #field: match #slot {
+                ::std::option::Option::Some(v) => v,
+                ::std::option::Option::None => #missing,
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::nbt::FromNbt for #ident #ty_generics #where_clause {
+            fn from_value(value: &::nbt::Value) -> ::nbt::Result<Self> {
+                let map = match *value {
+                    ::nbt::Value::Compound(ref map) => map,
+                    ref other => return ::std::result::Result::Err(
+                        ::nbt::Error::TagMismatch(other.id(), 0x0a)),
+                };
+                ::std::result::Result::Ok(#ident {
+                    #(#from_map,)*
+                })
+            }
+
+            fn from_raw_reader<R>(id: i8, src: &mut ::nbt::raw::RawReader<R>) -> ::nbt::Result<Self>
+                where R: ::std::io::Read,
+            {
+                if id != 0x0a {
+                    return ::std::result::Result::Err(::nbt::Error::TagMismatch(id, 0x0a));
+                }
+                #(let mut #slots = ::std::option::Option::None;)*
+                src.begin_nested()?;
+                loop {
+                    let (tag, name) = src.emit_next_header()?;
+                    if tag == 0x00 {
+                        break;
+                    }
+                    match &name[..] {
+                        #(#arms)*
+                        // Unknown fields are skipped.
+                        _ => { ::nbt::Value::from_raw_reader(tag, src)?; }
+                    }
+                }
+                src.end_nested();
+                ::std::result::Result::Ok(#ident {
+                    #(#from_slots,)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,7 @@
+extern crate trybuild;
+
+#[test]
+fn attribute_misuse() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
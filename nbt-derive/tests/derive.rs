@@ -0,0 +1,250 @@
+//! Round-trip tests for the `ToNbt` and `FromNbt` derives, comparing their
+//! output with equivalent Serde-derived types.
+
+extern crate nbt;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+use std::collections::HashMap;
+use std::io;
+
+use nbt::{Endianness, Error, FromNbt, Map, NbtCompound, ToNbt, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToNbt, FromNbt)]
+struct Item {
+    #[serde(rename = "id")]
+    #[nbt(rename = "id")]
+    name: String,
+    #[serde(rename = "Count")]
+    #[nbt(rename = "Count")]
+    count: i8,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, ToNbt, FromNbt)]
+struct Player {
+    health: f32,
+    score: i32,
+    xp: i64,
+    on_ground: bool,
+    pos: Vec<f64>,
+    inventory: Vec<Item>,
+    selected: Item,
+    attributes: HashMap<String, i16>,
+}
+
+fn player() -> Player {
+    Player {
+        health: 20.0,
+        score: 1234,
+        xp: -3,
+        on_ground: true,
+        pos: vec![1.5, 64.0, -3.25],
+        inventory: vec![
+            Item { name: "minecraft:stone".to_string(), count: 64 },
+            Item { name: "minecraft:dirt".to_string(), count: 1 },
+        ],
+        selected: Item { name: "minecraft:air".to_string(), count: 0 },
+        attributes: HashMap::new(),
+    }
+}
+
+#[test]
+fn roundtrip_matches_serde() {
+    let player = player();
+
+    let mut derived = Vec::new();
+    player.to_nbt_writer(&mut derived, "", Endianness::BigEndian).unwrap();
+    let mut serialized = Vec::new();
+    nbt::to_writer(&mut serialized, &player, None, Endianness::BigEndian).unwrap();
+    assert_eq!(derived, serialized);
+
+    let read = Player::from_nbt_reader(&mut io::Cursor::new(&derived), Endianness::BigEndian).unwrap();
+    assert_eq!(read, player);
+}
+
+#[test]
+fn value_roundtrip() {
+    let player = player();
+    let value = player.to_value();
+    match value {
+        Value::Compound(ref map) => {
+            assert_eq!(map["on_ground"], Value::Byte(1));
+            assert_eq!(map["selected"], Value::Compound(vec![
+                ("id".to_string(), Value::String("minecraft:air".to_string())),
+                ("Count".to_string(), Value::Byte(0)),
+            ].into_iter().collect()));
+        }
+        _ => panic!("expected a Compound"),
+    }
+    assert_eq!(Player::from_value(&value).unwrap(), player);
+}
+
+fn default_version() -> i32 {
+    3465
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Attributed {
+    #[nbt(array)]
+    uuid: Vec<i32>,
+    #[nbt(array)]
+    heights: Vec<i64>,
+    #[nbt(array)]
+    light: Option<Vec<i8>>,
+    bytes: Vec<i8>,
+    nested: Option<Item>,
+    #[nbt(default)]
+    tags: Vec<String>,
+    #[nbt(default = "default_version")]
+    version: i32,
+    #[nbt(skip)]
+    dirty: bool,
+}
+
+#[test]
+fn field_attributes() {
+    let attributed = Attributed {
+        uuid: vec![1, 2, 3, 4],
+        heights: vec![-1],
+        light: None,
+        bytes: vec![5],
+        nested: Some(Item { name: "x".to_string(), count: 2 }),
+        tags: Vec::new(),
+        version: 1,
+        dirty: true,
+    };
+
+    let value = attributed.to_value();
+    let map = match value {
+        Value::Compound(ref map) => map,
+        _ => panic!("expected a Compound"),
+    };
+    assert_eq!(map["uuid"], Value::IntArray(vec![1, 2, 3, 4]));
+    assert_eq!(map["heights"], Value::LongArray(vec![-1]));
+    assert_eq!(map["bytes"], Value::List(vec![Value::Byte(5)]));
+    assert!(!map.contains_key("light"));
+    assert!(!map.contains_key("dirty"));
+
+    let mut bytes = Vec::new();
+    attributed.to_nbt_writer(&mut bytes, "", Endianness::LittleEndian).unwrap();
+    let read = Attributed::from_nbt_reader(&mut io::Cursor::new(&bytes), Endianness::LittleEndian).unwrap();
+    assert_eq!(read, Attributed { dirty: false, ..attributed });
+
+    // Missing fields use their defaults.
+    let mut sparse = Map::new();
+    sparse.insert("uuid".to_string(), Value::IntArray(vec![]));
+    sparse.insert("heights".to_string(), Value::LongArray(vec![]));
+    sparse.insert("light".to_string(), Value::ByteArray(vec![7]));
+    sparse.insert("bytes".to_string(), Value::List(vec![]));
+    let read = Attributed::from_value(&Value::Compound(sparse)).unwrap();
+    assert_eq!(read.light, Some(vec![7]));
+    assert_eq!(read.nested, None);
+    assert_eq!(read.tags, Vec::<String>::new());
+    assert_eq!(read.version, 3465);
+}
+
+#[test]
+fn decoding_errors() {
+    let mut map = Map::new();
+    map.insert("id".to_string(), Value::String("x".to_string()));
+    assert_eq!(Item::from_value(&Value::Compound(map.clone())),
+               Err(Error::MissingField("Count".to_string())));
+
+    map.insert("Count".to_string(), Value::Int(1));
+    assert_eq!(Item::from_value(&Value::Compound(map)), Err(Error::TagMismatch(0x03, 0x01)));
+
+    assert_eq!(Item::from_value(&Value::Int(1)), Err(Error::TagMismatch(0x03, 0x0a)));
+}
+
+#[test]
+fn unknown_fields_are_skipped() {
+    let mut blob = nbt::Blob::new();
+    blob.insert("id", "minecraft:stone").unwrap();
+    blob.insert("Count", 3i8).unwrap();
+    blob.insert("Damage", Value::List(vec![Value::Short(1)])).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    let item = Item::from_nbt_reader(&mut io::Cursor::new(&bytes), Endianness::BigEndian).unwrap();
+    assert_eq!(item, Item { name: "minecraft:stone".to_string(), count: 3 });
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[test]
+fn generic_structs() {
+    let wrapper = Wrapper { inner: 5i16 };
+    assert_eq!(Wrapper::<i16>::from_value(&wrapper.to_value()).unwrap(), wrapper);
+}
+
+#[derive(Debug, PartialEq, NbtCompound)]
+struct Tagged {
+    #[nbt(tag = "int")]
+    level: i16,
+    #[nbt(tag = "double")]
+    speed: f32,
+    #[nbt(tag = "long")]
+    seed: Option<i32>,
+    #[nbt(tag = "int_array")]
+    uuid: Vec<i32>,
+}
+
+#[test]
+fn tag_overrides() {
+    let tagged = Tagged { level: 7, speed: 0.5, seed: Some(-1), uuid: vec![1, 2, 3, 4] };
+
+    let value = tagged.to_value();
+    let map = match value {
+        Value::Compound(ref map) => map,
+        _ => panic!("expected a Compound"),
+    };
+    assert_eq!(map["level"], Value::Int(7));
+    assert_eq!(map["speed"], Value::Double(0.5));
+    assert_eq!(map["seed"], Value::Long(-1));
+    assert_eq!(map["uuid"], Value::IntArray(vec![1, 2, 3, 4]));
+    assert_eq!(Tagged::from_value(&value).unwrap(), tagged);
+
+    let mut bytes = Vec::new();
+    tagged.to_nbt_writer(&mut bytes, "", Endianness::BigEndian).unwrap();
+    let read = Tagged::from_nbt_reader(&mut io::Cursor::new(&bytes), Endianness::BigEndian).unwrap();
+    assert_eq!(read, tagged);
+
+    // The stored tag must match the override, and its value must fit.
+    let mut map = map.clone();
+    map.insert("level".to_string(), Value::Short(7));
+    assert_eq!(Tagged::from_value(&Value::Compound(map.clone())), Err(Error::TagMismatch(0x02, 0x03)));
+    map.insert("level".to_string(), Value::Int(40000));
+    assert_eq!(Tagged::from_value(&Value::Compound(map)), Err(Error::OutOfRange(0x03)));
+
+
+    let mut blob = nbt::Blob::new();
+    blob.insert("level", 1i32).unwrap();
+    blob.insert("speed", 0.25f64).unwrap();
+    blob.insert("seed", 1i64 << 40).unwrap();
+    blob.insert("uuid", Value::IntArray(vec![])).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let read = Tagged::from_nbt_reader(&mut io::Cursor::new(&bytes), Endianness::BigEndian);
+    assert_eq!(read, Err(Error::OutOfRange(0x04)));
+}
+
+#[derive(Debug, PartialEq, ToNbt, FromNbt)]
+struct Node {
+    children: Vec<Node>,
+}
+
+#[test]
+fn depth_limit() {
+    let mut node = Node { children: vec![] };
+    for _ in 0..300 {
+        node = Node { children: vec![node] };
+    }
+    let mut bytes = Vec::new();
+    node.to_nbt_writer(&mut bytes, "", Endianness::BigEndian).unwrap();
+    assert_eq!(Node::from_nbt_reader(&mut io::Cursor::new(&bytes), Endianness::BigEndian),
+               Err(Error::DepthLimitExceeded(nbt::raw::DEFAULT_MAX_DEPTH)));
+}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(FromNbt)]
+struct Item {
+    #[nbt(default)]
+    name: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(ToNbt)]
+struct Item {
+    #[nbt(tag = "short")]
+    count: i32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(ToNbt)]
+struct Item {
+    #[nbt(rename = 5)]
+    name: String,
+}
+
+fn main() {}
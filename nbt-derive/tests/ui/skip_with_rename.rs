@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(FromNbt)]
+struct Item {
+    #[nbt(skip, rename = "id")]
+    name: String,
+}
+
+fn main() {}
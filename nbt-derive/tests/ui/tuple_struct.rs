@@ -0,0 +1,7 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(ToNbt)]
+struct Item(String);
+
+fn main() {}
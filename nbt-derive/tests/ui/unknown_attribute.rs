@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(ToNbt)]
+struct Item {
+    #[nbt(renamed = "id")]
+    name: String,
+}
+
+fn main() {}
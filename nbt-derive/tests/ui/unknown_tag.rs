@@ -0,0 +1,10 @@
+#[macro_use]
+extern crate nbt;
+
+#[derive(NbtCompound)]
+struct Item {
+    #[nbt(tag = "uint")]
+    count: i32,
+}
+
+fn main() {}
@@ -0,0 +1,96 @@
+//! Serde helpers that write sequences as NBT array tags.
+//!
+//! By default a `Vec<i32>` serializes as a `TAG_List` of `TAG_Int`. Vanilla
+//! fields such as UUIDs and heightmaps must be a `TAG_Int_Array` instead,
+//! which these modules produce when used with `#[serde(with = "...")]`:
+//!
+//! ```
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate nbt;
+//! # use nbt::Endianness;
+//! #[derive(Serialize, Deserialize)]
+//! struct Entity {
+//!     #[serde(with = "nbt::int_array")]
+//!     uuid: [i32; 4],
+//!     #[serde(with = "nbt::long_array")]
+//!     heightmap: Vec<i64>,
+//! }
+//!
+//! # fn main() {
+//! let entity = Entity { uuid: [1, 2, 3, 4], heightmap: vec![] };
+//! let mut bytes = Vec::new();
+//! nbt::to_writer(&mut bytes, &entity, None, Endianness::BigEndian).unwrap();
+//! assert_eq!(bytes[3], 0x0b); // TAG_Int_Array
+//! # }
+//! ```
+//!
+//! Other serializers see a tuple struct, which usually ends up as a plain
+//! sequence. Deserialization needs no help, since array tags already decode
+//! into any sequence type.
+
+use serde::{de, ser};
+use serde::ser::SerializeTupleStruct;
+
+pub(crate) const BYTE_ARRAY_TOKEN: &str = "$nbt::private::ByteArray";
+pub(crate) const INT_ARRAY_TOKEN: &str = "$nbt::private::IntArray";
+pub(crate) const LONG_ARRAY_TOKEN: &str = "$nbt::private::LongArray";
+
+/// The array tag requested by a tuple struct `name`, if any.
+pub(crate) fn array_tag(name: &str) -> Option<i8> {
+    match name {
+        BYTE_ARRAY_TOKEN => Some(0x07),
+        INT_ARRAY_TOKEN => Some(0x0b),
+        LONG_ARRAY_TOKEN => Some(0x0c),
+        _ => None,
+    }
+}
+
+fn serialize_array<T, S>(token: &'static str, array: &[T], serializer: S)
+                         -> Result<S::Ok, S::Error>
+    where T: ser::Serialize,
+          S: ser::Serializer,
+{
+    let mut state = serializer.serialize_tuple_struct(token, array.len())?;
+    for element in array {
+        state.serialize_field(element)?;
+    }
+    state.end()
+}
+
+macro_rules! array_module {
+    ($module:ident, $elem:ty, $token:ident, $tag:expr) => {
+        #[doc = concat!("Write a sequence of `", stringify!($elem), "` as a `", $tag,
+                        "` instead of a `TAG_List`,\nwith `#[serde(with = \"nbt::",
+                        stringify!($module), "\")]`.")]
+        pub mod $module {
+            use serde::{de, ser};
+
+            /// Serializes `array` as an array tag.
+            pub fn serialize<T, S>(array: &T, serializer: S) -> Result<S::Ok, S::Error>
+                where T: ?Sized + AsRef<[$elem]>,
+                      S: ser::Serializer,
+            {
+                super::serialize_array(super::$token, array.as_ref(), serializer)
+            }
+
+            /// Deserializes an array tag, or any other sequence.
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                where T: de::Deserialize<'de>,
+                      D: de::Deserializer<'de>,
+            {
+                super::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where T: de::Deserialize<'de>,
+          D: de::Deserializer<'de>,
+{
+    T::deserialize(deserializer)
+}
+
+array_module!(byte_array, i8, BYTE_ARRAY_TOKEN, "TAG_Byte_Array");
+array_module!(int_array, i32, INT_ARRAY_TOKEN, "TAG_Int_Array");
+array_module!(long_array, i64, LONG_ARRAY_TOKEN, "TAG_Long_Array");
@@ -0,0 +1,220 @@
+//! A command-line tool for inspecting and converting NBT files.
+//!
+//! Run `nbt-cli help` for usage. NBT input may be compressed with Gzip or
+//! zlib, or not at all, which is detected from its first bytes.
+
+extern crate nbt;
+extern crate serde_json;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::exit;
+
+use nbt::json::{self, JsonOptions};
+use nbt::{Blob, Compression, Endianness, Printer, WriteOptions};
+
+const USAGE: &str = "\
+usage: nbt-cli <command> [options] <input> [output]
+
+Reads <input>, or standard input if it is '-', and writes to [output], or
+standard output if it is '-' or missing.
+
+commands:
+  dump        print an NBT file as a tree
+  to-json     convert an NBT file to JSON
+  from-json   convert a JSON object to an NBT file
+  to-snbt     convert an NBT file to SNBT
+  from-snbt   convert an SNBT compound to an NBT file
+  convert     rewrite an NBT file with another byte order or compression
+  help        print this message
+
+options:
+  --endian be|le|net        the byte order of NBT input (default: be)
+  --out-endian be|le|net    the byte order of NBT output (default: --endian)
+  --compression gzip|zlib|none
+                            the compression of NBT output (default: none)
+  --compact                 write JSON or SNBT on a single line
+  --max-array <n>           dump arrays longer than <n> as only their length
+  --max-depth <n>           dump Lists and Compounds at most <n> levels deep
+  --color                   dump with ANSI colors
+  --longs-as-strings        write Longs as JSON strings
+";
+
+/// The parsed command line.
+struct Args {
+    command: String,
+    input: String,
+    output: Option<String>,
+    endian: Endianness,
+    out_endian: Option<Endianness>,
+    compression: Compression,
+    compact: bool,
+    longs_as_strings: bool,
+    printer: Printer,
+}
+
+/// A problem with the command line, or with running the command.
+enum Failure {
+    Usage(String),
+    Nbt(nbt::Error),
+}
+
+impl From<nbt::Error> for Failure {
+    fn from(e: nbt::Error) -> Failure {
+        Failure::Nbt(e)
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Failure {
+        Failure::Nbt(e.into())
+    }
+}
+
+fn parse_endian(arg: Option<String>) -> Result<Endianness, Failure> {
+    match arg.as_ref().map(|arg| &arg[..]) {
+        Some("be") => Ok(Endianness::BigEndian),
+        Some("le") => Ok(Endianness::LittleEndian),
+        Some("net") => Ok(Endianness::NetworkLittleEndian),
+        Some(other) => Err(Failure::Usage(format!("unknown byte order '{}'", other))),
+        None => Err(Failure::Usage("missing byte order".to_string())),
+    }
+}
+
+fn parse_count(arg: Option<String>) -> Result<usize, Failure> {
+    match arg {
+        Some(arg) => arg.parse().map_err(|_| Failure::Usage(format!("invalid count '{}'", arg))),
+        None => Err(Failure::Usage("missing count".to_string())),
+    }
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, Failure> {
+    let command = args.next().ok_or_else(|| Failure::Usage("missing command".to_string()))?;
+    let mut parsed = Args {
+        command,
+        input: String::new(),
+        output: None,
+        endian: Endianness::BigEndian,
+        out_endian: None,
+        compression: Compression::Uncompressed,
+        compact: false,
+        longs_as_strings: false,
+        printer: Printer::new(),
+    };
+    let mut paths = Vec::new();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--endian" => parsed.endian = parse_endian(args.next())?,
+            "--out-endian" => parsed.out_endian = Some(parse_endian(args.next())?),
+            "--compression" => parsed.compression = match args.next().as_ref().map(|arg| &arg[..]) {
+                Some("gzip") => Compression::Gzip,
+                Some("zlib") => Compression::Zlib,
+                Some("none") => Compression::Uncompressed,
+                Some(other) => return Err(Failure::Usage(format!("unknown compression '{}'", other))),
+                None => return Err(Failure::Usage("missing compression".to_string())),
+            },
+            "--compact" => parsed.compact = true,
+            "--longs-as-strings" => parsed.longs_as_strings = true,
+            "--max-array" => parsed.printer = parsed.printer.max_array_len(parse_count(args.next())?),
+            "--max-depth" => parsed.printer = parsed.printer.max_depth(parse_count(args.next())?),
+            "--color" => parsed.printer = parsed.printer.color(true),
+            "-" => paths.push(arg),
+            flag if flag.starts_with('-') => return Err(Failure::Usage(format!("unknown option '{}'", flag))),
+            _ => paths.push(arg),
+        }
+    }
+    let mut paths = paths.into_iter();
+    parsed.input = paths.next().ok_or_else(|| Failure::Usage("missing input".to_string()))?;
+    parsed.output = paths.next();
+    if paths.next().is_some() {
+        return Err(Failure::Usage("too many arguments".to_string()));
+    }
+    Ok(parsed)
+}
+
+fn open_input(path: &str) -> io::Result<Box<dyn Read>> {
+    if path == "-" {
+        Ok(Box::new(io::stdin()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+fn open_output(path: Option<&str>) -> io::Result<Box<dyn Write>> {
+    match path {
+        None | Some("-") => Ok(Box::new(io::stdout())),
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+    }
+}
+
+fn read_text(src: &mut dyn Read) -> io::Result<String> {
+    let mut text = String::new();
+    src.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+fn run(args: Args) -> Result<(), Failure> {
+    let mut src = open_input(&args.input)?;
+    let mut dst = open_output(args.output.as_ref().map(|path| &path[..]))?;
+    let write = WriteOptions::new(args.out_endian.unwrap_or(args.endian)).compression(args.compression);
+    let json_options = JsonOptions::new().longs_as_strings(args.longs_as_strings);
+
+    match &args.command[..] {
+        "dump" => {
+            let blob = Blob::from_reader_auto(&mut src, args.endian)?;
+            writeln!(dst, "{}", blob.display_with(&args.printer))?;
+        },
+        "to-json" => {
+            let blob = Blob::from_reader_auto(&mut src, args.endian)?;
+            let value = json::to_json(&blob.into_value(), &json_options);
+            let written = if args.compact {
+                serde_json::to_writer(&mut dst, &value)
+            } else {
+                serde_json::to_writer_pretty(&mut dst, &value)
+            };
+            written.map_err(io::Error::from)?;
+            writeln!(dst)?;
+        },
+        "from-json" => {
+            let parsed: serde_json::Value = serde_json::from_reader(src).map_err(io::Error::from)?;
+            let blob = Blob::from_value(json::from_json(&parsed, &json_options)?)?;
+            blob.to_writer_with(&mut dst, &write)?;
+        },
+        "to-snbt" => {
+            let blob = Blob::from_reader_auto(&mut src, args.endian)?;
+            let snbt = if args.compact { blob.to_snbt() } else { blob.to_snbt_pretty() };
+            writeln!(dst, "{}", snbt)?;
+        },
+        "from-snbt" => {
+            let blob = Blob::from_snbt(read_text(&mut src)?.trim())?;
+            blob.to_writer_with(&mut dst, &write)?;
+        },
+        "convert" => {
+            let blob = Blob::from_reader_auto(&mut src, args.endian)?;
+            blob.to_writer_with(&mut dst, &write)?;
+        },
+        other => return Err(Failure::Usage(format!("unknown command '{}'", other))),
+    }
+    dst.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let mut args = env::args().skip(1).peekable();
+    if let None | Some("help") | Some("--help") | Some("-h") = args.peek().map(|arg| &arg[..]) {
+        print!("{}", USAGE);
+        return;
+    }
+    match parse_args(args).and_then(run) {
+        Ok(()) => (),
+        Err(Failure::Usage(msg)) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            exit(2)
+        },
+        Err(Failure::Nbt(e)) => {
+            eprintln!("error: {}", e);
+            exit(1)
+        },
+    }
+}
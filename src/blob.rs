@@ -1,15 +1,36 @@
-use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::io;
-use std::ops::Index;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Index, IndexMut};
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::slice;
 
-use flate2::read::{GzDecoder, ZlibDecoder};
-use flate2::write::{GzEncoder, ZlibEncoder};
-use flate2::Compression;
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use flate::{self, read, GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder};
 
+use canonical;
 use error::{Error, Result};
-use raw::{Endianness, RawReader, RawWriter};
-use value::Value;
+use map::{self, Map};
+use options::{Compression, Decoder, ReadOptions, WriteOptions};
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use options::CompressionLevel;
+#[cfg(feature = "experimental_lz4")]
+use lz4::{Lz4Decoder, Lz4Encoder};
+use merge::{self, MergeStrategy};
+use patch::{self, NbtPatch};
+use path;
+use printer::{Printed, Printer};
+use raw::{self, Endianness, RawReader, RawWriter};
+use schema::{Schema, Violation};
+use snbt;
+use value::{DebugMap, FromValue, Value};
+#[cfg(feature = "experimental_zstd")]
+use zstd::{ZstdDecoder, ZstdEncoder};
 
 /// A generic, complete object in Named Binary Tag format.
 ///
@@ -22,6 +43,7 @@ use value::Value;
 /// (through Gzip or zlib compression) methods.
 ///
 /// ```rust
+/// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
 /// use nbt::{Blob, Value, Endianness};
 ///
 /// // Create a `Blob` from key/value pairs.
@@ -33,11 +55,21 @@ use value::Value;
 /// // Write a compressed binary representation to a byte array.
 /// let mut dst = Vec::new();
 /// nbt.to_zlib_writer(&mut dst, Endianness::BigEndian).unwrap();
+/// # }
 /// ```
-#[derive(Clone, Debug, PartialEq)]
+///
+/// Its `Display` and `Debug` output list entries sorted by name, unless the
+/// `preserve_order` feature keeps them in insertion order.
+#[derive(Clone, PartialEq, Eq)]
 pub struct Blob {
     title: String,
-    content: HashMap<String, Value>,
+    content: Map,
+}
+
+impl Default for Blob {
+    fn default() -> Blob {
+        Blob::new()
+    }
 }
 
 impl Blob {
@@ -45,7 +77,7 @@ impl Blob {
     pub fn new() -> Blob {
         Blob {
             title: "".to_string(),
-            content: HashMap::new(),
+            content: Map::new(),
         }
     }
 
@@ -56,16 +88,77 @@ impl Blob {
     {
         Blob {
             title: name.into(),
-            content: HashMap::new(),
+            content: Map::new(),
         }
     }
 
+    /// Converts the blob into a `Value::Compound` of its entries, so that it
+    /// can be nested inside another value. Its name is dropped.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Value};
+    ///
+    /// let mut item = Blob::new();
+    /// item.insert("id", "minecraft:diamond").unwrap();
+    /// item.insert("Count", 1i8).unwrap();
+    ///
+    /// let mut player = Blob::new();
+    /// player.insert("SelectedItem", item.clone()).unwrap();
+    /// assert_eq!(player["SelectedItem"], item.clone().into_value());
+    /// assert_eq!(Blob::from_value(player["SelectedItem"].clone()).unwrap(), item);
+    /// assert!(Blob::from_value(Value::Int(1)).is_err());
+    /// ```
+    pub fn into_value(self) -> Value {
+        Value::Compound(self.content)
+    }
+
+    /// Creates a blob with an empty name from the entries of a
+    /// `Value::Compound`, failing with `Error::TagMismatch` for any other
+    /// type.
+    pub fn from_value(value: Value) -> Result<Blob> {
+        match value {
+            Value::Compound(content) => Ok(Blob { title: "".to_string(), content }),
+            other => Err(Error::TagMismatch(other.id(), 0x0a)),
+        }
+    }
+
+    /// Reads an uncompressed `Blob` from the start of a buffer, such as a
+    /// network packet or an `mmap::Mmap`. Any bytes after it are ignored.
+    pub fn from_slice(src: &[u8], endian: Endianness) -> Result<Blob> {
+        let mut src = src;
+        Blob::from_reader(&mut src, endian)
+    }
+
     /// Extracts an `Blob` object from an `io::Read` source.
     pub fn from_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
     where
         R: io::Read,
     {
-        let mut src = RawReader::new(src, endian);
+        Blob::from_raw_reader(&mut RawReader::new(src, endian))
+    }
+
+    /// Extracts a `Blob` object from a `RawReader`, which allows the reader's
+    /// limits to be set first.
+    ///
+    /// ```rust
+    /// use nbt::raw::{Endianness, RawReader};
+    /// use nbt::{Blob, Error};
+    ///
+    /// // A root Compound holding a List of Lists of empty Lists.
+    /// let bytes = [
+    ///     0x0a, 0x00, 0x00,
+    ///         0x09, 0x00, 0x01, b'l', 0x09, 0x00, 0x00, 0x00, 0x01,
+    ///             0x00, 0x00, 0x00, 0x00, 0x00,
+    ///     0x00,
+    /// ];
+    /// let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    /// src.set_max_depth(2);
+    /// assert_eq!(Blob::from_raw_reader(&mut src).map_err(Error::without_location), Err(Error::DepthLimitExceeded(2)));
+    /// ```
+    pub fn from_raw_reader<R>(src: &mut RawReader<R>) -> Result<Blob>
+    where
+        R: io::Read,
+    {
         let (tag, title) = src.emit_next_header()?;
         // Although it would be possible to read NBT format files composed of
         // arbitrary objects using the current API, by convention all files
@@ -73,7 +166,7 @@ impl Blob {
         if tag != 0x0a {
             return Err(Error::NoRootCompound);
         }
-        let content = Value::from_raw_reader(tag, &mut src)?;
+        let content = Value::from_raw_reader(tag, src)?;
         match content {
             Value::Compound(map) => Ok(Blob {
                 title: title,
@@ -83,8 +176,285 @@ impl Blob {
         }
     }
 
+    /// Extracts a `Blob` object from an `io::Read` source, salvaging what it
+    /// can from damaged input as described for
+    /// `Value::from_raw_reader_lenient()`, and returning a warning for each
+    /// problem along with the `Blob`.
+    ///
+    /// An error is only returned if the root Compound's header cannot be
+    /// read.
+    ///
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut file = File::open("damaged.nbt").unwrap();
+    /// let (blob, warnings) = Blob::from_reader_lenient(&mut file, Endianness::BigEndian).unwrap();
+    /// for warning in &warnings {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// ```
+    pub fn from_reader_lenient<R>(src: &mut R, endian: Endianness) -> Result<(Blob, Vec<Error>)>
+    where
+        R: io::Read,
+    {
+        Blob::from_raw_reader_lenient(&mut RawReader::new(src, endian))
+    }
+
+    /// Extracts a `Blob` object from a `RawReader` like
+    /// `Blob::from_reader_lenient()`.
+    pub fn from_raw_reader_lenient<R>(src: &mut RawReader<R>) -> Result<(Blob, Vec<Error>)>
+    where
+        R: io::Read,
+    {
+        let (tag, title) = src.emit_next_header()?;
+        if tag != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        match Value::from_raw_reader_lenient(tag, src)? {
+            (Value::Compound(map), warnings) => Ok((Blob { title, content: map }, warnings)),
+            _ => Err(Error::NoRootCompound),
+        }
+    }
+
+    /// Extracts a `Blob` object from an `io::Read` source in the network
+    /// format used by Java Edition since 1.20.2 (protocol 764), where the
+    /// root Compound's type ID is not followed by a name.
+    ///
+    /// The resulting `Blob` has an empty name. A root tag other than a
+    /// Compound, including the `TAG_End` that the protocol uses to signal
+    /// absent NBT, yields `Error::NoRootCompound`.
+    pub fn from_network_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        Blob::from_raw_network_reader(&mut RawReader::new(src, endian))
+    }
+
+    /// Extracts a `Blob` object with an unnamed root from a `RawReader`.
+    fn from_raw_network_reader<R>(src: &mut RawReader<R>) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        let tag = src.read_bare_byte()?;
+        if tag != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        match Value::from_raw_reader(tag, src)? {
+            Value::Compound(content) => Ok(Blob {
+                title: "".to_string(),
+                content,
+            }),
+            _ => Err(Error::NoRootCompound),
+        }
+    }
+
+    /// Extracts a `Blob` object from an `io::Read` source, as described by
+    /// `options`.
+    ///
+    /// This is the counterpart of `to_writer_with()`, and covers all of the
+    /// other readers, along with the parsing limits of `RawReader`.
+    pub fn from_reader_with<R>(src: &mut R, options: &ReadOptions) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        Blob::read_with(options.decoder(src)?, options)
+    }
+
+    /// Returns an iterator over the documents stored back to back in `src`,
+    /// as described by `options`, which reads one `Blob` at a time until the
+    /// end of the stream.
+    ///
+    /// Any compression applies to the stream as a whole. Bytes after the
+    /// last complete document that do not make up another one are reported
+    /// as an error, located at the offset where its reading failed, and the
+    /// iterator then ends, as it does after any other error.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness, ReadOptions};
+    ///
+    /// let mut bytes = Vec::new();
+    /// for i in 0i32..3 {
+    ///     let mut blob = Blob::new();
+    ///     blob.insert("i", i).unwrap();
+    ///     blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    /// }
+    /// let options = ReadOptions::new(Endianness::BigEndian);
+    /// let blobs = Blob::iter_from_reader(&bytes[..], &options).collect::<Result<Vec<_>, _>>().unwrap();
+    /// assert_eq!(blobs.len(), 3);
+    /// ```
+    pub fn iter_from_reader<R>(src: R, options: &ReadOptions) -> Blobs<R>
+    where
+        R: io::Read,
+    {
+        let (src, pending) = match options.decoder(src) {
+            Ok(src) => (Some(options.raw_reader(io::BufReader::new(src))), None),
+            Err(e) => (None, Some(e)),
+        };
+        Blobs {
+            src,
+            pending,
+            unnamed_root: options.is_unnamed_root(),
+        }
+    }
+
+    /// Extracts a `Blob` object from an `io::Read` source that may be
+    /// compressed using the Gzip, zlib or Zstandard format, or not at all, by
+    /// looking at its first bytes.
+    ///
+    /// This suits mixed sources: region chunks usually use zlib, `level.dat`
+    /// uses Gzip, and network NBT is uncompressed. Raw NBT always starts with
+    /// a tag ID, so it cannot be mistaken for any of these headers.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    /// let blob = Blob::from_reader_auto(&mut file, Endianness::BigEndian).unwrap();
+    /// ```
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_reader_auto<R>(src: &mut R, endian: Endianness) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        use std::io::Read;
+
+        let mut magic = Vec::with_capacity(4);
+        src.by_ref().take(4).read_to_end(&mut magic)?;
+        let compression = match magic[..] {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            // The second byte of a zlib header makes the pair a multiple of 31.
+            [0x78, flags, ..] if (0x7800 | u16::from(flags)) % 31 == 0 => Compression::Zlib,
+            [0x28, 0xb5, 0x2f, 0xfd] => Compression::Zstd,
+            _ => Compression::Uncompressed,
+        };
+        let options = ReadOptions::new(endian).compression(compression);
+        Blob::from_reader_with(&mut io::Cursor::new(magic).chain(src), &options)
+    }
+
+    /// Extracts a `Blob` object from an uncompressed `io::Read` source whose
+    /// byte order is not known, returning it along with the byte order that
+    /// worked.
+    ///
+    /// The whole source is read into memory and parsed as big-endian, as
+    /// Java Edition writes it, and then as little-endian, as Bedrock Edition
+    /// does. A List or array longer than the input counts as a failure
+    /// without being read further, and so does a parse that leaves bytes
+    /// over unless the other byte order does no better. If neither works,
+    /// the big-endian error is returned.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut blob = Blob::new();
+    /// blob.insert("Version", 10i32).unwrap();
+    /// let bytes = blob.to_vec(Endianness::LittleEndian).unwrap();
+    /// let (read, endian) = Blob::from_reader_detect(&mut &bytes[..]).unwrap();
+    /// assert_eq!((read, endian), (blob, Endianness::LittleEndian));
+    /// ```
+    pub fn from_reader_detect<R>(src: &mut R) -> Result<(Blob, Endianness)>
+    where
+        R: io::Read,
+    {
+        let mut bytes = Vec::new();
+        src.read_to_end(&mut bytes)?;
+        let mut partial = None;
+        let mut error = None;
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian] {
+            let options = ReadOptions::new(endian)
+                .max_list_len(bytes.len())
+                .max_array_len(bytes.len());
+            let mut rest = &bytes[..];
+            match Blob::read_with(&mut rest, &options) {
+                Ok(blob) if rest.is_empty() => return Ok((blob, endian)),
+                Ok(blob) => partial = partial.or(Some((blob, endian))),
+                Err(e) => error = error.or(Some(e)),
+            }
+        }
+        partial.ok_or_else(|| error.expect("at least one byte order failed"))
+    }
+
+    /// Reads a `Blob` from a file, detecting its compression as in
+    /// `from_reader_auto()`.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    /// assert!(player.get("Inventory").is_some());
+    /// ```
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_path<P>(path: P, endian: Endianness) -> Result<Blob>
+    where
+        P: AsRef<Path>,
+    {
+        let mut src = io::BufReader::new(File::open(path)?);
+        Blob::from_reader_auto(&mut src, endian)
+    }
+
+    fn read_with<R>(src: R, options: &ReadOptions) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        let mut src = options.raw_reader(src);
+        if options.is_unnamed_root() {
+            Blob::from_raw_network_reader(&mut src)
+        } else {
+            Blob::from_raw_reader(&mut src)
+        }
+    }
+
+    /// Parses a `Blob` from stringified NBT (SNBT), the syntax used by
+    /// Minecraft commands such as `/data get`. The root must be a Compound,
+    /// and the resulting `Blob` has an empty name.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Value};
+    ///
+    /// let nbt = Blob::from_snbt(r#"{Health: 20.0f, "Custom Name": 'Steve', Pos: [0.5d, 64.0d, 0.5d]}"#).unwrap();
+    /// assert_eq!(nbt["Health"], Value::Float(20.0));
+    /// assert_eq!(nbt["Custom Name"], Value::String("Steve".to_string()));
+    /// ```
+    pub fn from_snbt(src: &str) -> Result<Blob> {
+        match Value::from_snbt(src)? {
+            Value::Compound(content) => Ok(Blob {
+                title: "".to_string(),
+                content,
+            }),
+            _ => Err(Error::NoRootCompound),
+        }
+    }
+
+    /// Formats the root Compound as compact SNBT. The `Blob`'s name is not
+    /// part of the output. See `Value::to_snbt()` for details.
+    ///
+    /// ```rust
+    /// use nbt::Blob;
+    ///
+    /// let mut nbt = Blob::new();
+    /// nbt.insert("name", "Herobrine").unwrap();
+    /// nbt.insert("health", 100i8).unwrap();
+    /// assert_eq!(nbt.to_snbt(), r#"{health:100b,name:"Herobrine"}"#);
+    /// ```
+    pub fn to_snbt(&self) -> String {
+        snbt::write_compound(&self.content, false)
+    }
+
+    /// Formats the root Compound as indented SNBT. See
+    /// `Value::to_snbt_pretty()`.
+    pub fn to_snbt_pretty(&self) -> String {
+        snbt::write_compound(&self.content, true)
+    }
+
+    /// Formats this `Blob` with the settings of a `Printer`, instead of
+    /// the defaults of its `Display` implementation.
+    pub fn display_with<'a>(&'a self, printer: &'a Printer) -> Printed<'a> {
+        Printed::blob(printer, &self.title, &self.content)
+    }
+
     /// Extracts an `Blob` object from an `io::Read` source that is
     /// compressed using the Gzip format.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
     pub fn from_gzip_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
     where
         R: io::Read,
@@ -96,6 +466,7 @@ impl Blob {
 
     /// Extracts an `Blob` object from an `io::Read` source that is
     /// compressed using the zlib format.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
     pub fn from_zlib_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
     where
         R: io::Read,
@@ -103,43 +474,308 @@ impl Blob {
         Blob::from_reader(&mut ZlibDecoder::new(src), endian)
     }
 
+    /// Extracts an `Blob` object from an `io::Read` source that is
+    /// compressed using LZ4, in the block stream format of lz4-java that
+    /// Minecraft uses for region file chunks.
+    #[cfg(feature = "experimental_lz4")]
+    pub fn from_lz4_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        Blob::from_reader(&mut Lz4Decoder::new(src), endian)
+    }
+
+    /// Extracts an `Blob` object from an `io::Read` source that is
+    /// compressed using Zstandard.
+    #[cfg(feature = "experimental_zstd")]
+    pub fn from_zstd_reader<R>(src: &mut R, endian: Endianness) -> Result<Blob>
+    where
+        R: io::Read,
+    {
+        Blob::from_reader(&mut ZstdDecoder::new(src), endian)
+    }
+
     /// Writes the binary representation of this `Blob` to an `io::Write`
     /// destination.
     pub fn to_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
+    where
+        W: io::Write,
+    {
+        self.to_raw_writer(&mut RawWriter::new(dst, endian))
+    }
+
+    /// The exact number of bytes that `to_writer()` writes for this `Blob`,
+    /// computed without encoding anything. See `Value::len_bytes()`.
+    pub fn len_bytes(&self, endian: Endianness) -> usize {
+        let entries: usize = self.content.iter()
+            .map(|(name, value)| 1 + raw::string_len(endian, name) + value.len_bytes(endian))
+            .sum();
+        1 + raw::string_len(endian, &self.title) + entries + 1
+    }
+
+    /// Writes the uncompressed binary representation of this `Blob` to a new
+    /// buffer.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut blob = Blob::new();
+    /// blob.insert("a", 1i8).unwrap();
+    /// let bytes = blob.to_vec(Endianness::BigEndian).unwrap();
+    /// assert_eq!(bytes, [0x0a, 0x00, 0x00, 0x01, 0x00, 0x01, b'a', 0x01, 0x00]);
+    /// assert_eq!(Blob::from_slice(&bytes, Endianness::BigEndian).unwrap(), blob);
+    /// ```
+    pub fn to_vec(&self, endian: Endianness) -> Result<Vec<u8>> {
+        let mut dst = Vec::new();
+        self.to_writer(&mut dst, endian)?;
+        Ok(dst)
+    }
+
+    /// Writes the binary representation of this `Blob` to a `RawWriter`,
+    /// which allows the writer's options to be set first.
+    ///
+    /// ```rust
+    /// use nbt::raw::{Endianness, RawWriter};
+    /// use nbt::Blob;
+    ///
+    /// let mut nbt = Blob::new();
+    /// nbt.insert("b", 1i8).unwrap();
+    /// nbt.insert("a", 2i8).unwrap();
+    ///
+    /// let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    /// dst.sort_keys(true);
+    /// nbt.to_raw_writer(&mut dst).unwrap();
+    /// assert_eq!(dst.into_inner(), vec![
+    ///     0x0a, 0x00, 0x00,
+    ///         0x01, 0x00, 0x01, b'a', 0x02,
+    ///         0x01, 0x00, 0x01, b'b', 0x01,
+    ///     0x00,
+    /// ]);
+    /// ```
+    pub fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+    where
+        W: io::Write,
+    {
+        dst.write_header(0x0a, &self.title)?;
+        self.write_content(dst)
+    }
+
+    /// Writes this `Blob` to an `io::Write` destination in the network format
+    /// used by Java Edition since 1.20.2, where the root Compound has no name.
+    /// The `Blob`'s name is not written. See `Blob::from_network_reader()`.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut nbt = Blob::named("ignored");
+    /// nbt.insert("a", 1i8).unwrap();
+    /// let mut dst = Vec::new();
+    /// nbt.to_network_writer(&mut dst, Endianness::BigEndian).unwrap();
+    /// assert_eq!(dst, vec![0x0a, 0x01, 0x00, 0x01, b'a', 0x01, 0x00]);
+    /// ```
+    pub fn to_network_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
     where
         W: io::Write,
     {
         let mut dst = RawWriter::new(dst, endian);
         dst.write_bare_byte(0x0a)?;
-        dst.write_bare_string(&self.title)?;
-        for (name, ref nbt) in self.content.iter() {
-            dst.write_bare_byte(nbt.id())?;
-            dst.write_bare_string(name)?;
-            nbt.to_raw_writer(&mut dst)?;
+        self.write_content(&mut dst)
+    }
+
+    /// Writes the binary representation of this `Blob` to an `io::Write`
+    /// destination, as described by `options`.
+    ///
+    /// This covers all of the other writers. Compressed output is finished
+    /// before returning, so that any error writing its trailer is reported.
+    pub fn to_writer_with<W>(&self, dst: &mut W, options: &WriteOptions) -> Result<()>
+    where
+        W: io::Write,
+    {
+        match options.get_compression() {
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Compression::Gzip => {
+                let mut dst = GzEncoder::new(dst, flate::level(options.get_level()));
+                self.write_with(&mut dst, options)?;
+                dst.finish()?;
+            },
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Compression::Zlib => {
+                let mut dst = ZlibEncoder::new(dst, flate::level(options.get_level()));
+                self.write_with(&mut dst, options)?;
+                dst.finish()?;
+            },
+            #[cfg(not(any(feature = "compression", feature = "rust_backend")))]
+            compression @ Compression::Gzip | compression @ Compression::Zlib => {
+                return Err(Error::UnknownChunkCompression(compression.id()));
+            },
+            Compression::Uncompressed => self.write_with(dst, options)?,
+            #[cfg(feature = "experimental_lz4")]
+            Compression::Lz4 => {
+                let mut dst = Lz4Encoder::new(dst);
+                self.write_with(&mut dst, options)?;
+                dst.finish()?;
+            },
+            #[cfg(not(feature = "experimental_lz4"))]
+            Compression::Lz4 => return Err(Error::UnknownChunkCompression(4)),
+            #[cfg(feature = "experimental_zstd")]
+            Compression::Zstd => {
+                let mut dst = ZstdEncoder::new(dst);
+                self.write_with(&mut dst, options)?;
+                dst.finish()?;
+            },
+            #[cfg(not(feature = "experimental_zstd"))]
+            Compression::Zstd => return Err(Error::UnknownChunkCompression(127)),
+        }
+        Ok(())
+    }
+
+    /// Writes the binary representation of this `Blob` to a file, compressed
+    /// using the Gzip format as Java Edition's player and level data are.
+    /// The file is created if it does not exist, and replaced if it does.
+    ///
+    /// See `write_to_path_with()` for other formats.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn write_to_path<P>(&self, path: P, endian: Endianness) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let options = WriteOptions::new(endian).compression(Compression::Gzip);
+        self.write_to_path_with(path, &options)
+    }
+
+    /// Writes the binary representation of this `Blob` to a file, as
+    /// described by `options`. The file is created if it does not exist, and
+    /// replaced if it does.
+    pub fn write_to_path_with<P>(&self, path: P, options: &WriteOptions) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut dst = io::BufWriter::new(File::create(path)?);
+        self.to_writer_with(&mut dst, options)?;
+        dst.into_inner().map_err(|e| e.into_error())?;
+        Ok(())
+    }
+
+    fn write_with<W>(&self, dst: W, options: &WriteOptions) -> Result<()>
+    where
+        W: io::Write,
+    {
+        let mut dst = options.raw_writer(dst);
+        if options.is_unnamed_root() {
+            dst.write_bare_byte(0x0a)?;
+            self.write_content(&mut dst)
+        } else {
+            self.to_raw_writer(&mut dst)
         }
-        dst.close_nbt()
+    }
+
+    /// Writes the root Compound's payload.
+    fn write_content<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+    where
+        W: io::Write,
+    {
+        dst.write_entries(self.content.iter(), |dst, name, nbt| {
+            dst.write_header(nbt.id(), name)?;
+            nbt.to_raw_writer(dst)
+        })
     }
 
     /// Writes the binary representation of this `Blob`, compressed using
     /// the Gzip format, to an `io::Write` destination.
+    ///
+    /// This uses the default compression level; see `to_writer_with()` to
+    /// choose another.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
     pub fn to_gzip_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
     where
         W: io::Write,
     {
-        self.to_writer(&mut GzEncoder::new(dst, Compression::Default), endian)
+        self.to_writer(&mut GzEncoder::new(dst, flate::level(CompressionLevel::Default)), endian)
     }
 
     /// Writes the binary representation of this `Blob`, compressed using
     /// the Zlib format, to an `io::Write` dst.
+    ///
+    /// This uses the default compression level; see `to_writer_with()` to
+    /// choose another.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
     pub fn to_zlib_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
     where
         W: io::Write,
     {
-        self.to_writer(&mut ZlibEncoder::new(dst, Compression::Default), endian)
+        self.to_writer(&mut ZlibEncoder::new(dst, flate::level(CompressionLevel::Default)), endian)
+    }
+
+    /// Writes the binary representation of this `Blob`, compressed using
+    /// LZ4 in the block stream format of lz4-java, to an `io::Write` dst.
+    #[cfg(feature = "experimental_lz4")]
+    pub fn to_lz4_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
+    where
+        W: io::Write,
+    {
+        let mut dst = Lz4Encoder::new(dst);
+        self.to_writer(&mut dst, endian)?;
+        dst.finish()?;
+        Ok(())
+    }
+
+    /// Writes the binary representation of this `Blob`, compressed using
+    /// Zstandard, to an `io::Write` dst.
+    ///
+    /// The output is a single frame that any zstd decoder accepts, though it
+    /// is larger than the reference encoder would produce.
+    #[cfg(feature = "experimental_zstd")]
+    pub fn to_zstd_writer<W>(&self, dst: &mut W, endian: Endianness) -> Result<()>
+    where
+        W: io::Write,
+    {
+        let mut dst = ZstdEncoder::new(dst);
+        self.to_writer(&mut dst, endian)?;
+        dst.finish()?;
+        Ok(())
+    }
+
+    /// Returns an `io::Read` source that produces the binary representation
+    /// of this `Blob` on demand.
+    ///
+    /// The bytes are identical to those written by `to_writer()`, but they are
+    /// generated incrementally as the caller reads, so the serialized form is
+    /// never held in memory all at once.
+    ///
+    /// ```rust
+    /// use std::io::Read;
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut nbt = Blob::new();
+    /// nbt.insert("name", "Herobrine").unwrap();
+    ///
+    /// let mut streamed = Vec::new();
+    /// nbt.reader(Endianness::BigEndian).read_to_end(&mut streamed).unwrap();
+    ///
+    /// let mut written = Vec::new();
+    /// nbt.to_writer(&mut written, Endianness::BigEndian).unwrap();
+    /// assert_eq!(streamed, written);
+    /// ```
+    pub fn reader(&self, endian: Endianness) -> BlobReader<'_> {
+        BlobReader::new(self, endian)
+    }
+
+    /// Returns an `io::Read` source that produces the binary representation
+    /// of this `Blob`, compressed using the Gzip format, on demand.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn gzip_reader(&self, endian: Endianness) -> read::GzEncoder<BlobReader<'_>> {
+        read::GzEncoder::new(self.reader(endian), flate::level(CompressionLevel::Default))
+    }
+
+    /// Returns an `io::Read` source that produces the binary representation
+    /// of this `Blob`, compressed using the zlib format, on demand.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn zlib_reader(&self, endian: Endianness) -> read::ZlibEncoder<BlobReader<'_>> {
+        read::ZlibEncoder::new(self.reader(endian), flate::level(CompressionLevel::Default))
     }
 
     /// Insert an `Value` with a given name into this `Blob` object. This
-    /// method is just a thin wrapper around the underlying `HashMap` method of
+    /// method is just a thin wrapper around the underlying `Map` method of
     /// the same name.
     ///
     /// This method will also return an error if a `Value::List` with
@@ -154,7 +790,7 @@ impl Blob {
         // inserted into the file.
         let nvalue = value.into();
         if let Value::List(ref vals) = nvalue {
-            if vals.len() != 0 {
+            if !vals.is_empty() {
                 let first_id = vals[0].id();
                 for nbt in vals {
                     if nbt.id() != first_id {
@@ -167,12 +803,520 @@ impl Blob {
         Ok(())
     }
 
-    /// Tries to get a named `Value` in the blob.
+    /// Tries to get a named `Value` in the blob. The name can be a `&str` or
+    /// a `String`, borrowed or not.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Value};
+    ///
+    /// let mut blob = Blob::new();
+    /// blob.insert("Health", 20.0f32).unwrap();
+    /// let name = String::from("Health");
+    /// assert_eq!(blob.get(&name), Some(&Value::Float(20.0)));
+    /// assert_eq!(blob.get("Food"), None);
+    /// ```
     pub fn get<S>(&self, name: S) -> Option<&Value>
     where
-        S: Into<&'static str>,
+        S: AsRef<str>,
+    {
+        self.content.get(name.as_ref())
+    }
+
+    /// Tries to get a named `Value` in the blob for modification in place.
+    pub fn get_mut<S>(&mut self, name: S) -> Option<&mut Value>
+    where
+        S: AsRef<str>,
+    {
+        self.content.get_mut(name.as_ref())
+    }
+
+    /// Gets the entry for a name, to read or modify it in place with a single
+    /// lookup.
+    ///
+    /// Lists inserted through the entry are not checked as in `insert()`:
+    /// writing a heterogeneous List fails with `Error::HeterogeneousList`
+    /// instead.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Value};
+    ///
+    /// let mut blob = Blob::new();
+    /// for _ in 0..3 {
+    ///     blob.entry("deaths")
+    ///         .and_modify(|v| if let Value::Int(ref mut n) = *v { *n += 1 })
+    ///         .or_insert(Value::Int(1));
+    /// }
+    /// assert_eq!(blob["deaths"], Value::Int(3));
+    /// ```
+    pub fn entry<S: Into<String>>(&mut self, name: S) -> map::Entry<'_> {
+        self.content.entry(name.into())
+    }
+
+    /// The number of entries in the blob.
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Whether the blob has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Iterates over the blob's names and values, in the order of its `Map`.
+    ///
+    /// ```rust
+    /// use nbt::{Blob, Value};
+    ///
+    /// let blob: Blob = vec![("health", 20i8), ("food", 18i8)].into_iter().collect();
+    /// assert_eq!(blob.len(), 2);
+    /// assert!(blob.iter().all(|(_, value)| *value == Value::Byte(20) || *value == Value::Byte(18)));
+    /// ```
+    pub fn iter(&self) -> map::Iter<'_> {
+        self.content.iter()
+    }
+
+    /// Iterates over the blob's names and values, with the values mutable.
+    pub fn iter_mut(&mut self) -> map::IterMut<'_> {
+        self.content.iter_mut()
+    }
+
+    /// Iterates over the names of the blob's entries.
+    pub fn keys(&self) -> map::Keys<'_> {
+        self.content.keys()
+    }
+
+    /// Iterates over the values of the blob's entries.
+    pub fn values(&self) -> map::Values<'_> {
+        self.content.values()
+    }
+
+    /// Iterates over the values of the blob's entries, mutably.
+    pub fn values_mut(&mut self) -> map::ValuesMut<'_> {
+        self.content.values_mut()
+    }
+
+    /// Looks up a nested value by a path starting with the name of one of the
+    /// blob's entries. See `Value::get_path()` for the syntax.
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+    /// use nbt::{Blob, Endianness, Value};
+    ///
+    /// let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    /// let player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    ///
+    /// match player.get_path("Inventory[0].id") {
+    ///     Some(&Value::String(ref id)) => assert!(id.starts_with("minecraft:")),
+    ///     other => panic!("unexpected item id {:?}", other),
+    /// }
+    /// assert_eq!(player.get_path("Inventory[1000].id"), None);
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path::get_in(&self.content, &path::parse(path)?)
+    }
+
+    /// Looks up a nested value by path, as in `get_path()`, for modification.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        path::get_in_mut(&mut self.content, &path::parse(path)?)
+    }
+
+    /// Iterates over the nested values matching a `Selector`, starting from
+    /// the blob's entries. See `Value::select()` for details.
+    pub fn select(&self, selector: &str) -> Result<path::Matches<'_>> {
+        Ok(path::Selector::parse(selector)?.select_in(&self.content))
+    }
+
+    /// Iterates over every value nested in the blob, along with their paths,
+    /// starting from its entries. See `Value::walk()` for details.
+    pub fn walk(&self) -> path::Walk<'_> {
+        path::Walk::entries(&self.content)
+    }
+
+    /// Visits every value nested in the blob, starting from its entries, so
+    /// that `visit` can change or remove them. See `Value::walk_mut()` for
+    /// details.
+    pub fn walk_mut<F>(&mut self, mut visit: F)
+    where
+        F: FnMut(&str, &mut Value) -> path::VisitAction,
+    {
+        let mut root = Value::Compound(mem::take(&mut self.content));
+        path::walk_mut(vec![(String::new(), &mut root)], &mut visit);
+        if let Value::Compound(content) = root {
+            self.content = content;
+        }
+    }
+
+    /// Computes the changes that turn this blob's entries into those of
+    /// `other`, as in `nbt::diff()`. The names of the blobs are not compared.
+    pub fn diff(&self, other: &Blob) -> NbtPatch {
+        patch::diff_entries(&self.content, &other.content)
+    }
+
+    /// Checks this blob's root Compound against a `Schema`, as in
+    /// `Schema::validate()`. The name of the blob is not checked.
+    pub fn validate(&self, schema: &Schema) -> Vec<Violation> {
+        schema.validate_entries(&self.content)
+    }
+
+    /// Replays the changes of a patch onto this blob's entries, as in
+    /// `Value::apply_patch()`.
+    pub fn apply_patch(&mut self, patch: &NbtPatch) -> Result<()> {
+        patch::apply_entries(&mut self.content, patch)
+    }
+
+    /// Merges the entries of `other` into this blob according to `strategy`,
+    /// keeping this blob's name.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::{MergeStrategy, Value};
+    ///
+    /// # fn main() {
+    /// let mut level = blob!({ Data: { LevelName: "world", GameType: 0i32 } });
+    /// let defaults = blob!({ Data: { GameType: 1i32, Difficulty: 2i8 } });
+    ///
+    /// level.merge(&defaults, MergeStrategy::KeepExisting);
+    /// assert_eq!(level.get_path("Data.GameType"), Some(&Value::Int(0)));
+    /// assert_eq!(level.get_path("Data.Difficulty"), Some(&Value::Byte(2)));
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: &Blob, strategy: MergeStrategy) {
+        merge::merge_entries(&mut self.content, &other.content, strategy)
+    }
+
+    /// A 64-bit hash of this blob's name and content, for deduplicating and
+    /// comparing NBT data across files.
+    ///
+    /// The hash is computed over a canonical form, where Compound entries are
+    /// sorted by key, empty Lists are all alike regardless of the element type
+    /// they were read with, and floats are compared by bit pattern with all
+    /// NaNs treated as one. It does not depend on the endianness or
+    /// compression the blob was read with, and is stable across runs,
+    /// platforms and versions of this crate. It is not cryptographic, so
+    /// blobs with equal hashes should still be compared before being treated
+    /// as identical.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    ///
+    /// # fn main() {
+    /// let a = blob!({ x: 1i32, y: 2i32, z: [] });
+    /// let b = blob!({ z: [], y: 2i32, x: 1i32 });
+    /// assert_eq!(a.canonical_hash(), b.canonical_hash());
+    /// assert_ne!(a.canonical_hash(), blob!({ x: 1i32 }).canonical_hash());
+    /// # }
+    /// ```
+    pub fn canonical_hash(&self) -> u64 {
+        canonical::hash_blob(&self.title, &self.content)
+    }
+
+    /// The number of bytes of heap memory that the blob owns, including its
+    /// name and everything nested in it. See `Value::deep_size_of()`.
+    pub fn deep_size_of(&self) -> usize {
+        let values: usize = self.content.values().map(Value::deep_size_of).sum();
+        self.title.capacity() + map::heap_size(&self.content) + values
+    }
+
+    /// Iterates over the elements of the named `Value::List`, converting each
+    /// of them to `T`. See `Value::iter_as()` for details.
+    ///
+    /// This fails with `Error::MissingField` if there is no such value.
+    pub fn iter_list_as<'a, T>(&'a self, name: &str) -> Result<impl Iterator<Item = Result<T>> + 'a>
+    where
+        T: TryFrom<&'a Value, Error = Error> + 'a,
     {
-        self.content.get(name.into())
+        match self.content.get(name) {
+            Some(value) => value.iter_as(),
+            None => Err(Error::MissingField(name.to_string())),
+        }
+    }
+
+    /// Removes a named `Value` from the blob, returning it if it was there.
+    /// With the `preserve_order` feature the other entries keep their order.
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        map::remove(&mut self.content, name)
+    }
+
+    /// Keeps only the entries for which `keep` returns true.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&str, &mut Value) -> bool,
+    {
+        self.content.retain(|k, v| keep(k, v));
+    }
+
+    /// Keeps only the entries, of the blob and of every Compound nested in
+    /// it, including those in Lists, for which `keep` returns true.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    ///
+    /// # fn main() {
+    /// let mut entity = blob!({
+    ///     UUID: [I; 1, 2, 3, 4],
+    ///     Passengers: [{ id: "minecraft:pig", UUID: [I; 5, 6, 7, 8] }],
+    /// });
+    /// entity.retain_recursive(|key, _| key != "UUID");
+    /// assert_eq!(entity, blob!({ Passengers: [{ id: "minecraft:pig" }] }));
+    /// # }
+    /// ```
+    pub fn retain_recursive<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&str, &Value) -> bool,
+    {
+        self.content.retain(|k, v| keep(k, v));
+        map::retain_recursive(self.content.values_mut().collect(), &mut keep);
+    }
+
+    /// Removes a named `Value` from the blob and converts it to `T` without
+    /// cloning.
+    ///
+    /// Returns `None` if there is no such value. If the value has the wrong
+    /// type it is still removed, and handed back untouched as the `Err`
+    /// variant so that the caller can reinsert it or try something else.
+    pub fn remove_as<T>(&mut self, name: &str) -> Option<StdResult<T, Value>>
+    where
+        T: FromValue,
+    {
+        map::remove(&mut self.content, name).map(T::try_from_value)
+    }
+}
+
+/// The maximum number of array elements encoded per refill of a
+/// `BlobReader`'s internal buffer.
+const ARRAY_CHUNK_LEN: usize = 1024;
+
+/// A position within the tree of values being streamed by a `BlobReader`.
+enum Frame<'a> {
+    Compound(map::Iter<'a>),
+    List(i8, slice::Iter<'a, Value>),
+    ByteArray(&'a [i8]),
+    IntArray(&'a [i32]),
+    LongArray(&'a [i64]),
+}
+
+/// An `io::Read` source that streams the binary representation of a `Blob`.
+///
+/// This is created by `Blob::reader()`. The tree is walked with an explicit
+/// stack, and only the bytes for the next tag (or the next chunk of a large
+/// array) are buffered at any one time.
+pub struct BlobReader<'a> {
+    blob: &'a Blob,
+    dst: RawWriter<Vec<u8>>,
+    pos: usize,
+    stack: Vec<Frame<'a>>,
+    started: bool,
+}
+
+impl<'a> BlobReader<'a> {
+    fn new(blob: &'a Blob, endian: Endianness) -> BlobReader<'a> {
+        BlobReader {
+            blob,
+            dst: RawWriter::new(Vec::new(), endian),
+            pos: 0,
+            stack: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// The capacity of the internal buffer, which bounds the memory used.
+    #[cfg(test)]
+    pub(crate) fn buffer_capacity(&self) -> usize {
+        self.dst.get_ref().capacity()
+    }
+
+    /// Refills the internal buffer with the next bytes of the stream, leaving
+    /// it empty only once the whole `Blob` has been produced.
+    fn fill(&mut self) -> Result<()> {
+        self.dst.get_mut().clear();
+        self.pos = 0;
+        let dst = &mut self.dst;
+
+        if !self.started {
+            self.started = true;
+            dst.write_header(0x0a, &self.blob.title)?;
+            dst.begin_compound();
+            self.stack.push(Frame::Compound(self.blob.content.iter()));
+            return Ok(());
+        }
+
+        while dst.get_ref().is_empty() {
+            let frame = match self.stack.pop() {
+                Some(frame) => frame,
+                None => break,
+            };
+            match frame {
+                Frame::Compound(mut iter) => match iter.next() {
+                    Some((name, nbt)) => {
+                        dst.write_header(nbt.id(), name)?;
+                        self.stack.push(Frame::Compound(iter));
+                        push_payload(nbt, dst, &mut self.stack)?;
+                    }
+                    None => dst.close_nbt()?,
+                },
+                Frame::List(id, mut iter) => {
+                    if let Some(nbt) = iter.next() {
+                        // Ensure that all of the tags are the same type.
+                        if nbt.id() != id {
+                            return Err(Error::HeterogeneousList);
+                        }
+                        self.stack.push(Frame::List(id, iter));
+                        push_payload(nbt, dst, &mut self.stack)?;
+                    }
+                }
+                Frame::ByteArray(vals) => {
+                    let (head, rest) = vals.split_at(vals.len().min(ARRAY_CHUNK_LEN));
+                    for &v in head {
+                        dst.write_bare_byte(v)?;
+                    }
+                    if !rest.is_empty() {
+                        self.stack.push(Frame::ByteArray(rest));
+                    }
+                }
+                Frame::IntArray(vals) => {
+                    let (head, rest) = vals.split_at(vals.len().min(ARRAY_CHUNK_LEN));
+                    for &v in head {
+                        dst.write_bare_int(v)?;
+                    }
+                    if !rest.is_empty() {
+                        self.stack.push(Frame::IntArray(rest));
+                    }
+                }
+                Frame::LongArray(vals) => {
+                    let (head, rest) = vals.split_at(vals.len().min(ARRAY_CHUNK_LEN));
+                    for &v in head {
+                        dst.write_bare_long(v)?;
+                    }
+                    if !rest.is_empty() {
+                        self.stack.push(Frame::LongArray(rest));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the part of a payload that precedes its children, and pushes a new
+/// frame for the children (if any) onto the stack.
+fn push_payload<'a>(
+    nbt: &'a Value,
+    dst: &mut RawWriter<Vec<u8>>,
+    stack: &mut Vec<Frame<'a>>,
+) -> Result<()> {
+    match *nbt {
+        Value::ByteArray(ref vals) => {
+            dst.write_bare_int(vals.len() as i32)?;
+            stack.push(Frame::ByteArray(vals));
+        }
+        Value::IntArray(ref vals) => {
+            dst.write_bare_int(vals.len() as i32)?;
+            stack.push(Frame::IntArray(vals));
+        }
+        Value::LongArray(ref vals) => {
+            dst.write_bare_int(vals.len() as i32)?;
+            stack.push(Frame::LongArray(vals));
+        }
+        Value::List(ref vals) => {
+            // As in `Value::to_raw_writer()`, empty lists are written with
+            // TAG_End as their type.
+            let id = vals.first().map_or(0, Value::id);
+            dst.write_bare_byte(id)?;
+            dst.write_bare_int(vals.len() as i32)?;
+            stack.push(Frame::List(id, vals.iter()));
+        }
+        Value::Compound(ref vals) => {
+            dst.begin_compound();
+            stack.push(Frame::Compound(vals.iter()));
+        }
+        ref other => other.to_raw_writer(dst)?,
+    }
+    Ok(())
+}
+
+impl<'a> io::Read for BlobReader<'a> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.dst.get_ref().len() {
+            self.fill()?;
+        }
+        let buf = self.dst.get_ref();
+        let n = out.len().min(buf.len() - self.pos);
+        out[..n].copy_from_slice(&buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// An iterator over consecutive documents in one stream.
+///
+/// This is created by `Blob::iter_from_reader()`.
+pub struct Blobs<R: io::Read> {
+    src: Option<RawReader<io::BufReader<Decoder<R>>>>,
+    pending: Option<Error>,
+    unnamed_root: bool,
+}
+
+impl<R: io::Read> Blobs<R> {
+    /// The number of bytes read so far, after any decompression, which is
+    /// the offset of the end of the last document read.
+    pub fn offset(&self) -> u64 {
+        self.src.as_ref().map_or(0, RawReader::offset)
+    }
+}
+
+impl<R: io::Read> Iterator for Blobs<R> {
+    type Item = Result<Blob>;
+
+    fn next(&mut self) -> Option<Result<Blob>> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        let result = {
+            let src = self.src.as_mut()?;
+            match src.get_mut().fill_buf() {
+                Ok([]) => None,
+                Ok(_) if self.unnamed_root => Some(Blob::from_raw_network_reader(src)),
+                Ok(_) => Some(Blob::from_raw_reader(src)),
+                Err(e) => Some(Err(e.into())),
+            }
+        };
+        match result {
+            Some(Ok(blob)) => Some(Ok(blob)),
+            Some(Err(e)) => {
+                let offset = self.offset();
+                self.src = None;
+                // Errors inside a document already say where they are.
+                match e {
+                    Error::Located(..) => Some(Err(e)),
+                    e => Some(Err(Error::Located(offset, String::new(), Box::new(e)))),
+                }
+            },
+            None => {
+                self.src = None;
+                None
+            },
+        }
+    }
+}
+
+impl From<Blob> for Value {
+    fn from(blob: Blob) -> Value {
+        blob.into_value()
+    }
+}
+
+impl From<Map> for Blob {
+    fn from(content: Map) -> Blob {
+        Blob { title: "".to_string(), content }
+    }
+}
+
+impl TryFrom<Value> for Blob {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Blob> {
+        Blob::from_value(value)
     }
 }
 
@@ -184,25 +1328,92 @@ impl<'a> Index<&'a str> for Blob {
     }
 }
 
+impl<'a> IndexMut<&'a str> for Blob {
+    fn index_mut<'b>(&'b mut self, s: &'a str) -> &'b mut Value {
+        self.content.get_mut(s).unwrap()
+    }
+}
+
+impl IntoIterator for Blob {
+    type Item = (String, Value);
+    type IntoIter = map::IntoIter;
+
+    fn into_iter(self) -> map::IntoIter {
+        self.content.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Blob {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = map::Iter<'a>;
+
+    fn into_iter(self) -> map::Iter<'a> {
+        self.content.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Blob {
+    type Item = (&'a String, &'a mut Value);
+    type IntoIter = map::IterMut<'a>;
+
+    fn into_iter(self) -> map::IterMut<'a> {
+        self.content.iter_mut()
+    }
+}
+
+/// Collects entries into a blob with an empty name. Unlike `insert()`, Lists
+/// are not checked here: writing a heterogeneous List fails with
+/// `Error::HeterogeneousList` instead.
+impl<K, V> FromIterator<(K, V)> for Blob
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Blob {
+        let mut blob = Blob::new();
+        blob.extend(iter);
+        blob
+    }
+}
+
+/// Adds entries, replacing any with the same names. As with `FromIterator`,
+/// Lists are not checked.
+impl<K, V> Extend<(K, V)> for Blob
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.content.extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+}
+
+impl fmt::Debug for Blob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Blob")
+            .field("title", &self.title)
+            .field("content", &DebugMap(&self.content))
+            .finish()
+    }
+}
+
 impl fmt::Display for Blob {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "TAG_Compound(\"{}\"): {} entry(ies)\n{{\n",
-            self.title,
-            self.content.len()
-        )?;
-        for (name, tag) in self.content.iter() {
-            write!(f, "  {}(\"{}\"): ", tag.tag_name(), name)?;
-            tag.print(f, 2)?;
-            write!(f, "\n")?;
-        }
-        write!(f, "}}")
+        Printer::new().print_blob(f, &self.title, &self.content)
+    }
+}
+
+/// Hashes the `canonical_hash()`, as `Value` does.
+impl Hash for Blob {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.canonical_hash());
     }
 }
 
 #[cfg(feature = "serde")]
 use serde::{self, ser::SerializeMap};
+#[cfg(feature = "serde")]
+use named::Named;
 
 #[cfg(feature = "serde")]
 impl serde::Serialize for Blob {
@@ -210,7 +1421,7 @@ impl serde::Serialize for Blob {
     where
         S: serde::ser::Serializer,
     {
-        // No support for named Blobs.
+        // The title is dropped; convert to `Named` to keep it.
         let mut state = serializer.serialize_map(Some(self.content.len()))?;
         for (k, v) in &self.content {
             state.serialize_entry(&k, &v)?;
@@ -225,11 +1436,25 @@ impl<'de> serde::Deserialize<'de> for Blob {
     where
         D: serde::de::Deserializer<'de>,
     {
-        // No support for named Blobs.
-        let map: HashMap<String, Value> = serde::de::Deserialize::deserialize(deserializer)?;
+        // The title is dropped; convert from `Named` to keep it.
+        let map: Map = serde::de::Deserialize::deserialize(deserializer)?;
         Ok(Blob {
             title: "".to_string(),
             content: map,
         })
     }
 }
+
+#[cfg(feature = "serde")]
+impl From<Blob> for Named<Map> {
+    fn from(blob: Blob) -> Self {
+        Named { name: blob.title, value: blob.content }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Named<Map>> for Blob {
+    fn from(named: Named<Map>) -> Self {
+        Blob { title: named.name, content: named.value }
+    }
+}
@@ -0,0 +1,82 @@
+//! Serde helper for `bool` fields inside `#[serde(flatten)]` structs.
+//!
+//! NBT has no boolean tag, so `bool` fields are stored as a `TAG_Byte` of 0 or
+//! 1 and decoded through `deserialize_bool`. Serde decodes flattened fields
+//! from a buffer instead, which only remembers that it held an integer and
+//! so rejects it as a `bool`. Marking such fields with
+//! `#[serde(with = "nbt::byte_bool")]` accepts the integer form as well:
+//!
+//! ```
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate nbt;
+//! # use nbt::Endianness;
+//! #[derive(Serialize, Deserialize)]
+//! struct Section {
+//!     #[serde(with = "nbt::byte_bool")]
+//!     lit: bool,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Chunk {
+//!     version: i32,
+//!     #[serde(flatten)]
+//!     section: Section,
+//! }
+//!
+//! # fn main() {
+//! let chunk = Chunk { version: 1, section: Section { lit: true } };
+//! let mut bytes = Vec::new();
+//! nbt::to_writer(&mut bytes, &chunk, None, Endianness::BigEndian).unwrap();
+//!
+//! let chunk: Chunk = nbt::from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+//! assert!(chunk.section.lit);
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::{de, ser};
+
+/// Serializes `value` as a byte of 0 or 1.
+pub fn serialize<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+    where S: ser::Serializer
+{
+    serializer.serialize_i8(*value as i8)
+}
+
+/// Deserializes a `bool`, or an integer of 0 or 1.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where D: de::Deserializer<'de>
+{
+    deserializer.deserialize_any(ByteBoolVisitor)
+}
+
+struct ByteBoolVisitor;
+
+impl<'de> de::Visitor<'de> for ByteBoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a boolean or a byte of 0 or 1")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<bool, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<bool, E> {
+        match value {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Signed(value), &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<bool, E> {
+        match value {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(value), &self)),
+        }
+    }
+}
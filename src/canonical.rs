@@ -0,0 +1,117 @@
+//! Content hashes of values, independent of map order.
+//!
+//! Values are hashed through a canonical encoding, which is fixed so that
+//! hashes can be stored and compared across runs, machines and versions of
+//! this crate. Each value is its tag ID followed by its payload, with numbers
+//! in big-endian and lengths as 64-bit integers:
+//!
+//! * Compound entries are sorted by key, and each is the key followed by the
+//!   value.
+//! * List elements each include their own tag ID, so that an empty List hashes
+//!   the same whatever element type it was read with.
+//! * Floats are hashed by their bit pattern, so `0.0` and `-0.0` differ, but
+//!   every NaN is replaced by the canonical quiet NaN first.
+//! * Strings are hashed as UTF-8.
+//!
+//! The hash function is 64-bit FNV-1a. It is fast and well distributed, but
+//! not cryptographic: treat equal hashes as a hint, and compare the values
+//! before relying on them being identical.
+
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use map::Map;
+use value::Value;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct Hasher(u64);
+
+impl Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_be_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.write(value.as_bytes());
+    }
+
+    fn write_value(&mut self, value: &Value) {
+        self.write(&[value.id() as u8]);
+        match *value {
+            Value::Byte(v) => self.write(&[v as u8]),
+            Value::Short(v) => self.write(&v.to_be_bytes()),
+            Value::Int(v) => self.write(&v.to_be_bytes()),
+            Value::Long(v) => self.write(&v.to_be_bytes()),
+            Value::Float(v) => {
+                let v = if v.is_nan() { f32::NAN } else { v };
+                self.write(&v.to_bits().to_be_bytes())
+            },
+            Value::Double(v) => {
+                let v = if v.is_nan() { f64::NAN } else { v };
+                self.write(&v.to_bits().to_be_bytes())
+            },
+            Value::ByteArray(ref vals) => {
+                self.write_len(vals.len());
+                for &v in vals {
+                    self.write(&[v as u8]);
+                }
+            },
+            Value::String(ref v) => self.write_str(v),
+            Value::List(ref vals) => {
+                self.write_len(vals.len());
+                for v in vals {
+                    self.write_value(v);
+                }
+            },
+            Value::Compound(ref map) => self.write_entries(map),
+            Value::IntArray(ref vals) => {
+                self.write_len(vals.len());
+                for v in vals {
+                    self.write(&v.to_be_bytes());
+                }
+            },
+            Value::LongArray(ref vals) => {
+                self.write_len(vals.len());
+                for v in vals {
+                    self.write(&v.to_be_bytes());
+                }
+            },
+        }
+    }
+
+    fn write_entries(&mut self, map: &Map) {
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by_key(|&(name, _)| name);
+        self.write_len(entries.len());
+        for (name, value) in entries {
+            self.write_str(name);
+            self.write_value(value);
+        }
+    }
+}
+
+/// The canonical hash of a value. See `Value::canonical_hash()`.
+pub(crate) fn hash_value(value: &Value) -> u64 {
+    let mut hasher = Hasher(FNV_OFFSET);
+    hasher.write_value(value);
+    hasher.0
+}
+
+/// The canonical hash of a named root Compound. See `Blob::canonical_hash()`.
+#[cfg(feature = "std")]
+pub(crate) fn hash_blob(title: &str, content: &Map) -> u64 {
+    let mut hasher = Hasher(FNV_OFFSET);
+    hasher.write_str(title);
+    hasher.write(&[0x0a]);
+    hasher.write_entries(content);
+    hasher.0
+}
@@ -0,0 +1,323 @@
+//! Rust type definitions inferred from sample NBT.
+//!
+//! A `Generator` is fed one or more sample files and infers the structure
+//! they share, which it writes out as Serde-derived structs: one for each
+//! kind of Compound, with a field for each key. Keys missing from some of
+//! the samples become `Option`s, array tags keep their tag through the
+//! `nbt::byte_array`, `nbt::int_array` and `nbt::long_array` helpers, and
+//! anything that takes different tags in different samples falls back to
+//! `nbt::Value`.
+//!
+//! ```rust
+//! # #[macro_use] extern crate nbt;
+//! use nbt::codegen::Generator;
+//!
+//! # fn main() {
+//! let mut generator = Generator::new("Chunk");
+//! generator.add_value(&nbt!({ xPos: 1i32, Heightmap: [L; 1, 2], Sections: [{ Y: 0i8 }] }));
+//! generator.add_value(&nbt!({ xPos: 2i32, Heightmap: [L; 3, 4], Sections: [] }));
+//! assert_eq!(generator.generate(), r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! pub struct Chunk {
+//!     #[serde(rename = "Heightmap", with = "nbt::long_array")]
+//!     pub heightmap: Vec<i64>,
+//!     #[serde(rename = "Sections")]
+//!     pub sections: Vec<Sections>,
+//!     #[serde(rename = "xPos")]
+//!     pub x_pos: i32,
+//! }
+//!
+//! #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+//! pub struct Sections {
+//!     #[serde(rename = "Y")]
+//!     pub y: i8,
+//! }
+//! "#);
+//! # }
+//! ```
+//!
+//! The output only names the derive macros, so `Serialize` and
+//! `Deserialize` must be in scope where it is included. Optional arrays are
+//! written as a plain `Vec` that is empty when the key is missing, since
+//! the array helpers cannot be combined with `Option`.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::Write;
+
+use blob::Blob;
+use value::Value;
+
+/// Infers struct definitions from sample values. See the module
+/// documentation.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    root: String,
+    shape: Option<Shape>,
+}
+
+/// The structure shared by the samples of one value.
+#[derive(Clone, Debug)]
+enum Shape {
+    /// A value that is neither a List nor a Compound, by its type ID.
+    Tag(i8),
+    /// A List, with the shape of its elements if any have been seen.
+    List(Option<Box<Shape>>),
+    Compound(Fields),
+    /// Values with different tags.
+    Mixed,
+}
+
+/// The entries seen in the samples of a Compound.
+#[derive(Clone, Debug, Default)]
+struct Fields {
+    /// How many samples there were.
+    samples: usize,
+    /// The shape of each key, and how many samples it was present in.
+    entries: BTreeMap<String, (Shape, usize)>,
+}
+
+impl Shape {
+    fn of(value: &Value) -> Shape {
+        let mut shape = match *value {
+            Value::List(_) => Shape::List(None),
+            Value::Compound(_) => Shape::Compound(Fields::default()),
+            _ => Shape::Tag(value.id()),
+        };
+        shape.merge(value);
+        shape
+    }
+
+    /// Widens this shape to also describe `value`.
+    fn merge(&mut self, value: &Value) {
+        match (&mut *self, value) {
+            (Shape::Tag(id), _) if *id == value.id() => {},
+            (Shape::List(element), Value::List(values)) => {
+                for value in values {
+                    match *element {
+                        Some(ref mut shape) => shape.merge(value),
+                        None => *element = Some(Box::new(Shape::of(value))),
+                    }
+                }
+            },
+            (Shape::Compound(fields), Value::Compound(entries)) => fields.merge(entries.iter()),
+            (Shape::Mixed, _) => {},
+            _ => *self = Shape::Mixed,
+        }
+    }
+}
+
+impl Fields {
+    fn merge<'a, I: Iterator<Item = (&'a String, &'a Value)>>(&mut self, entries: I) {
+        self.samples += 1;
+        for (key, value) in entries {
+            match self.entries.get_mut(key) {
+                Some((shape, count)) => {
+                    shape.merge(value);
+                    *count += 1;
+                },
+                None => { self.entries.insert(key.clone(), (Shape::of(value), 1)); },
+            }
+        }
+    }
+}
+
+impl Generator {
+    /// Creates a generator naming the struct for the root Compound `root`.
+    pub fn new<S: Into<String>>(root: S) -> Generator {
+        Generator { root: root.into(), shape: None }
+    }
+
+    /// Adds the root Compound of a sample file.
+    pub fn add_blob(&mut self, blob: &Blob) {
+        match self.shape {
+            Some(Shape::Compound(ref mut fields)) => fields.merge(blob.iter()),
+            Some(ref mut shape) => *shape = Shape::Mixed,
+            None => {
+                let mut fields = Fields::default();
+                fields.merge(blob.iter());
+                self.shape = Some(Shape::Compound(fields));
+            },
+        }
+    }
+
+    /// Adds a sample value, which is normally a Compound. For any other
+    /// value the root is a type alias.
+    pub fn add_value(&mut self, value: &Value) {
+        match self.shape {
+            Some(ref mut shape) => shape.merge(value),
+            None => self.shape = Some(Shape::of(value)),
+        }
+    }
+
+    /// Writes the definitions for the samples added so far, starting with
+    /// the root and followed by the structs nested in it.
+    pub fn generate(&self) -> String {
+        let mut out = Output { code: String::new(), names: BTreeSet::new(), pending: VecDeque::new() };
+        out.names.insert(self.root.clone());
+        match self.shape {
+            Some(Shape::Compound(ref fields)) => out.pending.push_back((self.root.clone(), fields)),
+            Some(ref shape) => {
+                let ty = out.type_of(shape, &self.root).0;
+                writeln!(out.code, "pub type {} = {};", self.root, ty).unwrap();
+            },
+            None => out.pending.push_back((self.root.clone(), &EMPTY)),
+        }
+        while let Some((name, fields)) = out.pending.pop_front() {
+            if !out.code.is_empty() {
+                out.code.push('\n');
+            }
+            out.write_struct(&name, fields);
+        }
+        out.code
+    }
+}
+
+static EMPTY: Fields = Fields { samples: 0, entries: BTreeMap::new() };
+
+/// The code written so far, and the structs still to write.
+struct Output<'a> {
+    code: String,
+    names: BTreeSet<String>,
+    pending: VecDeque<(String, &'a Fields)>,
+}
+
+impl<'a> Output<'a> {
+    fn write_struct(&mut self, name: &str, fields: &'a Fields) {
+        self.code.push_str("#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]\n");
+        writeln!(self.code, "pub struct {} {{", name).unwrap();
+        let mut idents = BTreeSet::new();
+        for (key, (shape, count)) in &fields.entries {
+            let ident = unique(&mut idents, field_name(key));
+            let (ty, with) = self.type_of(shape, key);
+            let optional = *count < fields.samples;
+            let mut attrs = Vec::new();
+            if ident != *key {
+                attrs.push(format!("rename = {:?}", key));
+            }
+            if optional {
+                attrs.push("default".to_string());
+                let empty = if with.is_some() { "Vec::is_empty" } else { "Option::is_none" };
+                attrs.push(format!("skip_serializing_if = \"{}\"", empty));
+            }
+            if let Some(with) = with {
+                attrs.push(format!("with = \"nbt::{}\"", with));
+            }
+            if !attrs.is_empty() {
+                writeln!(self.code, "    #[serde({})]", attrs.join(", ")).unwrap();
+            }
+            if optional && with.is_none() {
+                writeln!(self.code, "    pub {}: Option<{}>,", ident, ty).unwrap();
+            } else {
+                writeln!(self.code, "    pub {}: {},", ident, ty).unwrap();
+            }
+        }
+        self.code.push_str("}\n");
+    }
+
+    /// The Rust type for a shape, and the array helper it needs, queueing
+    /// any struct it names. `key` is where the shape was found, which names
+    /// the struct.
+    fn type_of(&mut self, shape: &'a Shape, key: &str) -> (String, Option<&'static str>) {
+        let ty = match *shape {
+            Shape::Tag(0x01) => "i8",
+            Shape::Tag(0x02) => "i16",
+            Shape::Tag(0x03) => "i32",
+            Shape::Tag(0x04) => "i64",
+            Shape::Tag(0x05) => "f32",
+            Shape::Tag(0x06) => "f64",
+            Shape::Tag(0x07) => return ("Vec<i8>".to_string(), Some("byte_array")),
+            Shape::Tag(0x08) => "String",
+            Shape::Tag(0x0b) => return ("Vec<i32>".to_string(), Some("int_array")),
+            Shape::Tag(0x0c) => return ("Vec<i64>".to_string(), Some("long_array")),
+            Shape::List(Some(ref element)) => {
+                // Lists of arrays have no helper, so their elements are
+                // left for `nbt::Value` to keep.
+                return match self.type_of(element, key) {
+                    (ty, None) => (format!("Vec<{}>", ty), None),
+                    (_, Some(_)) => ("Vec<nbt::Value>".to_string(), None),
+                };
+            },
+            Shape::Compound(ref fields) => {
+                let name = unique(&mut self.names, struct_name(key));
+                self.pending.push_back((name.clone(), fields));
+                return (name, None);
+            },
+            Shape::List(None) => "Vec<nbt::Value>",
+            Shape::Tag(_) | Shape::Mixed => "nbt::Value",
+        };
+        (ty.to_string(), None)
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Adds `name` to `used`, with a number appended if it is already there.
+fn unique(used: &mut BTreeSet<String>, name: String) -> String {
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while used.contains(&candidate) {
+        candidate = format!("{}{}", name, n);
+        n += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+/// Splits a key into words at punctuation and changes of case, so that
+/// `HurtByTimestamp`, `hurt_by_timestamp` and `UUIDMost` all split.
+fn words(key: &str) -> Vec<String> {
+    let chars: Vec<char> = key.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(word.split_off(0));
+            }
+            continue;
+        }
+        let prev = if i > 0 { chars[i - 1] } else { ' ' };
+        let next = chars.get(i + 1).cloned().unwrap_or(' ');
+        let boundary = c.is_uppercase()
+            && (prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next.is_lowercase()));
+        if boundary && !word.is_empty() {
+            words.push(word.split_off(0));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// The snake case field name for a key.
+fn field_name(key: &str) -> String {
+    let mut name = words(key).iter().map(|word| word.to_lowercase()).collect::<Vec<_>>().join("_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_numeric()) {
+        name.insert_str(0, "field_");
+    }
+    if KEYWORDS.contains(&&name[..]) {
+        name.push('_');
+    }
+    name
+}
+
+/// The camel case struct name for a key.
+fn struct_name(key: &str) -> String {
+    let mut name = String::new();
+    for word in words(key) {
+        let mut chars = word.chars();
+        name.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+        name.extend(chars);
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_numeric()) {
+        name.insert(0, 'T');
+    }
+    name
+}
@@ -1,13 +1,18 @@
 //! Deserialize Named Binary Tag data to a Rust data structure.
 
+use std::borrow::Cow;
 use std::io;
 
 use serde::de;
-use flate2::read;
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use flate::{GzDecoder, ZlibDecoder};
 
 use raw::{RawReader, Endianness};
 
 use error::{Error, Result};
+use named::NAMED_TOKEN;
+use value::{self, Value, VALUE_TOKEN};
 
 /// Decode an object from Named Binary Tag (NBT) format.
 ///
@@ -25,11 +30,12 @@ pub fn from_reader<R, T>(src: R, endian: Endianness) -> Result<T>
 ///
 /// Note that only maps and structs can be decoded, because the NBT format does
 /// not support bare types. Other types will return `Error::NoRootCompound`.
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 pub fn from_gzip_reader<R, T>(src: R, endian: Endianness) -> Result<T>
     where R: io::Read,
           T: de::DeserializeOwned,
 {
-    let gzip = read::GzDecoder::new(src)?;
+    let gzip = GzDecoder::new(src)?;
     from_reader(gzip, endian)
 }
 
@@ -37,14 +43,72 @@ pub fn from_gzip_reader<R, T>(src: R, endian: Endianness) -> Result<T>
 ///
 /// Note that only maps and structs can be decoded, because the NBT format does
 /// not support bare types. Other types will return `Error::NoRootCompound`.
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 pub fn from_zlib_reader<R, T>(src: R, endian: Endianness) -> Result<T>
     where R: io::Read,
           T: de::DeserializeOwned,
 {
-    let zlib = read::ZlibDecoder::new(src);
+    let zlib = ZlibDecoder::new(src);
     from_reader(zlib, endian)
 }
 
+/// Decode an object from Named Binary Tag (NBT) data held in memory.
+///
+/// Unlike `from_reader`, the decoded object may borrow from `src`: fields of
+/// type `&str` and `&[u8]` point straight into the buffer instead of being
+/// copied. Strings can only be borrowed when their Modified UTF-8 bytes are
+/// also valid UTF-8, which is the case unless they contain NUL characters or
+/// characters outside the Basic Multilingual Plane.
+///
+/// ```
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate nbt;
+/// # use nbt::Endianness;
+/// #[derive(Deserialize)]
+/// struct Sign<'a> {
+///     text: &'a str,
+/// }
+///
+/// # fn main() {
+/// let bytes = [0x0a, 0x00, 0x00,
+///              0x08, 0x00, 0x04, b't', b'e', b'x', b't',
+///              0x00, 0x02, b'h', b'i',
+///              0x00];
+/// let sign: Sign = nbt::de::from_slice(&bytes, Endianness::BigEndian).unwrap();
+/// assert_eq!(sign.text, "hi");
+/// # }
+/// ```
+///
+/// Note that only maps and structs can be decoded, because the NBT format does
+/// not support bare types. Other types will return `Error::NoRootCompound`.
+pub fn from_slice<'de, T>(src: &'de [u8], endian: Endianness) -> Result<T>
+    where T: de::Deserialize<'de>,
+{
+    let mut decoder = SliceDecoder::new(src, endian);
+    de::Deserialize::deserialize(&mut decoder)
+}
+
+/// A source of NBT payloads for the decoders below.
+///
+/// Readers can only produce owned strings and byte arrays, whereas slices can
+/// lend them out for the lifetime of the input.
+trait Source<'de> {
+    type Inner: io::Read;
+
+    fn reader(&mut self) -> &mut RawReader<Self::Inner>;
+
+    /// Reads a `TAG_String` payload.
+    fn read_str(&mut self) -> Result<Cow<'de, str>> {
+        self.reader().read_bare_string().map(Cow::Owned)
+    }
+
+    /// Reads a `TAG_Byte_Array` payload as unsigned bytes.
+    fn read_bytes(&mut self) -> Result<Cow<'de, [u8]>> {
+        let bytes = self.reader().read_bare_byte_array()?;
+        Ok(Cow::Owned(bytes.into_iter().map(|b| b as u8).collect()))
+    }
+}
+
 /// Decode objects from Named Binary Tag (NBT) format.
 ///
 /// Note that only maps and structs can be decoded, because the NBT format does
@@ -59,24 +123,48 @@ impl<R> Decoder<R> where R: io::Read {
     pub fn new(src: R, endian: Endianness) -> Self {
         Decoder { reader: RawReader::new(src, endian) }
     }
+
+    /// Sets whether Compound keys are interned, so that a key seen before is
+    /// lent to the visitor rather than decoded into a new `String`. Structs
+    /// then match their fields without allocating, which pays off for
+    /// inputs with many Compounds of the same shape, such as chunks.
+    ///
+    /// Nothing is gained for maps, `Value`s included, since they copy each
+    /// lent key into a `String` of their own, so the memory a decoded tree
+    /// uses is the same either way. See `RawReader::set_intern_keys()`.
+    pub fn set_intern_keys(&mut self, intern: bool) {
+        self.reader.set_intern_keys(intern);
+    }
+}
+
+impl<'de, R: io::Read> Source<'de> for Decoder<R> {
+    type Inner = R;
+
+    fn reader(&mut self) -> &mut RawReader<R> {
+        &mut self.reader
+    }
 }
 
 impl<'de: 'a, 'a, R: io::Read> de::Deserializer<'de> for &'a mut Decoder<R> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    /// The root of an NBT document is always a compound, so self-describing
+    /// types (such as the buffered contents of `#[serde(flatten)]` fields or
+    /// untagged enums) see a map.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
-        // The decoder cannot deserialize types by default. It can only handle
-        // maps and structs.
-        Err(Error::NoRootCompound)
+        self.deserialize_map(visitor)
     }
 
-    fn deserialize_struct<V>(self, _name: &'static str,
+    fn deserialize_struct<V>(self, name: &'static str,
                              _fields: &'static [&'static str], visitor: V)
                              -> Result<V::Value>
         where V: de::Visitor<'de>
     {
+        if name == NAMED_TOKEN {
+            return NamedDecoder::root(self, visitor);
+        }
         self.deserialize_map(visitor)
     }
 
@@ -87,11 +175,15 @@ impl<'de: 'a, 'a, R: io::Read> de::Deserializer<'de> for &'a mut Decoder<R> {
         visitor.visit_unit()
     }
 
-    /// Deserialize newtype structs by their underlying types.
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V)
+    /// Deserialize newtype structs by their underlying types, except that a
+    /// `Value` is told its root is a compound.
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V)
                                      -> Result<V::Value>
         where V: de::Visitor<'de>
     {
+        if name == VALUE_TOKEN {
+            return visitor.visit_enum(TagAccess { tag: 0x0a, de: self });
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -107,32 +199,220 @@ impl<'de: 'a, 'a, R: io::Read> de::Deserializer<'de> for &'a mut Decoder<R> {
         }
     }
 
+    /// Deserialize enums from a root compound with a single entry named
+    /// after the variant, as written by the encoder.
+    fn deserialize_enum<V>(self, _name: &'static str,
+                           _variants: &'static [&'static str], visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Ignore the header (if there is one).
+        let (tag, _) = self.reader.emit_next_header()?;
+
+        match tag {
+            0x0a => visitor.visit_enum(EnumDecoder::new(self)),
+            _ => Err(Error::NoRootCompound)
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string bytes byte_buf
+        unit seq tuple_struct tuple option identifier ignored_any
+    }
+}
+
+/// Decode objects from Named Binary Tag (NBT) data held in memory, borrowing
+/// strings and byte arrays from it where possible. See `from_slice`.
+///
+/// Note that only maps and structs can be decoded, because the NBT format does
+/// not support bare types. Other types will return `Error::NoRootCompound`.
+pub struct SliceDecoder<'de> {
+    reader: RawReader<&'de [u8]>,
+}
+
+impl<'de> SliceDecoder<'de> {
+
+    /// Create an NBT Decoder over a byte slice.
+    pub fn new(src: &'de [u8], endian: Endianness) -> Self {
+        SliceDecoder { reader: RawReader::new(src, endian) }
+    }
+
+    /// Splits the next `len` bytes off the remaining input.
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        let rest: &mut &'de [u8] = self.reader.get_mut();
+        if rest.len() < len {
+            return Err(Error::IncompleteNbtValue);
+        }
+        let (head, tail) = rest.split_at(len);
+        *rest = tail;
+        Ok(head)
+    }
+}
+
+impl<'de> Source<'de> for SliceDecoder<'de> {
+    type Inner = &'de [u8];
+
+    fn reader(&mut self) -> &mut RawReader<&'de [u8]> {
+        &mut self.reader
+    }
+
+    fn read_str(&mut self) -> Result<Cow<'de, str>> {
+        let len = self.reader.read_string_len()?;
+        let bytes = self.take(len)?;
+        self.reader.decode_str(bytes)
+    }
+
+    fn read_bytes(&mut self) -> Result<Cow<'de, [u8]>> {
+        let len = self.reader.read_array_len()?;
+        Ok(Cow::Borrowed(self.take(len)?))
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserializer<'de> for &'a mut SliceDecoder<'de> {
+    type Error = Error;
+
+    /// The root of an NBT document is always a compound, so self-describing
+    /// types (such as the buffered contents of `#[serde(flatten)]` fields or
+    /// untagged enums) see a map.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V>(self, name: &'static str,
+                             _fields: &'static [&'static str], visitor: V)
+                             -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if name == NAMED_TOKEN {
+            return NamedDecoder::root(self, visitor);
+        }
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V)
+                                  -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+
+    /// Deserialize newtype structs by their underlying types, except that a
+    /// `Value` is told its root is a compound.
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V)
+                                     -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if name == VALUE_TOKEN {
+            return visitor.visit_enum(TagAccess { tag: 0x0a, de: self });
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let (tag, _) = self.reader.emit_next_header()?;
+
+        match tag {
+            0x0a => visitor.visit_map(MapDecoder::new(self)),
+            _ => Err(Error::NoRootCompound)
+        }
+    }
+
+    /// Deserialize enums from a root compound with a single entry named
+    /// after the variant, as written by the encoder.
+    fn deserialize_enum<V>(self, _name: &'static str,
+                           _variants: &'static [&'static str], visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        // Ignore the header (if there is one).
+        let (tag, _) = self.reader.emit_next_header()?;
+
+        match tag {
+            0x0a => visitor.visit_enum(EnumDecoder::new(self)),
+            _ => Err(Error::NoRootCompound)
+        }
+    }
+
     forward_to_deserialize_any! {
         bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string bytes byte_buf
-        unit seq tuple_struct tuple option enum identifier ignored_any
+        unit seq tuple_struct tuple option identifier ignored_any
+    }
+}
+
+/// Decoder for the root of a `Named`, which presents the name of the root
+/// compound and its contents as the `name` and `value` fields.
+struct NamedDecoder<'a, S: 'a> {
+    outer: &'a mut S,
+    name: Option<String>,
+    done: bool,
+}
+
+impl<'a, S> NamedDecoder<'a, S> {
+
+    fn root<'de, V>(outer: &'a mut S, visitor: V) -> Result<V::Value>
+        where S: Source<'de>,
+              V: de::Visitor<'de>,
+    {
+        let (tag, name) = outer.reader().emit_next_header()?;
+
+        match tag {
+            0x0a => visitor.visit_map(NamedDecoder { outer, name: Some(name), done: false }),
+            _ => Err(Error::NoRootCompound)
+        }
+    }
+}
+
+impl<'de, 'a, S: Source<'de> + 'a> de::MapAccess<'de> for NamedDecoder<'a, S> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: de::DeserializeSeed<'de>
+    {
+        let key = match (&self.name, self.done) {
+            (&Some(_), _) => "name",
+            (&None, false) => "value",
+            (&None, true) => return Ok(None),
+        };
+        seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: de::DeserializeSeed<'de>
+    {
+        match self.name.take() {
+            Some(name) => seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(name)),
+            None => {
+                self.done = true;
+                seed.deserialize(&mut InnerDecoder { outer: self.outer, tag: 0x0a })
+            },
+        }
     }
 }
 
 /// Decoder for map-like types.
-struct MapDecoder<'a, R: io::Read + 'a> {
-    outer: &'a mut Decoder<R>,
+struct MapDecoder<'a, S: 'a> {
+    outer: &'a mut S,
     tag: Option<i8>,
 }
 
-impl<'a, R> MapDecoder<'a, R> where R: io::Read {
+impl<'a, S> MapDecoder<'a, S> {
 
-    fn new(outer: &'a mut Decoder<R>) -> Self {
+    fn new(outer: &'a mut S) -> Self {
         MapDecoder { outer: outer, tag: None }
     }
 }
 
-impl<'de: 'a, 'a, R: io::Read + 'a> de::MapAccess<'de> for MapDecoder<'a, R> {
+impl<'de, 'a, S: Source<'de> + 'a> de::MapAccess<'de> for MapDecoder<'a, S> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
         where K: de::DeserializeSeed<'de>
     {
-        let tag = self.outer.reader.read_bare_byte()?;
+        let tag = self.outer.reader().read_bare_byte()?;
 
         // NBT indicates the end of a compound type with a 0x00 tag.
         if tag == 0x00 {
@@ -142,6 +422,11 @@ impl<'de: 'a, 'a, R: io::Read + 'a> de::MapAccess<'de> for MapDecoder<'a, R> {
         // Keep track of the tag so that we can decode the field correctly.
         self.tag = Some(tag);
 
+        if self.outer.reader().interns_keys() {
+            let key = self.outer.reader().read_interned_string()?;
+            return seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(&*key)).map(Some);
+        }
+
         // TODO: Enforce that keys must be String. This is a bit of a hack.
         let mut de = InnerDecoder { outer: self.outer, tag: 0x08 };
 
@@ -160,36 +445,36 @@ impl<'de: 'a, 'a, R: io::Read + 'a> de::MapAccess<'de> for MapDecoder<'a, R> {
 }
 
 /// Decoder for list-like types.
-struct SeqDecoder<'a, R: io::Read + 'a> {
-    outer: &'a mut Decoder<R>,
+struct SeqDecoder<'a, S: 'a> {
+    outer: &'a mut S,
     tag: i8,
     length: i32,
     current: i32,
 }
 
-impl<'a, R> SeqDecoder<'a, R> where R: io::Read {
+impl<'a, S> SeqDecoder<'a, S> {
 
-    fn list(outer: &'a mut Decoder<R>) -> Result<Self> {
-        let tag = outer.reader.read_bare_byte()?;
-        let length = outer.reader.read_bare_int()?;
+    fn list<'de>(outer: &'a mut S) -> Result<Self> where S: Source<'de> {
+        let tag = outer.reader().read_bare_byte()?;
+        let length = outer.reader().read_list_len()? as i32;
         Ok(SeqDecoder { outer: outer, tag: tag, length: length,
                         current: 0 })
     }
 
-    fn byte_array(outer: &'a mut Decoder<R>) -> Result<Self> {
-        let length = outer.reader.read_bare_int()?;
+    fn byte_array<'de>(outer: &'a mut S) -> Result<Self> where S: Source<'de> {
+        let length = outer.reader().read_array_len()? as i32;
         Ok(SeqDecoder { outer: outer, tag: 0x01, length: length,
                         current: 0 })
     }
 
-    fn int_array(outer: &'a mut Decoder<R>) -> Result<Self> {
-        let length = outer.reader.read_bare_int()?;
+    fn int_array<'de>(outer: &'a mut S) -> Result<Self> where S: Source<'de> {
+        let length = outer.reader().read_array_len()? as i32;
         Ok(SeqDecoder { outer: outer, tag: 0x03, length: length,
                         current: 0 })
     }
 
-    fn long_array(outer: &'a mut Decoder<R>) -> Result<Self> {
-        let length = outer.reader.read_bare_int()?;
+    fn long_array<'de>(outer: &'a mut S) -> Result<Self> where S: Source<'de> {
+        let length = outer.reader().read_array_len()? as i32;
         Ok(SeqDecoder {
             outer,
             tag: 0x04,
@@ -199,7 +484,7 @@ impl<'a, R> SeqDecoder<'a, R> where R: io::Read {
     }
 }
 
-impl<'de: 'a, 'a, R: io::Read + 'a> de::SeqAccess<'de> for SeqDecoder<'a, R> {
+impl<'de, 'a, S: Source<'de> + 'a> de::SeqAccess<'de> for SeqDecoder<'a, S> {
     type Error = Error;
 
     fn next_element_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
@@ -223,31 +508,46 @@ impl<'de: 'a, 'a, R: io::Read + 'a> de::SeqAccess<'de> for SeqDecoder<'a, R> {
     }
 }
 
+/// Decodes a nested List or Compound payload with `decode`, keeping track of
+/// the nesting depth.
+fn nested<'de, S, T, F>(outer: &mut S, decode: F) -> Result<T>
+    where S: Source<'de>,
+          F: FnOnce(&mut S) -> Result<T>,
+{
+    outer.reader().begin_nested()?;
+    let value = decode(outer)?;
+    outer.reader().end_nested();
+    Ok(value)
+}
+
 /// Private inner decoder, for decoding raw (i.e. non-Compound) types.
-struct InnerDecoder<'a, R: io::Read + 'a> {
-    outer: &'a mut Decoder<R>,
+struct InnerDecoder<'a, S: 'a> {
+    outer: &'a mut S,
     tag: i8,
 }
 
-impl<'a, 'b: 'a, 'de, R: io::Read> de::Deserializer<'de> for &'b mut InnerDecoder<'a, R> {
+impl<'a, 'b: 'a, 'de, S: Source<'de>> de::Deserializer<'de> for &'b mut InnerDecoder<'a, S> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
     {
-        let ref mut outer = self.outer;
+        let outer = &mut *self.outer;
 
         match self.tag {
-            0x01 => visitor.visit_i8(outer.reader.read_bare_byte()?),
-            0x02 => visitor.visit_i16(outer.reader.read_bare_short()?),
-            0x03 => visitor.visit_i32(outer.reader.read_bare_int()?),
-            0x04 => visitor.visit_i64(outer.reader.read_bare_long()?),
-            0x05 => visitor.visit_f32(outer.reader.read_bare_float()?),
-            0x06 => visitor.visit_f64(outer.reader.read_bare_double()?),
+            0x01 => visitor.visit_i8(outer.reader().read_bare_byte()?),
+            0x02 => visitor.visit_i16(outer.reader().read_bare_short()?),
+            0x03 => visitor.visit_i32(outer.reader().read_bare_int()?),
+            0x04 => visitor.visit_i64(outer.reader().read_bare_long()?),
+            0x05 => visitor.visit_f32(outer.reader().read_bare_float()?),
+            0x06 => visitor.visit_f64(outer.reader().read_bare_double()?),
             0x07 => visitor.visit_seq(SeqDecoder::byte_array(outer)?),
-            0x08 => visitor.visit_string(outer.reader.read_bare_string()?),
-            0x09 => visitor.visit_seq(SeqDecoder::list(outer)?),
-            0x0a => visitor.visit_map(MapDecoder::new(outer)),
+            0x08 => match outer.read_str()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            0x09 => nested(outer, |outer| visitor.visit_seq(SeqDecoder::list(outer)?)),
+            0x0a => nested(outer, |outer| visitor.visit_map(MapDecoder::new(outer))),
             0x0b => visitor.visit_seq(SeqDecoder::int_array(outer)?),
             0x0c => visitor.visit_seq(SeqDecoder::long_array(outer)?),
             t => Err(Error::InvalidTypeId(t)),
@@ -260,8 +560,7 @@ impl<'a, 'b: 'a, 'de, R: io::Read> de::Deserializer<'de> for &'b mut InnerDecode
     {
         match self.tag {
             0x01 => {
-                let ref mut reader = self.outer.reader;
-                let value = reader.read_bare_byte()?;
+                let value = self.outer.reader().read_bare_byte()?;
                 match value {
                     0 => visitor.visit_bool(false),
                     1 => visitor.visit_bool(true),
@@ -272,6 +571,26 @@ impl<'a, 'b: 'a, 'de, R: io::Read> de::Deserializer<'de> for &'b mut InnerDecode
         }
     }
 
+    /// Deserialize `TAG_Byte_Array` payloads as raw bytes, borrowing them
+    /// from the input when decoding a slice.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.tag {
+            0x07 => match self.outer.read_bytes()? {
+                Cow::Borrowed(b) => visitor.visit_borrowed_bytes(b),
+                Cow::Owned(b) => visitor.visit_byte_buf(b),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_bytes(visitor)
+    }
+
     /// Interpret missing values as None.
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
         where V: de::Visitor<'de>
@@ -292,16 +611,318 @@ impl<'a, 'b: 'a, 'de, R: io::Read> de::Deserializer<'de> for &'b mut InnerDecode
         visitor.visit_unit()
     }
 
-    /// Deserialize newtype structs by their underlying types.
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V)
+    /// Deserialize newtype structs by their underlying types, except that a
+    /// `Value` is told the tag of its payload. Lists and Compounds need no
+    /// help, and skipping it keeps the stack used by deep nesting down.
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V)
                                      -> Result<V::Value>
         where V: de::Visitor<'de>
     {
+        match self.tag {
+            0x09 if name == VALUE_TOKEN => {
+                return nested(self.outer, |outer| visitor.visit_seq(SeqDecoder::list(outer)?));
+            },
+            0x0a if name == VALUE_TOKEN => {
+                return nested(self.outer, |outer| visitor.visit_map(MapDecoder::new(outer)));
+            },
+            _ if name == VALUE_TOKEN => return visitor.visit_enum(TagAccess { tag: self.tag, de: self }),
+            _ => {},
+        }
         visitor.visit_newtype_struct(self)
     }
 
+    /// Deserialize unit variants from a `TAG_String`, and other variants
+    /// from a compound with a single entry named after the variant.
+    fn deserialize_enum<V>(self, _name: &'static str,
+                           _variants: &'static [&'static str], visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self.tag {
+            0x08 => match self.outer.read_str()? {
+                Cow::Borrowed(s) => visitor.visit_enum(de::value::BorrowedStrDeserializer::new(s)),
+                Cow::Owned(s) => visitor.visit_enum(de::IntoDeserializer::<Error>::into_deserializer(s)),
+            },
+            0x0a => nested(self.outer, |outer| visitor.visit_enum(EnumDecoder::new(outer))),
+            _ => Err(Error::TagMismatch(self.tag, 0x08)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
+        map tuple_struct struct tuple identifier ignored_any
+    }
+}
+
+/// Decoder for enum variants stored as a compound with a single entry, whose
+/// name is the variant and whose payload holds its contents.
+struct EnumDecoder<'a, S: 'a> {
+    outer: &'a mut S,
+    tag: i8,
+}
+
+impl<'a, S> EnumDecoder<'a, S> {
+
+    fn new(outer: &'a mut S) -> Self {
+        EnumDecoder { outer, tag: 0x00 }
+    }
+
+    /// Consume the end of the enclosing compound, which must follow the
+    /// variant entry directly.
+    fn finish<'de>(self) -> Result<()> where S: Source<'de> {
+        match self.outer.reader().read_bare_byte()? {
+            0x00 => Ok(()),
+            _ => {
+                let name = self.outer.read_str()?;
+                Err(Error::UnexpectedField(name.into_owned()))
+            },
+        }
+    }
+
+    fn payload<'b>(&'b mut self) -> InnerDecoder<'b, S> {
+        InnerDecoder { outer: &mut *self.outer, tag: self.tag }
+    }
+}
+
+impl<'de, 'a, S: Source<'de> + 'a> de::EnumAccess<'de> for EnumDecoder<'a, S> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self)>
+        where V: de::DeserializeSeed<'de>
+    {
+        self.tag = self.outer.reader().read_bare_byte()?;
+        if self.tag == 0x00 {
+            return Err(de::Error::invalid_length(0, &"a compound with a single entry"));
+        }
+        let variant = seed.deserialize(&mut InnerDecoder { outer: &mut *self.outer, tag: 0x08 })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a, S: Source<'de> + 'a> de::VariantAccess<'de> for EnumDecoder<'a, S> {
+    type Error = Error;
+
+    fn unit_variant(mut self) -> Result<()> {
+        <de::IgnoredAny as de::Deserialize>::deserialize(&mut self.payload())?;
+        self.finish()
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value>
+        where T: de::DeserializeSeed<'de>
+    {
+        let value = seed.deserialize(&mut self.payload())?;
+        self.finish()?;
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(mut self, len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let value = de::Deserializer::deserialize_tuple(&mut self.payload(), len, visitor)?;
+        self.finish()?;
+        Ok(value)
+    }
+
+    fn struct_variant<V>(mut self, fields: &'static [&'static str], visitor: V)
+                         -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        let value = de::Deserializer::deserialize_struct(&mut self.payload(), "", fields, visitor)?;
+        self.finish()?;
+        Ok(value)
+    }
+}
+
+/// Hands a `Value` the tag of the payload it is deserialized from, as an
+/// enum variant named after the tag whose contents are the payload. This
+/// keeps tags that the payload's Rust types would lose, such as those of
+/// arrays.
+struct TagAccess<D> {
+    tag: i8,
+    de: D,
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> de::EnumAccess<'de> for TagAccess<D> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+        where V: de::DeserializeSeed<'de>
+    {
+        let name = value::variant_name(self.tag)?;
+        let variant = seed.deserialize(de::value::BorrowedStrDeserializer::<Error>::new(name))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, D: de::Deserializer<'de, Error = Error>> de::VariantAccess<'de> for TagAccess<D> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a unit variant"))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where T: de::DeserializeSeed<'de>
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a tuple variant"))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V)
+                         -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"a struct variant"))
+    }
+}
+
+/// Decode an object from a dynamic `Value` tree, without encoding it to bytes.
+///
+/// Tags map to Rust types the same way as in `from_reader`, except that bare
+/// values are allowed at the root.
+///
+/// ```
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate nbt;
+/// # use nbt::{Map, Value};
+/// #[derive(Deserialize)]
+/// struct Player {
+///     name: String,
+///     health: i8,
+/// }
+///
+/// # fn main() {
+/// let mut map = Map::new();
+/// map.insert("name".to_string(), Value::String("Herobrine".to_string()));
+/// map.insert("health".to_string(), Value::Byte(100));
+///
+/// let player: Player = nbt::de::from_value(Value::Compound(map)).unwrap();
+/// assert_eq!(player.name, "Herobrine");
+/// assert_eq!(player.health, 100);
+/// # }
+/// ```
+pub fn from_value<T>(value: Value) -> Result<T>
+    where T: de::DeserializeOwned,
+{
+    de::Deserialize::deserialize(value)
+}
+
+impl<'de> de::IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self {
+            Value::Byte(v) => visitor.visit_i8(v),
+            Value::Short(v) => visitor.visit_i16(v),
+            Value::Int(v) => visitor.visit_i32(v),
+            Value::Long(v) => visitor.visit_i64(v),
+            Value::Float(v) => visitor.visit_f32(v),
+            Value::Double(v) => visitor.visit_f64(v),
+            Value::ByteArray(v) => SeqDeserializer::new(v.into_iter()).deserialize_any(visitor),
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(v) => SeqDeserializer::new(v.into_iter()).deserialize_any(visitor),
+            Value::Compound(v) => MapDeserializer::new(v.into_iter()).deserialize_any(visitor),
+            Value::IntArray(v) => SeqDeserializer::new(v.into_iter()).deserialize_any(visitor),
+            Value::LongArray(v) => SeqDeserializer::new(v.into_iter()).deserialize_any(visitor),
+        }
+    }
+
+    /// Deserialize bool values from a byte. Fail if that byte is not 0 or 1.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self {
+            Value::Byte(0) => visitor.visit_bool(false),
+            Value::Byte(1) => visitor.visit_bool(true),
+            Value::Byte(b) => Err(Error::NonBooleanByte(b)),
+            other => Err(Error::TagMismatch(other.id(), 0x01)),
+        }
+    }
+
+    /// Deserialize `TAG_Byte_Array` values as raw bytes.
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self {
+            Value::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Interpret missing values as None.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V)
+                                  -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+
+    /// Deserialize newtype structs by their underlying types, except that a
+    /// `Value` is told the tag of the value it copies.
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V)
+                                     -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        if name == VALUE_TOKEN {
+            return visitor.visit_enum(TagAccess { tag: self.id(), de: self });
+        }
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Deserialize unit variants from a `Value::String`, and other variants
+    /// from a `Value::Compound` with a single entry named after the variant.
+    fn deserialize_enum<V>(self, _name: &'static str,
+                           _variants: &'static [&'static str], visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match self {
+            Value::String(v) => visitor.visit_enum(de::IntoDeserializer::<Error>::into_deserializer(v)),
+            Value::Compound(ref v) if v.len() != 1 =>
+                Err(de::Error::invalid_length(v.len(), &"a compound with a single entry")),
+            Value::Compound(v) =>
+                visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(v.into_iter()))),
+            other => Err(Error::TagMismatch(other.id(), 0x08)),
+        }
+    }
+
     forward_to_deserialize_any! {
-        u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string bytes byte_buf seq
-        map tuple_struct struct tuple enum identifier ignored_any
+        u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str string seq
+        map tuple_struct struct tuple identifier ignored_any
     }
 }
@@ -0,0 +1,1115 @@
+//! DEFLATE compression (RFC 1951) with the zlib (RFC 1950) and gzip
+//! (RFC 1952) wrappers, written in Rust so that the `rust_backend` feature
+//! can replace flate2 and its C dependency.
+//!
+//! The types mirror the parts of flate2 this crate uses, so `flate` can
+//! pick either backend. Encoders find matches with hash chains, whose
+//! length depends on the `CompressionLevel`, and write each block in
+//! whichever of the stored, fixed and dynamic forms is smallest.
+
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use options::CompressionLevel;
+
+const WINDOW_SIZE: usize = 32 * 1024;
+/// The input compressed as one block, which also fits a stored block.
+const BLOCK_LEN: usize = 65535;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// The order in which the lengths of the code length code are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn fixed_lengths() -> (Vec<u8>, Vec<u8>) {
+    let mut literals = vec![8; 288];
+    for length in &mut literals[144..256] {
+        *length = 9;
+    }
+    for length in &mut literals[256..280] {
+        *length = 7;
+    }
+    (literals, vec![5; 30])
+}
+
+/* Checksums */
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// The CRC-32 checksum of gzip, along with the length it covers.
+struct Crc {
+    crc: u32,
+    len: u32,
+}
+
+impl Crc {
+    fn new() -> Crc {
+        Crc { crc: 0xffff_ffff, len: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.crc = CRC_TABLE[((self.crc ^ byte as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+        self.len = self.len.wrapping_add(data.len() as u32);
+    }
+
+    fn sum(&self) -> u32 {
+        !self.crc
+    }
+}
+
+/// The Adler-32 checksum of zlib.
+struct Adler {
+    a: u32,
+    b: u32,
+}
+
+impl Adler {
+    fn new() -> Adler {
+        Adler { a: 1, b: 0 }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        // The largest run that cannot overflow before taking the modulus.
+        for chunk in data.chunks(5552) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= 65521;
+            self.b %= 65521;
+        }
+    }
+
+    fn sum(&self) -> u32 {
+        self.b << 16 | self.a
+    }
+}
+
+/* Decoding */
+
+/// Reads bits least significant first, as DEFLATE packs them.
+struct BitReader<R> {
+    inner: R,
+    bits: u64,
+    count: u32,
+}
+
+impl<R: BufRead> BitReader<R> {
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let byte = match self.inner.fill_buf()?.first() {
+            Some(&byte) => byte,
+            None => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+        self.inner.consume(1);
+        Ok(byte)
+    }
+
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            self.bits |= (self.read_byte()? as u64) << self.count;
+            self.count += 8;
+        }
+        let value = (self.bits & ((1 << n) - 1)) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Skips to the next byte boundary, after which `bits()` reads whole
+    /// bytes.
+    fn align(&mut self) {
+        let skip = self.count % 8;
+        self.bits >>= skip;
+        self.count -= skip;
+    }
+}
+
+/// A canonical Huffman code, decoded a bit at a time.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(invalid("over-subscribed Huffman code"));
+            }
+        }
+        let mut offsets = [0u16; 16];
+        for i in 1..15 {
+            offsets[i + 1] = offsets[i] + counts[i];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode<R: BufRead>(&self, src: &mut BitReader<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= src.bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+}
+
+enum Block {
+    Header,
+    Stored(usize),
+    Codes(Huffman, Huffman),
+    Done,
+}
+
+/// Decompresses a raw DEFLATE stream.
+struct Inflater<R> {
+    src: BitReader<R>,
+    /// Recent output, kept for matches to refer back to, followed by output
+    /// not yet read from `pos` on.
+    out: Vec<u8>,
+    pos: usize,
+    block: Block,
+    last: bool,
+}
+
+impl<R: BufRead> Inflater<R> {
+    fn new(src: R) -> Inflater<R> {
+        Inflater {
+            src: BitReader { inner: src, bits: 0, count: 0 },
+            out: Vec::new(),
+            pos: 0,
+            block: Block::Header,
+            last: false,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(self.block, Block::Done)
+    }
+
+    /// Decodes about a window's worth of output, or up to the end of the
+    /// current block.
+    fn decode(&mut self) -> io::Result<()> {
+        if self.out.len() > 2 * WINDOW_SIZE {
+            let drop = self.out.len() - WINDOW_SIZE;
+            self.out.drain(..drop);
+            self.pos -= drop;
+        }
+        let target = self.out.len() + WINDOW_SIZE;
+        match self.block {
+            Block::Header if self.last => self.block = Block::Done,
+            Block::Header => {
+                self.last = self.src.bits(1)? == 1;
+                self.block = match self.src.bits(2)? {
+                    0 => {
+                        self.src.align();
+                        let len = self.src.bits(16)?;
+                        if self.src.bits(16)? != !len & 0xffff {
+                            return Err(invalid("corrupt stored block length"));
+                        }
+                        Block::Stored(len as usize)
+                    },
+                    1 => {
+                        let (literals, distances) = fixed_lengths();
+                        Block::Codes(Huffman::new(&literals)?, Huffman::new(&distances)?)
+                    },
+                    2 => self.read_dynamic()?,
+                    _ => return Err(invalid("invalid block type")),
+                };
+            },
+            Block::Stored(ref mut left) => {
+                let n = cmp::min(*left, WINDOW_SIZE);
+                for _ in 0..n {
+                    let byte = self.src.bits(8)? as u8;
+                    self.out.push(byte);
+                }
+                *left -= n;
+                if *left == 0 {
+                    self.block = Block::Header;
+                }
+            },
+            Block::Codes(ref literals, ref distances) => {
+                while self.out.len() < target {
+                    let symbol = literals.decode(&mut self.src)? as usize;
+                    if symbol < 256 {
+                        self.out.push(symbol as u8);
+                        continue;
+                    }
+                    if symbol == 256 {
+                        self.block = Block::Header;
+                        break;
+                    }
+                    let index = symbol - 257;
+                    if index >= LENGTH_BASE.len() {
+                        return Err(invalid("invalid length code"));
+                    }
+                    let len = LENGTH_BASE[index] as usize + self.src.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                    let index = distances.decode(&mut self.src)? as usize;
+                    if index >= DISTANCE_BASE.len() {
+                        return Err(invalid("invalid distance code"));
+                    }
+                    let distance = DISTANCE_BASE[index] as usize
+                        + self.src.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                    if distance > self.out.len() {
+                        return Err(invalid("distance reaches before the start of the stream"));
+                    }
+                    let from = self.out.len() - distance;
+                    for k in 0..len {
+                        let byte = self.out[from + k];
+                        self.out.push(byte);
+                    }
+                }
+            },
+            Block::Done => {},
+        }
+        Ok(())
+    }
+
+    fn read_dynamic(&mut self) -> io::Result<Block> {
+        let literal_count = self.src.bits(5)? as usize + 257;
+        let distance_count = self.src.bits(5)? as usize + 1;
+        let code_count = self.src.bits(4)? as usize + 4;
+        let mut code_lengths = [0u8; 19];
+        for &i in &CODE_LENGTH_ORDER[..code_count] {
+            code_lengths[i] = self.src.bits(3)? as u8;
+        }
+        let code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let symbol = code.decode(&mut self.src)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => match lengths.last() {
+                    Some(&last) => (last, 3 + self.src.bits(2)?),
+                    None => return Err(invalid("repeated code length with no previous length")),
+                },
+                17 => (0, 3 + self.src.bits(3)?),
+                _ => (0, 11 + self.src.bits(7)?),
+            };
+            if lengths.len() + repeat as usize > literal_count + distance_count {
+                return Err(invalid("too many code lengths"));
+            }
+            lengths.resize(lengths.len() + repeat as usize, value);
+        }
+        if lengths[256] == 0 {
+            return Err(invalid("missing end-of-block code"));
+        }
+        Ok(Block::Codes(Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?))
+    }
+}
+
+impl<R: BufRead> Read for Inflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() && !self.is_done() && !buf.is_empty() {
+            self.decode()?;
+        }
+        let n = cmp::min(buf.len(), self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Decompresses a gzip stream with a single member from an `io::Read`
+/// source.
+pub(crate) struct GzDecoder<R> {
+    inner: Inflater<BufReader<R>>,
+    crc: Crc,
+    checked: bool,
+}
+
+impl<R: Read> GzDecoder<R> {
+    /// Reads the gzip header, and fails if it is incorrect.
+    pub(crate) fn new(src: R) -> io::Result<GzDecoder<R>> {
+        let mut src = BufReader::new(src);
+        let mut header = [0; 10];
+        src.read_exact(&mut header)?;
+        if header[..3] != [0x1f, 0x8b, 8] {
+            return Err(invalid("invalid gzip header"));
+        }
+        let flags = header[3];
+        if flags & 0x04 != 0 {
+            let mut len = [0; 2];
+            src.read_exact(&mut len)?;
+            let len = u16::from_le_bytes(len) as u64;
+            if io::copy(&mut src.by_ref().take(len), &mut io::sink())? != len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        // The file name and comment end with a zero byte.
+        for &flag in &[0x08, 0x10] {
+            if flags & flag != 0 {
+                let mut field = Vec::new();
+                src.read_until(0, &mut field)?;
+                if field.last() != Some(&0) {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+            }
+        }
+        if flags & 0x02 != 0 {
+            src.read_exact(&mut [0; 2])?;
+        }
+        Ok(GzDecoder { inner: Inflater::new(src), crc: Crc::new(), checked: false })
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.crc.update(&buf[..n]);
+        if n == 0 && !buf.is_empty() && !self.checked {
+            self.inner.src.align();
+            let crc = self.inner.src.bits(32)?;
+            let len = self.inner.src.bits(32)?;
+            if crc != self.crc.sum() || len != self.crc.len {
+                return Err(invalid("gzip checksum mismatch"));
+            }
+            self.checked = true;
+        }
+        Ok(n)
+    }
+}
+
+/// Decompresses a zlib stream from an `io::Read` source.
+pub(crate) struct ZlibDecoder<R> {
+    inner: Inflater<BufReader<R>>,
+    adler: Adler,
+    started: bool,
+    checked: bool,
+}
+
+impl<R: Read> ZlibDecoder<R> {
+    pub(crate) fn new(src: R) -> ZlibDecoder<R> {
+        ZlibDecoder {
+            inner: Inflater::new(BufReader::new(src)),
+            adler: Adler::new(),
+            started: false,
+            checked: false,
+        }
+    }
+}
+
+impl<R: Read> Read for ZlibDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.started {
+            let cmf = self.inner.src.bits(8)?;
+            let flags = self.inner.src.bits(8)?;
+            if cmf & 0x0f != 8 || cmf >> 4 > 7 || (cmf << 8 | flags) % 31 != 0 {
+                return Err(invalid("invalid zlib header"));
+            }
+            if flags & 0x20 != 0 {
+                return Err(invalid("zlib preset dictionaries are not supported"));
+            }
+            self.started = true;
+        }
+        let n = self.inner.read(buf)?;
+        self.adler.update(&buf[..n]);
+        if n == 0 && !buf.is_empty() && !self.checked {
+            self.inner.src.align();
+            let bytes = self.inner.src.bits(32)?.to_le_bytes();
+            if u32::from_be_bytes(bytes) != self.adler.sum() {
+                return Err(invalid("zlib checksum mismatch"));
+            }
+            self.checked = true;
+        }
+        Ok(n)
+    }
+}
+
+/* Encoding */
+
+/// Writes bits least significant first.
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    len: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.acc |= (value as u64 & ((1 << bits) - 1)) << self.len;
+        self.len += bits;
+        while self.len >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn align(&mut self) {
+        if self.len > 0 {
+            self.write(0, 8 - self.len);
+        }
+    }
+}
+
+/// Computes Huffman code lengths for `freqs`, no longer than `limit`. Every
+/// code is complete, so at least two symbols always get a length.
+fn code_lengths(freqs: &[u32], limit: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+    let used = freqs.iter().filter(|&&f| f > 0).count();
+    for f in freqs.iter_mut().take(2) {
+        if used < 2 && *f == 0 {
+            *f = 1;
+        }
+    }
+    loop {
+        let lengths = huffman_lengths(&freqs);
+        if lengths.iter().all(|&length| length <= limit) {
+            return lengths;
+        }
+        // Flattening the frequencies shortens the longest codes.
+        for f in &mut freqs {
+            if *f > 0 {
+                *f = f.div_ceil(2);
+            }
+        }
+    }
+}
+
+fn huffman_lengths(freqs: &[u32]) -> Vec<u8> {
+    let mut heap = BinaryHeap::new();
+    let mut parents = Vec::new();
+    let mut leaves = vec![usize::MAX; freqs.len()];
+    for (symbol, &f) in freqs.iter().enumerate() {
+        if f > 0 {
+            leaves[symbol] = parents.len();
+            heap.push(Reverse((f as u64, parents.len())));
+            parents.push(usize::MAX);
+        }
+    }
+    while heap.len() > 1 {
+        let Reverse((a, i)) = heap.pop().unwrap();
+        let Reverse((b, j)) = heap.pop().unwrap();
+        let node = parents.len();
+        parents.push(usize::MAX);
+        parents[i] = node;
+        parents[j] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+    leaves.iter().map(|&leaf| {
+        if leaf == usize::MAX {
+            return 0;
+        }
+        let mut depth = 0;
+        let mut node = leaf;
+        while parents[node] != usize::MAX {
+            node = parents[node];
+            depth += 1;
+        }
+        cmp::min(depth, 255) as u8
+    }).collect()
+}
+
+/// The canonical codes for `lengths`, bit-reversed for writing.
+fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut counts = [0u32; 16];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut next = [0u32; 16];
+    for bits in 1..16 {
+        next[bits] = (next[bits - 1] + counts[bits - 1]) << 1;
+    }
+    lengths.iter().map(|&length| {
+        if length == 0 {
+            return 0;
+        }
+        let code = next[length as usize];
+        next[length as usize] += 1;
+        code.reverse_bits() >> (32 - length as u32)
+    }).collect()
+}
+
+/// A literal, or a match of `len` bytes `distance` back.
+#[derive(Clone, Copy)]
+struct Token {
+    len: u16,
+    distance: u16,
+}
+
+fn length_code(len: usize) -> usize {
+    LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap()
+}
+
+fn distance_code(distance: usize) -> usize {
+    DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap()
+}
+
+/// Compresses data as a raw DEFLATE stream.
+struct Deflater {
+    level: CompressionLevel,
+    /// Recent input, for matches to refer back to, followed by input not
+    /// yet compressed from `start` on.
+    data: Vec<u8>,
+    start: usize,
+    /// The stream position of `data[0]`.
+    base: usize,
+    /// The latest stream position, plus one, of each 3-byte hash.
+    head: Vec<usize>,
+    /// The previous stream position, plus one, with the same hash as each
+    /// position in the window.
+    prev: Vec<usize>,
+    out: BitWriter,
+}
+
+impl Deflater {
+    fn new(level: CompressionLevel) -> Deflater {
+        Deflater {
+            level,
+            data: Vec::new(),
+            start: 0,
+            base: 0,
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; WINDOW_SIZE],
+            out: BitWriter { out: Vec::new(), acc: 0, len: 0 },
+        }
+    }
+
+    /// Adds input, compressing any whole blocks.
+    fn write(&mut self, buf: &[u8]) {
+        self.data.extend_from_slice(buf);
+        while self.data.len() - self.start >= BLOCK_LEN {
+            self.block(false);
+        }
+    }
+
+    /// Compresses the remaining input as the last block.
+    fn finish(&mut self) {
+        self.block(true);
+        self.out.align();
+    }
+
+    /// Takes the bytes written so far.
+    fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.out.out)
+    }
+
+    fn block(&mut self, last: bool) {
+        let end = cmp::min(self.start + BLOCK_LEN, self.data.len());
+        let tokens = self.find_matches(end);
+        self.write_block(&tokens, end, last);
+        self.start = end;
+        if self.start > 2 * WINDOW_SIZE {
+            let drop = self.start - WINDOW_SIZE;
+            self.data.drain(..drop);
+            self.start -= drop;
+            self.base += drop;
+        }
+    }
+
+    /// The number of candidates tried per position, whether to look one
+    /// byte ahead for a longer match, and the length that stops the search.
+    fn effort(&self) -> (usize, bool, usize) {
+        match self.level {
+            CompressionLevel::None => (0, false, 0),
+            CompressionLevel::Fast => (8, false, 32),
+            CompressionLevel::Default => (64, true, 128),
+            CompressionLevel::Best => (1024, true, MAX_MATCH),
+        }
+    }
+
+    fn insert(&mut self, i: usize) -> usize {
+        let d = &self.data;
+        let hash = ((d[i] as u32) << 16 | (d[i + 1] as u32) << 8 | d[i + 2] as u32)
+            .wrapping_mul(2_654_435_761) >> (32 - HASH_BITS);
+        let pos = self.base + i;
+        let candidate = self.head[hash as usize];
+        self.prev[pos % WINDOW_SIZE] = candidate;
+        self.head[hash as usize] = pos + 1;
+        candidate
+    }
+
+    /// The longest match for `data[i..end]`, following the chain from
+    /// `candidate`, as `(len, distance)`.
+    fn longest_match(&self, i: usize, end: usize, mut candidate: usize) -> (usize, usize) {
+        let (chain, _, nice) = self.effort();
+        let pos = self.base + i;
+        let max = cmp::min(MAX_MATCH, end - i);
+        let mut best = (0, 0);
+        for _ in 0..chain {
+            if candidate == 0 || pos - (candidate - 1) > WINDOW_SIZE {
+                break;
+            }
+            let from = candidate - 1 - self.base;
+            let len = self.data[from..].iter().zip(&self.data[i..i + max])
+                .take_while(|&(a, b)| a == b).count();
+            if len > best.0 {
+                best = (len, i - from);
+                if len >= nice {
+                    break;
+                }
+            }
+            candidate = self.prev[(candidate - 1) % WINDOW_SIZE];
+        }
+        if best.0 < MIN_MATCH { (0, 0) } else { best }
+    }
+
+    fn find_matches(&mut self, end: usize) -> Vec<Token> {
+        let (chain, lazy, _) = self.effort();
+        let mut tokens = Vec::new();
+        let mut i = self.start;
+        if chain == 0 {
+            return tokens;
+        }
+        let mut pending: Option<(usize, usize)> = None;
+        while i < end {
+            let found = if i + MIN_MATCH <= end {
+                let candidate = self.insert(i);
+                self.longest_match(i, end, candidate)
+            } else {
+                (0, 0)
+            };
+            let (len, distance) = match pending.take() {
+                // Keep the previous match unless this one is longer.
+                Some((len, distance)) if found.0 <= len => {
+                    let match_end = i - 1 + len;
+                    i += 1;
+                    while i < match_end {
+                        if i + MIN_MATCH <= end {
+                            self.insert(i);
+                        }
+                        i += 1;
+                    }
+                    tokens.push(Token { len: len as u16, distance: distance as u16 });
+                    continue;
+                },
+                Some(_) => {
+                    tokens.push(Token { len: 0, distance: self.data[i - 1] as u16 });
+                    found
+                },
+                None => found,
+            };
+            if len == 0 {
+                tokens.push(Token { len: 0, distance: self.data[i] as u16 });
+                i += 1;
+            } else if lazy && len < MAX_MATCH && i + 1 < end {
+                pending = Some((len, distance));
+                i += 1;
+            } else {
+                let match_end = i + len;
+                i += 1;
+                while i < match_end {
+                    if i + MIN_MATCH <= end {
+                        self.insert(i);
+                    }
+                    i += 1;
+                }
+                tokens.push(Token { len: len as u16, distance: distance as u16 });
+            }
+        }
+        if let Some((len, distance)) = pending {
+            tokens.push(Token { len: len as u16, distance: distance as u16 });
+        }
+        tokens
+    }
+
+    fn write_block(&mut self, tokens: &[Token], end: usize, last: bool) {
+        let len = end - self.start;
+        let stored_bits = 3 + (8 - (self.out.len as usize + 3) % 8) % 8 + 32 + 8 * len;
+        if self.level == CompressionLevel::None || (tokens.is_empty() && len > 0) {
+            return self.write_stored(end, last);
+        }
+
+        let mut literal_freqs = vec![0u32; 286];
+        let mut distance_freqs = vec![0u32; 30];
+        let mut extra_bits = 0;
+        for token in tokens {
+            if token.len == 0 {
+                literal_freqs[token.distance as usize] += 1;
+            } else {
+                let code = length_code(token.len as usize);
+                literal_freqs[257 + code] += 1;
+                let distance = distance_code(token.distance as usize);
+                distance_freqs[distance] += 1;
+                extra_bits += LENGTH_EXTRA[code] as usize + DISTANCE_EXTRA[distance] as usize;
+            }
+        }
+        literal_freqs[256] = 1;
+        let cost = |literals: &[u8], distances: &[u8]| -> usize {
+            literal_freqs.iter().zip(literals).map(|(&f, &l)| f as usize * l as usize).sum::<usize>()
+                + distance_freqs.iter().zip(distances).map(|(&f, &l)| f as usize * l as usize).sum::<usize>()
+                + extra_bits
+        };
+
+        let (fixed_literals, fixed_distances) = fixed_lengths();
+        let fixed_bits = 3 + cost(&fixed_literals, &fixed_distances);
+        let literals = code_lengths(&literal_freqs, 15);
+        let distances = code_lengths(&distance_freqs, 15);
+        let header = DynamicHeader::new(&literals, &distances);
+        let dynamic_bits = 3 + header.bits() + cost(&literals, &distances);
+
+        if stored_bits <= fixed_bits && stored_bits <= dynamic_bits {
+            self.write_stored(end, last);
+        } else if fixed_bits <= dynamic_bits {
+            self.out.write(last as u32 | 1 << 1, 3);
+            self.write_tokens(tokens, &fixed_literals, &fixed_distances);
+        } else {
+            self.out.write(last as u32 | 2 << 1, 3);
+            header.write(&mut self.out);
+            self.write_tokens(tokens, &literals, &distances);
+        }
+    }
+
+    fn write_stored(&mut self, end: usize, last: bool) {
+        let len = end - self.start;
+        self.out.write(last as u32, 3);
+        self.out.align();
+        self.out.write(len as u32, 16);
+        self.out.write(!len as u32 & 0xffff, 16);
+        self.out.out.extend_from_slice(&self.data[self.start..end]);
+    }
+
+    fn write_tokens(&mut self, tokens: &[Token], literals: &[u8], distances: &[u8]) {
+        let literal_codes = canonical_codes(literals);
+        let distance_codes = canonical_codes(distances);
+        for token in tokens {
+            if token.len == 0 {
+                let symbol = token.distance as usize;
+                self.out.write(literal_codes[symbol], literals[symbol] as u32);
+                continue;
+            }
+            let code = length_code(token.len as usize);
+            self.out.write(literal_codes[257 + code], literals[257 + code] as u32);
+            self.out.write((token.len - LENGTH_BASE[code]) as u32, LENGTH_EXTRA[code] as u32);
+            let code = distance_code(token.distance as usize);
+            self.out.write(distance_codes[code], distances[code] as u32);
+            self.out.write((token.distance - DISTANCE_BASE[code]) as u32, DISTANCE_EXTRA[code] as u32);
+        }
+        self.out.write(literal_codes[256], literals[256] as u32);
+    }
+}
+
+/// The code lengths of a dynamic block, run-length encoded with the code
+/// length code.
+struct DynamicHeader {
+    literal_count: usize,
+    distance_count: usize,
+    /// Code length symbols, with their extra bits and how many there are.
+    symbols: Vec<(u8, u32, u32)>,
+    lengths: Vec<u8>,
+    code_count: usize,
+}
+
+impl DynamicHeader {
+    fn new(literals: &[u8], distances: &[u8]) -> DynamicHeader {
+        let literal_count = cmp::max(257, literals.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1));
+        let distance_count = cmp::max(1, distances.iter().rposition(|&l| l > 0).map_or(0, |i| i + 1));
+        let all: Vec<u8> = literals[..literal_count].iter().chain(&distances[..distance_count]).cloned().collect();
+
+        let mut symbols = Vec::new();
+        let mut i = 0;
+        while i < all.len() {
+            let value = all[i];
+            let run = all[i..].iter().take_while(|&&l| l == value).count();
+            if value == 0 && run >= 11 {
+                let n = cmp::min(run, 138);
+                symbols.push((18, n as u32 - 11, 7));
+                i += n;
+            } else if value == 0 && run >= 3 {
+                let n = cmp::min(run, 10);
+                symbols.push((17, n as u32 - 3, 3));
+                i += n;
+            } else if value != 0 && run >= 4 {
+                symbols.push((value, 0, 0));
+                let n = cmp::min(run - 1, 6);
+                symbols.push((16, n as u32 - 3, 2));
+                i += 1 + n;
+            } else {
+                symbols.push((value, 0, 0));
+                i += 1;
+            }
+        }
+
+        let mut freqs = [0u32; 19];
+        for &(symbol, _, _) in &symbols {
+            freqs[symbol as usize] += 1;
+        }
+        let lengths = code_lengths(&freqs, 7);
+        let code_count = cmp::max(4, CODE_LENGTH_ORDER.iter().rposition(|&i| lengths[i] > 0).unwrap() + 1);
+        DynamicHeader { literal_count, distance_count, symbols, lengths, code_count }
+    }
+
+    fn bits(&self) -> usize {
+        14 + 3 * self.code_count + self.symbols.iter()
+            .map(|&(symbol, _, extra)| self.lengths[symbol as usize] as usize + extra as usize)
+            .sum::<usize>()
+    }
+
+    fn write(&self, out: &mut BitWriter) {
+        out.write(self.literal_count as u32 - 257, 5);
+        out.write(self.distance_count as u32 - 1, 5);
+        out.write(self.code_count as u32 - 4, 4);
+        for &i in &CODE_LENGTH_ORDER[..self.code_count] {
+            out.write(self.lengths[i] as u32, 3);
+        }
+        let codes = canonical_codes(&self.lengths);
+        for &(symbol, value, extra) in &self.symbols {
+            out.write(codes[symbol as usize], self.lengths[symbol as usize] as u32);
+            out.write(value, extra);
+        }
+    }
+}
+
+/// Compresses data written to it in the gzip format. The stream is
+/// finished by `finish()`, or when the encoder is dropped.
+pub struct GzEncoder<W: Write> {
+    inner: Option<W>,
+    deflater: Deflater,
+    crc: Crc,
+}
+
+impl<W: Write> GzEncoder<W> {
+    pub(crate) fn new(dst: W, level: CompressionLevel) -> GzEncoder<W> {
+        let mut deflater = Deflater::new(level);
+        // Extra flags of 2 and 4 mark the best and fastest compression.
+        let flags = match level {
+            CompressionLevel::Best => 2,
+            CompressionLevel::Fast | CompressionLevel::None => 4,
+            CompressionLevel::Default => 0,
+        };
+        deflater.out.out.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, flags, 255]);
+        GzEncoder { inner: Some(dst), deflater, crc: Crc::new() }
+    }
+
+    /// Writes the rest of the stream, returning the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        self.deflater.finish();
+        let mut trailer = self.deflater.take_output();
+        trailer.extend_from_slice(&self.crc.sum().to_le_bytes());
+        trailer.extend_from_slice(&self.crc.len.to_le_bytes());
+        let dst = self.inner.as_mut().unwrap();
+        dst.write_all(&trailer)?;
+        dst.flush()
+    }
+}
+
+impl<W: Write> Write for GzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.crc.update(buf);
+        self.deflater.write(buf);
+        let out = self.deflater.take_output();
+        self.inner.as_mut().unwrap().write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for GzEncoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+/// Compresses data written to it in the zlib format. The stream is
+/// finished by `finish()`, or when the encoder is dropped.
+pub struct ZlibEncoder<W: Write> {
+    inner: Option<W>,
+    deflater: Deflater,
+    adler: Adler,
+}
+
+impl<W: Write> ZlibEncoder<W> {
+    pub(crate) fn new(dst: W, level: CompressionLevel) -> ZlibEncoder<W> {
+        let mut deflater = Deflater::new(level);
+        let level_flag = match level {
+            CompressionLevel::None => 0,
+            CompressionLevel::Fast => 1,
+            CompressionLevel::Default => 2,
+            CompressionLevel::Best => 3,
+        };
+        let flags = level_flag << 6;
+        let check = (31 - (0x7800 | flags as u16) % 31) % 31;
+        deflater.out.out.extend_from_slice(&[0x78, flags | check as u8]);
+        ZlibEncoder { inner: Some(dst), deflater, adler: Adler::new() }
+    }
+
+    /// Writes the rest of the stream, returning the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn try_finish(&mut self) -> io::Result<()> {
+        self.deflater.finish();
+        let mut trailer = self.deflater.take_output();
+        trailer.extend_from_slice(&self.adler.sum().to_be_bytes());
+        let dst = self.inner.as_mut().unwrap();
+        dst.write_all(&trailer)?;
+        dst.flush()
+    }
+}
+
+impl<W: Write> Write for ZlibEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.adler.update(buf);
+        self.deflater.write(buf);
+        let out = self.deflater.take_output();
+        self.inner.as_mut().unwrap().write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for ZlibEncoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+/// Encoders that compress an `io::Read` source as it is read, like
+/// `flate2::read`.
+pub(crate) mod read {
+    use std::io::{self, Read, Write};
+
+    use options::CompressionLevel;
+
+    /// Reads `src` a block at a time through `encoder`, whose output is
+    /// collected in a shared buffer.
+    pub struct Pull<R, E> {
+        src: R,
+        encoder: Option<E>,
+        buf: Vec<u8>,
+        pos: usize,
+    }
+
+    impl<R: Read, E: Write + Finish> Read for Pull<R, E> {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            while self.pos == self.buf.len() && !out.is_empty() {
+                let encoder = match self.encoder {
+                    Some(ref mut encoder) => encoder,
+                    None => return Ok(0),
+                };
+                self.buf.clear();
+                self.pos = 0;
+                let mut chunk = [0; 8192];
+                let n = self.src.read(&mut chunk)?;
+                if n == 0 {
+                    self.buf = self.encoder.take().unwrap().finish_into()?;
+                } else {
+                    encoder.write_all(&chunk[..n])?;
+                    self.buf = encoder.take_buffered();
+                }
+            }
+            let n = ::std::cmp::min(out.len(), self.buf.len() - self.pos);
+            out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    /// Gives access to the output of an encoder that writes to a `Vec`.
+    pub trait Finish {
+        fn take_buffered(&mut self) -> Vec<u8>;
+        fn finish_into(self) -> io::Result<Vec<u8>>;
+    }
+
+    macro_rules! finish_impl {
+        ($ty:ident) => {
+            impl Finish for super::$ty<Vec<u8>> {
+                fn take_buffered(&mut self) -> Vec<u8> {
+                    ::std::mem::take(self.inner.as_mut().unwrap())
+                }
+
+                fn finish_into(self) -> io::Result<Vec<u8>> {
+                    self.finish()
+                }
+            }
+        };
+    }
+    finish_impl!(GzEncoder);
+    finish_impl!(ZlibEncoder);
+
+    /// Compresses an `io::Read` source in the gzip format as it is read.
+    pub type GzEncoder<R> = Pull<R, super::GzEncoder<Vec<u8>>>;
+    /// Compresses an `io::Read` source in the zlib format as it is read.
+    pub type ZlibEncoder<R> = Pull<R, super::ZlibEncoder<Vec<u8>>>;
+
+    impl<R: Read> Pull<R, super::GzEncoder<Vec<u8>>> {
+        pub(crate) fn new(src: R, level: CompressionLevel) -> Self {
+            Pull { src, encoder: Some(super::GzEncoder::new(Vec::new(), level)), buf: Vec::new(), pos: 0 }
+        }
+    }
+
+    impl<R: Read> Pull<R, super::ZlibEncoder<Vec<u8>>> {
+        pub(crate) fn new(src: R, level: CompressionLevel) -> Self {
+            Pull { src, encoder: Some(super::ZlibEncoder::new(Vec::new(), level)), buf: Vec::new(), pos: 0 }
+        }
+    }
+}
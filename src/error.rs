@@ -1,12 +1,17 @@
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+#[cfg(feature = "std")]
 use std::io::ErrorKind::InvalidInput;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
 use std::result::Result as StdResult;
 
 #[cfg(feature = "serde")]
 use serde;
 
+use value;
+
 /// A convenient alias type for results when reading/writing the Named Binary
 /// Tag format.
 pub type Result<T> = StdResult<T, Error>;
@@ -16,7 +21,11 @@ pub type Result<T> = StdResult<T, Error>;
 ///
 /// `Error`s can be seamlessly converted to more general `io::Error` objects
 /// using `std::convert::From::from()`.
+///
+/// New kinds of error may be added, so matches on this type need a wildcard
+/// arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Wraps errors emitted by methods during I/O operations.
     IoError(io::Error),
@@ -43,27 +52,138 @@ pub enum Error {
     /// An error encountered when parsing NBT binary representations, where
     /// deserialization encounters a field name it is not expecting.
     UnexpectedField(String),
+    /// An error encountered when decoding a Compound into a Rust type, where a
+    /// required field is not present.
+    MissingField(String),
+    /// An error emitted by `NbtWriter` when tags are written in an order that
+    /// would not produce a valid document.
+    InvalidNesting(&'static str),
+    /// An error for when a VarInt in network-encoded NBT is longer than its
+    /// type allows.
+    InvalidVarInt,
     /// An error encountered when deserializing a boolean from an invalid byte.
     NonBooleanByte(i8),
+    /// An error encountered when decoding a numeric tag into a Rust type too
+    /// small to hold its value. Includes the tag's type ID.
+    OutOfRange(i8),
     /// An error encountered when serializing a Rust type with no meaningful NBT
     /// representation.
     UnrepresentableType(&'static str),
     /// An error encountered when trying to (de)serialize a map key with a
     /// non-string type.
     NonStringMapKey,
+    /// An error encountered when parsing stringified NBT (SNBT). Includes the
+    /// byte offset at which parsing failed and a description of the problem.
+    InvalidSnbt(usize, String),
+    /// An error encountered when parsing a `Selector`. Includes the byte
+    /// offset at which parsing failed and a description of the problem.
+    InvalidSelector(usize, String),
+    /// An error for when a change in an `NbtPatch` does not apply, because
+    /// the value it would modify is not in the state the patch expects.
+    /// Includes the path of the change.
+    PatchConflict(String),
+    /// An error for when Lists and Compounds are nested more deeply than a
+    /// `RawReader` allows. Includes the maximum depth.
+    DepthLimitExceeded(usize),
+    /// An error for when a List or array declares a negative length.
+    /// Includes the length.
+    InvalidLength(i32),
+    /// An error for when a string, List or array is longer than a
+    /// `RawReader` allows, or a string is too long to be written. Includes
+    /// the maximum length.
+    LengthLimitExceeded(usize),
+    /// An error for when a region file chunk uses an unknown compression
+    /// scheme. Includes the scheme's ID.
+    UnknownChunkCompression(u8),
+    /// An error for when a region file's location table or a chunk header is
+    /// inconsistent. Includes the chunk's local coordinates and the problem.
+    CorruptChunk(i32, i32, &'static str),
+    /// An error for when a chunk is too large to be stored in a region file,
+    /// and no directory for external `.mcc` chunk files is known. Includes the
+    /// chunk's local coordinates.
+    ChunkTooLarge(i32, i32),
+    /// An error for when a region file chunk is stored in an external `.mcc`
+    /// file, but the region's location is not known. Includes the chunk's
+    /// local coordinates.
+    ExternalChunk(i32, i32),
+    /// An error for when a packed LongArray, such as a chunk section's block
+    /// states, cannot be unpacked or packed. Includes the problem.
+    InvalidPackedArray(&'static str),
+    /// An error for when a string is not a block state in the syntax of
+    /// commands, such as `minecraft:oak_stairs[facing=east]`. Includes the
+    /// string.
+    InvalidBlockState(String),
+    /// An error for when a schematic file, such as a Sponge `.schem`, is
+    /// inconsistent or uses an unsupported version. Includes the problem.
+    InvalidSchematic(&'static str),
+    /// An error encountered when decoding a `Value` or `Blob`, with where it
+    /// happened: the offset in bytes from where the `RawReader` started (see
+    /// `RawReader::offset()`), and the path of the tag being read, as in
+    /// `Level.Sections[2].Palette`. The path is empty for the root itself.
+    ///
+    /// A located error only compares equal to one with the same location;
+    /// use `Error::without_location()` to compare the errors themselves.
+    Located(u64, String, Box<Error>),
+}
+
+impl Error {
+    /// The byte offset at which decoding failed, if known. See
+    /// `Error::Located`.
+    pub fn offset(&self) -> Option<u64> {
+        match *self {
+            Error::Located(offset, _, _) => Some(offset),
+            _ => None,
+        }
+    }
+
+    /// The path of the tag being decoded when decoding failed, if known.
+    /// See `Error::Located`.
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Error::Located(_, ref path, _) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Returns this error without its location, if it has one.
+    pub fn without_location(self) -> Error {
+        match self {
+            Error::Located(_, _, e) => *e,
+            other => other,
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &Error::IoError(ref e)     => e.fmt(f),
+            Error::IoError(e)          => e.fmt(f),
             #[cfg(feature = "serde")]
-            &Error::Serde(ref msg)     => write!(f, "{}", msg),
+            Error::Serde(msg)          => write!(f, "{}", msg),
             &Error::InvalidTypeId(t)   => write!(f, "invalid NBT tag byte: '{}'", t),
-            &Error::TagMismatch(a, b)  => write!(f, "encountered NBT tag '{}' but expected '{}'", a, b),
+            &Error::TagMismatch(a, b)  => write!(f, "encountered NBT tag '{}' ({}) but expected '{}' ({})",
+                                                 a, value::tag_name_of(a), b, value::tag_name_of(b)),
             &Error::NonBooleanByte(b)  => write!(f, "encountered a byte value '{}' inside a boolean", b),
-            &Error::UnexpectedField(ref name) => write!(f, "encountered an unexpected field '{}'", name),
-            &Error::UnrepresentableType(ref name) => write!(f, "encountered type '{}', which has no meaningful NBT representation", name),
+            &Error::OutOfRange(t)      => write!(f, "the value of NBT tag '{}' is out of range for its type", t),
+            Error::UnexpectedField(name) => write!(f, "encountered an unexpected field '{}'", name),
+            Error::MissingField(name) => write!(f, "missing required field '{}'", name),
+            &Error::InvalidNesting(msg) => write!(f, "invalid NBT structure: {}", msg),
+            &Error::InvalidSnbt(pos, ref msg) => write!(f, "invalid SNBT at offset {}: {}", pos, msg),
+            &Error::InvalidSelector(pos, ref msg) => write!(f, "invalid selector at offset {}: {}", pos, msg),
+            Error::PatchConflict(path) => write!(f, "patch conflicts with the value at '{}'", path),
+            &Error::DepthLimitExceeded(max) => write!(f, "NBT data is nested more than {} levels deep", max),
+            &Error::InvalidLength(len) => write!(f, "encountered a negative length '{}'", len),
+            &Error::LengthLimitExceeded(max) => write!(f, "a string, List or array is longer than the limit of {}", max),
+            &Error::UnknownChunkCompression(id) => write!(f, "unknown chunk compression scheme '{}'", id),
+            &Error::CorruptChunk(x, z, msg) => write!(f, "corrupt region chunk ({}, {}): {}", x, z, msg),
+            &Error::ChunkTooLarge(x, z) => write!(f, "region chunk ({}, {}) is too large to store", x, z),
+            &Error::ExternalChunk(x, z) => write!(f, "region chunk ({}, {}) is stored in an external .mcc file in an unknown location", x, z),
+            &Error::InvalidPackedArray(msg) => write!(f, "invalid packed array: {}", msg),
+            Error::InvalidBlockState(state) => write!(f, "invalid block state '{}'", state),
+            &Error::InvalidSchematic(msg) => write!(f, "invalid schematic: {}", msg),
+            Error::UnrepresentableType(name) => write!(f, "encountered type '{}', which has no meaningful NBT representation", name),
+            Error::Located(offset, path, e) if path.is_empty() => write!(f, "{} at offset {}", e, offset),
+            Error::Located(offset, path, e) => write!(f, "{} at offset {} in '{}'", e, offset, path),
             // Static messages should suffice for the remaining errors.
             other => write!(f, "{}", other.description()),
         }
@@ -82,20 +202,48 @@ impl StdError for Error {
             Error::InvalidUtf8        => "a string is not valid UTF-8",
             Error::IncompleteNbtValue => "data does not represent a complete NbtValue",
             Error::NonStringMapKey    => "encountered a non-string map key",
+            Error::InvalidVarInt      => "a VarInt is longer than its type allows",
+            Error::InvalidNesting(_)  => "tags were written in an invalid order",
+            Error::InvalidSnbt(_, _)  => "invalid stringified NBT",
+            Error::InvalidSelector(_, _) => "invalid selector",
+            Error::PatchConflict(_)   => "patch conflicts with the value it is applied to",
+            Error::DepthLimitExceeded(_) => "NBT data is nested too deeply",
+            Error::InvalidLength(_)   => "encountered a negative length",
+            Error::LengthLimitExceeded(_) => "a string, List or array is too long",
+            Error::UnknownChunkCompression(_) => "unknown chunk compression scheme",
+            Error::CorruptChunk(_, _, _) => "corrupt region file chunk",
+            Error::ChunkTooLarge(_, _) => "region file chunk is too large to store",
+            Error::ExternalChunk(_, _) => "region file chunk is stored in an external file in an unknown location",
+            Error::InvalidPackedArray(_) => "invalid packed array",
+            Error::InvalidBlockState(_) => "invalid block state",
+            Error::InvalidSchematic(_) => "invalid schematic",
             Error::TagMismatch(_, _)  => "encountered one NBT tag but expected another",
             Error::UnexpectedField(_) => "encountered an unexpected field",
+            Error::MissingField(_)    => "missing a required field",
             Error::NonBooleanByte(_)  => "encountered a non-boolean byte value inside a boolean",
+            Error::OutOfRange(_)      => "a numeric value is out of range for its type",
             Error::UnrepresentableType(_) => "encountered a type with no meaningful NBT representation",
+            #[allow(deprecated)]
+            Error::Located(_, _, ref e) => e.description(),
         }
     }
 
-    // Deprecated in 1.33 in favour of source(), added in 1.30. This is recent
-    // enough that we should attempt to support older compilers, especially
-    // since we're on the 2015 edition at this time.
+    // Deprecated in 1.33 in favour of source(), added in 1.30. Both are
+    // implemented, so that error chains walked either way include the error
+    // a located one wraps.
     #[allow(deprecated)]
     fn cause(&self) -> Option<&dyn StdError> {
         match *self {
             Error::IoError(ref e) => e.cause(),
+            Error::Located(_, _, ref e) => Some(&**e),
+            _ => None
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::IoError(ref e) => e.source(),
+            Error::Located(_, _, ref e) => Some(&**e),
             _ => None
         }
     }
@@ -105,10 +253,14 @@ impl StdError for Error {
 impl PartialEq<Error> for Error {
     fn eq(&self, other: &Error) -> bool {
         use Error::{IoError, InvalidTypeId, HeterogeneousList, NoRootCompound,
-                    InvalidUtf8, IncompleteNbtValue, TagMismatch, UnexpectedField, NonBooleanByte,
-                    UnrepresentableType};
+                    InvalidUtf8, IncompleteNbtValue, InvalidVarInt, InvalidNesting, TagMismatch, UnexpectedField, MissingField,
+                    NonBooleanByte, OutOfRange, UnrepresentableType, NonStringMapKey, InvalidSnbt,
+                    InvalidSelector, PatchConflict, DepthLimitExceeded, InvalidLength,
+                    LengthLimitExceeded, UnknownChunkCompression, CorruptChunk, ChunkTooLarge, ExternalChunk,
+                    InvalidPackedArray};
 
         match (self, other) {
+            (Error::Located(a, b, c), Error::Located(d, e, f)) => a == d && b == e && c == f,
             (&IoError(_), &IoError(_))                 => true,
             #[cfg(feature = "serde")]
             (&Error::Serde(_), &Error::Serde(_))       => true,
@@ -117,10 +269,28 @@ impl PartialEq<Error> for Error {
             (&NoRootCompound, &NoRootCompound)         => true,
             (&InvalidUtf8, &InvalidUtf8)               => true,
             (&IncompleteNbtValue, &IncompleteNbtValue) => true,
+            (&InvalidVarInt, &InvalidVarInt)           => true,
+            (&InvalidNesting(a), &InvalidNesting(b))   => a == b,
             (&TagMismatch(a, b), &TagMismatch(c, d))   => a == c && b == d,
-            (&UnexpectedField(ref a), &UnexpectedField(ref b)) => a == b,
+            (UnexpectedField(a), UnexpectedField(b)) => a == b,
+            (MissingField(a), MissingField(b)) => a == b,
+            (&InvalidSnbt(a, ref b), &InvalidSnbt(c, ref d)) => a == c && b == d,
+            (&InvalidSelector(a, ref b), &InvalidSelector(c, ref d)) => a == c && b == d,
+            (PatchConflict(a), PatchConflict(b))       => a == b,
+            (&DepthLimitExceeded(a), &DepthLimitExceeded(b)) => a == b,
+            (&InvalidLength(a), &InvalidLength(b))     => a == b,
+            (&LengthLimitExceeded(a), &LengthLimitExceeded(b)) => a == b,
+            (&UnknownChunkCompression(a), &UnknownChunkCompression(b)) => a == b,
+            (&CorruptChunk(a, b, c), &CorruptChunk(d, e, f)) => a == d && b == e && c == f,
+            (&ChunkTooLarge(a, b), &ChunkTooLarge(c, d)) => a == c && b == d,
+            (&ExternalChunk(a, b), &ExternalChunk(c, d)) => a == c && b == d,
+            (&InvalidPackedArray(a), &InvalidPackedArray(b)) => a == b,
+            (Error::InvalidBlockState(a), Error::InvalidBlockState(b)) => a == b,
+            (&Error::InvalidSchematic(a), &Error::InvalidSchematic(b)) => a == b,
             (&NonBooleanByte(a), &NonBooleanByte(b))   => a == b,
-            (&UnrepresentableType(ref a), &UnrepresentableType(ref b)) => a == b,
+            (&OutOfRange(a), &OutOfRange(b))           => a == b,
+            (UnrepresentableType(a), UnrepresentableType(b)) => a == b,
+            (&NonStringMapKey, &NonStringMapKey)       => true,
             _ => false
         }
     }
@@ -137,21 +307,22 @@ impl From<io::Error> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<cesu8::Cesu8DecodingError> for Error {
     fn from(_: cesu8::Cesu8DecodingError) -> Error {
         Error::InvalidUtf8
     }
 }
 
+#[cfg(feature = "std")]
 impl From<Error> for io::Error {
     fn from(e: Error) -> io::Error {
         match e {
             Error::IoError(e) => e,
+            located @ Error::Located(..) => io::Error::new(InvalidInput, located.to_string()),
             Error::InvalidTypeId(id) =>
                 io::Error::new(InvalidInput, &format!("invalid NBT tag byte: {}", id)[..]),
-            Error::TagMismatch(a, b) =>
-                io::Error::new(InvalidInput, &format!("encountered NBT tag {} \
-                                                       but expected {}", a, b)[..]),
+            mismatch @ Error::TagMismatch(..) => io::Error::new(InvalidInput, mismatch.to_string()),
             Error::UnexpectedField(f) =>
                 io::Error::new(InvalidInput, &format!("encountered unexpected field \
                                                        with name {}", f)[..]),
@@ -0,0 +1,35 @@
+//! The backend for gzip and zlib compression: flate2 with the `compression`
+//! feature, or the `deflate` module with the `rust_backend` feature, which
+//! needs no C toolchain and takes precedence if both are enabled.
+
+use options::CompressionLevel;
+
+#[cfg(feature = "rust_backend")]
+pub(crate) use deflate::{read, GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder};
+
+#[cfg(not(feature = "rust_backend"))]
+pub(crate) use flate2::read::{GzDecoder, ZlibDecoder};
+#[cfg(not(feature = "rust_backend"))]
+pub(crate) use flate2::write::{GzEncoder, ZlibEncoder};
+
+#[cfg(not(feature = "rust_backend"))]
+pub(crate) mod read {
+    pub(crate) use flate2::read::{GzEncoder, ZlibEncoder};
+}
+
+/// The backend's compression level for `level`.
+#[cfg(feature = "rust_backend")]
+pub(crate) fn level(level: CompressionLevel) -> CompressionLevel {
+    level
+}
+
+/// The backend's compression level for `level`.
+#[cfg(not(feature = "rust_backend"))]
+pub(crate) fn level(level: CompressionLevel) -> ::flate2::Compression {
+    match level {
+        CompressionLevel::None => ::flate2::Compression::None,
+        CompressionLevel::Fast => ::flate2::Compression::Fast,
+        CompressionLevel::Default => ::flate2::Compression::Default,
+        CompressionLevel::Best => ::flate2::Compression::Best,
+    }
+}
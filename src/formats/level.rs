@@ -0,0 +1,192 @@
+//! The `level.dat` of a world, which holds its settings and state.
+//!
+//! The file is a gzipped, big endian Compound with a single entry, `Data`.
+//! Only the commonly used keys are modeled, and the ones that some versions
+//! of the game do not write are `Option`s; everything else is ignored when
+//! reading. Since writing the model on its own would therefore lose those
+//! keys, edits are best merged back into the file they were read from:
+//!
+//! ```rust,no_run
+//! use nbt::formats::level::Level;
+//! use nbt::{Blob, Endianness, Value};
+//! use std::fs::File;
+//!
+//! let mut file = Value::from(Blob::from_gzip_reader(&mut File::open("level.dat")?, Endianness::BigEndian)?);
+//! let mut level: Level = nbt::from_value(file.clone())?;
+//! level.data.level_name = "Renamed".to_string();
+//! level.data.game_rules.insert("keepInventory".to_string(), "true".to_string());
+//! level.write_into(&mut file)?;
+//! Blob::from_value(file)?.to_gzip_writer(&mut File::create("level.dat")?, Endianness::BigEndian)?;
+//! # Ok::<(), nbt::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use de;
+use formats::player::Player;
+use error::Result;
+use map::Map;
+use merge::MergeStrategy;
+use raw::Endianness;
+use ser;
+use value::Value;
+
+/// The contents of a `level.dat` file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    #[serde(rename = "Data")]
+    pub data: LevelData,
+}
+
+/// The settings and state of a world.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelData {
+    /// The data version of the game that last saved the world.
+    #[serde(rename = "DataVersion", skip_serializing_if = "Option::is_none")]
+    pub data_version: Option<i32>,
+    /// The version of the game that last saved the world.
+    #[serde(rename = "Version", skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<GameVersion>,
+    /// The version of the storage format, `19133` for Anvil.
+    pub version: i32,
+    #[serde(rename = "LevelName")]
+    pub level_name: String,
+    /// The default game mode: 0 for survival, 1 for creative, 2 for
+    /// adventure and 3 for spectator.
+    #[serde(rename = "GameType")]
+    pub game_type: i32,
+    /// The difficulty, from 0 for peaceful to 3 for hard.
+    #[serde(rename = "Difficulty")]
+    pub difficulty: i8,
+    #[serde(rename = "DifficultyLocked")]
+    pub difficulty_locked: bool,
+    pub hardcore: bool,
+    #[serde(rename = "allowCommands")]
+    pub allow_commands: bool,
+    pub initialized: bool,
+    /// When the world was last played, in milliseconds since the Unix
+    /// epoch.
+    #[serde(rename = "LastPlayed")]
+    pub last_played: i64,
+    /// The number of ticks the world has run for.
+    #[serde(rename = "Time")]
+    pub time: i64,
+    /// The time of day, in ticks, which is not reset each day.
+    #[serde(rename = "DayTime")]
+    pub day_time: i64,
+    #[serde(rename = "SpawnX")]
+    pub spawn_x: i32,
+    #[serde(rename = "SpawnY")]
+    pub spawn_y: i32,
+    #[serde(rename = "SpawnZ")]
+    pub spawn_z: i32,
+    #[serde(rename = "SpawnAngle", skip_serializing_if = "Option::is_none")]
+    pub spawn_angle: Option<f32>,
+    pub raining: bool,
+    /// The number of ticks until rain starts or stops.
+    #[serde(rename = "rainTime")]
+    pub rain_time: i32,
+    pub thundering: bool,
+    /// The number of ticks until thunder starts or stops.
+    #[serde(rename = "thunderTime")]
+    pub thunder_time: i32,
+    /// The number of ticks of clear weather left, from `/weather clear`.
+    #[serde(rename = "clearWeatherTime")]
+    pub clear_weather_time: i32,
+    /// The game rules by name, with their values as strings.
+    #[serde(rename = "GameRules")]
+    pub game_rules: BTreeMap<String, String>,
+    /// The seed of worlds from before 1.16, which have no
+    /// `world_gen_settings`.
+    #[serde(rename = "RandomSeed", skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<i64>,
+    /// The world type of worlds from before 1.16, such as `default` or
+    /// `flat`.
+    #[serde(rename = "generatorName", skip_serializing_if = "Option::is_none")]
+    pub generator_name: Option<String>,
+    #[serde(rename = "WorldGenSettings", skip_serializing_if = "Option::is_none")]
+    pub world_gen_settings: Option<WorldGenSettings>,
+    #[serde(rename = "DataPacks", skip_serializing_if = "Option::is_none")]
+    pub data_packs: Option<DataPacks>,
+    /// The player of a singleplayer world.
+    #[serde(rename = "Player", skip_serializing_if = "Option::is_none")]
+    pub player: Option<Player>,
+}
+
+/// The version of the game that last saved a world.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameVersion {
+    /// The data version.
+    #[serde(rename = "Id")]
+    pub id: i32,
+    /// The name of the version, such as `1.20.4`.
+    #[serde(rename = "Name")]
+    pub name: String,
+    /// The series of the version, `main` unless it is an experimental
+    /// snapshot.
+    #[serde(rename = "Series", default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    #[serde(rename = "Snapshot", default)]
+    pub snapshot: bool,
+}
+
+/// How the terrain of a world is generated, since 1.16.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldGenSettings {
+    pub seed: i64,
+    /// Whether structures are generated.
+    #[serde(default)]
+    pub generate_features: bool,
+    #[serde(default)]
+    pub bonus_chest: bool,
+    /// The generator of each dimension, by ID, such as
+    /// `minecraft:overworld`.
+    #[serde(default)]
+    pub dimensions: Map,
+}
+
+/// The data packs of a world, by name, such as `vanilla` or
+/// `file/example.zip`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataPacks {
+    #[serde(rename = "Enabled", default)]
+    pub enabled: Vec<String>,
+    #[serde(rename = "Disabled", default)]
+    pub disabled: Vec<String>,
+}
+
+impl Level {
+    /// Reads an uncompressed `level.dat`.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<Level> {
+        de::from_reader(src, Endianness::BigEndian)
+    }
+
+    /// Reads a gzipped `level.dat`, the form the game saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_gzip_reader<R: io::Read>(src: R) -> Result<Level> {
+        de::from_gzip_reader(src, Endianness::BigEndian)
+    }
+
+    /// Writes the model on its own, uncompressed. Keys the model does not
+    /// know are not written; see `write_into()`.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// Writes the model on its own, gzipped. Keys the model does not know
+    /// are not written; see `write_into()`.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn to_gzip_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_gzip_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// Merges the model into the full contents of a `level.dat`, replacing
+    /// the values it models and keeping everything else. Entries that are
+    /// `None` in the model are left as they are.
+    pub fn write_into(&self, file: &mut Value) -> Result<()> {
+        file.merge(&ser::to_value(self)?, MergeStrategy::Deep);
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+//! Litematica schematics (`.litematic`).
+//!
+//! A Litematica file holds one or more named regions, each a box of blocks
+//! with a palette of block states. The palette indices are packed into a
+//! `TAG_Long_Array` in the layout chunks used before 1.16, where entries
+//! span Longs, with at least two bits per entry. Block entities, entities
+//! and scheduled ticks are kept as the game saves them, with positions
+//! relative to the region. Files are gzipped and big endian.
+//!
+//! Regions convert to and from Sponge schematics, which store their blocks
+//! in the same order:
+//!
+//! ```rust
+//! use nbt::formats::litematic::{Litematic, Region};
+//! use nbt::formats::structure::BlockState;
+//!
+//! let mut region = Region::new([0, 64, 0], [3, 1, 1]);
+//! region.set_block([2, 0, 0], BlockState::new("minecraft:glass"));
+//! let mut litematic = Litematic::new("Window");
+//! litematic.regions.insert("Main".to_string(), region);
+//! litematic.update_metadata();
+//! assert_eq!(litematic.metadata.total_blocks, 1);
+//!
+//! let mut bytes = Vec::new();
+//! litematic.to_gzip_writer(&mut bytes).unwrap();
+//! let read = Litematic::from_gzip_reader(&bytes[..]).unwrap();
+//! let schematic = read.regions["Main"].to_schematic().unwrap();
+//! assert_eq!(schematic.block_at([2, 0, 0]), Some(&BlockState::new("minecraft:glass")));
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
+
+use de;
+use error::{Error, Result};
+use formats::schem::{Schematic, SchematicBlockEntity, SchematicEntity};
+use formats::structure::BlockState;
+use map::Map;
+use packed;
+use raw::Endianness;
+use ser;
+use value::Value;
+
+/// The fewest bits per entry that Litematica packs block states with.
+const MIN_BITS: u32 = 2;
+
+/// The contents of a Litematica file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Litematic {
+    /// The version of the format.
+    pub version: i32,
+    /// The minor version of the format, written by newer releases.
+    pub sub_version: Option<i32>,
+    /// The data version of the game the blocks are from.
+    pub data_version: i32,
+    pub metadata: Metadata,
+    /// The regions, by name.
+    pub regions: BTreeMap<String, Region>,
+}
+
+/// The description of a Litematica file, shown when browsing schematics.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Metadata {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub region_count: i32,
+    /// The number of blocks that are not air.
+    #[serde(default)]
+    pub total_blocks: i32,
+    #[serde(default)]
+    pub total_volume: i32,
+    /// When the file was created, in milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub time_created: i64,
+    /// When the file was last modified, in milliseconds since the Unix
+    /// epoch.
+    #[serde(default)]
+    pub time_modified: i64,
+    /// The size of the box enclosing every region.
+    #[serde(default, with = "coords")]
+    pub enclosing_size: [i32; 3],
+    /// A thumbnail, as ARGB pixels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "::arrays::int_array")]
+    pub preview_image_data: Vec<i32>,
+}
+
+/// A box of blocks in a Litematica file.
+///
+/// The size may be negative along any axis, when the region extends from
+/// its position towards negative coordinates. Blocks are indexed from the
+/// corner with the smallest coordinates either way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    /// The position of the region, relative to the schematic's origin.
+    pub position: [i32; 3],
+    pub size: [i32; 3],
+    /// The block states, which `blocks` index into.
+    pub palette: Vec<BlockState>,
+    /// The palette index of every block, ordered by y, then z, then x, as
+    /// given by `index()`.
+    pub blocks: Vec<u16>,
+    /// The block entities, with positions in `x`, `y` and `z`.
+    pub tile_entities: Vec<Value>,
+    pub entities: Vec<Value>,
+    pub pending_block_ticks: Vec<Value>,
+    pub pending_fluid_ticks: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Raw {
+    #[serde(rename = "MinecraftDataVersion", default)]
+    data_version: i32,
+    #[serde(rename = "Version")]
+    version: i32,
+    #[serde(rename = "SubVersion", default, skip_serializing_if = "Option::is_none")]
+    sub_version: Option<i32>,
+    #[serde(rename = "Metadata", default)]
+    metadata: Metadata,
+    #[serde(rename = "Regions")]
+    regions: BTreeMap<String, RawRegion>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RawRegion {
+    #[serde(with = "coords")]
+    position: [i32; 3],
+    #[serde(with = "coords")]
+    size: [i32; 3],
+    block_state_palette: Vec<BlockState>,
+    #[serde(with = "::arrays::long_array")]
+    block_states: Vec<i64>,
+    #[serde(default)]
+    tile_entities: Vec<Value>,
+    #[serde(default)]
+    entities: Vec<Value>,
+    #[serde(default)]
+    pending_block_ticks: Vec<Value>,
+    #[serde(default)]
+    pending_fluid_ticks: Vec<Value>,
+}
+
+/// Coordinates stored as a Compound of `x`, `y` and `z`.
+mod coords {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Coords {
+        x: i32,
+        y: i32,
+        z: i32,
+    }
+
+    pub fn serialize<S: Serializer>(pos: &[i32; 3], serializer: S) -> Result<S::Ok, S::Error> {
+        Coords { x: pos[0], y: pos[1], z: pos[2] }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[i32; 3], D::Error> {
+        let Coords { x, y, z } = Coords::deserialize(deserializer)?;
+        Ok([x, y, z])
+    }
+}
+
+impl Litematic {
+    /// A file with no regions, named `name`.
+    pub fn new<S: Into<String>>(name: S) -> Litematic {
+        Litematic {
+            version: 6,
+            sub_version: None,
+            data_version: 0,
+            metadata: Metadata { name: name.into(), ..Metadata::default() },
+            regions: BTreeMap::new(),
+        }
+    }
+
+    /// Reads an uncompressed Litematica file.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<Litematic> {
+        Litematic::from_raw(de::from_reader(src, Endianness::BigEndian)?)
+    }
+
+    /// Reads a gzipped Litematica file, the form the mod saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_gzip_reader<R: io::Read>(src: R) -> Result<Litematic> {
+        Litematic::from_raw(de::from_gzip_reader(src, Endianness::BigEndian)?)
+    }
+
+    /// Writes the file, uncompressed.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_writer(dst, &self.to_raw()?, None, Endianness::BigEndian)
+    }
+
+    /// Writes the file gzipped, the form the mod saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn to_gzip_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_gzip_writer(dst, &self.to_raw()?, None, Endianness::BigEndian)
+    }
+
+    /// Recomputes the region count, total blocks, total volume and
+    /// enclosing size in the metadata from the regions.
+    pub fn update_metadata(&mut self) {
+        let mut min = [i32::MAX; 3];
+        let mut max = [i32::MIN; 3];
+        let (mut blocks, mut volume) = (0, 0);
+        for region in self.regions.values() {
+            for axis in 0..3 {
+                let (low, high) = region.extent(axis);
+                min[axis] = min[axis].min(low);
+                max[axis] = max[axis].max(high);
+            }
+            volume += region.volume();
+            blocks += region.blocks.iter().filter(|&&i| {
+                region.palette.get(i as usize).is_some_and(|state| !is_air(state))
+            }).count();
+        }
+        self.metadata.region_count = self.regions.len() as i32;
+        self.metadata.total_blocks = blocks as i32;
+        self.metadata.total_volume = volume as i32;
+        self.metadata.enclosing_size = if self.regions.is_empty() {
+            [0; 3]
+        } else {
+            [max[0] - min[0] + 1, max[1] - min[1] + 1, max[2] - min[2] + 1]
+        };
+    }
+
+    fn from_raw(raw: Raw) -> Result<Litematic> {
+        let mut regions = BTreeMap::new();
+        for (name, region) in raw.regions {
+            regions.insert(name, Region::from_raw(region)?);
+        }
+        Ok(Litematic {
+            version: raw.version,
+            sub_version: raw.sub_version,
+            data_version: raw.data_version,
+            metadata: raw.metadata,
+            regions,
+        })
+    }
+
+    fn to_raw(&self) -> Result<Raw> {
+        let mut regions = BTreeMap::new();
+        for (name, region) in &self.regions {
+            regions.insert(name.clone(), region.to_raw()?);
+        }
+        Ok(Raw {
+            data_version: self.data_version,
+            version: self.version,
+            sub_version: self.sub_version,
+            metadata: self.metadata.clone(),
+            regions,
+        })
+    }
+}
+
+impl Region {
+    /// A region of the given position and size, filled with air.
+    pub fn new(position: [i32; 3], size: [i32; 3]) -> Region {
+        let volume = size.iter().map(|n| n.unsigned_abs() as usize).product();
+        Region {
+            position,
+            size,
+            palette: vec![BlockState::new("minecraft:air")],
+            blocks: vec![0; volume],
+            tile_entities: Vec::new(),
+            entities: Vec::new(),
+            pending_block_ticks: Vec::new(),
+            pending_fluid_ticks: Vec::new(),
+        }
+    }
+
+    /// The number of blocks in the region.
+    pub fn volume(&self) -> usize {
+        self.size.iter().map(|n| n.unsigned_abs() as usize).product()
+    }
+
+    /// The index in `blocks` of the block at `pos`, counted from the corner
+    /// of the region with the smallest coordinates, or `None` if it is
+    /// outside the region.
+    pub fn index(&self, pos: [i32; 3]) -> Option<usize> {
+        let [width, height, length] = self.size.map(|n| n.unsigned_abs() as usize);
+        let [x, y, z] = pos.map(|n| usize::try_from(n).unwrap_or(usize::MAX));
+        if x < width && y < height && z < length {
+            Some((y * length + z) * width + x)
+        } else {
+            None
+        }
+    }
+
+    /// The state of the block at `pos`, or `None` if it is outside the
+    /// region or its index is not in the palette.
+    pub fn block_at(&self, pos: [i32; 3]) -> Option<&BlockState> {
+        let index = *self.blocks.get(self.index(pos)?)?;
+        self.palette.get(index as usize)
+    }
+
+    /// Sets the block at `pos`, adding its state to the palette if it is
+    /// not there yet. Blocks outside the region are ignored.
+    pub fn set_block(&mut self, pos: [i32; 3], state: BlockState) {
+        let i = match self.index(pos) {
+            Some(i) if i < self.blocks.len() => i,
+            _ => return,
+        };
+        let index = match self.palette.iter().position(|s| *s == state) {
+            Some(index) => index,
+            None => {
+                self.palette.push(state);
+                self.palette.len() - 1
+            },
+        };
+        self.blocks[i] = index as u16;
+    }
+
+    /// The blocks, block entities and entities of this region as a version
+    /// 3 Sponge schematic.
+    pub fn to_schematic(&self) -> Result<Schematic> {
+        let size = self.size.map(|n| n.unsigned_abs());
+        if size.iter().any(|&n| n > u32::from(u16::MAX)) {
+            return Err(Error::InvalidSchematic("the region is too large for a schematic"));
+        }
+        let mut schematic = Schematic::new(size.map(|n| n as u16));
+        schematic.palette = self.palette.clone();
+        schematic.blocks = self.blocks.iter().map(|&i| u32::from(i)).collect();
+        for value in &self.tile_entities {
+            let mut data = value.clone();
+            let mut pos = [0; 3];
+            for (pos, key) in pos.iter_mut().zip(["x", "y", "z"]) {
+                *pos = match data.remove(key) {
+                    Some(Value::Int(n)) => n,
+                    _ => return Err(Error::InvalidSchematic("a block entity has no position")),
+                };
+            }
+            let (id, data) = split_id(data, "a block entity has no id")?;
+            schematic.block_entities.push(SchematicBlockEntity { pos, id, data });
+        }
+        for value in &self.entities {
+            let mut data = value.clone();
+            let pos = match data.remove("Pos") {
+                Some(Value::List(ref v)) => match v[..] {
+                    [Value::Double(x), Value::Double(y), Value::Double(z)] => [x, y, z],
+                    _ => return Err(Error::InvalidSchematic("an entity's Pos is not three Doubles")),
+                },
+                _ => return Err(Error::InvalidSchematic("an entity's Pos is not three Doubles")),
+            };
+            let (id, data) = split_id(data, "an entity has no id")?;
+            schematic.entities.push(SchematicEntity { pos, id, data });
+        }
+        Ok(schematic)
+    }
+
+    /// A region at `position` holding the blocks, block entities and
+    /// entities of a Sponge schematic.
+    pub fn from_schematic(schematic: &Schematic, position: [i32; 3]) -> Result<Region> {
+        if schematic.palette.len() > usize::from(u16::MAX) + 1 {
+            return Err(Error::InvalidSchematic("the palette is too large"));
+        }
+        let mut region = Region::new(position, schematic.size.map(i32::from));
+        region.palette = schematic.palette.clone();
+        region.blocks = schematic.blocks.iter().map(|&i| i as u16).collect();
+        for entity in &schematic.block_entities {
+            let mut data = entity.data.clone();
+            data.insert("x".to_string(), Value::Int(entity.pos[0]));
+            data.insert("y".to_string(), Value::Int(entity.pos[1]));
+            data.insert("z".to_string(), Value::Int(entity.pos[2]));
+            data.insert("id".to_string(), Value::String(entity.id.clone()));
+            region.tile_entities.push(Value::Compound(data));
+        }
+        for entity in &schematic.entities {
+            let mut data = entity.data.clone();
+            data.insert("Pos".to_string(), Value::List(entity.pos.iter().map(|&n| Value::Double(n)).collect()));
+            data.insert("id".to_string(), Value::String(entity.id.clone()));
+            region.entities.push(Value::Compound(data));
+        }
+        Ok(region)
+    }
+
+    /// The lowest and highest coordinates the region covers along `axis`.
+    fn extent(&self, axis: usize) -> (i32, i32) {
+        let (start, size) = (self.position[axis], self.size[axis]);
+        if size < 0 {
+            (start + size + 1, start)
+        } else {
+            (start, start + size.max(1) - 1)
+        }
+    }
+
+    fn from_raw(raw: RawRegion) -> Result<Region> {
+        let mut region = Region::new(raw.position, raw.size);
+        let bits = packed::bits_per_entry(raw.block_state_palette.len(), MIN_BITS);
+        let blocks = packed::unpack_spanning(&raw.block_states, bits, region.volume())?;
+        if blocks.iter().any(|&i| usize::from(i) >= raw.block_state_palette.len()) {
+            return Err(Error::InvalidSchematic("a block is not in the palette"));
+        }
+        region.palette = raw.block_state_palette;
+        region.blocks = blocks;
+        region.tile_entities = raw.tile_entities;
+        region.entities = raw.entities;
+        region.pending_block_ticks = raw.pending_block_ticks;
+        region.pending_fluid_ticks = raw.pending_fluid_ticks;
+        Ok(region)
+    }
+
+    fn to_raw(&self) -> Result<RawRegion> {
+        if self.blocks.len() != self.volume() {
+            return Err(Error::InvalidSchematic("the number of blocks does not match the size"));
+        }
+        if self.blocks.iter().any(|&i| usize::from(i) >= self.palette.len()) {
+            return Err(Error::InvalidSchematic("a block is not in the palette"));
+        }
+        let bits = packed::bits_per_entry(self.palette.len(), MIN_BITS);
+        Ok(RawRegion {
+            position: self.position,
+            size: self.size,
+            block_state_palette: self.palette.clone(),
+            block_states: packed::pack_spanning(&self.blocks, bits)?,
+            tile_entities: self.tile_entities.clone(),
+            entities: self.entities.clone(),
+            pending_block_ticks: self.pending_block_ticks.clone(),
+            pending_fluid_ticks: self.pending_fluid_ticks.clone(),
+        })
+    }
+}
+
+fn is_air(state: &BlockState) -> bool {
+    matches!(&state.name[..], "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air")
+}
+
+/// Splits the `id` from the rest of an entity's data.
+fn split_id(mut value: Value, missing: &'static str) -> Result<(String, Map)> {
+    let id = value.remove("id").and_then(|id| id.into_string().ok()).ok_or(Error::InvalidSchematic(missing))?;
+    let data = value.into_compound().map_err(|_| Error::InvalidSchematic(missing))?;
+    Ok((id, data))
+}
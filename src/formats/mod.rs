@@ -0,0 +1,15 @@
+//! Typed models of the NBT files written by Minecraft and common tools.
+//!
+//! Each module describes one kind of file with Serde structs, so that it can
+//! be read with `nbt::from_reader()` or the helpers the module provides,
+//! edited as plain Rust data and written back. Unknown keys are ignored when
+//! reading, and parts whose layout varies, such as the block entity data in
+//! a structure, are kept as `nbt::Value`s with their exact tags.
+
+pub mod structure;
+pub mod schem;
+pub mod litematic;
+pub mod level;
+pub mod player;
+pub mod servers;
+pub mod text;
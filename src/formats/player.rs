@@ -0,0 +1,229 @@
+//! Player data, as saved in `playerdata/<uuid>.dat` and in the `Player` of
+//! a singleplayer `level.dat`, and the item stacks that inventories and
+//! many other files hold.
+//!
+//! Player files are gzipped and big endian. As with `level.dat`, only the
+//! commonly used keys are modeled, so edits are best merged back into the
+//! file they were read from with `Player::write_into()`.
+//!
+//! ```rust
+//! # #[macro_use] extern crate nbt;
+//! use nbt::formats::player::{ItemStack, Player};
+//!
+//! # fn main() {
+//! let mut player = Player::default();
+//! player.health = 20.0;
+//! player.inventory.push(ItemStack::new("minecraft:diamond_sword", 1).in_slot(0));
+//!
+//! let mut bytes = Vec::new();
+//! player.to_gzip_writer(&mut bytes).unwrap();
+//! let read = Player::from_gzip_reader(&bytes[..]).unwrap();
+//! assert_eq!(read.item_in_slot(0).unwrap().id, "minecraft:diamond_sword");
+//! # }
+//! ```
+
+use std::convert::TryFrom;
+use std::io;
+
+use de;
+use error::Result;
+use map::Map;
+use merge::MergeStrategy;
+use raw::Endianness;
+use ser;
+use value::Value;
+
+/// The inventory slots of the armor, from boots to helmet.
+pub const ARMOR_SLOTS: [i8; 4] = [100, 101, 102, 103];
+
+/// The inventory slot of the offhand.
+pub const OFFHAND_SLOT: i8 = -106;
+
+/// The data of a player.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Player {
+    /// The data version of the game that last saved the player.
+    #[serde(rename = "DataVersion", skip_serializing_if = "Option::is_none")]
+    pub data_version: Option<i32>,
+    /// The position, as `[x, y, z]`.
+    #[serde(rename = "Pos")]
+    pub pos: Vec<f64>,
+    /// The velocity, in blocks per tick, as `[x, y, z]`.
+    #[serde(rename = "Motion")]
+    pub motion: Vec<f64>,
+    /// The rotation, in degrees, as `[yaw, pitch]`.
+    #[serde(rename = "Rotation")]
+    pub rotation: Vec<f32>,
+    #[serde(rename = "OnGround")]
+    pub on_ground: bool,
+    /// The dimension the player is in: an Int before 1.16, such as `0` for
+    /// the overworld, and an ID such as `minecraft:overworld` since.
+    #[serde(rename = "Dimension", skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<Value>,
+    /// The health, in half hearts. This was a Short before 1.9, so older
+    /// files are written with a Float.
+    #[serde(rename = "Health")]
+    pub health: f32,
+    #[serde(rename = "foodLevel")]
+    pub food_level: i32,
+    #[serde(rename = "foodSaturationLevel")]
+    pub food_saturation_level: f32,
+    #[serde(rename = "XpLevel")]
+    pub xp_level: i32,
+    /// The progress towards the next level, from 0 to 1.
+    #[serde(rename = "XpP")]
+    pub xp_progress: f32,
+    #[serde(rename = "XpTotal")]
+    pub xp_total: i32,
+    #[serde(rename = "Score")]
+    pub score: i32,
+    /// The game mode: 0 for survival, 1 for creative, 2 for adventure and
+    /// 3 for spectator.
+    #[serde(rename = "playerGameType")]
+    pub game_type: i32,
+    /// The selected hotbar slot, from 0 to 8.
+    #[serde(rename = "SelectedItemSlot")]
+    pub selected_item_slot: i32,
+    /// The items in the inventory, each with its `slot`.
+    #[serde(rename = "Inventory")]
+    pub inventory: Vec<ItemStack>,
+    /// The items in the ender chest, each with a `slot` from 0 to 26.
+    #[serde(rename = "EnderItems")]
+    pub ender_items: Vec<ItemStack>,
+    pub abilities: Abilities,
+}
+
+/// What a player is allowed to do, as set by the game mode.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Abilities {
+    pub flying: bool,
+    #[serde(rename = "flySpeed")]
+    pub fly_speed: f32,
+    #[serde(rename = "walkSpeed")]
+    pub walk_speed: f32,
+    /// Whether blocks break instantly, as in creative.
+    pub instabuild: bool,
+    pub invulnerable: bool,
+    #[serde(rename = "mayBuild")]
+    pub may_build: bool,
+    #[serde(rename = "mayfly")]
+    pub may_fly: bool,
+}
+
+/// A stack of items, as held in inventories, containers, item entities and
+/// many other places.
+///
+/// Since 1.20.5 a stack has an Int `count` and its data in `components`;
+/// before, it had a Byte `Count` and its data in `tag`, and before 1.13 a
+/// `Damage` that also gave the variant of many items. Each form is kept as
+/// it was read, and `count()` and `set_count()` work with either.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    /// The item ID, such as `minecraft:stone`.
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<i32>,
+    #[serde(rename = "Count", default, skip_serializing_if = "Option::is_none")]
+    pub legacy_count: Option<i8>,
+    /// The slot of the stack in its inventory, where it has one.
+    #[serde(rename = "Slot", default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<i8>,
+    /// The data components of the stack, by ID, since 1.20.5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub components: Option<Map>,
+    /// The data of the stack before 1.20.5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<Map>,
+    #[serde(rename = "Damage", default, skip_serializing_if = "Option::is_none")]
+    pub damage: Option<i16>,
+}
+
+impl Player {
+    /// Reads an uncompressed player file.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<Player> {
+        de::from_reader(src, Endianness::BigEndian)
+    }
+
+    /// Reads a gzipped player file, the form the game saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_gzip_reader<R: io::Read>(src: R) -> Result<Player> {
+        de::from_gzip_reader(src, Endianness::BigEndian)
+    }
+
+    /// Writes the model on its own, uncompressed. Keys the model does not
+    /// know are not written; see `write_into()`.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// Writes the model on its own, gzipped. Keys the model does not know
+    /// are not written; see `write_into()`.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn to_gzip_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_gzip_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// Merges the model into the full contents of a player file, replacing
+    /// the values it models and keeping everything else.
+    ///
+    /// Lists, such as the inventory, are replaced as a whole, so keys of
+    /// item stacks that `ItemStack` does not model are lost.
+    pub fn write_into(&self, file: &mut Value) -> Result<()> {
+        file.merge(&ser::to_value(self)?, MergeStrategy::Deep);
+        Ok(())
+    }
+
+    /// The stack in an inventory slot, if there is one. Slots 0 to 8 are
+    /// the hotbar and 9 to 35 the rest of the inventory.
+    pub fn item_in_slot(&self, slot: i8) -> Option<&ItemStack> {
+        self.inventory.iter().find(|item| item.slot == Some(slot))
+    }
+
+    /// The stack in the selected hotbar slot, if there is one.
+    pub fn selected_item(&self) -> Option<&ItemStack> {
+        self.item_in_slot(self.selected_item_slot as i8)
+    }
+}
+
+impl ItemStack {
+    /// Creates a stack in the form of 1.20.5 and later, with no components.
+    pub fn new<S: Into<String>>(id: S, count: i32) -> ItemStack {
+        ItemStack {
+            id: id.into(),
+            count: Some(count),
+            ..ItemStack::default()
+        }
+    }
+
+    /// Returns the stack, placed in `slot`.
+    pub fn in_slot(mut self, slot: i8) -> ItemStack {
+        self.slot = Some(slot);
+        self
+    }
+
+    /// The number of items in the stack, from whichever form it has. A
+    /// stack with no count holds a single item, as the game reads it.
+    pub fn count(&self) -> i32 {
+        match (self.count, self.legacy_count) {
+            (Some(count), _) => count,
+            (None, Some(count)) => i32::from(count),
+            (None, None) => 1,
+        }
+    }
+
+    /// Sets the number of items in the stack, in the form it already has.
+    /// A stack in the old form keeps it if the count fits in a Byte.
+    pub fn set_count(&mut self, count: i32) {
+        match self.legacy_count {
+            Some(ref mut legacy) if self.count.is_none() && i8::try_from(count).is_ok() => {
+                *legacy = count as i8;
+            }
+            _ => {
+                self.legacy_count = None;
+                self.count = Some(count);
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Sponge schematics (`.schem`), the format of WorldEdit and most other
+//! building tools.
+//!
+//! A schematic is a box of `size` blocks, each given by an index into a
+//! palette of block states, which the file stores as VarInts packed into a
+//! `TAG_Byte_Array`. Block entities and entities carry their own NBT.
+//! Versions 2 and 3 of the format are read and written, and version 1 is
+//! read as well; the versions differ in how the data is laid out, not in
+//! what it holds. Biomes are not read. Schematics are gzipped and big
+//! endian.
+//!
+//! ```rust
+//! use nbt::formats::schem::Schematic;
+//! use nbt::formats::structure::BlockState;
+//!
+//! let mut schematic = Schematic::new([2, 1, 1]);
+//! schematic.set_block([1, 0, 0], "minecraft:oak_log[axis=x]".parse().unwrap());
+//!
+//! let mut bytes = Vec::new();
+//! schematic.to_gzip_writer(&mut bytes).unwrap();
+//! let read = Schematic::from_gzip_reader(&bytes[..]).unwrap();
+//! assert_eq!(read.block_at([0, 0, 0]), Some(&BlockState::new("minecraft:air")));
+//! assert_eq!(read.block_at([1, 0, 0]).unwrap().to_string(), "minecraft:oak_log[axis=x]");
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
+
+use de;
+use error::{Error, Result};
+use formats::structure::BlockState;
+use map::Map;
+use raw::Endianness;
+use ser;
+use value::Value;
+
+/// The contents of a Sponge schematic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schematic {
+    /// The version of the format, which decides how the schematic is
+    /// written: 2 or 3.
+    pub version: i32,
+    /// The data version of the game the blocks are from.
+    pub data_version: i32,
+    /// Information about the schematic, such as its `Name` and `Author`.
+    pub metadata: Option<Map>,
+    /// The width, height and length of the schematic, along the x, y and
+    /// z axes.
+    pub size: [u16; 3],
+    /// Where the schematic is placed relative to the player pasting it.
+    pub offset: [i32; 3],
+    /// The block states, which `blocks` index into.
+    pub palette: Vec<BlockState>,
+    /// The palette index of every block, ordered by y, then z, then x, as
+    /// given by `index()`.
+    pub blocks: Vec<u32>,
+    pub block_entities: Vec<SchematicBlockEntity>,
+    pub entities: Vec<SchematicEntity>,
+}
+
+/// A block entity in a schematic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchematicBlockEntity {
+    /// The position of the block, relative to the schematic's origin.
+    pub pos: [i32; 3],
+    /// The type of the block entity, such as `minecraft:chest`.
+    pub id: String,
+    /// The rest of its data.
+    pub data: Map,
+}
+
+/// An entity in a schematic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchematicEntity {
+    /// The exact position of the entity, relative to the schematic's origin.
+    pub pos: [f64; 3],
+    /// The type of the entity, such as `minecraft:armor_stand`.
+    pub id: String,
+    /// The rest of its data.
+    pub data: Map,
+}
+
+/// The layout of versions 1 and 2, whose root is the schematic.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct V2 {
+    version: i32,
+    #[serde(default)]
+    data_version: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Map>,
+    width: i16,
+    height: i16,
+    length: i16,
+    #[serde(default, with = "::arrays::int_array")]
+    offset: Vec<i32>,
+    #[serde(default)]
+    palette_max: i32,
+    palette: BTreeMap<String, i32>,
+    #[serde(with = "::arrays::byte_array")]
+    block_data: Vec<i8>,
+    #[serde(default, alias = "TileEntities")]
+    block_entities: Vec<Value>,
+    #[serde(default)]
+    entities: Vec<Value>,
+}
+
+/// The layout of version 3, whose root holds the schematic.
+#[derive(Serialize, Deserialize)]
+struct V3File {
+    #[serde(rename = "Schematic")]
+    schematic: V3,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct V3 {
+    version: i32,
+    data_version: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Map>,
+    width: i16,
+    height: i16,
+    length: i16,
+    #[serde(default, with = "::arrays::int_array")]
+    offset: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blocks: Option<V3Blocks>,
+    #[serde(default)]
+    entities: Vec<Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct V3Blocks {
+    palette: BTreeMap<String, i32>,
+    #[serde(with = "::arrays::byte_array")]
+    data: Vec<i8>,
+    #[serde(default)]
+    block_entities: Vec<Value>,
+}
+
+impl Schematic {
+    /// A version 3 schematic of the given size, filled with air.
+    pub fn new(size: [u16; 3]) -> Schematic {
+        let volume = size.iter().map(|&n| n as usize).product();
+        Schematic {
+            version: 3,
+            data_version: 0,
+            metadata: None,
+            size,
+            offset: [0; 3],
+            palette: vec![BlockState::new("minecraft:air")],
+            blocks: vec![0; volume],
+            block_entities: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// Reads an uncompressed schematic of any version.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<Schematic> {
+        Schematic::from_root(de::from_reader(src, Endianness::BigEndian)?)
+    }
+
+    /// Reads a gzipped schematic of any version, the form tools save.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_gzip_reader<R: io::Read>(src: R) -> Result<Schematic> {
+        Schematic::from_root(de::from_gzip_reader(src, Endianness::BigEndian)?)
+    }
+
+    /// Writes the schematic, uncompressed, in the layout of its `version`.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        match self.version {
+            2 => ser::to_writer(dst, &self.to_v2()?, Some("Schematic"), Endianness::BigEndian),
+            3 => ser::to_writer(dst, &self.to_v3()?, None, Endianness::BigEndian),
+            _ => Err(Error::InvalidSchematic("only versions 2 and 3 can be written")),
+        }
+    }
+
+    /// Writes the schematic gzipped, in the layout of its `version`.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn to_gzip_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        match self.version {
+            2 => ser::to_gzip_writer(dst, &self.to_v2()?, Some("Schematic"), Endianness::BigEndian),
+            3 => ser::to_gzip_writer(dst, &self.to_v3()?, None, Endianness::BigEndian),
+            _ => Err(Error::InvalidSchematic("only versions 2 and 3 can be written")),
+        }
+    }
+
+    /// The index in `blocks` of the block at `pos`, or `None` if it is
+    /// outside the schematic.
+    pub fn index(&self, pos: [i32; 3]) -> Option<usize> {
+        let [width, height, length] = self.size.map(usize::from);
+        let [x, y, z] = pos.map(|n| usize::try_from(n).unwrap_or(usize::MAX));
+        if x < width && y < height && z < length {
+            Some((y * length + z) * width + x)
+        } else {
+            None
+        }
+    }
+
+    /// The state of the block at `pos`, or `None` if it is outside the
+    /// schematic or its index is not in the palette.
+    pub fn block_at(&self, pos: [i32; 3]) -> Option<&BlockState> {
+        let index = *self.blocks.get(self.index(pos)?)?;
+        self.palette.get(index as usize)
+    }
+
+    /// Sets the block at `pos`, adding its state to the palette if it is
+    /// not there yet. Blocks outside the schematic are ignored.
+    pub fn set_block(&mut self, pos: [i32; 3], state: BlockState) {
+        let i = match self.index(pos) {
+            Some(i) if i < self.blocks.len() => i,
+            _ => return,
+        };
+        let index = match self.palette.iter().position(|s| *s == state) {
+            Some(index) => index,
+            None => {
+                self.palette.push(state);
+                self.palette.len() - 1
+            },
+        };
+        self.blocks[i] = index as u32;
+    }
+
+    fn from_root(mut root: Value) -> Result<Schematic> {
+        let schematic = match root.remove("Schematic") {
+            Some(schematic @ Value::Compound(_)) => schematic,
+            Some(other) => return Err(Error::TagMismatch(other.id(), 0x0a)),
+            None => root,
+        };
+        let version = match schematic.get("Version") {
+            Some(&Value::Int(version)) => version,
+            _ => return Err(Error::MissingField("Version".to_string())),
+        };
+        match version {
+            1 | 2 => Schematic::from_v2(de::from_value(schematic)?),
+            3 => Schematic::from_v3(de::from_value(schematic)?),
+            _ => Err(Error::InvalidSchematic("the version is not supported")),
+        }
+    }
+
+    fn from_v2(raw: V2) -> Result<Schematic> {
+        let mut schematic = Schematic::empty(raw.version, raw.data_version, raw.metadata,
+                                             [raw.width, raw.height, raw.length], &raw.offset)?;
+        schematic.read_blocks(raw.palette, &raw.block_data)?;
+        for value in raw.block_entities {
+            schematic.block_entities.push(SchematicBlockEntity::from_value(value, false)?);
+        }
+        for value in raw.entities {
+            schematic.entities.push(SchematicEntity::from_value(value, false)?);
+        }
+        Ok(schematic)
+    }
+
+    fn from_v3(raw: V3) -> Result<Schematic> {
+        let mut schematic = Schematic::empty(raw.version, raw.data_version, raw.metadata,
+                                             [raw.width, raw.height, raw.length], &raw.offset)?;
+        // A schematic of only entities has no blocks, and is left filled
+        // with air.
+        if let Some(blocks) = raw.blocks {
+            schematic.read_blocks(blocks.palette, &blocks.data)?;
+            for value in blocks.block_entities {
+                schematic.block_entities.push(SchematicBlockEntity::from_value(value, true)?);
+            }
+        }
+        for value in raw.entities {
+            schematic.entities.push(SchematicEntity::from_value(value, true)?);
+        }
+        Ok(schematic)
+    }
+
+    fn empty(version: i32, data_version: i32, metadata: Option<Map>, size: [i16; 3], offset: &[i32])
+             -> Result<Schematic> {
+        let mut schematic = Schematic::new(size.map(|n| n as u16));
+        schematic.version = version;
+        schematic.data_version = data_version;
+        schematic.metadata = metadata;
+        schematic.offset = match *offset {
+            [] => [0; 3],
+            [x, y, z] => [x, y, z],
+            _ => return Err(Error::InvalidSchematic("the offset does not have three coordinates")),
+        };
+        Ok(schematic)
+    }
+
+    /// Reads a palette and VarInt block data in place of the air this
+    /// schematic was created with.
+    fn read_blocks(&mut self, palette: BTreeMap<String, i32>, data: &[i8]) -> Result<()> {
+        let mut states = vec![None; palette.len()];
+        for (name, index) in palette {
+            match usize::try_from(index).ok().and_then(|i| states.get_mut(i)) {
+                Some(slot) if slot.is_none() => *slot = Some(name.parse()?),
+                _ => return Err(Error::InvalidSchematic("the palette indices are not 0 to its length")),
+            }
+        }
+        self.palette = states.into_iter().map(Option::unwrap).collect();
+
+        let blocks = read_varints(data)?;
+        if blocks.len() != self.blocks.len() {
+            return Err(Error::InvalidSchematic("the number of blocks does not match the size"));
+        }
+        if blocks.iter().any(|&index| index as usize >= self.palette.len()) {
+            return Err(Error::InvalidSchematic("a block is not in the palette"));
+        }
+        self.blocks = blocks;
+        Ok(())
+    }
+
+    /// The palette by name and the VarInt block data of this schematic.
+    fn write_blocks(&self) -> Result<(BTreeMap<String, i32>, Vec<i8>)> {
+        if self.blocks.iter().any(|&index| index as usize >= self.palette.len()) {
+            return Err(Error::InvalidSchematic("a block is not in the palette"));
+        }
+        let mut palette = BTreeMap::new();
+        for (i, state) in self.palette.iter().enumerate() {
+            if palette.insert(state.to_string(), i as i32).is_some() {
+                return Err(Error::InvalidSchematic("a block state is in the palette twice"));
+            }
+        }
+        Ok((palette, write_varints(&self.blocks)))
+    }
+
+    fn raw_size(&self) -> Result<[i16; 3]> {
+        if self.blocks.len() != self.size.iter().map(|&n| n as usize).product::<usize>() {
+            return Err(Error::InvalidSchematic("the number of blocks does not match the size"));
+        }
+        Ok(self.size.map(|n| n as i16))
+    }
+
+    fn to_v2(&self) -> Result<V2> {
+        let [width, height, length] = self.raw_size()?;
+        let (palette, block_data) = self.write_blocks()?;
+        Ok(V2 {
+            version: 2,
+            data_version: self.data_version,
+            metadata: self.metadata.clone(),
+            width,
+            height,
+            length,
+            offset: self.offset.to_vec(),
+            palette_max: palette.len() as i32,
+            palette,
+            block_data,
+            block_entities: self.block_entities.iter().map(|e| e.to_value(false)).collect(),
+            entities: self.entities.iter().map(|e| e.to_value(false)).collect(),
+        })
+    }
+
+    fn to_v3(&self) -> Result<V3File> {
+        let [width, height, length] = self.raw_size()?;
+        let (palette, data) = self.write_blocks()?;
+        let block_entities = self.block_entities.iter().map(|e| e.to_value(true)).collect();
+        Ok(V3File {
+            schematic: V3 {
+                version: 3,
+                data_version: self.data_version,
+                metadata: self.metadata.clone(),
+                width,
+                height,
+                length,
+                offset: self.offset.to_vec(),
+                blocks: Some(V3Blocks { palette, data, block_entities }),
+                entities: self.entities.iter().map(|e| e.to_value(true)).collect(),
+            },
+        })
+    }
+}
+
+impl SchematicBlockEntity {
+    fn from_value(value: Value, nested: bool) -> Result<SchematicBlockEntity> {
+        let (pos, id, data) = split_entity(value, nested, "a block entity is not a valid Compound")?;
+        let pos = match pos {
+            Value::IntArray(ref v) if v.len() == 3 => [v[0], v[1], v[2]],
+            _ => return Err(Error::InvalidSchematic("a block entity's Pos is not three Ints")),
+        };
+        Ok(SchematicBlockEntity { pos, id, data })
+    }
+
+    fn to_value(&self, nested: bool) -> Value {
+        join_entity(Value::IntArray(self.pos.to_vec()), &self.id, &self.data, nested)
+    }
+}
+
+impl SchematicEntity {
+    fn from_value(value: Value, nested: bool) -> Result<SchematicEntity> {
+        let (pos, id, data) = split_entity(value, nested, "an entity is not a valid Compound")?;
+        let pos = match pos {
+            Value::List(ref v) => match v[..] {
+                [Value::Double(x), Value::Double(y), Value::Double(z)] => [x, y, z],
+                _ => return Err(Error::InvalidSchematic("an entity's Pos is not three Doubles")),
+            },
+            _ => return Err(Error::InvalidSchematic("an entity's Pos is not three Doubles")),
+        };
+        Ok(SchematicEntity { pos, id, data })
+    }
+
+    fn to_value(&self, nested: bool) -> Value {
+        let pos = Value::List(self.pos.iter().map(|&n| Value::Double(n)).collect());
+        join_entity(pos, &self.id, &self.data, nested)
+    }
+}
+
+/// Splits an entity or block entity into its `Pos`, its `Id` and the rest
+/// of its data, which version 3 nests in `Data` and earlier versions keep
+/// alongside.
+fn split_entity(mut value: Value, nested: bool, invalid: &'static str) -> Result<(Value, String, Map)> {
+    let pos = value.remove("Pos").ok_or(Error::InvalidSchematic(invalid))?;
+    let id = value.remove("Id").and_then(|id| id.into_string().ok()).ok_or(Error::InvalidSchematic(invalid))?;
+    let data = if nested { value.remove("Data").unwrap_or(Value::Compound(Map::new())) } else { value };
+    let data = data.into_compound().map_err(|_| Error::InvalidSchematic(invalid))?;
+    Ok((pos, id, data))
+}
+
+fn join_entity(pos: Value, id: &str, data: &Map, nested: bool) -> Value {
+    let mut map = if nested { Map::new() } else { data.clone() };
+    map.insert("Pos".to_string(), pos);
+    map.insert("Id".to_string(), Value::String(id.to_string()));
+    if nested {
+        map.insert("Data".to_string(), Value::Compound(data.clone()));
+    }
+    Value::Compound(map)
+}
+
+/// Decodes unsigned VarInts, seven bits to a byte with the least
+/// significant first.
+fn read_varints(data: &[i8]) -> Result<Vec<u32>> {
+    let mut values = Vec::new();
+    let (mut value, mut shift) = (0u32, 0);
+    for &byte in data {
+        if shift >= 32 || (shift == 28 && byte as u8 & 0x70 != 0) {
+            return Err(Error::InvalidVarInt);
+        }
+        value |= u32::from(byte as u8 & 0x7f) << shift;
+        if byte as u8 & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+        }
+    }
+    if shift != 0 {
+        return Err(Error::InvalidSchematic("the block data ends inside a VarInt"));
+    }
+    Ok(values)
+}
+
+fn write_varints(values: &[u32]) -> Vec<i8> {
+    let mut data = Vec::with_capacity(values.len());
+    for &value in values {
+        let mut value = value;
+        while value >= 0x80 {
+            data.push((value as u8 & 0x7f | 0x80) as i8);
+            value >>= 7;
+        }
+        data.push(value as i8);
+    }
+    data
+}
@@ -0,0 +1,94 @@
+//! The client's server list, `servers.dat`.
+//!
+//! The file is an uncompressed, big endian Compound holding a `servers`
+//! List, in the order the game shows them.
+//!
+//! ```rust
+//! use nbt::formats::servers::{Server, ServerList};
+//!
+//! let mut list = ServerList::default();
+//! list.servers.push(Server::new("Home", "192.168.1.20:25565"));
+//! list.servers.push(Server::new("Hypixel", "mc.hypixel.net"));
+//! list.find_mut("mc.hypixel.net").unwrap().accept_textures = Some(false);
+//! assert!(list.remove("192.168.1.20:25565").is_some());
+//!
+//! let mut bytes = Vec::new();
+//! list.to_writer(&mut bytes).unwrap();
+//! assert_eq!(ServerList::from_reader(&bytes[..]).unwrap(), list);
+//! ```
+
+use std::io;
+
+use de;
+use error::Result;
+use raw::Endianness;
+use ser;
+
+/// The contents of a `servers.dat` file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerList {
+    #[serde(default)]
+    pub servers: Vec<Server>,
+}
+
+/// An entry of the server list.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Server {
+    /// The name shown in the list.
+    #[serde(default)]
+    pub name: String,
+    /// The address, as entered, such as `example.com` or `10.0.0.1:25566`.
+    pub ip: String,
+    /// The server icon from the last ping, a Base64 encoded 64x64 PNG.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Whether to use the server's resource pack: `Some(true)` to accept it,
+    /// `Some(false)` to refuse it and `None` to ask.
+    #[serde(rename = "acceptTextures", default, skip_serializing_if = "Option::is_none")]
+    pub accept_textures: Option<bool>,
+    /// Whether the entry is hidden, as for servers joined through the
+    /// multiplayer screen's direct connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+}
+
+impl ServerList {
+    /// Reads a server list.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<ServerList> {
+        de::from_reader(src, Endianness::BigEndian)
+    }
+
+    /// Writes the server list.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// The first entry with the address `ip`, if there is one.
+    pub fn find(&self, ip: &str) -> Option<&Server> {
+        self.servers.iter().find(|server| server.ip == ip)
+    }
+
+    /// A mutable reference to the first entry with the address `ip`, if
+    /// there is one.
+    pub fn find_mut(&mut self, ip: &str) -> Option<&mut Server> {
+        self.servers.iter_mut().find(|server| server.ip == ip)
+    }
+
+    /// Removes the first entry with the address `ip` and returns it, if
+    /// there is one.
+    pub fn remove(&mut self, ip: &str) -> Option<Server> {
+        let index = self.servers.iter().position(|server| server.ip == ip)?;
+        Some(self.servers.remove(index))
+    }
+}
+
+impl Server {
+    /// Creates an entry with no icon, which asks about resource packs.
+    pub fn new<S: Into<String>, T: Into<String>>(name: S, ip: T) -> Server {
+        Server {
+            name: name.into(),
+            ip: ip.into(),
+            ..Server::default()
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Structure files, as saved by structure blocks and used by the vanilla
+//! world generator.
+//!
+//! A structure is a box of `size` blocks, each given by an index into a
+//! palette of block states. Only the blocks actually stored are listed, so
+//! air that the structure should not overwrite is simply absent, and block
+//! entities and entities carry their own NBT. Structure files are gzipped
+//! and big endian.
+//!
+//! ```rust
+//! # #[macro_use] extern crate nbt;
+//! use nbt::formats::structure::{BlockState, Structure, StructureBlock};
+//!
+//! # fn main() {
+//! let mut structure = Structure::new([1, 2, 1]);
+//! structure.palette.push(BlockState::new("minecraft:chest").with("facing", "north"));
+//! structure.blocks.push(StructureBlock {
+//!     state: 0,
+//!     pos: vec![0, 1, 0],
+//!     nbt: Some(nbt!({ id: "minecraft:chest", Items: [] })),
+//! });
+//!
+//! let mut bytes = Vec::new();
+//! structure.to_gzip_writer(&mut bytes).unwrap();
+//! let read = Structure::from_gzip_reader(&bytes[..]).unwrap();
+//! assert_eq!(read, structure);
+//! assert_eq!(read.block_at([0, 1, 0]).unwrap().1.to_string(), "minecraft:chest[facing=north]");
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use de;
+use error::{Error, Result};
+use raw::Endianness;
+use ser;
+use value::Value;
+
+/// The contents of a structure file.
+///
+/// Most structures have a single `palette`. Some, such as shipwrecks, have
+/// several alternative `palettes` instead, of the same length, one of which
+/// is picked each time the structure is placed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Structure {
+    /// The data version of the game that saved the structure.
+    #[serde(rename = "DataVersion")]
+    pub data_version: i32,
+    /// The size of the structure along the x, y and z axes.
+    pub size: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<BlockState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palettes: Vec<Vec<BlockState>>,
+    #[serde(default)]
+    pub blocks: Vec<StructureBlock>,
+    #[serde(default)]
+    pub entities: Vec<StructureEntity>,
+}
+
+/// A block state: the ID of a block and the values of its properties, such
+/// as `minecraft:oak_stairs` facing east.
+///
+/// It is displayed and parsed in the syntax of commands, as
+/// `minecraft:oak_stairs[facing=east,half=bottom]`, which schematic formats
+/// use for the names in their palettes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlockState {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Properties", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+}
+
+/// A block stored in a structure.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StructureBlock {
+    /// The index of the block's state in the palette.
+    pub state: i32,
+    /// The position of the block, relative to the structure's origin.
+    pub pos: Vec<i32>,
+    /// The data of the block entity, if the block has one, without its
+    /// position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nbt: Option<Value>,
+}
+
+/// An entity stored in a structure.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StructureEntity {
+    /// The exact position of the entity, relative to the structure's origin.
+    pub pos: Vec<f64>,
+    /// The position of the block the entity is in.
+    #[serde(rename = "blockPos")]
+    pub block_pos: Vec<i32>,
+    /// The data of the entity, without its position or UUID.
+    pub nbt: Value,
+}
+
+impl Structure {
+    /// An empty structure of the given size, with no palette, blocks or
+    /// entities and a data version of zero.
+    pub fn new(size: [i32; 3]) -> Structure {
+        Structure {
+            data_version: 0,
+            size: size.to_vec(),
+            palette: Vec::new(),
+            palettes: Vec::new(),
+            blocks: Vec::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    /// Reads an uncompressed structure file.
+    pub fn from_reader<R: io::Read>(src: R) -> Result<Structure> {
+        de::from_reader(src, Endianness::BigEndian)
+    }
+
+    /// Reads a gzipped structure file, the form the game saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn from_gzip_reader<R: io::Read>(src: R) -> Result<Structure> {
+        de::from_gzip_reader(src, Endianness::BigEndian)
+    }
+
+    /// Writes the structure, uncompressed.
+    pub fn to_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// Writes the structure gzipped, the form the game saves.
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub fn to_gzip_writer<W: ?Sized + io::Write>(&self, dst: &mut W) -> Result<()> {
+        ser::to_gzip_writer(dst, self, None, Endianness::BigEndian)
+    }
+
+    /// The palette that the blocks' states index into: `palette`, or the
+    /// first of `palettes` if there are several.
+    pub fn default_palette(&self) -> &[BlockState] {
+        match self.palettes.first() {
+            Some(palette) if self.palette.is_empty() => palette,
+            _ => &self.palette,
+        }
+    }
+
+    /// The state of a block in the default palette, or `None` if its index
+    /// is out of range.
+    pub fn state_of(&self, block: &StructureBlock) -> Option<&BlockState> {
+        usize::try_from(block.state).ok().and_then(|i| self.default_palette().get(i))
+    }
+
+    /// The block stored at `pos`, with its state in the default palette.
+    pub fn block_at(&self, pos: [i32; 3]) -> Option<(&StructureBlock, &BlockState)> {
+        let block = self.blocks.iter().find(|block| block.pos[..] == pos[..])?;
+        self.state_of(block).map(|state| (block, state))
+    }
+}
+
+impl BlockState {
+    /// The default state of the block with ID `name`, without properties.
+    pub fn new<S: Into<String>>(name: S) -> BlockState {
+        BlockState { name: name.into(), properties: BTreeMap::new() }
+    }
+
+    /// Sets a property.
+    pub fn with<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> BlockState {
+        self.properties.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl fmt::Display for BlockState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.properties.is_empty() {
+            f.write_str("[")?;
+            for (i, (key, value)) in self.properties.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",")?;
+                }
+                write!(f, "{}={}", key, value)?;
+            }
+            f.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BlockState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<BlockState> {
+        let invalid = || Error::InvalidBlockState(s.to_string());
+        let (name, properties) = match s.find('[') {
+            Some(i) if s.ends_with(']') => (&s[..i], Some(&s[i + 1..s.len() - 1])),
+            Some(_) => return Err(invalid()),
+            None => (s, None),
+        };
+        if name.is_empty() || name.contains([']', ',', '=']) {
+            return Err(invalid());
+        }
+        let mut state = BlockState::new(name);
+        for property in properties.into_iter().filter(|p| !p.is_empty()).flat_map(|p| p.split(',')) {
+            match property.split_once('=') {
+                Some((key, value)) if !key.is_empty() && !value.is_empty() && !value.contains('=') => {
+                    state.properties.insert(key.to_string(), value.to_string());
+                },
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(state)
+    }
+}
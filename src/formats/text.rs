@@ -0,0 +1,272 @@
+//! Text components, the formatted text of item names, lore, signs, books
+//! and chat.
+//!
+//! A text component is either a plain String or a Compound with some
+//! content, such as `text`, and its style. A List is also accepted, as the
+//! first component with the rest appended to its `extra`. Since 1.20.3 many
+//! places store components as NBT rather than as JSON strings, and the same
+//! types read and write both: use `nbt::from_value()` on the NBT, or
+//! `serde_json` on the JSON.
+//!
+//! ```rust
+//! # #[macro_use] extern crate nbt;
+//! use nbt::formats::text::{Component, Text};
+//!
+//! # fn main() {
+//! let name: Text = nbt::from_value(nbt!({
+//!     text: "Excalibur",
+//!     color: "gold",
+//!     bold: 1i8,
+//!     extra: [" ", { text: "+1", color: "green" }],
+//! })).unwrap();
+//! assert_eq!(name.to_plain_string(), "Excalibur +1");
+//!
+//! let mut lore = Component::text("A legendary sword");
+//! lore.italic = Some(false);
+//! let lore = nbt::to_value(&Text::from(lore)).unwrap();
+//! assert_eq!(lore, nbt!({ text: "A legendary sword", italic: 0i8 }));
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use value::Value;
+
+/// A text component.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Text {
+    /// Unformatted text, written as a String.
+    Plain(String),
+    /// Text with a style or other content, written as a Compound.
+    Component(Box<Component>),
+}
+
+/// A text component written as a Compound.
+///
+/// Only one of the content fields, `text`, `translate` and `keybind`, is
+/// normally set. Style fields that are `None` are inherited from the
+/// parent component.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Component {
+    /// The literal text. Plain text wrapped in a Compound as the only entry,
+    /// under the empty key, is read here too; the game writes that in Lists
+    /// that mix Strings and Compounds.
+    #[serde(alias = "", default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// A translation key, such as `item.minecraft.diamond_sword`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translate: Option<String>,
+    /// The text to use if the translation key is unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+    /// The arguments of the translation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", serialize_with = "serialize_list")]
+    pub with: Vec<Text>,
+    /// A key binding, such as `key.jump`, shown as the key it is bound to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keybind: Option<String>,
+    /// A color name, such as `red`, or a hex code, such as `#ff8800`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// A font, such as `minecraft:uniform`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underlined: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strikethrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscated: Option<bool>,
+    /// Text inserted into the chat box when the component is shift-clicked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insertion: Option<String>,
+    #[serde(rename = "clickEvent", default, skip_serializing_if = "Option::is_none")]
+    pub click_event: Option<ClickEvent>,
+    #[serde(rename = "hoverEvent", default, skip_serializing_if = "Option::is_none")]
+    pub hover_event: Option<HoverEvent>,
+    /// Components appended after this one, which inherit its style.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", serialize_with = "serialize_list")]
+    pub extra: Vec<Text>,
+}
+
+/// What happens when a component is clicked.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClickEvent {
+    /// The action, such as `open_url`, `run_command`, `suggest_command` or
+    /// `copy_to_clipboard`.
+    pub action: String,
+    /// The URL, command or text the action uses.
+    pub value: String,
+}
+
+/// What is shown when a component is hovered over.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HoverEvent {
+    /// The action: `show_text`, `show_item` or `show_entity`.
+    pub action: String,
+    /// What to show: a text component for `show_text` and a Compound
+    /// describing the item or entity otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contents: Option<Value>,
+}
+
+impl Text {
+    /// The text with all formatting removed. Translated components give
+    /// their fallback or, failing that, their key, and key bindings give
+    /// the name of the binding.
+    pub fn to_plain_string(&self) -> String {
+        let mut s = String::new();
+        self.push_plain(&mut s);
+        s
+    }
+
+    fn push_plain(&self, s: &mut String) {
+        match *self {
+            Text::Plain(ref text) => s.push_str(text),
+            Text::Component(ref component) => {
+                let content = component.text.as_ref()
+                    .or(component.fallback.as_ref())
+                    .or(component.translate.as_ref())
+                    .or(component.keybind.as_ref());
+                if let Some(content) = content {
+                    s.push_str(content);
+                }
+                for text in &component.extra {
+                    text.push_plain(s);
+                }
+            }
+        }
+    }
+}
+
+impl Component {
+    /// Creates a component of literal text with no style.
+    pub fn text<S: Into<String>>(text: S) -> Component {
+        Component {
+            text: Some(text.into()),
+            ..Component::default()
+        }
+    }
+
+    /// Creates a component of a translation key with no style.
+    pub fn translate<S: Into<String>>(key: S) -> Component {
+        Component {
+            translate: Some(key.into()),
+            ..Component::default()
+        }
+    }
+}
+
+impl HoverEvent {
+    /// Creates an event that shows `text`.
+    pub fn show_text(text: &Text) -> HoverEvent {
+        HoverEvent {
+            action: "show_text".to_string(),
+            contents: Some(text_to_value(text)),
+        }
+    }
+}
+
+fn text_to_value(text: &Text) -> Value {
+    // Text is always representable: its fields are Strings, bools and
+    // Lists of Compounds or of Strings.
+    ::ser::to_value(text).expect("text components can be written")
+}
+
+impl<'a> From<&'a str> for Text {
+    fn from(text: &'a str) -> Text {
+        Text::Plain(text.to_string())
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Text {
+        Text::Plain(text)
+    }
+}
+
+impl From<Component> for Text {
+    fn from(component: Component) -> Text {
+        Text::Component(Box::new(component))
+    }
+}
+
+impl fmt::Display for Text {
+    /// Writes the text with all formatting removed, as
+    /// `to_plain_string()`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_plain_string())
+    }
+}
+
+impl Serialize for Text {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Text::Plain(ref text) => serializer.serialize_str(text),
+            Text::Component(ref component) => component.serialize(serializer),
+        }
+    }
+}
+
+/// Writes a List of components, wrapping plain text in Compounds if some
+/// are Compounds, since NBT Lists hold a single type.
+fn serialize_list<S: Serializer>(list: &[Text], serializer: S) -> Result<S::Ok, S::Error> {
+    let mixed = list.iter().any(|text| matches!(text, Text::Component(_)))
+        && list.iter().any(|text| matches!(text, Text::Plain(_)));
+    if !mixed {
+        return serializer.collect_seq(list);
+    }
+    serializer.collect_seq(list.iter().map(|text| match *text {
+        Text::Plain(ref text) => Text::from(Component::text(text.as_str())),
+        ref component => component.clone(),
+    }))
+}
+
+impl<'de> Deserialize<'de> for Text {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Text, D::Error> {
+        deserializer.deserialize_any(TextVisitor)
+    }
+}
+
+struct TextVisitor;
+
+impl<'de> de::Visitor<'de> for TextVisitor {
+    type Value = Text;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a text component")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Text, E> {
+        Ok(Text::from(text))
+    }
+
+    fn visit_string<E: de::Error>(self, text: String) -> Result<Text, E> {
+        Ok(Text::Plain(text))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Text, A::Error> {
+        Component::deserialize(MapAccessDeserializer::new(map)).map(Text::from)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Text, A::Error> {
+        let mut list = Vec::<Text>::deserialize(SeqAccessDeserializer::new(seq))?.into_iter();
+        let first = match list.next() {
+            Some(first) => first,
+            None => return Err(de::Error::invalid_length(0, &"a non-empty List")),
+        };
+        let mut component = match first {
+            Text::Plain(text) => Component::text(text),
+            Text::Component(component) => *component,
+        };
+        component.extra.extend(list);
+        Ok(Text::from(component))
+    }
+}
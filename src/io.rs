@@ -0,0 +1,185 @@
+//! The I/O traits that `RawReader`, `RawWriter` and `Value` read and write
+//! through.
+//!
+//! With the `std` feature these are simply `std::io`'s. Without it they are
+//! minimal stand-ins with the same names and signatures, implemented for
+//! byte slices and `Vec<u8>`, so that NBT can be decoded from a buffer in
+//! `no_std` environments. Other sources can implement `Read` and `Write`
+//! themselves.
+
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::core_io::{Error, ErrorKind, Read, Result, Write};
+
+#[cfg(not(feature = "std"))]
+mod core_io {
+    use std::cmp;
+    use std::fmt;
+    use std::prelude::*;
+    use std::result;
+
+    /// A specialized `Result` type for I/O operations.
+    pub type Result<T> = result::Result<T, Error>;
+
+    /// The general category of an I/O error, as in `std::io`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ErrorKind {
+        /// Data was not valid for the operation.
+        InvalidData,
+        /// A parameter was incorrect.
+        InvalidInput,
+        /// The input ended before as many bytes as needed had been read.
+        UnexpectedEof,
+        /// Nothing could be written, as the destination is full.
+        WriteZero,
+        /// Any other error.
+        Other,
+    }
+
+    /// An I/O error: its kind and a description.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: ErrorKind,
+        message: String,
+    }
+
+    impl Error {
+        /// Creates an error of the given kind with a description.
+        pub fn new<M: fmt::Display>(kind: ErrorKind, message: M) -> Error {
+            Error { kind, message: message.to_string() }
+        }
+
+        /// The kind of this error.
+        pub fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Error {
+            let message = match kind {
+                ErrorKind::InvalidData => "invalid data",
+                ErrorKind::InvalidInput => "invalid input parameter",
+                ErrorKind::UnexpectedEof => "unexpected end of file",
+                ErrorKind::WriteZero => "failed to write whole buffer",
+                ErrorKind::Other => "other error",
+            };
+            Error::new(kind, message)
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.message)
+        }
+    }
+
+    impl ::std::error::Error for Error {
+        fn description(&self) -> &str {
+            &self.message
+        }
+    }
+
+    /// A source of bytes.
+    pub trait Read {
+        /// Reads some bytes into `buf`, returning how many. Zero means the
+        /// source is exhausted, unless `buf` is empty.
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
+
+        /// Fills `buf` completely, failing with `ErrorKind::UnexpectedEof`
+        /// if the source ends first.
+        fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.read(buf)? {
+                    0 => return Err(ErrorKind::UnexpectedEof.into()),
+                    n => buf = &mut buf[n..],
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// A destination for bytes.
+    pub trait Write {
+        /// Writes some of `buf`, returning how many bytes were written.
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        /// Writes all of `buf`, failing with `ErrorKind::WriteZero` if the
+        /// destination fills up first.
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf)? {
+                    0 => return Err(ErrorKind::WriteZero.into()),
+                    n => buf = &buf[n..],
+                }
+            }
+            Ok(())
+        }
+
+        /// Flushes any buffered output.
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for &[u8] {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = cmp::min(buf.len(), self.len());
+            let (head, tail) = self.split_at(n);
+            buf[..n].copy_from_slice(head);
+            *self = tail;
+            Ok(n)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for Box<R> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            (**self).read(buf)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    impl Write for &mut [u8] {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            let n = cmp::min(buf.len(), self.len());
+            let (head, tail) = ::std::mem::take(self).split_at_mut(n);
+            head.copy_from_slice(&buf[..n]);
+            *self = tail;
+            Ok(n)
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for Box<W> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+    }
+}
@@ -0,0 +1,617 @@
+//! Converting between `Value`s and `serde_json::Value`s.
+//!
+//! JSON has fewer types than NBT, so a round trip loses some information
+//! unless it is put back:
+//!
+//! * Bytes, Shorts, Ints and Longs all become JSON integers, and Floats and
+//!   Doubles become JSON numbers. Floats are written with the shortest
+//!   decimal that reads back as the same `f32`, so `0.1f32` is `0.1` rather
+//!   than `0.10000000149011612`. NaN and the infinities become `null`.
+//! * Byte, Int and Long arrays become JSON arrays of integers, as Lists do.
+//! * Longs beyond 2^53 lose precision in JavaScript and many other JSON
+//!   readers; `JsonOptions::longs_as_strings()` writes them as strings.
+//!
+//! Reading JSON back, integers become Ints or, when out of their range,
+//! Longs, other numbers become Doubles, and booleans become Bytes. Arrays
+//! become Lists, or arrays as set by `JsonOptions::arrays()`; the numbers in
+//! a List are widened to a common type so that it stays homogeneous.
+//! `from_json_like()` takes the original `Value` as a template instead, and
+//! restores the types it has for the same paths.
+//!
+//! ```rust
+//! use nbt::json::{self, JsonOptions};
+//! use nbt::Value;
+//!
+//! let value = nbt::nbt!({ "Health": 20.0f32, "Pos": [0.5, 64.0, 0.5], "Inventory": [] });
+//! let options = JsonOptions::new();
+//! let text = json::to_json(&value, &options).to_string();
+//! assert_eq!(text, r#"{"Health":20.0,"Inventory":[],"Pos":[0.5,64.0,0.5]}"#);
+//!
+//! let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+//! assert_eq!(json::from_json(&parsed, &options).unwrap()["Health"], Value::Double(20.0));
+//! assert_eq!(json::from_json_like(&parsed, &value, &options).unwrap(), value);
+//! ```
+//!
+//! # Streaming
+//!
+//! `nbt_to_json_writer()` and `json_to_nbt_writer()` convert whole documents
+//! without holding either of them in memory, one tag at a time, which suits
+//! very large structure files. Writing NBT this way needs a seekable
+//! destination, since the length of a List comes before its elements but is
+//! only known after them.
+//!
+//! `de::Decoder` is a self-describing `serde::Deserializer`, so it can also be
+//! fed to any serializer with a crate such as `serde_transcode`. In the other
+//! direction, `NbtSink` is a `serde::de::DeserializeSeed` that writes NBT
+//! from any self-describing deserializer.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::result::Result as StdResult;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde_json::{self, Number, Value as Json};
+
+use error::{Error, Result};
+use map::Map;
+use raw::{Endianness, RawWriter};
+use stream::{NbtReader, Token};
+use value::Value;
+
+/// How JSON arrays are read back by `from_json()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ArrayPolicy {
+    /// Every JSON array becomes a List. This is the default.
+    #[default]
+    Lists,
+    /// Non-empty JSON arrays of integers become Int arrays, or Long arrays if
+    /// any element is out of the range of an Int. Other arrays become Lists.
+    IntArrays,
+}
+
+/// How to convert between `Value`s and JSON.
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    longs_as_strings: bool,
+    arrays: ArrayPolicy,
+}
+
+impl JsonOptions {
+    /// The default options, which write Longs as JSON integers and read
+    /// arrays as Lists.
+    pub fn new() -> JsonOptions {
+        JsonOptions::default()
+    }
+
+    /// Sets whether `to_json()` writes Longs, and the elements of Long
+    /// arrays, as decimal strings so that no precision is lost. They are only
+    /// read back as Longs by `from_json_like()`, where the template has a
+    /// Long. It is off by default.
+    pub fn longs_as_strings(mut self, strings: bool) -> JsonOptions {
+        self.longs_as_strings = strings;
+        self
+    }
+
+    /// Sets how `from_json()` reads JSON arrays. See `ArrayPolicy`.
+    pub fn arrays(mut self, policy: ArrayPolicy) -> JsonOptions {
+        self.arrays = policy;
+        self
+    }
+}
+
+/// Converts a `Value` to JSON, as described in the module documentation.
+pub fn to_json(value: &Value, options: &JsonOptions) -> Json {
+    let long = |v: i64| if options.longs_as_strings {
+        Json::String(v.to_string())
+    } else {
+        Json::from(v)
+    };
+    match *value {
+        Value::Byte(v) => Json::from(v),
+        Value::Short(v) => Json::from(v),
+        Value::Int(v) => Json::from(v),
+        Value::Long(v) => long(v),
+        // The shortest decimal that round-trips as an `f32`.
+        Value::Float(v) => float(v.to_string().parse().unwrap_or(f64::NAN)),
+        Value::Double(v) => float(v),
+        Value::ByteArray(ref vals) => Json::Array(vals.iter().map(|&v| Json::from(v)).collect()),
+        Value::String(ref val) => Json::String(val.clone()),
+        Value::List(ref vals) => Json::Array(vals.iter().map(|v| to_json(v, options)).collect()),
+        Value::Compound(ref map) => Json::Object(map.iter().map(|(k, v)| (k.clone(), to_json(v, options))).collect()),
+        Value::IntArray(ref vals) => Json::Array(vals.iter().map(|&v| Json::from(v)).collect()),
+        Value::LongArray(ref vals) => Json::Array(vals.iter().map(|&v| long(v)).collect()),
+    }
+}
+
+/// Converts JSON to a `Value`, as described in the module documentation.
+///
+/// Fails with `Error::UnrepresentableType` for `null`, and with
+/// `Error::HeterogeneousList` for arrays mixing, say, strings and numbers.
+pub fn from_json(json: &Json, options: &JsonOptions) -> Result<Value> {
+    convert(json, None, options)
+}
+
+/// Converts JSON to a `Value` like `from_json()`, but giving each value the
+/// type of the one at the same path in `template` where it fits, so that a
+/// `Value` exported with `to_json()`, edited as JSON, and read back with the
+/// original as the template keeps its types.
+///
+/// Values that do not fit the template's type, such as `300` where it has a
+/// Byte, or have no counterpart in it, are converted as by `from_json()`.
+/// Each element of a JSON array takes the type of the template List's element
+/// at the same index, or of its first element if the List is shorter.
+pub fn from_json_like(json: &Json, template: &Value, options: &JsonOptions) -> Result<Value> {
+    convert(json, Some(template), options)
+}
+
+impl<'a> From<&'a Value> for Json {
+    /// Converts with the default `JsonOptions`.
+    fn from(value: &'a Value) -> Json {
+        to_json(value, &JsonOptions::default())
+    }
+}
+
+impl From<Value> for Json {
+    /// Converts with the default `JsonOptions`.
+    fn from(value: Value) -> Json {
+        to_json(&value, &JsonOptions::default())
+    }
+}
+
+impl<'a> TryFrom<&'a Json> for Value {
+    type Error = Error;
+
+    /// Converts with the default `JsonOptions`.
+    fn try_from(json: &'a Json) -> Result<Value> {
+        from_json(json, &JsonOptions::default())
+    }
+}
+
+impl TryFrom<Json> for Value {
+    type Error = Error;
+
+    /// Converts with the default `JsonOptions`.
+    fn try_from(json: Json) -> Result<Value> {
+        from_json(&json, &JsonOptions::default())
+    }
+}
+
+fn float(v: f64) -> Json {
+    Number::from_f64(v).map_or(Json::Null, Json::Number)
+}
+
+fn convert(json: &Json, hint: Option<&Value>, options: &JsonOptions) -> Result<Value> {
+    match *json {
+        Json::Null => Err(Error::UnrepresentableType("null")),
+        Json::Bool(v) => Ok(Value::Byte(v as i8)),
+        Json::Number(ref n) => Ok(number(n, hint)),
+        Json::String(ref s) => Ok(match (hint, s.parse()) {
+            (Some(&Value::Long(_)), Ok(v)) => Value::Long(v),
+            _ => Value::String(s.clone()),
+        }),
+        Json::Array(ref items) => array(items, hint, options),
+        Json::Object(ref entries) => {
+            let hints = match hint {
+                Some(Value::Compound(hints)) => Some(hints),
+                _ => None,
+            };
+            let mut map = Map::new();
+            for (key, item) in entries {
+                let hint = hints.and_then(|hints| hints.get(key));
+                map.insert(key.clone(), convert(item, hint, options)?);
+            }
+            Ok(Value::Compound(map))
+        },
+    }
+}
+
+fn number(n: &Number, hint: Option<&Value>) -> Value {
+    let v = match n.as_i64() {
+        Some(v) => v,
+        None => {
+            let v = n.as_f64().unwrap_or(f64::NAN);
+            return match hint {
+                Some(&Value::Float(_)) => Value::Float(v as f32),
+                _ => Value::Double(v),
+            };
+        },
+    };
+    let hinted = match hint {
+        Some(&Value::Byte(_)) => i8::try_from(v).ok().map(Value::Byte),
+        Some(&Value::Short(_)) => i16::try_from(v).ok().map(Value::Short),
+        Some(&Value::Long(_)) => Some(Value::Long(v)),
+        Some(&Value::Float(_)) => Some(Value::Float(v as f32)),
+        Some(&Value::Double(_)) => Some(Value::Double(v as f64)),
+        _ => None,
+    };
+    hinted.unwrap_or_else(|| i32::try_from(v).map_or(Value::Long(v), Value::Int))
+}
+
+fn array(items: &[Json], hint: Option<&Value>, options: &JsonOptions) -> Result<Value> {
+    let ints = || items.iter().map(Json::as_i64).collect::<Option<Vec<_>>>();
+    let narrow = |ints: &[i64]| ints.iter().map(|&v| i32::try_from(v).ok()).collect::<Option<Vec<_>>>();
+    match hint {
+        Some(&Value::ByteArray(_)) => {
+            let bytes = ints().and_then(|ints| ints.into_iter().map(|v| i8::try_from(v).ok()).collect());
+            if let Some(bytes) = bytes {
+                return Ok(Value::ByteArray(bytes));
+            }
+        },
+        Some(&Value::IntArray(_)) => {
+            if let Some(ints) = ints().as_ref().and_then(|ints| narrow(ints)) {
+                return Ok(Value::IntArray(ints));
+            }
+        },
+        Some(&Value::LongArray(_)) => {
+            let longs = items.iter().map(|item| match *item {
+                Json::String(ref s) => s.parse().ok(),
+                ref item => item.as_i64(),
+            }).collect();
+            if let Some(longs) = longs {
+                return Ok(Value::LongArray(longs));
+            }
+        },
+        Some(&Value::List(_)) => (),
+        _ if options.arrays == ArrayPolicy::IntArrays && !items.is_empty() => {
+            if let Some(longs) = ints() {
+                return Ok(narrow(&longs).map_or(Value::LongArray(longs), Value::IntArray));
+            }
+        },
+        _ => (),
+    }
+
+    let hints = match hint {
+        Some(Value::List(hints)) => &hints[..],
+        _ => &[],
+    };
+    if hints.is_empty() && items.iter().all(Json::is_number) {
+        // Widen the numbers to a common type.
+        let vals = match ints() {
+            Some(longs) => match narrow(&longs) {
+                Some(ints) => ints.into_iter().map(Value::Int).collect(),
+                None => longs.into_iter().map(Value::Long).collect(),
+            },
+            None => items.iter().map(|item| Value::Double(item.as_f64().unwrap_or(f64::NAN))).collect(),
+        };
+        return Ok(Value::List(vals));
+    }
+    let vals = items.iter().enumerate()
+        .map(|(i, item)| convert(item, hints.get(i).or(hints.first()), options))
+        .collect::<Result<Vec<_>>>()?;
+    if vals.windows(2).any(|pair| pair[0].id() != pair[1].id()) {
+        return Err(Error::HeterogeneousList);
+    }
+    Ok(Value::List(vals))
+}
+
+/// Converts the NBT document in `src` to JSON text in `dst`, as `to_json()`
+/// would but without building either tree. Entries are written in the order
+/// they are read, and the root's name is dropped.
+///
+/// ```rust
+/// use nbt::json::{self, JsonOptions};
+/// use nbt::{Blob, Endianness};
+///
+/// let mut blob = Blob::new();
+/// blob.insert("Health", 20i16).unwrap();
+/// let bytes = blob.to_vec(Endianness::BigEndian).unwrap();
+/// let mut text = Vec::new();
+/// json::nbt_to_json_writer(&bytes[..], &mut text, Endianness::BigEndian, &JsonOptions::new()).unwrap();
+/// assert_eq!(text, br#"{"Health":20}"#);
+/// ```
+pub fn nbt_to_json_writer<R, W>(src: R, mut dst: W, endian: Endianness, options: &JsonOptions) -> Result<()>
+    where R: io::Read,
+          W: io::Write,
+{
+    let mut src = NbtReader::new(src, endian);
+    // Whether each open container is a Compound, and whether it has had any
+    // entries yet.
+    let mut stack: Vec<(bool, bool)> = Vec::new();
+    while let Some(token) = src.next_token()? {
+        if let Token::End = token {
+            let (compound, _) = stack.pop().ok_or(Error::InvalidNesting("unbalanced End"))?;
+            dst.write_all(if compound { b"}" } else { b"]" })?;
+            continue;
+        }
+        if let Some(&mut (compound, ref mut started)) = stack.last_mut() {
+            if *started {
+                dst.write_all(b",")?;
+            }
+            *started = true;
+            if compound {
+                write_json(&mut dst, token.name().unwrap_or(""))?;
+                dst.write_all(b":")?;
+            }
+        }
+        let value = match token {
+            Token::Byte(_, v) => Value::Byte(v),
+            Token::Short(_, v) => Value::Short(v),
+            Token::Int(_, v) => Value::Int(v),
+            Token::Long(_, v) => Value::Long(v),
+            Token::Float(_, v) => Value::Float(v),
+            Token::Double(_, v) => Value::Double(v),
+            Token::ByteArray(_, v) => Value::ByteArray(v),
+            Token::String(_, v) => Value::String(v),
+            Token::IntArray(_, v) => Value::IntArray(v),
+            Token::LongArray(_, v) => Value::LongArray(v),
+            Token::ListStart(..) => {
+                stack.push((false, false));
+                dst.write_all(b"[")?;
+                continue;
+            },
+            Token::CompoundStart(_) => {
+                stack.push((true, false));
+                dst.write_all(b"{")?;
+                continue;
+            },
+            Token::End => unreachable!(),
+        };
+        write_json(&mut dst, &to_json(&value, options))?;
+    }
+    dst.flush().map_err(From::from)
+}
+
+fn write_json<W, T>(dst: &mut W, value: &T) -> Result<()>
+    where W: io::Write,
+          T: ?Sized + serde::Serialize,
+{
+    serde_json::to_writer(dst, value).map_err(|e| Error::IoError(e.into()))
+}
+
+/// Converts the JSON text in `src`, which must hold an object, to an NBT
+/// document with an empty root name in `dst`, without building either tree.
+///
+/// Values are converted as by `from_json()` with `ArrayPolicy::Lists`,
+/// except that the numbers in a List are not widened, as the first element
+/// has already been written by the time a wider one is seen: a List such as
+/// `[1, 3000000000]` fails with `Error::HeterogeneousList`. Reading `src`
+/// one byte at a time, this is best given an `io::BufReader`.
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use nbt::{json, Blob, Endianness, Value};
+///
+/// let mut dst = Cursor::new(Vec::new());
+/// json::json_to_nbt_writer(&br#"{"Pos": [0.5, 64.0, 0.5]}"#[..], &mut dst, Endianness::BigEndian).unwrap();
+/// let blob = Blob::from_slice(dst.get_ref(), Endianness::BigEndian).unwrap();
+/// assert_eq!(blob["Pos"], Value::List(vec![Value::Double(0.5), Value::Double(64.0), Value::Double(0.5)]));
+/// ```
+pub fn json_to_nbt_writer<R, W>(src: R, dst: W, endian: Endianness) -> Result<()>
+    where R: io::Read,
+          W: io::Write + io::Seek,
+{
+    let mut sink = NbtSink::new(dst, endian);
+    let mut de = serde_json::Deserializer::from_reader(src);
+    if let Err(e) = (&mut sink).deserialize(&mut de).and_then(|()| de.end()) {
+        return Err(sink.error.take().unwrap_or_else(|| Error::Serde(e.to_string())));
+    }
+    sink.into_inner().flush().map_err(From::from)
+}
+
+/// A `serde::de::DeserializeSeed` that writes the map produced by any
+/// self-describing deserializer as an NBT document with an empty root name,
+/// without building a `Value` tree.
+///
+/// Values are converted as described for `json_to_nbt_writer()`. Other
+/// deserializers may also produce byte strings, which have no NBT
+/// counterpart. The destination must be seekable so that the length of each
+/// List can be filled in after its elements have been written.
+pub struct NbtSink<W: io::Write + io::Seek> {
+    dst: RawWriter<Tracked<W>>,
+    error: Option<Error>,
+}
+
+impl<W> NbtSink<W>
+    where W: io::Write + io::Seek,
+{
+    /// Creates a sink that writes to `dst`, starting at its current position.
+    pub fn new(dst: W, endian: Endianness) -> NbtSink<W> {
+        NbtSink {
+            dst: RawWriter::new(Tracked { inner: dst, pos: 0 }, endian),
+            error: None,
+        }
+    }
+
+    /// Unwraps this sink, returning the destination.
+    pub fn into_inner(self) -> W {
+        self.dst.into_inner().inner
+    }
+
+    /// Records `e` to be returned in place of the deserializer's error.
+    fn fail<E: de::Error>(&mut self, e: Error) -> E {
+        let err = E::custom(&e);
+        self.error = Some(e);
+        err
+    }
+
+    /// Overwrites the length of a List written with `write_fixed_len()` at
+    /// `at` bytes from the start.
+    fn patch_len(&mut self, at: u64, len: usize) -> Result<()> {
+        use std::io::SeekFrom;
+
+        let len = i32::try_from(len).map_err(|_| Error::LengthLimitExceeded(i32::MAX as usize))?;
+        let end = self.dst.get_ref().pos;
+        self.dst.get_mut().inner.seek(SeekFrom::Current(at as i64 - end as i64))?;
+        self.dst.get_mut().pos = at;
+        self.dst.write_fixed_len(len)?;
+        let dst = self.dst.get_mut();
+        dst.inner.seek(SeekFrom::Current(end as i64 - dst.pos as i64))?;
+        dst.pos = end;
+        Ok(())
+    }
+}
+
+impl<'de, W> DeserializeSeed<'de> for &mut NbtSink<W>
+    where W: io::Write + io::Seek,
+{
+    type Value = ();
+
+    fn deserialize<D>(self, src: D) -> StdResult<(), D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        src.deserialize_any(Payload { sink: self, prefix: Prefix::Root }).map(|_| ())
+    }
+}
+
+/// A writer that counts the bytes written to it, so that `NbtSink` can seek
+/// back to the lengths of Lists.
+struct Tracked<W> {
+    inner: W,
+    pos: u64,
+}
+
+impl<W: io::Write> io::Write for Tracked<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// What has to be written before a payload, which depends on its type.
+enum Prefix {
+    /// The root Compound's header.
+    Root,
+    /// A header with the name of a Compound entry.
+    Header(String),
+    /// The type ID and length of the List that this payload starts.
+    First,
+    /// Nothing, but the payload must have the type of the List's first.
+    Next(i8),
+}
+
+/// A `Visitor` writing a single payload, which produces its type ID.
+struct Payload<'a, W: io::Write + io::Seek> {
+    sink: &'a mut NbtSink<W>,
+    prefix: Prefix,
+}
+
+impl<'a, W> Payload<'a, W>
+    where W: io::Write + io::Seek,
+{
+    /// Writes what comes before a payload of type `id`, and then the payload
+    /// itself with `write`.
+    fn write<E, F>(self, id: i8, write: F) -> StdResult<i8, E>
+        where E: de::Error,
+              F: FnOnce(&mut RawWriter<Tracked<W>>) -> Result<()>,
+    {
+        let dst = &mut self.sink.dst;
+        let result = match self.prefix {
+            Prefix::Root if id != 0x0a => Err(Error::NoRootCompound),
+            Prefix::Root => dst.write_header(id, ""),
+            Prefix::Header(ref name) => dst.write_header(id, name),
+            Prefix::First => dst.write_bare_byte(id).and_then(|()| dst.write_fixed_len(0)),
+            Prefix::Next(first) if id != first => Err(Error::HeterogeneousList),
+            Prefix::Next(_) => Ok(()),
+        };
+        match result.and_then(|()| write(dst)) {
+            Ok(()) => Ok(id),
+            Err(e) => Err(self.sink.fail(e)),
+        }
+    }
+}
+
+impl<'de, 'a, W> Visitor<'de> for Payload<'a, W>
+    where W: io::Write + io::Seek,
+{
+    type Value = i8;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value with an NBT representation")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> StdResult<i8, E> {
+        self.write(0x01, |dst| dst.write_bare_byte(v as i8))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> StdResult<i8, E> {
+        match i32::try_from(v) {
+            Ok(v) => self.write(0x03, |dst| dst.write_bare_int(v)),
+            Err(_) => self.write(0x04, |dst| dst.write_bare_long(v)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> StdResult<i8, E> {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) => self.visit_f64(v as f64),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> StdResult<i8, E> {
+        self.write(0x06, |dst| dst.write_bare_double(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> StdResult<i8, E> {
+        self.write(0x08, |dst| dst.write_bare_string(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> StdResult<i8, E> {
+        Err(self.sink.fail(Error::UnrepresentableType("null")))
+    }
+
+    fn visit_none<E: de::Error>(self) -> StdResult<i8, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D>(self, src: D) -> StdResult<i8, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        src.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<i8, A::Error>
+        where A: SeqAccess<'de>,
+    {
+        let sink = self.sink;
+        Payload { sink: &mut *sink, prefix: self.prefix }.write(0x09, |_| Ok(()))?;
+        let start = sink.dst.get_ref().pos;
+        let mut first = None;
+        let mut len = 0;
+        loop {
+            let prefix = first.map_or(Prefix::First, Prefix::Next);
+            match seq.next_element_seed(Payload { sink: &mut *sink, prefix })? {
+                Some(id) => first = first.or(Some(id)),
+                None => break,
+            }
+            len += 1;
+        }
+        let result = match first {
+            // An empty List of TAG_End.
+            None => sink.dst.write_bare_byte(0x00).and_then(|()| sink.dst.write_bare_int(0)),
+            // The length follows the element type.
+            Some(_) => sink.patch_len(start + 1, len),
+        };
+        result.map(|()| 0x09).map_err(|e| sink.fail(e))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> StdResult<i8, A::Error>
+        where A: MapAccess<'de>,
+    {
+        let sink = self.sink;
+        Payload { sink: &mut *sink, prefix: self.prefix }.write(0x0a, |_| Ok(()))?;
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value_seed(Payload { sink: &mut *sink, prefix: Prefix::Header(key) })?;
+        }
+        // The TAG_End, written directly so that the writer has no open
+        // Compounds to complain about if a later error leaves this one open.
+        sink.dst.write_bare_byte(0x00).map(|()| 0x0a).map_err(|e| sink.fail(e))
+    }
+}
+
+impl<'de, 'a, W> DeserializeSeed<'de> for Payload<'a, W>
+    where W: io::Write + io::Seek,
+{
+    type Value = i8;
+
+    fn deserialize<D>(self, src: D) -> StdResult<i8, D::Error>
+        where D: de::Deserializer<'de>,
+    {
+        src.deserialize_any(self)
+    }
+}
@@ -1,31 +1,121 @@
 //! MC Named Binary Tag type.
+//!
+//! Without the default `std` feature the crate is `no_std`, needing only
+//! `alloc`: the `raw` codec and the `Value` tree remain, reading and writing
+//! through the traits in `io`, while `Blob`, region files, compression and
+//! Serde support need `std`.
 
-extern crate byteorder;
-extern crate cesu8;
-extern crate flate2;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))] #[macro_use] extern crate alloc;
+#[cfg(feature = "std")] extern crate byteorder;
+#[cfg(feature = "std")] extern crate cesu8;
+#[cfg(all(feature = "compression", not(feature = "rust_backend")))] extern crate flate2;
+#[cfg(feature = "preserve_order")] extern crate indexmap;
+#[cfg(all(feature = "memmap", unix))] extern crate libc;
 
 /* Re-export the core API from submodules. */
-pub use blob::Blob;
+#[cfg(feature = "std")]
+pub use blob::{Blob, BlobReader, Blobs};
 pub use error::{Error, Result};
-pub use value::Value;
-pub use raw::Endianness;
+pub use map::{Entry, Map};
+pub use value::{FromValue, Value, ValueIndex};
+#[cfg(feature = "std")]
+pub use value_ref::{ArenaIter, ArenaRef, ArrayRef, Element, ValueArena, ValueRef};
+pub use path::{Matches, Selector, VisitAction, Walk};
+pub use patch::{diff, Change, NbtPatch};
+pub use merge::MergeStrategy;
+pub use printer::{Printed, Printer};
+#[cfg(feature = "std")]
+pub use options::{Compression, CompressionLevel, ReadOptions, WriteOptions};
+pub use raw::{Endianness, StringEncoding};
+#[cfg(feature = "std")]
+pub use mapping::{FromNbt, NbtArray, NbtTagged, ToNbt};
+#[cfg(feature = "std")]
+pub use scan::{extract, transcode, validate, NbtStats};
+#[cfg(feature = "std")]
+pub use stream::{NbtReader, NbtWriter, Token};
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub use nbt_derive::{FromNbt, NbtCompound, ToNbt};
 
 #[cfg(feature = "serde")]
 #[doc(inline)]
-pub use de::{from_reader, from_gzip_reader, from_zlib_reader};
+pub use de::{from_reader, from_slice, from_value};
+#[cfg(all(feature = "serde", any(feature = "compression", feature = "rust_backend")))]
+#[doc(inline)]
+pub use de::{from_gzip_reader, from_zlib_reader};
 #[cfg(feature = "serde")]
 #[doc(inline)]
-pub use ser::{to_writer, to_gzip_writer, to_zlib_writer};
+pub use ser::{to_value, to_vec, to_writer};
+#[cfg(all(feature = "serde", any(feature = "compression", feature = "rust_backend")))]
+#[doc(inline)]
+pub use ser::{to_gzip_writer, to_zlib_writer};
+#[cfg(feature = "serde")]
+pub use named::Named;
+#[cfg(feature = "serde")]
+pub use arrays::{byte_array, int_array, long_array};
+
+/// Stands in for the standard library without the `std` feature, so that
+/// the modules shared with `no_std` builds can keep importing from `std`.
+#[cfg(not(feature = "std"))]
+mod std {
+    pub use core::*;
+    pub use alloc::{borrow, collections, fmt, rc, sync, vec};
+    pub use io;
 
-mod raw;
-mod blob;
+    /// The parts of the standard prelude that `core`'s lacks.
+    pub mod prelude {
+        pub use alloc::boxed::Box;
+        pub use alloc::string::{String, ToString};
+        pub use alloc::vec::Vec;
+    }
+}
+
+#[macro_use] mod literal;
+pub mod io;
+pub mod raw;
+#[cfg(feature = "std")] mod blob;
 mod error;
+mod map;
+mod mutf8;
 mod value;
+#[cfg(feature = "std")] mod value_ref;
+mod path;
+mod patch;
+mod merge;
+mod canonical;
+mod printer;
+#[cfg(feature = "std")] mod options;
+#[cfg(any(feature = "compression", feature = "rust_backend"))] mod flate;
+#[cfg(feature = "rust_backend")] mod deflate;
+#[cfg(feature = "experimental_lz4")] mod lz4;
+#[cfg(feature = "experimental_zstd")] mod zstd;
+mod snbt;
+#[cfg(feature = "std")] pub mod mapping;
+#[cfg(feature = "codegen")] pub mod codegen;
+#[cfg(all(feature = "memmap", unix))] pub mod mmap;
+#[cfg(feature = "std")] pub mod region;
+#[cfg(feature = "std")] mod scan;
+pub mod schema;
+pub mod packed;
+pub mod uuid;
+#[cfg(feature = "std")] pub mod stream;
+
+#[cfg(feature = "derive")] extern crate nbt_derive;
+#[cfg(feature = "rayon")] extern crate rayon;
 
 #[cfg(feature = "serde")] #[macro_use] extern crate serde;
+#[cfg(feature = "json")] extern crate serde_json;
 
 #[cfg(feature = "serde")] #[macro_use] mod macros;
+#[cfg(feature = "serde")] mod arrays;
+#[cfg(feature = "serde")] pub mod byte_bool;
+#[cfg(feature = "serde")] mod named;
 #[cfg(feature = "serde")] pub mod de;
 #[cfg(feature = "serde")] pub mod ser;
+#[cfg(feature = "json")] pub mod json;
+#[cfg(feature = "formats")] pub mod formats;
 
-#[cfg(test)] mod tests;
+#[cfg(all(test, feature = "std"))] mod tests;
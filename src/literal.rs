@@ -0,0 +1,138 @@
+//! The `nbt!` and `blob!` macros for writing NBT values inline.
+
+/// Construct a `Value` from a literal, in the manner of `serde_json::json!`.
+///
+/// Compounds are written as `{ key: value, ... }`, where each key is a string
+/// literal or a bare identifier, and Lists as `[value, ...]`. The specialized
+/// array tags use the SNBT prefixes `[B; ...]`, `[I; ...]` and `[L; ...]`.
+/// Any other expression is converted with `Value::from`, so numbers need a
+/// suffix (or a typed variable) to pick their tag:
+///
+/// ```
+/// #[macro_use] extern crate nbt;
+/// use nbt::Value;
+///
+/// # fn main() {
+/// let name = "Steve";
+/// let player = nbt!({
+///     "Health": 20.0f32,
+///     "Tags": ["a", "b"],
+///     UUID: [I; 1, 2, 3, 4],
+///     Inventory: [
+///         { id: "minecraft:stone", Count: 64i8 },
+///     ],
+///     Name: name,
+/// });
+///
+/// match player {
+///     Value::Compound(ref map) => {
+///         assert_eq!(map["Health"], Value::Float(20.0));
+///         assert_eq!(map["UUID"], Value::IntArray(vec![1, 2, 3, 4]));
+///         assert_eq!(map["Name"], Value::String("Steve".to_string()));
+///     }
+///     _ => unreachable!(),
+/// }
+/// # }
+/// ```
+///
+/// The structure is checked when compiling, but the tags of List elements are
+/// not: writing a heterogeneous List fails with `Error::HeterogeneousList`.
+#[macro_export]
+macro_rules! nbt {
+    // Lists: munch the tokens of each element up to the next comma.
+    (@list [$($out:expr),*] ()) => {
+        vec![$($out),*]
+    };
+    (@list [$($out:expr),*] ($($elem:tt)+) , $($rest:tt)*) => {
+        $crate::nbt!(@list [$($out,)* $crate::nbt!($($elem)+)] () $($rest)*)
+    };
+    (@list [$($out:expr),*] ($($elem:tt)+)) => {
+        $crate::nbt!(@list [$($out,)* $crate::nbt!($($elem)+)] ())
+    };
+    (@list [$($out:expr),*] ($($elem:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@list [$($out),*] ($($elem)* $next) $($rest)*)
+    };
+
+    // Compounds: likewise for the value of each entry.
+    (@compound $map:ident) => {};
+    (@compound $map:ident $key:tt : $($rest:tt)*) => {
+        $crate::nbt!(@entry $map ($key) () $($rest)*);
+    };
+    (@entry $map:ident ($key:tt) ($($value:tt)+) , $($rest:tt)*) => {
+        $map.insert($crate::nbt!(@key $key), $crate::nbt!($($value)+));
+        $crate::nbt!(@compound $map $($rest)*);
+    };
+    (@entry $map:ident ($key:tt) ($($value:tt)+)) => {
+        $map.insert($crate::nbt!(@key $key), $crate::nbt!($($value)+));
+    };
+    (@entry $map:ident ($key:tt) ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::nbt!(@entry $map ($key) ($($value)* $next) $($rest)*);
+    };
+    (@key $key:ident) => {
+        ::std::string::String::from(stringify!($key))
+    };
+    (@key $key:expr) => {
+        ::std::string::String::from($key)
+    };
+
+    ([B; $($elem:expr),* $(,)*]) => {
+        $crate::Value::ByteArray(vec![$($elem),*])
+    };
+    ([I; $($elem:expr),* $(,)*]) => {
+        $crate::Value::IntArray(vec![$($elem),*])
+    };
+    ([L; $($elem:expr),* $(,)*]) => {
+        $crate::Value::LongArray(vec![$($elem),*])
+    };
+    ([$($tt:tt)*]) => {
+        $crate::Value::List($crate::nbt!(@list [] () $($tt)*))
+    };
+    ({$($tt:tt)*}) => {
+        $crate::Value::Compound({
+            let mut map = $crate::Map::new();
+            $crate::nbt!(@compound map $($tt)*);
+            map
+        })
+    };
+    ($value:expr) => {
+        $crate::Value::from($value)
+    };
+}
+
+/// Construct a `Blob` from a Compound literal, with an optional root name.
+///
+/// The entries are written as in `nbt!`:
+///
+/// ```
+/// #[macro_use] extern crate nbt;
+///
+/// # fn main() {
+/// # #[cfg(feature = "std")] {
+/// let level = blob!("Data", {
+///     LevelName: "world",
+///     GameType: 1i32,
+/// });
+/// assert_eq!(level["LevelName"], nbt::Value::String("world".to_string()));
+/// # }
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if one of the entries is a heterogeneous List, which `Blob::insert`
+/// would reject.
+#[macro_export]
+macro_rules! blob {
+    ({$($tt:tt)*}) => {
+        $crate::blob!("", {$($tt)*})
+    };
+    ($name:expr, {$($tt:tt)*}) => {{
+        let mut blob = $crate::Blob::named($name);
+        if let $crate::Value::Compound(map) = $crate::nbt!({$($tt)*}) {
+            for (key, value) in map {
+                blob.insert(key, value).expect("heterogeneous List in blob! literal");
+            }
+        }
+        blob
+    }};
+}
@@ -0,0 +1,363 @@
+//! LZ4 compression in the block stream format written by lz4-java's
+//! `LZ4BlockOutputStream`, which Minecraft uses for region file chunks with
+//! compression scheme 4.
+//!
+//! A stream is a sequence of blocks, each holding up to 64 KiB of data and
+//! starting with a 21-byte header: the magic `LZ4Block`, a token with the
+//! method and block size, then the compressed length, the original length
+//! and a checksum of the original data, all little-endian. The stream ends
+//! with an empty block.
+
+use std::cmp;
+use std::io::{self, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+const MAGIC: &[u8; 8] = b"LZ4Block";
+
+const METHOD_RAW: u8 = 0x10;
+const METHOD_LZ4: u8 = 0x20;
+
+/// Block sizes are stored as a power of two above this.
+const LEVEL_BASE: u8 = 10;
+/// The block size written by `Lz4Encoder`, and lz4-java's default.
+const BLOCK_LEVEL: u8 = 16;
+const BLOCK_SIZE: usize = 1 << BLOCK_LEVEL;
+
+/// The seed of the block checksums, which keep only the lower 28 bits.
+const CHECKSUM_SEED: u32 = 0x9747_b28c;
+const CHECKSUM_MASK: u32 = 0x0fff_ffff;
+
+const MIN_MATCH: usize = 4;
+/// A match must start at least this many bytes before the end of a block.
+const MF_LIMIT: usize = 12;
+/// The last bytes of a block are always literals.
+const LAST_LITERALS: usize = 5;
+const HASH_BITS: u32 = 12;
+const MAX_OFFSET: usize = 65535;
+
+/// Decompresses an LZ4 block stream from an `io::Read` source.
+pub(crate) struct Lz4Decoder<R> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl<R: Read> Lz4Decoder<R> {
+    pub(crate) fn new(inner: R) -> Lz4Decoder<R> {
+        Lz4Decoder { inner, block: Vec::new(), pos: 0, finished: false }
+    }
+
+    /// Reads the next block into `self.block`, or marks the stream as
+    /// finished at the empty block.
+    fn read_block(&mut self) -> io::Result<()> {
+        let mut magic = [0; 8];
+        self.inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("invalid LZ4 block magic"));
+        }
+        let token = self.inner.read_u8()?;
+        let block_size = 1usize << (LEVEL_BASE + (token & 0x0f));
+        let compressed_len = self.inner.read_i32::<LittleEndian>()?;
+        let original_len = self.inner.read_i32::<LittleEndian>()?;
+        let checksum = self.inner.read_u32::<LittleEndian>()?;
+        if original_len < 0 || compressed_len < 0 || original_len as usize > block_size {
+            return Err(invalid("invalid LZ4 block length"));
+        }
+        let (compressed_len, original_len) = (compressed_len as usize, original_len as usize);
+
+        self.block.clear();
+        self.pos = 0;
+        if original_len == 0 {
+            if compressed_len != 0 || checksum != 0 {
+                return Err(invalid("invalid LZ4 end block"));
+            }
+            self.finished = true;
+            return Ok(());
+        }
+        match token & 0xf0 {
+            METHOD_RAW if compressed_len == original_len => {
+                read_exactly(&mut self.inner, original_len, &mut self.block)?;
+            },
+            METHOD_LZ4 if compressed_len <= max_compressed_len(original_len) => {
+                let mut data = Vec::with_capacity(compressed_len);
+                read_exactly(&mut self.inner, compressed_len, &mut data)?;
+                decompress_block(&data, &mut self.block, original_len)?;
+            },
+            METHOD_RAW | METHOD_LZ4 => return Err(invalid("invalid LZ4 block length")),
+            _ => return Err(invalid("unknown LZ4 block method")),
+        }
+        if xxh32(&self.block, CHECKSUM_SEED) & CHECKSUM_MASK != checksum {
+            return Err(invalid("LZ4 block checksum mismatch"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Lz4Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.block.len() {
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.read_block()?;
+        }
+        let n = cmp::min(buf.len(), self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Compresses data written to it as an LZ4 block stream. `finish()` must be
+/// called to write the final block.
+pub(crate) struct Lz4Encoder<W> {
+    inner: W,
+    block: Vec<u8>,
+}
+
+impl<W: Write> Lz4Encoder<W> {
+    pub(crate) fn new(inner: W) -> Lz4Encoder<W> {
+        Lz4Encoder { inner, block: Vec::with_capacity(BLOCK_SIZE) }
+    }
+
+    /// Writes any buffered data and the empty block that ends the stream,
+    /// returning the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.write_block()?;
+        self.write_header(METHOD_RAW, 0, 0, 0)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let checksum = xxh32(&self.block, CHECKSUM_SEED) & CHECKSUM_MASK;
+        let mut data = Vec::new();
+        compress_block(&self.block, &mut data);
+        if data.len() < self.block.len() {
+            self.write_header(METHOD_LZ4, data.len(), self.block.len(), checksum)?;
+            self.inner.write_all(&data)?;
+        } else {
+            let len = self.block.len();
+            self.write_header(METHOD_RAW, len, len, checksum)?;
+            self.inner.write_all(&self.block)?;
+        }
+        self.block.clear();
+        Ok(())
+    }
+
+    fn write_header(&mut self, method: u8, compressed_len: usize, original_len: usize,
+                    checksum: u32) -> io::Result<()>
+    {
+        self.inner.write_all(MAGIC)?;
+        self.inner.write_u8(method | (BLOCK_LEVEL - LEVEL_BASE))?;
+        self.inner.write_i32::<LittleEndian>(compressed_len as i32)?;
+        self.inner.write_i32::<LittleEndian>(original_len as i32)?;
+        self.inner.write_u32::<LittleEndian>(checksum)
+    }
+}
+
+impl<W: Write> Write for Lz4Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+        if self.block.len() == BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads exactly `len` bytes, without trusting `len` for the allocation.
+fn read_exactly<R: Read>(src: &mut R, len: usize, dst: &mut Vec<u8>) -> io::Result<()> {
+    if src.take(len as u64).read_to_end(dst)? != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// The largest compressed form of `len` bytes, should they not compress.
+fn max_compressed_len(len: usize) -> usize {
+    len + len / 255 + 16
+}
+
+/// Decompresses one LZ4 block, which must expand to exactly `len` bytes.
+fn decompress_block(src: &[u8], dst: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    let corrupt = || invalid("corrupt LZ4 block");
+    let mut i = 0;
+    loop {
+        let token = *src.get(i).ok_or_else(corrupt)?;
+        i += 1;
+
+        let literals = read_length(src, &mut i, (token >> 4) as usize).ok_or_else(corrupt)?;
+        let end = i.checked_add(literals).ok_or_else(corrupt)?;
+        if dst.len() + literals > len {
+            return Err(corrupt());
+        }
+        dst.extend_from_slice(src.get(i..end).ok_or_else(corrupt)?);
+        i = end;
+        if i == src.len() {
+            break;
+        }
+
+        let offset = match src.get(i..i + 2) {
+            Some(bytes) => bytes[0] as usize | (bytes[1] as usize) << 8,
+            None => return Err(corrupt()),
+        };
+        i += 2;
+        let matched = read_length(src, &mut i, (token & 0x0f) as usize).ok_or_else(corrupt)?
+            + MIN_MATCH;
+        if offset == 0 || offset > dst.len() || dst.len() + matched > len {
+            return Err(corrupt());
+        }
+        // Matches may overlap the bytes they produce, so copy one at a time.
+        let start = dst.len() - offset;
+        for k in 0..matched {
+            let byte = dst[start + k];
+            dst.push(byte);
+        }
+    }
+    if dst.len() != len {
+        return Err(corrupt());
+    }
+    Ok(())
+}
+
+/// Reads the rest of a literal or match length whose token nibble is `len`.
+fn read_length(src: &[u8], i: &mut usize, mut len: usize) -> Option<usize> {
+    if len == 15 {
+        loop {
+            let byte = *src.get(*i)?;
+            *i += 1;
+            len = len.checked_add(byte as usize)?;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Some(len)
+}
+
+/// Compresses `src` as one LZ4 block, finding matches with a small hash
+/// table of the last position of each 4-byte sequence.
+fn compress_block(src: &[u8], dst: &mut Vec<u8>) {
+    let mut table = vec![0usize; 1 << HASH_BITS];
+    let mut anchor = 0;
+    let mut i = 0;
+    while i + MF_LIMIT <= src.len() {
+        let seq = read_u32(src, i);
+        let hash = (seq.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize;
+        // Positions are stored plus one, so that zero means none.
+        let candidate = table[hash];
+        table[hash] = i + 1;
+        if candidate != 0 && i - (candidate - 1) <= MAX_OFFSET
+            && read_u32(src, candidate - 1) == seq
+        {
+            let candidate = candidate - 1;
+            let max = src.len() - LAST_LITERALS - i;
+            let mut matched = MIN_MATCH;
+            while matched < max && src[candidate + matched] == src[i + matched] {
+                matched += 1;
+            }
+            write_sequence(dst, &src[anchor..i], Some((i - candidate, matched)));
+            i += matched;
+            anchor = i;
+        } else {
+            i += 1;
+        }
+    }
+    write_sequence(dst, &src[anchor..], None);
+}
+
+fn write_sequence(dst: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let match_len = matched.map_or(0, |(_, len)| len - MIN_MATCH);
+    dst.push((cmp::min(literals.len(), 15) << 4 | cmp::min(match_len, 15)) as u8);
+    if literals.len() >= 15 {
+        write_length(dst, literals.len() - 15);
+    }
+    dst.extend_from_slice(literals);
+    if let Some((offset, _)) = matched {
+        dst.push(offset as u8);
+        dst.push((offset >> 8) as u8);
+        if match_len >= 15 {
+            write_length(dst, match_len - 15);
+        }
+    }
+}
+
+fn write_length(dst: &mut Vec<u8>, mut len: usize) {
+    while len >= 255 {
+        dst.push(255);
+        len -= 255;
+    }
+    dst.push(len as u8);
+}
+
+fn read_u32(src: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([src[i], src[i + 1], src[i + 2], src[i + 3]])
+}
+
+const PRIME_1: u32 = 2_654_435_761;
+const PRIME_2: u32 = 2_246_822_519;
+const PRIME_3: u32 = 3_266_489_917;
+const PRIME_4: u32 = 668_265_263;
+const PRIME_5: u32 = 374_761_393;
+
+/// The 32-bit xxHash of `data`.
+fn xxh32(data: &[u8], seed: u32) -> u32 {
+    fn round(acc: u32, input: u32) -> u32 {
+        acc.wrapping_add(input.wrapping_mul(PRIME_2)).rotate_left(13).wrapping_mul(PRIME_1)
+    }
+
+    let mut i = 0;
+    let mut hash = if data.len() >= 16 {
+        let mut lanes = [
+            seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+            seed.wrapping_add(PRIME_2),
+            seed,
+            seed.wrapping_sub(PRIME_1),
+        ];
+        while i + 16 <= data.len() {
+            for lane in lanes.iter_mut() {
+                *lane = round(*lane, read_u32(data, i));
+                i += 4;
+            }
+        }
+        lanes[0].rotate_left(1)
+            .wrapping_add(lanes[1].rotate_left(7))
+            .wrapping_add(lanes[2].rotate_left(12))
+            .wrapping_add(lanes[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(PRIME_5)
+    };
+
+    hash = hash.wrapping_add(data.len() as u32);
+    while i + 4 <= data.len() {
+        hash = hash.wrapping_add(read_u32(data, i).wrapping_mul(PRIME_3))
+            .rotate_left(17).wrapping_mul(PRIME_4);
+        i += 4;
+    }
+    for &byte in &data[i..] {
+        hash = hash.wrapping_add((byte as u32).wrapping_mul(PRIME_5))
+            .rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 16)
+}
@@ -0,0 +1,170 @@
+//! The map type backing Compounds.
+//!
+//! `Map` holds the entries of a `Value::Compound` or a `Blob`, and `Entry`,
+//! as returned by `Blob::entry()` and `Value::entry()`, is a view into one of
+//! them, which is either vacant or occupied.
+//!
+//! `Map` is a `HashMap` by default. With the `preserve_order` feature it is
+//! an `IndexMap` instead, which keeps entries in the order they were read or
+//! inserted, so that rewriting a file does not shuffle its keys. Equality
+//! ignores the order either way. Without the `std` feature it is a
+//! `BTreeMap`, as `alloc` has no hash maps.
+
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+use std::collections::{hash_map, HashMap};
+#[cfg(not(feature = "std"))]
+use std::collections::{btree_map, BTreeMap};
+use std::mem;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::{map as index_map, IndexMap};
+
+use value::Value;
+
+/// Whether iterating a `Map` visits entries in an arbitrary order, which
+/// can change between runs, so that output meant to be stable must sort
+/// them. Only the `HashMap` backing does.
+pub(crate) const ARBITRARY_ORDER: bool = cfg!(all(feature = "std", not(feature = "preserve_order")));
+
+/// The entries of a `Value::Compound` or a `Blob`, in a `HashMap`.
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+pub type Map = HashMap<String, Value>;
+
+/// The entries of a `Value::Compound` or a `Blob`, in a `BTreeMap`.
+#[cfg(not(feature = "std"))]
+pub type Map = BTreeMap<String, Value>;
+
+/// The entries of a `Value::Compound` or a `Blob`, in an `IndexMap`.
+#[cfg(feature = "preserve_order")]
+pub type Map = IndexMap<String, Value>;
+
+/// One entry of a `Map`, as a `hash_map::Entry`.
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+pub type Entry<'a> = hash_map::Entry<'a, String, Value>;
+
+/// One entry of a `Map`, as a `btree_map::Entry`.
+#[cfg(not(feature = "std"))]
+pub type Entry<'a> = btree_map::Entry<'a, String, Value>;
+
+/// One entry of a `Map`, as a `index_map::Entry`.
+#[cfg(feature = "preserve_order")]
+pub type Entry<'a> = index_map::Entry<'a, String, Value>;
+
+// The iterators over a `Map`, named the same whichever type it is.
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+mod iter {
+    use super::{hash_map, Value};
+
+    pub type Iter<'a> = hash_map::Iter<'a, String, Value>;
+    pub type IterMut<'a> = hash_map::IterMut<'a, String, Value>;
+    pub type IntoIter = hash_map::IntoIter<String, Value>;
+    pub type Keys<'a> = hash_map::Keys<'a, String, Value>;
+    pub type Values<'a> = hash_map::Values<'a, String, Value>;
+    pub type ValuesMut<'a> = hash_map::ValuesMut<'a, String, Value>;
+}
+
+#[cfg(feature = "preserve_order")]
+mod iter {
+    use super::{index_map, Value};
+
+    pub type Iter<'a> = index_map::Iter<'a, String, Value>;
+    pub type IterMut<'a> = index_map::IterMut<'a, String, Value>;
+    pub type IntoIter = index_map::IntoIter<String, Value>;
+    pub type Keys<'a> = index_map::Keys<'a, String, Value>;
+    pub type Values<'a> = index_map::Values<'a, String, Value>;
+    pub type ValuesMut<'a> = index_map::ValuesMut<'a, String, Value>;
+}
+
+#[cfg(feature = "std")]
+pub(crate) use self::iter::*;
+
+#[cfg(not(feature = "std"))]
+pub(crate) type Iter<'a> = btree_map::Iter<'a, String, Value>;
+
+/// Creates a `Map` with room for `len` entries.
+#[cfg(feature = "std")]
+pub(crate) fn with_capacity(len: usize) -> Map {
+    Map::with_capacity(len)
+}
+
+/// Creates a `Map`; B-trees allocate as they grow.
+#[cfg(not(feature = "std"))]
+pub(crate) fn with_capacity(_len: usize) -> Map {
+    Map::new()
+}
+
+/// Removes an entry, keeping the order of the others.
+#[cfg(not(feature = "preserve_order"))]
+pub(crate) fn remove(map: &mut Map, key: &str) -> Option<Value> {
+    map.remove(key)
+}
+
+/// Removes an entry, keeping the order of the others.
+#[cfg(feature = "preserve_order")]
+pub(crate) fn remove(map: &mut Map, key: &str) -> Option<Value> {
+    map.shift_remove(key)
+}
+
+// `heap_size()` estimates the heap memory used by the table of a `Map` and
+// its keys, but not by the values in it, from the layout of each backing.
+
+/// `hashbrown`, which backs `HashMap`, has a control byte for each bucket.
+#[cfg(all(feature = "std", not(feature = "preserve_order")))]
+pub(crate) fn heap_size(map: &Map) -> usize {
+    keys_size(map) + hash_table_size(map.capacity(), mem::size_of::<(String, Value)>())
+}
+
+/// `IndexMap` keeps entries and hashes in a `Vec`, indexed by a hash table.
+#[cfg(feature = "preserve_order")]
+pub(crate) fn heap_size(map: &Map) -> usize {
+    let entries = map.capacity() * (mem::size_of::<usize>() + mem::size_of::<(String, Value)>());
+    keys_size(map) + entries + hash_table_size(map.capacity(), mem::size_of::<usize>())
+}
+
+/// `BTreeMap` is assumed to have full nodes, of 11 entries each.
+#[cfg(not(feature = "std"))]
+pub(crate) fn heap_size(map: &Map) -> usize {
+    const NODE_ENTRIES: usize = 11;
+    let node = NODE_ENTRIES * mem::size_of::<(String, Value)>() + 2 * mem::size_of::<usize>();
+    keys_size(map) + map.len().div_ceil(NODE_ENTRIES) * node
+}
+
+fn keys_size(map: &Map) -> usize {
+    map.keys().map(|key| key.capacity()).sum()
+}
+
+/// The size of a `hashbrown` table able to hold `capacity` entries of
+/// `size` bytes: a power of two number of buckets, at most 7/8 full, each
+/// with a control byte, and a group of 16 padding control bytes.
+#[cfg(feature = "std")]
+fn hash_table_size(capacity: usize, size: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = if capacity < 8 {
+        if capacity < 4 { 4 } else { 8 }
+    } else {
+        (capacity * 8 / 7).next_power_of_two()
+    };
+    buckets * (size + 1) + 16
+}
+
+/// Keeps the entries of every Compound in or nested in the values on
+/// `stack` for which `keep` returns true. Removed entries are not descended
+/// into.
+pub(crate) fn retain_recursive<F>(mut stack: Vec<&mut Value>, keep: &mut F)
+    where F: FnMut(&str, &Value) -> bool,
+{
+    while let Some(value) = stack.pop() {
+        match *value {
+            Value::Compound(ref mut map) => {
+                map.retain(|k, v| keep(k, v));
+                stack.extend(map.values_mut());
+            },
+            Value::List(ref mut vals) => stack.extend(vals.iter_mut()),
+            _ => (),
+        }
+    }
+}
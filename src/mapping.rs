@@ -0,0 +1,526 @@
+//! Direct mapping between Rust types and NBT values, without `serde`.
+//!
+//! The `ToNbt` and `FromNbt` traits can be implemented by hand, or derived for
+//! structs with named fields using the `derive` feature, which maps each
+//! struct to a Compound:
+//!
+//! ```rust,ignore
+//! #[macro_use] extern crate nbt;
+//!
+//! #[derive(ToNbt, FromNbt)]
+//! struct Player {
+//!     #[nbt(rename = "Health")]
+//!     health: f32,
+//!     #[nbt(tag = "int")]
+//!     level: i16,
+//!     #[nbt(array)]
+//!     uuid: Vec<i32>,
+//!     #[nbt(default)]
+//!     tags: Vec<String>,
+//!     #[nbt(skip)]
+//!     dirty: bool,
+//! }
+//! ```
+//!
+//! The supported field attributes are:
+//!
+//! * `rename = "name"`: use a different key in the Compound.
+//! * `skip`: neither write nor read the field, using `Default` when decoding.
+//! * `default` or `default = "path"`: use `Default::default()` (or the given
+//!   function) when the field is missing.
+//! * `array`: encode a `Vec<i8>`, `Vec<i32>` or `Vec<i64>` as a
+//!   `TAG_Byte_Array`, `TAG_Int_Array` or `TAG_Long_Array` respectively,
+//!   rather than as a `TAG_List`.
+//!
+//! * `tag = "name"`: store the field as the named tag instead of its natural
+//!   one, which may be any of `byte`, `short`, `int`, `long`, `float`,
+//!   `double`, `byte_array`, `int_array` or `long_array`. The field's type
+//!   must implement `NbtTagged` for that tag; numbers can be stored in a wider
+//!   tag, and are checked to fit when decoding.
+//!
+//! Fields of type `Option<T>` are omitted when `None`, and decoded as `None`
+//! when missing.
+//!
+//! `#[derive(NbtCompound)]` is shorthand for deriving both `ToNbt` and
+//! `FromNbt`.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+
+use error::{Error, Result};
+use raw::{self, Endianness, RawReader, RawWriter};
+use value::Value;
+
+/// A type that can be encoded as an NBT value.
+pub trait ToNbt {
+    /// The type ID of the tag that this value is encoded as.
+    fn tag_id(&self) -> i8;
+
+    /// Converts this value into a `Value` tree.
+    fn to_value(&self) -> Value;
+
+    /// Writes the payload of this value directly to a `RawWriter`.
+    ///
+    /// The default implementation goes through `to_value()`; implementations
+    /// should override it to avoid building the intermediate tree.
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        self.to_value().to_raw_writer(dst)
+    }
+
+    /// Writes this value as a complete NBT document with the given root name.
+    ///
+    /// This fails with `Error::NoRootCompound` unless the value is encoded as
+    /// a Compound.
+    fn to_nbt_writer<W>(&self, dst: &mut W, name: &str, endian: Endianness) -> Result<()>
+        where W: io::Write,
+    {
+        if self.tag_id() != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        let mut dst = RawWriter::new(dst, endian);
+        dst.write_header(0x0a, name)?;
+        self.to_raw_writer(&mut dst)
+    }
+}
+
+/// A type that can be decoded from an NBT value.
+pub trait FromNbt: Sized {
+    /// Converts a `Value` tree into this type.
+    fn from_value(value: &Value) -> Result<Self>;
+
+    /// Reads a payload with the given type ID directly from a `RawReader`.
+    ///
+    /// The default implementation goes through `from_value()`;
+    /// implementations should override it to avoid building the intermediate
+    /// tree.
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        Self::from_value(&Value::from_raw_reader(id, src)?)
+    }
+
+    /// Reads this type from a complete NBT document, ignoring the root name.
+    fn from_nbt_reader<R>(src: &mut R, endian: Endianness) -> Result<Self>
+        where R: io::Read,
+    {
+        let mut src = RawReader::new(src, endian);
+        let (tag, _) = src.emit_next_header()?;
+        if tag != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        Self::from_raw_reader(tag, &mut src)
+    }
+}
+
+/// A vector that can be encoded as one of the specialized array tags.
+///
+/// This is used by fields marked `#[nbt(array)]`.
+pub trait NbtArray: Sized {
+    /// The type ID of the array tag.
+    fn array_tag_id(&self) -> i8;
+
+    /// Converts this vector into the matching array `Value`.
+    fn to_array_value(&self) -> Value;
+
+    /// Writes the array payload directly to a `RawWriter`.
+    fn to_array_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write;
+
+    /// Converts an array `Value` into this vector.
+    fn from_array_value(value: &Value) -> Result<Self>;
+
+    /// Reads an array payload with the given type ID from a `RawReader`.
+    fn from_array_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read;
+}
+
+macro_rules! array_impl {
+    ($ty:ty, $id:expr, $variant:ident, $write:ident, $read:ident) => {
+        impl NbtArray for Vec<$ty> {
+            fn array_tag_id(&self) -> i8 { $id }
+
+            fn to_array_value(&self) -> Value { Value::$variant(self.clone()) }
+
+            fn to_array_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+                where W: io::Write,
+            {
+                dst.$write(self)
+            }
+
+            fn from_array_value(value: &Value) -> Result<Self> {
+                match *value {
+                    Value::$variant(ref v) => Ok(v.clone()),
+                    ref other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+
+            fn from_array_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+                where R: io::Read,
+            {
+                match id {
+                    $id => src.$read(),
+                    other => Err(Error::TagMismatch(other, $id)),
+                }
+            }
+        }
+    };
+}
+
+array_impl!(i8, 0x07, ByteArray, write_bare_byte_array, read_bare_byte_array);
+array_impl!(i32, 0x0b, IntArray, write_bare_int_array, read_bare_int_array);
+array_impl!(i64, 0x0c, LongArray, write_bare_long_array, read_bare_long_array);
+
+/// Marker types for the tags accepted by `#[nbt(tag = "...")]`.
+pub mod tag {
+    /// `TAG_Byte`.
+    pub struct Byte;
+    /// `TAG_Short`.
+    pub struct Short;
+    /// `TAG_Int`.
+    pub struct Int;
+    /// `TAG_Long`.
+    pub struct Long;
+    /// `TAG_Float`.
+    pub struct Float;
+    /// `TAG_Double`.
+    pub struct Double;
+    /// `TAG_Byte_Array`.
+    pub struct ByteArray;
+    /// `TAG_Int_Array`.
+    pub struct IntArray;
+    /// `TAG_Long_Array`.
+    pub struct LongArray;
+}
+
+/// A type that can be encoded as the tag `T`, which is one of the markers in
+/// the `tag` module.
+///
+/// This is used by fields marked `#[nbt(tag = "...")]`.
+pub trait NbtTagged<T>: Sized {
+    /// The type ID of the tag `T`.
+    fn tagged_id(&self) -> i8;
+
+    /// Converts this value into a `Value` of the tag `T`.
+    fn to_tagged_value(&self) -> Value;
+
+    /// Writes this value directly to a `RawWriter` as a payload of the tag `T`.
+    fn to_tagged_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write;
+
+    /// Converts a `Value` of the tag `T` into this type.
+    fn from_tagged_value(value: &Value) -> Result<Self>;
+
+    /// Reads a payload of the tag `T` from a `RawReader`, failing with
+    /// `Error::TagMismatch` if the given type ID is another tag.
+    fn from_tagged_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read;
+}
+
+macro_rules! tagged_impl {
+    ($ty:ty as $tag:ident($stored:ty), $narrow:expr) => {
+        impl NbtTagged<tag::$tag> for $ty {
+            fn tagged_id(&self) -> i8 {
+                ToNbt::tag_id(&<$stored>::from(*self))
+            }
+
+            fn to_tagged_value(&self) -> Value {
+                ToNbt::to_value(&<$stored>::from(*self))
+            }
+
+            fn to_tagged_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+                where W: io::Write,
+            {
+                ToNbt::to_raw_writer(&<$stored>::from(*self), dst)
+            }
+
+            fn from_tagged_value(value: &Value) -> Result<Self> {
+                let stored = <$stored as FromNbt>::from_value(value)?;
+                $narrow(stored).ok_or(Error::OutOfRange(value.id()))
+            }
+
+            fn from_tagged_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+                where R: io::Read,
+            {
+                let stored = <$stored as FromNbt>::from_raw_reader(id, src)?;
+                $narrow(stored).ok_or(Error::OutOfRange(id))
+            }
+        }
+    };
+    ($ty:ty as $tag:ident($stored:ty)) => {
+        tagged_impl!($ty as $tag($stored), |v| <$ty>::try_from(v).ok());
+    };
+}
+
+tagged_impl!(i8 as Byte(i8));
+tagged_impl!(i8 as Short(i16));
+tagged_impl!(i8 as Int(i32));
+tagged_impl!(i8 as Long(i64));
+tagged_impl!(i16 as Short(i16));
+tagged_impl!(i16 as Int(i32));
+tagged_impl!(i16 as Long(i64));
+tagged_impl!(i32 as Int(i32));
+tagged_impl!(i32 as Long(i64));
+tagged_impl!(i64 as Long(i64));
+tagged_impl!(f32 as Float(f32), Some);
+// Doubles are narrowed like Minecraft does, rounding to the nearest float.
+tagged_impl!(f32 as Double(f64), |v: f64| Some(v as f32));
+tagged_impl!(f64 as Double(f64), Some);
+
+macro_rules! tagged_array_impl {
+    ($ty:ty as $tag:ident) => {
+        impl NbtTagged<tag::$tag> for Vec<$ty> {
+            fn tagged_id(&self) -> i8 { self.array_tag_id() }
+
+            fn to_tagged_value(&self) -> Value { self.to_array_value() }
+
+            fn to_tagged_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+                where W: io::Write,
+            {
+                self.to_array_raw_writer(dst)
+            }
+
+            fn from_tagged_value(value: &Value) -> Result<Self> {
+                Self::from_array_value(value)
+            }
+
+            fn from_tagged_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+                where R: io::Read,
+            {
+                Self::from_array_raw_reader(id, src)
+            }
+        }
+    };
+}
+
+tagged_array_impl!(i8 as ByteArray);
+tagged_array_impl!(i32 as IntArray);
+tagged_array_impl!(i64 as LongArray);
+
+macro_rules! primitive_impl {
+    ($ty:ty, $id:expr, $variant:ident, $write:ident, $read:ident) => {
+        impl ToNbt for $ty {
+            fn tag_id(&self) -> i8 { $id }
+
+            fn to_value(&self) -> Value { Value::$variant(self.clone()) }
+
+            fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+                where W: io::Write,
+            {
+                dst.$write(*self)
+            }
+        }
+
+        impl FromNbt for $ty {
+            fn from_value(value: &Value) -> Result<Self> {
+                match *value {
+                    Value::$variant(v) => Ok(v),
+                    ref other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+
+            fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+                where R: io::Read,
+            {
+                match id {
+                    $id => src.$read(),
+                    other => Err(Error::TagMismatch(other, $id)),
+                }
+            }
+        }
+    };
+}
+
+primitive_impl!(i8, 0x01, Byte, write_bare_byte, read_bare_byte);
+primitive_impl!(i16, 0x02, Short, write_bare_short, read_bare_short);
+primitive_impl!(i32, 0x03, Int, write_bare_int, read_bare_int);
+primitive_impl!(i64, 0x04, Long, write_bare_long, read_bare_long);
+primitive_impl!(f32, 0x05, Float, write_bare_float, read_bare_float);
+primitive_impl!(f64, 0x06, Double, write_bare_double, read_bare_double);
+
+/// Booleans are encoded as a `TAG_Byte` of 0 or 1.
+impl ToNbt for bool {
+    fn tag_id(&self) -> i8 { 0x01 }
+
+    fn to_value(&self) -> Value { Value::Byte(*self as i8) }
+
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        dst.write_bare_byte(*self as i8)
+    }
+}
+
+impl FromNbt for bool {
+    fn from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::Byte(0) => Ok(false),
+            Value::Byte(1) => Ok(true),
+            Value::Byte(b) => Err(Error::NonBooleanByte(b)),
+            ref other => Err(Error::TagMismatch(other.id(), 0x01)),
+        }
+    }
+
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        match i8::from_raw_reader(id, src)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(Error::NonBooleanByte(b)),
+        }
+    }
+}
+
+impl ToNbt for String {
+    fn tag_id(&self) -> i8 { 0x08 }
+
+    fn to_value(&self) -> Value { Value::String(self.clone()) }
+
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        dst.write_bare_string(self)
+    }
+}
+
+impl FromNbt for String {
+    fn from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::String(ref v) => Ok(v.clone()),
+            ref other => Err(Error::TagMismatch(other.id(), 0x08)),
+        }
+    }
+
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        match id {
+            0x08 => src.read_bare_string(),
+            other => Err(Error::TagMismatch(other, 0x08)),
+        }
+    }
+}
+
+impl ToNbt for Value {
+    fn tag_id(&self) -> i8 { self.id() }
+
+    fn to_value(&self) -> Value { self.clone() }
+
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        Value::to_raw_writer(self, dst)
+    }
+}
+
+impl FromNbt for Value {
+    fn from_value(value: &Value) -> Result<Self> {
+        Ok(value.clone())
+    }
+
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        Value::from_raw_reader(id, src)
+    }
+}
+
+/// Vectors are encoded as a `TAG_List`; use `#[nbt(array)]` on a derived
+/// field to encode them as one of the array tags instead.
+impl<T> ToNbt for Vec<T> where T: ToNbt {
+    fn tag_id(&self) -> i8 { 0x09 }
+
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(ToNbt::to_value).collect())
+    }
+
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        // As with `Value::List`, empty lists are written with TAG_End.
+        let id = self.first().map_or(0x00, ToNbt::tag_id);
+        dst.write_bare_byte(id)?;
+        dst.write_bare_int(self.len() as i32)?;
+        for elem in self {
+            if elem.tag_id() != id {
+                return Err(Error::HeterogeneousList);
+            }
+            elem.to_raw_writer(dst)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> FromNbt for Vec<T> where T: FromNbt {
+    fn from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::List(ref vals) => vals.iter().map(T::from_value).collect(),
+            ref other => Err(Error::TagMismatch(other.id(), 0x09)),
+        }
+    }
+
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        if id != 0x09 {
+            return Err(Error::TagMismatch(id, 0x09));
+        }
+        src.begin_nested()?;
+        let elem_id = src.read_bare_byte()?;
+        let len = src.read_list_len()?;
+        let mut buf = Vec::with_capacity(len.min(raw::PREALLOC_LEN));
+        for _ in 0..len {
+            buf.push(T::from_raw_reader(elem_id, src)?);
+        }
+        src.end_nested();
+        Ok(buf)
+    }
+}
+
+impl<T> ToNbt for HashMap<String, T> where T: ToNbt {
+    fn tag_id(&self) -> i8 { 0x0a }
+
+    fn to_value(&self) -> Value {
+        Value::Compound(self.iter().map(|(k, v)| (k.clone(), v.to_value())).collect())
+    }
+
+    fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        dst.write_entries(self.iter(), |dst, name, v| {
+            dst.write_header(v.tag_id(), name)?;
+            v.to_raw_writer(dst)
+        })
+    }
+}
+
+impl<T> FromNbt for HashMap<String, T> where T: FromNbt {
+    fn from_value(value: &Value) -> Result<Self> {
+        match *value {
+            Value::Compound(ref vals) => vals.iter()
+                .map(|(k, v)| Ok((k.clone(), T::from_value(v)?)))
+                .collect(),
+            ref other => Err(Error::TagMismatch(other.id(), 0x0a)),
+        }
+    }
+
+    fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Self>
+        where R: io::Read,
+    {
+        if id != 0x0a {
+            return Err(Error::TagMismatch(id, 0x0a));
+        }
+        src.begin_nested()?;
+        let mut buf = HashMap::new();
+        loop {
+            let (id, name) = src.emit_next_header()?;
+            if id == 0x00 { break; }
+            buf.insert(name, T::from_raw_reader(id, src)?);
+        }
+        src.end_nested();
+        Ok(buf)
+    }
+}
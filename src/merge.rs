@@ -0,0 +1,43 @@
+//! Merging of Compounds, as done by the `/data merge` command.
+
+use map::Map;
+use value::Value;
+
+/// How `Blob::merge()` and `Value::merge()` combine two Compounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Merge Compounds present on both sides recursively, and otherwise take
+    /// the new value. Lists and arrays are replaced rather than merged. This
+    /// matches `/data merge`.
+    Deep,
+    /// Replace each entry with the new one, without looking inside.
+    Overwrite,
+    /// Only add entries that are missing, merging Compounds present on both
+    /// sides recursively. This is useful for filling in defaults.
+    KeepExisting,
+}
+
+/// Merges the entries of `from` into `into`.
+pub(crate) fn merge_entries(into: &mut Map, from: &Map,
+                            strategy: MergeStrategy)
+{
+    for (key, value) in from {
+        match into.get_mut(key) {
+            Some(existing) if strategy == MergeStrategy::Overwrite => *existing = value.clone(),
+            Some(existing) => merge(existing, value, strategy),
+            None => {
+                into.insert(key.clone(), value.clone());
+            },
+        }
+    }
+}
+
+/// Merges `from` into `into`. Unless both are Compounds, this keeps `into`
+/// with `KeepExisting`, or replaces it otherwise.
+pub(crate) fn merge(into: &mut Value, from: &Value, strategy: MergeStrategy) {
+    match (&mut *into, from) {
+        (Value::Compound(a), Value::Compound(b)) => merge_entries(a, b, strategy),
+        _ if strategy != MergeStrategy::KeepExisting => *into = from.clone(),
+        _ => {},
+    }
+}
@@ -0,0 +1,99 @@
+//! Read-only memory maps of files, with the `memmap` feature on Unix.
+//!
+//! Mapping a large file, such as a region holding a thousand chunks, lets the
+//! operating system page in only the parts that are actually read, straight
+//! from its page cache, instead of copying the whole file into a buffer of
+//! its own first.
+//!
+//! ```rust,no_run
+//! use std::fs::File;
+//! use nbt::{Blob, Endianness};
+//! use nbt::mmap::Mmap;
+//!
+//! let file = File::open("level.nbt").unwrap();
+//! let map = unsafe { Mmap::map(&file) }.unwrap();
+//! let blob = Blob::from_reader(&mut &map[..], Endianness::BigEndian).unwrap();
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::slice;
+
+use libc;
+
+/// A read-only memory map of a whole file.
+///
+/// It derefs to the bytes of the file, and can be wrapped in a
+/// `std::io::Cursor` where a seekable source is needed.
+pub struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and owned by this value alone.
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Maps all of `file`, which must be open for reading.
+    ///
+    /// # Safety
+    ///
+    /// The map reflects later changes to the file, so it must not be
+    /// modified or truncated, by this process or any other, while the map is
+    /// alive. Reading a truncated part of the map kills the process with
+    /// `SIGBUS`.
+    pub unsafe fn map(file: &File) -> io::Result<Mmap> {
+        let len = file.metadata()?.len();
+        if len > usize::MAX as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "file is too large to map"));
+        }
+        let len = len as usize;
+        // Empty mappings are rejected by mmap(), and need no memory anyway.
+        if len == 0 {
+            return Ok(Mmap { ptr: ptr::null_mut(), len });
+        }
+        let ptr = libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, file.as_raw_fd(), 0);
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mmap { ptr, len })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for Mmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mmap").field("ptr", &self.ptr).field("len", &self.len).finish()
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
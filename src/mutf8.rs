@@ -0,0 +1,126 @@
+//! Java's Modified UTF-8, in which NBT strings are stored.
+//!
+//! It differs from UTF-8 in writing the null character as the two bytes
+//! `C0 80`, and characters outside the Basic Multilingual Plane as a pair
+//! of 3-byte surrogates rather than 4 bytes.
+
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+use std::str;
+
+use error::{Error, Result};
+
+/// Decodes Modified UTF-8. Input that is already valid UTF-8 is accepted
+/// as is, without copying.
+pub(crate) fn decode(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    if let Ok(text) = str::from_utf8(bytes) {
+        return Ok(Cow::Borrowed(text));
+    }
+    let mut decoded = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (c, len) = decode_char(bytes, i)?;
+        decoded.push(c);
+        i += len;
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+/// Decodes Modified UTF-8 like `decode()`, but replaces each byte that does
+/// not start a valid character with U+FFFD REPLACEMENT CHARACTER.
+pub(crate) fn decode_lossy(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match decode_char(bytes, i) {
+            Ok((c, len)) => {
+                decoded.push(c);
+                i += len;
+            },
+            Err(_) => {
+                decoded.push('\u{fffd}');
+                i += 1;
+            },
+        }
+    }
+    Cow::Owned(decoded)
+}
+
+/// Decodes the character starting at `bytes[i]`, and how many bytes it took.
+fn decode_char(bytes: &[u8], i: usize) -> Result<(char, usize)> {
+    let first = bytes[i];
+    let (c, len) = match first {
+        0x00 => return Err(Error::InvalidUtf8),
+        0x01..=0x7f => (u32::from(first), 1),
+        0xc0 if bytes.get(i + 1) == Some(&0x80) => (0, 2),
+        0xc2..=0xdf => (u32::from(first & 0x1f) << 6 | cont(bytes, i + 1)?, 2),
+        0xe0..=0xef => {
+            let unit = u32::from(first & 0x0f) << 12 | cont(bytes, i + 1)? << 6 | cont(bytes, i + 2)?;
+            match unit {
+                0x0000..=0x07ff => return Err(Error::InvalidUtf8),
+                0xd800..=0xdbff => {
+                    // The low surrogate must follow as another 3 bytes.
+                    if bytes.get(i + 3) != Some(&0xed) {
+                        return Err(Error::InvalidUtf8);
+                    }
+                    let low = 0xd000 | cont(bytes, i + 4)? << 6 | cont(bytes, i + 5)?;
+                    if !(0xdc00..=0xdfff).contains(&low) {
+                        return Err(Error::InvalidUtf8);
+                    }
+                    (0x10000 + ((unit - 0xd800) << 10 | (low - 0xdc00)), 6)
+                },
+                0xdc00..=0xdfff => return Err(Error::InvalidUtf8),
+                _ => (unit, 3),
+            }
+        },
+        _ => return Err(Error::InvalidUtf8),
+    };
+    Ok((::std::char::from_u32(c).ok_or(Error::InvalidUtf8)?, len))
+}
+
+/// The low six bits of the continuation byte at `bytes[i]`.
+fn cont(bytes: &[u8], i: usize) -> Result<u32> {
+    match bytes.get(i) {
+        Some(&byte) if byte & 0xc0 == 0x80 => Ok(u32::from(byte & 0x3f)),
+        _ => Err(Error::InvalidUtf8),
+    }
+}
+
+/// Encodes a string as Modified UTF-8, without copying if it contains no
+/// null or supplementary characters.
+pub(crate) fn encode(text: &str) -> Cow<'_, [u8]> {
+    if !text.bytes().any(|b| b == 0 || b >= 0xf0) {
+        return Cow::Borrowed(text.as_bytes());
+    }
+    let mut encoded = Vec::with_capacity(text.len() + text.len() / 4);
+    for c in text.chars() {
+        match c {
+            '\0' => encoded.extend_from_slice(&[0xc0, 0x80]),
+            '\u{10000}'..='\u{10ffff}' => {
+                let mut units = [0; 2];
+                for &unit in c.encode_utf16(&mut units).iter() {
+                    encoded.extend_from_slice(&[
+                        0xe0 | (unit >> 12) as u8,
+                        0x80 | (unit >> 6 & 0x3f) as u8,
+                        0x80 | (unit & 0x3f) as u8,
+                    ]);
+                }
+            },
+            _ => encoded.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+/// The length of `text` once encoded by `encode()`.
+pub(crate) fn encoded_len(text: &str) -> usize {
+    text.chars().map(|c| match c {
+        '\0' => 2,
+        '\u{10000}'..='\u{10ffff}' => 6,
+        _ => c.len_utf8(),
+    }).sum()
+}
@@ -0,0 +1,100 @@
+//! A serde wrapper that keeps the name of the root compound.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{de, ser};
+use serde::ser::SerializeStruct;
+
+/// The struct name `Named` uses to identify itself to the NBT encoder and
+/// decoder.
+pub(crate) const NAMED_TOKEN: &str = "$nbt::private::Named";
+
+const FIELDS: &[&str] = &["name", "value"];
+
+/// A value together with the name of the root compound it is stored in.
+///
+/// Plain `to_writer` takes the root name as an argument, and `from_reader`
+/// throws it away. Wrapping the object in `Named` instead carries the name
+/// through serde, so files like `level.dat` (whose root is named `Data` in
+/// older versions) round-trip faithfully:
+///
+/// ```
+/// # extern crate nbt;
+/// # use std::collections::HashMap;
+/// use nbt::{Endianness, Named, Value};
+///
+/// # fn main() {
+/// let mut value = HashMap::new();
+/// value.insert("LevelName".to_string(), Value::String("world".to_string()));
+/// let level = Named { name: "Data".to_string(), value };
+///
+/// let mut bytes = Vec::new();
+/// nbt::to_writer(&mut bytes, &level, None, Endianness::BigEndian).unwrap();
+/// assert_eq!(&bytes[..7], &[0x0a, 0x00, 0x04, b'D', b'a', b't', b'a']);
+///
+/// let read: Named<HashMap<String, Value>> =
+///     nbt::from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+/// assert_eq!(read, level);
+/// # }
+/// ```
+///
+/// The name given in `Named` takes precedence over the `header` argument of
+/// `to_writer`. `value` must serialize to a map or struct.
+///
+/// Other serializers, and nested positions within an NBT document, see an
+/// ordinary struct with `name` and `value` fields.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Named<T> {
+    /// The name of the root compound.
+    pub name: String,
+    /// The contents of the root compound.
+    pub value: T,
+}
+
+impl<T: ser::Serialize> ser::Serialize for Named<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: ser::Serializer
+    {
+        let mut state = serializer.serialize_struct(NAMED_TOKEN, 2)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("value", &self.value)?;
+        state.end()
+    }
+}
+
+impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Named<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: de::Deserializer<'de>
+    {
+        deserializer.deserialize_struct(NAMED_TOKEN, FIELDS, NamedVisitor(PhantomData))
+    }
+}
+
+struct NamedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: de::Deserialize<'de>> de::Visitor<'de> for NamedVisitor<T> {
+    type Value = Named<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a named compound")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Named<T>, A::Error>
+        where A: de::MapAccess<'de>
+    {
+        let mut name = None;
+        let mut value = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "name" => name = Some(map.next_value()?),
+                "value" => value = Some(map.next_value()?),
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+        Ok(Named {
+            name: name.ok_or_else(|| de::Error::missing_field("name"))?,
+            value: value.ok_or_else(|| de::Error::missing_field("value"))?,
+        })
+    }
+}
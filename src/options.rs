@@ -0,0 +1,348 @@
+//! Options for reading and writing `Blob`s, for use with
+//! `Blob::from_reader_with()` and `Blob::to_writer_with()`.
+
+use std::io;
+
+use error::{Error, Result};
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use flate::{GzDecoder, ZlibDecoder};
+#[cfg(feature = "experimental_lz4")]
+use lz4::Lz4Decoder;
+#[cfg(feature = "experimental_zstd")]
+use zstd::ZstdDecoder;
+use raw::{Endianness, RawReader, RawWriter, StringEncoding, DEFAULT_MAX_DEPTH};
+
+/// The compression scheme of an NBT payload, such as a file or a region file
+/// chunk.
+///
+/// Every scheme exists whatever features are enabled, so that enabling one
+/// elsewhere in a build does not change this type. Reading or writing with a
+/// scheme whose feature is disabled fails with
+/// `Error::UnknownChunkCompression`. New schemes may be added, so matches
+/// on this type need a wildcard arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Gzip compression (scheme 1), used by `level.dat` and player data.
+    /// Needs the `compression` or `rust_backend` feature.
+    Gzip,
+    /// Zlib compression (scheme 2), used by Minecraft for region file chunks
+    /// by default. Needs the `compression` or `rust_backend` feature.
+    Zlib,
+    /// No compression (scheme 3), as in network packets.
+    Uncompressed,
+    /// LZ4 compression (scheme 4), which Minecraft 1.20.5 and later can use
+    /// for region file chunks. Needs the `experimental_lz4` feature.
+    Lz4,
+    /// Zstandard compression, which some servers and archives use. Region
+    /// files have no scheme for it. Needs the `experimental_zstd` feature.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the scheme's ID as stored in a region file. `Zstd` has none,
+    /// and gives 127, the ID of custom schemes, which `from_id()` does not
+    /// accept.
+    pub fn id(self) -> u8 {
+        match self {
+            Compression::Gzip => 1,
+            Compression::Zlib => 2,
+            Compression::Uncompressed => 3,
+            Compression::Lz4 => 4,
+            Compression::Zstd => 127,
+        }
+    }
+
+    /// Looks up a scheme by the ID stored in a region file.
+    pub fn from_id(id: u8) -> Result<Compression> {
+        match id {
+            1 => Ok(Compression::Gzip),
+            2 => Ok(Compression::Zlib),
+            3 => Ok(Compression::Uncompressed),
+            4 => Ok(Compression::Lz4),
+            _ => Err(Error::UnknownChunkCompression(id)),
+        }
+    }
+}
+
+/// How hard to compress, trading speed for size. This has no effect on
+/// uncompressed or LZ4 output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CompressionLevel {
+    /// Store the data without compressing it, while still using the
+    /// compressed format.
+    None,
+    /// Compress as quickly as possible, as suits a server saving many chunks.
+    Fast,
+    /// A balance between speed and size, as used by Minecraft.
+    #[default]
+    Default,
+    /// Compress as small as possible, as suits archiving.
+    Best,
+}
+
+/// How to read a `Blob`: the byte order, compression, string encoding, limits
+/// on untrusted input, and whether the root tag has a name.
+///
+/// ```rust
+/// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+/// use nbt::{Blob, Compression, Endianness, ReadOptions};
+///
+/// let options = ReadOptions::new(Endianness::BigEndian)
+///     .compression(Compression::Gzip)
+///     .max_depth(64)
+///     .max_array_len(1 << 16);
+///
+/// let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+/// let blob = Blob::from_reader_with(&mut file, &options).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReadOptions {
+    endian: Endianness,
+    compression: Compression,
+    unnamed_root: bool,
+    string_encoding: StringEncoding,
+    lossy_strings: bool,
+    max_depth: usize,
+    max_string_len: usize,
+    max_list_len: usize,
+    max_array_len: usize,
+}
+
+impl ReadOptions {
+    /// Options for reading uncompressed data with the given byte order, with
+    /// a named root and no limits other than `raw::DEFAULT_MAX_DEPTH`.
+    pub fn new(endian: Endianness) -> ReadOptions {
+        ReadOptions {
+            endian,
+            compression: Compression::Uncompressed,
+            unnamed_root: false,
+            string_encoding: StringEncoding::default(),
+            lossy_strings: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_string_len: usize::MAX,
+            max_list_len: usize::MAX,
+            max_array_len: usize::MAX,
+        }
+    }
+
+    /// Sets the compression scheme of the input.
+    pub fn compression(mut self, compression: Compression) -> ReadOptions {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets whether the root Compound's type ID is followed by a name. It is
+    /// not in the network format used by Java Edition since 1.20.2; see
+    /// `Blob::from_network_reader()`.
+    pub fn unnamed_root(mut self, unnamed: bool) -> ReadOptions {
+        self.unnamed_root = unnamed;
+        self
+    }
+
+    /// Sets how strings are decoded. See `RawReader::set_string_encoding()`.
+    pub fn string_encoding(mut self, encoding: StringEncoding) -> ReadOptions {
+        self.string_encoding = encoding;
+        self
+    }
+
+    /// Sets whether invalid strings are decoded lossily instead of failing.
+    /// See `RawReader::set_lossy_strings()`.
+    pub fn lossy_strings(mut self, lossy: bool) -> ReadOptions {
+        self.lossy_strings = lossy;
+        self
+    }
+
+    /// Sets the maximum nesting depth. See `RawReader::set_max_depth()`.
+    pub fn max_depth(mut self, max_depth: usize) -> ReadOptions {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum length of a string. See
+    /// `RawReader::set_max_string_len()`.
+    pub fn max_string_len(mut self, max_len: usize) -> ReadOptions {
+        self.max_string_len = max_len;
+        self
+    }
+
+    /// Sets the maximum length of a List. See `RawReader::set_max_list_len()`.
+    pub fn max_list_len(mut self, max_len: usize) -> ReadOptions {
+        self.max_list_len = max_len;
+        self
+    }
+
+    /// Sets the maximum length of an array. See
+    /// `RawReader::set_max_array_len()`.
+    pub fn max_array_len(mut self, max_len: usize) -> ReadOptions {
+        self.max_array_len = max_len;
+        self
+    }
+
+    pub(crate) fn is_unnamed_root(&self) -> bool {
+        self.unnamed_root
+    }
+
+    /// Wraps `src` to decompress it as set by `compression()`.
+    pub(crate) fn decoder<R: io::Read>(&self, src: R) -> Result<Decoder<R>> {
+        Ok(match self.compression {
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Compression::Gzip => Decoder::Gzip(GzDecoder::new(src)?),
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Compression::Zlib => Decoder::Zlib(ZlibDecoder::new(src)),
+            #[cfg(not(any(feature = "compression", feature = "rust_backend")))]
+            Compression::Gzip | Compression::Zlib => return Err(Error::UnknownChunkCompression(self.compression.id())),
+            Compression::Uncompressed => Decoder::Uncompressed(src),
+            #[cfg(feature = "experimental_lz4")]
+            Compression::Lz4 => Decoder::Lz4(Lz4Decoder::new(src)),
+            #[cfg(not(feature = "experimental_lz4"))]
+            Compression::Lz4 => return Err(Error::UnknownChunkCompression(self.compression.id())),
+            #[cfg(feature = "experimental_zstd")]
+            Compression::Zstd => Decoder::Zstd(ZstdDecoder::new(src)),
+            #[cfg(not(feature = "experimental_zstd"))]
+            Compression::Zstd => return Err(Error::UnknownChunkCompression(self.compression.id())),
+        })
+    }
+
+    /// Creates a `RawReader` with these options' byte order, string
+    /// encoding and limits.
+    pub(crate) fn raw_reader<R: io::Read>(&self, src: R) -> RawReader<R> {
+        let mut src = RawReader::new(src, self.endian);
+        src.set_string_encoding(self.string_encoding);
+        src.set_lossy_strings(self.lossy_strings);
+        src.set_max_depth(self.max_depth);
+        src.set_max_string_len(self.max_string_len);
+        src.set_max_list_len(self.max_list_len);
+        src.set_max_array_len(self.max_array_len);
+        src
+    }
+}
+
+/// A source decompressed by one of the schemes of `Compression`.
+pub(crate) enum Decoder<R: io::Read> {
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    Gzip(GzDecoder<R>),
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    Zlib(ZlibDecoder<R>),
+    Uncompressed(R),
+    #[cfg(feature = "experimental_lz4")]
+    Lz4(Lz4Decoder<R>),
+    #[cfg(feature = "experimental_zstd")]
+    Zstd(ZstdDecoder<R>),
+}
+
+impl<R: io::Read> io::Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Decoder::Gzip(ref mut src) => src.read(buf),
+            #[cfg(any(feature = "compression", feature = "rust_backend"))]
+            Decoder::Zlib(ref mut src) => src.read(buf),
+            Decoder::Uncompressed(ref mut src) => src.read(buf),
+            #[cfg(feature = "experimental_lz4")]
+            Decoder::Lz4(ref mut src) => src.read(buf),
+            #[cfg(feature = "experimental_zstd")]
+            Decoder::Zstd(ref mut src) => src.read(buf),
+        }
+    }
+}
+
+/// How to write a `Blob`: the byte order, compression scheme and level, key
+/// order, string encoding, and whether the root tag has a name.
+///
+/// ```rust
+/// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+/// use nbt::{Blob, Compression, CompressionLevel, Endianness, WriteOptions};
+///
+/// let mut blob = Blob::new();
+/// blob.insert("name", "Herobrine").unwrap();
+///
+/// let options = WriteOptions::new(Endianness::BigEndian)
+///     .compression(Compression::Zlib)
+///     .level(CompressionLevel::Fast)
+///     .sort_keys(true);
+/// let mut dst = Vec::new();
+/// blob.to_writer_with(&mut dst, &options).unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct WriteOptions {
+    endian: Endianness,
+    compression: Compression,
+    level: CompressionLevel,
+    unnamed_root: bool,
+    sort_keys: bool,
+    string_encoding: StringEncoding,
+}
+
+impl WriteOptions {
+    /// Options for writing uncompressed data with the given byte order and
+    /// a named root.
+    pub fn new(endian: Endianness) -> WriteOptions {
+        WriteOptions {
+            endian,
+            compression: Compression::Uncompressed,
+            level: CompressionLevel::Default,
+            unnamed_root: false,
+            sort_keys: false,
+            string_encoding: StringEncoding::default(),
+        }
+    }
+
+    /// Sets the compression scheme of the output.
+    pub fn compression(mut self, compression: Compression) -> WriteOptions {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the compression level, which is `CompressionLevel::Default`
+    /// unless changed.
+    pub fn level(mut self, level: CompressionLevel) -> WriteOptions {
+        self.level = level;
+        self
+    }
+
+    /// Sets whether to leave out the root Compound's name, as in the network
+    /// format used by Java Edition since 1.20.2. See
+    /// `Blob::to_network_writer()`.
+    pub fn unnamed_root(mut self, unnamed: bool) -> WriteOptions {
+        self.unnamed_root = unnamed;
+        self
+    }
+
+    /// Sets whether to write Compound entries sorted by key. See
+    /// `RawWriter::sort_keys()`.
+    pub fn sort_keys(mut self, sort: bool) -> WriteOptions {
+        self.sort_keys = sort;
+        self
+    }
+
+    /// Sets how strings are encoded. See `RawWriter::set_string_encoding()`.
+    pub fn string_encoding(mut self, encoding: StringEncoding) -> WriteOptions {
+        self.string_encoding = encoding;
+        self
+    }
+
+    pub(crate) fn get_compression(&self) -> Compression {
+        self.compression
+    }
+
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    pub(crate) fn get_level(&self) -> CompressionLevel {
+        self.level
+    }
+
+    pub(crate) fn is_unnamed_root(&self) -> bool {
+        self.unnamed_root
+    }
+
+    /// Creates a `RawWriter` with these options' byte order, key order and
+    /// string encoding.
+    pub(crate) fn raw_writer<W: io::Write>(&self, dst: W) -> RawWriter<W> {
+        let mut dst = RawWriter::new(dst, self.endian);
+        dst.sort_keys(self.sort_keys);
+        dst.set_string_encoding(self.string_encoding);
+        dst
+    }
+}
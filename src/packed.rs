@@ -0,0 +1,223 @@
+//! The packed LongArrays of chunk sections and heightmaps.
+//!
+//! Since 1.16, arrays of small integers, such as the palette indices in the
+//! `data` of a section's `block_states`, are packed into a `TAG_Long_Array`
+//! with a fixed number of bits per entry. Each Long holds as many whole
+//! entries as fit, starting from its least significant bits, and no entry
+//! spans two Longs, so the high bits of each Long may be unused. Before
+//! 1.16 entries were packed end to end instead, spanning Longs as needed;
+//! `unpack_spanning()` and `pack_spanning()` handle that layout.
+//!
+//! ```rust
+//! use nbt::packed;
+//!
+//! // Five bits per entry leave 4 bits of each Long unused.
+//! let indices: Vec<u16> = (0..24).collect();
+//! let data = packed::pack(&indices, 5).unwrap();
+//! assert_eq!(data.len(), 2);
+//! assert_eq!(packed::unpack(&data, 5, 24).unwrap(), indices);
+//! ```
+
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use error::{Error, Result};
+
+/// The number of blocks in a chunk section, which is the number of entries
+/// in its block states.
+pub const SECTION_BLOCKS: usize = 4096;
+
+/// The number of entries in a chunk section's biomes, one for each 4x4x4
+/// cell.
+pub const SECTION_BIOMES: usize = 64;
+
+/// The fewest bits per entry that block states use, however small their
+/// palette.
+pub const BLOCK_STATE_MIN_BITS: u32 = 4;
+
+/// The fewest bits per entry that biomes use, however small their palette.
+pub const BIOME_MIN_BITS: u32 = 1;
+
+/// The bits per entry of heightmaps, such as `MOTION_BLOCKING`, which hold
+/// one height for each column of a chunk.
+pub const HEIGHTMAP_BITS: u32 = 9;
+
+/// The heights of the columns of a chunk, indexed by `[z][x]` in local
+/// coordinates, as stored in a heightmap.
+pub type Heightmap = [[u16; 16]; 16];
+
+/// The number of bits per entry for indices into a palette of `len`
+/// entries: enough to hold `len - 1`, but no fewer than `min_bits`.
+pub fn bits_per_entry(len: usize, min_bits: u32) -> u32 {
+    let needed = usize::BITS - len.saturating_sub(1).leading_zeros();
+    needed.max(min_bits)
+}
+
+/// The number of Longs that `count` entries of `bits` bits pack into.
+///
+/// # Panics
+///
+/// If `bits` is not between 1 and 16.
+pub fn packed_len(count: usize, bits: u32) -> usize {
+    assert!((1..=16).contains(&bits), "packed arrays need 1 to 16 bits per entry");
+    let per_long = (64 / bits) as usize;
+    count.div_ceil(per_long)
+}
+
+/// Unpacks `count` entries of `bits` bits from a packed LongArray.
+///
+/// The array must be exactly as long as `packed_len()` requires.
+pub fn unpack(data: &[i64], bits: u32, count: usize) -> Result<Vec<u16>> {
+    check_bits(bits)?;
+    if data.len() != packed_len(count, bits) {
+        return Err(Error::InvalidPackedArray("the array length does not match the number of entries"));
+    }
+    let per_long = (64 / bits) as usize;
+    let mask = (1u64 << bits) - 1;
+    let mut values = Vec::with_capacity(count);
+    for (i, &long) in data.iter().enumerate() {
+        let long = long as u64;
+        let n = per_long.min(count - i * per_long);
+        values.extend((0..n).map(|j| ((long >> (j as u32 * bits)) & mask) as u16));
+    }
+    Ok(values)
+}
+
+/// Packs entries into a LongArray with `bits` bits per entry, padding the
+/// last Long with zeros.
+pub fn pack(values: &[u16], bits: u32) -> Result<Vec<i64>> {
+    check_bits(bits)?;
+    let per_long = (64 / bits) as usize;
+    let mut data = Vec::with_capacity(packed_len(values.len(), bits));
+    for chunk in values.chunks(per_long) {
+        let mut long = 0u64;
+        for (j, &value) in chunk.iter().enumerate() {
+            if u64::from(value) >> bits != 0 {
+                return Err(Error::InvalidPackedArray("an entry does not fit in the bits per entry"));
+            }
+            long |= u64::from(value) << (j as u32 * bits);
+        }
+        data.push(long as i64);
+    }
+    Ok(data)
+}
+
+/// Unpacks `count` entries of `bits` bits from a LongArray in the layout
+/// used before 1.16, where entries span Longs.
+pub fn unpack_spanning(data: &[i64], bits: u32, count: usize) -> Result<Vec<u16>> {
+    check_bits(bits)?;
+    if data.len() != spanning_len(count, bits) {
+        return Err(Error::InvalidPackedArray("the array length does not match the number of entries"));
+    }
+    let mask = (1u64 << bits) - 1;
+    let values = (0..count).map(|i| {
+        let start = i * bits as usize;
+        let (long, offset) = (start / 64, (start % 64) as u32);
+        let mut value = data[long] as u64 >> offset;
+        if offset + bits > 64 {
+            value |= (data[long + 1] as u64) << (64 - offset);
+        }
+        (value & mask) as u16
+    });
+    Ok(values.collect())
+}
+
+/// Packs entries into a LongArray in the layout used before 1.16, where
+/// entries span Longs.
+pub fn pack_spanning(values: &[u16], bits: u32) -> Result<Vec<i64>> {
+    check_bits(bits)?;
+    let mut data = vec![0u64; spanning_len(values.len(), bits)];
+    for (i, &value) in values.iter().enumerate() {
+        if u64::from(value) >> bits != 0 {
+            return Err(Error::InvalidPackedArray("an entry does not fit in the bits per entry"));
+        }
+        let start = i * bits as usize;
+        let (long, offset) = (start / 64, (start % 64) as u32);
+        data[long] |= u64::from(value) << offset;
+        if offset + bits > 64 {
+            data[long + 1] |= u64::from(value) >> (64 - offset);
+        }
+    }
+    Ok(data.into_iter().map(|long| long as i64).collect())
+}
+
+fn spanning_len(count: usize, bits: u32) -> usize {
+    (count * bits as usize).div_ceil(64)
+}
+
+/// Unpacks a heightmap, in either the layout of 1.16 and later, of 37
+/// Longs, or the older one of 36.
+///
+/// ```rust
+/// use nbt::packed;
+///
+/// let mut heights = [[64u16; 16]; 16];
+/// heights[15][0] = 319;
+/// let data = packed::pack_heightmap(&heights).unwrap();
+/// assert_eq!(data.len(), 37);
+/// assert_eq!(packed::unpack_heightmap(&data).unwrap(), heights);
+/// ```
+pub fn unpack_heightmap(data: &[i64]) -> Result<Heightmap> {
+    let values = if data.len() == spanning_len(256, HEIGHTMAP_BITS) {
+        unpack_spanning(data, HEIGHTMAP_BITS, 256)?
+    } else {
+        unpack(data, HEIGHTMAP_BITS, 256)?
+    };
+    let mut heights = [[0; 16]; 16];
+    for (row, values) in heights.iter_mut().zip(values.chunks(16)) {
+        row.copy_from_slice(values);
+    }
+    Ok(heights)
+}
+
+/// Packs a heightmap in the layout of 1.16 and later. Use
+/// `pack_spanning()` on the rows in order for older versions.
+pub fn pack_heightmap(heights: &Heightmap) -> Result<Vec<i64>> {
+    let values: Vec<u16> = heights.iter().flat_map(|row| row.iter().cloned()).collect();
+    pack(&values, HEIGHTMAP_BITS)
+}
+
+/// Unpacks `count` indices into a palette of `palette_len` entries, such as
+/// the `data` and `palette` of a section's `block_states`, checking that
+/// every index is in the palette.
+///
+/// A palette of a single entry has no data, and every index is zero.
+///
+/// ```rust
+/// use nbt::packed;
+///
+/// let indices = vec![0u16; packed::SECTION_BLOCKS];
+/// let data = packed::pack_indices(&indices, 1, packed::BLOCK_STATE_MIN_BITS).unwrap();
+/// assert!(data.is_empty());
+/// assert_eq!(packed::unpack_indices(&data, 1, packed::SECTION_BLOCKS, packed::BLOCK_STATE_MIN_BITS).unwrap(), indices);
+/// ```
+pub fn unpack_indices(data: &[i64], palette_len: usize, count: usize, min_bits: u32) -> Result<Vec<u16>> {
+    if palette_len == 1 && data.is_empty() {
+        return Ok(vec![0; count]);
+    }
+    let indices = unpack(data, bits_per_entry(palette_len, min_bits), count)?;
+    if indices.iter().any(|&i| usize::from(i) >= palette_len) {
+        return Err(Error::InvalidPackedArray("an index is not in the palette"));
+    }
+    Ok(indices)
+}
+
+/// Packs indices into a palette of `palette_len` entries, with the bits
+/// per entry the palette needs, or into nothing if it has a single entry.
+pub fn pack_indices(indices: &[u16], palette_len: usize, min_bits: u32) -> Result<Vec<i64>> {
+    if indices.iter().any(|&i| usize::from(i) >= palette_len) {
+        return Err(Error::InvalidPackedArray("an index is not in the palette"));
+    }
+    if palette_len == 1 {
+        return Ok(Vec::new());
+    }
+    pack(indices, bits_per_entry(palette_len, min_bits))
+}
+
+fn check_bits(bits: u32) -> Result<()> {
+    if (1..=16).contains(&bits) {
+        Ok(())
+    } else {
+        Err(Error::InvalidPackedArray("the bits per entry are not between 1 and 16"))
+    }
+}
@@ -0,0 +1,299 @@
+//! Structural differences between NBT values.
+
+use std::fmt;
+use std::iter::FromIterator;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+use std::slice;
+use std::vec;
+
+use error::{Error, Result};
+use map::{self, Map};
+use path::{self, Segment};
+use value::Value;
+
+/// A single difference between two values, at a path in the syntax of
+/// `Value::get_path()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// An entry of a Compound, or an element at the end of a List, that is
+    /// only present in the new value.
+    Added { path: String, value: Value },
+    /// An entry of a Compound, or an element at the end of a List, that is
+    /// only present in the old value.
+    Removed { path: String, value: Value },
+    /// A value that was replaced, because it changed tag, because it is an
+    /// array or a List whose elements changed tag, or simply because it is
+    /// different.
+    Changed { path: String, from: Value, to: Value },
+}
+
+impl Change {
+    /// The path to the value that this change affects.
+    pub fn path(&self) -> &str {
+        match *self {
+            Change::Added { ref path, .. } => path,
+            Change::Removed { ref path, .. } => path,
+            Change::Changed { ref path, .. } => path,
+        }
+    }
+}
+
+/// The differences between two values, as computed by `diff()`, which can be
+/// replayed with `Value::apply_patch()`.
+///
+/// Changes are ordered by path, with the entries of Compounds sorted by key.
+/// Elements removed from the end of a List are listed last first, so that the
+/// changes can be replayed in order. Patches can also be built by hand, by
+/// collecting an iterator of `Change`s.
+///
+/// The `Display` implementation lists one change per line, with values in
+/// SNBT:
+///
+/// ```text
+/// ~ Health: 20.0f -> 18.5f
+/// + Inventory[1]: {Count:1b,id:"minecraft:dirt"}
+/// - Motion
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NbtPatch {
+    changes: Vec<Change>,
+}
+
+impl NbtPatch {
+    /// The individual changes.
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Iterates over the individual changes.
+    pub fn iter(&self) -> slice::Iter<'_, Change> {
+        self.changes.iter()
+    }
+
+    /// The number of changes.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Whether the two values were equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl FromIterator<Change> for NbtPatch {
+    fn from_iter<I: IntoIterator<Item = Change>>(iter: I) -> NbtPatch {
+        NbtPatch { changes: iter.into_iter().collect() }
+    }
+}
+
+impl IntoIterator for NbtPatch {
+    type Item = Change;
+    type IntoIter = vec::IntoIter<Change>;
+
+    fn into_iter(self) -> vec::IntoIter<Change> {
+        self.changes.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a NbtPatch {
+    type Item = &'a Change;
+    type IntoIter = slice::Iter<'a, Change>;
+
+    fn into_iter(self) -> slice::Iter<'a, Change> {
+        self.changes.iter()
+    }
+}
+
+impl fmt::Display for NbtPatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            match *change {
+                Change::Added { ref path, ref value } =>
+                    writeln!(f, "+ {}: {}", path, value.to_snbt())?,
+                Change::Removed { ref path, .. } => writeln!(f, "- {}", path)?,
+                Change::Changed { ref path, ref from, ref to } =>
+                    writeln!(f, "~ {}: {} -> {}", path, from.to_snbt(), to.to_snbt())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Computes the changes that turn `from` into `to`.
+///
+/// Compounds are compared entry by entry, and Lists element by element, so
+/// that a change deep within a chunk is reported at its own path. Unlike a
+/// comparison through JSON, the tags of numbers are significant: a `TAG_Byte`
+/// changing into a `TAG_Short` of the same value is a change.
+///
+/// ```rust
+/// #[macro_use] extern crate nbt;
+/// use nbt::Change;
+///
+/// # fn main() {
+/// let before = nbt!({ Health: 20.0f32, Inventory: [{ id: "minecraft:stone", Count: 64i8 }] });
+/// let after = nbt!({ Health: 20.0f32, Inventory: [{ id: "minecraft:stone", Count: 63i8 }] });
+///
+/// let patch = nbt::diff(&before, &after);
+/// assert_eq!(patch.changes(), &[Change::Changed {
+///     path: "Inventory[0].Count".to_string(),
+///     from: nbt::Value::Byte(64),
+///     to: nbt::Value::Byte(63),
+/// }]);
+/// # }
+/// ```
+pub fn diff(from: &Value, to: &Value) -> NbtPatch {
+    let mut changes = Vec::new();
+    diff_value(&mut changes, String::new(), from, to);
+    NbtPatch { changes }
+}
+
+/// Computes the changes that turn the entries of one Compound into those of
+/// another.
+#[cfg(feature = "std")]
+pub(crate) fn diff_entries(from: &Map, to: &Map) -> NbtPatch {
+    let mut changes = Vec::new();
+    diff_compound(&mut changes, "", from, to);
+    NbtPatch { changes }
+}
+
+fn diff_value(changes: &mut Vec<Change>, path: String, from: &Value, to: &Value) {
+    match (from, to) {
+        (Value::Compound(a), Value::Compound(b)) => diff_compound(changes, &path, a, b),
+        (Value::List(a), Value::List(b)) if same_element_tag(a, b) => diff_list(changes, &path, a, b),
+        _ if from != to => changes.push(Change::Changed { path, from: from.clone(), to: to.clone() }),
+        _ => {},
+    }
+}
+
+fn diff_compound(changes: &mut Vec<Change>, path: &str, from: &Map,
+                 to: &Map)
+{
+    let mut keys: Vec<&String> = from.keys().chain(to.keys().filter(|k| !from.contains_key(*k))).collect();
+    keys.sort();
+    for key in keys {
+        let mut path = path.to_string();
+        path::write_key(&mut path, key);
+        match (from.get(key), to.get(key)) {
+            (Some(a), Some(b)) => diff_value(changes, path, a, b),
+            (Some(a), None) => changes.push(Change::Removed { path, value: a.clone() }),
+            (None, Some(b)) => changes.push(Change::Added { path, value: b.clone() }),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_list(changes: &mut Vec<Change>, path: &str, from: &[Value], to: &[Value]) {
+    for (i, (a, b)) in from.iter().zip(to).enumerate() {
+        diff_value(changes, format!("{}[{}]", path, i), a, b);
+    }
+    for (i, b) in to.iter().enumerate().skip(from.len()) {
+        changes.push(Change::Added { path: format!("{}[{}]", path, i), value: b.clone() });
+    }
+    for (i, a) in from.iter().enumerate().skip(to.len()).rev() {
+        changes.push(Change::Removed { path: format!("{}[{}]", path, i), value: a.clone() });
+    }
+}
+
+/// Whether two Lists can be compared element by element, which is only the
+/// case if doing so never leaves a List with mixed tags.
+fn same_element_tag(a: &[Value], b: &[Value]) -> bool {
+    match (a.first(), b.first()) {
+        (Some(x), Some(y)) => x.id() == y.id(),
+        _ => true,
+    }
+}
+
+/// Applies each change of `patch` to `root` in turn. See `Value::apply_patch()`.
+pub(crate) fn apply(root: &mut Value, patch: &NbtPatch) -> Result<()> {
+    let mut patched = root.clone();
+    for change in &patch.changes {
+        let segments = path::parse(change.path()).ok_or_else(|| conflict(change))?;
+        match segments.split_last() {
+            None => match *change {
+                Change::Changed { ref from, ref to, .. } if patched == *from => patched = to.clone(),
+                _ => return Err(conflict(change)),
+            },
+            Some((last, init)) => {
+                let parent = path::get_mut(&mut patched, init).ok_or_else(|| conflict(change))?;
+                apply_at(parent, last, change)?;
+            },
+        }
+    }
+    *root = patched;
+    Ok(())
+}
+
+/// Applies each change of `patch` to the entries of a Compound in turn.
+#[cfg(feature = "std")]
+pub(crate) fn apply_entries(root: &mut Map, patch: &NbtPatch) -> Result<()> {
+    let mut patched = root.clone();
+    for change in &patch.changes {
+        let segments = path::parse(change.path()).ok_or_else(|| conflict(change))?;
+        match segments.split_last() {
+            Some((Segment::Key(key), [])) =>
+                apply_key(&mut patched, key, change)?,
+            Some((last, init)) => {
+                let parent = path::get_in_mut(&mut patched, init).ok_or_else(|| conflict(change))?;
+                apply_at(parent, last, change)?;
+            },
+            None => return Err(conflict(change)),
+        }
+    }
+    *root = patched;
+    Ok(())
+}
+
+fn conflict(change: &Change) -> Error {
+    Error::PatchConflict(change.path().to_string())
+}
+
+fn apply_at(parent: &mut Value, last: &Segment, change: &Change) -> Result<()> {
+    match (parent, last) {
+        (Value::Compound(map), Segment::Key(key)) => apply_key(map, key, change),
+        (Value::List(list), Segment::Index(i)) => apply_index(list, *i, change),
+        _ => Err(conflict(change)),
+    }
+}
+
+fn apply_key(map: &mut Map, key: &str, change: &Change) -> Result<()> {
+    match *change {
+        Change::Added { ref value, .. } if !map.contains_key(key) => {
+            map.insert(key.to_string(), value.clone());
+        },
+        Change::Removed { ref value, .. } if map.get(key) == Some(value) => {
+            map::remove(map, key);
+        },
+        Change::Changed { ref from, ref to, .. } if map.get(key) == Some(from) => {
+            map.insert(key.to_string(), to.clone());
+        },
+        _ => return Err(conflict(change)),
+    }
+    Ok(())
+}
+
+/// Elements can only be added or removed at the end of a List, and must keep
+/// it homogeneous.
+fn apply_index(list: &mut Vec<Value>, i: usize, change: &Change) -> Result<()> {
+    match *change {
+        Change::Added { ref value, .. } if i == list.len() => {
+            if list.first().is_some_and(|first| first.id() != value.id()) {
+                return Err(Error::HeterogeneousList);
+            }
+            list.push(value.clone());
+        },
+        Change::Removed { ref value, .. } if i + 1 == list.len() && list[i] == *value => {
+            list.pop();
+        },
+        Change::Changed { ref from, ref to, .. } if list.get(i) == Some(from) => {
+            if list.len() > 1 && from.id() != to.id() {
+                return Err(Error::HeterogeneousList);
+            }
+            list[i] = to.clone();
+        },
+        _ => return Err(conflict(change)),
+    }
+    Ok(())
+}
@@ -0,0 +1,640 @@
+//! Paths into nested values, such as `Level.Sections[3].Palette[0].Name`.
+//!
+//! A path is a sequence of Compound keys separated by dots, each optionally
+//! followed by List indices in brackets. Keys containing dots, brackets or
+//! other awkward characters can be quoted, as in `Data."minecraft:overworld"`,
+//! with backslashes escaping quotes and backslashes inside. The empty path
+//! refers to the value itself.
+
+use std::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use error::{Error, Result};
+use map::{self, Map};
+use snbt;
+use value::Value;
+
+/// A single step of a path.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Segment<'a> {
+    /// An entry of a Compound.
+    Key(Cow<'a, str>),
+    /// An element of a List.
+    Index(usize),
+}
+
+/// Splits `path` into its segments, or returns `None` if it is malformed.
+pub(crate) fn parse(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if rest.starts_with('[') {
+            let end = rest.find(']')?;
+            segments.push(Segment::Index(rest[1..end].parse().ok()?));
+            rest = &rest[end + 1..];
+            continue;
+        }
+        if !segments.is_empty() {
+            if !rest.starts_with('.') {
+                return None;
+            }
+            rest = &rest[1..];
+        }
+        let (key, tail) = parse_key(rest, "")?;
+        segments.push(Segment::Key(key));
+        rest = tail;
+    }
+    Some(segments)
+}
+
+/// Parses a quoted or bare key from the start of `src`, returning it along
+/// with the remainder of the path.
+/// Bare keys end at a dot, a bracket, a quote or any of the characters in
+/// `stop`.
+fn parse_key<'a>(src: &'a str, stop: &str) -> Option<(Cow<'a, str>, &'a str)> {
+    if !src.starts_with('"') {
+        let end = src.find(|c| c == '.' || c == '[' || c == '"' || stop.contains(c)).unwrap_or(src.len());
+        if end == 0 {
+            return None;
+        }
+        return Some((Cow::Borrowed(&src[..end]), &src[end..]));
+    }
+
+    let mut key = String::new();
+    let mut chars = src.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((Cow::Owned(key), &src[i + 1..])),
+            '\\' => key.push(chars.next()?.1),
+            c => key.push(c),
+        }
+    }
+    // Unterminated quote.
+    None
+}
+
+/// Follows `segments` from `value`.
+pub(crate) fn get<'v>(mut value: &'v Value, segments: &[Segment]) -> Option<&'v Value> {
+    for segment in segments {
+        value = match (segment, value) {
+            (Segment::Key(key), Value::Compound(map)) => map.get(&key[..])?,
+            (Segment::Index(i), Value::List(list)) => list.get(*i)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Follows `segments` from `value`, mutably.
+pub(crate) fn get_mut<'v>(mut value: &'v mut Value, segments: &[Segment]) -> Option<&'v mut Value> {
+    for segment in segments {
+        value = match (segment, value) {
+            (Segment::Key(key), Value::Compound(map)) => map.get_mut(&key[..])?,
+            (Segment::Index(i), Value::List(list)) => list.get_mut(*i)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Follows `segments` from the entries of a Compound, which must begin with a
+/// key.
+#[cfg(feature = "std")]
+pub(crate) fn get_in<'v>(map: &'v Map, segments: &[Segment]) -> Option<&'v Value> {
+    match segments.split_first() {
+        Some((Segment::Key(key), rest)) => get(map.get(&key[..])?, rest),
+        _ => None,
+    }
+}
+
+/// Follows `segments` from the entries of a Compound, mutably.
+#[cfg(feature = "std")]
+pub(crate) fn get_in_mut<'v>(map: &'v mut Map, segments: &[Segment])
+                             -> Option<&'v mut Value>
+{
+    match segments.split_first() {
+        Some((Segment::Key(key), rest)) => get_mut(map.get_mut(&key[..])?, rest),
+        _ => None,
+    }
+}
+
+/// Follows a pointer, in the style of a JSON Pointer, from `value`.
+pub(crate) fn pointer<'v>(mut value: &'v Value, pointer: &str) -> Option<&'v Value> {
+    for token in pointer_tokens(pointer) {
+        value = match *value {
+            Value::Compound(ref map) => map.get(&token[..])?,
+            Value::List(ref list) => list.get(pointer_index(&token)?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Follows a pointer from `value`, mutably.
+pub(crate) fn pointer_mut<'v>(mut value: &'v mut Value, pointer: &str) -> Option<&'v mut Value> {
+    for token in pointer_tokens(pointer) {
+        value = match *value {
+            Value::Compound(ref mut map) => map.get_mut(&token[..])?,
+            Value::List(ref mut list) => list.get_mut(pointer_index(&token)?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Splits a pointer into its unescaped tokens. The leading slash is
+/// optional, and an empty pointer has no tokens.
+fn pointer_tokens(pointer: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    let pointer = pointer.strip_prefix('/').unwrap_or(pointer);
+    let tokens = if pointer.is_empty() { None } else { Some(pointer.split('/')) };
+    tokens.into_iter().flatten().map(|token| {
+        if token.contains('~') {
+            Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+        } else {
+            Cow::Borrowed(token)
+        }
+    })
+}
+
+/// Parses a List index from a pointer, which must be plain digits with no
+/// leading zeros, as in `serde_json`.
+fn pointer_index(token: &str) -> Option<usize> {
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit())
+        || (token.len() > 1 && token.starts_with('0'))
+    {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// A query over nested values, extending paths with wildcards and filters.
+///
+/// In addition to the keys and indices of `Value::get_path()`, a selector may
+/// contain:
+///
+/// * `*` in place of a key, matching every entry of a Compound;
+/// * `[*]`, matching every element of a List;
+/// * `[?path]`, matching the elements of a List that contain `path`, and
+///   `[?path==value]` or `[?path!=value]`, matching those where it is present
+///   and equal (or not equal) to a value given in SNBT.
+///
+/// ```rust
+/// #[macro_use] extern crate nbt;
+/// use nbt::{Selector, Value};
+///
+/// # fn main() {
+/// let chunk = nbt!({ Sections: [
+///     { Y: 0i8, Palette: [{ Name: "minecraft:air" }] },
+///     { Y: 1i8, Palette: [{ Name: "minecraft:stone" }, { Name: "minecraft:dirt" }] },
+/// ] });
+///
+/// let selector: Selector = "Sections[?Y==1b].Palette[*].Name".parse().unwrap();
+/// let names: Vec<_> = selector.select(&chunk).collect();
+/// assert_eq!(names, vec![
+///     ("Sections[1].Palette[0].Name".to_string(), &Value::from("minecraft:stone")),
+///     ("Sections[1].Palette[1].Name".to_string(), &Value::from("minecraft:dirt")),
+/// ]);
+/// # }
+/// ```
+///
+/// Each match comes with a path to it which `get_path()` accepts. Matches are
+/// produced in order, with the entries of a Compound sorted by key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    steps: Rc<Vec<Step>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    AnyKey,
+    AnyIndex,
+    Filter(Filter),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Filter {
+    path: Vec<Step>,
+    /// The value compared against, and whether it must be equal.
+    test: Option<(bool, Value)>,
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        let mut found = value;
+        for step in &self.path {
+            found = match (step, found) {
+                (Step::Key(key), Value::Compound(map)) => match map.get(key) {
+                    Some(v) => v,
+                    None => return false,
+                },
+                (Step::Index(i), Value::List(list)) => match list.get(*i) {
+                    Some(v) => v,
+                    None => return false,
+                },
+                _ => return false,
+            };
+        }
+        match self.test {
+            None => true,
+            Some((equal, ref expected)) => (found == expected) == equal,
+        }
+    }
+}
+
+impl Selector {
+    /// Parses a selector, failing with `Error::InvalidSelector` if it is
+    /// malformed.
+    pub fn parse(src: &str) -> Result<Selector> {
+        let mut parser = SelectorParser { src, pos: 0 };
+        let steps = parser.parse_steps(false)?;
+        if parser.pos != src.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Selector { steps: Rc::new(steps) })
+    }
+
+    /// Iterates over the values within `value` that match this selector,
+    /// along with their paths.
+    pub fn select<'v>(&self, value: &'v Value) -> Matches<'v> {
+        Matches {
+            steps: self.steps.clone(),
+            stack: vec![(0, String::new(), Node::Value(value))],
+        }
+    }
+
+    /// Iterates over the values within the entries of a Compound that match
+    /// this selector, along with their paths.
+    #[cfg(feature = "std")]
+    pub(crate) fn select_in<'v>(&self, map: &'v Map) -> Matches<'v> {
+        Matches {
+            steps: self.steps.clone(),
+            stack: vec![(0, String::new(), Node::Entries(map))],
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Selector> {
+        Selector::parse(src)
+    }
+}
+
+struct SelectorParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidSelector(self.pos, msg.to_string())
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses steps up to the end of the selector or, within a filter, up to
+    /// its comparison or closing bracket. Filters may only contain keys and
+    /// indices.
+    fn parse_steps(&mut self, in_filter: bool) -> Result<Vec<Step>> {
+        let mut steps = Vec::new();
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() || (in_filter && (rest.starts_with(']') || rest.starts_with('=')
+                                                 || rest.starts_with('!'))) {
+                return Ok(steps);
+            }
+            if self.eat("[") {
+                steps.push(self.parse_bracket(in_filter)?);
+                if !self.eat("]") {
+                    return Err(self.error("expected ']'"));
+                }
+                continue;
+            }
+            if !steps.is_empty() && !self.eat(".") {
+                return Err(self.error("expected '.' or '['"));
+            }
+            if !in_filter && self.eat("*") {
+                steps.push(Step::AnyKey);
+                continue;
+            }
+            let start = self.pos;
+            match parse_key(self.rest(), "]=!") {
+                Some((key, tail)) => {
+                    self.pos = self.src.len() - tail.len();
+                    steps.push(Step::Key(key.into_owned()));
+                },
+                None => {
+                    self.pos = start;
+                    return Err(self.error("expected a key"));
+                },
+            }
+        }
+    }
+
+    /// Parses the contents of a pair of brackets.
+    fn parse_bracket(&mut self, in_filter: bool) -> Result<Step> {
+        if !in_filter && self.eat("*") {
+            return Ok(Step::AnyIndex);
+        }
+        if !in_filter && self.eat("?") {
+            let path = self.parse_steps(true)?;
+            let equal = if self.eat("==") {
+                true
+            } else if self.eat("!=") {
+                false
+            } else {
+                return Ok(Step::Filter(Filter { path, test: None }));
+            };
+            let start = self.pos;
+            let (value, len) = snbt::parse_prefix(self.rest()).map_err(|e| match e {
+                Error::InvalidSnbt(pos, msg) => Error::InvalidSelector(start + pos, msg),
+                other => other,
+            })?;
+            self.pos += len;
+            return Ok(Step::Filter(Filter { path, test: Some((equal, value)) }));
+        }
+        let digits = self.rest().find(|c: char| !c.is_ascii_digit()).unwrap_or(self.rest().len());
+        match self.rest()[..digits].parse() {
+            Ok(i) => {
+                self.pos += digits;
+                Ok(Step::Index(i))
+            },
+            Err(_) => Err(self.error("expected an index")),
+        }
+    }
+}
+
+/// A position that a `Matches` iterator has yet to explore.
+enum Node<'v> {
+    Value(&'v Value),
+    /// The entries of a `Blob`, which are not wrapped in a `Value`.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Entries(&'v Map),
+}
+
+/// An iterator over the values matching a `Selector`, along with their
+/// paths.
+///
+/// This is created by `Selector::select()`, `Value::select()` and
+/// `Blob::select()`.
+pub struct Matches<'v> {
+    steps: Rc<Vec<Step>>,
+    /// Nodes still to visit, with the number of steps matched to reach them
+    /// and their paths. The next node to visit is at the end.
+    stack: Vec<(usize, String, Node<'v>)>,
+}
+
+impl<'v> Iterator for Matches<'v> {
+    type Item = (String, &'v Value);
+
+    fn next(&mut self) -> Option<(String, &'v Value)> {
+        while let Some((depth, path, node)) = self.stack.pop() {
+            let (map, list) = match node {
+                Node::Value(value) if depth == self.steps.len() => return Some((path, value)),
+                // A blob is not itself a value, so cannot be matched.
+                Node::Entries(_) if depth == self.steps.len() => continue,
+                Node::Value(Value::Compound(map)) | Node::Entries(map) => (Some(map), None),
+                Node::Value(Value::List(list)) => (None, Some(list)),
+                Node::Value(_) => continue,
+            };
+
+            let stack = &mut self.stack;
+            let mut push_key = |key: &str, value| {
+                let mut path = path.clone();
+                write_key(&mut path, key);
+                stack.push((depth + 1, path, Node::Value(value)));
+            };
+            match (&self.steps[depth], map) {
+                (Step::Key(key), Some(map)) => {
+                    if let Some(value) = map.get(key) {
+                        push_key(key, value);
+                    }
+                    continue;
+                },
+                (Step::AnyKey, Some(map)) => {
+                    let mut entries: Vec<_> = map.iter().collect();
+                    entries.sort_by(|a, b| b.0.cmp(a.0));
+                    for (key, value) in entries {
+                        push_key(key, value);
+                    }
+                    continue;
+                },
+                _ => {},
+            }
+
+            let list = match list {
+                Some(list) => list,
+                None => continue,
+            };
+            let elements = list.iter().enumerate().rev();
+            let elements: Vec<_> = match self.steps[depth] {
+                Step::Index(i) => list.get(i).map(|v| (i, v)).into_iter().collect(),
+                Step::AnyIndex => elements.collect(),
+                Step::Filter(ref filter) => elements.filter(|&(_, v)| filter.matches(v)).collect(),
+                _ => continue,
+            };
+            for (i, value) in elements {
+                stack.push((depth + 1, format!("{}[{}]", path, i), Node::Value(value)));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over every value in a tree, along with its path, created by
+/// `Value::walk()` and `Blob::walk()`.
+///
+/// Values are visited depth first, each before the values inside it, with
+/// Lists in order and the entries of Compounds in the order of the `Map`,
+/// which is the order they were read in with the `preserve_order` feature.
+/// Without it, entries are sorted by key, so that the order is the same from
+/// one run to the next.
+///
+/// Paths are plain `String`s in the syntax of `Value::get_path()`, as are
+/// those of `Selector` matches and located errors, rather than a type of
+/// their own: they can be printed, compared and passed back to `get_path()`
+/// as they are, and a path type would have to be converted to one for each.
+pub struct Walk<'v> {
+    /// Values still to visit, with their paths. The next value to visit is
+    /// at the end.
+    stack: Vec<(String, &'v Value)>,
+}
+
+impl<'v> Walk<'v> {
+    pub(crate) fn new(value: &'v Value) -> Walk<'v> {
+        Walk { stack: vec![(String::new(), value)] }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn entries(map: &'v Map) -> Walk<'v> {
+        let mut walk = Walk { stack: Vec::new() };
+        walk.push_entries("", map);
+        walk
+    }
+
+    fn push_entries(&mut self, path: &str, map: &'v Map) {
+        let mut entries: Vec<_> = map.iter().collect();
+        if map::ARBITRARY_ORDER {
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+        }
+        for (key, value) in entries.into_iter().rev() {
+            let mut path = path.to_string();
+            write_key(&mut path, key);
+            self.stack.push((path, value));
+        }
+    }
+}
+
+impl<'v> Iterator for Walk<'v> {
+    type Item = (String, &'v Value);
+
+    fn next(&mut self) -> Option<(String, &'v Value)> {
+        let (path, value) = self.stack.pop()?;
+        match *value {
+            Value::Compound(ref map) => self.push_entries(&path, map),
+            Value::List(ref list) => {
+                for (i, element) in list.iter().enumerate().rev() {
+                    self.stack.push((format!("{}[{}]", path, i), element));
+                }
+            },
+            _ => {},
+        }
+        Some((path, value))
+    }
+}
+
+/// What `Value::walk_mut()` does after visiting a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VisitAction {
+    /// Go on to visit the values inside this one.
+    Continue,
+    /// Keep this value, but do not visit the values inside it.
+    Skip,
+    /// Remove this value, and everything inside it, from its Compound or
+    /// List.
+    Remove,
+    /// Keep this value and stop walking.
+    Stop,
+}
+
+/// Visits every value nested in the values on `stack`, which are not
+/// themselves visited, so that `visit` can edit, remove or skip them. See
+/// `Value::walk_mut()`.
+pub(crate) fn walk_mut<F>(mut stack: Vec<(String, &mut Value)>, visit: &mut F)
+    where F: FnMut(&str, &mut Value) -> VisitAction,
+{
+    while let Some((path, value)) = stack.pop() {
+        let mut stop = false;
+        let mut children = Vec::new();
+        match *value {
+            Value::Compound(ref mut map) => {
+                // Only the keys of the few entries that are not continued
+                // into are cloned, as the map cannot be changed while the
+                // entries are borrowed.
+                let mut removed = Vec::new();
+                let mut skipped = Vec::new();
+                for (key, child) in sorted_entries_mut(map) {
+                    let mut child_path = path.clone();
+                    write_key(&mut child_path, key);
+                    match visit(&child_path, child) {
+                        VisitAction::Continue => {},
+                        VisitAction::Skip => skipped.push(key.clone()),
+                        VisitAction::Remove => removed.push(key.clone()),
+                        VisitAction::Stop => {
+                            stop = true;
+                            break;
+                        },
+                    }
+                }
+                for key in &removed {
+                    map::remove(map, key);
+                }
+                if stop {
+                    return;
+                }
+                skipped.sort();
+                for (key, child) in sorted_entries_mut(map) {
+                    if skipped.binary_search(key).is_err() {
+                        let mut child_path = path.clone();
+                        write_key(&mut child_path, key);
+                        children.push((child_path, child));
+                    }
+                }
+            },
+            Value::List(ref mut list) => {
+                let mut actions = Vec::with_capacity(list.len());
+                for (i, child) in list.iter_mut().enumerate() {
+                    let action = visit(&format!("{}[{}]", path, i), child);
+                    actions.push(action);
+                    if action == VisitAction::Stop {
+                        stop = true;
+                        break;
+                    }
+                }
+                actions.resize(list.len(), VisitAction::Skip);
+                let mut actions_iter = actions.iter();
+                list.retain(|_| actions_iter.next() != Some(&VisitAction::Remove));
+                if stop {
+                    return;
+                }
+                let kept = actions.into_iter().filter(|&action| action != VisitAction::Remove);
+                for (action, (i, child)) in kept.zip(list.iter_mut().enumerate()) {
+                    if action == VisitAction::Continue {
+                        children.push((format!("{}[{}]", path, i), child));
+                    }
+                }
+            },
+            _ => {},
+        }
+        stack.extend(children.into_iter().rev());
+    }
+}
+
+/// The entries of a `Map` for modification, sorted by key unless the map
+/// keeps them in order.
+fn sorted_entries_mut(map: &mut Map) -> Vec<(&String, &mut Value)> {
+    let mut entries: Vec<_> = map.iter_mut().collect();
+    if map::ARBITRARY_ORDER {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+    }
+    entries
+}
+
+/// Appends `key` to a path, quoting it unless it only contains characters
+/// that can never be mistaken for syntax.
+pub(crate) fn write_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    let bare = !key.is_empty()
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '+' || c == ':');
+    if bare {
+        path.push_str(key);
+        return;
+    }
+    path.push('"');
+    for c in key.chars() {
+        if c == '"' || c == '\\' {
+            path.push('\\');
+        }
+        path.push(c);
+    }
+    path.push('"');
+}
@@ -0,0 +1,235 @@
+//! Configurable tree printing of NBT values.
+
+use std::fmt;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use map;
+#[cfg(feature = "std")]
+use map::Map;
+use value::{Entries, Value};
+
+const RESET: &str = "\x1b[0m";
+const TAG: &str = "\x1b[36m";
+const NAME: &str = "\x1b[1m";
+const NUMBER: &str = "\x1b[33m";
+const STRING: &str = "\x1b[32m";
+const ELIDED: &str = "\x1b[2m";
+
+/// Prints `Value`s and `Blob`s as an indented tree, the format of their
+/// `Display` implementations.
+///
+/// The default settings print everything, indenting by two spaces, with the
+/// entries of Compounds sorted by name unless the `preserve_order` feature
+/// keeps them in insertion order. Large
+/// arrays and deep nesting can be cut short to keep dumps of whole chunks
+/// readable:
+///
+/// ```rust
+/// # #[macro_use] extern crate nbt;
+/// use nbt::Printer;
+///
+/// # fn main() {
+/// let chunks = nbt!([{ Heights: [I; 1, 2, 3] }, { Sections: [{ Y: 0i8 }] }]);
+/// let printer = Printer::new().max_array_len(2).max_depth(2);
+/// assert_eq!(chunks.display_with(&printer).to_string(), "\
+/// 2 entries of type TAG_Compound
+/// {
+///   TAG_Compound(None): 1 entry(ies)
+///   {
+///     TAG_IntArray(Heights): [I; 3 ints...]
+///   }
+///   TAG_Compound(None): 1 entry(ies)
+///   {
+///     TAG_List(Sections): 1 entries of type TAG_Compound {...}
+///   }
+/// }");
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Printer {
+    indent: usize,
+    max_array_len: Option<usize>,
+    max_depth: Option<usize>,
+    color: bool,
+    sort_keys: bool,
+}
+
+impl Default for Printer {
+    fn default() -> Printer {
+        Printer::new()
+    }
+}
+
+impl Printer {
+    /// A printer indenting by two spaces, without limits or color.
+    pub fn new() -> Printer {
+        Printer {
+            indent: 2,
+            max_array_len: None,
+            max_depth: None,
+            color: false,
+            sort_keys: map::ARBITRARY_ORDER,
+        }
+    }
+
+    /// Sets the number of spaces each level of nesting is indented by.
+    pub fn indent(mut self, width: usize) -> Printer {
+        self.indent = width;
+        self
+    }
+
+    /// Prints arrays with more than `len` elements as only their length,
+    /// such as `[I; 12345 ints...]`.
+    pub fn max_array_len(mut self, len: usize) -> Printer {
+        self.max_array_len = Some(len);
+        self
+    }
+
+    /// Prints the contents of Lists and Compounds nested no more than
+    /// `depth` levels deep, with deeper ones shown as `{...}`. A depth of
+    /// zero prints only the outermost value's size.
+    pub fn max_depth(mut self, depth: usize) -> Printer {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets whether the entries of Compounds are printed sorted by name,
+    /// rather than in the order the backing `Map` iterates them.
+    pub fn sort_keys(mut self, sort: bool) -> Printer {
+        self.sort_keys = sort;
+        self
+    }
+
+    /// Highlights tags, names and values with ANSI escape codes, for
+    /// printing to a terminal.
+    pub fn color(mut self, color: bool) -> Printer {
+        self.color = color;
+        self
+    }
+
+    /// Writes a `Value` to a destination such as a `fmt::Formatter` or a
+    /// `String`.
+    pub fn print_value<W: fmt::Write>(&self, dst: &mut W, value: &Value) -> fmt::Result {
+        self.payload(dst, value, 0, 0)
+    }
+
+    /// Writes the root Compound of a `Blob`, with its name, as described
+    /// by its parts.
+    #[cfg(feature = "std")]
+    pub(crate) fn print_blob<W: fmt::Write>(&self, dst: &mut W, title: &str, content: &Map) -> fmt::Result {
+        self.paint(dst, TAG, format_args!("TAG_Compound"))?;
+        dst.write_str("(")?;
+        self.paint(dst, NAME, format_args!("\"{}\"", title))?;
+        write!(dst, "): {} entry(ies)", content.len())?;
+        self.entries(dst, 0, 0, Entries::new(content, self.sort_keys).map(|(name, tag)| (Some(name), tag)), true)
+    }
+
+    /// Writes the payload of `value`, the part after its tag and name,
+    /// with its braces at column `offset`.
+    pub(crate) fn payload<W: fmt::Write>(&self, dst: &mut W, value: &Value, offset: usize, depth: usize) -> fmt::Result {
+        match *value {
+            Value::Byte(v)   => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::Short(v)  => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::Int(v)    => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::Long(v)   => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::Float(v)  => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::Double(v) => self.paint(dst, NUMBER, format_args!("{}", v)),
+            Value::ByteArray(ref v) => self.array(dst, v, "B", "bytes"),
+            Value::String(ref v) => self.paint(dst, STRING, format_args!("{}", v)),
+            Value::IntArray(ref v) => self.array(dst, v, "I", "ints"),
+            Value::LongArray(ref v) => self.array(dst, v, "L", "longs"),
+            Value::List(ref v) => {
+                if v.is_empty() {
+                    return dst.write_str("zero entries");
+                }
+                write!(dst, "{} entries of type {}", v.len(), v[0].tag_name())?;
+                self.entries(dst, offset, depth, v.iter().map(|tag| (None, tag)), false)
+            }
+            Value::Compound(ref v) => {
+                write!(dst, "{} entry(ies)", v.len())?;
+                let entries = Entries::new(v, self.sort_keys);
+                self.entries(dst, offset, depth, entries.map(|(name, tag)| (Some(name), tag)), false)
+            }
+        }
+    }
+
+    /// Writes the braced, one-per-line children of a List or Compound,
+    /// or `{...}` if it is too deep.
+    fn entries<'a, W, I>(&self, dst: &mut W, offset: usize, depth: usize, entries: I, quote: bool) -> fmt::Result
+        where W: fmt::Write, I: Iterator<Item = (Option<&'a String>, &'a Value)>
+    {
+        if self.max_depth.is_some_and(|max| depth >= max) {
+            dst.write_str(" ")?;
+            return self.paint(dst, ELIDED, format_args!("{{...}}"));
+        }
+        write!(dst, "\n{:width$}{{\n", "", width = offset)?;
+        let inner = offset + self.indent;
+        for (name, tag) in entries {
+            write!(dst, "{:width$}", "", width = inner)?;
+            self.paint(dst, TAG, format_args!("{}", tag.tag_name()))?;
+            dst.write_str("(")?;
+            match name {
+                Some(name) if quote => self.paint(dst, NAME, format_args!("\"{}\"", name))?,
+                Some(name) => self.paint(dst, NAME, format_args!("{}", name))?,
+                None => dst.write_str("None")?,
+            }
+            dst.write_str("): ")?;
+            self.payload(dst, tag, inner, depth + 1)?;
+            dst.write_str("\n")?;
+        }
+        write!(dst, "{:width$}}}", "", width = offset)
+    }
+
+    fn array<W: fmt::Write, T: fmt::Debug>(&self, dst: &mut W, v: &[T], prefix: &str, unit: &str) -> fmt::Result {
+        match self.max_array_len {
+            Some(max) if v.len() > max => {
+                self.paint(dst, ELIDED, format_args!("[{}; {} {}...]", prefix, v.len(), unit))
+            }
+            _ => self.paint(dst, NUMBER, format_args!("{:?}", v)),
+        }
+    }
+
+    /// Writes `args`, in `color` if coloring is enabled.
+    fn paint<W: fmt::Write>(&self, dst: &mut W, color: &str, args: fmt::Arguments) -> fmt::Result {
+        if self.color {
+            write!(dst, "{}{}{}", color, args, RESET)
+        } else {
+            dst.write_fmt(args)
+        }
+    }
+}
+
+/// A `Value` or `Blob` printed with a `Printer`, returned by their
+/// `display_with()` methods.
+pub struct Printed<'a> {
+    printer: &'a Printer,
+    root: Root<'a>,
+}
+
+enum Root<'a> {
+    Value(&'a Value),
+    #[cfg(feature = "std")]
+    Blob(&'a str, &'a Map),
+}
+
+impl<'a> Printed<'a> {
+    pub(crate) fn value(printer: &'a Printer, value: &'a Value) -> Printed<'a> {
+        Printed { printer, root: Root::Value(value) }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn blob(printer: &'a Printer, title: &'a str, content: &'a Map) -> Printed<'a> {
+        Printed { printer, root: Root::Blob(title, content) }
+    }
+}
+
+impl<'a> fmt::Display for Printed<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.root {
+            Root::Value(value) => self.printer.print_value(f, value),
+            #[cfg(feature = "std")]
+            Root::Blob(title, content) => self.printer.print_blob(f, title, content),
+        }
+    }
+}
@@ -1,149 +1,749 @@
 //! Primitive functions for serializing and deserializing NBT data.
+//!
+//! This module exposes the low-level codec that the rest of the crate is built
+//! on. It is intended for protocol work, where NBT payloads are interleaved
+//! with other framing, or for custom container formats. The types and methods
+//! documented here follow the crate's semantic versioning guarantees.
+//!
+//! # The NBT byte layout
+//!
+//! A *named tag* is a header followed by a payload. The header is a single
+//! type ID byte and a name written as a string (see `write_bare_string`). The
+//! special `TAG_End` (`0x00`) has no name and no payload, and closes the
+//! innermost open Compound.
+//!
+//! The payload of a Compound is a sequence of named tags terminated by
+//! `TAG_End`. The payload of a List is a type ID byte, an `Int` count, and
+//! that many *unnamed* payloads of that type. Arrays are an `Int` count
+//! followed by that many elements. All other payloads are fixed-size numbers
+//! or strings.
+//!
+//! # Network encoding
+//!
+//! With `Endianness::NetworkLittleEndian`, used by Bedrock Edition's network
+//! protocol, numbers are little-endian as for `LittleEndian`, except that
+//! `Int` and `Long` payloads are written as ZigZag-encoded VarInts, and
+//! string lengths as unsigned VarInts. Since List and array lengths are
+//! written as `Int`s, they are VarInts too.
+//!
+//! A VarInt stores seven bits per byte, least significant group first, with
+//! the high bit of each byte set if more bytes follow. ZigZag encoding maps
+//! signed integers to unsigned ones so that numbers of small magnitude stay
+//! short: 0, -1, 1, -2, ... become 0, 1, 2, 3, ...
+//!
+//! # Using `RawWriter`
+//!
+//! A complete NBT document is a header with type `0x0a` followed by a
+//! Compound payload. Every Compound payload must be opened with
+//! `begin_compound()` and closed with `close_nbt()`, which writes the
+//! `TAG_End`; `RawWriter` keeps a count of the open Compounds and, in debug
+//! builds, asserts that the two calls are correctly paired.
+//!
+//! ```rust
+//! # #[cfg(feature = "std")] {
+//! use nbt::raw::{Endianness, RawWriter};
+//! use nbt::{Blob, Value};
+//!
+//! let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+//! dst.write_header(0x0a, "").unwrap();
+//! dst.begin_compound();
+//! dst.write_header(0x08, "name").unwrap();
+//! dst.write_bare_string("Herobrine").unwrap();
+//! dst.write_header(0x09, "pos").unwrap();
+//! dst.write_bare_byte(0x06).unwrap(); // A list of doubles...
+//! dst.write_bare_int(2).unwrap(); // ...with two entries.
+//! dst.write_bare_double(1.5).unwrap();
+//! dst.write_bare_double(-3.0).unwrap();
+//! dst.close_nbt().unwrap();
+//! let bytes = dst.into_inner();
+//!
+//! let blob = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+//! assert_eq!(blob["name"], Value::String("Herobrine".to_string()));
+//! # }
+//! ```
+//!
+//! # Using `RawReader`
+//!
+//! `RawReader` has no knowledge of where it is in a document: the caller is
+//! responsible for reading each payload with the method matching its type.
+//! In particular `emit_next_header()` may only be called where a named tag is
+//! expected, i.e. at the start of a document or between the entries of a
+//! Compound. Whole payloads can be read with `Value::from_raw_reader()`.
+//!
+//! The following example parses a stream of frames, each consisting of a
+//! one-byte packet ID followed by a complete NBT document:
+//!
+//! ```rust
+//! use nbt::raw::{Endianness, RawReader};
+//! use nbt::Value;
+//!
+//! let stream = vec![
+//!     0x01, // Packet ID.
+//!         0x0a, 0x00, 0x00, // Root Compound, no name.
+//!             0x03, 0x00, 0x01, 0x78, 0x00, 0x00, 0x00, 0x2a, // Int "x" = 42
+//!         0x00, // TAG_End
+//!     0x02,
+//!         0x0a, 0x00, 0x00,
+//!         0x00,
+//! ];
+//!
+//! let mut src = RawReader::new(&stream[..], Endianness::BigEndian);
+//! let mut packets = Vec::new();
+//! while !src.get_ref().is_empty() {
+//!     let id = src.read_bare_byte().unwrap();
+//!     let (tag, _name) = src.emit_next_header().unwrap();
+//!     let payload = Value::from_raw_reader(tag, &mut src).unwrap();
+//!     packets.push((id, payload));
+//! }
+//! assert_eq!(packets.len(), 2);
+//! assert_eq!(packets[1].1, Value::Compound(Default::default()));
+//! ```
 
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::io;
-
-use byteorder::{ReadBytesExt, WriteBytesExt};
-use cesu8::{from_java_cesu8, to_java_cesu8};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+use std::str;
+use std::sync::Arc;
 
 use error::{Error, Result};
+use mutf8;
 
-#[derive(Debug, Clone, Copy)]
+/// The byte order used for multi-byte numbers.
+///
+/// Java Edition uses `BigEndian` throughout, while Bedrock Edition files use
+/// `LittleEndian`, and Bedrock's network protocol uses `NetworkLittleEndian`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Endianness {
     LittleEndian,
     BigEndian,
+    /// Little-endian, with VarInt-encoded `Int`s, `Long`s, and lengths. See
+    /// the [module documentation](index.html#network-encoding).
+    NetworkLittleEndian,
+}
+
+/// How strings are encoded.
+///
+/// Java Edition writes strings in Java's Modified UTF-8, in which the null
+/// character is the two bytes `C0 80`, and characters outside the Basic
+/// Multilingual Plane, such as emoji, are a pair of 3-byte surrogates rather
+/// than 4 bytes. Bedrock Edition uses standard UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringEncoding {
+    /// Modified UTF-8, the default. When reading, strings that are valid
+    /// standard UTF-8 are accepted as well.
+    #[default]
+    ModifiedUtf8,
+    /// Standard UTF-8 only.
+    Utf8,
 }
 
-pub(crate) struct RawWriter<W: io::Write> {
+/// A writer for the primitive components of the NBT format.
+///
+/// Each `write_bare_*` method writes a single payload of the corresponding
+/// type with no header. See the [module documentation](index.html) for the
+/// rules on combining them.
+pub struct RawWriter<W: io::Write> {
     inner: W,
     endian: Endianness,
+    depth: usize,
+    sort_keys: bool,
+    string_encoding: StringEncoding,
 }
 
 impl<W> RawWriter<W>
     where W: io::Write,
 {
+    /// Creates a writer for the given `io::Write` destination.
     pub fn new(inner: W, endian: Endianness) -> Self {
-        RawWriter { inner, endian }
+        RawWriter { inner, endian, depth: 0, sort_keys: false, string_encoding: StringEncoding::default() }
+    }
+
+    /// Gets a reference to the underlying `io::Write` destination.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying `io::Write` destination.
+    ///
+    /// Writing to it directly is allowed, for instance to interleave other
+    /// framing, but must not happen in the middle of a payload.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this writer, returning the underlying `io::Write` destination.
+    ///
+    /// In debug builds, this panics if any Compound is still open, as the
+    /// output would then be missing a `TAG_End`.
+    pub fn into_inner(self) -> W {
+        debug_assert!(self.depth == 0,
+                      "RawWriter::into_inner called with {} open Compound(s): missing TAG_End",
+                      self.depth);
+        self.inner
+    }
+
+    /// Sets whether `Value`s, `Blob`s and `ToNbt` maps written through this
+    /// writer emit Compound entries sorted by key, rather than in the order
+    /// their map happens to iterate in. This makes the output for equal
+    /// values byte-for-byte identical. It is off by default.
+    ///
+    /// This only affects the types that write whole maps themselves; entries
+    /// written with `write_header()` are written as they come.
+    pub fn sort_keys(&mut self, sort: bool) {
+        self.sort_keys = sort;
+    }
+
+    /// Whether Compound entries are written sorted by key. See `sort_keys()`.
+    pub fn sorts_keys(&self) -> bool {
+        self.sort_keys
+    }
+
+    /// Sets how strings are encoded, which is Modified UTF-8 unless changed.
+    /// Files for Bedrock Edition should use `StringEncoding::Utf8`.
+    pub fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.string_encoding = encoding;
+    }
+
+    /// How strings are encoded. See `set_string_encoding()`.
+    pub fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
+    }
+
+    /// Writes a Compound payload with the given entries, sorting them by key
+    /// if `sort_keys()` is set. `write` must write the header and payload of
+    /// each entry.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_entries<'a, I, V, F>(&mut self, entries: I, mut write: F) -> Result<()>
+    where
+        I: Iterator<Item = (&'a String, &'a V)>,
+        V: 'a,
+        F: FnMut(&mut Self, &'a str, &'a V) -> Result<()>,
+    {
+        self.begin_compound();
+        if self.sort_keys {
+            let mut entries: Vec<_> = entries.collect();
+            entries.sort_by_key(|&(name, _)| name);
+            for (name, value) in entries {
+                write(self, name, value)?;
+            }
+        } else {
+            for (name, value) in entries {
+                write(self, name, value)?;
+            }
+        }
+        self.close_nbt()
+    }
+
+    /// The number of Compound payloads that have been opened with
+    /// `begin_compound()` but not yet closed.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Writes the header of a named tag: the type ID byte followed by the
+    /// name, encoded as by `write_bare_string()`.
+    ///
+    /// The payload must be written next. `TAG_End` has no header, so it is
+    /// written with `close_nbt()` instead.
+    pub fn write_header(&mut self, tag: i8, name: &str) -> Result<()> {
+        debug_assert!(tag != 0x00, "TAG_End must be written with close_nbt()");
+        self.write_bare_byte(tag)?;
+        self.write_bare_string(name)
+    }
+
+    /// Marks the start of a Compound payload.
+    ///
+    /// This writes nothing, since a Compound payload has no prefix; but every
+    /// call must be paired with a later `close_nbt()`.
+    pub fn begin_compound(&mut self) {
+        self.depth += 1;
     }
 
     /// A convenience function for closing NBT format objects.
     ///
     /// This function writes a single `0x00` byte to the `io::Write` destination,
     /// which in the NBT format indicates that an open Compound is now closed.
+    ///
+    /// In debug builds, this panics if there is no Compound open.
     pub fn close_nbt(&mut self) -> Result<()>
     {
-        self.inner.write_u8(0x00).map_err(From::from)
+        debug_assert!(self.depth > 0,
+                      "close_nbt() called without a matching begin_compound()");
+        self.depth = self.depth.saturating_sub(1);
+        self.inner.write_all(&[0x00]).map_err(From::from)
     }
 
+    /// Writes a `TAG_Byte` payload: one byte, in two's complement.
     #[inline]
     pub fn write_bare_byte(&mut self, value: i8) -> Result<()>
     {
-        self.inner.write_i8(value).map_err(From::from)
+        self.inner.write_all(&[value as u8]).map_err(From::from)
     }
 
+    /// Writes a `TAG_Short` payload: two bytes, most significant first for
+    /// `BigEndian` and least significant first for `LittleEndian`.
     #[inline]
     pub fn write_bare_short(&mut self, value: i16) -> Result<()>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.write_i16::<byteorder::LittleEndian>(value).map_err(From::from),
-            Endianness::BigEndian => self.inner.write_i16::<byteorder::BigEndian>(value).map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => self.inner.write_all(&value.to_le_bytes()).map_err(From::from),
+            Endianness::BigEndian => self.inner.write_all(&value.to_be_bytes()).map_err(From::from),
         }
     }
 
+    /// Writes a `TAG_Int` payload: four bytes in the configured byte order, or
+    /// a ZigZag-encoded VarInt of up to five bytes for `NetworkLittleEndian`.
     #[inline]
     pub fn write_bare_int(&mut self, value: i32) -> Result<()>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.write_i32::<byteorder::LittleEndian>(value).map_err(From::from),
-            Endianness::BigEndian => self.inner.write_i32::<byteorder::BigEndian>(value).map_err(From::from),
+            Endianness::NetworkLittleEndian => self.write_varint(zigzag_encode(value.into())),
+            Endianness::LittleEndian => self.inner.write_all(&value.to_le_bytes()).map_err(From::from),
+            Endianness::BigEndian => self.inner.write_all(&value.to_be_bytes()).map_err(From::from),
         }
     }
 
+    /// Writes a `TAG_Long` payload: eight bytes in the configured byte order,
+    /// or a ZigZag-encoded VarInt of up to ten bytes for
+    /// `NetworkLittleEndian`.
     #[inline]
     pub fn write_bare_long(&mut self, value: i64) -> Result<()>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.write_i64::<byteorder::LittleEndian>(value).map_err(From::from),
-            Endianness::BigEndian => self.inner.write_i64::<byteorder::BigEndian>(value).map_err(From::from),
+            Endianness::NetworkLittleEndian => self.write_varint(zigzag_encode(value)),
+            Endianness::LittleEndian => self.inner.write_all(&value.to_le_bytes()).map_err(From::from),
+            Endianness::BigEndian => self.inner.write_all(&value.to_be_bytes()).map_err(From::from),
         }
     }
 
+    /// Writes a `TAG_Float` payload: an IEEE 754 single, as four bytes in the
+    /// configured byte order.
     #[inline]
     pub fn write_bare_float(&mut self, value: f32) -> Result<()>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.write_f32::<byteorder::LittleEndian>(value).map_err(From::from),
-            Endianness::BigEndian => self.inner.write_f32::<byteorder::BigEndian>(value).map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => self.inner.write_all(&value.to_le_bytes()).map_err(From::from),
+            Endianness::BigEndian => self.inner.write_all(&value.to_be_bytes()).map_err(From::from),
         }
     }
 
+    /// Writes a `TAG_Double` payload: an IEEE 754 double, as eight bytes in
+    /// the configured byte order.
     #[inline]
     pub fn write_bare_double(&mut self, value: f64) -> Result<()>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.write_f64::<byteorder::LittleEndian>(value).map_err(From::from),
-            Endianness::BigEndian => self.inner.write_f64::<byteorder::BigEndian>(value).map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => self.inner.write_all(&value.to_le_bytes()).map_err(From::from),
+            Endianness::BigEndian => self.inner.write_all(&value.to_be_bytes()).map_err(From::from),
         }
     }
 
+    /// Writes a `TAG_Byte_Array` payload: the length as by `write_bare_int()`,
+    /// then one byte per element.
     #[inline]
     pub fn write_bare_byte_array(&mut self, value: &[i8]) -> Result<()>
     {
         self.write_bare_int(value.len() as i32)?;
-        for &v in value {
-            self.write_bare_byte(v)?;
-        }
-        Ok(())
+        self.write_array(value, |byte| [byte as u8])
     }
 
+    /// Writes a `TAG_Int_Array` payload: the length as by `write_bare_int()`,
+    /// then four bytes per element in the configured byte order.
     #[inline]
     pub fn write_bare_int_array(&mut self, value: &[i32]) -> Result<()>
     {
         self.write_bare_int(value.len() as i32)?;
-        for &v in value {
-            self.write_bare_int(v)?;
-        }
-        Ok(())
+        self.write_int_elements(value)
     }
 
+    /// Writes a `TAG_Long_Array` payload: the length as by `write_bare_int()`,
+    /// then eight bytes per element in the configured byte order.
     #[inline]
     pub fn write_bare_long_array(&mut self, value: &[i64]) -> Result<()>
     {
         self.write_bare_int(value.len() as i32)?;
-        for &v in value {
-            self.write_bare_long(v)?;
-        }
-        Ok(())
+        self.write_long_elements(value)
     }
 
+    /// Writes a `TAG_String` payload: the length in bytes as an unsigned
+    /// `TAG_Short` (or as an unsigned VarInt for `NetworkLittleEndian`), then
+    /// the string in Java's Modified UTF-8, or in standard UTF-8 if set with
+    /// `set_string_encoding()`.
+    ///
+    /// Strings longer than 65535 bytes once encoded cannot be represented
+    /// outside of `NetworkLittleEndian`, and fail with
+    /// `Error::LengthLimitExceeded`.
     #[inline]
     pub fn write_bare_string(&mut self, value: &str) -> Result<()>
     {
-        let encoded = to_java_cesu8(value);
-        self.write_bare_short(encoded.len() as i16)?;
+        let encoded = match self.string_encoding {
+            StringEncoding::ModifiedUtf8 => mutf8::encode(value),
+            StringEncoding::Utf8 => Cow::Borrowed(value.as_bytes()),
+        };
+        match self.endian {
+            Endianness::NetworkLittleEndian => self.write_varint(encoded.len() as u64)?,
+            _ if encoded.len() > u16::MAX as usize =>
+                return Err(Error::LengthLimitExceeded(u16::MAX as usize)),
+            _ => self.write_bare_short(encoded.len() as u16 as i16)?,
+        }
         self.inner.write_all(&encoded).map_err(From::from)
     }
 
+    /// Writes a List or array length in a fixed number of bytes, so that it
+    /// can be overwritten once the real length is known: as by
+    /// `write_bare_int()`, except that for `NetworkLittleEndian` the VarInt is
+    /// padded to five bytes with continuation bits.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_fixed_len(&mut self, len: i32) -> Result<()> {
+        if let Endianness::NetworkLittleEndian = self.endian {
+            let mut value = zigzag_encode(len.into());
+            let mut buf = [0x80; 5];
+            for byte in &mut buf {
+                *byte |= value as u8 & 0x7f;
+                value >>= 7;
+            }
+            buf[4] &= 0x7f;
+            return self.inner.write_all(&buf).map_err(From::from);
+        }
+        self.write_bare_int(len)
+    }
+
+    /// Writes an unsigned VarInt.
+    fn write_varint(&mut self, value: u64) -> Result<()> {
+        let (buf, len) = encode_varint(value);
+        self.inner.write_all(&buf[..len]).map_err(From::from)
+    }
+
+    /// Writes the elements of a `TAG_Int_Array` payload.
+    fn write_int_elements(&mut self, value: &[i32]) -> Result<()> {
+        match self.endian {
+            Endianness::NetworkLittleEndian => self.write_varint_array(value, |v| zigzag_encode(v.into())),
+            Endianness::LittleEndian => self.write_array(value, i32::to_le_bytes),
+            Endianness::BigEndian => self.write_array(value, i32::to_be_bytes),
+        }
+    }
+
+    /// Writes the elements of a `TAG_Long_Array` payload.
+    fn write_long_elements(&mut self, value: &[i64]) -> Result<()> {
+        match self.endian {
+            Endianness::NetworkLittleEndian => self.write_varint_array(value, zigzag_encode),
+            Endianness::LittleEndian => self.write_array(value, i64::to_le_bytes),
+            Endianness::BigEndian => self.write_array(value, i64::to_be_bytes),
+        }
+    }
+
+    /// Writes fixed-size array elements, converted to bytes with `convert`,
+    /// with a single `write_all()` of one contiguous buffer.
+    fn write_array<T: Copy, const N: usize>(&mut self, value: &[T], convert: fn(T) -> [u8; N]) -> Result<()> {
+        let mut buf = Vec::with_capacity(value.len() * N);
+        buf.extend(value.iter().flat_map(|&v| convert(v)));
+        self.inner.write_all(&buf).map_err(From::from)
+    }
+
+    /// Writes array elements as unsigned VarInts, with a single
+    /// `write_all()` as for `write_array()`.
+    fn write_varint_array<T: Copy>(&mut self, value: &[T], convert: fn(T) -> u64) -> Result<()> {
+        let mut buf = Vec::with_capacity(value.len() * 2);
+        for &v in value {
+            let (bytes, len) = encode_varint(convert(v));
+            buf.extend_from_slice(&bytes[..len]);
+        }
+        self.inner.write_all(&buf).map_err(From::from)
+    }
+}
+
+/// Encodes an unsigned VarInt, returning its bytes and how many are used.
+fn encode_varint(mut value: u64) -> ([u8; 10], usize) {
+    let mut buf = [0; 10];
+    let mut len = 0;
+    while value >= 0x80 {
+        buf[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buf[len] = value as u8;
+    (buf, len + 1)
+}
+
+/// The length of an unsigned VarInt.
+fn varint_len(value: u64) -> usize {
+    cmp::max(1, (64 - value.leading_zeros() as usize).div_ceil(7))
+}
+
+/// The number of bytes that `RawWriter::write_bare_int()` writes for `value`.
+pub(crate) fn int_len(endian: Endianness, value: i32) -> usize {
+    match endian {
+        Endianness::NetworkLittleEndian => varint_len(zigzag_encode(value.into())),
+        _ => 4,
+    }
+}
+
+/// The number of bytes that `RawWriter::write_bare_long()` writes for
+/// `value`.
+pub(crate) fn long_len(endian: Endianness, value: i64) -> usize {
+    match endian {
+        Endianness::NetworkLittleEndian => varint_len(zigzag_encode(value)),
+        _ => 8,
+    }
 }
 
-pub(crate) struct RawReader<R: io::Read> {
+/// The number of bytes that `RawWriter::write_bare_string()` writes for
+/// `value` in Modified UTF-8, including its length prefix.
+pub(crate) fn string_len(endian: Endianness, value: &str) -> usize {
+    let len = mutf8::encoded_len(value);
+    match endian {
+        Endianness::NetworkLittleEndian => varint_len(len as u64) + len,
+        _ => 2 + len,
+    }
+}
+
+/// The default for `RawReader::set_max_depth()`, which is the limit that
+/// Minecraft itself enforces.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// The most elements that are allocated for a List or array before any of
+/// them have been read. Larger payloads grow their buffer as they are read,
+/// so that a declared length far beyond the actual input cannot cause a huge
+/// allocation.
+pub(crate) const PREALLOC_LEN: usize = 4096;
+
+/// The most strings a `RawReader` interns. Further strings are still read,
+/// but not shared, so that input with endless distinct keys cannot grow the
+/// cache without bound.
+const MAX_INTERNED: usize = 4096;
+
+/// The longest string, in bytes of its encoding, that a `RawReader` interns.
+/// Keys are short; longer strings are rarely repeated.
+const MAX_INTERNED_LEN: usize = 64;
+
+/// A reader for the primitive components of the NBT format.
+///
+/// Each `read_bare_*` method reads a single payload of the corresponding type
+/// with no header. See the [module documentation](index.html) for details.
+pub struct RawReader<R: io::Read> {
     inner: R,
     endian: Endianness,
+    string_encoding: StringEncoding,
+    lossy_strings: bool,
+    offset: u64,
+    depth: usize,
+    max_depth: usize,
+    max_string_len: usize,
+    max_list_len: usize,
+    max_array_len: usize,
+    interned: Option<Interner>,
+}
+
+/// The strings a `RawReader` has interned, by the bytes of their encoding.
+#[derive(Default)]
+struct Interner {
+    strings: BTreeMap<Vec<u8>, Arc<str>>,
+    buf: Vec<u8>,
 }
 
 impl<R> RawReader<R>
     where R: io::Read,
 {
+    /// Creates a reader for the given `io::Read` source.
     pub fn new(inner: R, endian: Endianness) -> Self {
-        RawReader { inner, endian }
+        RawReader {
+            inner,
+            endian,
+            string_encoding: StringEncoding::default(),
+            lossy_strings: false,
+            offset: 0,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_string_len: usize::MAX,
+            max_list_len: usize::MAX,
+            max_array_len: usize::MAX,
+            interned: None,
+        }
+    }
+
+    /// Gets a reference to the underlying `io::Read` source.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying `io::Read` source.
+    ///
+    /// Reading from it directly is allowed, for instance to consume other
+    /// framing, but must not happen in the middle of a payload.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this reader, returning the underlying `io::Read` source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Sets the maximum number of Lists and Compounds that may be nested
+    /// inside one another, which is `DEFAULT_MAX_DEPTH` unless changed.
+    ///
+    /// Decoders other than `Value::from_raw_reader()` recurse once for each
+    /// level, so without a limit a small, crafted input could exhaust the
+    /// stack. Deeper input fails with `Error::DepthLimitExceeded` instead.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// The maximum nesting depth. See `set_max_depth()`.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets how strings are decoded, which is Modified UTF-8 unless changed.
+    /// With `StringEncoding::Utf8`, Modified UTF-8's encodings of the null
+    /// character and of surrogate pairs fail with `Error::InvalidUtf8`.
+    pub fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.string_encoding = encoding;
+        self.clear_interned();
+    }
+
+    /// How strings are decoded. See `set_string_encoding()`.
+    pub fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
+    }
+
+    /// Sets whether strings that are invalid in their encoding are decoded
+    /// anyway, with each offending byte replaced by U+FFFD REPLACEMENT
+    /// CHARACTER, rather than failing with `Error::InvalidUtf8`. Off by
+    /// default. The original bytes can be read with
+    /// `read_bare_string_bytes()` instead.
+    pub fn set_lossy_strings(&mut self, lossy: bool) {
+        self.lossy_strings = lossy;
+        self.clear_interned();
+    }
+
+    /// Whether invalid strings are decoded lossily. See
+    /// `set_lossy_strings()`.
+    pub fn lossy_strings(&self) -> bool {
+        self.lossy_strings
+    }
+
+    /// Sets whether `read_interned_string()` shares the strings it reads, so
+    /// that each distinct string is decoded and allocated only once. Off by
+    /// default. This is meant for Compound keys, which repeat across every
+    /// entity, block entity and item of a file.
+    ///
+    /// Only callers of `read_interned_string()` see the shared strings: the
+    /// serde `Decoder`, for one, lends them to visitors as `&str`, which lets
+    /// struct fields be matched without allocating. It saves time, not
+    /// memory, for anything that keeps the keys: `Value` and `Blob` read
+    /// strings with `read_bare_string()`, and their Compounds, like any
+    /// `HashMap<String, _>`, own a `String` for every key regardless.
+    ///
+    /// At most 4096 strings of up to 64 bytes each are kept, for as long as
+    /// the reader lives. Turning it off drops them.
+    pub fn set_intern_keys(&mut self, intern: bool) {
+        if !intern {
+            self.interned = None;
+        } else if self.interned.is_none() {
+            self.interned = Some(Interner::default());
+        }
+    }
+
+    /// Whether strings are interned. See `set_intern_keys()`.
+    pub fn interns_keys(&self) -> bool {
+        self.interned.is_some()
+    }
+
+    /// Sets the maximum length of a `TAG_String` payload, in bytes of
+    /// its encoding. There is no limit by default, although outside of
+    /// `NetworkLittleEndian` strings cannot be longer than 65535 bytes anyway.
+    ///
+    /// Longer strings fail with `Error::LengthLimitExceeded` as soon as their
+    /// length is read, before anything is allocated for them.
+    pub fn set_max_string_len(&mut self, max_len: usize) {
+        self.max_string_len = max_len;
+    }
+
+    /// The maximum length of a string. See `set_max_string_len()`.
+    pub fn max_string_len(&self) -> usize {
+        self.max_string_len
+    }
+
+    /// Sets the maximum number of elements in a List, which is unlimited by
+    /// default. Longer Lists fail with `Error::LengthLimitExceeded` as soon as
+    /// their length is read.
+    pub fn set_max_list_len(&mut self, max_len: usize) {
+        self.max_list_len = max_len;
+    }
+
+    /// The maximum length of a List. See `set_max_list_len()`.
+    pub fn max_list_len(&self) -> usize {
+        self.max_list_len
+    }
+
+    /// Sets the maximum number of elements in a `TAG_Byte_Array`,
+    /// `TAG_Int_Array` or `TAG_Long_Array`, which is unlimited by default.
+    /// Longer arrays fail with `Error::LengthLimitExceeded` as soon as their
+    /// length is read.
+    ///
+    /// Even without a limit, buffers for Lists and arrays grow as elements are
+    /// read rather than being allocated up front, so a declared length only
+    /// costs memory once the input actually contains that many elements.
+    pub fn set_max_array_len(&mut self, max_len: usize) {
+        self.max_array_len = max_len;
+    }
+
+    /// The maximum length of an array. See `set_max_array_len()`.
+    pub fn max_array_len(&self) -> usize {
+        self.max_array_len
+    }
+
+    /// The number of List and Compound payloads that have been entered with
+    /// `begin_nested()` but not yet left.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The number of bytes read through this reader so far. Bytes are only
+    /// counted once the `read_exact()` that asked for them has succeeded, so
+    /// after a failed read this is the offset at which that read started,
+    /// even though the source may already have consumed part of it.
+    ///
+    /// Bytes read directly from the underlying source with `get_mut()` are
+    /// not counted.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Marks the start of a List or Compound payload, failing if that would
+    /// exceed the maximum depth.
+    ///
+    /// Decoders that recurse into nested payloads should call this before
+    /// doing so, and `end_nested()` once the payload has been read. After an
+    /// error the reader is left mid-payload anyway, so the two calls need not
+    /// be paired on that path.
+    pub fn begin_nested(&mut self) -> Result<()> {
+        if self.depth >= self.max_depth {
+            return Err(Error::DepthLimitExceeded(self.max_depth));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Marks the end of a List or Compound payload started with
+    /// `begin_nested()`.
+    pub fn end_nested(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
     }
 
     /// Extracts the next header (tag and name) from an NBT format source.
     ///
     /// This function will also return the `TAG_End` byte and an empty name if it
-    /// encounters it.
+    /// encounters it. It must only be called where a named tag is expected;
+    /// calling it in the middle of a payload will misinterpret the data.
     pub fn emit_next_header(&mut self) -> Result<(i8, String)>
     {
-        let tag  = self.inner.read_i8()?;
+        let tag  = self.read_bare_byte()?;
 
         match tag {
             0x00 => { Ok((tag, "".to_string())) },
@@ -154,111 +754,363 @@ impl<R> RawReader<R>
         }
     }
 
+    /// Reads a `TAG_Byte` payload. See `RawWriter::write_bare_byte()`.
     #[inline]
     pub fn read_bare_byte(&mut self) -> Result<i8>
     {
-        self.inner.read_i8().map_err(From::from)
+        Ok(i8::from_be_bytes(self.read_bytes()?))
     }
 
+    /// Reads a `TAG_Short` payload. See `RawWriter::write_bare_short()`.
     #[inline]
     pub fn read_bare_short(&mut self) -> Result<i16>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.read_i16::<byteorder::LittleEndian>().map_err(From::from),
-            Endianness::BigEndian => self.inner.read_i16::<byteorder::BigEndian>().map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => Ok(i16::from_le_bytes(self.read_bytes()?)),
+            Endianness::BigEndian => Ok(i16::from_be_bytes(self.read_bytes()?)),
         }
     }
 
+    /// Reads a `TAG_Int` payload. See `RawWriter::write_bare_int()`.
     #[inline]
     pub fn read_bare_int(&mut self) -> Result<i32>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.read_i32::<byteorder::LittleEndian>().map_err(From::from),
-            Endianness::BigEndian => self.inner.read_i32::<byteorder::BigEndian>().map_err(From::from),
+            Endianness::NetworkLittleEndian => Ok(zigzag_decode(self.read_varint(5)?) as i32),
+            Endianness::LittleEndian => Ok(i32::from_le_bytes(self.read_bytes()?)),
+            Endianness::BigEndian => Ok(i32::from_be_bytes(self.read_bytes()?)),
         }
     }
 
+    /// Reads a `TAG_Long` payload. See `RawWriter::write_bare_long()`.
     #[inline]
     pub fn read_bare_long(&mut self) -> Result<i64>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.read_i64::<byteorder::LittleEndian>().map_err(From::from),
-            Endianness::BigEndian => self.inner.read_i64::<byteorder::BigEndian>().map_err(From::from),
+            Endianness::NetworkLittleEndian => Ok(zigzag_decode(self.read_varint(10)?)),
+            Endianness::LittleEndian => Ok(i64::from_le_bytes(self.read_bytes()?)),
+            Endianness::BigEndian => Ok(i64::from_be_bytes(self.read_bytes()?)),
         }
     }
 
+    /// Reads a `TAG_Float` payload. See `RawWriter::write_bare_float()`.
     #[inline]
     pub fn read_bare_float(&mut self) -> Result<f32>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.read_f32::<byteorder::LittleEndian>().map_err(From::from),
-            Endianness::BigEndian => self.inner.read_f32::<byteorder::BigEndian>().map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => Ok(f32::from_le_bytes(self.read_bytes()?)),
+            Endianness::BigEndian => Ok(f32::from_be_bytes(self.read_bytes()?)),
         }
     }
 
+    /// Reads a `TAG_Double` payload. See `RawWriter::write_bare_double()`.
     #[inline]
     pub fn read_bare_double(&mut self) -> Result<f64>
     {
         match self.endian {
-            Endianness::LittleEndian => self.inner.read_f64::<byteorder::LittleEndian>().map_err(From::from),
-            Endianness::BigEndian => self.inner.read_f64::<byteorder::BigEndian>().map_err(From::from),
+            Endianness::LittleEndian | Endianness::NetworkLittleEndian => Ok(f64::from_le_bytes(self.read_bytes()?)),
+            Endianness::BigEndian => Ok(f64::from_be_bytes(self.read_bytes()?)),
         }
     }
 
+    /// Reads a `TAG_Byte_Array` payload. See
+    /// `RawWriter::write_bare_byte_array()`.
     #[inline]
     pub fn read_bare_byte_array(&mut self) -> Result<Vec<i8>>
     {
-        // FIXME: Is there a way to return [u8; len]?
-        let len = self.read_bare_int()? as usize;
-        let mut buf = Vec::with_capacity(len);
-        // FIXME: Test performance vs transmute.
-        for _ in 0..len {
-            buf.push(self.read_bare_byte()?);
-        }
-        Ok(buf)
+        let len = self.read_array_len()?;
+        self.read_array(len, |[byte]| byte as i8)
     }
 
+    /// Reads a `TAG_Int_Array` payload. See
+    /// `RawWriter::write_bare_int_array()`.
     #[inline]
     pub fn read_bare_int_array(&mut self) -> Result<Vec<i32>>
     {
-        // FIXME: Is there a way to return [i32; len]?
-        let len = self.read_bare_int()? as usize;
-        let mut buf = Vec::with_capacity(len);
-        // FIXME: Test performance vs transmute.
-        for _ in 0..len {
-            buf.push(self.read_bare_int()?);
-        }
-        Ok(buf)
+        let len = self.read_array_len()?;
+        self.read_int_elements(len)
     }
 
+    /// Reads a `TAG_Long_Array` payload. See
+    /// `RawWriter::write_bare_long_array()`.
     #[inline]
     pub fn read_bare_long_array(&mut self) -> Result<Vec<i64>>
     {
-        let len = self.read_bare_int()? as usize;
-        let mut buf = Vec::with_capacity(len);
-        for _ in 0..len {
-            buf.push(self.read_bare_long()?);
-        }
-        Ok(buf)
+        let len = self.read_array_len()?;
+        self.read_long_elements(len)
     }
 
+    /// Reads a `TAG_String` payload. See `RawWriter::write_bare_string()`.
+    ///
+    /// By default both Modified UTF-8 and standard UTF-8 input are accepted;
+    /// see `set_string_encoding()` and `set_lossy_strings()`.
     #[inline]
     pub fn read_bare_string(&mut self) -> Result<String>
     {
-        let len = self.read_bare_short()? as usize;
+        let bytes = self.read_bare_string_bytes()?;
+
+        // Most strings are plain UTF-8, which needs no copy.
+        match String::from_utf8(bytes) {
+            Ok(text) => Ok(text),
+            Err(e) => self.decode_str(e.as_bytes()).map(Cow::into_owned),
+        }
+    }
+
+    /// Reads a `TAG_String` payload as a shared string. With
+    /// `set_intern_keys(true)`, a string read before is returned without
+    /// decoding it again, as another reference to the same allocation;
+    /// otherwise this is `read_bare_string()`.
+    ///
+    /// ```rust
+    /// use std::sync::Arc;
+    /// use nbt::raw::{Endianness, RawReader};
+    ///
+    /// let bytes = [0, 2, b'i', b'd', 0, 2, b'i', b'd'];
+    /// let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    /// src.set_intern_keys(true);
+    /// let first = src.read_interned_string().unwrap();
+    /// let second = src.read_interned_string().unwrap();
+    /// assert_eq!(&*first, "id");
+    /// assert!(Arc::ptr_eq(&first, &second));
+    /// ```
+    pub fn read_interned_string(&mut self) -> Result<Arc<str>> {
+        let mut interned = match self.interned.take() {
+            Some(interned) => interned,
+            None => return self.read_bare_string().map(Arc::from),
+        };
+        let result = self.read_interned_into(&mut interned);
+        self.interned = Some(interned);
+        result
+    }
+
+    fn read_interned_into(&mut self, interned: &mut Interner) -> Result<Arc<str>> {
+        self.read_string_into(&mut interned.buf)?;
+        if let Some(string) = interned.strings.get(&interned.buf) {
+            return Ok(string.clone());
+        }
+        let string: Arc<str> = match str::from_utf8(&interned.buf) {
+            Ok(text) => Arc::from(text),
+            Err(_) => Arc::from(&*self.decode_str(&interned.buf)?),
+        };
+        if interned.strings.len() < MAX_INTERNED && interned.buf.len() <= MAX_INTERNED_LEN {
+            interned.strings.insert(interned.buf.clone(), string.clone());
+        }
+        Ok(string)
+    }
+
+    /// Forgets the interned strings, which are stale once strings decode
+    /// differently.
+    fn clear_interned(&mut self) {
+        if let Some(ref mut interned) = self.interned {
+            interned.strings.clear();
+        }
+    }
+
+    /// Reads a `TAG_String` payload without decoding it, returning the bytes
+    /// of its encoding as they are, valid or not.
+    pub fn read_bare_string_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.read_string_into(&mut bytes)?;
+        Ok(bytes)
+    }
 
-        if len == 0 { return Ok("".to_string()); }
+    /// Reads the bytes of a `TAG_String` payload into `bytes`, replacing its
+    /// contents, so that one buffer can be reused for many strings.
+    pub(crate) fn read_string_into(&mut self, bytes: &mut Vec<u8>) -> Result<()> {
+        let len = self.read_string_len()?;
 
-        let mut bytes = vec![0; len];
-        let mut n_read = 0usize;
-        while n_read < bytes.len() {
-            match self.inner.read(&mut bytes[n_read..])? {
-                0 => return Err(Error::IncompleteNbtValue),
-                n => n_read += n
+        // Strings are short unless the input is crafted, so read them in one
+        // go where possible, but never trust a length beyond what has been
+        // read so far.
+        bytes.clear();
+        bytes.reserve(len.min(PREALLOC_LEN));
+        while bytes.len() < len {
+            let start = bytes.len();
+            bytes.resize(start + cmp::min(len - start, PREALLOC_LEN), 0);
+            self.fill(&mut bytes[start..])?;
+        }
+        Ok(())
+    }
+
+    /// Reads past an array payload of the given type ID without keeping its
+    /// elements, returning how many there were.
+    #[cfg(feature = "std")]
+    pub(crate) fn skip_array(&mut self, id: i8) -> Result<usize> {
+        let len = self.read_array_len()?;
+        let width = match (id, self.endian) {
+            (0x07, _) => 1,
+            (0x0b, Endianness::NetworkLittleEndian) => {
+                for _ in 0..len {
+                    self.read_varint(5)?;
+                }
+                return Ok(len);
+            },
+            (0x0c, Endianness::NetworkLittleEndian) => {
+                for _ in 0..len {
+                    self.read_varint(10)?;
+                }
+                return Ok(len);
+            },
+            (0x0b, _) => 4,
+            (0x0c, _) => 8,
+            _ => return Err(Error::InvalidTypeId(id)),
+        };
+        let mut buf = [0; 4096];
+        let mut remaining = len as u64 * width;
+        while remaining > 0 {
+            let n = cmp::min(remaining, buf.len() as u64) as usize;
+            self.fill(&mut buf[..n])?;
+            remaining -= n as u64;
+        }
+        Ok(len)
+    }
+
+    /// Copies an array payload of the given type ID to `dst` in its byte
+    /// order, holding at most `PREALLOC_LEN` elements at a time, and returns
+    /// how many there were.
+    #[cfg(feature = "std")]
+    pub(crate) fn copy_array<W: io::Write>(&mut self, id: i8, dst: &mut RawWriter<W>) -> Result<usize> {
+        let len = self.read_array_len()?;
+        dst.write_bare_int(len as i32)?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = cmp::min(remaining, PREALLOC_LEN);
+            match id {
+                0x07 => dst.write_array(&self.read_array(n, |[byte]| byte)?, |byte| [byte])?,
+                0x0b => dst.write_int_elements(&self.read_int_elements(n)?)?,
+                0x0c => dst.write_long_elements(&self.read_long_elements(n)?)?,
+                _ => return Err(Error::InvalidTypeId(id)),
             }
+            remaining -= n;
         }
+        Ok(len)
+    }
+
+    /// Decodes the bytes of a string as set by `set_string_encoding()` and
+    /// `set_lossy_strings()`.
+    pub(crate) fn decode_str<'a>(&self, bytes: &'a [u8]) -> Result<Cow<'a, str>> {
+        match (self.string_encoding, self.lossy_strings) {
+            (StringEncoding::ModifiedUtf8, false) => mutf8::decode(bytes),
+            (StringEncoding::ModifiedUtf8, true) => Ok(mutf8::decode_lossy(bytes)),
+            (StringEncoding::Utf8, false) => str::from_utf8(bytes).map(Cow::Borrowed).map_err(|_| Error::InvalidUtf8),
+            (StringEncoding::Utf8, true) => Ok(String::from_utf8_lossy(bytes)),
+        }
+    }
+
+    /// Reads the length prefix of a `TAG_String` payload, in bytes.
+    pub(crate) fn read_string_len(&mut self) -> Result<usize> {
+        let len = match self.endian {
+            Endianness::NetworkLittleEndian => self.read_varint(5)? as usize,
+            // The length is unsigned, as with Java's `DataInput.readUTF()`.
+            _ => self.read_bare_short()? as u16 as usize,
+        };
+        check_len(len, self.max_string_len)
+    }
+
+    /// Reads the element count of a List payload.
+    pub(crate) fn read_list_len(&mut self) -> Result<usize> {
+        let len = self.read_bare_int()?;
+        check_len(non_negative(len)?, self.max_list_len)
+    }
+
+    /// Reads the element count of an array payload.
+    pub(crate) fn read_array_len(&mut self) -> Result<usize> {
+        let len = self.read_bare_int()?;
+        check_len(non_negative(len)?, self.max_array_len)
+    }
+
+    /// Fills `buf` from the source, counting the bytes read.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.inner.read_exact(buf)?;
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    /// Reads the bytes of a fixed-size number.
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.fill(&mut buf)?;
+        Ok(buf)
+    }
 
-        let decoded = from_java_cesu8(&bytes)?;
-        Ok(decoded.into_owned())
+    /// Reads `len` fixed-size array elements, converting each from its bytes
+    /// with `convert`.
+    ///
+    /// The bytes are read with one `read_exact()` per `PREALLOC_LEN`
+    /// elements rather than one per element, and converted a whole chunk at a
+    /// time, which the compiler turns into vectorized byte swaps.
+    fn read_array<T, const N: usize>(&mut self, len: usize, convert: fn([u8; N]) -> T) -> Result<Vec<T>> {
+        let mut buf = Vec::with_capacity(len.min(PREALLOC_LEN));
+        let mut bytes = vec![0; len.min(PREALLOC_LEN) * N];
+        while buf.len() < len {
+            let chunk = &mut bytes[..cmp::min(len - buf.len(), PREALLOC_LEN) * N];
+            self.fill(chunk)?;
+            buf.extend(chunk.chunks_exact(N).map(|element| convert(<[u8; N]>::try_from(element).unwrap())));
+        }
+        Ok(buf)
     }
+
+    /// Reads `len` elements of a `TAG_Int_Array` payload.
+    fn read_int_elements(&mut self, len: usize) -> Result<Vec<i32>> {
+        match self.endian {
+            Endianness::NetworkLittleEndian => self.read_varint_array(len, Self::read_bare_int),
+            Endianness::LittleEndian => self.read_array(len, i32::from_le_bytes),
+            Endianness::BigEndian => self.read_array(len, i32::from_be_bytes),
+        }
+    }
+
+    /// Reads `len` elements of a `TAG_Long_Array` payload.
+    fn read_long_elements(&mut self, len: usize) -> Result<Vec<i64>> {
+        match self.endian {
+            Endianness::NetworkLittleEndian => self.read_varint_array(len, Self::read_bare_long),
+            Endianness::LittleEndian => self.read_array(len, i64::from_le_bytes),
+            Endianness::BigEndian => self.read_array(len, i64::from_be_bytes),
+        }
+    }
+
+    /// Reads `len` VarInt array elements one at a time, as their sizes vary.
+    fn read_varint_array<T>(&mut self, len: usize, read: fn(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let mut buf = Vec::with_capacity(len.min(PREALLOC_LEN));
+        for _ in 0..len {
+            buf.push(read(self)?);
+        }
+        Ok(buf)
+    }
+
+    /// Reads an unsigned VarInt of at most `max_len` bytes.
+    fn read_varint(&mut self, max_len: usize) -> Result<u64> {
+        let mut value = 0u64;
+        for i in 0..max_len {
+            let [byte] = self.read_bytes()?;
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::InvalidVarInt)
+    }
+}
+
+fn non_negative(len: i32) -> Result<usize> {
+    if len < 0 {
+        return Err(Error::InvalidLength(len));
+    }
+    Ok(len as usize)
+}
+
+fn check_len(len: usize, max_len: usize) -> Result<usize> {
+    if len > max_len {
+        return Err(Error::LengthLimitExceeded(max_len));
+    }
+    Ok(len)
+}
+
+/// Maps signed integers to unsigned ones, interleaving positive and negative
+/// values so that both have short VarInt encodings.
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
 }
@@ -0,0 +1,667 @@
+//! Reading and writing chunks in Anvil region (`.mca`) files.
+//!
+//! A region file stores a 32×32 grid of chunks. It begins with two 4 KiB
+//! tables, each holding one big-endian 4-byte entry per chunk, indexed by
+//! `x + z * 32` in local chunk coordinates:
+//!
+//! * the *location* table, where each entry holds a 3-byte sector offset from
+//!   the start of the file followed by a 1-byte sector count (an all-zero entry
+//!   marks a chunk that has not been generated), and
+//! * the *timestamp* table, holding the time each chunk was last saved, in
+//!   seconds since the Unix epoch.
+//!
+//! The rest of the file is split into 4 KiB sectors. Each chunk starts with
+//! a 4-byte big-endian length, a 1-byte compression scheme (1 for gzip and 2
+//! for zlib with the default `compression` feature or `rust_backend`, 3 for
+//! uncompressed, 4 for LZ4 with the `experimental_lz4` feature), and then
+//! `length - 1` bytes holding the chunk's NBT in big-endian byte order.
+//!
+//! Chunks too large to fit in 255 sectors are instead stored in a separate
+//! file named `c.<x>.<z>.mcc` next to the region file, where `x` and `z` are
+//! world chunk coordinates. In this case the chunk's record in the region
+//! holds no payload, and its compression scheme has the `0x80` bit set.
+//! `RegionFile` reads and writes these files transparently when it knows
+//! where the region file lives and which region it is; see
+//! `RegionFile::with_external_dir()`.
+//!
+//! With the `memmap` feature on Unix, `RegionFile::open_mmap()` reads a
+//! region through a memory map instead.
+//!
+//! ```rust,no_run
+//! use nbt::region::RegionFile;
+//!
+//! let mut region = RegionFile::open("world/region/r.0.0.mca").unwrap();
+//! if let Some(chunk) = region.read_chunk(3, 7).unwrap() {
+//!     println!("{}", chunk["Status"]);
+//! }
+//! for chunk in region.chunks() {
+//!     let (x, z, blob) = chunk.unwrap();
+//!     println!("chunk ({}, {}): {:?}", x, z, blob.get("Status"));
+//! }
+//! ```
+//!
+//! Chunks are written with `RegionFile::write_chunk()`, which reuses the
+//! chunk's existing sectors if it still fits, and otherwise moves it to the
+//! first free run of sectors large enough to hold it, extending the file if
+//! necessary:
+//!
+//! ```rust,no_run
+//! # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+//! use nbt::Blob;
+//! use nbt::region::{Compression, RegionFile};
+//!
+//! let mut region = RegionFile::open_rw("world/region/r.0.0.mca").unwrap();
+//! let mut chunk = Blob::new();
+//! chunk.insert("Status", "minecraft:empty").unwrap();
+//! region.write_chunk(3, 7, &chunk, Compression::Zlib).unwrap();
+//! # }
+//! ```
+
+use std::fs::{File, OpenOptions};
+#[cfg(all(feature = "memmap", unix))]
+use std::io::Cursor;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use blob::Blob;
+use error::{Error, Result};
+#[cfg(all(feature = "memmap", unix))]
+use mmap::Mmap;
+use raw::Endianness;
+
+pub use options::Compression;
+use options::{CompressionLevel, ReadOptions, WriteOptions};
+
+/// The size in bytes of a region file sector.
+pub const SECTOR_LEN: u64 = 4096;
+
+/// The number of chunks along each side of a region.
+pub const REGION_WIDTH: i32 = 32;
+
+const CHUNK_COUNT: usize = 1024;
+
+/// The largest number of sectors a chunk can occupy, as the sector count is
+/// stored in a single byte.
+const MAX_CHUNK_SECTORS: u64 = 255;
+
+/// The flag set on the compression scheme of chunks stored in `.mcc` files.
+const EXTERNAL_FLAG: u8 = 0x80;
+
+/// Where the `.mcc` files of a region are stored.
+struct External {
+    dir: PathBuf,
+    region_x: i32,
+    region_z: i32,
+}
+
+impl External {
+    fn path(&self, index: usize) -> PathBuf {
+        let (x, z) = chunk_coords(index);
+        let x = self.region_x * REGION_WIDTH + x;
+        let z = self.region_z * REGION_WIDTH + z;
+        self.dir.join(format!("c.{}.{}.mcc", x, z))
+    }
+}
+
+/// An Anvil region file, read from (and optionally written to) any seekable
+/// source.
+///
+/// Chunk coordinates are taken modulo 32, so both local coordinates in
+/// `0..32` and world chunk coordinates can be passed.
+pub struct RegionFile<R> {
+    inner: R,
+    locations: Vec<u32>,
+    timestamps: Vec<u32>,
+    external: Option<External>,
+    level: CompressionLevel,
+}
+
+impl RegionFile<File> {
+    /// Opens the region file at `path` for reading.
+    ///
+    /// If the file is named `r.<x>.<z>.mca`, chunks stored in `.mcc` files in
+    /// the same directory can be read.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<RegionFile<File>> {
+        let path = path.as_ref();
+        let region = RegionFile::new(File::open(path)?)?;
+        Ok(region.with_external_path(path))
+    }
+
+    /// Opens the region file at `path` for reading and writing. If the file
+    /// does not exist or is empty, it is initialized as an empty region.
+    ///
+    /// If the file is named `r.<x>.<z>.mca`, chunks stored in `.mcc` files in
+    /// the same directory can be read, and oversized chunks are written to
+    /// them.
+    pub fn open_rw<P: AsRef<Path>>(path: P) -> Result<RegionFile<File>> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let region = if file.metadata()?.len() == 0 {
+            RegionFile::create_in(file)?
+        } else {
+            RegionFile::new(file)?
+        };
+        Ok(region.with_external_path(path))
+    }
+
+    /// Creates an empty region file at `path`, truncating any existing file.
+    /// External chunks are handled as for `open_rw()`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<RegionFile<File>> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        Ok(RegionFile::create_in(file)?.with_external_path(path))
+    }
+}
+
+#[cfg(all(feature = "memmap", unix))]
+impl RegionFile<Cursor<Mmap>> {
+    /// Opens the region file at `path` for reading through a memory map, so
+    /// that only the sectors of the chunks actually read are paged in, and
+    /// they are not copied into a buffer of their own first. Requires the
+    /// `memmap` feature, and is only available on Unix.
+    ///
+    /// Chunks stored in `.mcc` files are found as for `open()`.
+    ///
+    /// ```rust,no_run
+    /// use nbt::region::RegionFile;
+    ///
+    /// let mut region = unsafe { RegionFile::open_mmap("world/region/r.0.0.mca") }.unwrap();
+    /// for chunk in region.chunks() {
+    ///     let (x, z, blob) = chunk.unwrap();
+    ///     println!("chunk ({}, {}): {:?}", x, z, blob.get("Status"));
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// As for `Mmap::map()`, the file must not be modified or truncated while
+    /// the region is open.
+    pub unsafe fn open_mmap<P: AsRef<Path>>(path: P) -> Result<RegionFile<Cursor<Mmap>>> {
+        let path = path.as_ref();
+        let map = Mmap::map(&File::open(path)?)?;
+        let region = RegionFile::new(Cursor::new(map))?;
+        Ok(region.with_external_path(path))
+    }
+}
+
+impl<R> RegionFile<R>
+where
+    R: Read + Seek,
+{
+    /// Reads the location and timestamp tables from the start of `inner`.
+    pub fn new(mut inner: R) -> Result<RegionFile<R>> {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut locations = vec![0; CHUNK_COUNT];
+        inner.read_u32_into::<BigEndian>(&mut locations)?;
+        let mut timestamps = vec![0; CHUNK_COUNT];
+        inner.read_u32_into::<BigEndian>(&mut timestamps)?;
+        Ok(RegionFile {
+            inner,
+            locations,
+            timestamps,
+            external: None,
+            level: CompressionLevel::Default,
+        })
+    }
+
+    /// Whether the chunk at `(x, z)` is present in this region.
+    pub fn has_chunk(&self, x: i32, z: i32) -> bool {
+        self.locations[chunk_index(x, z)] != 0
+    }
+
+    /// Reads and decompresses the chunk at `(x, z)`, or returns `None` if it
+    /// is not present.
+    pub fn read_chunk(&mut self, x: i32, z: i32) -> Result<Option<Blob>> {
+        let index = chunk_index(x, z);
+        match self.read_payload(index)? {
+            Some((compression, data)) => decode_chunk(compression, &data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the time the chunk at `(x, z)` was last saved, in seconds since
+    /// the Unix epoch, or 0 if it is not present.
+    ///
+    /// This only consults the timestamp table read when the region was
+    /// opened, so it is cheap to use to skip unchanged chunks.
+    pub fn timestamp(&self, x: i32, z: i32) -> u32 {
+        self.timestamps[chunk_index(x, z)]
+    }
+
+    /// Returns an iterator over the local coordinates and timestamps of all
+    /// present chunks, in the order of the location table.
+    ///
+    /// ```rust,no_run
+    /// use nbt::region::RegionFile;
+    ///
+    /// let last_backup = 1_600_000_000;
+    /// let mut region = RegionFile::open("world/region/r.0.0.mca").unwrap();
+    /// let changed: Vec<_> = region.timestamps()
+    ///     .filter(|&(_, _, time)| time > last_backup)
+    ///     .map(|(x, z, _)| (x, z))
+    ///     .collect();
+    /// for (x, z) in changed {
+    ///     let chunk = region.read_chunk(x, z).unwrap().unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    pub fn timestamps(&self) -> impl Iterator<Item = (i32, i32, u32)> + '_ {
+        self.locations.iter().zip(&self.timestamps).enumerate()
+            .filter(|&(_, (&location, _))| location != 0)
+            .map(|(index, (_, &timestamp))| {
+                let (x, z) = chunk_coords(index);
+                (x, z, timestamp)
+            })
+    }
+
+    /// Returns an iterator over all present chunks and their local
+    /// coordinates, in the order of the location table.
+    pub fn chunks(&mut self) -> Chunks<'_, R> {
+        Chunks { region: self, index: 0 }
+    }
+
+    /// Returns a parallel iterator over all present chunks and their local
+    /// coordinates, decompressing and parsing them on the rayon thread pool.
+    ///
+    /// The compressed payloads are first read from the source one at a time,
+    /// so the whole compressed region is held in memory while the iterator is
+    /// alive. Failing to read a chunk does not prevent the others from being
+    /// decoded. Requires the `rayon` feature.
+    ///
+    /// ```rust,no_run
+    /// extern crate rayon;
+    /// # extern crate nbt;
+    /// use nbt::region::RegionFile;
+    /// use rayon::prelude::*;
+    ///
+    /// # fn main() {
+    /// let mut region = RegionFile::open("world/region/r.0.0.mca").unwrap();
+    /// let chunks: Vec<_> = region.chunks_par().collect::<Result<_, _>>().unwrap();
+    /// # let _: &Vec<(i32, i32, nbt::Blob)> = &chunks;
+    /// # }
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn chunks_par(&mut self) -> impl ParallelIterator<Item = Result<(i32, i32, Blob)>> {
+        let payloads: Vec<_> = (0..CHUNK_COUNT)
+            .filter_map(|index| match self.read_payload(index) {
+                Ok(Some(payload)) => Some((index, Ok(payload))),
+                Ok(None) => None,
+                Err(e) => Some((index, Err(e))),
+            })
+            .collect();
+        payloads.into_par_iter().map(|(index, payload)| {
+            let (x, z) = chunk_coords(index);
+            let (compression, data) = payload?;
+            decode_chunk(compression, &data).map(|blob| (x, z, blob))
+        })
+    }
+
+    /// Reads the compression scheme and still-compressed payload of the chunk
+    /// at `index`.
+    fn read_payload(&mut self, index: usize) -> Result<Option<(Compression, Vec<u8>)>> {
+        let mut record = match self.read_record(index)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        if record[4] & EXTERNAL_FLAG == 0 {
+            let compression = Compression::from_id(record[4])?;
+            record.drain(..5);
+            return Ok(Some((compression, record)));
+        }
+
+        let compression = Compression::from_id(record[4] & !EXTERNAL_FLAG)?;
+        let path = match self.external {
+            Some(ref external) => external.path(index),
+            None => {
+                let (x, z) = chunk_coords(index);
+                return Err(Error::ExternalChunk(x, z));
+            }
+        };
+        Ok(Some((compression, fs::read(path)?)))
+    }
+
+    /// Reads the stored bytes of the chunk at `index`: the length prefix, the
+    /// compression scheme, and the payload, without any trailing padding.
+    fn read_record(&mut self, index: usize) -> Result<Option<Vec<u8>>> {
+        let location = self.locations[index];
+        if location == 0 {
+            return Ok(None);
+        }
+        let (x, z) = chunk_coords(index);
+        let offset = u64::from(location >> 8);
+        let sectors = u64::from(location & 0xff);
+        if offset < 2 {
+            return Err(Error::CorruptChunk(x, z, "sector offset points into the header"));
+        }
+
+        self.inner.seek(SeekFrom::Start(offset * SECTOR_LEN))?;
+        let len = u64::from(self.inner.read_u32::<BigEndian>()?);
+        if len == 0 {
+            return Err(Error::CorruptChunk(x, z, "chunk has zero length"));
+        }
+        if len + 4 > sectors * SECTOR_LEN {
+            return Err(Error::CorruptChunk(x, z, "chunk is longer than its allocated sectors"));
+        }
+        let mut record = vec![0; (len + 4) as usize];
+        record[..4].copy_from_slice(&(len as u32).to_be_bytes());
+        self.inner.read_exact(&mut record[4..]).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => Error::CorruptChunk(x, z, "chunk extends past the end of the file"),
+            _ => Error::IoError(e),
+        })?;
+        Ok(Some(record))
+    }
+}
+
+impl<R> RegionFile<R>
+where
+    R: Read + Write + Seek,
+{
+    /// Writes the tables of an empty region to the start of `inner`.
+    pub fn create_in(mut inner: R) -> Result<RegionFile<R>> {
+        inner.seek(SeekFrom::Start(0))?;
+        inner.write_all(&[0; 2 * SECTOR_LEN as usize])?;
+        Ok(RegionFile {
+            inner,
+            locations: vec![0; CHUNK_COUNT],
+            timestamps: vec![0; CHUNK_COUNT],
+            external: None,
+            level: CompressionLevel::Default,
+        })
+    }
+
+    /// Sets the compression level used by `write_chunk()`, which is
+    /// `CompressionLevel::Default` unless changed.
+    pub fn set_compression_level(&mut self, level: CompressionLevel) {
+        self.level = level;
+    }
+
+    /// Compresses `blob` and stores it as the chunk at `(x, z)`, updating the
+    /// location table and setting the chunk's timestamp to the current time.
+    ///
+    /// Chunks whose compressed form needs more than 255 sectors (just under
+    /// 1 MiB) are written to a `.mcc` file if the region's location is known,
+    /// and yield `Error::ChunkTooLarge` otherwise. Region files have no
+    /// scheme for `Compression::Zstd`, which yields
+    /// `Error::UnknownChunkCompression`.
+    pub fn write_chunk(&mut self, x: i32, z: i32, blob: &Blob, compression: Compression) -> Result<()> {
+        if compression == Compression::Zstd {
+            // Region files have no scheme for Zstandard.
+            return Err(Error::UnknownChunkCompression(compression.id()));
+        }
+        let index = chunk_index(x, z);
+        let mut data = encode_chunk(compression, self.level, blob)?;
+        let mut scheme = compression.id();
+        if (data.len() as u64 + 5).div_ceil(SECTOR_LEN) > MAX_CHUNK_SECTORS {
+            match self.external {
+                Some(ref external) => fs::write(external.path(index), &data)?,
+                None => {
+                    let (x, z) = chunk_coords(index);
+                    return Err(Error::ChunkTooLarge(x, z));
+                }
+            }
+            data.clear();
+            scheme |= EXTERNAL_FLAG;
+        } else {
+            self.remove_external(index)?;
+        }
+
+        // The length prefix counts the compression byte but not itself.
+        let len = data.len() as u64 + 1;
+        let sectors = (len + 4).div_ceil(SECTOR_LEN);
+        let offset = self.allocate(index, sectors);
+        self.inner.seek(SeekFrom::Start(offset * SECTOR_LEN))?;
+        self.inner.write_u32::<BigEndian>(len as u32)?;
+        self.inner.write_u8(scheme)?;
+        self.inner.write_all(&data)?;
+        // Pad to a whole number of sectors, as Minecraft expects.
+        let padding = sectors * SECTOR_LEN - (len + 4);
+        self.inner.write_all(&vec![0; padding as usize])?;
+
+        self.set_location(index, (offset as u32) << 8 | sectors as u32)?;
+        self.set_timestamp_entry(index, now())
+    }
+
+    /// Sets the timestamp of the chunk at `(x, z)`, in seconds since the Unix
+    /// epoch. This is done automatically by `write_chunk()`.
+    pub fn set_timestamp(&mut self, x: i32, z: i32, timestamp: u32) -> Result<()> {
+        self.set_timestamp_entry(chunk_index(x, z), timestamp)
+    }
+
+    /// Removes the chunk at `(x, z)` from the location and timestamp tables,
+    /// along with its `.mcc` file if it has one. Its sectors become free for
+    /// reuse by later writes.
+    pub fn remove_chunk(&mut self, x: i32, z: i32) -> Result<()> {
+        let index = chunk_index(x, z);
+        self.remove_external(index)?;
+        self.set_location(index, 0)?;
+        self.set_timestamp_entry(index, 0)
+    }
+
+    /// Rewrites the region with its chunks packed densely after the header,
+    /// in their current order, dropping dead sectors left behind by chunks
+    /// that were moved or removed. Chunk payloads and timestamps are copied
+    /// unchanged.
+    ///
+    /// Returns the new length of the region in bytes. Anything stored past it
+    /// is no longer referenced, but the underlying destination is not
+    /// truncated; for files, do this with `File::set_len()`:
+    ///
+    /// ```rust,no_run
+    /// use nbt::region::RegionFile;
+    ///
+    /// let mut region = RegionFile::open_rw("world/region/r.0.0.mca").unwrap();
+    /// let len = region.compact().unwrap();
+    /// region.get_ref().set_len(len).unwrap();
+    /// ```
+    ///
+    /// If any chunk is corrupt, an error is returned before anything has been
+    /// written.
+    pub fn compact(&mut self) -> Result<u64> {
+        // Read every chunk up front, so that moving one cannot clobber
+        // another that has not been copied yet.
+        let mut order: Vec<usize> = (0..CHUNK_COUNT).filter(|&i| self.locations[i] != 0).collect();
+        order.sort_by_key(|&i| self.locations[i] >> 8);
+        let mut records = Vec::with_capacity(order.len());
+        for index in order {
+            if let Some(record) = self.read_record(index)? {
+                records.push((index, record));
+            }
+        }
+
+        let mut locations = vec![0; CHUNK_COUNT];
+        let mut offset = 2;
+        for (index, record) in records {
+            let sectors = (record.len() as u64).div_ceil(SECTOR_LEN);
+            self.inner.seek(SeekFrom::Start(offset * SECTOR_LEN))?;
+            self.inner.write_all(&record)?;
+            let padding = sectors * SECTOR_LEN - record.len() as u64;
+            self.inner.write_all(&vec![0; padding as usize])?;
+            locations[index] = (offset as u32) << 8 | sectors as u32;
+            offset += sectors;
+        }
+
+        self.inner.seek(SeekFrom::Start(0))?;
+        for &location in &locations {
+            self.inner.write_u32::<BigEndian>(location)?;
+        }
+        self.locations = locations;
+        Ok(offset * SECTOR_LEN)
+    }
+
+    /// Flushes the underlying destination.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Picks the sector offset at which to store `sectors` sectors of the
+    /// chunk at `index`.
+    fn allocate(&self, index: usize, sectors: u64) -> u64 {
+        // Overwrite the chunk in place if it still fits.
+        let current = self.locations[index];
+        if current != 0 && u64::from(current & 0xff) >= sectors && current >> 8 >= 2 {
+            return u64::from(current >> 8);
+        }
+
+        // Otherwise find the first free run, ignoring the chunk's own sectors
+        // so that it can grow into free space that follows it.
+        let mut used = vec![true, true];
+        for (i, &location) in self.locations.iter().enumerate() {
+            if i == index || location == 0 {
+                continue;
+            }
+            let start = (location >> 8) as usize;
+            let end = start + (location & 0xff) as usize;
+            if used.len() < end {
+                used.resize(end, false);
+            }
+            for slot in &mut used[start..end] {
+                *slot = true;
+            }
+        }
+        let mut run = 0;
+        for (i, &slot) in used.iter().enumerate() {
+            run = if slot { 0 } else { run + 1 };
+            if run == sectors {
+                return (i + 1) as u64 - sectors;
+            }
+        }
+        // Extend the file, reusing any free sectors at its end.
+        (used.len() - run as usize) as u64
+    }
+
+    /// Deletes any stale `.mcc` file for the chunk at `index`.
+    fn remove_external(&mut self, index: usize) -> Result<()> {
+        if let Some(ref external) = self.external {
+            match fs::remove_file(external.path(index)) {
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+                other => other?,
+            }
+        }
+        Ok(())
+    }
+
+    fn set_location(&mut self, index: usize, location: u32) -> Result<()> {
+        self.inner.seek(SeekFrom::Start(index as u64 * 4))?;
+        self.inner.write_u32::<BigEndian>(location)?;
+        self.locations[index] = location;
+        Ok(())
+    }
+
+    fn set_timestamp_entry(&mut self, index: usize, timestamp: u32) -> Result<()> {
+        self.inner.seek(SeekFrom::Start(SECTOR_LEN + index as u64 * 4))?;
+        self.inner.write_u32::<BigEndian>(timestamp)?;
+        self.timestamps[index] = timestamp;
+        Ok(())
+    }
+}
+
+impl<R> RegionFile<R> {
+    /// Stores and looks up chunks too large for the region in `.mcc` files in
+    /// `dir`. `region_x` and `region_z` are the coordinates of this region,
+    /// as in its file name `r.<region_x>.<region_z>.mca`, and are needed to
+    /// name the files.
+    ///
+    /// This is done automatically for regions opened by path.
+    pub fn with_external_dir<P: AsRef<Path>>(mut self, dir: P, region_x: i32, region_z: i32) -> RegionFile<R> {
+        self.external = Some(External { dir: dir.as_ref().to_path_buf(), region_x, region_z });
+        self
+    }
+
+    /// Enables `.mcc` support if `path` has the standard region file name.
+    fn with_external_path(self, path: &Path) -> RegionFile<R> {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+        let parts: Vec<&str> = name.split('.').collect();
+        if let ["r", x, z, "mca"] = parts[..] {
+            if let (Ok(x), Ok(z)) = (x.parse(), z.parse()) {
+                let dir = path.parent().unwrap_or_else(|| Path::new(""));
+                return self.with_external_dir(dir, x, z);
+            }
+        }
+        self
+    }
+
+    /// Returns a reference to the underlying source.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying source.
+    ///
+    /// Writing to the source other than through the `RegionFile` may leave
+    /// its cached location table out of date.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the `RegionFile`, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// An iterator over the chunks of a `RegionFile`, created by
+/// `RegionFile::chunks()`.
+///
+/// Yields the local `(x, z)` coordinates of each present chunk along with its
+/// contents.
+pub struct Chunks<'a, R: 'a> {
+    region: &'a mut RegionFile<R>,
+    index: usize,
+}
+
+impl<'a, R> Iterator for Chunks<'a, R>
+where
+    R: Read + Seek,
+{
+    type Item = Result<(i32, i32, Blob)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < CHUNK_COUNT {
+            let index = self.index;
+            self.index += 1;
+            let (x, z) = chunk_coords(index);
+            match self.region.read_payload(index) {
+                Ok(Some((compression, data))) => {
+                    return Some(decode_chunk(compression, &data).map(|blob| (x, z, blob)));
+                }
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+fn chunk_index(x: i32, z: i32) -> usize {
+    ((x & (REGION_WIDTH - 1)) + (z & (REGION_WIDTH - 1)) * REGION_WIDTH) as usize
+}
+
+fn chunk_coords(index: usize) -> (i32, i32) {
+    let index = index as i32;
+    (index % REGION_WIDTH, index / REGION_WIDTH)
+}
+
+/// The current time in seconds since the Unix epoch, as stored in the
+/// timestamp table.
+fn now() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0)
+}
+
+fn encode_chunk(compression: Compression, level: CompressionLevel, blob: &Blob) -> Result<Vec<u8>> {
+    let options = WriteOptions::new(Endianness::BigEndian).compression(compression).level(level);
+    let mut data = Vec::new();
+    blob.to_writer_with(&mut data, &options)?;
+    Ok(data)
+}
+
+fn decode_chunk(compression: Compression, mut data: &[u8]) -> Result<Blob> {
+    let options = ReadOptions::new(Endianness::BigEndian).compression(compression);
+    Blob::from_reader_with(&mut data, &options)
+}
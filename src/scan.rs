@@ -0,0 +1,350 @@
+//! Reading NBT documents without building whole `Value` trees.
+//!
+//! `validate()` walks a whole document, checking its structure, the lengths
+//! of its Lists, strings and arrays, and the encoding of its strings as
+//! `Blob::from_reader_with()` would, but keeps none of it. Apart from one
+//! reused buffer for strings, it allocates nothing, so untrusted input can be
+//! vetted before it is parsed for real.
+//!
+//! `extract()` skips over everything but the value at a path, and decodes
+//! only that, which suits indexing many files by a single field.
+//!
+//! `transcode()` rewrites a document in another byte order as it reads it,
+//! holding no more than one string or one chunk of an array at a time.
+
+use std::io;
+
+use error::{Error, Result};
+use options::ReadOptions;
+use path::{self, Segment};
+use raw::{Endianness, RawReader, RawWriter};
+use value::Value;
+
+/// What `validate()` found in a valid document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NbtStats {
+    /// The number of tags, counting the root, every entry of a Compound and
+    /// every element of a List, but not the elements of arrays.
+    pub tags: u64,
+    /// The number of strings, including the names of tags.
+    pub strings: u64,
+    /// The number of elements in all of the arrays.
+    pub array_elements: u64,
+    /// The deepest nesting of Lists and Compounds, where the root Compound
+    /// is at depth 1.
+    pub max_depth: usize,
+    /// The length of the document in bytes, after any decompression.
+    pub bytes: u64,
+}
+
+/// Checks that `src` holds a single valid document as described by
+/// `options`, without building a `Value` tree, and returns some statistics
+/// about it.
+///
+/// The first problem found is returned as an `Error::Located` with the
+/// offset at which it was found, after any decompression. The source is read
+/// up to the end of the document, so any trailing bytes are not checked.
+///
+/// ```rust
+/// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+/// use nbt::{Compression, Endianness, ReadOptions};
+///
+/// let options = ReadOptions::new(Endianness::BigEndian)
+///     .compression(Compression::Gzip)
+///     .max_depth(64);
+/// let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+/// let stats = nbt::validate(&mut file, &options).unwrap();
+/// assert!(stats.tags > 100 && stats.max_depth <= 64);
+/// # }
+/// ```
+pub fn validate<R>(src: R, options: &ReadOptions) -> Result<NbtStats>
+    where R: io::Read,
+{
+    let mut src = options.raw_reader(options.decoder(src)?);
+    let mut stats = NbtStats::default();
+    let mut buf = Vec::new();
+    let result = (|| {
+        let tag = src.read_bare_byte()?;
+        if tag != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        if !options.is_unnamed_root() {
+            read_string(&mut src, &mut buf, &mut stats)?;
+        }
+        skip_payload(&mut src, tag, &mut buf, &mut stats)
+    })();
+    match result {
+        Ok(()) => {
+            stats.bytes = src.offset();
+            Ok(stats)
+        },
+        Err(e) => Err(Error::Located(src.offset(), String::new(), Box::new(e))),
+    }
+}
+
+/// Reads the value at `path` from the document in `src`, decoding only that
+/// value and skipping over everything before it. The path starts with the
+/// name of one of the root Compound's entries, as for `Blob::get_path()`.
+///
+/// Returns `Ok(None)` if the path is malformed, or if the document has no
+/// value there. Reading stops as soon as the value has been decoded or ruled
+/// out, so the rest of the document is neither read nor checked.
+///
+/// ```rust
+/// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+/// use nbt::{Compression, Endianness, ReadOptions, Value};
+///
+/// let options = ReadOptions::new(Endianness::BigEndian).compression(Compression::Gzip);
+/// let file = std::fs::File::open("tests/complex_player.dat").unwrap();
+/// match nbt::extract(file, &options, "Inventory[0].id").unwrap() {
+///     Some(Value::String(id)) => assert!(id.starts_with("minecraft:")),
+///     other => panic!("unexpected item id {:?}", other),
+/// }
+/// # }
+/// ```
+pub fn extract<R>(src: R, options: &ReadOptions, path: &str) -> Result<Option<Value>>
+    where R: io::Read,
+{
+    let segments = match path::parse(path) {
+        Some(segments) => segments,
+        None => return Ok(None),
+    };
+    let mut src = options.raw_reader(options.decoder(src)?);
+    let result = (|| {
+        let tag = src.read_bare_byte()?;
+        if tag != 0x0a {
+            return Err(Error::NoRootCompound);
+        }
+        if !options.is_unnamed_root() {
+            src.read_bare_string()?;
+        }
+        find(&mut src, tag, &segments)
+    })();
+    result.map_err(|e| match e {
+        located @ Error::Located(..) => located,
+        e => Error::Located(src.offset(), String::new(), Box::new(e)),
+    })
+}
+
+/// Follows `segments` through the payload of type `id` from `src`, skipping
+/// whatever is not on the way, and decodes the value at the end.
+fn find<R>(src: &mut RawReader<R>, mut id: i8, segments: &[Segment]) -> Result<Option<Value>>
+    where R: io::Read,
+{
+    let mut buf = Vec::new();
+    let mut stats = NbtStats::default();
+    for segment in segments {
+        id = match (segment, id) {
+            (Segment::Key(key), 0x0a) => {
+                src.begin_nested()?;
+                loop {
+                    let tag = src.read_bare_byte()?;
+                    if tag == 0x00 {
+                        return Ok(None);
+                    }
+                    src.read_string_into(&mut buf)?;
+                    if src.decode_str(&buf)? == *key {
+                        break tag;
+                    }
+                    skip_payload(src, tag, &mut buf, &mut stats)?;
+                }
+            },
+            (Segment::Index(i), 0x09) => {
+                src.begin_nested()?;
+                let elem = src.read_bare_byte()?;
+                if *i >= src.read_list_len()? {
+                    return Ok(None);
+                }
+                for _ in 0..*i {
+                    skip_payload(src, elem, &mut buf, &mut stats)?;
+                }
+                elem
+            },
+            _ => return Ok(None),
+        };
+    }
+    Value::from_raw_reader(id, src).map(Some)
+}
+
+/// Copies the document in `src`, in the byte order `from`, to `dst` in the
+/// byte order `to`, without building a `Value` tree.
+///
+/// Tags are copied one at a time in the order they are read, so memory use
+/// is bounded by the nesting depth, the longest string, and a fixed chunk of
+/// array elements. The root tag may be of any type, but must be named, and
+/// strings are rewritten in Modified UTF-8. Errors are returned as an
+/// `Error::Located` with the offset in `src`, by which point part of the
+/// document may already have been written; `dst` is flushed on success.
+///
+/// ```rust
+/// use nbt::{Blob, Endianness};
+///
+/// let mut blob = Blob::new();
+/// blob.insert("Version", 19133i32).unwrap();
+/// let java = blob.to_vec(Endianness::BigEndian).unwrap();
+/// let mut bedrock = Vec::new();
+/// nbt::transcode(&java[..], &mut bedrock, Endianness::BigEndian, Endianness::LittleEndian).unwrap();
+/// assert_eq!(bedrock, blob.to_vec(Endianness::LittleEndian).unwrap());
+/// ```
+pub fn transcode<R, W>(src: R, dst: W, from: Endianness, to: Endianness) -> Result<()>
+    where R: io::Read,
+          W: io::Write,
+{
+    let mut src = RawReader::new(src, from);
+    let mut dst = RawWriter::new(dst, to);
+    let mut buf = Vec::new();
+    let result = (|| {
+        let tag = src.read_bare_byte()?;
+        if tag == 0x00 {
+            return Err(Error::InvalidTypeId(tag));
+        }
+        copy_header(&mut src, &mut dst, tag, &mut buf)?;
+        copy_payload(&mut src, &mut dst, tag, &mut buf)
+    })();
+    if let Err(e) = result {
+        return Err(Error::Located(src.offset(), String::new(), Box::new(e)));
+    }
+    dst.get_mut().flush().map_err(From::from)
+}
+
+/// Copies the name of a tag of type `id`, whose ID has already been read, and
+/// writes the whole header.
+fn copy_header<R, W>(src: &mut RawReader<R>, dst: &mut RawWriter<W>, id: i8, buf: &mut Vec<u8>) -> Result<()>
+    where R: io::Read,
+          W: io::Write,
+{
+    src.read_string_into(buf)?;
+    dst.write_header(id, &src.decode_str(buf)?)
+}
+
+/// Copies one payload of type `id` from `src` to `dst`. Lists and Compounds
+/// are walked with an explicit stack, as in `skip_payload()`.
+fn copy_payload<R, W>(src: &mut RawReader<R>, dst: &mut RawWriter<W>, id: i8, buf: &mut Vec<u8>) -> Result<()>
+    where R: io::Read,
+          W: io::Write,
+{
+    let mut stack = Vec::new();
+    let mut next = Some(id);
+    loop {
+        if let Some(id) = next.take() {
+            match id {
+                0x01 => dst.write_bare_byte(src.read_bare_byte()?)?,
+                0x02 => dst.write_bare_short(src.read_bare_short()?)?,
+                0x03 => dst.write_bare_int(src.read_bare_int()?)?,
+                0x04 => dst.write_bare_long(src.read_bare_long()?)?,
+                0x05 => dst.write_bare_float(src.read_bare_float()?)?,
+                0x06 => dst.write_bare_double(src.read_bare_double()?)?,
+                0x07 | 0x0b | 0x0c => { src.copy_array(id, dst)?; },
+                0x08 => {
+                    src.read_string_into(buf)?;
+                    dst.write_bare_string(&src.decode_str(buf)?)?;
+                },
+                0x09 => {
+                    src.begin_nested()?;
+                    let elem = src.read_bare_byte()?;
+                    let len = src.read_list_len()?;
+                    dst.write_bare_byte(elem)?;
+                    dst.write_bare_int(len as i32)?;
+                    stack.push(Frame::List(elem, len));
+                },
+                0x0a => {
+                    src.begin_nested()?;
+                    dst.begin_compound();
+                    stack.push(Frame::Compound);
+                },
+                id => return Err(Error::InvalidTypeId(id)),
+            }
+        }
+        next = match stack.last_mut() {
+            None => return Ok(()),
+            Some(&mut Frame::List(elem, ref mut remaining)) if *remaining > 0 => {
+                *remaining -= 1;
+                Some(elem)
+            },
+            Some(&mut Frame::List(..)) => None,
+            Some(&mut Frame::Compound) => match src.read_bare_byte()? {
+                0x00 => None,
+                id => {
+                    copy_header(src, dst, id, buf)?;
+                    Some(id)
+                },
+            },
+        };
+        if next.is_none() {
+            if let Some(Frame::Compound) = stack.pop() {
+                dst.close_nbt()?;
+            }
+            src.end_nested();
+        }
+    }
+}
+
+enum Frame {
+    Compound,
+    List(i8, usize),
+}
+
+/// Reads past one payload of type `id`, checking it and counting what it
+/// holds into `stats`. Lists and Compounds are walked with an explicit stack.
+pub(crate) fn skip_payload<R>(src: &mut RawReader<R>, id: i8, buf: &mut Vec<u8>, stats: &mut NbtStats) -> Result<()>
+    where R: io::Read,
+{
+    let mut stack = Vec::new();
+    let mut next = Some(id);
+    loop {
+        if let Some(id) = next.take() {
+            stats.tags += 1;
+            match id {
+                0x01 => { src.read_bare_byte()?; },
+                0x02 => { src.read_bare_short()?; },
+                0x03 => { src.read_bare_int()?; },
+                0x04 => { src.read_bare_long()?; },
+                0x05 => { src.read_bare_float()?; },
+                0x06 => { src.read_bare_double()?; },
+                0x07 | 0x0b | 0x0c => stats.array_elements += src.skip_array(id)? as u64,
+                0x08 => read_string(src, buf, stats)?,
+                0x09 => {
+                    src.begin_nested()?;
+                    let elem = src.read_bare_byte()?;
+                    let len = src.read_list_len()?;
+                    stack.push(Frame::List(elem, len));
+                },
+                0x0a => {
+                    src.begin_nested()?;
+                    stack.push(Frame::Compound);
+                },
+                id => return Err(Error::InvalidTypeId(id)),
+            }
+            stats.max_depth = stats.max_depth.max(stack.len());
+        }
+        next = match stack.last_mut() {
+            None => return Ok(()),
+            Some(&mut Frame::List(elem, ref mut remaining)) if *remaining > 0 => {
+                *remaining -= 1;
+                Some(elem)
+            },
+            Some(&mut Frame::List(..)) => None,
+            Some(&mut Frame::Compound) => match src.read_bare_byte()? {
+                0x00 => None,
+                id => {
+                    read_string(src, buf, stats)?;
+                    Some(id)
+                },
+            },
+        };
+        if next.is_none() {
+            stack.pop();
+            src.end_nested();
+        }
+    }
+}
+
+/// Reads a string into `buf` and checks that it decodes.
+fn read_string<R>(src: &mut RawReader<R>, buf: &mut Vec<u8>, stats: &mut NbtStats) -> Result<()>
+    where R: io::Read,
+{
+    src.read_string_into(buf)?;
+    src.decode_str(buf)?;
+    stats.strings += 1;
+    Ok(())
+}
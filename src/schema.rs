@@ -0,0 +1,330 @@
+//! Declarative checks of the structure of NBT values.
+//!
+//! A `Schema` describes what a value should look like: its tag, the range
+//! of a number, the length of a string, array or List, and the keys of a
+//! Compound with schemas of their own. Validating a value against it lists
+//! every place where it does not conform, rather than stopping at the first,
+//! so that untrusted NBT, such as items sent by a client, can be rejected
+//! with a useful explanation.
+//!
+//! ```rust
+//! # #[macro_use] extern crate nbt;
+//! use nbt::schema::{Schema, Violation};
+//!
+//! # fn main() {
+//! let item = Schema::compound()
+//!     .required("id", Schema::string().len(1, 64))
+//!     .required("Count", Schema::byte().range(1.0, 64.0))
+//!     .optional("tag", Schema::compound().allow_unknown());
+//!
+//! assert!(item.validate(&nbt!({ id: "minecraft:stone", Count: 3i8 })).is_empty());
+//! assert_eq!(item.validate(&nbt!({ Count: 100i8, Slot: 0i8 })), vec![
+//!     Violation::OutOfRange { path: "Count".to_string(), value: 100.0 },
+//!     Violation::Unexpected { path: "Slot".to_string() },
+//!     Violation::Missing { path: "id".to_string() },
+//! ]);
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use map::Map;
+use path;
+use value::{self, Value};
+
+/// The expected structure of a value.
+///
+/// Schemas are built from a constructor for the expected tag, or `any()`,
+/// followed by the constraints that apply to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schema {
+    kind: Kind,
+    min: Option<f64>,
+    max: Option<f64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Any,
+    Tag(i8),
+    List(Box<Schema>),
+    Compound { fields: BTreeMap<String, Field>, allow_unknown: bool },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Field {
+    schema: Schema,
+    required: bool,
+}
+
+impl Schema {
+    fn new(kind: Kind) -> Schema {
+        Schema { kind, min: None, max: None, min_len: None, max_len: None }
+    }
+
+    /// Accepts a value of any tag.
+    pub fn any() -> Schema { Schema::new(Kind::Any) }
+
+    /// Expects a `TAG_Byte`.
+    pub fn byte() -> Schema { Schema::new(Kind::Tag(0x01)) }
+
+    /// Expects a `TAG_Short`.
+    pub fn short() -> Schema { Schema::new(Kind::Tag(0x02)) }
+
+    /// Expects a `TAG_Int`.
+    pub fn int() -> Schema { Schema::new(Kind::Tag(0x03)) }
+
+    /// Expects a `TAG_Long`.
+    pub fn long() -> Schema { Schema::new(Kind::Tag(0x04)) }
+
+    /// Expects a `TAG_Float`.
+    pub fn float() -> Schema { Schema::new(Kind::Tag(0x05)) }
+
+    /// Expects a `TAG_Double`.
+    pub fn double() -> Schema { Schema::new(Kind::Tag(0x06)) }
+
+    /// Expects a `TAG_Byte_Array`.
+    pub fn byte_array() -> Schema { Schema::new(Kind::Tag(0x07)) }
+
+    /// Expects a `TAG_String`.
+    pub fn string() -> Schema { Schema::new(Kind::Tag(0x08)) }
+
+    /// Expects a `TAG_Int_Array`.
+    pub fn int_array() -> Schema { Schema::new(Kind::Tag(0x0b)) }
+
+    /// Expects a `TAG_Long_Array`.
+    pub fn long_array() -> Schema { Schema::new(Kind::Tag(0x0c)) }
+
+    /// Expects a `TAG_List` whose elements all conform to `element`.
+    pub fn list(element: Schema) -> Schema { Schema::new(Kind::List(Box::new(element))) }
+
+    /// Expects a `TAG_Compound`, with no entries until some are added with
+    /// `required()` or `optional()`.
+    pub fn compound() -> Schema {
+        Schema::new(Kind::Compound { fields: BTreeMap::new(), allow_unknown: false })
+    }
+
+    /// Expects a number between `min` and `max`, inclusive. Values of
+    /// other tags are not affected.
+    pub fn range(mut self, min: f64, max: f64) -> Schema {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Expects a string of `min` to `max` characters, an array or List of
+    /// `min` to `max` elements, or a Compound of `min` to `max` entries.
+    pub fn len(mut self, min: usize, max: usize) -> Schema {
+        self.min_len = Some(min);
+        self.max_len = Some(max);
+        self
+    }
+
+    /// Adds an entry that a Compound must have.
+    ///
+    /// # Panics
+    ///
+    /// If this schema does not expect a Compound.
+    pub fn required<S: Into<String>>(self, key: S, schema: Schema) -> Schema {
+        self.field(key.into(), schema, true)
+    }
+
+    /// Adds an entry that a Compound may have.
+    ///
+    /// # Panics
+    ///
+    /// If this schema does not expect a Compound.
+    pub fn optional<S: Into<String>>(self, key: S, schema: Schema) -> Schema {
+        self.field(key.into(), schema, false)
+    }
+
+    /// Accepts entries of a Compound that the schema does not mention,
+    /// which are otherwise violations.
+    ///
+    /// # Panics
+    ///
+    /// If this schema does not expect a Compound.
+    pub fn allow_unknown(mut self) -> Schema {
+        match self.kind {
+            Kind::Compound { ref mut allow_unknown, .. } => *allow_unknown = true,
+            _ => panic!("allow_unknown() needs a Compound schema"),
+        }
+        self
+    }
+
+    fn field(mut self, key: String, schema: Schema, required: bool) -> Schema {
+        match self.kind {
+            Kind::Compound { ref mut fields, .. } => { fields.insert(key, Field { schema, required }); },
+            _ => panic!("required() and optional() need a Compound schema"),
+        }
+        self
+    }
+
+    /// Checks `value` against this schema, returning every violation, or
+    /// nothing if it conforms. The entries of Compounds are checked in the
+    /// order of their names.
+    pub fn validate(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.check(&mut violations, String::new(), value);
+        violations
+    }
+
+    /// Checks the root Compound of a `Blob`, given by its entries.
+    #[cfg(feature = "std")]
+    pub(crate) fn validate_entries(&self, entries: &Map) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        match self.kind {
+            Kind::Tag(id) if id != 0x0a => {
+                violations.push(Violation::WrongTag { path: String::new(), expected: id, found: 0x0a });
+            },
+            Kind::List(_) => {
+                violations.push(Violation::WrongTag { path: String::new(), expected: 0x09, found: 0x0a });
+            },
+            _ => if self.check_len(&mut violations, "", entries.len()) {
+                self.check_compound(&mut violations, "", entries);
+            },
+        }
+        violations
+    }
+
+    fn check(&self, violations: &mut Vec<Violation>, path: String, value: &Value) {
+        let expected = match self.kind {
+            Kind::Any => None,
+            Kind::Tag(id) => Some(id),
+            Kind::List(_) => Some(0x09),
+            Kind::Compound { .. } => Some(0x0a),
+        };
+        if let Some(expected) = expected.filter(|&id| id != value.id()) {
+            violations.push(Violation::WrongTag { path, expected, found: value.id() });
+            return;
+        }
+
+        if let Some(number) = number(value) {
+            if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max) {
+                violations.push(Violation::OutOfRange { path, value: number });
+                return;
+            }
+        }
+        if let Some(len) = len(value) {
+            if !self.check_len(violations, &path, len) {
+                return;
+            }
+        }
+
+        match (&self.kind, value) {
+            (Kind::List(element), Value::List(values)) => {
+                for (i, value) in values.iter().enumerate() {
+                    element.check(violations, format!("{}[{}]", path, i), value);
+                }
+            },
+            (Kind::Compound { .. }, Value::Compound(entries)) => self.check_compound(violations, &path, entries),
+            _ => {},
+        }
+    }
+
+    /// Checks a length, returning whether it is in range.
+    fn check_len(&self, violations: &mut Vec<Violation>, path: &str, len: usize) -> bool {
+        if self.min_len.is_some_and(|min| len < min) || self.max_len.is_some_and(|max| len > max) {
+            violations.push(Violation::WrongLength { path: path.to_string(), len });
+            return false;
+        }
+        true
+    }
+
+    fn check_compound(&self, violations: &mut Vec<Violation>, path: &str, entries: &Map) {
+        let (fields, allow_unknown) = match self.kind {
+            Kind::Compound { ref fields, allow_unknown } => (fields, allow_unknown),
+            _ => return,
+        };
+        let mut keys: Vec<&String> = fields.keys().chain(entries.keys().filter(|k| !fields.contains_key(*k))).collect();
+        keys.sort();
+        for key in keys {
+            let mut path = path.to_string();
+            path::write_key(&mut path, key);
+            match (fields.get(key), entries.get(key)) {
+                (Some(field), Some(value)) => field.schema.check(violations, path, value),
+                (Some(field), None) if field.required => violations.push(Violation::Missing { path }),
+                (None, Some(_)) if !allow_unknown => violations.push(Violation::Unexpected { path }),
+                _ => {},
+            }
+        }
+    }
+}
+
+/// The value of a number, of any tag, for comparing with a range.
+fn number(value: &Value) -> Option<f64> {
+    match *value {
+        Value::Byte(v) => Some(v.into()),
+        Value::Short(v) => Some(v.into()),
+        Value::Int(v) => Some(v.into()),
+        Value::Long(v) => Some(v as f64),
+        Value::Float(v) => Some(v.into()),
+        Value::Double(v) => Some(v),
+        _ => None,
+    }
+}
+
+/// The length of a string, array, List or Compound.
+fn len(value: &Value) -> Option<usize> {
+    match *value {
+        Value::ByteArray(ref v) => Some(v.len()),
+        Value::String(ref v) => Some(v.chars().count()),
+        Value::List(ref v) => Some(v.len()),
+        Value::Compound(ref v) => Some(v.len()),
+        Value::IntArray(ref v) => Some(v.len()),
+        Value::LongArray(ref v) => Some(v.len()),
+        _ => None,
+    }
+}
+
+/// A place where a value does not conform to a `Schema`, at a path in the
+/// syntax of `Value::get_path()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// A required entry of a Compound is absent.
+    Missing { path: String },
+    /// An entry of a Compound is not in the schema.
+    Unexpected { path: String },
+    /// A value has the wrong tag, given with the expected one as type IDs.
+    WrongTag { path: String, expected: i8, found: i8 },
+    /// A number is outside its range.
+    OutOfRange { path: String, value: f64 },
+    /// A string, array, List or Compound is too short or too long.
+    WrongLength { path: String, len: usize },
+}
+
+impl Violation {
+    /// The path to the value that violates the schema, which is empty for
+    /// the root.
+    pub fn path(&self) -> &str {
+        match *self {
+            Violation::Missing { ref path } => path,
+            Violation::Unexpected { ref path } => path,
+            Violation::WrongTag { ref path, .. } => path,
+            Violation::OutOfRange { ref path, .. } => path,
+            Violation::WrongLength { ref path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path().is_empty() {
+            write!(f, "{}: ", self.path())?;
+        }
+        match *self {
+            Violation::Missing { .. } => write!(f, "missing required entry"),
+            Violation::Unexpected { .. } => write!(f, "unexpected entry"),
+            Violation::WrongTag { expected, found, .. } => write!(
+                f, "expected {}, found {}", value::tag_name_of(expected), value::tag_name_of(found)
+            ),
+            Violation::OutOfRange { value, .. } => write!(f, "{} is out of range", value),
+            Violation::WrongLength { len, .. } => write!(f, "length {} is out of range", len),
+        }
+    }
+}
@@ -1,15 +1,22 @@
 //! Serialize a Rust data structure into Named Binary Tag data.
 
+use std::borrow::Cow;
 use std::io;
 
 use serde;
 use serde::ser;
-use flate2::Compression;
-use flate2::write::{GzEncoder, ZlibEncoder};
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use flate::{self, GzEncoder, ZlibEncoder};
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use options::CompressionLevel;
 
 use raw::{RawWriter, Endianness};
 
 use error::{Error, Result};
+use arrays::array_tag;
+use named::NAMED_TOKEN;
+use map::Map;
+use value::Value;
 
 /// Encode `value` in Named Binary Tag format to the given `io::Write`
 /// destination, with an optional header.
@@ -23,25 +30,54 @@ pub fn to_writer<'a, W, T>(dst: &mut W, value: &T, header: Option<&'a str>, endi
     value.serialize(&mut encoder)
 }
 
+/// Encode `value` in Named Binary Tag format to a new buffer, with an
+/// optional header.
+///
+/// ```
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate nbt;
+/// # use nbt::Endianness;
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Sign {
+///     text: String,
+/// }
+///
+/// # fn main() {
+/// let sign = Sign { text: "hi".to_string() };
+/// let bytes = nbt::to_vec(&sign, None, Endianness::BigEndian).unwrap();
+/// assert_eq!(nbt::from_slice::<Sign>(&bytes, Endianness::BigEndian).unwrap(), sign);
+/// # }
+/// ```
+#[inline]
+pub fn to_vec<T>(value: &T, header: Option<&str>, endian: Endianness) -> Result<Vec<u8>>
+    where T: ?Sized + ser::Serialize,
+{
+    let mut dst = Vec::new();
+    to_writer(&mut dst, value, header, endian)?;
+    Ok(dst)
+}
+
 /// Encode `value` in Named Binary Tag format to the given `io::Write`
 /// destination, with an optional header.
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 pub fn to_gzip_writer<'a, W, T>(dst: &mut W, value: &T, header: Option<&'a str>, endian: Endianness)
                            -> Result<()>
     where W: ?Sized + io::Write,
           T: ?Sized + ser::Serialize,
 {
-    let mut encoder = Encoder::new(GzEncoder::new(dst, Compression::Default), header, endian);
+    let mut encoder = Encoder::new(GzEncoder::new(dst, flate::level(CompressionLevel::Default)), header, endian);
     value.serialize(&mut encoder)
 }
 
 /// Encode `value` in Named Binary Tag format to the given `io::Write`
 /// destination, with an optional header.
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 pub fn to_zlib_writer<'a, W, T>(dst: &mut W, value: &T, header: Option<&'a str>, endian: Endianness)
                            -> Result<()>
     where W: ?Sized + io::Write,
           T: ?Sized + ser::Serialize,
 {
-    let mut encoder = Encoder::new(ZlibEncoder::new(dst, Compression::Default), header, endian);
+    let mut encoder = Encoder::new(ZlibEncoder::new(dst, flate::level(CompressionLevel::Default)), header, endian);
     value.serialize(&mut encoder)
 }
 
@@ -53,14 +89,14 @@ pub fn to_zlib_writer<'a, W, T>(dst: &mut W, value: &T, header: Option<&'a str>,
 /// return errors.
 pub struct Encoder<'a, W: io::Write> {
     writer: RawWriter<W>,
-    header: Option<&'a str>,
+    header: Option<Cow<'a, str>>,
 }
 
 impl<'a, W> Encoder<'a, W> where W: io::Write {
 
     /// Create an encoder with optional `header` from a given Writer.
     pub fn new(writer: W, header: Option<&'a str>, endian: Endianness) -> Self {
-        Encoder { writer: RawWriter::new(writer, endian), header: header }
+        Encoder { writer: RawWriter::new(writer, endian), header: header.map(Cow::Borrowed) }
     }
 
     /// Write the NBT tag and an optional header to the underlying writer.
@@ -76,6 +112,29 @@ impl<'a, W> Encoder<'a, W> where W: io::Write {
     }
 }
 
+impl<'a, W> Encoder<'a, W> where W: io::Write {
+
+    /// Write the payload of an externally tagged newtype variant, which is a
+    /// compound with a single entry named after the variant.
+    fn write_newtype_variant<T>(&mut self, variant: &'static str, value: &T) -> Result<()>
+        where T: ?Sized + ser::Serialize
+    {
+        self.writer.begin_compound();
+        value.serialize(&mut TagEncoder::from_outer(self, Some(variant)))?;
+        value.serialize(&mut InnerEncoder::from_outer(self))?;
+        self.writer.close_nbt()
+    }
+
+    /// Start the payload of an externally tagged struct variant, which is a
+    /// compound with a single compound entry named after the variant.
+    fn begin_struct_variant<'c>(&'c mut self, variant: &'static str) -> Result<Compound<'c, 'a, W>> {
+        self.writer.begin_compound();
+        self.writer.write_header(0x0a, variant)?;
+        self.writer.begin_compound();
+        Ok(Compound { outer: self, length: 0, sigil: false, named: false, key: None })
+    }
+}
+
 /// "Inner" version of the NBT encoder, capable of serializing bare types.
 struct InnerEncoder<'a, 'b: 'a, W: io::Write + 'a> {
     outer: &'a mut Encoder<'b, W>,
@@ -92,11 +151,15 @@ pub struct Compound<'a, 'b: 'a, W: io::Write + 'a> {
     outer: &'a mut Encoder<'b, W>,
     length: i32,
     sigil: bool,
+    named: bool,
+    /// A map key waiting for its value, since the tag has to be written first.
+    key: Option<String>,
 }
 
 impl<'a, 'b, W> Compound<'a, 'b, W> where W: io::Write {
     fn from_outer(outer: &'a mut Encoder<'b, W>) -> Self {
-        Compound { outer: outer, length: 0, sigil: false }
+        outer.writer.begin_compound();
+        Compound { outer: outer, length: 0, sigil: false, named: false, key: None }
     }
 
     fn for_seq(outer: &'a mut Encoder<'b, W>, length: i32) -> Result<Self> {
@@ -105,7 +168,7 @@ impl<'a, 'b, W> Compound<'a, 'b, W> where W: io::Write {
             outer.writer.write_bare_byte(0x00)?;
             outer.writer.write_bare_int(0)?;
         }
-        Ok(Compound { outer: outer, length: length, sigil: false })
+        Ok(Compound { outer: outer, length: length, sigil: false, named: false, key: None })
     }
 }
 
@@ -131,6 +194,23 @@ impl<'a, 'b, W> ser::SerializeSeq for Compound<'a, 'b, W>
     }
 }
 
+impl<'a, 'b, W> ser::SerializeTupleStruct for Compound<'a, 'b, W>
+    where W: io::Write
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        value.serialize(&mut InnerEncoder::from_outer(self.outer))
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
 impl<'a, 'b, W> ser::SerializeStruct for Compound<'a, 'b, W>
     where W: io::Write
 {
@@ -141,11 +221,46 @@ impl<'a, 'b, W> ser::SerializeStruct for Compound<'a, 'b, W>
                                   -> Result<()>
         where T: serde::Serialize
     {
+        if self.named {
+            // The fields of a root `Named`: its name, then the compound that
+            // is written under that name.
+            return match key {
+                "name" => match to_value(value)? {
+                    Value::String(name) => {
+                        self.outer.header = Some(Cow::Owned(name));
+                        Ok(())
+                    },
+                    other => Err(Error::TagMismatch(other.id(), 0x08)),
+                },
+                _ => value.serialize(&mut *self.outer),
+            };
+        }
         value.serialize(&mut TagEncoder::from_outer(self.outer, Some(key)))?;
         value.serialize(&mut InnerEncoder::from_outer(self.outer))
     }
 
     fn end(self) -> Result<()> {
+        if self.named {
+            return Ok(());
+        }
+        self.outer.writer.close_nbt()
+    }
+}
+
+impl<'a, 'b, W> ser::SerializeStructVariant for Compound<'a, 'b, W>
+    where W: io::Write
+{
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.outer.writer.close_nbt()?;
         self.outer.writer.close_nbt()
     }
 }
@@ -156,16 +271,24 @@ impl<'a, 'b, W> ser::SerializeMap for Compound<'a, 'b, W>
     type Ok = ();
     type Error = Error;
 
-    fn serialize_key<T: ?Sized>(&mut self, _key: &T) -> Result<()>
+    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<()>
         where T: serde::Serialize
     {
-        unimplemented!()
+        match to_value(key)? {
+            Value::String(key) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(Error::NonStringMapKey),
+        }
     }
 
-    fn serialize_value<T: ?Sized>(&mut self, _value: &T) -> Result<()>
+    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<()>
         where T: serde::Serialize
     {
-        unimplemented!()
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        value.serialize(&mut TagEncoder::from_outer(self.outer, Some(key)))?;
+        value.serialize(&mut InnerEncoder::from_outer(self.outer))
     }
 
     fn serialize_entry<K: ?Sized, V: ?Sized>(&mut self, key: &K, value: &V) -> Result<()>
@@ -190,19 +313,20 @@ impl<'a, 'b, W> serde::Serializer for &'a mut Encoder<'b, W> where W: io::Write
     type SerializeTupleVariant = ser::Impossible<(), Error>;
     type SerializeMap = Compound<'a, 'b, W>;
     type SerializeStruct = Compound<'a, 'b, W>;
-    type SerializeStructVariant = ser::Impossible<(), Error>;
+    type SerializeStructVariant = Compound<'a, 'b, W>;
 
     return_expr_for_serialized_types!(
         Err(Error::NoRootCompound); bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64
-            char str bytes none some unit unit_variant newtype_variant
-            seq tuple tuple_struct tuple_variant struct_variant
+            char str bytes none some unit unit_variant seq tuple tuple_struct
+            tuple_variant
     );
 
     /// Serialize unit structs as empty `Tag_Compound` data.
     #[inline]
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        let header = self.header; // Circumvent strange borrowing errors.
-        self.write_header(0x0a, header)?;
+        let header = self.header.clone(); // Circumvent strange borrowing errors.
+        self.write_header(0x0a, header.as_deref())?;
+        self.writer.begin_compound();
         self.writer.close_nbt()
     }
 
@@ -216,21 +340,49 @@ impl<'a, 'b, W> serde::Serializer for &'a mut Encoder<'b, W> where W: io::Write
         value.serialize(self)
     }
 
+    /// Serialize newtype variants as `Tag_Compound` data with a single entry
+    /// named after the variant.
+    #[inline]
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32,
+                                    variant: &'static str, value: &T)
+                                    -> Result<()>
+        where T: ?Sized + ser::Serialize
+    {
+        let header = self.header.clone(); // Circumvent strange borrowing errors.
+        self.write_header(0x0a, header.as_deref())?;
+        self.write_newtype_variant(variant, value)
+    }
+
+    /// Serialize struct variants as `Tag_Compound` data with a single
+    /// compound entry named after the variant.
+    #[inline]
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32,
+                                variant: &'static str, _len: usize)
+                                -> Result<Self::SerializeStructVariant>
+    {
+        let header = self.header.clone(); // Circumvent strange borrowing errors.
+        self.write_header(0x0a, header.as_deref())?;
+        self.begin_struct_variant(variant)
+    }
+
     /// Serialize maps as `Tag_Compound` data.
     #[inline]
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        let header = self.header; // Circumvent strange borrowing errors.
-        self.write_header(0x0a, header)?;
+        let header = self.header.clone(); // Circumvent strange borrowing errors.
+        self.write_header(0x0a, header.as_deref())?;
         Ok(Compound::from_outer(self))
     }
 
     /// Serialize structs as `Tag_Compound` data.
     #[inline]
-    fn serialize_struct(self, _name: &'static str, _len: usize)
+    fn serialize_struct(self, name: &'static str, _len: usize)
                         -> Result<Self::SerializeStruct>
     {
-        let header = self.header; // Circumvent strange borrowing errors.
-        self.write_header(0x0a, header)?;
+        if name == NAMED_TOKEN {
+            return Ok(Compound { outer: self, length: 0, sigil: false, named: true, key: None });
+        }
+        let header = self.header.clone(); // Circumvent strange borrowing errors.
+        self.write_header(0x0a, header.as_deref())?;
         Ok(Compound::from_outer(self))
     }
 }
@@ -240,15 +392,14 @@ impl<'a, 'b, W> serde::Serializer for &'a mut InnerEncoder<'a, 'b, W> where W: i
     type Error = Error;
     type SerializeSeq = Compound<'a, 'b, W>;
     type SerializeTuple = ser::Impossible<(), Error>;
-    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = Compound<'a, 'b, W>;
     type SerializeTupleVariant = ser::Impossible<(), Error>;
     type SerializeMap = Compound<'a, 'b, W>;
     type SerializeStruct = Compound<'a, 'b, W>;
-    type SerializeStructVariant = ser::Impossible<(), Error>;
+    type SerializeStructVariant = Compound<'a, 'b, W>;
 
     unrepresentable!(
-        u8 u16 u32 u64 char unit unit_variant newtype_variant tuple tuple_struct
-            tuple_variant struct_variant
+        u8 u16 u32 u64 char unit tuple tuple_variant
     );
 
     #[inline]
@@ -291,9 +442,11 @@ impl<'a, 'b, W> serde::Serializer for &'a mut InnerEncoder<'a, 'b, W> where W: i
         self.outer.writer.write_bare_string(value)
     }
 
+    /// Serialize bytes (e.g. from `serde_bytes`) as `TAG_Byte_Array` data.
     #[inline]
-    fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
-        Err(Error::UnrepresentableType("u8"))
+    fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        self.outer.writer.write_bare_int(value.len() as i32)?;
+        io::Write::write_all(self.outer.writer.get_mut(), value).map_err(From::from)
     }
 
     #[inline]
@@ -310,9 +463,35 @@ impl<'a, 'b, W> serde::Serializer for &'a mut InnerEncoder<'a, 'b, W> where W: i
 
     #[inline]
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.outer.writer.begin_compound();
         self.outer.writer.close_nbt()
     }
 
+    /// Serialize unit variants as `TAG_String` data holding the variant name.
+    #[inline]
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32,
+                              variant: &'static str) -> Result<()>
+    {
+        self.outer.writer.write_bare_string(variant)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32,
+                                    variant: &'static str, value: &T)
+                                    -> Result<()>
+        where T: ?Sized + ser::Serialize
+    {
+        self.outer.write_newtype_variant(variant, value)
+    }
+
+    #[inline]
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32,
+                                variant: &'static str, _len: usize)
+                                -> Result<Self::SerializeStructVariant>
+    {
+        self.outer.begin_struct_variant(variant)
+    }
+
     #[inline]
     fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T)
                                            -> Result<()>
@@ -330,6 +509,19 @@ impl<'a, 'b, W> serde::Serializer for &'a mut InnerEncoder<'a, 'b, W> where W: i
         }
     }
 
+    /// Serialize the tuple structs produced by `nbt::int_array` and friends
+    /// as array payloads, which are a length followed by bare elements.
+    #[inline]
+    fn serialize_tuple_struct(self, name: &'static str, len: usize)
+                              -> Result<Self::SerializeTupleStruct>
+    {
+        if array_tag(name).is_none() {
+            return Err(Error::UnrepresentableType("tuple_struct"));
+        }
+        self.outer.writer.write_bare_int(len as i32)?;
+        Ok(Compound { outer: self.outer, length: len as i32, sigil: false, named: false, key: None })
+    }
+
     #[inline]
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
         Ok(Compound::from_outer(self.outer))
@@ -369,7 +561,7 @@ impl<'a, 'b: 'a, W: 'a> serde::Serializer for &'a mut MapKeyEncoder<'a, 'b, W>
 
     return_expr_for_serialized_types!(
         Err(Error::NonStringMapKey); bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64
-            char bytes unit unit_variant newtype_variant unit_struct seq tuple
+            char bytes unit newtype_variant unit_struct seq tuple
             tuple_struct tuple_variant struct_variant newtype_struct map struct
     );
 
@@ -386,6 +578,12 @@ impl<'a, 'b: 'a, W: 'a> serde::Serializer for &'a mut MapKeyEncoder<'a, 'b, W>
     fn serialize_str(self, value: &str) -> Result<()> {
         self.outer.writer.write_bare_string(value)
     }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32,
+                              variant: &'static str) -> Result<()>
+    {
+        self.serialize_str(variant)
+    }
 }
 
 /// A serializer for valid map keys.
@@ -419,15 +617,14 @@ where W: io::Write,
     type Error = Error;
     type SerializeSeq = NoOp;
     type SerializeTuple = ser::Impossible<(), Error>;
-    type SerializeTupleStruct = ser::Impossible<(), Error>;
+    type SerializeTupleStruct = NoOp;
     type SerializeTupleVariant = ser::Impossible<(), Error>;
     type SerializeMap = NoOp;
     type SerializeStruct = NoOp;
-    type SerializeStructVariant = ser::Impossible<(), Error>;
+    type SerializeStructVariant = NoOp;
 
     unrepresentable!(
-        u8 u16 u32 u64 char unit unit_variant newtype_variant tuple tuple_struct
-            tuple_variant struct_variant
+        u8 u16 u32 u64 char unit tuple tuple_variant
     );
 
     #[inline]
@@ -470,9 +667,10 @@ where W: io::Write,
         self.write_header(0x08)
     }
 
+    /// Serialize bytes (e.g. from `serde_bytes`) as `TAG_Byte_Array` data.
     #[inline]
     fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
-        Err(Error::UnrepresentableType("u8"))
+        self.write_header(0x07)
     }
 
     #[inline]
@@ -492,6 +690,31 @@ where W: io::Write,
         self.write_header(0x0a)
     }
 
+    #[inline]
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32,
+                              _variant: &'static str) -> Result<()>
+    {
+        self.write_header(0x08)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32,
+                                    _variant: &'static str, _value: &T)
+                                    -> Result<()>
+        where T: ?Sized + ser::Serialize
+    {
+        self.write_header(0x0a)
+    }
+
+    #[inline]
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32,
+                                _variant: &'static str, _len: usize)
+                                -> Result<Self::SerializeStructVariant>
+    {
+        self.write_header(0x0a)?;
+        Ok(NoOp)
+    }
+
     #[inline]
     fn serialize_newtype_struct<T: ?Sized>(self, _name: &'static str, value: &T)
                                            -> Result<()>
@@ -510,6 +733,19 @@ where W: io::Write,
         }
     }
 
+    #[inline]
+    fn serialize_tuple_struct(self, name: &'static str, _len: usize)
+                              -> Result<Self::SerializeTupleStruct>
+    {
+        match array_tag(name) {
+            Some(tag) => {
+                self.write_header(tag)?;
+                Ok(NoOp)
+            },
+            None => Err(Error::UnrepresentableType("tuple_struct")),
+        }
+    }
+
     #[inline]
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
         self.write_header(0x0a)?;
@@ -544,6 +780,36 @@ impl ser::SerializeSeq for NoOp {
     }
 }
 
+impl ser::SerializeTupleStruct for NoOp {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for NoOp {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, _key: &'static str, _value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
 impl ser::SerializeStruct for NoOp {
     type Ok = ();
     type Error = Error;
@@ -580,3 +846,315 @@ impl ser::SerializeMap for NoOp {
         Ok(())
     }
 }
+
+/// Convert `value` into a dynamic `Value` tree, without encoding it to bytes.
+///
+/// Types map to NBT tags the same way as in `to_writer`, except that bare
+/// values are allowed at the root: `to_value(&5i32)` is `Value::Int(5)`.
+///
+/// ```
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate nbt;
+/// # use nbt::Value;
+/// #[derive(Serialize)]
+/// struct Player {
+///     name: String,
+///     health: i8,
+/// }
+///
+/// # fn main() {
+/// let player = Player { name: "Herobrine".to_string(), health: 100 };
+/// let value = nbt::ser::to_value(&player).unwrap();
+/// let map = value.into_compound().unwrap();
+/// assert_eq!(map["name"], Value::String("Herobrine".to_string()));
+/// assert_eq!(map["health"], Value::Byte(100));
+/// # }
+/// ```
+pub fn to_value<T>(value: &T) -> Result<Value>
+    where T: ?Sized + ser::Serialize,
+{
+    match value.serialize(ValueEncoder)? {
+        Some(value) => Ok(value),
+        None => Err(Error::UnrepresentableType("none")),
+    }
+}
+
+/// Serializer for `to_value`. It produces `None` for `Option::None`, so that
+/// compounds can leave such fields out, as `Encoder` does.
+struct ValueEncoder;
+
+impl serde::Serializer for ValueEncoder {
+    type Ok = Option<Value>;
+    type Error = Error;
+    type SerializeSeq = ValueList;
+    type SerializeTuple = ser::Impossible<Option<Value>, Error>;
+    type SerializeTupleStruct = ValueList;
+    type SerializeTupleVariant = ser::Impossible<Option<Value>, Error>;
+    type SerializeMap = ValueCompound;
+    type SerializeStruct = ValueCompound;
+    type SerializeStructVariant = ValueCompound;
+
+    unrepresentable!(
+        u8 u16 u32 u64 char unit tuple tuple_variant
+    );
+
+    #[inline]
+    fn serialize_bool(self, value: bool) -> Result<Option<Value>> {
+        self.serialize_i8(value as i8)
+    }
+
+    #[inline]
+    fn serialize_i8(self, value: i8) -> Result<Option<Value>> {
+        Ok(Some(Value::Byte(value)))
+    }
+
+    #[inline]
+    fn serialize_i16(self, value: i16) -> Result<Option<Value>> {
+        Ok(Some(Value::Short(value)))
+    }
+
+    #[inline]
+    fn serialize_i32(self, value: i32) -> Result<Option<Value>> {
+        Ok(Some(Value::Int(value)))
+    }
+
+    #[inline]
+    fn serialize_i64(self, value: i64) -> Result<Option<Value>> {
+        Ok(Some(Value::Long(value)))
+    }
+
+    #[inline]
+    fn serialize_f32(self, value: f32) -> Result<Option<Value>> {
+        Ok(Some(Value::Float(value)))
+    }
+
+    #[inline]
+    fn serialize_f64(self, value: f64) -> Result<Option<Value>> {
+        Ok(Some(Value::Double(value)))
+    }
+
+    #[inline]
+    fn serialize_str(self, value: &str) -> Result<Option<Value>> {
+        Ok(Some(Value::String(value.to_string())))
+    }
+
+    /// Serialize bytes (e.g. from `serde_bytes`) as `TAG_Byte_Array` data.
+    #[inline]
+    fn serialize_bytes(self, value: &[u8]) -> Result<Option<Value>> {
+        Ok(Some(Value::ByteArray(value.iter().map(|&b| b as i8).collect())))
+    }
+
+    #[inline]
+    fn serialize_none(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, value: &T) -> Result<Option<Value>>
+        where T: ?Sized + ser::Serialize
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>> {
+        Ok(Some(Value::Compound(Map::new())))
+    }
+
+    #[inline]
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32,
+                              variant: &'static str) -> Result<Option<Value>>
+    {
+        self.serialize_str(variant)
+    }
+
+    #[inline]
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32,
+                                    variant: &'static str, value: &T)
+                                    -> Result<Option<Value>>
+        where T: ?Sized + ser::Serialize
+    {
+        let mut map = Map::new();
+        match value.serialize(ValueEncoder)? {
+            Some(value) => map.insert(variant.to_string(), value),
+            None => return Err(Error::UnrepresentableType("none")),
+        };
+        Ok(Some(Value::Compound(map)))
+    }
+
+    #[inline]
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32,
+                                variant: &'static str, _len: usize)
+                                -> Result<ValueCompound>
+    {
+        Ok(ValueCompound { map: Map::new(), key: None, variant: Some(variant) })
+    }
+
+    #[inline]
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T)
+                                           -> Result<Option<Value>>
+        where T: ?Sized + ser::Serialize
+    {
+        value.serialize(self)
+    }
+
+    #[inline]
+    fn serialize_seq(self, len: Option<usize>) -> Result<ValueList> {
+        Ok(ValueList { items: Vec::with_capacity(len.unwrap_or(0)), array: None })
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> Result<ValueList> {
+        match array_tag(name) {
+            Some(tag) => Ok(ValueList { items: Vec::with_capacity(len), array: Some(tag) }),
+            None => Err(Error::UnrepresentableType("tuple_struct")),
+        }
+    }
+
+    #[inline]
+    fn serialize_map(self, _len: Option<usize>) -> Result<ValueCompound> {
+        Ok(ValueCompound { map: Map::new(), key: None, variant: None })
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<ValueCompound> {
+        self.serialize_map(None)
+    }
+}
+
+#[doc(hidden)]
+pub struct ValueList {
+    items: Vec<Value>,
+    /// The array tag to produce instead of a `Value::List`, if any.
+    array: Option<i8>,
+}
+
+impl ser::SerializeSeq for ValueList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        let value = match value.serialize(ValueEncoder)? {
+            Some(value) => value,
+            None => return Err(Error::UnrepresentableType("none")),
+        };
+        if let Some(first) = self.items.first() {
+            if first.id() != value.id() {
+                return Err(Error::HeterogeneousList);
+            }
+        }
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::List(self.items)))
+    }
+}
+
+impl ser::SerializeTupleStruct for ValueList {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        macro_rules! collect {
+            ($variant:ident, $elem:ident, $tag:expr) => {
+                self.items.into_iter().map(|item| match item {
+                    Value::$elem(v) => Ok(v),
+                    other => Err(Error::TagMismatch(other.id(), $tag)),
+                }).collect::<Result<_>>().map(|v| Some(Value::$variant(v)))
+            };
+        }
+        match self.array {
+            Some(0x07) => collect!(ByteArray, Byte, 0x01),
+            Some(0x0b) => collect!(IntArray, Int, 0x03),
+            Some(0x0c) => collect!(LongArray, Long, 0x04),
+            _ => Ok(Some(Value::List(self.items))),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct ValueCompound {
+    map: Map,
+    key: Option<String>,
+    /// The struct variant these are the fields of, if any.
+    variant: Option<&'static str>,
+}
+
+impl ser::SerializeMap for ValueCompound {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        match key.serialize(ValueEncoder)? {
+            Some(Value::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(Error::NonStringMapKey),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        let key = self.key.take().expect("serialize_value called before serialize_key");
+        if let Some(value) = value.serialize(ValueEncoder)? {
+            self.map.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::Compound(self.map)))
+    }
+}
+
+impl ser::SerializeStruct for ValueCompound {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T)
+                                  -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        if let Some(value) = value.serialize(ValueEncoder)? {
+            self.map.insert(key.to_string(), value);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::Compound(self.map)))
+    }
+}
+
+impl ser::SerializeStructVariant for ValueCompound {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: ?Sized + serde::Serialize
+    {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        let mut outer = Map::new();
+        if let Some(variant) = self.variant {
+            outer.insert(variant.to_string(), Value::Compound(self.map));
+        }
+        Ok(Some(Value::Compound(outer)))
+    }
+}
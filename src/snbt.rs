@@ -0,0 +1,461 @@
+//! Parsing of stringified NBT (SNBT), the syntax used by Minecraft commands.
+
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+
+use error::{Error, Result};
+use map::Map;
+use value::Value;
+
+/// Parses a complete SNBT document into a `Value`.
+pub(crate) fn parse(src: &str) -> Result<Value> {
+    let mut parser = Parser { src, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos != src.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Parses a single SNBT value from the start of `src`, returning it along
+/// with the number of bytes it took up.
+pub(crate) fn parse_prefix(src: &str) -> Result<(Value, usize)> {
+    let mut parser = Parser { src, pos: 0 };
+    let value = parser.parse_value()?;
+    Ok((value, parser.pos))
+}
+
+/// Characters that may appear in unquoted strings and keys.
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '+'
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidSnbt(self.pos, msg.to_string())
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Skips whitespace and consumes `c` if it is the next character.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char, msg: &str) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(msg))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_compound(),
+            Some('[') => self.parse_list_or_array(),
+            Some('"') | Some('\'') => Ok(Value::String(self.parse_quoted()?)),
+            Some(_) => {
+                let start = self.pos;
+                let literal = self.parse_unquoted();
+                if literal.is_empty() {
+                    self.pos = start;
+                    return Err(self.error("expected a value"));
+                }
+                Ok(parse_literal(literal))
+            }
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<Value> {
+        self.expect('{', "expected '{'")?;
+        let mut map = Map::new();
+        if self.eat('}') {
+            return Ok(Value::Compound(map));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some('"') | Some('\'') => self.parse_quoted()?,
+                _ => self.parse_unquoted().to_string(),
+            };
+            if key.is_empty() {
+                return Err(self.error("expected a key"));
+            }
+            self.expect(':', "expected ':' after key")?;
+            let value = self.parse_value()?;
+            map.insert(key, value);
+            if self.eat('}') {
+                return Ok(Value::Compound(map));
+            }
+            self.expect(',', "expected ',' or '}'")?;
+        }
+    }
+
+    fn parse_list_or_array(&mut self) -> Result<Value> {
+        self.expect('[', "expected '['")?;
+        // Arrays are introduced by a type prefix, as in `[I; 1, 2, 3]`.
+        let rest = &self.src[self.pos..];
+        let mut chars = rest.chars();
+        if let (Some(prefix), Some(';')) = (chars.next(), chars.next()) {
+            if prefix == 'B' || prefix == 'I' || prefix == 'L' {
+                self.pos += 2;
+                return self.parse_array(prefix);
+            }
+        }
+
+        let mut vals = Vec::new();
+        if self.eat(']') {
+            return Ok(Value::List(vals));
+        }
+        loop {
+            let value = self.parse_value()?;
+            if let Some(first) = vals.first() {
+                if first.id() != value.id() {
+                    return Err(Error::HeterogeneousList);
+                }
+            }
+            vals.push(value);
+            if self.eat(']') {
+                return Ok(Value::List(vals));
+            }
+            self.expect(',', "expected ',' or ']'")?;
+        }
+    }
+
+    fn parse_array(&mut self, prefix: char) -> Result<Value> {
+        let mut vals = Vec::new();
+        if !self.eat(']') {
+            loop {
+                self.skip_whitespace();
+                let start = self.pos;
+                let elem = match (prefix, parse_literal(self.parse_unquoted())) {
+                    ('B', Value::Byte(v)) => Some(v as i64),
+                    ('B', Value::Int(v)) if v >= i8::MIN as i32 && v <= i8::MAX as i32 => Some(v as i64),
+                    ('I', Value::Int(v)) => Some(v as i64),
+                    ('L', Value::Int(v)) => Some(v as i64),
+                    ('L', Value::Long(v)) => Some(v),
+                    _ => None,
+                };
+                match elem {
+                    Some(v) => vals.push(v),
+                    None => {
+                        self.pos = start;
+                        return Err(self.error("invalid array element"));
+                    }
+                }
+                if self.eat(']') {
+                    break;
+                }
+                self.expect(',', "expected ',' or ']'")?;
+            }
+        }
+        Ok(match prefix {
+            'B' => Value::ByteArray(vals.into_iter().map(|v| v as i8).collect()),
+            'I' => Value::IntArray(vals.into_iter().map(|v| v as i32).collect()),
+            _ => Value::LongArray(vals),
+        })
+    }
+
+    fn parse_unquoted(&mut self) -> &'a str {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !is_unquoted_char(c) {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn parse_quoted(&mut self) -> Result<String> {
+        let quote = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error("expected a string")),
+        };
+        self.pos += 1;
+        let mut buf = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.pos += c.len_utf8();
+            if c == quote {
+                return Ok(buf);
+            }
+            if c != '\\' {
+                buf.push(c);
+                continue;
+            }
+            let escaped = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.pos += escaped.len_utf8();
+            match escaped {
+                '\\' | '"' | '\'' => buf.push(escaped),
+                'n' => buf.push('\n'),
+                't' => buf.push('\t'),
+                'r' => buf.push('\r'),
+                'b' => buf.push('\u{8}'),
+                'f' => buf.push('\u{c}'),
+                's' => buf.push(' '),
+                'u' => {
+                    let hex = self.src.get(self.pos..self.pos + 4);
+                    let c = hex.and_then(|h| u32::from_str_radix(h, 16).ok())
+                        .and_then(::std::char::from_u32);
+                    match c {
+                        Some(c) => {
+                            buf.push(c);
+                            self.pos += 4;
+                        }
+                        None => return Err(self.error("invalid unicode escape")),
+                    }
+                }
+                _ => {
+                    self.pos -= escaped.len_utf8();
+                    return Err(self.error("invalid escape sequence"));
+                }
+            }
+        }
+    }
+}
+
+/// Interprets an unquoted literal as a number or boolean if it has that form,
+/// and as a string otherwise.
+///
+/// As in Minecraft, literals that look numeric but are out of range for their
+/// type are treated as strings.
+fn parse_literal(literal: &str) -> Value {
+    if literal.eq_ignore_ascii_case("true") {
+        return Value::Byte(1);
+    }
+    if literal.eq_ignore_ascii_case("false") {
+        return Value::Byte(0);
+    }
+    let parsed = match literal.chars().last() {
+        Some('b') | Some('B') if is_integer(&literal[..literal.len() - 1]) => {
+            literal[..literal.len() - 1].parse().ok().map(Value::Byte)
+        }
+        Some('s') | Some('S') if is_integer(&literal[..literal.len() - 1]) => {
+            literal[..literal.len() - 1].parse().ok().map(Value::Short)
+        }
+        Some('l') | Some('L') if is_integer(&literal[..literal.len() - 1]) => {
+            literal[..literal.len() - 1].parse().ok().map(Value::Long)
+        }
+        Some('f') | Some('F') if is_decimal(&literal[..literal.len() - 1]) => {
+            literal[..literal.len() - 1].parse().ok().map(Value::Float)
+        }
+        Some('d') | Some('D') if is_decimal(&literal[..literal.len() - 1]) => {
+            literal[..literal.len() - 1].parse().ok().map(Value::Double)
+        }
+        _ if is_integer(literal) => literal.parse().ok().map(Value::Int),
+        _ if is_decimal(literal) && literal.contains('.') => {
+            literal.parse().ok().map(Value::Double)
+        }
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(literal.to_string()))
+}
+
+/// Whether `s` is an optionally-signed decimal integer.
+fn is_integer(s: &str) -> bool {
+    let digits = s.trim_start_matches(['-', '+']);
+    s.len() - digits.len() <= 1 && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether `s` is an optionally-signed decimal number, optionally with a
+/// fractional part and exponent.
+fn is_decimal(s: &str) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+    let mut parts = mantissa.splitn(2, '.');
+    let whole = parts.next().unwrap_or("");
+    let frac = parts.next().unwrap_or("");
+    let digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    let mantissa_ok = digits(whole) && digits(frac) && !(whole.is_empty() && frac.is_empty());
+    let exponent_ok = match exponent {
+        Some(e) => is_integer(e),
+        None => true,
+    };
+    mantissa_ok && exponent_ok
+}
+
+/// Formats a `Value` as SNBT, either compactly on one line or indented with
+/// four spaces per level.
+///
+/// Compound keys are emitted in sorted order so that the output is
+/// deterministic.
+pub(crate) fn write(value: &Value, pretty: bool) -> String {
+    let mut writer = Writer { buf: String::new(), pretty, depth: 0 };
+    writer.write_value(value);
+    writer.buf
+}
+
+/// Formats the contents of a Compound as SNBT, as with `write()`.
+#[cfg(feature = "std")]
+pub(crate) fn write_compound(map: &Map, pretty: bool) -> String {
+    let mut writer = Writer { buf: String::new(), pretty, depth: 0 };
+    writer.write_compound(map);
+    writer.buf
+}
+
+struct Writer {
+    buf: String,
+    pretty: bool,
+    depth: usize,
+}
+
+impl Writer {
+    fn newline(&mut self) {
+        if self.pretty {
+            self.buf.push('\n');
+            for _ in 0..self.depth {
+                self.buf.push_str("    ");
+            }
+        }
+    }
+
+    fn separator(&mut self) {
+        self.buf.push(',');
+        if self.pretty {
+            self.buf.push(' ');
+        }
+    }
+
+    fn write_value(&mut self, value: &Value) {
+        match *value {
+            Value::Byte(v) => self.buf.push_str(&format!("{}b", v)),
+            Value::Short(v) => self.buf.push_str(&format!("{}s", v)),
+            Value::Int(v) => self.buf.push_str(&v.to_string()),
+            Value::Long(v) => self.buf.push_str(&format!("{}L", v)),
+            // `{:?}` always includes a decimal point or exponent.
+            Value::Float(v) => self.buf.push_str(&format!("{:?}f", v)),
+            Value::Double(v) => self.buf.push_str(&format!("{:?}d", v)),
+            Value::String(ref v) => write_quoted(&mut self.buf, v),
+            Value::ByteArray(ref v) => self.write_array('B', v.iter().map(|x| format!("{}b", x))),
+            Value::IntArray(ref v) => self.write_array('I', v.iter().map(|x| x.to_string())),
+            Value::LongArray(ref v) => self.write_array('L', v.iter().map(|x| format!("{}L", x))),
+            Value::List(ref vals) => self.write_list(vals),
+            Value::Compound(ref map) => self.write_compound(map),
+        }
+    }
+
+    fn write_array<I: Iterator<Item = String>>(&mut self, prefix: char, elems: I) {
+        self.buf.push('[');
+        self.buf.push(prefix);
+        self.buf.push(';');
+        for (i, elem) in elems.enumerate() {
+            if i > 0 {
+                self.separator();
+            } else if self.pretty {
+                self.buf.push(' ');
+            }
+            self.buf.push_str(&elem);
+        }
+        self.buf.push(']');
+    }
+
+    fn write_list(&mut self, vals: &[Value]) {
+        // Only lists of containers are broken over several lines.
+        let nested = match vals.first() {
+            Some(&Value::Compound(_)) | Some(&Value::List(_)) => self.pretty,
+            _ => false,
+        };
+        self.buf.push('[');
+        if !nested {
+            for (i, val) in vals.iter().enumerate() {
+                if i > 0 {
+                    self.separator();
+                }
+                self.write_value(val);
+            }
+            self.buf.push(']');
+            return;
+        }
+        self.depth += 1;
+        for (i, val) in vals.iter().enumerate() {
+            if i > 0 {
+                self.buf.push(',');
+            }
+            self.newline();
+            self.write_value(val);
+        }
+        self.depth -= 1;
+        self.newline();
+        self.buf.push(']');
+    }
+
+    fn write_compound(&mut self, map: &Map) {
+        self.buf.push('{');
+        if map.is_empty() {
+            self.buf.push('}');
+            return;
+        }
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        self.depth += 1;
+        for (i, key) in keys.into_iter().enumerate() {
+            if i > 0 {
+                self.buf.push(',');
+            }
+            self.newline();
+            if !key.is_empty() && key.chars().all(is_unquoted_char) {
+                self.buf.push_str(key);
+            } else {
+                write_quoted(&mut self.buf, key);
+            }
+            self.buf.push(':');
+            if self.pretty {
+                self.buf.push(' ');
+            }
+            self.write_value(&map[key]);
+        }
+        self.depth -= 1;
+        self.newline();
+        self.buf.push('}');
+    }
+}
+
+/// Writes `s` as a quoted string, preferring double quotes unless the string
+/// contains double quotes but no single quotes.
+fn write_quoted(buf: &mut String, s: &str) {
+    let quote = if s.contains('"') && !s.contains('\'') { '\'' } else { '"' };
+    buf.push(quote);
+    for c in s.chars() {
+        if c == quote || c == '\\' {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+    buf.push(quote);
+}
@@ -0,0 +1,411 @@
+//! Streaming access to NBT documents, one tag at a time.
+//!
+//! `NbtReader` is a pull parser: each call produces the next `Token` of the
+//! document without building a `Value` tree, so that very large documents
+//! can be scanned in constant memory (apart from the contents of individual
+//! strings and arrays). `NbtWriter` is its counterpart for output.
+//!
+//! ```rust
+//! use nbt::{Blob, Endianness, NbtReader, Token, Value};
+//!
+//! let mut nbt = Blob::new();
+//! nbt.insert("pos", Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)])).unwrap();
+//! let mut bytes = Vec::new();
+//! nbt.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+//!
+//! let tokens: Vec<Token> = NbtReader::new(&bytes[..], Endianness::BigEndian)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(tokens, vec![
+//!     Token::CompoundStart(Some("".to_string())),
+//!     Token::ListStart(Some("pos".to_string()), 0x03, 3),
+//!     Token::Int(None, 1),
+//!     Token::Int(None, 2),
+//!     Token::Int(None, 3),
+//!     Token::End,
+//!     Token::End,
+//! ]);
+//! ```
+//!
+//! Writing the same document with `NbtWriter`:
+//!
+//! ```rust
+//! use nbt::{Blob, Endianness, NbtWriter, Value};
+//!
+//! let mut dst = NbtWriter::new(Vec::new(), Endianness::BigEndian);
+//! dst.begin_compound("").unwrap();
+//! dst.begin_list("pos", 0x03, 3).unwrap();
+//! for i in 1..4 {
+//!     dst.write_int("", i).unwrap();
+//! }
+//! dst.end().unwrap();
+//! dst.end().unwrap();
+//! let bytes = dst.finish().unwrap();
+//!
+//! let nbt = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+//! assert_eq!(nbt["pos"], Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]));
+//! ```
+
+use std::io;
+
+use error::{Error, Result};
+use raw::{Endianness, RawReader, RawWriter};
+use value::Value;
+
+/// A single event produced by `NbtReader`.
+///
+/// The name of a tag is `Some` for the root tag and for the entries of a
+/// Compound, and `None` for the elements of a List.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Byte(Option<String>, i8),
+    Short(Option<String>, i16),
+    Int(Option<String>, i32),
+    Long(Option<String>, i64),
+    Float(Option<String>, f32),
+    Double(Option<String>, f64),
+    ByteArray(Option<String>, Vec<i8>),
+    String(Option<String>, String),
+    IntArray(Option<String>, Vec<i32>),
+    LongArray(Option<String>, Vec<i64>),
+    /// The start of a List, with the type ID of its elements and their count.
+    /// The elements follow, and then an `End`.
+    ListStart(Option<String>, i8, i32),
+    /// The start of a Compound. Its entries follow, and then an `End`.
+    CompoundStart(Option<String>),
+    /// The end of the innermost open List or Compound.
+    End,
+}
+
+impl Token {
+    /// The name of the tag this token starts or holds, if it has one. `End`
+    /// tokens have no name.
+    pub fn name(&self) -> Option<&str> {
+        let name = match *self {
+            Token::Byte(ref name, _) | Token::Short(ref name, _) | Token::Int(ref name, _)
+            | Token::Long(ref name, _) | Token::Float(ref name, _) | Token::Double(ref name, _)
+            | Token::ByteArray(ref name, _) | Token::String(ref name, _)
+            | Token::IntArray(ref name, _) | Token::LongArray(ref name, _)
+            | Token::ListStart(ref name, _, _) | Token::CompoundStart(ref name) => name,
+            Token::End => return None,
+        };
+        name.as_ref().map(|name| &name[..])
+    }
+}
+
+enum Frame {
+    Compound,
+    List { tag: i8, remaining: i32 },
+}
+
+/// A pull parser producing the `Token`s of a single NBT document from an
+/// `io::Read` source.
+///
+/// Reading stops after the root tag is complete; the source can then be
+/// recovered with `into_inner()`. The reader is also an `Iterator`, which
+/// ends after the first error.
+pub struct NbtReader<R: io::Read> {
+    src: RawReader<R>,
+    stack: Vec<Frame>,
+    done: bool,
+}
+
+impl<R> NbtReader<R>
+    where R: io::Read,
+{
+    /// Creates a reader for the document at the start of `src`.
+    pub fn new(src: R, endian: Endianness) -> Self {
+        NbtReader { src: RawReader::new(src, endian), stack: Vec::new(), done: false }
+    }
+
+    /// The number of Lists and Compounds that have been started but not yet
+    /// ended.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Unwraps this reader, returning the underlying `io::Read` source.
+    pub fn into_inner(self) -> R {
+        self.src.into_inner()
+    }
+
+    /// Reads the next token, or returns `None` once the root tag has ended.
+    pub fn next_token(&mut self) -> Result<Option<Token>> {
+        if self.done {
+            return Ok(None);
+        }
+        let (tag, name) = match self.stack.last_mut() {
+            None => {
+                let (tag, name) = self.src.emit_next_header()?;
+                if tag == 0x00 {
+                    return Err(Error::NoRootCompound);
+                }
+                (tag, Some(name))
+            }
+            Some(&mut Frame::Compound) => {
+                let (tag, name) = self.src.emit_next_header()?;
+                if tag == 0x00 {
+                    return Ok(Some(self.end()));
+                }
+                (tag, Some(name))
+            }
+            Some(&mut Frame::List { tag, ref mut remaining }) => {
+                if *remaining <= 0 {
+                    return Ok(Some(self.end()));
+                }
+                *remaining -= 1;
+                (tag, None)
+            }
+        };
+        let token = self.read_payload(tag, name)?;
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+        Ok(Some(token))
+    }
+
+    /// Skips the rest of the innermost open List or Compound, including its
+    /// `End` token. Does nothing at the top level.
+    pub fn skip_container(&mut self) -> Result<()> {
+        let depth = self.stack.len();
+        while self.stack.len() >= depth && depth > 0 {
+            if self.next_token()?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn end(&mut self) -> Token {
+        self.stack.pop();
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+        Token::End
+    }
+
+    fn read_payload(&mut self, tag: i8, name: Option<String>) -> Result<Token> {
+        let src = &mut self.src;
+        Ok(match tag {
+            0x01 => Token::Byte(name, src.read_bare_byte()?),
+            0x02 => Token::Short(name, src.read_bare_short()?),
+            0x03 => Token::Int(name, src.read_bare_int()?),
+            0x04 => Token::Long(name, src.read_bare_long()?),
+            0x05 => Token::Float(name, src.read_bare_float()?),
+            0x06 => Token::Double(name, src.read_bare_double()?),
+            0x07 => Token::ByteArray(name, src.read_bare_byte_array()?),
+            0x08 => Token::String(name, src.read_bare_string()?),
+            0x09 => {
+                let elem = src.read_bare_byte()?;
+                let len = src.read_list_len()? as i32;
+                self.stack.push(Frame::List { tag: elem, remaining: len });
+                Token::ListStart(name, elem, len)
+            }
+            0x0a => {
+                self.stack.push(Frame::Compound);
+                Token::CompoundStart(name)
+            }
+            0x0b => Token::IntArray(name, src.read_bare_int_array()?),
+            0x0c => Token::LongArray(name, src.read_bare_long_array()?),
+            e => return Err(Error::InvalidTypeId(e)),
+        })
+    }
+}
+
+impl<R> Iterator for NbtReader<R>
+    where R: io::Read,
+{
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Result<Token>> {
+        let token = self.next_token();
+        if token.is_err() {
+            self.done = true;
+        }
+        token.transpose()
+    }
+}
+
+/// A push-style writer for a single NBT document.
+///
+/// Tags are written in document order: Compounds and Lists are opened with
+/// `begin_compound()` and `begin_list()`, and closed with `end()`. The writer
+/// checks that the calls are correctly nested, and that each List receives
+/// exactly as many elements as it declared, all of its element type. A call
+/// that would produce an invalid document returns an error without writing
+/// anything.
+///
+/// Every method takes the name of the tag to write. It is written for the
+/// root tag and for the entries of a Compound, and ignored for the elements
+/// of a List, which have no names.
+pub struct NbtWriter<W: io::Write> {
+    dst: RawWriter<W>,
+    stack: Vec<Frame>,
+    done: bool,
+}
+
+macro_rules! write_method {
+    ($(#[$attr:meta])* $method:ident, $ty:ty, $tag:expr, $raw:ident) => {
+        $(#[$attr])*
+        pub fn $method(&mut self, name: &str, value: $ty) -> Result<()> {
+            self.begin_tag($tag, name)?;
+            self.dst.$raw(value)?;
+            self.finish_tag();
+            Ok(())
+        }
+    }
+}
+
+impl<W> NbtWriter<W>
+    where W: io::Write,
+{
+    /// Creates a writer for the given `io::Write` destination.
+    pub fn new(dst: W, endian: Endianness) -> Self {
+        NbtWriter { dst: RawWriter::new(dst, endian), stack: Vec::new(), done: false }
+    }
+
+    /// The number of Lists and Compounds that have been started but not yet
+    /// ended.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Unwraps this writer, returning the underlying `io::Write` destination,
+    /// or an error if the document is not complete.
+    pub fn finish(self) -> Result<W> {
+        if !self.done {
+            return Err(Error::InvalidNesting("the document is incomplete"));
+        }
+        Ok(self.dst.into_inner())
+    }
+
+    write_method!(
+        /// Writes a `TAG_Byte`.
+        write_byte, i8, 0x01, write_bare_byte);
+    write_method!(
+        /// Writes a `TAG_Short`.
+        write_short, i16, 0x02, write_bare_short);
+    write_method!(
+        /// Writes a `TAG_Int`.
+        write_int, i32, 0x03, write_bare_int);
+    write_method!(
+        /// Writes a `TAG_Long`.
+        write_long, i64, 0x04, write_bare_long);
+    write_method!(
+        /// Writes a `TAG_Float`.
+        write_float, f32, 0x05, write_bare_float);
+    write_method!(
+        /// Writes a `TAG_Double`.
+        write_double, f64, 0x06, write_bare_double);
+    write_method!(
+        /// Writes a `TAG_Byte_Array`.
+        write_byte_array, &[i8], 0x07, write_bare_byte_array);
+    write_method!(
+        /// Writes a `TAG_String`.
+        write_string, &str, 0x08, write_bare_string);
+    write_method!(
+        /// Writes a `TAG_Int_Array`.
+        write_int_array, &[i32], 0x0b, write_bare_int_array);
+    write_method!(
+        /// Writes a `TAG_Long_Array`.
+        write_long_array, &[i64], 0x0c, write_bare_long_array);
+
+    /// Writes a complete `Value` of any type, for mixing materialized values
+    /// into a streamed document.
+    pub fn write_value(&mut self, name: &str, value: &Value) -> Result<()> {
+        self.begin_tag(value.id(), name)?;
+        value.to_raw_writer(&mut self.dst)?;
+        self.finish_tag();
+        Ok(())
+    }
+
+    /// Writes a `Token` as produced by `NbtReader`, so that documents can be
+    /// filtered or transformed as they stream through. A missing name is
+    /// written as an empty one.
+    pub fn write_token(&mut self, token: &Token) -> Result<()> {
+        let name = token.name().unwrap_or("");
+        match *token {
+            Token::Byte(_, v) => self.write_byte(name, v),
+            Token::Short(_, v) => self.write_short(name, v),
+            Token::Int(_, v) => self.write_int(name, v),
+            Token::Long(_, v) => self.write_long(name, v),
+            Token::Float(_, v) => self.write_float(name, v),
+            Token::Double(_, v) => self.write_double(name, v),
+            Token::ByteArray(_, ref v) => self.write_byte_array(name, v),
+            Token::String(_, ref v) => self.write_string(name, v),
+            Token::IntArray(_, ref v) => self.write_int_array(name, v),
+            Token::LongArray(_, ref v) => self.write_long_array(name, v),
+            Token::ListStart(_, tag, len) => self.begin_list(name, tag, len),
+            Token::CompoundStart(_) => self.begin_compound(name),
+            Token::End => self.end(),
+        }
+    }
+
+    /// Starts a Compound. Its entries are written next, followed by `end()`.
+    pub fn begin_compound(&mut self, name: &str) -> Result<()> {
+        self.begin_tag(0x0a, name)?;
+        self.dst.begin_compound();
+        self.stack.push(Frame::Compound);
+        Ok(())
+    }
+
+    /// Starts a List of `len` elements with type ID `tag`. Exactly `len`
+    /// elements of that type are written next, followed by `end()`.
+    ///
+    /// An empty List may have any element type; Minecraft uses `TAG_End`
+    /// (`0x00`).
+    pub fn begin_list(&mut self, name: &str, tag: i8, len: i32) -> Result<()> {
+        if !(0x00..=0x0c).contains(&tag) || (tag == 0x00 && len != 0) {
+            return Err(Error::InvalidTypeId(tag));
+        }
+        if len < 0 {
+            return Err(Error::InvalidNesting("a List cannot have a negative length"));
+        }
+        self.begin_tag(0x09, name)?;
+        self.dst.write_bare_byte(tag)?;
+        self.dst.write_bare_int(len)?;
+        self.stack.push(Frame::List { tag, remaining: len });
+        Ok(())
+    }
+
+    /// Ends the innermost open List or Compound.
+    pub fn end(&mut self) -> Result<()> {
+        match self.stack.last() {
+            None => return Err(Error::InvalidNesting("there is no open List or Compound to end")),
+            Some(&Frame::List { remaining, .. }) if remaining > 0 => {
+                return Err(Error::InvalidNesting("a List was ended before all of its elements were written"));
+            }
+            Some(&Frame::List { .. }) => {}
+            Some(&Frame::Compound) => self.dst.close_nbt()?,
+        }
+        self.stack.pop();
+        self.finish_tag();
+        Ok(())
+    }
+
+    /// Checks that a tag of type `tag` may be written next, and writes its
+    /// header if it needs one.
+    fn begin_tag(&mut self, tag: i8, name: &str) -> Result<()> {
+        match self.stack.last_mut() {
+            None if self.done => Err(Error::InvalidNesting("the document is already complete")),
+            None | Some(&mut Frame::Compound) => self.dst.write_header(tag, name),
+            Some(&mut Frame::List { tag: expected, ref mut remaining }) => {
+                if tag != expected {
+                    return Err(Error::HeterogeneousList);
+                }
+                if *remaining == 0 {
+                    return Err(Error::InvalidNesting("a List was given more elements than declared"));
+                }
+                *remaining -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Marks the document as complete if the tag just finished was the root.
+    fn finish_tag(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::io;
 use std::fs::File;
 
@@ -7,6 +6,7 @@ use std::fs::File;
 use raw::Endianness;
 use blob::Blob;
 use error::Error;
+use map::Map;
 use value::Value;
 
 #[test]
@@ -81,7 +81,7 @@ fn nbt_empty_nbtfile() {
 
 #[test]
 fn nbt_nested_compound() {
-    let mut inner = HashMap::new();
+    let mut inner = Map::new();
     inner.insert("test".to_string(), Value::Byte(123));
     let mut nbt = Blob::new();
     nbt.insert("inner", Value::Compound(inner)).unwrap();
@@ -195,7 +195,7 @@ fn nbt_no_end_tag() {
     ];
 
     // Will fail, because there is no end tag.
-    assert_eq!(Blob::from_reader(&mut io::Cursor::new(&bytes[..]), Endianness::BigEndian),
+    assert_eq!(Blob::from_reader(&mut io::Cursor::new(&bytes[..]), Endianness::BigEndian).map_err(Error::without_location),
             Err(Error::IncompleteNbtValue));
 }
 
@@ -210,7 +210,7 @@ fn nbt_invalid_id() {
                 0x01,
         0x00
     ];
-    assert_eq!(Blob::from_reader(&mut io::Cursor::new(&bytes[..]), Endianness::BigEndian),
+    assert_eq!(Blob::from_reader(&mut io::Cursor::new(&bytes[..]), Endianness::BigEndian).map_err(Error::without_location),
                Err(Error::InvalidTypeId(15)));
 }
 
@@ -226,6 +226,7 @@ fn nbt_invalid_list() {
 }
 
 #[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 fn nbt_bad_compression() {
     // These aren't in the zlib or gzip format, so they'll fail.
     let bytes = vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -234,6 +235,7 @@ fn nbt_bad_compression() {
 }
 
 #[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 fn nbt_compression() {
     // Create a non-trivial Blob.
     let mut nbt = Blob::new();
@@ -257,6 +259,7 @@ fn nbt_compression() {
 }
 
 #[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 fn nbt_bigtest() {
     let mut bigtest_file = File::open("tests/big1.nbt").unwrap();
     let bigtest = Blob::from_gzip_reader(&mut bigtest_file, Endianness::BigEndian).unwrap();
@@ -366,3 +369,607 @@ fn nbt_modified_utf8() {
     let file = Blob::from_reader(&mut src, Endianness::BigEndian).unwrap();
     assert_eq!(&file, &nbt);
 }
+
+/// Reads all of `src` using reads of at most `size` bytes at a time.
+fn read_in_chunks<R: io::Read>(src: &mut R, size: usize) -> Vec<u8> {
+    let mut dst = Vec::new();
+    let mut buf = vec![0; size];
+    loop {
+        match src.read(&mut buf).unwrap() {
+            0 => return dst,
+            n => dst.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn nbt_blob_reader_matches_writer() {
+    let mut bigtest_file = File::open("tests/big1.nbt").unwrap();
+    let bigtest = Blob::from_gzip_reader(&mut bigtest_file, Endianness::BigEndian).unwrap();
+
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let mut expected = Vec::new();
+        bigtest.to_writer(&mut expected, endian).unwrap();
+        for size in 1..4 {
+            let streamed = read_in_chunks(&mut bigtest.reader(endian), size);
+            assert_eq!(&streamed, &expected);
+        }
+    }
+}
+
+#[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn nbt_blob_reader_compressed() {
+    let mut arrays_file = File::open("tests/arrays.nbt").unwrap();
+    let arrays = Blob::from_reader(&mut arrays_file, Endianness::BigEndian).unwrap();
+
+    let gzip = read_in_chunks(&mut arrays.gzip_reader(Endianness::BigEndian), 3);
+    let gz_file = Blob::from_gzip_reader(&mut io::Cursor::new(gzip), Endianness::BigEndian).unwrap();
+    assert_eq!(&arrays, &gz_file);
+
+    let zlib = read_in_chunks(&mut arrays.zlib_reader(Endianness::BigEndian), 2);
+    let zlib_file = Blob::from_zlib_reader(&mut io::Cursor::new(zlib), Endianness::BigEndian).unwrap();
+    assert_eq!(&arrays, &zlib_file);
+}
+
+#[test]
+fn nbt_blob_reader_bounded_memory() {
+    use std::io::Read;
+
+    let mut nbt = Blob::new();
+    nbt.insert("longs", vec![0x0102030405060708i64; 1 << 20]).unwrap();
+
+    let mut reader = nbt.reader(Endianness::BigEndian);
+    let mut buf = [0; 4096];
+    let mut total = 0;
+    loop {
+        match reader.read(&mut buf).unwrap() {
+            0 => break,
+            n => total += n,
+        }
+        // The array alone is 8 MiB, but only a small chunk is ever buffered.
+        assert!(reader.buffer_capacity() <= 16 * 1024);
+    }
+    assert_eq!(total, 1 + 2 + 1 + 2 + 5 + 4 + 8 * (1 << 20) + 1);
+}
+
+#[test]
+fn nbt_blob_reader_invalid_list() {
+    let mut inner = Map::new();
+    inner.insert("list".to_string(), Value::List(vec![Value::Byte(1), Value::Short(1)]));
+    let mut nbt = Blob::new();
+    nbt.insert("inner", Value::Compound(inner)).unwrap();
+
+    let mut dst = Vec::new();
+    let err = io::copy(&mut nbt.reader(Endianness::BigEndian), &mut dst).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn value_into_conversions() {
+    assert_eq!(Value::String("a".to_string()).into_string(), Ok("a".to_string()));
+    assert_eq!(Value::List(vec![Value::Int(1)]).into_list(), Ok(vec![Value::Int(1)]));
+    assert_eq!(Value::IntArray(vec![1]).into_int_array(), Ok(vec![1]));
+
+    // The original value is handed back on failure.
+    assert_eq!(Value::Int(1).into_compound(), Err(Value::Int(1)));
+    assert_eq!(Value::IntArray(vec![1]).into_long_array(), Err(Value::IntArray(vec![1])));
+    assert_eq!(Value::ByteArray(vec![1]).into_list(), Err(Value::ByteArray(vec![1])));
+    assert_eq!(Value::LongArray(vec![1]).into_byte_array(), Err(Value::LongArray(vec![1])));
+    assert_eq!(Value::List(Vec::new()).into_string(), Err(Value::List(Vec::new())));
+}
+
+#[test]
+fn nbt_remove_as() {
+    let mut nbt = Blob::new();
+    nbt.insert("name", "Herobrine").unwrap();
+    nbt.insert("health", 100i8).unwrap();
+
+    assert_eq!(nbt.remove_as::<String>("name"), Some(Ok("Herobrine".to_string())));
+    assert_eq!(nbt.remove_as::<String>("name"), None);
+    assert_eq!(nbt.remove_as::<Vec<Value>>("health"), Some(Err(Value::Byte(100))));
+    assert_eq!(nbt, Blob::new());
+}
+
+#[test]
+fn value_iter_as() {
+    let list = Value::List(vec![Value::Double(1.0), Value::Double(2.5)]);
+    let vals: Vec<f64> = list.iter_as().unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(vals, vec![1.0, 2.5]);
+
+    let empty = Value::List(Vec::new());
+    assert_eq!(empty.iter_as::<i32>().unwrap().count(), 0);
+
+    // Not a list at all.
+    assert_eq!(Value::IntArray(vec![1]).iter_as::<i32>().err(), Some(Error::TagMismatch(0x0b, 0x09)));
+    // The wrong element type is caught before iterating.
+    assert_eq!(list.iter_as::<f32>().err(), Some(Error::TagMismatch(0x06, 0x05)));
+    assert_eq!(list.iter_compounds().err(), Some(Error::TagMismatch(0x06, 0x0a)));
+
+    // Lists that lie about their homogeneity fail per element.
+    let mixed = Value::List(vec![Value::Int(1), Value::Short(2), Value::Int(3)]);
+    let vals: Vec<_> = mixed.iter_as::<i32>().unwrap().collect();
+    assert_eq!(vals, vec![Ok(1), Err(Error::TagMismatch(0x02, 0x03)), Ok(3)]);
+}
+
+#[test]
+fn nbt_iter_list_as() {
+    let mut nbt = Blob::new();
+    nbt.insert("tags", Value::List(vec![Value::String("a".to_string())])).unwrap();
+
+    let tags: Vec<&str> = nbt.iter_list_as("tags").unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(tags, vec!["a"]);
+    assert_eq!(nbt.iter_list_as::<&str>("missing").err(), Some(Error::MissingField("missing".to_string())));
+}
+
+#[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn nbt_network_roundtrip() {
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    let player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+
+    let mut named = Vec::new();
+    player.to_writer(&mut named, Endianness::BigEndian).unwrap();
+    let mut nameless = Vec::new();
+    player.to_network_writer(&mut nameless, Endianness::BigEndian).unwrap();
+    // The network form drops the two-byte name length (the name is empty).
+    assert_eq!(&named[..3], &[0x0a, 0x00, 0x00]);
+    assert_eq!(nameless[0], 0x0a);
+    assert_eq!(&nameless[1..], &named[3..]);
+
+    let read = Blob::from_network_reader(&mut &nameless[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read, player);
+
+    // TAG_End marks absent NBT, which is not a Blob.
+    assert_eq!(Blob::from_network_reader(&mut &[0x00][..], Endianness::BigEndian),
+               Err(Error::NoRootCompound));
+    assert!(Blob::from_network_reader(&mut &[0x0a][..], Endianness::BigEndian).is_err());
+}
+
+#[test]
+fn nbt_blob_collections() {
+    let mut nbt: Blob = vec![("health", Value::Byte(20)), ("name", Value::from("Steve"))].into_iter().collect();
+    assert_eq!(nbt.len(), 2);
+    assert!(!nbt.is_empty() && Blob::new().is_empty());
+
+    nbt.extend(vec![("food".to_string(), 18i8), ("health".to_string(), 10i8)]);
+    assert_eq!(nbt.len(), 3);
+    assert_eq!(nbt["health"], Value::Byte(10));
+
+    let mut keys: Vec<&String> = nbt.keys().collect();
+    keys.sort();
+    assert_eq!(keys, ["food", "health", "name"]);
+    assert_eq!(nbt.values().filter(|v| v.id() == 0x01).count(), 2);
+
+    for value in nbt.values_mut() {
+        if let Value::Byte(ref mut n) = *value {
+            *n += 1;
+        }
+    }
+    for (name, value) in &mut nbt {
+        if name == "name" {
+            *value = Value::from("Alex");
+        }
+    }
+    assert_eq!(nbt.iter().map(|(k, _)| k.len()).sum::<usize>(), 14);
+    let mut entries: Vec<(String, Value)> = nbt.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(entries, [
+        ("food".to_string(), Value::Byte(19)),
+        ("health".to_string(), Value::Byte(11)),
+        ("name".to_string(), Value::from("Alex")),
+    ]);
+}
+
+#[test]
+fn nbt_entries() {
+    let mut nbt = Blob::new();
+    {
+        let level = nbt.entry("Level").or_insert_with(|| Value::Compound(Map::new()));
+        level.entry("xPos").unwrap().or_insert(Value::Int(4));
+        level.entry("xPos").unwrap().and_modify(|v| *v = Value::Int(5)).or_insert(Value::Int(0));
+        assert!(level.entry("Sections").unwrap().key() == "Sections");
+    }
+    assert_eq!(nbt.get_path("Level.xPos"), Some(&Value::Int(5)));
+    assert_eq!(nbt.len(), 1);
+
+    let name = nbt.entry("name".to_string()).or_insert_with(|| Value::from("Steve"));
+    assert_eq!(*name, Value::from("Steve"));
+    assert!(nbt.entry("name").and_modify(|v| *v = Value::Byte(0)).or_insert(Value::Byte(1)) == &Value::Byte(0));
+    assert!(nbt.get_path_mut("Level.xPos").unwrap().entry("Sections").is_none());
+}
+
+#[test]
+fn nbt_remove_and_retain() {
+    let mut nbt = Blob::new();
+    nbt.insert("id", "minecraft:zombie").unwrap();
+    nbt.insert("Pos", Value::List(vec![Value::Double(0.5), Value::Double(64.0), Value::Double(0.5)])).unwrap();
+    nbt.insert("Health", 20.0f32).unwrap();
+    let mut passenger = Map::new();
+    passenger.insert("id".to_string(), Value::from("minecraft:chicken"));
+    passenger.insert("Pos".to_string(), Value::List(vec![]));
+    nbt.insert("Passengers", Value::List(vec![Value::List(vec![Value::Compound(passenger)])])).unwrap();
+
+    assert_eq!(nbt.remove("Health"), Some(Value::Float(20.0)));
+    assert_eq!(nbt.remove("Health"), None);
+
+    nbt.retain_recursive(|key, _| key != "Pos");
+    assert_eq!(nbt.get("Pos"), None);
+    assert_eq!(nbt.get_path("Passengers[0][0].Pos"), None);
+    assert_eq!(nbt.get_path("Passengers[0][0].id"), Some(&Value::from("minecraft:chicken")));
+
+    let rider = nbt.get_path_mut("Passengers[0][0]").unwrap();
+    assert_eq!(rider.remove("id"), Some(Value::from("minecraft:chicken")));
+    assert_eq!(*rider, Value::Compound(Map::new()));
+    assert_eq!(Value::Int(1).remove("id"), None);
+
+    nbt.retain(|key, value| key == "id" && value.id() == 0x08);
+    assert_eq!(nbt.len(), 1);
+    let mut root = Value::Compound(Map::new());
+    root.retain(|_, _| false);
+    root.retain_recursive(|_, _| false);
+}
+
+#[test]
+fn nbt_get_with_runtime_names() {
+    let mut nbt = Blob::new();
+    nbt.insert("Score", 1i32).unwrap();
+    let names: Vec<String> = vec!["Score".to_string(), "Missing".to_string()];
+
+    assert_eq!(nbt.get(&names[0]), Some(&Value::Int(1)));
+    assert_eq!(nbt.get(names[1].clone()), None);
+    if let Some(&mut Value::Int(ref mut score)) = nbt.get_mut(&names[0]) {
+        *score += 41;
+    }
+    assert_eq!(nbt.get("Score"), Some(&Value::Int(42)));
+    assert_eq!(nbt.get_mut("Missing"), None);
+}
+
+#[test]
+fn nbt_index_nested_values() {
+    let mut level = Map::new();
+    level.insert("xPos".to_string(), Value::Int(0));
+    level.insert("Heights".to_string(), Value::List(vec![Value::Short(1), Value::Short(2)]));
+    let mut nbt = Blob::new();
+    nbt.insert("Level", Value::Compound(level)).unwrap();
+
+    nbt["Level"]["xPos"] = 5.into();
+    nbt["Level"]["Heights"][1] = Value::Short(7);
+    let key = "Heights".to_string();
+    assert_eq!(nbt["Level"][&key][1], Value::Short(7));
+    assert_eq!(nbt.get_path("Level.xPos"), Some(&Value::Int(5)));
+
+    assert_eq!(nbt["Level"].get("zPos"), None);
+    assert_eq!(nbt["Level"].get(0), None);
+    assert_eq!(nbt["Level"]["Heights"].get(2), None);
+    assert_eq!(nbt["Level"]["xPos"].get("x"), None);
+    *nbt["Level"].get_mut(key).unwrap() = Value::IntArray(vec![3]);
+    assert_eq!(nbt["Level"]["Heights"], Value::IntArray(vec![3]));
+}
+
+#[test]
+#[should_panic(expected = "no such entry or element in TAG_List")]
+fn nbt_index_out_of_bounds() {
+    let list = Value::List(vec![Value::Byte(1)]);
+    let _ = &list[1];
+}
+
+#[test]
+fn nbt_typed_accessors() {
+    let values = vec![
+        Value::Byte(1), Value::Short(2), Value::Int(3), Value::Long(4),
+        Value::Float(5.0), Value::Double(6.0), Value::ByteArray(vec![7]),
+        Value::from("8"), Value::List(vec![]), Value::Compound(Map::new()),
+        Value::IntArray(vec![11]), Value::LongArray(vec![12]),
+    ];
+    assert_eq!(values[0].as_i8(), Some(1));
+    assert_eq!(values[1].as_i16(), Some(2));
+    assert_eq!(values[2].as_i32(), Some(3));
+    assert_eq!(values[3].as_i64(), Some(4));
+    assert_eq!(values[4].as_f32(), Some(5.0));
+    assert_eq!(values[5].as_f64(), Some(6.0));
+    assert_eq!(values[6].as_byte_array(), Some(&[7][..]));
+    assert_eq!(values[7].as_str(), Some("8"));
+    assert_eq!(values[8].as_list(), Some(&[][..]));
+    assert_eq!(values[9].as_compound(), Some(&Map::new()));
+    assert_eq!(values[10].as_int_array(), Some(&[11][..]));
+    assert_eq!(values[11].as_long_array(), Some(&[12][..]));
+
+    let predicates: [fn(&Value) -> bool; 12] = [
+        Value::is_byte, Value::is_short, Value::is_int, Value::is_long,
+        Value::is_float, Value::is_double, Value::is_byte_array, Value::is_string,
+        Value::is_list, Value::is_compound, Value::is_int_array, Value::is_long_array,
+    ];
+    for (i, value) in values.iter().enumerate() {
+        for (j, is) in predicates.iter().enumerate() {
+            assert_eq!(is(value), i == j);
+        }
+    }
+    // Numbers are not converted between widths.
+    assert_eq!(values[0].as_i32(), None);
+    assert_eq!(values[2].as_i64(), None);
+
+    let mut list = Value::List(vec![Value::Byte(1)]);
+    list.as_list_mut().unwrap().push(Value::Byte(2));
+    assert_eq!(list.as_list().map(|l| l.len()), Some(2));
+    assert!(list.as_compound_mut().is_none());
+}
+
+#[test]
+fn nbt_try_from_values() {
+    use std::convert::{TryFrom, TryInto};
+
+    assert_eq!(i32::try_from(Value::Int(7)), Ok(7));
+    assert_eq!(f64::try_from(Value::Double(0.5)), Ok(0.5));
+    assert_eq!(String::try_from(Value::from("Steve")), Ok("Steve".to_string()));
+    let ints: Vec<i32> = Value::IntArray(vec![1, 2]).try_into().unwrap();
+    assert_eq!(ints, [1, 2]);
+    let mut map = Map::new();
+    map.insert("a".to_string(), Value::Byte(1));
+    assert_eq!(Map::try_from(Value::Compound(map.clone())), Ok(map.clone()));
+
+    let value = Value::List(vec![Value::Long(3)]);
+    let list: Vec<Value> = (&value).try_into().unwrap();
+    assert_eq!(list, [Value::Long(3)]);
+    assert_eq!(Vec::<i64>::try_from(&Value::LongArray(vec![4])), Ok(vec![4]));
+    assert_eq!(String::try_from(&Value::from("x")), Ok("x".to_string()));
+
+    assert_eq!(bool::try_from(Value::Byte(1)), Ok(true));
+    assert_eq!(bool::try_from(&Value::Byte(0)), Ok(false));
+    assert_eq!(bool::try_from(Value::Byte(2)), Err(Error::NonBooleanByte(2)));
+
+    let err = i8::try_from(Value::Int(300)).unwrap_err();
+    assert_eq!(err, Error::TagMismatch(0x03, 0x01));
+    assert_eq!(err.to_string(), "encountered NBT tag '3' (TAG_Int) but expected '1' (TAG_Byte)");
+    assert_eq!(Vec::<i8>::try_from(Value::Compound(map)).unwrap_err().to_string(),
+               "encountered NBT tag '10' (TAG_Compound) but expected '7' (TAG_ByteArray)");
+}
+
+#[test]
+fn nbt_from_rust_types() {
+    let mut nbt = Blob::new();
+    nbt.insert("OnGround", true).unwrap();
+    nbt.insert("Data", vec![0u8, 200]).unwrap();
+    nbt.insert("Bytes", &b"hi"[..]).unwrap();
+    nbt.insert("UUID", [1i32, 2, 3, 4]).unwrap();
+    nbt.insert("Pos", [0.5f64, 64.0, 0.5]).unwrap();
+    nbt.insert("Rotation", vec![90.0f32, 0.0]).unwrap();
+    nbt.insert("Lore", vec!["a".to_string(), "b".to_string()]).unwrap();
+    nbt.insert("Tags", ["x", "y"]).unwrap();
+    nbt.insert("Empty", Map::new()).unwrap();
+    nbt.insert("Values", vec![Value::Short(1)]).unwrap();
+
+    assert_eq!(nbt["OnGround"], Value::Byte(1));
+    assert_eq!(Value::from(false), Value::Byte(0));
+    assert_eq!(nbt["Data"], Value::ByteArray(vec![0, -56]));
+    assert_eq!(nbt["Bytes"], Value::ByteArray(vec![104, 105]));
+    assert_eq!(Value::from([7u8]), Value::ByteArray(vec![7]));
+    assert_eq!(Value::from([7i8]), Value::ByteArray(vec![7]));
+    assert_eq!(Value::from([7i64]), Value::LongArray(vec![7]));
+    assert_eq!(nbt["UUID"], Value::IntArray(vec![1, 2, 3, 4]));
+    assert_eq!(nbt["Pos"], Value::List(vec![Value::Double(0.5), Value::Double(64.0), Value::Double(0.5)]));
+    assert_eq!(nbt["Rotation"], Value::List(vec![Value::Float(90.0), Value::Float(0.0)]));
+    assert_eq!(nbt["Lore"], Value::List(vec![Value::from("a"), Value::from("b")]));
+    assert_eq!(nbt["Tags"], Value::List(vec![Value::from("x"), Value::from("y")]));
+    assert_eq!(Value::from(vec![1i16]), Value::List(vec![Value::Short(1)]));
+    assert_eq!(nbt["Empty"], Value::Compound(Map::new()));
+    assert_eq!(nbt["Values"], Value::List(vec![Value::Short(1)]));
+}
+
+#[test]
+fn nbt_blob_values() {
+    use std::convert::TryFrom;
+
+    let mut section = Blob::named("ignored");
+    section.insert("Y", 3i8).unwrap();
+    let mut chunk = Blob::new();
+    chunk.insert("Sections", vec![section.clone().into_value(), section.clone().into()]).unwrap();
+    chunk.insert("Heightmaps", Blob::new()).unwrap();
+    assert_eq!(chunk.get_path("Sections[1].Y"), Some(&Value::Byte(3)));
+    assert_eq!(chunk["Heightmaps"], Value::Compound(Map::new()));
+
+    let nested = Blob::try_from(chunk["Sections"][0].clone()).unwrap();
+    assert_eq!(nested["Y"], Value::Byte(3));
+    assert_eq!(nested, Blob::from(section.into_value().into_compound().unwrap()));
+    assert_eq!(Blob::try_from(Value::Byte(0)), Err(Error::TagMismatch(0x01, 0x0a)));
+}
+
+#[test]
+fn nbt_slices() {
+    let mut nbt = Blob::new();
+    nbt.insert("name", "Herobrine").unwrap();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let mut bytes = nbt.to_vec(endian).unwrap();
+        let mut written = Vec::new();
+        nbt.to_writer(&mut written, endian).unwrap();
+        assert_eq!(bytes, written);
+
+        let len = bytes.len();
+        bytes.extend_from_slice(&[0xff, 0xff]);
+        assert_eq!(Blob::from_slice(&bytes, endian).unwrap(), nbt);
+        assert_eq!(Blob::from_slice(&bytes[..len - 1], endian).map_err(Error::without_location), Err(Error::IncompleteNbtValue));
+    }
+}
+
+#[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn nbt_len_bytes() {
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    let mut player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    player.insert("Odd", "\0 \u{1f600} \u{e9}").unwrap();
+    player.insert("Longs", Value::LongArray(vec![0, -1, i64::MIN, i64::MAX, 300])).unwrap();
+    player.insert("Ints", Value::IntArray(vec![0, -65, i32::MIN, 64])).unwrap();
+    player.insert("Bytes", Value::ByteArray(vec![1; 200])).unwrap();
+    let player = { let mut named = Blob::named("Player"); named.extend(player); named };
+
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        assert_eq!(player.len_bytes(endian), player.to_vec(endian).unwrap().len());
+        for value in player.values() {
+            let mut dst = Vec::new();
+            value.to_writer(&mut dst, endian).unwrap();
+            assert_eq!(value.len_bytes(endian), dst.len());
+        }
+    }
+}
+
+#[test]
+fn nbt_root_tags() {
+    let roots = vec![
+        Value::List(vec![Value::Int(1), Value::Int(2)]),
+        Value::String("bare".to_string()),
+        Value::Long(-3),
+        Value::Compound(Map::new()),
+    ];
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        for root in &roots {
+            let mut dst = Vec::new();
+            root.to_writer_root(&mut dst, "root", endian).unwrap();
+            let (name, value) = Value::from_reader_root(&mut &dst[..], endian).unwrap();
+            assert_eq!((name.as_str(), &value), ("root", root));
+        }
+    }
+
+    let mut dst = Vec::new();
+    roots[0].to_writer_root(&mut dst, "root", Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_reader(&mut &dst[..], Endianness::BigEndian), Err(Error::NoRootCompound));
+    // The tag, the name, and the List's element type, length and values.
+    assert_eq!(dst.len(), 1 + 2 + 4 + 1 + 4 + 4 * 2);
+
+    let empty = Blob::named("root");
+    dst.clear();
+    Value::from(empty.clone()).to_writer_root(&mut dst, "root", Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_reader(&mut &dst[..], Endianness::BigEndian).unwrap(), empty);
+    assert_eq!(Value::from_reader_root(&mut &[0x00][..], Endianness::BigEndian).err().map(Error::without_location), Some(Error::InvalidTypeId(0)));
+}
+
+#[test]
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn nbt_detect_endianness() {
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    let player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian] {
+        let bytes = player.to_vec(endian).unwrap();
+        let (read, detected) = Blob::from_reader_detect(&mut &bytes[..]).unwrap();
+        assert_eq!((read, detected), (player.clone(), endian));
+    }
+
+    // An empty Compound with an empty name reads the same either way, so
+    // big-endian wins.
+    let bytes = Blob::new().to_vec(Endianness::LittleEndian).unwrap();
+    assert_eq!(Blob::from_reader_detect(&mut &bytes[..]).unwrap().1, Endianness::BigEndian);
+
+    assert_eq!(Blob::from_reader_detect(&mut &[0x0a, 0x00][..]), Err(Error::IncompleteNbtValue));
+    assert_eq!(Blob::from_reader_detect(&mut &[0x01, 0x00, 0x00, 0x05][..]), Err(Error::NoRootCompound));
+}
+
+#[test]
+fn nbt_printer() {
+    use printer::Printer;
+
+    let mut nbt = Blob::named("Level");
+    nbt.insert("Inventory", Value::List(vec![Value::Compound(
+        vec![("Slot".to_string(), Value::Byte(3))].into_iter().collect(),
+    )])).unwrap();
+    let expected = "\
+TAG_Compound(\"Level\"): 1 entry(ies)
+{
+  TAG_List(\"Inventory\"): 1 entries of type TAG_Compound
+  {
+    TAG_Compound(None): 1 entry(ies)
+    {
+      TAG_Byte(Slot): 3
+    }
+  }
+}";
+    assert_eq!(nbt.to_string(), expected);
+    assert_eq!(nbt.display_with(&Printer::new()).to_string(), expected);
+    assert_eq!(nbt.display_with(&Printer::new().indent(0).max_depth(1)).to_string(), "\
+TAG_Compound(\"Level\"): 1 entry(ies)
+{
+TAG_List(\"Inventory\"): 1 entries of type TAG_Compound {...}
+}");
+    assert_eq!(nbt.display_with(&Printer::new().max_depth(0)).to_string(),
+               "TAG_Compound(\"Level\"): 1 entry(ies) {...}");
+
+    let array = Value::LongArray(vec![1, 2, 3]);
+    assert_eq!(array.display_with(&Printer::new().max_array_len(3)).to_string(), "[1, 2, 3]");
+    assert_eq!(array.display_with(&Printer::new().max_array_len(2)).to_string(), "[L; 3 longs...]");
+    assert_eq!(Value::ByteArray(vec![0; 20]).display_with(&Printer::new().max_array_len(0)).to_string(),
+               "[B; 20 bytes...]");
+    assert_eq!(Value::Int(5).display_with(&Printer::new().color(true)).to_string(), "\x1b[33m5\x1b[0m");
+    assert_eq!(Value::List(vec![Value::String("a".to_string())]).display_with(&Printer::new().color(true)).to_string(),
+               "1 entries of type TAG_String\n{\n  \x1b[36mTAG_String\x1b[0m(None): \x1b[32ma\x1b[0m\n}");
+}
+
+#[test]
+fn nbt_stable_output() {
+    use printer::Printer;
+
+    let mut nbt = Blob::new();
+    let mut inner = Map::new();
+    for name in ["a", "b", "c", "d", "e", "f", "g", "h"].iter() {
+        nbt.insert(*name, Value::Byte(1)).unwrap();
+        inner.insert(name.to_string(), Value::Short(2));
+    }
+    let names = |text: String| -> Vec<String> {
+        text.lines().skip(1).filter_map(|line| line.split('(').nth(1)).map(|s| s.to_string()).collect()
+    };
+    let printed = names(nbt.to_string());
+    let mut sorted = printed.clone();
+    sorted.sort();
+    assert_eq!((printed.len(), &printed), (8, &sorted));
+
+    let value = Value::Compound(inner);
+    assert_eq!(format!("{:?}", value), "Compound({\"a\": Short(2), \"b\": Short(2), \"c\": Short(2), \"d\": Short(2), \
+                                        \"e\": Short(2), \"f\": Short(2), \"g\": Short(2), \"h\": Short(2)})");
+    assert!(format!("{:?}", nbt).starts_with("Blob { title: \"\", content: {\"a\": Byte(1), \"b\": Byte(1), "));
+    assert_eq!(format!("{:?}", Value::List(vec![Value::Int(1)])), "List([Int(1)])");
+
+    // Unsorted output follows the map's own order.
+    let unsorted: Vec<String> = value.as_compound().unwrap().keys().map(|k| format!("{}): 2", k)).collect();
+    assert_eq!(names(value.display_with(&Printer::new().sort_keys(false)).to_string()), unsorted);
+}
+
+#[test]
+fn value_ordering() {
+    use std::cmp::Ordering;
+
+    assert!(Value::Byte(100) < Value::Short(-100));
+    assert!(Value::Float(-0.0) < Value::Float(0.0));
+    assert!(Value::Double(f64::INFINITY) < Value::Double(f64::NAN));
+    assert!(nbt!([1i32, 2i32]) < nbt!([1i32, 2i32, 0i32]));
+    assert!(nbt!([I; 1, 2]) < nbt!([I; 1, 3]));
+
+    let forward: Value = (0..16).map(|i| (format!("k{:02}", i), Value::Int(i))).collect::<Blob>().into();
+    let backward: Value = (0..16).rev().map(|i| (format!("k{:02}", i), Value::Int(i))).collect::<Blob>().into();
+    assert_eq!(forward.cmp(&backward), Ordering::Equal);
+    assert!(nbt!({ a: 1i32, b: 1i32 }) < nbt!({ a: 1i32, c: 0i32 }));
+    assert!(nbt!({ a: 1i32 }) < nbt!({ a: 2i32 }));
+    assert!(nbt!({ a: 1i32 }) < nbt!({ a: 1i32, b: 0i8 }));
+}
+
+#[test]
+fn value_deep_size_of() {
+    use std::mem::size_of;
+
+    assert_eq!(Value::Int(1).deep_size_of(), 0);
+    assert_eq!(Value::Compound(Map::new()).deep_size_of(), 0);
+    let mut name = String::with_capacity(32);
+    name.push_str("Steve");
+    assert_eq!(Value::String(name).deep_size_of(), 32);
+    assert_eq!(Value::LongArray(vec![0; 37]).deep_size_of(), 37 * 8);
+
+    let list = Value::List(vec![Value::ByteArray(vec![0; 2]), Value::ByteArray(vec![0; 10])]);
+    assert_eq!(list.deep_size_of(), 2 * size_of::<Value>() + 2 + 10);
+
+    // A Compound counts its keys and table on top of its values.
+    let compound = nbt!({ list: (list.clone()) });
+    let size = compound.deep_size_of();
+    assert!(size >= list.deep_size_of() + "list".len() + size_of::<Value>());
+
+    let mut blob = Blob::from_value(compound).unwrap();
+    assert_eq!(blob.deep_size_of(), size);
+    blob.insert("name", "Steve").unwrap();
+    assert!(blob.deep_size_of() > size);
+}
@@ -0,0 +1,105 @@
+//! Conversions between UUIDs and their NBT representations.
+//!
+//! Since 1.16, UUIDs are a `TAG_Int_Array` of four ints, most significant
+//! first. Older files store them as a pair of Longs, named `UUIDMost` and
+//! `UUIDLeast` or with some other prefix before `Most` and `Least`. UUIDs
+//! are handled here as `u128`, which the `uuid` crate converts with
+//! `Uuid::as_u128()` and `Uuid::from_u128()`.
+//!
+//! With Serde, `#[serde(with = "nbt::uuid")]` writes a `u128` field as the
+//! modern array:
+//!
+//! ```
+//! # #[macro_use] extern crate serde_derive;
+//! # extern crate nbt;
+//! # use nbt::Endianness;
+//! # #[cfg(feature = "serde")]
+//! #[derive(Serialize, Deserialize)]
+//! struct Entity {
+//!     #[serde(rename = "UUID", with = "nbt::uuid")]
+//!     uuid: u128,
+//! }
+//!
+//! # fn main() {
+//! # #[cfg(feature = "serde")] {
+//! let entity = Entity { uuid: 0x0123_4567_89ab_cdef_0123_4567_89ab_cdef };
+//! let value = nbt::to_value(&entity).unwrap();
+//! assert_eq!(value["UUID"], nbt::Value::IntArray(vec![0x0123_4567, -0x7654_3211, 0x0123_4567, -0x7654_3211]));
+//! # }
+//! # }
+//! ```
+
+use value::Value;
+
+/// Splits a UUID into the four ints of its array form.
+pub fn to_int_array(uuid: u128) -> [i32; 4] {
+    [(uuid >> 96) as i32, (uuid >> 64) as i32, (uuid >> 32) as i32, uuid as i32]
+}
+
+/// Joins the four ints of the array form of a UUID.
+pub fn from_int_array(ints: [i32; 4]) -> u128 {
+    ints.iter().fold(0, |uuid, &int| uuid << 32 | u128::from(int as u32))
+}
+
+/// Splits a UUID into its most and least significant halves, the legacy
+/// `UUIDMost` and `UUIDLeast` Longs.
+pub fn to_most_least(uuid: u128) -> (i64, i64) {
+    ((uuid >> 64) as i64, uuid as i64)
+}
+
+/// Joins the most and least significant halves of a UUID.
+pub fn from_most_least(most: i64, least: i64) -> u128 {
+    u128::from(most as u64) << 64 | u128::from(least as u64)
+}
+
+/// The `TAG_Int_Array` holding a UUID.
+pub fn to_value(uuid: u128) -> Value {
+    Value::IntArray(to_int_array(uuid).to_vec())
+}
+
+/// Reads a UUID from a `TAG_Int_Array` of four ints, or `None` for any
+/// other value.
+pub fn from_value(value: &Value) -> Option<u128> {
+    match *value {
+        Value::IntArray(ref v) if v.len() == 4 => Some(from_int_array([v[0], v[1], v[2], v[3]])),
+        _ => None,
+    }
+}
+
+/// Reads a legacy UUID from the Longs `{prefix}Most` and `{prefix}Least`
+/// of a Compound, such as `UUIDMost` and `UUIDLeast` for the prefix
+/// `UUID`, or `None` if either is missing or not a Long.
+///
+/// ```rust
+/// # #[macro_use] extern crate nbt;
+/// # fn main() {
+/// let entity = nbt!({ UUIDMost: 1i64, UUIDLeast: -1i64 });
+/// let uuid = nbt::uuid::from_legacy(&entity, "UUID").unwrap();
+/// assert_eq!(uuid, 0x0000_0000_0000_0001_ffff_ffff_ffff_ffff);
+/// assert_eq!(nbt::uuid::to_value(uuid), nbt!([I; 0, 1, -1, -1]));
+/// # }
+/// ```
+pub fn from_legacy(compound: &Value, prefix: &str) -> Option<u128> {
+    let map = match *compound {
+        Value::Compound(ref map) => map,
+        _ => return None,
+    };
+    match (map.get(&format!("{}Most", prefix)[..]), map.get(&format!("{}Least", prefix)[..])) {
+        (Some(&Value::Long(most)), Some(&Value::Long(least))) => Some(from_most_least(most, least)),
+        _ => None,
+    }
+}
+
+/// Serializes a UUID as a `TAG_Int_Array`, for `#[serde(with = "nbt::uuid")]`.
+#[cfg(feature = "serde")]
+pub fn serialize<S: ::serde::Serializer>(uuid: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+    ::arrays::int_array::serialize(&to_int_array(*uuid), serializer)
+}
+
+/// Deserializes a UUID from a `TAG_Int_Array`, or any other sequence, of
+/// four ints.
+#[cfg(feature = "serde")]
+pub fn deserialize<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    let ints: [i32; 4] = ::serde::Deserialize::deserialize(deserializer)?;
+    Ok(from_int_array(ints))
+}
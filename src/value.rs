@@ -1,15 +1,45 @@
-use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::cmp::Ordering;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::io;
+use std::mem;
+use std::ops::{Index, IndexMut};
+#[cfg(not(feature = "std"))]
+use std::prelude::*;
+use std::result::Result as StdResult;
+use std::slice;
+use std::vec;
 
+use canonical;
 use error::{Error, Result};
-use raw::{Endianness, RawWriter, RawReader};
+use map::{self, Map};
+use merge::{self, MergeStrategy};
+use patch::{self, NbtPatch};
+use path;
+use printer::{Printed, Printer};
+use raw::{self, Endianness, RawWriter, RawReader};
+use snbt;
 
 /// Values which can be represented in the Named Binary Tag format.
-#[derive(Clone, Debug, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
-#[cfg_attr(feature = "serde", derive(Deserialize))]
-#[cfg_attr(feature = "serde", serde(untagged))]
+///
+/// The `Debug` output lists the entries of Compounds sorted by name, unless
+/// the `preserve_order` feature keeps them in insertion order, so that it
+/// is the same from one run to the next.
+///
+/// With Serde, a `Value` keeps its exact tags when encoded and decoded as
+/// NBT, including those of arrays and of the numbers in them, so it can hold
+/// the parts of a document that a typed struct leaves unstructured. Other
+/// formats see plain numbers, strings, sequences and maps, and integers from
+/// them become the smallest tag that fits.
+///
+/// Like reading and writing, cloning and comparing with `==` walk nested
+/// Lists and Compounds with an explicit stack, so a tree of any depth can be
+/// held. Dropping, `Debug`, `Display`, `Hash` and `Ord` recurse once per
+/// level instead, using up to a few hundred bytes of stack for each: trees
+/// within `raw::DEFAULT_MAX_DEPTH` are safe on any thread, but those read
+/// without a depth limit can overflow a thread's stack at some tens of
+/// thousands of levels, and should be dropped with `drop_iteratively()`.
 pub enum Value {
     Byte(i8),
     Short(i16),
@@ -20,7 +50,7 @@ pub enum Value {
     ByteArray(Vec<i8>),
     String(String),
     List(Vec<Value>),
-    Compound(HashMap<String, Value>),
+    Compound(Map),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
@@ -47,23 +77,610 @@ impl Value {
 
     /// A string representation of this tag.
     pub fn tag_name(&self) -> &str {
+        tag_name_of(self.id())
+    }
+
+    /// Moves the map out of a `Value::Compound`, or returns the original
+    /// value if it is of any other type.
+    ///
+    /// Like the rest of the `into_*` conversions this never clones, so it can
+    /// be used to restructure a tree in place:
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "std")] {
+    /// use nbt::{Blob, Value};
+    ///
+    /// let mut item = nbt::Map::new();
+    /// item.insert("Count".to_string(), Value::Byte(1));
+    /// let mut nbt = Blob::new();
+    /// nbt.insert("Items", Value::List(vec![Value::Compound(item)])).unwrap();
+    ///
+    /// // Take the list out, transform its elements, and put it back.
+    /// let items: Vec<Value> = nbt.remove_as("Items").unwrap().unwrap();
+    /// let items = items.into_iter().map(|item| {
+    ///     let mut item = item.into_compound().unwrap();
+    ///     item.insert("Count".to_string(), Value::Byte(64));
+    ///     Value::Compound(item)
+    /// }).collect::<Vec<_>>();
+    /// nbt.insert("Items", Value::List(items)).unwrap();
+    /// # }
+    /// ```
+    pub fn into_compound(self) -> StdResult<Map, Value> {
+        match self {
+            Value::Compound(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Moves the elements out of a `Value::List`, or returns the original
+    /// value if it is of any other type.
+    pub fn into_list(self) -> StdResult<Vec<Value>, Value> {
+        match self {
+            Value::List(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Moves the string out of a `Value::String`, or returns the original
+    /// value if it is of any other type.
+    pub fn into_string(self) -> StdResult<String, Value> {
+        match self {
+            Value::String(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Moves the bytes out of a `Value::ByteArray`, or returns the original
+    /// value if it is of any other type.
+    pub fn into_byte_array(self) -> StdResult<Vec<i8>, Value> {
+        match self {
+            Value::ByteArray(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Moves the integers out of a `Value::IntArray`, or returns the original
+    /// value if it is of any other type.
+    pub fn into_int_array(self) -> StdResult<Vec<i32>, Value> {
+        match self {
+            Value::IntArray(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Moves the integers out of a `Value::LongArray`, or returns the
+    /// original value if it is of any other type.
+    pub fn into_long_array(self) -> StdResult<Vec<i64>, Value> {
+        match self {
+            Value::LongArray(v) => Ok(v),
+            other => Err(other),
+        }
+    }
+
+    /// Drops this value without recursing into its nested Lists and
+    /// Compounds, so that a tree of any depth can be dropped without
+    /// overflowing the stack.
+    pub fn drop_iteratively(self) {
+        let mut stack = vec![self];
+        while let Some(mut value) = stack.pop() {
+            match value {
+                Value::List(ref mut vals) => stack.extend(vals.drain(..).filter(is_nested)),
+                Value::Compound(ref mut vals) => stack.extend(mem::take(vals).into_values().filter(is_nested)),
+                _ => {},
+            }
+        }
+    }
+
+    /// Gets an entry of a `Value::Compound` by key, or an element of a
+    /// `Value::List` by position. Returns `None` if there is no such entry or
+    /// element, or if the value is of another type, where indexing with
+    /// `value[index]` would panic.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Value;
+    ///
+    /// # fn main() {
+    /// let mut chunk = nbt!({ Level: { xPos: 1i32, Sections: [{ Y: 0i8 }] } });
+    /// chunk["Level"]["xPos"] = 5.into();
+    /// assert_eq!(chunk["Level"]["xPos"], Value::Int(5));
+    /// assert_eq!(chunk["Level"]["Sections"][0]["Y"], Value::Byte(0));
+    /// assert_eq!(chunk.get("Level").and_then(|level| level.get("zPos")), None);
+    /// assert_eq!(chunk["Level"].get(0), None);
+    /// # }
+    /// ```
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Gets an entry of a Compound or an element of a List for modification,
+    /// as in `get()`.
+    pub fn get_mut<I: ValueIndex>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// The exact number of bytes that `to_raw_writer()` writes for this
+    /// value's payload with the given byte order, computed without encoding
+    /// anything. Strings are counted in Modified UTF-8, the default.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Endianness;
+    ///
+    /// # fn main() {
+    /// let value = nbt!({ name: "Steve", scores: [I; 1, 2, 3] });
+    /// let mut dst = Vec::new();
+    /// value.to_writer(&mut dst, Endianness::NetworkLittleEndian).unwrap();
+    /// assert_eq!(value.len_bytes(Endianness::NetworkLittleEndian), dst.len());
+    /// # }
+    /// ```
+    pub fn len_bytes(&self, endian: Endianness) -> usize {
+        let mut len = 0;
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            len += match *value {
+                Value::Byte(_) => 1,
+                Value::Short(_) => 2,
+                Value::Int(v) => raw::int_len(endian, v),
+                Value::Long(v) => raw::long_len(endian, v),
+                Value::Float(_) => 4,
+                Value::Double(_) => 8,
+                Value::ByteArray(ref v) => raw::int_len(endian, v.len() as i32) + v.len(),
+                Value::String(ref v) => raw::string_len(endian, v),
+                Value::List(ref vals) => {
+                    stack.extend(vals);
+                    1 + raw::int_len(endian, vals.len() as i32)
+                },
+                Value::Compound(ref map) => {
+                    stack.extend(map.values());
+                    map.keys().map(|name| 1 + raw::string_len(endian, name)).sum::<usize>() + 1
+                },
+                Value::IntArray(ref v) => {
+                    raw::int_len(endian, v.len() as i32) + v.iter().map(|&n| raw::int_len(endian, n)).sum::<usize>()
+                },
+                Value::LongArray(ref v) => {
+                    raw::int_len(endian, v.len() as i32) + v.iter().map(|&n| raw::long_len(endian, n)).sum::<usize>()
+                },
+            };
+        }
+        len
+    }
+
+    /// The number of bytes of heap memory that this value owns, including
+    /// everything nested in it, but not the `size_of::<Value>()` bytes of
+    /// the value itself.
+    ///
+    /// Strings, arrays and Lists count their full capacity, which may exceed
+    /// their length. The tables of Compounds are estimated from their
+    /// capacity and the layout of the map type, which is not public, and the
+    /// allocator's own overhead is not counted, so treat the result as a
+    /// close lower bound for budgeting rather than an exact figure.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Value;
+    ///
+    /// # fn main() {
+    /// let heights = Value::IntArray(vec![0; 256]);
+    /// assert_eq!(heights.deep_size_of(), 1024);
+    ///
+    /// let chunk = nbt!({ Heightmaps: { WORLD_SURFACE: [L; 0, 0, 0, 0] } });
+    /// assert!(chunk.deep_size_of() > 4 * 8 + "Heightmaps".len() + "WORLD_SURFACE".len());
+    /// # }
+    /// ```
+    pub fn deep_size_of(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            size += match *value {
+                Value::ByteArray(ref v) => v.capacity(),
+                Value::String(ref v) => v.capacity(),
+                Value::List(ref vals) => {
+                    stack.extend(vals);
+                    vals.capacity() * mem::size_of::<Value>()
+                },
+                Value::Compound(ref map) => {
+                    stack.extend(map.values());
+                    map::heap_size(map)
+                },
+                Value::IntArray(ref v) => v.capacity() * 4,
+                Value::LongArray(ref v) => v.capacity() * 8,
+                _ => 0,
+            };
+        }
+        size
+    }
+
+    /// Gets the entry for a key of a `Value::Compound`, to read or modify it
+    /// in place with a single lookup. Returns `None` for any other type.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Value;
+    ///
+    /// # fn main() {
+    /// let mut item = nbt!({ id: "minecraft:stone" });
+    /// item.entry("Count").unwrap().or_insert(Value::Byte(1));
+    /// item.entry("id").unwrap().and_modify(|id| *id = Value::from("minecraft:dirt"));
+    /// assert_eq!(item, nbt!({ id: "minecraft:dirt", Count: 1i8 }));
+    /// assert!(Value::Int(0).entry("Count").is_none());
+    /// # }
+    /// ```
+    pub fn entry<S: Into<String>>(&mut self, key: S) -> Option<map::Entry<'_>> {
+        match *self {
+            Value::Compound(ref mut map) => Some(map.entry(key.into())),
+            _ => None,
+        }
+    }
+
+    /// Removes an entry from a `Value::Compound`, returning it if it was
+    /// there. Returns `None` for any other type.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
         match *self {
-            Value::Byte(_)      => "TAG_Byte",
-            Value::Short(_)     => "TAG_Short",
-            Value::Int(_)       => "TAG_Int",
-            Value::Long(_)      => "TAG_Long",
-            Value::Float(_)     => "TAG_Float",
-            Value::Double(_)    => "TAG_Double",
-            Value::ByteArray(_) => "TAG_ByteArray",
-            Value::String(_)    => "TAG_String",
-            Value::List(_)      => "TAG_List",
-            Value::Compound(_)  => "TAG_Compound",
-            Value::IntArray(_)  => "TAG_IntArray",
-            Value::LongArray(_) => "TAG_LongArray",
-        }
-    }
-
-    pub(crate) fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+            Value::Compound(ref mut map) => map::remove(map, key),
+            _ => None,
+        }
+    }
+
+    /// Keeps only the entries of a `Value::Compound` for which `keep`
+    /// returns true. Values of any other type are left alone.
+    pub fn retain<F>(&mut self, mut keep: F)
+        where F: FnMut(&str, &mut Value) -> bool,
+    {
+        if let Value::Compound(ref mut map) = *self {
+            map.retain(|k, v| keep(k, v));
+        }
+    }
+
+    /// Keeps only the entries, of every Compound in this value including
+    /// itself, for which `keep` returns true. See `Blob::retain_recursive()`.
+    pub fn retain_recursive<F>(&mut self, mut keep: F)
+        where F: FnMut(&str, &Value) -> bool,
+    {
+        map::retain_recursive(vec![self], &mut keep);
+    }
+
+    /// Iterates over the elements of a `Value::List`, converting each of them
+    /// to `T`.
+    ///
+    /// This fails immediately if the value is not a list, or if its first
+    /// element cannot be converted. Each remaining element is converted as it
+    /// is reached, so lists that are not actually homogeneous yield an error
+    /// in place of the offending element.
+    ///
+    /// ```rust
+    /// # #[cfg(any(feature = "compression", feature = "rust_backend"))] {
+    /// use std::convert::TryInto;
+    /// use nbt::{Blob, Endianness};
+    ///
+    /// let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    /// let player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    ///
+    /// for item in player["Inventory"].iter_compounds().unwrap() {
+    ///     let item = item.unwrap();
+    ///     let id: &str = (&item["id"]).try_into().unwrap();
+    ///     assert!(id.starts_with("minecraft:"));
+    /// }
+    ///
+    /// let pos: Vec<f64> = player["Pos"].iter_as::<f64>().unwrap()
+    ///     .collect::<nbt::Result<_>>().unwrap();
+    /// assert_eq!(pos.len(), 3);
+    /// # }
+    /// ```
+    pub fn iter_as<'a, T>(&'a self) -> Result<impl Iterator<Item = Result<T>> + 'a>
+        where T: TryFrom<&'a Value, Error = Error> + 'a,
+    {
+        let vals = match *self {
+            Value::List(ref vals) => vals,
+            ref other => return Err(Error::TagMismatch(other.id(), 0x09)),
+        };
+        let first = match vals.first() {
+            Some(v) => Some(T::try_from(v)?),
+            None => None,
+        };
+        Ok(first.into_iter().map(Ok).chain(vals.iter().skip(1).map(T::try_from)))
+    }
+
+    /// Iterates over the elements of a `Value::List` of Compounds.
+    ///
+    /// This is shorthand for `iter_as::<&Map>()`.
+    pub fn iter_compounds<'a>(&'a self)
+        -> Result<impl Iterator<Item = Result<&'a Map>> + 'a>
+    {
+        self.iter_as()
+    }
+
+    /// Looks up a nested value by a path such as
+    /// `Level.Sections[3].Palette[0].Name`, made of Compound keys separated
+    /// by dots and List indices in brackets.
+    ///
+    /// Keys may be quoted to include dots or brackets, as in
+    /// `Data."minecraft:overworld".seed`. Returns `None` if the path is
+    /// malformed, or leads to a missing entry, to an index past the end of a
+    /// List, or through a value of the wrong type. The elements of the array
+    /// tags are not `Value`s, so they cannot be indexed this way.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Value;
+    ///
+    /// # fn main() {
+    /// let mut chunk = nbt!({ Level: { Sections: [{ Y: 0i8 }, { Y: 1i8 }] } });
+    /// assert_eq!(chunk.get_path("Level.Sections[1].Y"), Some(&Value::Byte(1)));
+    /// assert_eq!(chunk.get_path("Level.Sections[2].Y"), None);
+    ///
+    /// *chunk.get_path_mut("Level.Sections[0].Y").unwrap() = Value::Byte(-1);
+    /// assert_eq!(chunk.get_path("Level.Sections[0].Y"), Some(&Value::Byte(-1)));
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path::get(self, &path::parse(path)?)
+    }
+
+    /// Looks up a nested value by path, as in `get_path()`, for modification.
+    pub fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        path::get_mut(self, &path::parse(path)?)
+    }
+
+    /// Looks up a nested value by a pointer, in the style of a JSON Pointer
+    /// as in `serde_json`, such as `Level/Entities/0/Pos`.
+    ///
+    /// The pointer is a list of tokens separated by slashes, each a key of a
+    /// Compound or an index into a List, with `~1` for a slash and `~0` for a
+    /// tilde within a key. The leading slash is optional, and as with
+    /// `get_path()` the empty pointer refers to this value itself.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Value;
+    ///
+    /// # fn main() {
+    /// let mut chunk = nbt!({ Level: { Entities: [{ Pos: [0.5f64, 64.0f64, 0.5f64] }] } });
+    /// assert_eq!(chunk.pointer("/Level/Entities/0/Pos/1"), Some(&Value::Double(64.0)));
+    ///
+    /// let pos = chunk.pointer_mut("Level/Entities/0/Pos").unwrap().replace(nbt!([0.0f64, 70.0f64, 0.0f64]));
+    /// assert_eq!(pos, nbt!([0.5f64, 64.0f64, 0.5f64]));
+    /// let entity = chunk.pointer_mut("Level/Entities/0").map(Value::take).unwrap();
+    /// assert_eq!(entity, nbt!({ Pos: [0.0f64, 70.0f64, 0.0f64] }));
+    /// # }
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        path::pointer(self, pointer)
+    }
+
+    /// Looks up a nested value by a pointer, as in `pointer()`, for
+    /// modification.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        path::pointer_mut(self, pointer)
+    }
+
+    /// Moves this value out, leaving an empty Compound in its place, which
+    /// does not allocate.
+    pub fn take(&mut self) -> Value {
+        self.replace(Value::Compound(Map::new()))
+    }
+
+    /// Puts `value` in the place of this value, and returns this value.
+    pub fn replace(&mut self, value: Value) -> Value {
+        mem::replace(self, value)
+    }
+
+    /// Iterates over the nested values matching a `Selector`, which extends
+    /// the syntax of `get_path()` with wildcards and filters, along with their
+    /// paths.
+    ///
+    /// This fails with `Error::InvalidSelector` if the selector is malformed.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    ///
+    /// # fn main() {
+    /// let player = nbt!({ Inventory: [
+    ///     { id: "minecraft:stone", Count: 64i8 },
+    ///     { id: "minecraft:dirt", Count: 1i8 },
+    /// ] });
+    ///
+    /// let ids: Vec<_> = player.select("Inventory[*].id").unwrap()
+    ///     .map(|(path, _)| path)
+    ///     .collect();
+    /// assert_eq!(ids, vec!["Inventory[0].id", "Inventory[1].id"]);
+    /// # }
+    /// ```
+    pub fn select(&self, selector: &str) -> Result<path::Matches<'_>> {
+        Ok(path::Selector::parse(selector)?.select(self))
+    }
+
+    /// Iterates over this value and every value nested in it, along with
+    /// their paths, which `get_path()` accepts. This value itself comes
+    /// first, with the empty path. See `Walk` for the order of the rest.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    ///
+    /// # fn main() {
+    /// let chunk = nbt!({ Entities: [{ id: "minecraft:cow" }, { id: "minecraft:pig", Passengers: [{ id: "minecraft:cow" }] }] });
+    /// let cows: Vec<String> = chunk.walk()
+    ///     .filter(|&(_, value)| *value == nbt!("minecraft:cow"))
+    ///     .map(|(path, _)| path)
+    ///     .collect();
+    /// assert_eq!(cows, vec!["Entities[0].id", "Entities[1].Passengers[0].id"]);
+    /// # }
+    /// ```
+    pub fn walk(&self) -> path::Walk<'_> {
+        path::Walk::new(self)
+    }
+
+    /// Visits every value nested in this one, but not this value itself,
+    /// with its path, so that `visit` can change it in place. What `visit`
+    /// returns decides whether the value is kept, and whether the values in
+    /// it are visited next.
+    ///
+    /// All of the values in a Compound or List are visited, and removed as
+    /// asked, before the values inside any of them, which are then visited
+    /// in order. Each path is that of the value in the tree as it is when
+    /// visited, so the values in a List element that follows a removed one
+    /// have its new index. Editing a value before continuing into it visits
+    /// the values of the edited version.
+    ///
+    /// Paths are given as `&str`, in the same syntax as those of `walk()`,
+    /// rather than as a path type, so that `visit` can compare them with
+    /// string literals and keep one only by copying it.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::VisitAction;
+    ///
+    /// # fn main() {
+    /// let mut chunk = nbt!({
+    ///     Entities: [
+    ///         { id: "minecraft:zombie_pigman", Passengers: [{ id: "minecraft:zombie_pigman" }] },
+    ///         { id: "minecraft:bat" },
+    ///     ],
+    ///     Data: { id: "minecraft:zombie_pigman" },
+    /// });
+    /// chunk.walk_mut(|path, value| {
+    ///     if path == "Data" {
+    ///         VisitAction::Skip
+    ///     } else if *value == nbt!({ id: "minecraft:bat" }) {
+    ///         VisitAction::Remove
+    ///     } else {
+    ///         if *value == nbt!("minecraft:zombie_pigman") {
+    ///             *value = nbt!("minecraft:zombified_piglin");
+    ///         }
+    ///         VisitAction::Continue
+    ///     }
+    /// });
+    /// assert_eq!(chunk, nbt!({
+    ///     Entities: [
+    ///         { id: "minecraft:zombified_piglin", Passengers: [{ id: "minecraft:zombified_piglin" }] },
+    ///     ],
+    ///     Data: { id: "minecraft:zombie_pigman" },
+    /// }));
+    /// # }
+    /// ```
+    pub fn walk_mut<F>(&mut self, mut visit: F)
+        where F: FnMut(&str, &mut Value) -> path::VisitAction,
+    {
+        path::walk_mut(vec![(String::new(), self)], &mut visit);
+    }
+
+    /// Replays the changes of a patch computed by `nbt::diff()` onto this
+    /// value, which may be a different tree from the one the patch was
+    /// computed from.
+    ///
+    /// Each change only applies if the value it modifies is in the state the
+    /// patch expects: entries to be added must be missing, and entries to be
+    /// removed or changed must still hold their old values. Otherwise this
+    /// fails with `Error::PatchConflict` at the path of the first conflicting
+    /// change, and leaves the value untouched.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::Error;
+    ///
+    /// # fn main() {
+    /// let before = nbt!({ Health: 20.0f32, XpLevel: 3i32 });
+    /// let after = nbt!({ Health: 18.5f32, XpLevel: 3i32 });
+    /// let patch = nbt::diff(&before, &after);
+    ///
+    /// let mut replica = before.clone();
+    /// replica.apply_patch(&patch).unwrap();
+    /// assert_eq!(replica, after);
+    ///
+    /// // The health has changed since, so the patch no longer applies.
+    /// let mut stale = nbt!({ Health: 10.0f32, XpLevel: 3i32 });
+    /// assert_eq!(stale.apply_patch(&patch), Err(Error::PatchConflict("Health".to_string())));
+    /// # }
+    /// ```
+    pub fn apply_patch(&mut self, patch: &NbtPatch) -> Result<()> {
+        patch::apply(self, patch)
+    }
+
+    /// Merges `other` into this value according to `strategy`.
+    ///
+    /// If both are Compounds their entries are combined, and otherwise this
+    /// value is replaced by `other` (or kept, with `KeepExisting`).
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    /// use nbt::MergeStrategy;
+    ///
+    /// # fn main() {
+    /// let mut item = nbt!({ id: "minecraft:bow", tag: { Damage: 3i32, Unbreakable: 0i8 } });
+    /// item.merge(&nbt!({ tag: { Unbreakable: 1i8 } }), MergeStrategy::Deep);
+    /// assert_eq!(item, nbt!({ id: "minecraft:bow", tag: { Damage: 3i32, Unbreakable: 1i8 } }));
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: &Value, strategy: MergeStrategy) {
+        merge::merge(self, other, strategy)
+    }
+
+    /// A 64-bit hash of this value's content, which is the same for equal
+    /// values however their Compounds are ordered, and is stable across runs
+    /// and platforms. See the notes on `Blob::canonical_hash()`.
+    pub fn canonical_hash(&self) -> u64 {
+        canonical::hash_value(self)
+    }
+
+    /// Writes the payload of this `Value` to a `RawWriter`.
+    ///
+    /// Nested Lists and Compounds are walked with an explicit stack rather
+    /// than by recursion, so that even very deep trees cannot overflow the
+    /// call stack.
+    pub fn to_raw_writer<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
+        where W: io::Write,
+    {
+        let mut stack = Vec::new();
+        let mut next = Some(self);
+        while let Some(nbt) = next {
+            match *nbt {
+                Value::List(ref vals) => {
+                    // This is a bit of a trick: if the list is empty, don't
+                    // bother checking its type. Otherwise, use the first
+                    // element of the list.
+                    let id = vals.first().map_or(0, Value::id);
+                    dst.write_bare_byte(id)?;
+                    dst.write_bare_int(vals.len() as i32)?;
+                    stack.push(WriteFrame::List(id, vals.iter()));
+                },
+                Value::Compound(ref vals) => {
+                    dst.begin_compound();
+                    stack.push(WriteFrame::Compound(Entries::new(vals, dst.sorts_keys())));
+                },
+                ref scalar => scalar.write_scalar(dst)?,
+            }
+
+            // Find the next payload to write, closing finished Compounds on
+            // the way out.
+            next = None;
+            while let Some(frame) = stack.last_mut() {
+                match *frame {
+                    WriteFrame::List(id, ref mut iter) => if let Some(nbt) = iter.next() {
+                        // Ensure that all of the tags are the same type.
+                        if nbt.id() != id {
+                            return Err(Error::HeterogeneousList);
+                        }
+                        next = Some(nbt);
+                    },
+                    WriteFrame::Compound(ref mut entries) => match entries.next() {
+                        Some((name, nbt)) => {
+                            dst.write_header(nbt.id(), name)?;
+                            next = Some(nbt);
+                        },
+                        None => dst.close_nbt()?,
+                    },
+                }
+                if next.is_some() {
+                    break;
+                }
+                stack.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the payload of a `Value` other than a List or Compound.
+    fn write_scalar<W>(&self, dst: &mut RawWriter<W>) -> Result<()>
         where W: io::Write,
     {
         match *self {
@@ -74,39 +691,10 @@ impl Value {
             Value::Float(val)  => dst.write_bare_float(val),
             Value::Double(val) => dst.write_bare_double(val),
             Value::ByteArray(ref vals) => dst.write_bare_byte_array(&vals[..]),
-            Value::String(ref val) => dst.write_bare_string(&val),
-            Value::List(ref vals) => {
-                // This is a bit of a trick: if the list is empty, don't bother
-                // checking its type.
-                if vals.len() == 0 {
-                    dst.write_bare_byte(0)?; // TAG_End
-                    dst.write_bare_int(0)?;
-                } else {
-                    // Otherwise, use the first element of the list.
-                    let first_id = vals[0].id();
-                    dst.write_bare_byte(first_id)?;
-                    dst.write_bare_int(vals.len() as i32)?;
-                    for nbt in vals {
-                        // Ensure that all of the tags are the same type.
-                        if nbt.id() != first_id {
-                            return Err(Error::HeterogeneousList);
-                        }
-                        nbt.to_raw_writer(dst)?;
-                    }
-                }
-                Ok(())
-            },
-            Value::Compound(ref vals)  => {
-                for (name, ref nbt) in vals {
-                    // Write the header for the tag.
-                    dst.write_bare_byte(nbt.id())?;
-                    dst.write_bare_string(name)?;
-                    nbt.to_raw_writer(dst)?;
-                }
-                dst.close_nbt()
-            },
+            Value::String(ref val) => dst.write_bare_string(val),
             Value::IntArray(ref vals) => dst.write_bare_int_array(&vals[..]),
             Value::LongArray(ref vals) => dst.write_bare_long_array(&vals[..]),
+            Value::List(_) | Value::Compound(_) => unreachable!("nested payloads are written by to_raw_writer()"),
         }
     }
 
@@ -118,7 +706,170 @@ impl Value {
         self.to_raw_writer(&mut dst)
     }
 
-    pub(crate) fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Value>
+    /// Reads the payload of a `Value` with a given type ID from a
+    /// `RawReader`.
+    ///
+    /// Nested Lists and Compounds are read with an explicit stack rather
+    /// than by recursion, so that the reader's maximum depth can be raised
+    /// without risk of overflowing the call stack.
+    ///
+    /// Errors are returned as `Error::Located`, giving the offset in the
+    /// input and the path of the tag at which decoding failed.
+    pub fn from_raw_reader<R>(id: i8, src: &mut RawReader<R>) -> Result<Value>
+        where R: io::Read,
+    {
+        let mut stack = Vec::new();
+        Value::read_tree(id, src, &mut stack, None).map_err(|e| locate(e, &stack, src.offset()))
+    }
+
+    /// Reads the payload of a `Value` like `from_raw_reader()`, but salvages
+    /// what it can from damaged input instead of failing outright. Along with
+    /// the value, this returns a warning for each problem encountered, as an
+    /// `Error::Located` saying where it was.
+    ///
+    /// Entries of a Compound and elements of a List whose name or string
+    /// value is not valid (Modified) UTF-8 have a known length, so they are
+    /// skipped and reading continues with the next one. Any other problem
+    /// inside a List or Compound, such as an unknown type ID or truncated
+    /// input, leaves no way to find the next tag, so reading stops there and
+    /// everything read so far is returned, with the Lists and Compounds that
+    /// were still open closed off. The source is then left wherever reading
+    /// stopped.
+    ///
+    /// An error is only returned if nothing could be read at all.
+    ///
+    /// ```rust
+    /// use nbt::{Endianness, Value};
+    /// use nbt::raw::RawReader;
+    ///
+    /// // A Compound holding a Byte named "a", then a tag of unknown type 0x0f.
+    /// let bytes = [0x01, 0x00, 0x01, b'a', 0x05, 0x0f, 0x00, 0x01, b'b'];
+    /// let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    /// let (value, warnings) = Value::from_raw_reader_lenient(0x0a, &mut src).unwrap();
+    /// assert_eq!(value, nbt::nbt!({ "a": 5i8 }));
+    /// assert_eq!(warnings[0].path(), Some("b"));
+    /// ```
+    pub fn from_raw_reader_lenient<R>(id: i8, src: &mut RawReader<R>) -> Result<(Value, Vec<Error>)>
+        where R: io::Read,
+    {
+        let mut stack = Vec::new();
+        let mut warnings = Vec::new();
+        let value = Value::read_tree(id, src, &mut stack, Some(&mut warnings))
+            .map_err(|e| locate(e, &stack, src.offset()))?;
+        Ok((value, warnings))
+    }
+
+    /// Reads a payload, keeping the Lists and Compounds that are still being
+    /// read on `stack`, so that the location of any error can be found.
+    ///
+    /// With `warnings`, problems inside a List or Compound are recorded there
+    /// and recovered from as described for `from_raw_reader_lenient()`.
+    fn read_tree<R>(id: i8, src: &mut RawReader<R>, stack: &mut Vec<ReadFrame>,
+                    mut warnings: Option<&mut Vec<Error>>) -> Result<Value>
+        where R: io::Read,
+    {
+        // On failure, either give up or salvage what has been read so far.
+        macro_rules! attempt {
+            ($e:expr) => {
+                match $e {
+                    Ok(value) => value,
+                    Err(e) => return match warnings {
+                        Some(ref mut warnings) if !stack.is_empty() => {
+                            warnings.push(locate(e, stack, src.offset()));
+                            Ok(close_frames(stack, src))
+                        },
+                        _ => Err(e),
+                    },
+                }
+            };
+        }
+
+        let mut id = id;
+        loop {
+            let mut value = match Value::begin_payload(id, src, stack) {
+                // An invalid string has been read in full, so it can just be
+                // left out of its parent.
+                Err(Error::InvalidUtf8) if id == 0x08 && warnings.is_some() && !stack.is_empty() => {
+                    if let Some(ref mut warnings) = warnings {
+                        warnings.push(locate(Error::InvalidUtf8, stack, src.offset()));
+                    }
+                    None
+                },
+                result => attempt!(result),
+            };
+
+            // Store the value in its parent and find the type of the next
+            // one, finishing the parents that it completes.
+            loop {
+                let frame = match stack.last_mut() {
+                    Some(frame) => frame,
+                    None => return Ok(value.expect("a value is read before the stack empties")),
+                };
+                match *frame {
+                    ReadFrame::List(elem_id, len, ref mut read, ref mut buf) => {
+                        buf.extend(value.take());
+                        if *read < len {
+                            *read += 1;
+                            id = elem_id;
+                            break;
+                        }
+                    },
+                    ReadFrame::Compound(ref mut buf, ref mut name) => {
+                        let current = name.take();
+                        if let Some(tag) = value.take() {
+                            buf.insert(current.unwrap_or_default(), tag);
+                        }
+                        let next_id = attempt!(src.read_bare_byte());
+                        if next_id != 0x00 {
+                            match src.read_bare_string() {
+                                Ok(next_name) => {
+                                    id = next_id;
+                                    *name = Some(next_name);
+                                    break;
+                                },
+                                // Skip the entry, whose payload can still be
+                                // read as usual.
+                                Err(Error::InvalidUtf8) if warnings.is_some() => {
+                                    if let Some(ref mut warnings) = warnings {
+                                        warnings.push(locate(Error::InvalidUtf8, stack, src.offset()));
+                                    }
+                                    attempt!(Value::read_tree(next_id, src, &mut Vec::new(), None));
+                                    continue;
+                                },
+                                Err(e) => attempt!(Err(e)),
+                            }
+                        }
+                    },
+                }
+                src.end_nested();
+                value = stack.pop().map(ReadFrame::into_value);
+            }
+        }
+    }
+
+    /// Reads a whole scalar payload, or the start of a List or Compound,
+    /// which is pushed onto `stack`.
+    fn begin_payload<R>(id: i8, src: &mut RawReader<R>, stack: &mut Vec<ReadFrame>) -> Result<Option<Value>>
+        where R: io::Read,
+    {
+        match id {
+            0x09 => {
+                src.begin_nested()?;
+                let id = src.read_bare_byte()?;
+                let len = src.read_list_len()?;
+                stack.push(ReadFrame::List(id, len, 0, Vec::with_capacity(len.min(raw::PREALLOC_LEN))));
+                Ok(None)
+            },
+            0x0a => {
+                src.begin_nested()?;
+                stack.push(ReadFrame::Compound(Map::new(), None));
+                Ok(None)
+            },
+            id => Value::read_scalar(id, src).map(Some),
+        }
+    }
+
+    fn read_scalar<R>(id: i8, src: &mut RawReader<R>) -> Result<Value>
         where R: io::Read,
     {
         match id {
@@ -130,31 +881,51 @@ impl Value {
             0x06 => Ok(Value::Double(src.read_bare_double()?)),
             0x07 => Ok(Value::ByteArray(src.read_bare_byte_array()?)),
             0x08 => Ok(Value::String(src.read_bare_string()?)),
-            0x09 => { // List
-                let id = src.read_bare_byte()?;
-                let len = src.read_bare_int()? as usize;
-                let mut buf = Vec::with_capacity(len);
-                for _ in 0..len {
-                    buf.push(Value::from_raw_reader(id, src)?);
-                }
-                Ok(Value::List(buf))
-            },
-            0x0a => { // Compound
-                let mut buf = HashMap::new();
-                loop {
-                    let (id, name) = src.emit_next_header()?;
-                    if id == 0x00 { break; }
-                    let tag = Value::from_raw_reader(id, src)?;
-                    buf.insert(name, tag);
-                }
-                Ok(Value::Compound(buf))
-            },
             0x0b => Ok(Value::IntArray(src.read_bare_int_array()?)),
             0x0c => Ok(Value::LongArray(src.read_bare_long_array()?)),
             e => Err(Error::InvalidTypeId(e))
         }
     }
 
+    /// Parses a `Value` from stringified NBT (SNBT), the syntax used by
+    /// Minecraft commands such as `/data get`.
+    ///
+    /// ```rust
+    /// use nbt::Value;
+    ///
+    /// let value = Value::from_snbt("[B; 1b, 2b, -3b]").unwrap();
+    /// assert_eq!(value, Value::ByteArray(vec![1, 2, -3]));
+    /// ```
+    pub fn from_snbt(src: &str) -> Result<Value> {
+        snbt::parse(src)
+    }
+
+    /// Formats this `Value` as compact SNBT that can be pasted into Minecraft
+    /// commands or read back with `Value::from_snbt()`.
+    ///
+    /// Unlike the `Display` output, every number carries its type suffix and
+    /// strings are quoted and escaped. Compound keys are sorted.
+    ///
+    /// ```rust
+    /// use nbt::Value;
+    ///
+    /// let value = Value::List(vec![Value::Short(1), Value::Short(2)]);
+    /// assert_eq!(value.to_snbt(), "[1s,2s]");
+    /// assert_eq!(Value::from_snbt(&value.to_snbt()).unwrap(), value);
+    /// ```
+    ///
+    /// Floating point values that are NaN or infinite have no SNBT
+    /// representation and do not round-trip.
+    pub fn to_snbt(&self) -> String {
+        snbt::write(self, false)
+    }
+
+    /// Formats this `Value` as SNBT like `to_snbt()`, but with Compounds and
+    /// nested Lists broken over several lines and indented.
+    pub fn to_snbt_pretty(&self) -> String {
+        snbt::write(self, true)
+    }
+
     /// Reads the payload of an `Value` with a given type ID from an
     /// `io::Read` source.
     pub fn from_reader<R>(id: i8, src: &mut R, endian: Endianness) -> Result<Value>
@@ -164,42 +935,410 @@ impl Value {
         Value::from_raw_reader(id, &mut src)
     }
 
+    /// Reads a whole named tag of any type from an `io::Read` source,
+    /// returning its name along with its value.
+    ///
+    /// By convention files have a root Compound, which is all that
+    /// `Blob::from_reader()` accepts, but some tooling and older files use a
+    /// root List or even a bare primitive instead.
+    ///
+    /// ```rust
+    /// use nbt::{Endianness, Value};
+    ///
+    /// let bytes = [0x09, 0x00, 0x01, b'l', 0x01, 0x00, 0x00, 0x00, 0x02, 0x05, 0x06];
+    /// let (name, value) = Value::from_reader_root(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    /// assert_eq!((name.as_str(), value), ("l", Value::List(vec![Value::Byte(5), Value::Byte(6)])));
+    /// ```
+    pub fn from_reader_root<R>(src: &mut R, endian: Endianness) -> Result<(String, Value)>
+        where R: io::Read
+    {
+        let mut src = RawReader::new(src, endian);
+        let (tag, name) = src.emit_next_header()?;
+        let value = Value::from_raw_reader(tag, &mut src)?;
+        Ok((name, value))
+    }
+
+    /// Writes this `Value` as a whole tag with the given name to an
+    /// `io::Write` destination, which `from_reader_root()` reads back.
+    pub fn to_writer_root<W>(&self, dst: &mut W, name: &str, endian: Endianness) -> Result<()>
+        where W: io::Write
+    {
+        let mut dst = RawWriter::new(dst, endian);
+        dst.write_header(self.id(), name)?;
+        self.to_raw_writer(&mut dst)
+    }
+
+    /// Writes this value as the `Display` implementation does, with its
+    /// braces at column `offset`.
     pub fn print(&self, f: &mut fmt::Formatter, offset: usize) -> fmt::Result {
+        Printer::new().payload(f, self, offset, 0)
+    }
+
+    /// Formats this value with the settings of a `Printer`.
+    pub fn display_with<'a>(&'a self, printer: &'a Printer) -> Printed<'a> {
+        Printed::value(printer, self)
+    }
+}
+
+/// A List or Compound being read by `Value::from_raw_reader()`.
+enum ReadFrame {
+    /// The element type and length, how many elements have been started,
+    /// and the elements read so far.
+    List(i8, usize, usize, Vec<Value>),
+    /// The entries read so far, and the name of the entry being read, if
+    /// its header has been read.
+    Compound(Map, Option<String>),
+}
+
+impl ReadFrame {
+    fn into_value(self) -> Value {
+        match self {
+            ReadFrame::List(_, _, _, buf) => Value::List(buf),
+            ReadFrame::Compound(buf, _) => Value::Compound(buf),
+        }
+    }
+}
+
+/// Adds the location described by `stack` and `offset` to an error.
+fn locate(e: Error, stack: &[ReadFrame], offset: u64) -> Error {
+    if let Error::Located(..) = e {
+        return e;
+    }
+    let mut path = String::new();
+    for frame in stack {
+        match *frame {
+            ReadFrame::List(_, _, read, _) => path.push_str(&format!("[{}]", read.saturating_sub(1))),
+            ReadFrame::Compound(_, Some(ref name)) => path::write_key(&mut path, name),
+            ReadFrame::Compound(_, None) => {},
+        }
+    }
+    Error::Located(offset, path, Box::new(e))
+}
+
+/// Closes all the Lists and Compounds still being read, keeping what they
+/// hold so far, and returns the outermost.
+fn close_frames<R: io::Read>(stack: &mut Vec<ReadFrame>, src: &mut RawReader<R>) -> Value {
+    let mut value = None;
+    while let Some(mut frame) = stack.pop() {
+        src.end_nested();
+        match frame {
+            ReadFrame::List(_, _, _, ref mut buf) => buf.extend(value.take()),
+            ReadFrame::Compound(ref mut buf, ref mut name) => if let Some(tag) = value.take() {
+                buf.insert(name.take().unwrap_or_default(), tag);
+            },
+        }
+        value = Some(frame.into_value());
+    }
+    value.expect("close_frames() needs at least one frame")
+}
+
+/// A List or Compound being written by `Value::to_raw_writer()`, with the
+/// children that remain to be written.
+enum WriteFrame<'a> {
+    List(i8, slice::Iter<'a, Value>),
+    Compound(Entries<'a>),
+}
+
+/// The entries of a Compound, in the order they are written or printed.
+pub(crate) enum Entries<'a> {
+    Unsorted(map::Iter<'a>),
+    Sorted(vec::IntoIter<(&'a String, &'a Value)>),
+}
+
+impl<'a> Entries<'a> {
+    pub(crate) fn new(map: &'a Map, sort: bool) -> Entries<'a> {
+        if sort {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|&(name, _)| name);
+            Entries::Sorted(entries.into_iter())
+        } else {
+            Entries::Unsorted(map.iter())
+        }
+    }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a String, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
         match *self {
-            Value::Byte(v)   => write!(f, "{}", v),
-            Value::Short(v)  => write!(f, "{}", v),
-            Value::Int(v)    => write!(f, "{}", v),
-            Value::Long(v)   => write!(f, "{}", v),
-            Value::Float(v)  => write!(f, "{}", v),
-            Value::Double(v) => write!(f, "{}", v),
-            Value::ByteArray(ref v) => write!(f, "{:?}", v),
-            Value::String(ref v) => write!(f, "{}", v),
-            Value::IntArray(ref v) => write!(f, "{:?}", v),
-            Value::LongArray(ref v) => write!(f, "{:?}", v),
-            Value::List(ref v) => {
-                if v.len() == 0 {
-                    write!(f, "zero entries")
-                } else {
-                    write!(f, "{} entries of type {}\n{:>width$}\n", v.len(), v[0].tag_name(), "{", width = offset + 1)?;
-                    for tag in v {
-                        let new_offset = offset + 2;
-                        write!(f, "{:>width$}(None): ", tag.tag_name(), width = new_offset + tag.tag_name().len())?;
-                        tag.print(f, new_offset)?;
-                        write!(f, "\n")?;
+            Entries::Unsorted(ref mut iter) => iter.next(),
+            Entries::Sorted(ref mut iter) => iter.next(),
+        }
+    }
+}
+
+/// Formats the entries of a Map as `Debug` does, but sorted by name if
+/// the map's own order is arbitrary.
+pub(crate) struct DebugMap<'a>(pub(crate) &'a Map);
+
+impl<'a> fmt::Debug for DebugMap<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(Entries::new(self.0, map::ARBITRARY_ORDER)).finish()
+    }
+}
+
+/// The name of the tag with a type ID, as in `Value::tag_name()`.
+pub(crate) fn tag_name_of(id: i8) -> &'static str {
+    match id {
+        0x00 => "TAG_End",
+        0x01 => "TAG_Byte",
+        0x02 => "TAG_Short",
+        0x03 => "TAG_Int",
+        0x04 => "TAG_Long",
+        0x05 => "TAG_Float",
+        0x06 => "TAG_Double",
+        0x07 => "TAG_ByteArray",
+        0x08 => "TAG_String",
+        0x09 => "TAG_List",
+        0x0a => "TAG_Compound",
+        0x0b => "TAG_IntArray",
+        0x0c => "TAG_LongArray",
+        _ => "an unknown tag",
+    }
+}
+
+macro_rules! copy_accessors {
+    ($($variant:ident($ty:ty) => $is:ident, $as:ident;)*) => {
+        impl Value {
+            $(
+                #[doc = concat!("Whether this is a `Value::", stringify!($variant), "`.")]
+                pub fn $is(&self) -> bool {
+                    matches!(*self, Value::$variant(_))
+                }
+
+                #[doc = concat!("The number in a `Value::", stringify!($variant), "`, or `None` for any ")]
+                #[doc = "other type. Other numeric types are not converted."]
+                pub fn $as(&self) -> Option<$ty> {
+                    match *self {
+                        Value::$variant(v) => Some(v),
+                        _ => None,
                     }
-                    write!(f, "{:>width$}", "}", width = offset + 1)
                 }
-            }
-            Value::Compound(ref v) => {
-                write!(f, "{} entry(ies)\n{:>width$}\n", v.len(), "{", width = offset + 1)?;
-                for (name, tag) in v {
-                    let new_offset = offset + 2;
-                    write!(f, "{:>width$}({}): ", tag.tag_name(), name, width = new_offset + tag.tag_name().len())?;
-                    tag.print(f, new_offset)?;
-                    write!(f, "\n")?;
+            )*
+        }
+    };
+}
+
+macro_rules! ref_accessors {
+    ($($variant:ident($ty:ty) => $is:ident, $as:ident;)*) => {
+        impl Value {
+            $(
+                #[doc = concat!("Whether this is a `Value::", stringify!($variant), "`.")]
+                pub fn $is(&self) -> bool {
+                    matches!(*self, Value::$variant(_))
                 }
-                write!(f, "{:>width$}", "}", width = offset + 1)
-            }
+
+                #[doc = concat!("Borrows the payload of a `Value::", stringify!($variant), "`, or returns ")]
+                #[doc = "`None` for any other type."]
+                pub fn $as(&self) -> Option<&$ty> {
+                    match *self {
+                        Value::$variant(ref v) => Some(v),
+                        _ => None,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+copy_accessors! {
+    Byte(i8) => is_byte, as_i8;
+    Short(i16) => is_short, as_i16;
+    Int(i32) => is_int, as_i32;
+    Long(i64) => is_long, as_i64;
+    Float(f32) => is_float, as_f32;
+    Double(f64) => is_double, as_f64;
+}
+
+ref_accessors! {
+    ByteArray([i8]) => is_byte_array, as_byte_array;
+    String(str) => is_string, as_str;
+    List([Value]) => is_list, as_list;
+    Compound(Map) => is_compound, as_compound;
+    IntArray([i32]) => is_int_array, as_int_array;
+    LongArray([i64]) => is_long_array, as_long_array;
+}
+
+impl Value {
+    /// Borrows the elements of a `Value::List` mutably, or returns `None` for
+    /// any other type.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate nbt;
+    ///
+    /// # fn main() {
+    /// let mut player = nbt!({ Pos: [0.5f64, 64.0f64, 0.5f64], Health: 20.0f32 });
+    /// assert_eq!(player["Health"].as_f32(), Some(20.0));
+    /// assert_eq!(player["Health"].as_f64(), None);
+    /// assert!(player["Pos"].is_list() && !player["Pos"].is_compound());
+    ///
+    /// player["Pos"].as_list_mut().unwrap().truncate(2);
+    /// assert_eq!(player["Pos"].as_list().map(|pos| pos.len()), Some(2));
+    /// # }
+    /// ```
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match *self {
+            Value::List(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Borrows the entries of a `Value::Compound` mutably, or returns `None`
+    /// for any other type.
+    pub fn as_compound_mut(&mut self) -> Option<&mut Map> {
+        match *self {
+            Value::Compound(ref mut v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+/// Types that can index into a `Value`: strings for the entries of a
+/// Compound, and `usize` for the elements of a List.
+///
+/// The elements of the array tags are not `Value`s, so they cannot be indexed
+/// this way; match on the variant and index the `Vec` instead.
+pub trait ValueIndex {
+    /// The value at this index of `value`, if it has one.
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+
+    /// The value at this index of `value` for modification, if it has one.
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match *value {
+            Value::List(ref vals) => vals.get(*self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        match *value {
+            Value::List(ref mut vals) => vals.get_mut(*self),
+            _ => None,
+        }
+    }
+}
+
+impl ValueIndex for str {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match *value {
+            Value::Compound(ref map) => map.get(self),
+            _ => None,
+        }
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        match *value {
+            Value::Compound(ref mut map) => map.get_mut(self),
+            _ => None,
+        }
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self[..].index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        self[..].index_into_mut(value)
+    }
+}
+
+impl<I: ValueIndex + ?Sized> ValueIndex for &I {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(value)
+    }
+
+    fn index_into_mut<'v>(&self, value: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(value)
+    }
+}
+
+/// Indexes into a Compound by key or a List by position.
+///
+/// # Panics
+///
+/// Panics if the value is of another type, or has no such entry or element.
+/// Use `Value::get()` to check instead. Assigning through `IndexMut` can
+/// only replace an existing entry; add new ones with `Value::entry()`.
+impl<I: ValueIndex> Index<I> for Value {
+    type Output = Value;
+
+    fn index(&self, index: I) -> &Value {
+        match index.index_into(self) {
+            Some(value) => value,
+            None => panic!("no such entry or element in {}", self.tag_name()),
+        }
+    }
+}
+
+impl<I: ValueIndex> IndexMut<I> for Value {
+    fn index_mut(&mut self, index: I) -> &mut Value {
+        if index.index_into(self).is_none() {
+            panic!("no such entry or element in {}", self.tag_name());
+        }
+        index.index_into_mut(self).unwrap()
+    }
+}
+
+/// Types that can be moved out of a `Value` without cloning.
+///
+/// This is implemented for the payloads of the container variants, and is
+/// used by `Blob::remove_as()`. On failure the original `Value` is returned
+/// untouched.
+pub trait FromValue: Sized {
+    /// Converts `value`, or returns it unchanged if it has the wrong type.
+    fn try_from_value(value: Value) -> StdResult<Self, Value>;
+}
+
+impl FromValue for Value {
+    fn try_from_value(value: Value) -> StdResult<Value, Value> { Ok(value) }
+}
+
+impl FromValue for Map {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_compound() }
+}
+
+impl FromValue for Vec<Value> {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_list() }
+}
+
+impl FromValue for String {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_string() }
+}
+
+impl FromValue for Vec<i8> {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_byte_array() }
+}
+
+impl FromValue for Vec<i32> {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_int_array() }
+}
+
+impl FromValue for Vec<i64> {
+    fn try_from_value(value: Value) -> StdResult<Self, Value> { value.into_long_array() }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Byte(ref v)      => f.debug_tuple("Byte").field(v).finish(),
+            Value::Short(ref v)     => f.debug_tuple("Short").field(v).finish(),
+            Value::Int(ref v)       => f.debug_tuple("Int").field(v).finish(),
+            Value::Long(ref v)      => f.debug_tuple("Long").field(v).finish(),
+            Value::Float(ref v)     => f.debug_tuple("Float").field(v).finish(),
+            Value::Double(ref v)    => f.debug_tuple("Double").field(v).finish(),
+            Value::ByteArray(ref v) => f.debug_tuple("ByteArray").field(v).finish(),
+            Value::String(ref v)    => f.debug_tuple("String").field(v).finish(),
+            Value::List(ref v)      => f.debug_tuple("List").field(v).finish(),
+            Value::Compound(ref v)  => f.debug_tuple("Compound").field(&DebugMap(v)).finish(),
+            Value::IntArray(ref v)  => f.debug_tuple("IntArray").field(v).finish(),
+            Value::LongArray(ref v) => f.debug_tuple("LongArray").field(v).finish(),
         }
     }
 }
@@ -210,6 +1349,236 @@ impl fmt::Display for Value {
     }
 }
 
+/// Floats are equal when their bit patterns are, so that equality is
+/// reflexive and `Value` can be `Eq`: `0.0` and `-0.0` differ, and a NaN
+/// equals a NaN with the same bits. This differs from `==` on `f32` and
+/// `f64`; compare the numbers themselves for IEEE semantics. Compounds are
+/// equal when they have the same entries, in any order.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        let mut stack = Vec::new();
+        let mut next = (self, other);
+        loop {
+            if !eq_shallow(next.0, next.1, &mut stack) {
+                return false;
+            }
+            match stack.pop() {
+                Some(pair) => next = pair,
+                None => return true,
+            }
+        }
+    }
+}
+
+impl Eq for Value {}
+
+/// Compares the Lists and Compounds in `a` and `b` by their lengths and
+/// leaves, pushing pairs of nested Lists and Compounds onto `stack` to be
+/// compared later.
+fn eq_shallow<'a>(a: &'a Value, b: &'a Value, stack: &mut Vec<(&'a Value, &'a Value)>) -> bool {
+    match (a, b) {
+        (Value::List(a), Value::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| eq_or_push(a, b, stack))
+        },
+        (Value::Compound(a), Value::Compound(b)) => {
+            a.len() == b.len() && a.iter().all(|(name, a)| match b.get(name.as_str()) {
+                Some(b) => eq_or_push(a, b, stack),
+                None => false,
+            })
+        },
+        (Value::Byte(a), Value::Byte(b))           => a == b,
+        (Value::Short(a), Value::Short(b))         => a == b,
+        (Value::Int(a), Value::Int(b))             => a == b,
+        (Value::Long(a), Value::Long(b))           => a == b,
+        (Value::Float(a), Value::Float(b))         => a.to_bits() == b.to_bits(),
+        (Value::Double(a), Value::Double(b))       => a.to_bits() == b.to_bits(),
+        (Value::ByteArray(a), Value::ByteArray(b)) => a == b,
+        (Value::String(a), Value::String(b))       => a == b,
+        (Value::IntArray(a), Value::IntArray(b))   => a == b,
+        (Value::LongArray(a), Value::LongArray(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn eq_or_push<'a>(a: &'a Value, b: &'a Value, stack: &mut Vec<(&'a Value, &'a Value)>) -> bool {
+    match *a {
+        Value::List(_) | Value::Compound(_) => {
+            stack.push((a, b));
+            true
+        },
+        _ => eq_shallow(a, b, stack),
+    }
+}
+
+impl Clone for Value {
+    fn clone(&self) -> Value {
+        let mut stack = Vec::new();
+        let mut next = self;
+        loop {
+            let mut done = match *next {
+                Value::List(ref vals) => {
+                    stack.push(CloneFrame::List(vals.iter(), Vec::with_capacity(vals.len())));
+                    None
+                },
+                Value::Compound(ref vals) => {
+                    stack.push(CloneFrame::Compound(vals.iter(), map::with_capacity(vals.len()), None));
+                    None
+                },
+                Value::Byte(v)          => Some(Value::Byte(v)),
+                Value::Short(v)         => Some(Value::Short(v)),
+                Value::Int(v)           => Some(Value::Int(v)),
+                Value::Long(v)          => Some(Value::Long(v)),
+                Value::Float(v)         => Some(Value::Float(v)),
+                Value::Double(v)        => Some(Value::Double(v)),
+                Value::ByteArray(ref v) => Some(Value::ByteArray(v.clone())),
+                Value::String(ref v)    => Some(Value::String(v.clone())),
+                Value::IntArray(ref v)  => Some(Value::IntArray(v.clone())),
+                Value::LongArray(ref v) => Some(Value::LongArray(v.clone())),
+            };
+
+            // Find the next value to clone, finishing Lists and Compounds
+            // on the way out.
+            loop {
+                let frame = match stack.last_mut() {
+                    Some(frame) => frame,
+                    None => return done.expect("the outermost value is finished last"),
+                };
+                if let Some(value) = done.take() {
+                    frame.push(value);
+                }
+                match frame.next_child() {
+                    Some(child) => {
+                        next = child;
+                        break;
+                    },
+                    None => done = stack.pop().map(CloneFrame::into_value),
+                }
+            }
+        }
+    }
+}
+
+/// A List or Compound being cloned, with the children that remain to be
+/// cloned and those cloned so far.
+enum CloneFrame<'a> {
+    List(slice::Iter<'a, Value>, Vec<Value>),
+    /// The name of the entry being cloned is kept until it is finished.
+    Compound(map::Iter<'a>, Map, Option<&'a String>),
+}
+
+impl<'a> CloneFrame<'a> {
+    fn next_child(&mut self) -> Option<&'a Value> {
+        match *self {
+            CloneFrame::List(ref mut iter, _) => iter.next(),
+            CloneFrame::Compound(ref mut iter, _, ref mut name) => iter.next().map(|(key, value)| {
+                *name = Some(key);
+                value
+            }),
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        match *self {
+            CloneFrame::List(_, ref mut buf) => buf.push(value),
+            CloneFrame::Compound(_, ref mut buf, ref mut name) => {
+                let name = name.take().expect("an entry is being cloned");
+                buf.insert(name.clone(), value);
+            },
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            CloneFrame::List(_, buf) => Value::List(buf),
+            CloneFrame::Compound(_, buf, _) => Value::Compound(buf),
+        }
+    }
+}
+
+/// Whether `value` is a List or Compound with children, which
+/// `drop_iteratively()` must empty before it is dropped.
+fn is_nested(value: &Value) -> bool {
+    match *value {
+        Value::List(ref vals) => !vals.is_empty(),
+        Value::Compound(ref vals) => !vals.is_empty(),
+        _ => false,
+    }
+}
+
+/// Hashes the `canonical_hash()`, so the hash does not depend on the order
+/// of Compounds, and each call walks the whole tree.
+///
+/// ```rust
+/// #[macro_use] extern crate nbt;
+/// use std::collections::HashSet;
+///
+/// # fn main() {
+/// let palette = vec![
+///     nbt!({ Name: "minecraft:stone" }),
+///     nbt!({ Name: "minecraft:oak_log", Properties: { axis: "y" } }),
+///     nbt!({ Name: "minecraft:stone" }),
+/// ];
+/// let unique: HashSet<_> = palette.into_iter().collect();
+/// assert_eq!(unique.len(), 2);
+/// # }
+/// ```
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.canonical_hash());
+    }
+}
+
+/// Values are ordered by tag ID first, so that every Byte comes before every
+/// Short, and then by content:
+///
+/// * numbers by value, with floats in the total order of `f32::total_cmp()`,
+///   which places `-0.0` before `0.0` and agrees with `==`;
+/// * Strings by their UTF-8 bytes;
+/// * Lists and arrays element by element, a prefix coming first;
+/// * Compounds as Lists of their entries sorted by name, each compared by
+///   name and then value, so the order of a Compound does not matter.
+///
+/// ```rust
+/// #[macro_use] extern crate nbt;
+///
+/// # fn main() {
+/// let mut values = vec![nbt!("b"), nbt!(2i32), nbt!("a"), nbt!(1i8), nbt!(-1i32)];
+/// values.sort();
+/// assert_eq!(values, vec![nbt!(1i8), nbt!(-1i32), nbt!(2i32), nbt!("a"), nbt!("b")]);
+/// # }
+/// ```
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Byte(a), Value::Byte(b))           => a.cmp(b),
+            (Value::Short(a), Value::Short(b))         => a.cmp(b),
+            (Value::Int(a), Value::Int(b))             => a.cmp(b),
+            (Value::Long(a), Value::Long(b))           => a.cmp(b),
+            (Value::Float(a), Value::Float(b))         => a.total_cmp(b),
+            (Value::Double(a), Value::Double(b))       => a.total_cmp(b),
+            (Value::ByteArray(a), Value::ByteArray(b)) => a.cmp(b),
+            (Value::String(a), Value::String(b))       => a.cmp(b),
+            (Value::List(a), Value::List(b))           => a.cmp(b),
+            (Value::Compound(a), Value::Compound(b))   => sorted_entries(a).cmp(&sorted_entries(b)),
+            (Value::IntArray(a), Value::IntArray(b))   => a.cmp(b),
+            (Value::LongArray(a), Value::LongArray(b)) => a.cmp(b),
+            _ => self.id().cmp(&other.id()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn sorted_entries(map: &Map) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|&(name, _)| name);
+    entries
+}
+
 impl From<i8> for Value {
     fn from(t: i8) -> Value { Value::Byte(t) }
 }
@@ -265,3 +1634,326 @@ impl From<Vec<i64>> for Value {
 impl<'a> From<&'a [i64]> for Value {
     fn from(t: &'a [i64]) -> Value { Value::LongArray(t.into()) }
 }
+
+/// Booleans are stored as a `TAG_Byte` of 0 or 1, as Minecraft does.
+impl From<bool> for Value {
+    fn from(t: bool) -> Value { Value::Byte(t as i8) }
+}
+
+/// Unsigned bytes are stored with the same bits, so values above 127 read
+/// back as negative `i8`s.
+impl From<Vec<u8>> for Value {
+    fn from(t: Vec<u8>) -> Value { Value::ByteArray(t.into_iter().map(|b| b as i8).collect()) }
+}
+
+impl<'a> From<&'a [u8]> for Value {
+    fn from(t: &'a [u8]) -> Value { Value::ByteArray(t.iter().map(|&b| b as i8).collect()) }
+}
+
+impl<const N: usize> From<[u8; N]> for Value {
+    fn from(t: [u8; N]) -> Value { Value::from(&t[..]) }
+}
+
+impl<const N: usize> From<[i8; N]> for Value {
+    fn from(t: [i8; N]) -> Value { Value::ByteArray(t.to_vec()) }
+}
+
+impl<const N: usize> From<[i32; N]> for Value {
+    fn from(t: [i32; N]) -> Value { Value::IntArray(t.to_vec()) }
+}
+
+impl<const N: usize> From<[i64; N]> for Value {
+    fn from(t: [i64; N]) -> Value { Value::LongArray(t.to_vec()) }
+}
+
+impl From<Map> for Value {
+    fn from(t: Map) -> Value { Value::Compound(t) }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(t: Vec<Value>) -> Value { Value::List(t) }
+}
+
+// Types without an array tag of their own become Lists.
+macro_rules! from_list {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> From<Vec<$ty>> for Value {
+                fn from(t: Vec<$ty>) -> Value { Value::List(t.into_iter().map(Value::from).collect()) }
+            }
+
+            impl<'a, const N: usize> From<[$ty; N]> for Value {
+                fn from(t: [$ty; N]) -> Value { Value::List(IntoIterator::into_iter(t).map(Value::from).collect()) }
+            }
+        )*
+    };
+}
+
+from_list!(i16, f32, f64, String, &'a str);
+
+macro_rules! try_from_ref_copy {
+    ($ty:ty, $variant:ident, $id:expr) => {
+        impl<'a> TryFrom<&'a Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: &'a Value) -> Result<$ty> {
+                match *value {
+                    Value::$variant(v) => Ok(v),
+                    ref other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! try_from_ref_borrow {
+    ($ty:ty, $variant:ident, $id:expr) => {
+        impl<'a> TryFrom<&'a Value> for &'a $ty {
+            type Error = Error;
+
+            fn try_from(value: &'a Value) -> Result<&'a $ty> {
+                match *value {
+                    Value::$variant(ref v) => Ok(v),
+                    ref other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! try_from_owned {
+    ($ty:ty, $variant:ident, $id:expr) => {
+        impl TryFrom<Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<$ty> {
+                match value {
+                    Value::$variant(v) => Ok(v),
+                    other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! try_from_ref_clone {
+    ($ty:ty, $variant:ident, $id:expr) => {
+        impl<'a> TryFrom<&'a Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: &'a Value) -> Result<$ty> {
+                match *value {
+                    Value::$variant(ref v) => Ok(v.clone()),
+                    ref other => Err(Error::TagMismatch(other.id(), $id)),
+                }
+            }
+        }
+    };
+}
+
+try_from_ref_copy!(i8, Byte, 0x01);
+try_from_ref_copy!(i16, Short, 0x02);
+try_from_ref_copy!(i32, Int, 0x03);
+try_from_ref_copy!(i64, Long, 0x04);
+try_from_ref_copy!(f32, Float, 0x05);
+try_from_ref_copy!(f64, Double, 0x06);
+try_from_ref_borrow!([i8], ByteArray, 0x07);
+try_from_ref_borrow!(str, String, 0x08);
+try_from_ref_borrow!([Value], List, 0x09);
+try_from_ref_borrow!(Map, Compound, 0x0a);
+try_from_ref_borrow!([i32], IntArray, 0x0b);
+try_from_ref_borrow!([i64], LongArray, 0x0c);
+
+try_from_owned!(i8, Byte, 0x01);
+try_from_owned!(i16, Short, 0x02);
+try_from_owned!(i32, Int, 0x03);
+try_from_owned!(i64, Long, 0x04);
+try_from_owned!(f32, Float, 0x05);
+try_from_owned!(f64, Double, 0x06);
+try_from_owned!(Vec<i8>, ByteArray, 0x07);
+try_from_owned!(String, String, 0x08);
+try_from_owned!(Vec<Value>, List, 0x09);
+try_from_owned!(Map, Compound, 0x0a);
+try_from_owned!(Vec<i32>, IntArray, 0x0b);
+try_from_owned!(Vec<i64>, LongArray, 0x0c);
+
+try_from_ref_clone!(Vec<i8>, ByteArray, 0x07);
+try_from_ref_clone!(String, String, 0x08);
+try_from_ref_clone!(Vec<Value>, List, 0x09);
+try_from_ref_clone!(Map, Compound, 0x0a);
+try_from_ref_clone!(Vec<i32>, IntArray, 0x0b);
+try_from_ref_clone!(Vec<i64>, LongArray, 0x0c);
+
+/// Booleans are stored as a `TAG_Byte` of 0 or 1. Other bytes fail with
+/// `Error::NonBooleanByte`.
+impl<'a> TryFrom<&'a Value> for bool {
+    type Error = Error;
+
+    fn try_from(value: &'a Value) -> Result<bool> {
+        match *value {
+            Value::Byte(0) => Ok(false),
+            Value::Byte(1) => Ok(true),
+            Value::Byte(b) => Err(Error::NonBooleanByte(b)),
+            ref other => Err(Error::TagMismatch(other.id(), 0x01)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<bool> {
+        bool::try_from(&value)
+    }
+}
+
+/// The name of the newtype struct a `Value` deserializes as, which asks the
+/// NBT decoders to report the tag of the value as an enum variant.
+#[cfg(feature = "serde")]
+pub(crate) const VALUE_TOKEN: &str = "$nbt::private::Value";
+
+/// The variants reported to the `Value` visitor, indexed by type ID.
+#[cfg(feature = "serde")]
+const VARIANTS: &[&str] = &[
+    "Byte", "Short", "Int", "Long", "Float", "Double", "ByteArray", "String", "List", "Compound",
+    "IntArray", "LongArray",
+];
+
+/// The variant naming the tag with type ID `id`, as read by the `Value`
+/// visitor, or an error if `id` is not the ID of a payload.
+#[cfg(feature = "serde")]
+pub(crate) fn variant_name(id: i8) -> Result<&'static str> {
+    VARIANTS.get(id.wrapping_sub(1) as usize).cloned().ok_or(Error::InvalidTypeId(id))
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Value {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> StdResult<S::Ok, S::Error> {
+        match *self {
+            Value::Byte(v) => serializer.serialize_i8(v),
+            Value::Short(v) => serializer.serialize_i16(v),
+            Value::Int(v) => serializer.serialize_i32(v),
+            Value::Long(v) => serializer.serialize_i64(v),
+            Value::Float(v) => serializer.serialize_f32(v),
+            Value::Double(v) => serializer.serialize_f64(v),
+            Value::ByteArray(ref v) => ::arrays::byte_array::serialize(v, serializer),
+            Value::String(ref v) => serializer.serialize_str(v),
+            Value::List(ref v) => v.serialize(serializer),
+            Value::Compound(ref v) => v.serialize(serializer),
+            Value::IntArray(ref v) => ::arrays::int_array::serialize(v, serializer),
+            Value::LongArray(ref v) => ::arrays::long_array::serialize(v, serializer),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Value {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> StdResult<Value, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_TOKEN, ValueVisitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct ValueVisitor;
+
+#[cfg(feature = "serde")]
+impl ValueVisitor {
+    /// The smallest tag that holds an integer from a format without tags.
+    fn integer<E: ::serde::de::Error>(v: i128) -> StdResult<Value, E> {
+        if let Ok(v) = i8::try_from(v) {
+            Ok(Value::Byte(v))
+        } else if let Ok(v) = i16::try_from(v) {
+            Ok(Value::Short(v))
+        } else if let Ok(v) = i32::try_from(v) {
+            Ok(Value::Int(v))
+        } else if let Ok(v) = i64::try_from(v) {
+            Ok(Value::Long(v))
+        } else {
+            Err(E::custom(format_args!("integer {} does not fit in a TAG_Long", v)))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an NBT value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> StdResult<Value, E> {
+        Ok(Value::Byte(i8::from(v)))
+    }
+
+    fn visit_i64<E: ::serde::de::Error>(self, v: i64) -> StdResult<Value, E> {
+        ValueVisitor::integer(v.into())
+    }
+
+    fn visit_u64<E: ::serde::de::Error>(self, v: u64) -> StdResult<Value, E> {
+        ValueVisitor::integer(v.into())
+    }
+
+    fn visit_f32<E>(self, v: f32) -> StdResult<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> StdResult<Value, E> {
+        Ok(Value::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> StdResult<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> StdResult<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> StdResult<Value, E> {
+        Ok(Value::ByteArray(v.iter().map(|&b| b as i8).collect()))
+    }
+
+    fn visit_newtype_struct<D: ::serde::Deserializer<'de>>(self, deserializer: D) -> StdResult<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: ::serde::de::SeqAccess<'de>>(self, mut seq: A) -> StdResult<Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::List(values))
+    }
+
+    fn visit_map<A: ::serde::de::MapAccess<'de>>(self, mut access: A) -> StdResult<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some((key, value)) = access.next_entry()? {
+            map.insert(key, value);
+        }
+        Ok(Value::Compound(map))
+    }
+
+    /// The NBT decoders give the tag of the value as the variant, with the
+    /// payload as its contents.
+    fn visit_enum<A: ::serde::de::EnumAccess<'de>>(self, data: A) -> StdResult<Value, A::Error> {
+        use serde::de::VariantAccess;
+
+        let (variant, payload): (String, _) = data.variant()?;
+        match &variant[..] {
+            "Byte" => payload.newtype_variant().map(Value::Byte),
+            "Short" => payload.newtype_variant().map(Value::Short),
+            "Int" => payload.newtype_variant().map(Value::Int),
+            "Long" => payload.newtype_variant().map(Value::Long),
+            "Float" => payload.newtype_variant().map(Value::Float),
+            "Double" => payload.newtype_variant().map(Value::Double),
+            "ByteArray" => payload.newtype_variant().map(Value::ByteArray),
+            "String" => payload.newtype_variant().map(Value::String),
+            "List" => payload.newtype_variant().map(Value::List),
+            "Compound" => payload.newtype_variant().map(Value::Compound),
+            "IntArray" => payload.newtype_variant().map(Value::IntArray),
+            "LongArray" => payload.newtype_variant().map(Value::LongArray),
+            other => Err(::serde::de::Error::unknown_variant(other, VARIANTS)),
+        }
+    }
+}
@@ -0,0 +1,655 @@
+//! Borrowed NBT values, parsed in place from a byte slice.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+use error::{Error, Result};
+use raw::{Endianness, RawReader};
+use value::Value;
+
+/// A borrowed counterpart to `Value`, parsed directly off a byte slice.
+///
+/// Strings and arrays point into the input instead of being copied out of it,
+/// which makes inspection-only workloads (find one field, discard the rest)
+/// much cheaper than building a `Blob`. Use `to_value` to get an owned copy of
+/// anything worth keeping.
+///
+/// Compounds keep their entries in the order they were read, and are searched
+/// linearly by `get`.
+///
+/// ```rust
+/// use nbt::{Blob, Endianness, ValueRef};
+///
+/// let mut blob = Blob::new();
+/// blob.insert("name", "Herobrine").unwrap();
+/// blob.insert("health", 100i8).unwrap();
+/// let mut bytes = Vec::new();
+/// blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+///
+/// let root = ValueRef::from_slice(&bytes, Endianness::BigEndian).unwrap();
+/// assert_eq!(root.get("name").and_then(ValueRef::as_str), Some("Herobrine"));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [i8]),
+    /// Strings are borrowed unless their Modified UTF-8 encoding differs from
+    /// UTF-8, which only happens for NUL and supplementary characters.
+    String(Cow<'a, str>),
+    List(Vec<ValueRef<'a>>),
+    Compound(Vec<(Cow<'a, str>, ValueRef<'a>)>),
+    IntArray(ArrayRef<'a, i32>),
+    LongArray(ArrayRef<'a, i64>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Parses the root compound of NBT data held in `src`. Its name, if any,
+    /// is discarded.
+    pub fn from_slice(src: &'a [u8], endian: Endianness) -> Result<ValueRef<'a>> {
+        let mut parser = Parser { reader: RawReader::new(src, endian), endian };
+        match parser.reader.read_bare_byte()? {
+            0x0a => {
+                parser.read_str()?;
+                parser.read(0x0a)
+            },
+            _ => Err(Error::NoRootCompound),
+        }
+    }
+
+    /// The type ID of this `ValueRef`, which is a single byte in the range
+    /// `0x01` to `0x0c`.
+    pub fn id(&self) -> i8 {
+        match *self {
+            ValueRef::Byte(_)      => 0x01,
+            ValueRef::Short(_)     => 0x02,
+            ValueRef::Int(_)       => 0x03,
+            ValueRef::Long(_)      => 0x04,
+            ValueRef::Float(_)     => 0x05,
+            ValueRef::Double(_)    => 0x06,
+            ValueRef::ByteArray(_) => 0x07,
+            ValueRef::String(_)    => 0x08,
+            ValueRef::List(_)      => 0x09,
+            ValueRef::Compound(_)  => 0x0a,
+            ValueRef::IntArray(_)  => 0x0b,
+            ValueRef::LongArray(_) => 0x0c,
+        }
+    }
+
+    /// Looks up an entry of a compound by name. Returns `None` if there is no
+    /// such entry or this is not a compound.
+    pub fn get(&self, name: &str) -> Option<&ValueRef<'a>> {
+        match *self {
+            ValueRef::Compound(ref entries) => entries.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a string, or `None` for any other type.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            ValueRef::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Copies this value and everything it borrows into an owned `Value`.
+    pub fn to_value(&self) -> Value {
+        match *self {
+            ValueRef::Byte(v) => Value::Byte(v),
+            ValueRef::Short(v) => Value::Short(v),
+            ValueRef::Int(v) => Value::Int(v),
+            ValueRef::Long(v) => Value::Long(v),
+            ValueRef::Float(v) => Value::Float(v),
+            ValueRef::Double(v) => Value::Double(v),
+            ValueRef::ByteArray(v) => Value::ByteArray(v.to_vec()),
+            ValueRef::String(ref v) => Value::String(v.clone().into_owned()),
+            ValueRef::List(ref v) => Value::List(v.iter().map(ValueRef::to_value).collect()),
+            ValueRef::Compound(ref v) => Value::Compound(
+                v.iter().map(|(k, v)| (k.clone().into_owned(), v.to_value())).collect()
+            ),
+            ValueRef::IntArray(ref v) => Value::IntArray(v.to_vec()),
+            ValueRef::LongArray(ref v) => Value::LongArray(v.to_vec()),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b ValueRef<'a>> for Value {
+    fn from(value: &'b ValueRef<'a>) -> Value {
+        value.to_value()
+    }
+}
+
+/// A borrowed `TAG_Int_Array` or `TAG_Long_Array`.
+///
+/// The elements stay encoded in the input and are decoded as they are
+/// iterated over, since their byte order (or VarInt encoding) rarely matches
+/// the host's.
+#[derive(Clone, Copy)]
+pub struct ArrayRef<'a, T> {
+    bytes: &'a [u8],
+    len: usize,
+    endian: Endianness,
+    marker: PhantomData<T>,
+}
+
+impl<'a, T: Element> ArrayRef<'a, T> {
+    /// The number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the elements of the array, decoding them on the fly.
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        let mut reader = RawReader::new(self.bytes, self.endian);
+        (0..self.len).map(move |_| {
+            T::read(&mut reader).expect("array elements are checked when parsed")
+        })
+    }
+
+    /// Decodes the array into a `Vec`.
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, T: Element + fmt::Debug> fmt::Debug for ArrayRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, 'b, T: Element + PartialEq> PartialEq<ArrayRef<'b, T>> for ArrayRef<'a, T> {
+    fn eq(&self, other: &ArrayRef<'b, T>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+/// Element types of `ArrayRef`.
+pub trait Element: Sized {
+    #[doc(hidden)]
+    fn read(src: &mut RawReader<&[u8]>) -> Result<Self>;
+}
+
+impl Element for i32 {
+    fn read(src: &mut RawReader<&[u8]>) -> Result<i32> {
+        src.read_bare_int()
+    }
+}
+
+impl Element for i64 {
+    fn read(src: &mut RawReader<&[u8]>) -> Result<i64> {
+        src.read_bare_long()
+    }
+}
+
+/// Reusable storage for parsing documents without allocating.
+///
+/// `parse()` lays out a whole document in the arena's own buffers, with
+/// strings and arrays pointing into the input as for `ValueRef`, and returns
+/// a handle to its root. The buffers are kept for the next `parse()`, so a
+/// program that scans many chunks, inspects them and moves on allocates
+/// only until the arena has grown to fit the largest of them.
+///
+/// ```rust
+/// use nbt::{Blob, Endianness, ValueArena, ValueRef};
+///
+/// let mut arena = ValueArena::new();
+/// for health in 1..4i8 {
+///     let mut blob = Blob::new();
+///     blob.insert("health", health).unwrap();
+///     let mut bytes = Vec::new();
+///     blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+///
+///     let root = arena.parse(&bytes, Endianness::BigEndian).unwrap();
+///     let value = root.get("health").unwrap().to_value_ref();
+///     assert_eq!(value, ValueRef::Byte(health));
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ValueArena {
+    nodes: Vec<Node>,
+    /// Strings whose Modified UTF-8 encoding differs from UTF-8, decoded.
+    text: String,
+}
+
+/// A value laid out in a `ValueArena`: a List or Compound is followed by
+/// its elements, each followed by its own.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    name: Span,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Span),
+    String(Span),
+    /// `end` is the index of the node after the last element.
+    List { len: usize, end: usize },
+    Compound { len: usize, end: usize },
+    IntArray(Span, usize),
+    LongArray(Span, usize),
+}
+
+/// Bytes of the input, or of the arena's decoded text.
+#[derive(Clone, Copy, Debug, Default)]
+struct Span {
+    start: usize,
+    len: usize,
+    decoded: bool,
+}
+
+impl ValueArena {
+    /// Creates an empty arena, which allocates nothing until it is used.
+    pub fn new() -> ValueArena {
+        ValueArena::default()
+    }
+
+    /// Parses the root compound of NBT data held in `src`, replacing the
+    /// previous document. Its name is kept, as the root's `name()`.
+    pub fn parse<'t>(&'t mut self, src: &'t [u8], endian: Endianness) -> Result<ArenaRef<'t>> {
+        self.nodes.clear();
+        self.text.clear();
+        let mut parser = ArenaParser {
+            parser: Parser { reader: RawReader::new(src, endian), endian },
+            src,
+            arena: self,
+        };
+        match parser.parser.reader.read_bare_byte()? {
+            0x0a => {
+                let name = parser.parser.read_str()?;
+                let name = parser.span_of_str(name);
+                parser.read(0x0a, name)?;
+            },
+            _ => return Err(Error::NoRootCompound),
+        }
+        Ok(ArenaRef { arena: self, src, endian, index: 0 })
+    }
+}
+
+/// A value in a `ValueArena`, borrowing from the arena and the input.
+///
+/// Lists and Compounds are read through `iter()` and `get()`. Other values
+/// are best matched with `to_value_ref()`, which allocates nothing for them.
+#[derive(Clone, Copy)]
+pub struct ArenaRef<'t> {
+    arena: &'t ValueArena,
+    src: &'t [u8],
+    endian: Endianness,
+    index: usize,
+}
+
+impl<'t> ArenaRef<'t> {
+    fn node(&self) -> Node {
+        self.arena.nodes[self.index]
+    }
+
+    fn text(&self, span: Span) -> &'t str {
+        if span.decoded {
+            return &self.arena.text[span.start..span.start + span.len];
+        }
+        let bytes = &self.src[span.start..span.start + span.len];
+        // Only spans of the input that decoded to themselves are kept.
+        unsafe { ::std::str::from_utf8_unchecked(bytes) }
+    }
+
+    fn bytes(&self, span: Span) -> &'t [u8] {
+        &self.src[span.start..span.start + span.len]
+    }
+
+    /// The type ID of this value, as for `ValueRef::id()`.
+    pub fn id(&self) -> i8 {
+        match self.node().kind {
+            Kind::Byte(_)          => 0x01,
+            Kind::Short(_)         => 0x02,
+            Kind::Int(_)           => 0x03,
+            Kind::Long(_)          => 0x04,
+            Kind::Float(_)         => 0x05,
+            Kind::Double(_)        => 0x06,
+            Kind::ByteArray(_)     => 0x07,
+            Kind::String(_)        => 0x08,
+            Kind::List { .. }      => 0x09,
+            Kind::Compound { .. }  => 0x0a,
+            Kind::IntArray(..)     => 0x0b,
+            Kind::LongArray(..)    => 0x0c,
+        }
+    }
+
+    /// The name of this entry of a Compound, or of the root. Elements of a
+    /// List have an empty name.
+    pub fn name(&self) -> &'t str {
+        self.text(self.node().name)
+    }
+
+    /// The number of elements of a List or entries of a Compound, or 0 for
+    /// any other type.
+    pub fn len(&self) -> usize {
+        match self.node().kind {
+            Kind::List { len, .. } | Kind::Compound { len, .. } => len,
+            _ => 0,
+        }
+    }
+
+    /// Whether `len()` is 0.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the elements of a List or the entries of a Compound,
+    /// in the order they were read. Other types have none.
+    pub fn iter(&self) -> ArenaIter<'t> {
+        ArenaIter { next: ArenaRef { index: self.index + 1, ..*self }, remaining: self.len() }
+    }
+
+    /// Looks up an entry of a compound by name. Returns `None` if there is no
+    /// such entry or this is not a compound.
+    pub fn get(&self, name: &str) -> Option<ArenaRef<'t>> {
+        match self.node().kind {
+            Kind::Compound { .. } => self.iter().find(|entry| entry.name() == name),
+            _ => None,
+        }
+    }
+
+    /// Returns the contents of a string, or `None` for any other type.
+    pub fn as_str(&self) -> Option<&'t str> {
+        match self.node().kind {
+            Kind::String(span) => Some(self.text(span)),
+            _ => None,
+        }
+    }
+
+    /// This value as a `ValueRef`. Only Lists and Compounds allocate, for
+    /// the `Vec`s of their elements.
+    pub fn to_value_ref(&self) -> ValueRef<'t> {
+        match self.node().kind {
+            Kind::Byte(v) => ValueRef::Byte(v),
+            Kind::Short(v) => ValueRef::Short(v),
+            Kind::Int(v) => ValueRef::Int(v),
+            Kind::Long(v) => ValueRef::Long(v),
+            Kind::Float(v) => ValueRef::Float(v),
+            Kind::Double(v) => ValueRef::Double(v),
+            Kind::ByteArray(span) => {
+                let bytes = self.bytes(span);
+                // i8 and u8 share size and alignment, so this is a no-op.
+                ValueRef::ByteArray(unsafe {
+                    ::std::slice::from_raw_parts(bytes.as_ptr() as *const i8, bytes.len())
+                })
+            },
+            Kind::String(span) => ValueRef::String(Cow::Borrowed(self.text(span))),
+            Kind::List { .. } => ValueRef::List(self.iter().map(|v| v.to_value_ref()).collect()),
+            Kind::Compound { .. } => ValueRef::Compound(
+                self.iter().map(|v| (Cow::Borrowed(v.name()), v.to_value_ref())).collect()
+            ),
+            Kind::IntArray(span, len) => ValueRef::IntArray(self.array(span, len)),
+            Kind::LongArray(span, len) => ValueRef::LongArray(self.array(span, len)),
+        }
+    }
+
+    fn array<T>(&self, span: Span, len: usize) -> ArrayRef<'t, T> {
+        ArrayRef { bytes: self.bytes(span), len, endian: self.endian, marker: PhantomData }
+    }
+
+    /// Copies this value and everything it borrows into an owned `Value`.
+    pub fn to_value(&self) -> Value {
+        match self.node().kind {
+            Kind::List { .. } => Value::List(self.iter().map(|v| v.to_value()).collect()),
+            Kind::Compound { .. } => Value::Compound(
+                self.iter().map(|v| (v.name().to_string(), v.to_value())).collect()
+            ),
+            _ => self.to_value_ref().to_value(),
+        }
+    }
+}
+
+impl<'t> fmt::Debug for ArenaRef<'t> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_value_ref(), f)
+    }
+}
+
+impl<'t> IntoIterator for ArenaRef<'t> {
+    type Item = ArenaRef<'t>;
+    type IntoIter = ArenaIter<'t>;
+
+    fn into_iter(self) -> ArenaIter<'t> {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of an `ArenaRef`. See `ArenaRef::iter()`.
+#[derive(Clone, Debug)]
+pub struct ArenaIter<'t> {
+    next: ArenaRef<'t>,
+    remaining: usize,
+}
+
+impl<'t> Iterator for ArenaIter<'t> {
+    type Item = ArenaRef<'t>;
+
+    fn next(&mut self) -> Option<ArenaRef<'t>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let item = self.next;
+        self.next.index = match item.node().kind {
+            Kind::List { end, .. } | Kind::Compound { end, .. } => end,
+            _ => item.index + 1,
+        };
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'t> ExactSizeIterator for ArenaIter<'t> {}
+
+/// Lays out a document in a `ValueArena`, parsing its pieces as `Parser`
+/// does.
+struct ArenaParser<'a, 't> {
+    parser: Parser<'a>,
+    src: &'a [u8],
+    arena: &'t mut ValueArena,
+}
+
+impl<'a, 't> ArenaParser<'a, 't> {
+    fn read(&mut self, id: i8, name: Span) -> Result<()> {
+        let index = self.arena.nodes.len();
+        let kind = match id {
+            0x09 | 0x0a => Kind::List { len: 0, end: 0 },
+            id => self.leaf(id)?,
+        };
+        self.arena.nodes.push(Node { name, kind });
+        let kind = match id {
+            0x09 => self.read_list()?,
+            0x0a => self.read_compound()?,
+            _ => return Ok(()),
+        };
+        self.arena.nodes[index].kind = kind;
+        Ok(())
+    }
+
+    fn read_list(&mut self) -> Result<Kind> {
+        self.parser.reader.begin_nested()?;
+        let id = self.parser.reader.read_bare_byte()?;
+        let len = self.parser.reader.read_list_len()?;
+        for _ in 0..len {
+            self.read(id, Span::default())?;
+        }
+        self.parser.reader.end_nested();
+        Ok(Kind::List { len, end: self.arena.nodes.len() })
+    }
+
+    fn read_compound(&mut self) -> Result<Kind> {
+        self.parser.reader.begin_nested()?;
+        let mut len = 0;
+        loop {
+            let id = self.parser.reader.read_bare_byte()?;
+            if id == 0x00 { break; }
+            let name = self.parser.read_str()?;
+            let name = self.span_of_str(name);
+            self.read(id, name)?;
+            len += 1;
+        }
+        self.parser.reader.end_nested();
+        Ok(Kind::Compound { len, end: self.arena.nodes.len() })
+    }
+
+    fn leaf(&mut self, id: i8) -> Result<Kind> {
+        Ok(match self.parser.read_scalar(id)? {
+            ValueRef::Byte(v) => Kind::Byte(v),
+            ValueRef::Short(v) => Kind::Short(v),
+            ValueRef::Int(v) => Kind::Int(v),
+            ValueRef::Long(v) => Kind::Long(v),
+            ValueRef::Float(v) => Kind::Float(v),
+            ValueRef::Double(v) => Kind::Double(v),
+            ValueRef::ByteArray(v) => {
+                let bytes = unsafe { ::std::slice::from_raw_parts(v.as_ptr() as *const u8, v.len()) };
+                Kind::ByteArray(self.span_of(bytes))
+            },
+            ValueRef::String(v) => Kind::String(self.span_of_str(v)),
+            ValueRef::IntArray(v) => Kind::IntArray(self.span_of(v.bytes), v.len),
+            ValueRef::LongArray(v) => Kind::LongArray(self.span_of(v.bytes), v.len),
+            ValueRef::List(_) | ValueRef::Compound(_) => unreachable!("not a scalar"),
+        })
+    }
+
+    /// The span of a piece split off the input.
+    fn span_of(&self, bytes: &'a [u8]) -> Span {
+        if bytes.is_empty() {
+            return Span::default();
+        }
+        let start = bytes.as_ptr() as usize - self.src.as_ptr() as usize;
+        Span { start, len: bytes.len(), decoded: false }
+    }
+
+    /// The span of a string, which is copied into the arena if decoding
+    /// changed it.
+    fn span_of_str(&mut self, text: Cow<'a, str>) -> Span {
+        match text {
+            Cow::Borrowed(text) => self.span_of(text.as_bytes()),
+            Cow::Owned(text) => {
+                let start = self.arena.text.len();
+                self.arena.text.push_str(&text);
+                Span { start, len: text.len(), decoded: true }
+            },
+        }
+    }
+}
+
+/// Parses `ValueRef`s by splitting borrowed pieces off the input.
+struct Parser<'a> {
+    reader: RawReader<&'a [u8]>,
+    endian: Endianness,
+}
+
+impl<'a> Parser<'a> {
+    fn read(&mut self, id: i8) -> Result<ValueRef<'a>> {
+        // As for `Value::from_raw_reader()`, only the recursive cases are
+        // kept here so that each level of nesting needs little stack.
+        match id {
+            0x09 => self.read_list(),
+            0x0a => self.read_compound(),
+            id => self.read_scalar(id),
+        }
+    }
+
+    fn read_scalar(&mut self, id: i8) -> Result<ValueRef<'a>> {
+        match id {
+            0x01 => Ok(ValueRef::Byte(self.reader.read_bare_byte()?)),
+            0x02 => Ok(ValueRef::Short(self.reader.read_bare_short()?)),
+            0x03 => Ok(ValueRef::Int(self.reader.read_bare_int()?)),
+            0x04 => Ok(ValueRef::Long(self.reader.read_bare_long()?)),
+            0x05 => Ok(ValueRef::Float(self.reader.read_bare_float()?)),
+            0x06 => Ok(ValueRef::Double(self.reader.read_bare_double()?)),
+            0x07 => {
+                let len = self.reader.read_array_len()?;
+                let bytes = self.take(len)?;
+                // i8 and u8 share size and alignment, so this is a no-op.
+                let bytes = unsafe {
+                    ::std::slice::from_raw_parts(bytes.as_ptr() as *const i8, bytes.len())
+                };
+                Ok(ValueRef::ByteArray(bytes))
+            },
+            0x08 => Ok(ValueRef::String(self.read_str()?)),
+            0x0b => Ok(ValueRef::IntArray(self.read_array()?)),
+            0x0c => Ok(ValueRef::LongArray(self.read_array()?)),
+            e => Err(Error::InvalidTypeId(e)),
+        }
+    }
+
+    fn read_list(&mut self) -> Result<ValueRef<'a>> {
+        self.reader.begin_nested()?;
+        let id = self.reader.read_bare_byte()?;
+        let len = self.reader.read_list_len()?;
+        let mut buf = Vec::new();
+        for _ in 0..len {
+            buf.push(self.read(id)?);
+        }
+        self.reader.end_nested();
+        Ok(ValueRef::List(buf))
+    }
+
+    fn read_compound(&mut self) -> Result<ValueRef<'a>> {
+        self.reader.begin_nested()?;
+        let mut buf = Vec::new();
+        loop {
+            let id = self.reader.read_bare_byte()?;
+            if id == 0x00 { break; }
+            let name = self.read_str()?;
+            buf.push((name, self.read(id)?));
+        }
+        self.reader.end_nested();
+        Ok(ValueRef::Compound(buf))
+    }
+
+    fn read_str(&mut self) -> Result<Cow<'a, str>> {
+        let len = self.reader.read_string_len()?;
+        let bytes = self.take(len)?;
+        self.reader.decode_str(bytes)
+    }
+
+    /// Finds the extent of an array by decoding it once, which also checks
+    /// that its elements can be decoded again later.
+    fn read_array<T: Element>(&mut self) -> Result<ArrayRef<'a, T>> {
+        let len = self.reader.read_array_len()?;
+        let start: &'a [u8] = self.reader.get_ref();
+        for _ in 0..len {
+            T::read(&mut self.reader)?;
+        }
+        let used = start.len() - self.reader.get_ref().len();
+        Ok(ArrayRef { bytes: &start[..used], len, endian: self.endian, marker: PhantomData })
+    }
+
+    /// Splits the next `len` bytes off the remaining input.
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let rest: &mut &'a [u8] = self.reader.get_mut();
+        if rest.len() < len {
+            return Err(Error::IncompleteNbtValue);
+        }
+        let (head, tail) = rest.split_at(len);
+        *rest = tail;
+        Ok(head)
+    }
+}
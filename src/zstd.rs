@@ -0,0 +1,1119 @@
+//! Zstandard compression, as specified by RFC 8878.
+//!
+//! The decoder handles any frame that does not need a dictionary. The
+//! encoder finds matches much like the one in `lz4`, and writes them with
+//! raw literals and the predefined FSE tables. It compresses less than the
+//! reference encoder, but its frames are accepted by any decoder.
+
+use std::cmp;
+use std::io::{self, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+const MAGIC: u32 = 0xfd2f_b528;
+/// Skippable frames, which hold metadata, use any magic in this range.
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a50;
+const SKIPPABLE_MASK: u32 = 0xffff_fff0;
+
+const MAX_BLOCK_SIZE: usize = 128 * 1024;
+/// Limits the allocation made for a frame's declared content size.
+const PREALLOC_LEN: usize = 1 << 20;
+
+const BLOCK_RAW: u32 = 0;
+const BLOCK_RLE: u32 = 1;
+const BLOCK_COMPRESSED: u32 = 2;
+
+const LITERALS_RAW: u8 = 0;
+const LITERALS_RLE: u8 = 1;
+const LITERALS_COMPRESSED: u8 = 2;
+
+const MODE_PREDEFINED: u8 = 0;
+const MODE_RLE: u8 = 1;
+const MODE_FSE: u8 = 2;
+
+const MAX_HUFFMAN_BITS: u32 = 11;
+
+const MIN_MATCH: usize = 4;
+const HASH_BITS: u32 = 15;
+/// Keeps offset codes within the predefined offset table.
+const MAX_OFFSET: usize = 1 << 27;
+
+/// The baseline and number of extra bits of each literal length code.
+const LITERAL_LENGTHS: [(u32, u32); 36] = [
+    (0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (6, 0), (7, 0),
+    (8, 0), (9, 0), (10, 0), (11, 0), (12, 0), (13, 0), (14, 0), (15, 0),
+    (16, 1), (18, 1), (20, 1), (22, 1), (24, 2), (28, 2), (32, 3), (40, 3),
+    (48, 4), (64, 6), (128, 7), (256, 8), (512, 9), (1024, 10), (2048, 11), (4096, 12),
+    (8192, 13), (16384, 14), (32768, 15), (65536, 16),
+];
+
+/// The baseline and number of extra bits of each match length code.
+const MATCH_LENGTHS: [(u32, u32); 53] = [
+    (3, 0), (4, 0), (5, 0), (6, 0), (7, 0), (8, 0), (9, 0), (10, 0),
+    (11, 0), (12, 0), (13, 0), (14, 0), (15, 0), (16, 0), (17, 0), (18, 0),
+    (19, 0), (20, 0), (21, 0), (22, 0), (23, 0), (24, 0), (25, 0), (26, 0),
+    (27, 0), (28, 0), (29, 0), (30, 0), (31, 0), (32, 0), (33, 0), (34, 0),
+    (35, 1), (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3),
+    (67, 4), (83, 4), (99, 5), (131, 7), (259, 8), (515, 9), (1027, 10), (2051, 11),
+    (4099, 12), (8195, 13), (16387, 14), (32771, 15), (65539, 16),
+];
+
+/// The predefined distributions of literal length, match length and offset
+/// codes, with their accuracy logs.
+const LITERAL_LENGTH_DEFAULT: (&[i16], u32) = (&[
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+], 6);
+const MATCH_LENGTH_DEFAULT: (&[i16], u32) = (&[
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1,
+    -1, -1, -1, -1, -1,
+], 6);
+const OFFSET_DEFAULT: (&[i16], u32) = (&[
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+], 5);
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn corrupt() -> io::Error {
+    invalid("corrupt zstd data")
+}
+
+/// The position of the highest set bit of `n`, which must not be zero.
+fn highest_bit(n: u32) -> u32 {
+    31 - n.leading_zeros()
+}
+
+/// Reads exactly `len` bytes, without trusting `len` for the allocation.
+fn read_exactly<R: Read>(src: &mut R, len: usize, dst: &mut Vec<u8>) -> io::Result<()> {
+    if src.take(len as u64).read_to_end(dst)? != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+/// Reads up to eight bytes starting at `i` as a little-endian integer,
+/// padding with zeros past the end of `data`.
+fn load(data: &[u8], i: usize) -> u64 {
+    let mut value = 0;
+    for (k, &byte) in data.iter().skip(i).take(8).enumerate() {
+        value |= (byte as u64) << (8 * k);
+    }
+    value
+}
+
+/* Decoding */
+
+/// Decompresses a stream of zstd frames from an `io::Read` source.
+///
+/// Each frame is decoded whole before any of it is returned, since matches
+/// may reach anywhere back in the frame.
+pub(crate) struct ZstdDecoder<R> {
+    inner: R,
+    frame: Vec<u8>,
+    pos: usize,
+    started: bool,
+    finished: bool,
+}
+
+impl<R: Read> ZstdDecoder<R> {
+    pub(crate) fn new(inner: R) -> ZstdDecoder<R> {
+        ZstdDecoder { inner, frame: Vec::new(), pos: 0, started: false, finished: false }
+    }
+
+    /// Decodes the next frame into `self.frame`, skipping skippable frames,
+    /// or marks the stream as finished once at least one frame was read.
+    fn read_frame(&mut self) -> io::Result<()> {
+        loop {
+            let mut magic = Vec::with_capacity(4);
+            self.inner.by_ref().take(4).read_to_end(&mut magic)?;
+            match magic.len() {
+                0 if self.started => {
+                    self.finished = true;
+                    return Ok(());
+                },
+                4 => {},
+                _ => return Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+            let magic = u32::from_le_bytes([magic[0], magic[1], magic[2], magic[3]]);
+            if magic & SKIPPABLE_MASK == SKIPPABLE_MAGIC {
+                let len = self.inner.read_u32::<LittleEndian>()? as u64;
+                if io::copy(&mut self.inner.by_ref().take(len), &mut io::sink())? != len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                continue;
+            }
+            if magic != MAGIC {
+                return Err(invalid("invalid zstd magic"));
+            }
+            self.started = true;
+            self.frame.clear();
+            self.pos = 0;
+            return decode_frame(&mut self.inner, &mut self.frame);
+        }
+    }
+}
+
+impl<R: Read> Read for ZstdDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.frame.len() {
+            if self.finished || buf.is_empty() {
+                return Ok(0);
+            }
+            self.read_frame()?;
+        }
+        let n = cmp::min(buf.len(), self.frame.len() - self.pos);
+        buf[..n].copy_from_slice(&self.frame[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Decodes a frame, after its magic number, onto the end of `out`.
+fn decode_frame<R: Read>(src: &mut R, out: &mut Vec<u8>) -> io::Result<()> {
+    let descriptor = src.read_u8()?;
+    let single_segment = descriptor & 0x20 != 0;
+    let has_checksum = descriptor & 0x04 != 0;
+    if descriptor & 0x08 != 0 {
+        return Err(invalid("reserved zstd frame header bit is set"));
+    }
+    if !single_segment {
+        // The window size only bounds how far back matches reach, which
+        // does not matter when the whole frame is kept.
+        src.read_u8()?;
+    }
+    let dictionary = match descriptor & 0x03 {
+        0 => 0,
+        1 => src.read_u8()? as u32,
+        2 => src.read_u16::<LittleEndian>()? as u32,
+        _ => src.read_u32::<LittleEndian>()?,
+    };
+    if dictionary != 0 {
+        return Err(invalid("zstd dictionaries are not supported"));
+    }
+    let content_size = match descriptor >> 6 {
+        0 if single_segment => Some(src.read_u8()? as u64),
+        0 => None,
+        1 => Some(src.read_u16::<LittleEndian>()? as u64 + 256),
+        2 => Some(src.read_u32::<LittleEndian>()? as u64),
+        _ => Some(src.read_u64::<LittleEndian>()?),
+    };
+    if let Some(size) = content_size {
+        out.reserve(cmp::min(size, PREALLOC_LEN as u64) as usize);
+    }
+
+    let mut state = DecoderState::new();
+    let mut block = Vec::new();
+    loop {
+        let header = src.read_u24::<LittleEndian>()?;
+        let size = (header >> 3) as usize;
+        if size > MAX_BLOCK_SIZE {
+            return Err(invalid("zstd block is too large"));
+        }
+        match (header >> 1) & 0x03 {
+            BLOCK_RAW => read_exactly(src, size, out)?,
+            BLOCK_RLE => {
+                let byte = src.read_u8()?;
+                out.resize(out.len() + size, byte);
+            },
+            BLOCK_COMPRESSED => {
+                block.clear();
+                read_exactly(src, size, &mut block)?;
+                state.decode_block(&block, out)?;
+            },
+            _ => return Err(invalid("reserved zstd block type")),
+        }
+        if header & 1 != 0 {
+            break;
+        }
+    }
+
+    if content_size.is_some_and(|size| size != out.len() as u64) {
+        return Err(invalid("zstd frame does not match its content size"));
+    }
+    if has_checksum && src.read_u32::<LittleEndian>()? != xxh64(out, 0) as u32 {
+        return Err(invalid("zstd frame checksum mismatch"));
+    }
+    Ok(())
+}
+
+/// The tables and offsets that compressed blocks may reuse from earlier
+/// blocks of the same frame.
+struct DecoderState {
+    huffman: Option<HuffmanTable>,
+    literal_lengths: Option<FseTable>,
+    offsets: Option<FseTable>,
+    match_lengths: Option<FseTable>,
+    repeats: [usize; 3],
+}
+
+impl DecoderState {
+    fn new() -> DecoderState {
+        DecoderState {
+            huffman: None,
+            literal_lengths: None,
+            offsets: None,
+            match_lengths: None,
+            repeats: [1, 4, 8],
+        }
+    }
+
+    fn decode_block(&mut self, data: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+        let (literals, data) = self.decode_literals(data)?;
+
+        let (count, mut i) = match *data {
+            [] => return Err(corrupt()),
+            [0, ..] => {
+                out.extend_from_slice(&literals);
+                return Ok(());
+            },
+            [n, ..] if n < 128 => (n as usize, 1),
+            [255, low, high, ..] => (low as usize + ((high as usize) << 8) + 0x7f00, 3),
+            [n, low, ..] if n < 255 => ((((n - 128) as usize) << 8) + low as usize, 2),
+            _ => return Err(corrupt()),
+        };
+        let modes = *data.get(i).ok_or_else(corrupt)?;
+        i += 1;
+        if modes & 0x03 != 0 {
+            return Err(invalid("reserved zstd sequence mode bits are set"));
+        }
+        update_table(&mut self.literal_lengths, modes >> 6, data, &mut i, LITERAL_LENGTH_DEFAULT, 9, 35)?;
+        update_table(&mut self.offsets, (modes >> 4) & 0x03, data, &mut i, OFFSET_DEFAULT, 8, 31)?;
+        update_table(&mut self.match_lengths, (modes >> 2) & 0x03, data, &mut i, MATCH_LENGTH_DEFAULT, 9, 52)?;
+
+        let literal_lengths = self.literal_lengths.as_ref().ok_or_else(corrupt)?;
+        let offsets = self.offsets.as_ref().ok_or_else(corrupt)?;
+        let match_lengths = self.match_lengths.as_ref().ok_or_else(corrupt)?;
+
+        let mut bits = BackwardBits::new(data.get(i..).ok_or_else(corrupt)?)?;
+        let mut literal_state = bits.read(literal_lengths.log);
+        let mut offset_state = bits.read(offsets.log);
+        let mut match_state = bits.read(match_lengths.log);
+
+        let start = out.len();
+        let mut pos = 0;
+        for k in 0..count {
+            let offset_code = offsets.entries[offset_state].symbol as u32;
+            let (match_base, match_bits) = MATCH_LENGTHS[match_lengths.entries[match_state].symbol as usize];
+            let (literal_base, literal_bits) = LITERAL_LENGTHS[literal_lengths.entries[literal_state].symbol as usize];
+
+            let offset = (1u64 << offset_code) + bits.read(offset_code) as u64;
+            let match_len = match_base as usize + bits.read(match_bits);
+            let literal_len = literal_base as usize + bits.read(literal_bits);
+            if k + 1 < count {
+                literal_state = literal_lengths.update(literal_state, &mut bits);
+                match_state = match_lengths.update(match_state, &mut bits);
+                offset_state = offsets.update(offset_state, &mut bits);
+            }
+            let offset = resolve_offset(&mut self.repeats, offset, literal_len);
+
+            let literals = literals.get(pos..pos + literal_len).ok_or_else(corrupt)?;
+            if out.len() - start + literal_len + match_len > MAX_BLOCK_SIZE {
+                return Err(corrupt());
+            }
+            out.extend_from_slice(literals);
+            pos += literal_len;
+            if offset == 0 || offset > out.len() {
+                return Err(corrupt());
+            }
+            let from = out.len() - offset;
+            if offset >= match_len {
+                out.extend_from_within(from..from + match_len);
+            } else {
+                // The match overlaps the bytes it produces.
+                for k in 0..match_len {
+                    let byte = out[from + k];
+                    out.push(byte);
+                }
+            }
+        }
+        if !bits.is_empty() {
+            return Err(corrupt());
+        }
+        if out.len() - start + literals.len() - pos > MAX_BLOCK_SIZE {
+            return Err(corrupt());
+        }
+        out.extend_from_slice(&literals[pos..]);
+        Ok(())
+    }
+
+    /// Decodes the literals section of a block, returning the literals and
+    /// the rest of the block.
+    fn decode_literals<'a>(&mut self, data: &'a [u8]) -> io::Result<(Vec<u8>, &'a [u8])> {
+        let first = *data.first().ok_or_else(corrupt)?;
+        let kind = first & 0x03;
+        let format = (first >> 2) & 0x03;
+        if kind == LITERALS_RAW || kind == LITERALS_RLE {
+            let (size, header) = match format {
+                0 | 2 => ((first >> 3) as usize, 1),
+                1 => ((first >> 4) as usize + ((*data.get(1).ok_or_else(corrupt)? as usize) << 4), 2),
+                _ => ((load(data, 0) as usize & 0xff_ffff) >> 4, 3),
+            };
+            if header > data.len() {
+                return Err(corrupt());
+            }
+            return if kind == LITERALS_RAW {
+                let literals = data.get(header..header + size).ok_or_else(corrupt)?;
+                Ok((literals.to_vec(), &data[header + size..]))
+            } else {
+                let byte = *data.get(header).ok_or_else(corrupt)?;
+                Ok((vec![byte; size], &data[header + 1..]))
+            };
+        }
+
+        let (streams, header, bits) = match format {
+            0 => (1, 3, 10),
+            1 => (4, 3, 10),
+            2 => (4, 4, 14),
+            _ => (4, 5, 18),
+        };
+        if header > data.len() {
+            return Err(corrupt());
+        }
+        let value = load(data, 0) & ((1 << (8 * header)) - 1);
+        let size = (value >> 4) as usize & ((1 << bits) - 1);
+        let compressed_size = (value >> (4 + bits)) as usize & ((1 << bits) - 1);
+        if size > MAX_BLOCK_SIZE {
+            return Err(corrupt());
+        }
+        let mut body = data.get(header..header + compressed_size).ok_or_else(corrupt)?;
+        let rest = &data[header + compressed_size..];
+
+        if kind == LITERALS_COMPRESSED {
+            let (table, used) = HuffmanTable::read(body)?;
+            self.huffman = Some(table);
+            body = &body[used..];
+        }
+        let table = self.huffman.as_ref().ok_or_else(corrupt)?;
+        let mut literals = Vec::with_capacity(size);
+        if streams == 1 {
+            table.decode_stream(body, size, &mut literals)?;
+        } else {
+            if body.len() < 6 {
+                return Err(corrupt());
+            }
+            let lens = [
+                body[0] as usize | (body[1] as usize) << 8,
+                body[2] as usize | (body[3] as usize) << 8,
+                body[4] as usize | (body[5] as usize) << 8,
+            ];
+            let segment = size.div_ceil(4);
+            if lens.iter().sum::<usize>() + 6 > body.len() || segment * 3 > size {
+                return Err(corrupt());
+            }
+            let mut at = 6;
+            for &len in &lens {
+                table.decode_stream(&body[at..at + len], segment, &mut literals)?;
+                at += len;
+            }
+            table.decode_stream(&body[at..], size - segment * 3, &mut literals)?;
+        }
+        Ok((literals, rest))
+    }
+}
+
+/// Turns an offset value into an offset, updating the repeat offsets.
+fn resolve_offset(repeats: &mut [usize; 3], value: u64, literal_len: usize) -> usize {
+    if value > 3 {
+        let offset = (value - 3) as usize;
+        *repeats = [offset, repeats[0], repeats[1]];
+        return offset;
+    }
+    // Without literals, the repeat offsets are shifted by one.
+    let index = value as usize - if literal_len == 0 { 0 } else { 1 };
+    let offset = match index {
+        0 => repeats[0],
+        1 => repeats[1],
+        2 => repeats[2],
+        _ => repeats[0].wrapping_sub(1),
+    };
+    match index {
+        0 => {},
+        1 => *repeats = [offset, repeats[0], repeats[2]],
+        _ => *repeats = [offset, repeats[0], repeats[1]],
+    }
+    offset
+}
+
+/// Replaces `table` as given by a sequence compression mode.
+fn update_table(table: &mut Option<FseTable>, mode: u8, data: &[u8], i: &mut usize,
+                default: (&[i16], u32), max_log: u32, max_symbol: usize) -> io::Result<()>
+{
+    match mode {
+        MODE_PREDEFINED => *table = Some(FseTable::new(default.0, default.1)?),
+        MODE_RLE => {
+            let symbol = *data.get(*i).ok_or_else(corrupt)?;
+            if symbol as usize > max_symbol {
+                return Err(corrupt());
+            }
+            *i += 1;
+            *table = Some(FseTable { log: 0, entries: vec![FseEntry { symbol, bits: 0, base: 0 }] });
+        },
+        MODE_FSE => {
+            let (probabilities, log, used) = read_probabilities(&data[*i..], max_log, max_symbol)?;
+            *i += used;
+            *table = Some(FseTable::new(&probabilities, log)?);
+        },
+        _ if table.is_some() => {},
+        _ => return Err(corrupt()),
+    }
+    Ok(())
+}
+
+/// Reads the bits of a backward bitstream, which is written forwards and
+/// read from its end, starting below the highest set bit of its last byte.
+struct BackwardBits<'a> {
+    data: &'a [u8],
+    /// The number of bits left, which goes negative once reads pass the
+    /// start of the stream and return zeros.
+    pos: isize,
+}
+
+impl<'a> BackwardBits<'a> {
+    fn new(data: &'a [u8]) -> io::Result<BackwardBits<'a>> {
+        match data.last() {
+            Some(&last) if last != 0 => Ok(BackwardBits {
+                data,
+                pos: ((data.len() - 1) * 8) as isize + highest_bit(last as u32) as isize,
+            }),
+            _ => Err(corrupt()),
+        }
+    }
+
+    fn peek(&self, n: u32) -> usize {
+        let n = n as isize;
+        if n == 0 || self.pos <= 0 {
+            return 0;
+        }
+        if self.pos >= n {
+            let start = (self.pos - n) as usize;
+            ((load(self.data, start / 8) >> (start % 8)) & ((1 << n) - 1)) as usize
+        } else {
+            ((load(self.data, 0) & ((1 << self.pos) - 1)) << (n - self.pos)) as usize
+        }
+    }
+
+    fn consume(&mut self, n: u32) {
+        self.pos -= n as isize;
+    }
+
+    fn read(&mut self, n: u32) -> usize {
+        let value = self.peek(n);
+        self.consume(n);
+        value
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos == 0
+    }
+
+    fn overflowed(&self) -> bool {
+        self.pos < 0
+    }
+}
+
+#[derive(Clone, Copy)]
+struct FseEntry {
+    symbol: u8,
+    bits: u8,
+    base: u16,
+}
+
+/// A table for decoding symbols coded with finite state entropy.
+struct FseTable {
+    log: u32,
+    entries: Vec<FseEntry>,
+}
+
+impl FseTable {
+    fn new(probabilities: &[i16], log: u32) -> io::Result<FseTable> {
+        let size = 1usize << log;
+        let symbols = spread_symbols(probabilities, log)?;
+        let mut next: Vec<u32> = probabilities.iter().map(|&p| cmp::max(p, 1) as u32).collect();
+        let entries = symbols.iter().map(|&symbol| {
+            let state = next[symbol as usize];
+            next[symbol as usize] += 1;
+            let bits = log - highest_bit(state);
+            FseEntry { symbol, bits: bits as u8, base: ((state << bits) as usize - size) as u16 }
+        }).collect();
+        Ok(FseTable { log, entries })
+    }
+
+    /// Decodes the state that follows `state`.
+    fn update(&self, state: usize, bits: &mut BackwardBits) -> usize {
+        let entry = self.entries[state];
+        entry.base as usize + bits.read(entry.bits as u32)
+    }
+}
+
+/// Assigns each state of an FSE table to a symbol, in proportion to the
+/// symbols' probabilities. Symbols with a probability of -1 ("less than
+/// one") get a single state at the end of the table.
+fn spread_symbols(probabilities: &[i16], log: u32) -> io::Result<Vec<u8>> {
+    let size = 1usize << log;
+    if probabilities.iter().map(|&p| p.unsigned_abs() as usize).sum::<usize>() != size {
+        return Err(corrupt());
+    }
+    let mut symbols = vec![0; size];
+    let mut high = size;
+    for (symbol, &p) in probabilities.iter().enumerate() {
+        if p == -1 {
+            high -= 1;
+            symbols[high] = symbol as u8;
+        }
+    }
+    let step = (size >> 1) + (size >> 3) + 3;
+    let mut pos = 0;
+    for (symbol, &p) in probabilities.iter().enumerate() {
+        for _ in 0..cmp::max(p, 0) {
+            symbols[pos] = symbol as u8;
+            pos = (pos + step) & (size - 1);
+            while pos >= high {
+                pos = (pos + step) & (size - 1);
+            }
+        }
+    }
+    if pos != 0 {
+        return Err(corrupt());
+    }
+    Ok(symbols)
+}
+
+/// Reads an FSE table description, returning the symbols' probabilities,
+/// the accuracy log, and the number of bytes used.
+fn read_probabilities(data: &[u8], max_log: u32, max_symbol: usize) -> io::Result<(Vec<i16>, u32, usize)> {
+    let mut pos = 0;
+    let mut read = |n: u32, consume: u32| {
+        let value = (load(data, pos / 8) >> (pos % 8)) as u32 & ((1 << n) - 1);
+        pos += consume as usize;
+        value
+    };
+    let log = read(4, 4) + 5;
+    if log > max_log {
+        return Err(corrupt());
+    }
+
+    let mut probabilities = Vec::new();
+    let mut remaining = (1i32 << log) + 1;
+    let mut threshold = 1i32 << log;
+    let mut bits = log + 1;
+    while remaining > 1 {
+        if probabilities.len() > max_symbol {
+            return Err(corrupt());
+        }
+        let max = 2 * threshold - 1 - remaining;
+        let value = read(bits, 0) as i32;
+        let mut count = value & (threshold - 1);
+        if count < max {
+            read(0, bits - 1);
+        } else {
+            read(0, bits);
+            count = value & (2 * threshold - 1);
+            if count >= threshold {
+                count -= max;
+            }
+        }
+        // Probabilities are stored plus one, so that -1 can be represented.
+        count -= 1;
+        remaining -= count.abs();
+        if remaining < 1 {
+            return Err(corrupt());
+        }
+        probabilities.push(count as i16);
+        if count == 0 {
+            loop {
+                let repeat = read(2, 2);
+                probabilities.resize(probabilities.len() + repeat as usize, 0);
+                if repeat != 3 {
+                    break;
+                }
+            }
+        }
+        while remaining < threshold {
+            bits -= 1;
+            threshold >>= 1;
+        }
+    }
+    let used = pos.div_ceil(8);
+    if probabilities.len() > max_symbol + 1 || used > data.len() {
+        return Err(corrupt());
+    }
+    Ok((probabilities, log, used))
+}
+
+/// A table for decoding Huffman-coded literals, indexed by the next
+/// `max_bits` bits of a stream.
+struct HuffmanTable {
+    max_bits: u32,
+    entries: Vec<(u8, u8)>,
+}
+
+impl HuffmanTable {
+    /// Reads a Huffman tree description, returning the table and the number
+    /// of bytes used.
+    fn read(data: &[u8]) -> io::Result<(HuffmanTable, usize)> {
+        let header = *data.first().ok_or_else(corrupt)? as usize;
+        let (mut weights, used) = if header < 128 {
+            let compressed = data.get(1..1 + header).ok_or_else(corrupt)?;
+            (decode_weights(compressed)?, 1 + header)
+        } else {
+            let count = header - 127;
+            let bytes = data.get(1..1 + count.div_ceil(2)).ok_or_else(corrupt)?;
+            let weights = (0..count).map(|i| {
+                if i % 2 == 0 { bytes[i / 2] >> 4 } else { bytes[i / 2] & 0x0f }
+            }).collect();
+            (weights, 1 + count.div_ceil(2))
+        };
+
+        // The last weight is implied by the others, completing a power of two.
+        let mut total = 0u32;
+        for &weight in &weights {
+            if weight as u32 > MAX_HUFFMAN_BITS {
+                return Err(corrupt());
+            }
+            if weight > 0 {
+                total += 1 << (weight - 1);
+            }
+        }
+        if total == 0 {
+            return Err(corrupt());
+        }
+        let max_bits = highest_bit(total) + 1;
+        let left = (1 << max_bits) - total;
+        if max_bits > MAX_HUFFMAN_BITS || !left.is_power_of_two() || weights.len() > 255 {
+            return Err(corrupt());
+        }
+        weights.push(highest_bit(left) as u8 + 1);
+
+        // Codes are assigned in order of increasing weight, then symbol.
+        let mut starts = vec![0usize; max_bits as usize + 2];
+        for &weight in &weights {
+            if weight > 0 {
+                starts[weight as usize + 1] += 1 << (weight - 1);
+            }
+        }
+        for w in 1..starts.len() {
+            starts[w] += starts[w - 1];
+        }
+        let mut entries = vec![(0, 0); 1 << max_bits];
+        for (symbol, &weight) in weights.iter().enumerate() {
+            if weight > 0 {
+                let start = starts[weight as usize];
+                let len = 1 << (weight - 1);
+                for entry in &mut entries[start..start + len] {
+                    *entry = (symbol as u8, (max_bits + 1 - weight as u32) as u8);
+                }
+                starts[weight as usize] += len;
+            }
+        }
+        Ok((HuffmanTable { max_bits, entries }, used))
+    }
+
+    /// Decodes `count` literals from a stream that must be used up exactly.
+    fn decode_stream(&self, data: &[u8], count: usize, out: &mut Vec<u8>) -> io::Result<()> {
+        let mut bits = BackwardBits::new(data)?;
+        for _ in 0..count {
+            let (symbol, len) = self.entries[bits.peek(self.max_bits)];
+            out.push(symbol);
+            bits.consume(len as u32);
+        }
+        if !bits.is_empty() {
+            return Err(corrupt());
+        }
+        Ok(())
+    }
+}
+
+/// Decodes FSE-compressed Huffman weights, which use two interleaved states.
+fn decode_weights(data: &[u8]) -> io::Result<Vec<u8>> {
+    let (probabilities, log, used) = read_probabilities(data, 6, 255)?;
+    let table = FseTable::new(&probabilities, log)?;
+    let mut bits = BackwardBits::new(&data[used..])?;
+    let mut states = [bits.read(log), bits.read(log)];
+    let mut weights = Vec::new();
+    for k in 0.. {
+        if weights.len() >= 255 {
+            return Err(corrupt());
+        }
+        let state = &mut states[k % 2];
+        weights.push(table.entries[*state].symbol);
+        *state = table.update(*state, &mut bits);
+        if bits.overflowed() {
+            weights.push(table.entries[states[(k + 1) % 2]].symbol);
+            break;
+        }
+    }
+    Ok(weights)
+}
+
+/* Encoding */
+
+/// Compresses data written to it as a single zstd frame. Data is buffered
+/// until `finish()` is called, so that the frame can record its size.
+pub(crate) struct ZstdEncoder<W> {
+    inner: W,
+    data: Vec<u8>,
+}
+
+impl<W: Write> ZstdEncoder<W> {
+    pub(crate) fn new(inner: W) -> ZstdEncoder<W> {
+        ZstdEncoder { inner, data: Vec::new() }
+    }
+
+    /// Writes the frame, returning the underlying writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        let frame = encode_frame(&self.data);
+        self.inner.write_all(&frame)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ZstdEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes bits forwards, as the decoder reads them backwards.
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    len: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter { out: Vec::new(), acc: 0, len: 0 }
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        self.acc |= (value as u64 & ((1 << bits) - 1)) << self.len;
+        self.len += bits;
+        while self.len >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    /// Adds the end marker and pads the stream to a whole byte.
+    fn finish(mut self) -> Vec<u8> {
+        self.write(1, 1);
+        if self.len > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+/// A table for encoding symbols with finite state entropy.
+struct FseEncoder {
+    log: u32,
+    states: Vec<u16>,
+    /// For each symbol, values used to find the number of bits to write and
+    /// the next state.
+    transforms: Vec<(u32, i32)>,
+}
+
+impl FseEncoder {
+    fn new(probabilities: &[i16], log: u32) -> FseEncoder {
+        let size = 1usize << log;
+        let symbols = spread_symbols(probabilities, log).expect("invalid predefined table");
+        let mut cumulative = vec![0usize];
+        for &p in probabilities {
+            let last = cumulative[cumulative.len() - 1];
+            cumulative.push(last + cmp::max(p, 1) as usize);
+        }
+        let mut states = vec![0u16; size];
+        let mut next = cumulative.clone();
+        for (u, &symbol) in symbols.iter().enumerate() {
+            states[next[symbol as usize]] = (size + u) as u16;
+            next[symbol as usize] += 1;
+        }
+        let transforms = probabilities.iter().zip(&cumulative).map(|(&p, &total)| {
+            if p == 0 {
+                (0, 0)
+            } else if p == -1 || p == 1 {
+                ((log << 16).wrapping_sub(size as u32), total as i32 - 1)
+            } else {
+                let max_bits = log - highest_bit(p as u32 - 1);
+                ((max_bits << 16).wrapping_sub((p as u32) << max_bits), total as i32 - p as i32)
+            }
+        }).collect();
+        FseEncoder { log, states, transforms }
+    }
+
+    /// The state for the last symbol to be encoded, which is decoded first.
+    fn initial_state(&self, symbol: u8) -> u32 {
+        let (delta_bits, delta_state) = self.transforms[symbol as usize];
+        let bits = (delta_bits + (1 << 15)) >> 16;
+        let value = (bits << 16).wrapping_sub(delta_bits);
+        self.states[((value >> bits) as i32 + delta_state) as usize] as u32
+    }
+
+    fn encode(&self, state: &mut u32, symbol: u8, out: &mut BitWriter) {
+        let (delta_bits, delta_state) = self.transforms[symbol as usize];
+        let bits = state.wrapping_add(delta_bits) >> 16;
+        out.write(*state, bits);
+        *state = self.states[((*state >> bits) as i32 + delta_state) as usize] as u32;
+    }
+
+    fn flush(&self, state: u32, out: &mut BitWriter) {
+        out.write(state, self.log);
+    }
+}
+
+/// A sequence's code and extra bits for one of its three values.
+#[derive(Clone, Copy)]
+struct Code {
+    code: u8,
+    extra: u32,
+    bits: u32,
+}
+
+impl Code {
+    fn length(table: &[(u32, u32)], value: u32) -> Code {
+        let code = table.iter().rposition(|&(base, _)| base <= value).unwrap();
+        Code { code: code as u8, extra: value - table[code].0, bits: table[code].1 }
+    }
+
+    fn offset(offset: usize) -> Code {
+        // Values of 1 to 3 refer to repeat offsets, which are not used.
+        let value = offset as u32 + 3;
+        let code = highest_bit(value);
+        Code { code: code as u8, extra: value - (1 << code), bits: code }
+    }
+}
+
+struct Encoder {
+    literal_lengths: FseEncoder,
+    offsets: FseEncoder,
+    match_lengths: FseEncoder,
+    table: Vec<usize>,
+}
+
+fn encode_frame(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.write_u32::<LittleEndian>(MAGIC).unwrap();
+    // A single segment with its size and checksum.
+    let len = data.len() as u64;
+    let size_flag = match len {
+        0..=255 => 0,
+        256..=65791 => 1,
+        _ if len <= u32::MAX as u64 => 2,
+        _ => 3,
+    };
+    out.push(size_flag << 6 | 0x20 | 0x04);
+    match size_flag {
+        0 => out.push(len as u8),
+        1 => out.write_u16::<LittleEndian>((len - 256) as u16).unwrap(),
+        2 => out.write_u32::<LittleEndian>(len as u32).unwrap(),
+        _ => out.write_u64::<LittleEndian>(len).unwrap(),
+    }
+
+    let mut encoder = Encoder {
+        literal_lengths: FseEncoder::new(LITERAL_LENGTH_DEFAULT.0, LITERAL_LENGTH_DEFAULT.1),
+        offsets: FseEncoder::new(OFFSET_DEFAULT.0, OFFSET_DEFAULT.1),
+        match_lengths: FseEncoder::new(MATCH_LENGTH_DEFAULT.0, MATCH_LENGTH_DEFAULT.1),
+        table: vec![0; 1 << HASH_BITS],
+    };
+    let mut start = 0;
+    loop {
+        let end = cmp::min(start + MAX_BLOCK_SIZE, data.len());
+        let last = (end == data.len()) as u32;
+        match encoder.encode_block(data, start, end) {
+            Some(block) => {
+                out.write_u24::<LittleEndian>(last | BLOCK_COMPRESSED << 1 | (block.len() as u32) << 3).unwrap();
+                out.extend_from_slice(&block);
+            },
+            None => {
+                out.write_u24::<LittleEndian>(last | BLOCK_RAW << 1 | ((end - start) as u32) << 3).unwrap();
+                out.extend_from_slice(&data[start..end]);
+            },
+        }
+        if end == data.len() {
+            break;
+        }
+        start = end;
+    }
+
+    out.write_u32::<LittleEndian>(xxh64(data, 0) as u32).unwrap();
+    out
+}
+
+impl Encoder {
+    /// Compresses `data[start..end]`, or returns `None` if that does not
+    /// make it smaller. Matches may reach back before `start`.
+    fn encode_block(&mut self, data: &[u8], start: usize, end: usize) -> Option<Vec<u8>> {
+        let mut literals = Vec::new();
+        let mut sequences = Vec::new();
+        let mut anchor = start;
+        let mut i = start;
+        while i + MIN_MATCH <= end {
+            let seq = read_u32(data, i);
+            let hash = (seq.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize;
+            // Positions are stored plus one, so that zero means none.
+            let candidate = self.table[hash];
+            self.table[hash] = i + 1;
+            if candidate != 0 && i - (candidate - 1) <= MAX_OFFSET
+                && read_u32(data, candidate - 1) == seq
+            {
+                let candidate = candidate - 1;
+                let mut len = MIN_MATCH;
+                while i + len < end && data[candidate + len] == data[i + len] {
+                    len += 1;
+                }
+                literals.extend_from_slice(&data[anchor..i]);
+                sequences.push((i - anchor, i - candidate, len));
+                i += len;
+                anchor = i;
+            } else {
+                i += 1;
+            }
+        }
+        literals.extend_from_slice(&data[anchor..end]);
+
+        let mut out = Vec::new();
+        let n = literals.len();
+        match n {
+            0..=31 => out.push((n << 3) as u8 | LITERALS_RAW),
+            32..=4095 => {
+                out.push(((n & 0x0f) << 4) as u8 | 0x04 | LITERALS_RAW);
+                out.push((n >> 4) as u8);
+            },
+            _ => {
+                out.push(((n & 0x0f) << 4) as u8 | 0x0c | LITERALS_RAW);
+                out.push((n >> 4) as u8);
+                out.push((n >> 12) as u8);
+            },
+        }
+        out.extend_from_slice(&literals);
+
+        let n = sequences.len();
+        match n {
+            0..=127 => out.push(n as u8),
+            128..=0x7eff => {
+                out.push((n >> 8) as u8 + 128);
+                out.push(n as u8);
+            },
+            _ => {
+                out.push(255);
+                out.write_u16::<LittleEndian>((n - 0x7f00) as u16).unwrap();
+            },
+        }
+        if n > 0 {
+            out.push(MODE_PREDEFINED << 6 | MODE_PREDEFINED << 4 | MODE_PREDEFINED << 2);
+            out.extend_from_slice(&self.encode_sequences(&sequences));
+        }
+
+        if out.len() < end - start { Some(out) } else { None }
+    }
+
+    /// Encodes `(literal length, offset, match length)` sequences. They are
+    /// written in reverse, so that the decoder reads them in order.
+    fn encode_sequences(&self, sequences: &[(usize, usize, usize)]) -> Vec<u8> {
+        let codes: Vec<_> = sequences.iter().map(|&(literal_len, offset, match_len)| (
+            Code::length(&LITERAL_LENGTHS, literal_len as u32),
+            Code::offset(offset),
+            Code::length(&MATCH_LENGTHS, match_len as u32),
+        )).collect();
+        let mut out = BitWriter::new();
+        let write_extra = |out: &mut BitWriter, &(literal, offset, matched): &(Code, Code, Code)| {
+            out.write(literal.extra, literal.bits);
+            out.write(matched.extra, matched.bits);
+            out.write(offset.extra, offset.bits);
+        };
+
+        let last = &codes[codes.len() - 1];
+        let mut match_state = self.match_lengths.initial_state(last.2.code);
+        let mut offset_state = self.offsets.initial_state(last.1.code);
+        let mut literal_state = self.literal_lengths.initial_state(last.0.code);
+        write_extra(&mut out, last);
+        for codes in codes[..codes.len() - 1].iter().rev() {
+            self.offsets.encode(&mut offset_state, codes.1.code, &mut out);
+            self.match_lengths.encode(&mut match_state, codes.2.code, &mut out);
+            self.literal_lengths.encode(&mut literal_state, codes.0.code, &mut out);
+            write_extra(&mut out, codes);
+        }
+        self.match_lengths.flush(match_state, &mut out);
+        self.offsets.flush(offset_state, &mut out);
+        self.literal_lengths.flush(literal_state, &mut out);
+        out.finish()
+    }
+}
+
+fn read_u32(data: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+}
+
+const PRIME_1: u64 = 11_400_714_785_074_694_791;
+const PRIME_2: u64 = 14_029_467_366_897_019_727;
+const PRIME_3: u64 = 1_609_587_929_392_839_161;
+const PRIME_4: u64 = 9_650_029_242_287_828_579;
+const PRIME_5: u64 = 2_870_177_450_012_600_261;
+
+/// The 64-bit xxHash of `data`, whose lower half checksums a frame.
+fn xxh64(data: &[u8], seed: u64) -> u64 {
+    fn round(acc: u64, input: u64) -> u64 {
+        acc.wrapping_add(input.wrapping_mul(PRIME_2)).rotate_left(31).wrapping_mul(PRIME_1)
+    }
+    fn merge(hash: u64, lane: u64) -> u64 {
+        (hash ^ round(0, lane)).wrapping_mul(PRIME_1).wrapping_add(PRIME_4)
+    }
+
+    let mut i = 0;
+    let mut hash = if data.len() >= 32 {
+        let mut lanes = [
+            seed.wrapping_add(PRIME_1).wrapping_add(PRIME_2),
+            seed.wrapping_add(PRIME_2),
+            seed,
+            seed.wrapping_sub(PRIME_1),
+        ];
+        while i + 32 <= data.len() {
+            for lane in lanes.iter_mut() {
+                *lane = round(*lane, load(data, i));
+                i += 8;
+            }
+        }
+        let hash = lanes[0].rotate_left(1)
+            .wrapping_add(lanes[1].rotate_left(7))
+            .wrapping_add(lanes[2].rotate_left(12))
+            .wrapping_add(lanes[3].rotate_left(18));
+        lanes.iter().fold(hash, |hash, &lane| merge(hash, lane))
+    } else {
+        seed.wrapping_add(PRIME_5)
+    };
+
+    hash = hash.wrapping_add(data.len() as u64);
+    while i + 8 <= data.len() {
+        hash ^= round(0, load(data, i));
+        hash = hash.rotate_left(27).wrapping_mul(PRIME_1).wrapping_add(PRIME_4);
+        i += 8;
+    }
+    if i + 4 <= data.len() {
+        hash ^= (load(data, i) & 0xffff_ffff).wrapping_mul(PRIME_1);
+        hash = hash.rotate_left(23).wrapping_mul(PRIME_2).wrapping_add(PRIME_3);
+        i += 4;
+    }
+    for &byte in &data[i..] {
+        hash ^= (byte as u64).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
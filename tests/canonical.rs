@@ -0,0 +1,77 @@
+//! Tests for canonical content hashes.
+#![cfg(feature = "std")]
+
+#[macro_use]
+extern crate nbt;
+
+use std::collections::{HashMap, HashSet};
+
+use nbt::{Blob, Endianness, Value};
+
+#[test]
+fn hash_is_stable() {
+    // The canonical form is part of the API: this must never change.
+    assert_eq!(nbt!({ a: 1i8, b: [1i32, 2i32] }).canonical_hash(), 14868878696537870820);
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn hash_ignores_order_and_encoding() {
+    let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::LittleEndian).unwrap();
+    let reread = Blob::from_reader(&mut &bytes[..], Endianness::LittleEndian).unwrap();
+    assert_eq!(reread.canonical_hash(), blob.canonical_hash());
+
+    let mut forward = Blob::new();
+    let mut backward = Blob::new();
+    let keys: Vec<String> = (0..32).map(|i| format!("key{}", i)).collect();
+    for key in &keys {
+        forward.insert(key.as_str(), 1i8).unwrap();
+    }
+    for key in keys.iter().rev() {
+        backward.insert(key.as_str(), 1i8).unwrap();
+    }
+    assert_eq!(forward.canonical_hash(), backward.canonical_hash());
+}
+
+#[test]
+fn hash_distinguishes_content() {
+    assert_ne!(nbt!(1i8).canonical_hash(), nbt!(1i16).canonical_hash());
+    assert_ne!(nbt!({ a: 1i8 }).canonical_hash(), nbt!({ b: 1i8 }).canonical_hash());
+    assert_ne!(nbt!(0.0f32).canonical_hash(), nbt!(-0.0f32).canonical_hash());
+    assert_ne!(nbt!([B; 1]).canonical_hash(), nbt!([1i8]).canonical_hash());
+    assert_ne!(blob!("a", {}).canonical_hash(), blob!("b", {}).canonical_hash());
+}
+
+#[test]
+fn hash_normalizes_nans_and_empty_lists() {
+    let nan = f64::from_bits(0x7ff8_0000_dead_beef);
+    assert_eq!(Value::Double(nan).canonical_hash(), Value::Double(f64::NAN).canonical_hash());
+    assert_eq!(Value::Float(-f32::NAN).canonical_hash(), Value::Float(f32::NAN).canonical_hash());
+
+    // An empty List of Compounds, as written by Minecraft.
+    let bytes = [0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, b'l', 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let read = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read.canonical_hash(), blob!({ l: [] }).canonical_hash());
+}
+
+#[test]
+fn values_as_keys() {
+    assert_eq!(nbt!(f64::NAN), nbt!(f64::NAN));
+    assert_ne!(nbt!(0.0f32), nbt!(-0.0f32));
+    assert_ne!(Value::Double(f64::from_bits(0x7ff8_0000_dead_beef)), Value::Double(f64::NAN));
+
+    let forward: Value = (0..32).map(|i| (format!("key{}", i), Value::Int(i))).collect::<Blob>().into();
+    let backward: Value = (0..32).rev().map(|i| (format!("key{}", i), Value::Int(i))).collect::<Blob>().into();
+    let mut indices = HashMap::new();
+    indices.insert(forward, 0);
+    assert_eq!(indices.get(&backward), Some(&0));
+
+    let states = vec![nbt!({ Name: "minecraft:air" }), nbt!({ Name: "minecraft:air" }), nbt!({ Name: "minecraft:dirt" })];
+    assert_eq!(states.into_iter().collect::<HashSet<_>>().len(), 2);
+    let blobs: HashSet<Blob> = vec![blob!({ a: 1i8 }), blob!({ a: 1i8 }), blob!("named", { a: 1i8 })].into_iter().collect();
+    assert_eq!(blobs.len(), 2);
+}
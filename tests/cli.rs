@@ -0,0 +1,62 @@
+//! Tests for the `nbt-cli` tool.
+#![cfg(feature = "cli")]
+
+extern crate nbt;
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use nbt::{Blob, Endianness, Value};
+
+fn run(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_nbt-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn conversions() {
+    let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+
+    let dump = run(&["dump", "tests/complex_player.dat"], b"");
+    assert!(dump.status.success());
+    assert!(String::from_utf8(dump.stdout).unwrap().contains("minecraft:diamond_sword"));
+
+    let short = run(&["dump", "--max-depth", "1", "tests/complex_player.dat"], b"");
+    assert!(String::from_utf8(short.stdout).unwrap().contains("TAG_List(\"Inventory\"): 25 entries of type TAG_Compound {...}"));
+
+    // To Bedrock's byte order and back, through standard input and output.
+    let le = run(&["convert", "--out-endian", "le", "tests/complex_player.dat"], b"");
+    assert!(le.status.success());
+    assert_eq!(Blob::from_slice(&le.stdout, Endianness::LittleEndian).unwrap(), player);
+    let gzip = run(&["convert", "--endian", "le", "--out-endian", "be", "--compression", "gzip", "-"], &le.stdout);
+    assert_eq!(Blob::from_gzip_reader(&mut &gzip.stdout[..], Endianness::BigEndian).unwrap(), player);
+
+    let snbt = run(&["to-snbt", "--compact", "tests/complex_player.dat"], b"");
+    let nbt = run(&["from-snbt", "-"], &snbt.stdout);
+    assert_eq!(Blob::from_slice(&nbt.stdout, Endianness::BigEndian).unwrap(), player);
+
+    let json = run(&["from-json", "-"], br#"{"a": [1, 2], "b": "text"}"#);
+    let blob = Blob::from_slice(&json.stdout, Endianness::BigEndian).unwrap();
+    assert_eq!(blob["a"], Value::List(vec![Value::Int(1), Value::Int(2)]));
+    let back = run(&["to-json", "--compact", "-"], &json.stdout);
+    assert_eq!(String::from_utf8(back.stdout).unwrap(), "{\"a\":[1,2],\"b\":\"text\"}\n");
+}
+
+#[test]
+fn failures() {
+    let usage = run(&["dump"], b"");
+    assert_eq!(usage.status.code(), Some(2));
+    assert!(String::from_utf8(usage.stderr).unwrap().starts_with("error: missing input"));
+    assert_eq!(run(&["frobnicate", "-"], b"").status.code(), Some(2));
+    assert_eq!(run(&["dump", "--endian", "middle", "-"], b"").status.code(), Some(2));
+    assert_eq!(run(&["dump", "--max-depth", "x", "-"], b"").status.code(), Some(2));
+    assert_eq!(run(&["dump", "-"], b"\x0a\x00").status.code(), Some(1));
+    assert!(run(&["help"], b"").status.success());
+}
@@ -0,0 +1,57 @@
+//! Tests for the structs generated from sample files.
+#![cfg(all(feature = "codegen", feature = "serde"))]
+
+#[macro_use] extern crate nbt;
+#[macro_use] extern crate serde_derive;
+
+use nbt::codegen::Generator;
+use nbt::{Blob, Endianness};
+
+mod player {
+    #![allow(dead_code)]
+    extern crate nbt;
+    include!("codegen_player.rs.in");
+}
+
+#[test]
+fn generated_structs_read_their_sample() {
+    let blob = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    let mut generator = Generator::new("Player");
+    generator.add_blob(&blob);
+    assert_eq!(generator.generate(), include_str!("codegen_player.rs.in"));
+
+    let bytes = blob.to_vec(Endianness::BigEndian).unwrap();
+    let player: player::Player = nbt::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(player.inventory[0].id, "minecraft:diamond_sword");
+    assert_eq!(player.inventory[2].tag, None);
+    let mut written = Vec::new();
+    nbt::to_writer(&mut written, &player, None, Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_slice(&written, Endianness::BigEndian).unwrap(), blob);
+}
+
+#[test]
+fn merge_samples() {
+    let mut generator = Generator::new("Entity");
+    generator.add_value(&nbt!({ id: "pig", UUID: [I; 1, 2, 3, 4], Age: 5i32, type: 1i8, "2d": 0i8 }));
+    generator.add_value(&nbt!({ id: "cow", Age: 6i16, type: 2i8, Passengers: [[1i8]], "2d": 1i8 }));
+    assert_eq!(generator.generate(), r#"#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    #[serde(rename = "2d")]
+    pub field_2d: i8,
+    #[serde(rename = "Age")]
+    pub age: nbt::Value,
+    #[serde(rename = "Passengers", default, skip_serializing_if = "Option::is_none")]
+    pub passengers: Option<Vec<Vec<i8>>>,
+    #[serde(rename = "UUID", default, skip_serializing_if = "Vec::is_empty", with = "nbt::int_array")]
+    pub uuid: Vec<i32>,
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: i8,
+}
+"#);
+
+    let mut generator = Generator::new("Heights");
+    generator.add_value(&nbt!([[I; 1], [I; 2]]));
+    assert_eq!(generator.generate(), "pub type Heights = Vec<nbt::Value>;\n");
+    assert!(Generator::new("Empty").generate().starts_with("#[derive"));
+}
@@ -0,0 +1,18 @@
+//! Fixtures shared by the compression tests.
+
+use nbt::{Blob, Value};
+
+/// A Blob of about `scale` times 450 KB, with both repetitive and
+/// incompressible stretches, so that it spans several compression blocks.
+pub fn large_blob(scale: usize) -> Blob {
+    let mut state = 1u32;
+    let noise: Vec<i8> = (0..100_000 * scale).map(|_| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (state >> 24) as i8
+    }).collect();
+    let mut blob = Blob::new();
+    blob.insert("noise", Value::ByteArray(noise)).unwrap();
+    blob.insert("zeros", Value::ByteArray(vec![0; 150_000 * scale])).unwrap();
+    blob.insert("pattern", Value::IntArray((0..50_000 * scale as i32).map(|i| i % 7).collect())).unwrap();
+    blob
+}
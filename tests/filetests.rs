@@ -1,6 +1,7 @@
 //! Crate for testing whether the deserialize codegen is capable of handling the
 //! sample NBT files in the test/ directory, which include real
 //! Minecraft-generated files.
+#![cfg(feature = "serde")]
 
 #[macro_use] extern crate serde_derive;
 extern crate serde;
@@ -9,7 +10,9 @@ extern crate nbt;
 
 use std::fs::File;
 
-use nbt::de::{from_reader, from_gzip_reader};
+use nbt::de::from_reader;
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+use nbt::de::from_gzip_reader;
 use nbt::Endianness;
 
 // Include structure definitions.
@@ -58,6 +61,7 @@ fn deserialize_small4() {
     assert_eq!(nbt, read)
 }
 
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 #[test]
 #[ignore]
 fn deserialize_big1() {
@@ -166,20 +170,40 @@ fn deserialize_big1() {
     assert_eq!(nbt, read)
 }
 
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 #[test]
 fn deserialize_simple_player() {
     let mut file = File::open("tests/simple_player.dat").unwrap();
     let _: PlayerData = from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
 }
 
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 #[test]
 fn deserialize_complex_player() {
     let mut file = File::open("tests/complex_player.dat").unwrap();
     let _: PlayerData = from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
 }
 
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
 #[test]
 fn deserialize_level() {
     let mut file = File::open("tests/level.dat").unwrap();
     let _: Level = from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
 }
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn deserialize_level_with_interned_keys() {
+    use serde::Deserialize;
+
+    let mut file = File::open("tests/level.dat").unwrap();
+    let mut bytes = Vec::new();
+    nbt::Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap()
+        .to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    let mut decoder = nbt::de::Decoder::new(&bytes[..], Endianness::BigEndian);
+    decoder.set_intern_keys(true);
+    let interned = Level::deserialize(&mut decoder).unwrap();
+    let plain: Level = from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(nbt::to_value(&interned).unwrap(), nbt::to_value(&plain).unwrap());
+}
@@ -0,0 +1,124 @@
+//! Tests for converting between `Value`s and JSON.
+#![cfg(feature = "json")]
+
+#[macro_use] extern crate nbt;
+#[macro_use] extern crate serde_json;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use nbt::json::{self, ArrayPolicy, JsonOptions};
+use nbt::{Blob, Endianness, Error, Value};
+
+#[test]
+fn values_to_json() {
+    let value = nbt!({
+        "b": 1i8, "s": -2i16, "i": 3, "l": 9_007_199_254_740_993i64,
+        "f": 0.1f32, "d": 0.1, "nan": f64::NAN,
+        "bytes": [B; 1, -1], "ints": [I; 2], "longs": [L; 3],
+        "str": "text", "list": [[1i8], []], "compound": {},
+    });
+    assert_eq!(json::to_json(&value, &JsonOptions::new()), json!({
+        "b": 1, "s": -2, "i": 3, "l": 9_007_199_254_740_993i64,
+        "f": 0.1, "d": 0.1, "nan": null,
+        "bytes": [1, -1], "ints": [2], "longs": [3],
+        "str": "text", "list": [[1], []], "compound": {},
+    }));
+    let strings = json::to_json(&value, &JsonOptions::new().longs_as_strings(true));
+    assert_eq!((&strings["l"], &strings["longs"]), (&json!("9007199254740993"), &json!(["3"])));
+    assert_eq!(serde_json::Value::from(&value), json::to_json(&value, &JsonOptions::new()));
+}
+
+#[test]
+fn json_to_values() {
+    let options = JsonOptions::new();
+    let json = json!({
+        "int": 5, "long": 3_000_000_000i64, "double": 1.5, "bool": true, "str": "x",
+        "ints": [1, 2], "widened": [1, 3_000_000_000i64], "doubles": [1, 2.5],
+        "nested": [{ "a": [] }], "empty": [],
+    });
+    assert_eq!(json::from_json(&json, &options).unwrap(), nbt!({
+        "int": 5, "long": 3_000_000_000i64, "double": 1.5, "bool": 1i8, "str": "x",
+        "ints": [1, 2], "widened": [1i64, 3_000_000_000i64], "doubles": [1.0, 2.5],
+        "nested": [{ "a": [] }], "empty": [],
+    }));
+
+    let arrays = JsonOptions::new().arrays(ArrayPolicy::IntArrays);
+    assert_eq!(json::from_json(&json!([1, 2]), &arrays).unwrap(), Value::IntArray(vec![1, 2]));
+    assert_eq!(json::from_json(&json!([1, 3_000_000_000i64]), &arrays).unwrap(), Value::LongArray(vec![1, 3_000_000_000]));
+    assert_eq!(json::from_json(&json!([1.5]), &arrays).unwrap(), nbt!([1.5]));
+    assert_eq!(json::from_json(&json!([]), &arrays).unwrap(), Value::List(vec![]));
+
+    assert_eq!(json::from_json(&json!(null), &options), Err(Error::UnrepresentableType("null")));
+    assert_eq!(json::from_json(&json!(["a", 1]), &options), Err(Error::HeterogeneousList));
+    assert_eq!(Value::try_from(json!({ "a": [null] })), Err(Error::UnrepresentableType("null")));
+}
+
+#[test]
+fn round_trip_with_template() {
+    let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap().into_value();
+    let mut value = player.clone();
+    if let Value::Compound(ref mut map) = value {
+        map.insert("Longs".to_string(), Value::LongArray(vec![i64::MIN, i64::MAX]));
+        map.insert("Bytes".to_string(), Value::ByteArray(vec![-128, 127]));
+        map.insert("Float".to_string(), Value::Float(0.1));
+    }
+    for options in &[JsonOptions::new(), JsonOptions::new().longs_as_strings(true)] {
+        let text = json::to_json(&value, options).to_string();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(json::from_json_like(&parsed, &value, options).unwrap(), value);
+    }
+
+    // Values that do not fit the template are read as without one.
+    let template = nbt!({ "b": 0i8, "list": [0i16], "bytes": [B;] });
+    let json = json!({ "b": 300, "list": [1, 2], "bytes": [1, 1000], "new": 1.0 });
+    assert_eq!(json::from_json_like(&json, &template, &JsonOptions::new()).unwrap(), nbt!({
+        "b": 300, "list": [1i16, 2i16], "bytes": [1, 1000], "new": 1.0,
+    }));
+}
+
+#[test]
+fn stream_nbt_to_json() {
+    let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    for &endian in &[Endianness::BigEndian, Endianness::NetworkLittleEndian] {
+        let bytes = player.to_vec(endian).unwrap();
+        for options in &[JsonOptions::new(), JsonOptions::new().longs_as_strings(true)] {
+            let mut text = Vec::new();
+            json::nbt_to_json_writer(&bytes[..], &mut text, endian, options).unwrap();
+            let parsed: serde_json::Value = serde_json::from_slice(&text).unwrap();
+            assert_eq!(parsed, json::to_json(&player.clone().into_value(), options));
+        }
+    }
+    let err = json::nbt_to_json_writer(&[0x0a, 0x00][..], &mut Vec::new(), Endianness::BigEndian, &JsonOptions::new());
+    assert_eq!(err, Err(Error::IncompleteNbtValue));
+}
+
+#[test]
+fn stream_json_to_nbt() {
+    let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap().into_value();
+    let text = json::to_json(&player, &JsonOptions::new()).to_string();
+    let expected = json::from_json(&serde_json::from_str(&text).unwrap(), &JsonOptions::new()).unwrap();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        // Writing starts at the destination's current position.
+        let mut dst = Cursor::new(vec![0xff; 3]);
+        dst.set_position(3);
+        json::json_to_nbt_writer(text.as_bytes(), &mut dst, endian).unwrap();
+        let blob = Blob::from_slice(&dst.get_ref()[3..], endian).unwrap();
+        assert_eq!(blob.into_value(), expected);
+    }
+
+    let mut dst = Cursor::new(Vec::new());
+    json::json_to_nbt_writer(&br#"{"empty": [], "nested": [[], [{}]]}"#[..], &mut dst, Endianness::BigEndian).unwrap();
+    let blob = Blob::from_slice(dst.get_ref(), Endianness::BigEndian).unwrap();
+    assert_eq!(blob.into_value(), nbt!({ "empty": [], "nested": [[], [{}]] }));
+
+    let convert = |text: &str| json::json_to_nbt_writer(text.as_bytes(), Cursor::new(Vec::new()), Endianness::BigEndian);
+    assert_eq!(convert(r#"{"a": [1, "x"]}"#), Err(Error::HeterogeneousList));
+    assert_eq!(convert(r#"{"a": [1, 3000000000]}"#), Err(Error::HeterogeneousList));
+    assert_eq!(convert(r#"{"a": {"b": null}}"#), Err(Error::UnrepresentableType("null")));
+    assert_eq!(convert(r#"[1]"#), Err(Error::NoRootCompound));
+    match convert(r#"{"a": 1} 2"#) {
+        Err(Error::Serde(msg)) => assert!(msg.contains("trailing characters"), "{}", msg),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
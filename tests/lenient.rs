@@ -0,0 +1,108 @@
+//! Tests for salvaging damaged input with the lenient readers.
+#![cfg(feature = "std")]
+
+#[macro_use] extern crate nbt;
+
+use nbt::raw::{Endianness, RawReader};
+use nbt::{Blob, Error, Value};
+
+fn write(blob: &Blob) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    bytes
+}
+
+/// Replaces the first occurrence of `from` in `bytes` with `to`, which must
+/// be as long.
+fn corrupt(bytes: &mut [u8], from: &[u8], to: &[u8]) {
+    let at = bytes.windows(from.len()).position(|w| w == from).unwrap();
+    bytes[at..at + to.len()].copy_from_slice(to);
+}
+
+#[test]
+fn invalid_strings_are_skipped() {
+    let mut blob = Blob::new();
+    blob.insert("good", 1i8).unwrap();
+    blob.insert("bad!", nbt!({ "inner": [1, 2, 3] })).unwrap();
+    blob.insert("names", nbt!(["ok", "xx!!", "fine"])).unwrap();
+    blob.insert("text", "yy!!").unwrap();
+    let mut bytes = write(&blob);
+    corrupt(&mut bytes, b"bad!", &[b'b', b'a', b'd', 0xff]);
+    corrupt(&mut bytes, b"xx!!", &[0xc3, 0x28, 0x00, 0x00]);
+    corrupt(&mut bytes, b"yy!!", &[0xed, 0xa0, 0x00, 0x00]);
+
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).map_err(Error::without_location), Err(Error::InvalidUtf8));
+
+    let mut src = &bytes[..];
+    let (read, warnings) = Blob::from_reader_lenient(&mut src, Endianness::BigEndian).unwrap();
+    assert!(src.is_empty());
+    let mut expected = Blob::new();
+    expected.insert("good", 1i8).unwrap();
+    expected.insert("names", nbt!(["ok", "fine"])).unwrap();
+    assert_eq!(read, expected);
+
+    let mut paths: Vec<_> = warnings.iter().map(|w| w.path().unwrap()).collect();
+    paths.sort();
+    assert_eq!(paths, ["", "names[1]", "text"]);
+    let warnings: Vec<_> = warnings.into_iter().map(Error::without_location).collect();
+    assert_eq!(warnings, [Error::InvalidUtf8, Error::InvalidUtf8, Error::InvalidUtf8]);
+}
+
+#[test]
+fn unrecoverable_problems_keep_what_was_read() {
+    let mut blob = Blob::new();
+    blob.insert("Level", nbt!({
+        "Sections": [
+            { "Blocks": [B; 1, 2, 3] },
+            { "Blocks": [B; 4, 5, 6] },
+        ],
+    })).unwrap();
+    let bytes = write(&blob);
+
+    // Truncated partway through the second section's array.
+    let len = bytes.len() - 6;
+    let (read, warnings) = Blob::from_reader_lenient(&mut &bytes[..len], Endianness::BigEndian).unwrap();
+    assert_eq!(warnings[0].path(), Some("Level.Sections[1].Blocks"));
+    let warnings: Vec<_> = warnings.into_iter().map(Error::without_location).collect();
+    assert_eq!(warnings, [Error::IncompleteNbtValue]);
+    match read.get_path("Level.Sections") {
+        Some(Value::List(sections)) => {
+            assert_eq!(sections.len(), 2);
+            assert_eq!(sections[0], nbt!({ "Blocks": [B; 1, 2, 3] }));
+            assert_eq!(sections[1], Value::Compound(Default::default()));
+        },
+        other => panic!("unexpected sections {:?}", other),
+    }
+
+    // An unknown type ID, whose payload cannot be skipped.
+    let bytes = [0x0a, 0x00, 0x00, 0x01, 0x00, 0x01, b'a', 0x05, 0x0f, 0x00, 0x01, b'b', 0x00];
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    let (read, warnings) = Blob::from_raw_reader_lenient(&mut src).unwrap();
+    assert_eq!(read["a"], Value::Byte(5));
+    assert_eq!(warnings, [Error::Located(12, "b".to_string(), Box::new(Error::InvalidTypeId(0x0f)))]);
+    assert_eq!(src.depth(), 0);
+}
+
+#[test]
+fn nothing_to_salvage() {
+    let bytes = [0x0a, 0x00];
+    assert_eq!(Blob::from_reader_lenient(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), Some(Error::IncompleteNbtValue));
+    let bytes = [0x01, 0x00, 0x00, 0x05];
+    assert_eq!(Blob::from_reader_lenient(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), Some(Error::NoRootCompound));
+
+    let mut src = RawReader::new(&[0x00, 0x01, 0xff][..], Endianness::BigEndian);
+    assert_eq!(Value::from_raw_reader_lenient(0x08, &mut src).err().map(Error::without_location), Some(Error::InvalidUtf8));
+    let mut src = RawReader::new(&[][..], Endianness::BigEndian);
+    assert_eq!(Value::from_raw_reader_lenient(0x09, &mut src).err().map(Error::without_location), Some(Error::IncompleteNbtValue));
+}
+
+#[test]
+fn intact_input_has_no_warnings() {
+    let mut blob = Blob::new();
+    blob.insert("list", nbt!([[1i8], [2i8, 3i8]])).unwrap();
+    blob.insert("compound", nbt!({ "s": "\u{1f600}" })).unwrap();
+    let bytes = write(&blob);
+    let (read, warnings) = Blob::from_reader_lenient(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read, blob);
+    assert!(warnings.is_empty());
+}
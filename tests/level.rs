@@ -0,0 +1,89 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use std::fs::File;
+
+use nbt::formats::level::Level;
+use nbt::{Blob, Endianness, Value};
+
+#[test]
+fn legacy_level() {
+    let level = Level::from_gzip_reader(File::open("tests/level.dat").unwrap()).unwrap();
+    let data = &level.data;
+    assert_eq!(data.level_name, "1.0.0");
+    assert_eq!(data.version, 19133);
+    assert_eq!(data.random_seed, Some(3274077273284000072));
+    assert_eq!(data.game_rules["randomTickSpeed"], "3");
+    assert_eq!(data.data_version, None);
+    assert_eq!(data.world_gen_settings, None);
+    assert_eq!(data.player.as_ref().unwrap().xp_level, 51);
+}
+
+#[test]
+fn edits_keep_unknown_keys() {
+    let mut src = File::open("tests/level.dat").unwrap();
+    let original = Value::from(Blob::from_gzip_reader(&mut src, Endianness::BigEndian).unwrap());
+    let mut level: Level = nbt::from_value(original.clone()).unwrap();
+
+    // Only the Short health of this old file changes, as it is written
+    // with a Float.
+    let mut file = original.clone();
+    level.write_into(&mut file).unwrap();
+    let mut expected = original.clone();
+    expected["Data"]["Player"]["Health"] = Value::Float(20.0);
+    assert_eq!(file, expected);
+
+    level.data.level_name = "Renamed".to_string();
+    level.data.game_rules.insert("keepInventory".to_string(), "true".to_string());
+    level.write_into(&mut file).unwrap();
+    let data = &file["Data"];
+    assert_eq!(data["LevelName"], Value::from("Renamed"));
+    assert_eq!(data["GameRules"]["keepInventory"], Value::from("true"));
+    assert_eq!(data["BorderSize"], original["Data"]["BorderSize"]);
+}
+
+#[test]
+fn modern_level() {
+    let file = nbt!({
+        Data: {
+            DataVersion: 3700i32,
+            Version: { Id: 3700i32, Name: "1.20.4", Series: "main", Snapshot: 0i8 },
+            version: 19133i32,
+            LevelName: "New World",
+            GameType: 1i32,
+            Difficulty: 2i8,
+            SpawnX: 0i32,
+            SpawnY: 64i32,
+            SpawnZ: 0i32,
+            SpawnAngle: 0.0f32,
+            WorldGenSettings: {
+                seed: -42i64,
+                generate_features: 1i8,
+                bonus_chest: 0i8,
+                dimensions: { "minecraft:overworld": { type: "minecraft:overworld" } },
+            },
+            DataPacks: { Enabled: ["vanilla"], Disabled: [] },
+            WanderingTraderSpawnChance: 25i32,
+        }
+    });
+    let mut bytes = Vec::new();
+    file.to_writer_root(&mut bytes, "", Endianness::BigEndian).unwrap();
+    let level = Level::from_reader(&bytes[..]).unwrap();
+
+    let data = &level.data;
+    assert_eq!(data.game_version.as_ref().unwrap().name, "1.20.4");
+    assert_eq!(data.game_type, 1);
+    assert_eq!(data.spawn_y, 64);
+    assert_eq!(data.random_seed, None);
+    let settings = data.world_gen_settings.as_ref().unwrap();
+    assert_eq!(settings.seed, -42);
+    assert!(settings.generate_features);
+    assert!(settings.dimensions.contains_key("minecraft:overworld"));
+    assert_eq!(data.data_packs.as_ref().unwrap().enabled, vec!["vanilla"]);
+
+    let mut written = Vec::new();
+    level.to_writer(&mut written).unwrap();
+    assert_eq!(Level::from_reader(&written[..]).unwrap(), level);
+}
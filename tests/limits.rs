@@ -0,0 +1,150 @@
+//! Tests for the limits that protect against malicious input.
+#![cfg(feature = "serde")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::raw::{self, Endianness, RawReader};
+use nbt::{Blob, Error, Value, ValueRef};
+
+/// A document whose root Compound holds `depth - 1` Lists nested inside one
+/// another.
+fn nested_lists(depth: usize) -> Vec<u8> {
+    let mut bytes = vec![0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, b'l'];
+    for _ in 2..depth {
+        bytes.extend_from_slice(&[0x09, 0x00, 0x00, 0x00, 0x01]);
+    }
+    bytes.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    bytes
+}
+
+#[test]
+fn depth_limit() {
+    let limit = raw::DEFAULT_MAX_DEPTH;
+    let ok = nested_lists(limit);
+    assert!(Blob::from_reader(&mut &ok[..], Endianness::BigEndian).is_ok());
+    assert!(ValueRef::from_slice(&ok, Endianness::BigEndian).is_ok());
+
+    let deep = nested_lists(limit + 1);
+    let err = Some(Error::DepthLimitExceeded(limit));
+    assert_eq!(Blob::from_reader(&mut &deep[..], Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(ValueRef::from_slice(&deep, Endianness::BigEndian).err().map(Error::without_location), err);
+
+    let mut src = RawReader::new(&deep[..], Endianness::BigEndian);
+    src.set_max_depth(limit + 1);
+    assert!(Blob::from_raw_reader(&mut src).is_ok());
+    assert_eq!(src.depth(), 0);
+}
+
+#[test]
+fn depth_limit_prevents_stack_overflow() {
+    // Deep enough to overflow the stack of a test thread if recursed into.
+    let deep = nested_lists(1_000_000);
+    let err = Some(Error::DepthLimitExceeded(raw::DEFAULT_MAX_DEPTH));
+    assert_eq!(Blob::from_reader(&mut &deep[..], Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(nbt::from_slice::<Value>(&deep, Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(nbt::from_reader::<_, Value>(&deep[..], Endianness::BigEndian).err().map(Error::without_location), err);
+}
+
+#[test]
+fn deep_trees_without_depth_limit() {
+    // Deep enough to overflow the stack of a test thread if recursed into.
+    let deep = nested_lists(200_000);
+    let mut src = RawReader::new(&deep[3..], Endianness::BigEndian);
+    src.set_max_depth(usize::MAX);
+    let value = Value::from_raw_reader(0x0a, &mut src).unwrap();
+    assert_eq!(src.depth(), 0);
+    assert!(src.into_inner().is_empty());
+
+    let mut dst = vec![0x0a, 0x00, 0x00];
+    value.to_writer(&mut dst, Endianness::BigEndian).unwrap();
+    assert_eq!(dst, deep);
+
+    // Cloning and comparing do not recurse either, nor does dropping with
+    // `drop_iteratively()`.
+    let copy = value.clone();
+    assert_eq!(copy, value);
+    let deeper = nested_lists(200_001);
+    let mut src = RawReader::new(&deeper[3..], Endianness::BigEndian);
+    src.set_max_depth(usize::MAX);
+    let other = Value::from_raw_reader(0x0a, &mut src).unwrap();
+    assert_ne!(other, value);
+    for tree in [value, copy, other] {
+        tree.drop_iteratively();
+    }
+
+    let mut compounds = Value::Compound(Default::default());
+    for _ in 0..200_000 {
+        compounds = nbt!({ c: (compounds) });
+    }
+    let copy = compounds.clone();
+    assert_eq!(copy, compounds);
+    copy.drop_iteratively();
+    compounds.drop_iteratively();
+}
+
+#[test]
+fn huge_declared_lengths() {
+    // A Long_Array claiming 2^31 - 1 elements, followed by only one.
+    let bytes = [0x0a, 0x00, 0x00, 0x0c, 0x00, 0x01, b'a', 0x7f, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 1];
+    let err = Some(Error::IncompleteNbtValue);
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(nbt::from_slice::<Value>(&bytes, Endianness::BigEndian).err().map(Error::without_location), err);
+
+    // Likewise for a List of Lists.
+    let bytes = [0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, b'l', 0x09, 0x7f, 0xff, 0xff, 0xff];
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), err);
+}
+
+#[test]
+fn length_limits() {
+    let mut blob = Blob::new();
+    blob.insert("s", "four").unwrap();
+    blob.insert("l", Value::List(vec![Value::Byte(1); 4])).unwrap();
+    blob.insert("a", Value::IntArray(vec![1; 4])).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    let read = |max_string_len, max_list_len, max_array_len| {
+        let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+        src.set_max_string_len(max_string_len);
+        src.set_max_list_len(max_list_len);
+        src.set_max_array_len(max_array_len);
+        Blob::from_raw_reader(&mut src).err().map(Error::without_location)
+    };
+    assert_eq!(read(4, 4, 4), None);
+    assert_eq!(read(3, 4, 4), Some(Error::LengthLimitExceeded(3)));
+    assert_eq!(read(4, 3, 4), Some(Error::LengthLimitExceeded(3)));
+    assert_eq!(read(4, 4, 3), Some(Error::LengthLimitExceeded(3)));
+}
+
+#[test]
+fn negative_lengths() {
+    let bytes = [0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, b'l', 0x01, 0xff, 0xff, 0xff, 0xff, 0x00];
+    let err = Some(Error::InvalidLength(-1));
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(ValueRef::from_slice(&bytes, Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(nbt::from_slice::<Value>(&bytes, Endianness::BigEndian).err().map(Error::without_location), err);
+
+    let bytes = [0x0a, 0x00, 0x00, 0x07, 0x00, 0x01, b'b', 0x80, 0x00, 0x00, 0x00, 0x00];
+    let err = Some(Error::InvalidLength(i32::MIN));
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).err().map(Error::without_location), err);
+    assert_eq!(nbt::from_slice::<Value>(&bytes, Endianness::BigEndian).err().map(Error::without_location), err);
+}
+
+#[test]
+fn long_strings() {
+    // Lengths of 32768 bytes and up do not fit in a signed Short.
+    let mut blob = Blob::new();
+    blob.insert("s", "x".repeat(40000)).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap(), blob);
+
+    blob.insert("s", "x".repeat(65536)).unwrap();
+    assert_eq!(blob.to_writer(&mut Vec::new(), Endianness::BigEndian),
+               Err(Error::LengthLimitExceeded(65535)));
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::NetworkLittleEndian).unwrap();
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::NetworkLittleEndian).unwrap(), blob);
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::formats::litematic::{Litematic, Region};
+use nbt::formats::structure::BlockState;
+use nbt::{Endianness, Error, Value};
+
+fn read(root: &Value) -> nbt::Result<Litematic> {
+    let mut bytes = Vec::new();
+    root.to_writer_root(&mut bytes, "", Endianness::BigEndian).unwrap();
+    Litematic::from_reader(&bytes[..])
+}
+
+fn file(block_states: Value) -> Value {
+    nbt!({
+        MinecraftDataVersion: 3465i32,
+        Version: 6i32,
+        SubVersion: 1i32,
+        Metadata: {
+            Name: "Garden",
+            Author: "Alex",
+            Description: "",
+            RegionCount: 1i32,
+            TotalBlocks: 3i32,
+            TotalVolume: 4i32,
+            TimeCreated: 1700000000000i64,
+            TimeModified: 1700000000000i64,
+            EnclosingSize: { x: 2i32, y: 1i32, z: 2i32 },
+        },
+        Regions: {
+            Bed: {
+                Position: { x: 0i32, y: 0i32, z: 0i32 },
+                Size: { x: 2i32, y: 1i32, z: -2i32 },
+                BlockStatePalette: [
+                    { Name: "minecraft:air" },
+                    { Name: "minecraft:farmland", Properties: { moisture: "7" } },
+                    { Name: "minecraft:chest", Properties: { facing: "north" } },
+                ],
+                BlockStates: block_states,
+                TileEntities: [{ x: 0i32, y: 0i32, z: 1i32, id: "minecraft:chest", Items: [] }],
+                Entities: [{ id: "minecraft:cat", Pos: [0.5, 0.0, 0.5], UUID: [I; 1, 2, 3, 4] }],
+                PendingBlockTicks: [],
+                PendingFluidTicks: [],
+            },
+        },
+    })
+}
+
+#[test]
+fn litematica_files() {
+    // Two bits per block: 0, 1, 2, 1 from the least significant bits.
+    let original = file(nbt!([L; 0b01_10_01_00]));
+    let litematic = read(&original).unwrap();
+    assert_eq!((litematic.version, litematic.sub_version, litematic.data_version), (6, Some(1), 3465));
+    assert_eq!(litematic.metadata.author, "Alex");
+    let region = &litematic.regions["Bed"];
+    assert_eq!(region.size, [2, 1, -2]);
+    assert_eq!(region.blocks, vec![0, 1, 2, 1]);
+    assert_eq!(region.block_at([0, 0, 1]), Some(&BlockState::new("minecraft:chest").with("facing", "north")));
+    assert_eq!(region.block_at([0, 0, 2]), None);
+
+    let mut bytes = Vec::new();
+    litematic.to_writer(&mut bytes).unwrap();
+    assert_eq!(Value::from_reader_root(&mut &bytes[..], Endianness::BigEndian).unwrap().1, original);
+
+    let mut updated = litematic.clone();
+    updated.update_metadata();
+    assert_eq!(updated.metadata, litematic.metadata);
+
+    assert_eq!(read(&file(nbt!([L; 1, 2]))).err(),
+               Some(Error::InvalidPackedArray("the array length does not match the number of entries")));
+    assert_eq!(read(&file(nbt!([L; 0b11]))).err(), Some(Error::InvalidSchematic("a block is not in the palette")));
+}
+
+#[test]
+fn conversion_to_schematics() {
+    let litematic = read(&file(nbt!([L; 0b01_10_01_00]))).unwrap();
+    let region = &litematic.regions["Bed"];
+    let schematic = region.to_schematic().unwrap();
+    assert_eq!(schematic.size, [2, 1, 2]);
+    assert_eq!(schematic.block_at([0, 0, 1]), region.block_at([0, 0, 1]));
+    assert_eq!(schematic.block_entities[0].pos, [0, 0, 1]);
+    assert_eq!(schematic.block_entities[0].id, "minecraft:chest");
+    assert_eq!(schematic.entities[0].id, "minecraft:cat");
+    assert_eq!(schematic.entities[0].data["UUID"], Value::IntArray(vec![1, 2, 3, 4]));
+
+    let back = Region::from_schematic(&schematic, [0, 0, 0]).unwrap();
+    assert_eq!(back.blocks, region.blocks);
+    assert_eq!(back.tile_entities, region.tile_entities);
+    assert_eq!(back.entities, region.entities);
+}
+
+#[test]
+fn metadata_of_several_regions() {
+    let mut litematic = Litematic::new("Towers");
+    let mut east = Region::new([4, 0, 0], [1, 3, 1]);
+    east.set_block([0, 2, 0], BlockState::new("minecraft:stone"));
+    east.set_block([0, 1, 0], BlockState::new("minecraft:cave_air"));
+    litematic.regions.insert("East".to_string(), east);
+    litematic.regions.insert("West".to_string(), Region::new([0, 0, 0], [-2, 1, 1]));
+    litematic.update_metadata();
+    assert_eq!(litematic.metadata.region_count, 2);
+    assert_eq!(litematic.metadata.total_blocks, 1);
+    assert_eq!(litematic.metadata.total_volume, 5);
+    assert_eq!(litematic.metadata.enclosing_size, [6, 3, 1]);
+
+    let mut bytes = Vec::new();
+    litematic.to_gzip_writer(&mut bytes).unwrap();
+    assert_eq!(Litematic::from_gzip_reader(&bytes[..]).unwrap(), litematic);
+}
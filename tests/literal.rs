@@ -0,0 +1,77 @@
+#![cfg(feature = "std")]
+#[macro_use]
+extern crate nbt;
+
+use nbt::{Blob, Endianness, Error, Map, Value};
+
+#[test]
+fn scalars_and_arrays() {
+    assert_eq!(nbt!(1i8), Value::Byte(1));
+    assert_eq!(nbt!(-2i16), Value::Short(-2));
+    assert_eq!(nbt!(3 + 4), Value::Int(7));
+    assert_eq!(nbt!("text"), Value::String("text".to_string()));
+    assert_eq!(nbt!([B; 1, -1]), Value::ByteArray(vec![1, -1]));
+    assert_eq!(nbt!([I; 1, 2, 3,]), Value::IntArray(vec![1, 2, 3]));
+    assert_eq!(nbt!([L;]), Value::LongArray(vec![]));
+}
+
+#[test]
+fn nested_compounds_and_lists() {
+    let count = 64i8;
+    let value = nbt!({
+        "Health": 20.0f32,
+        Tags: ["a", "b",],
+        Motion: [0.5f64, -1.0f64, 0.0f64],
+        Empty: [],
+        Inventory: [
+            { id: "minecraft:stone", Count: count },
+            { id: "minecraft:dirt", Count: count / 2 },
+        ],
+        Pos: nbt!([I; 1, 2, 3]),
+    });
+
+    let mut stone = Map::new();
+    stone.insert("id".to_string(), Value::String("minecraft:stone".to_string()));
+    stone.insert("Count".to_string(), Value::Byte(64));
+    let mut dirt = Map::new();
+    dirt.insert("id".to_string(), Value::String("minecraft:dirt".to_string()));
+    dirt.insert("Count".to_string(), Value::Byte(32));
+
+    let mut expected = Map::new();
+    expected.insert("Health".to_string(), Value::Float(20.0));
+    expected.insert("Tags".to_string(), Value::List(vec![
+        Value::String("a".to_string()),
+        Value::String("b".to_string()),
+    ]));
+    expected.insert("Motion".to_string(), Value::List(vec![
+        Value::Double(0.5), Value::Double(-1.0), Value::Double(0.0),
+    ]));
+    expected.insert("Empty".to_string(), Value::List(vec![]));
+    expected.insert("Inventory".to_string(), Value::List(vec![
+        Value::Compound(stone),
+        Value::Compound(dirt),
+    ]));
+    expected.insert("Pos".to_string(), Value::IntArray(vec![1, 2, 3]));
+
+    assert_eq!(value, Value::Compound(expected));
+    assert_eq!(nbt!({}), Value::Compound(Map::new()));
+}
+
+#[test]
+fn heterogeneous_lists_fail_to_write() {
+    let value = nbt!([1i8, "two"]);
+    let mut dst = Vec::new();
+    assert_eq!(value.to_writer(&mut dst, Endianness::BigEndian), Err(Error::HeterogeneousList));
+}
+
+#[test]
+fn blob_literals() {
+    let blob = blob!("Data", { LevelName: "world", GameType: 1i32 });
+
+    let mut expected = Blob::named("Data");
+    expected.insert("LevelName", "world").unwrap();
+    expected.insert("GameType", 1i32).unwrap();
+    assert_eq!(blob, expected);
+
+    assert_eq!(blob!({}), Blob::new());
+}
@@ -0,0 +1,83 @@
+//! Tests for LZ4 compression.
+#![cfg(feature = "experimental_lz4")]
+
+extern crate nbt;
+
+mod common;
+
+use std::fs::File;
+use std::io::Cursor;
+
+use nbt::region::{Compression, RegionFile};
+use nbt::{Blob, Endianness, ReadOptions, WriteOptions};
+
+/// The level.dat fixture, as compressed by the reference implementation.
+fn level() -> Blob {
+    let mut file = File::open("tests/level_lz4.dat").unwrap();
+    Blob::from_lz4_reader(&mut file, Endianness::BigEndian).unwrap()
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn read_lz4_java_stream() {
+    // Compressed by the reference LZ4 implementation, in lz4-java's framing.
+    let mut file = File::open("tests/level.dat").unwrap();
+    assert_eq!(level(), Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap());
+}
+
+#[test]
+fn lz4_roundtrip() {
+    for blob in &[level(), common::large_blob(1), Blob::new()] {
+        let mut dst = Vec::new();
+        blob.to_lz4_writer(&mut dst, Endianness::BigEndian).unwrap();
+        assert_eq!(&Blob::from_lz4_reader(&mut &dst[..], Endianness::BigEndian).unwrap(), blob);
+
+        let mut raw = Vec::new();
+        blob.to_writer(&mut raw, Endianness::BigEndian).unwrap();
+        assert!(dst.len() < raw.len() || raw.len() < 100);
+
+        let mut options_dst = Vec::new();
+        let write = WriteOptions::new(Endianness::BigEndian).compression(Compression::Lz4);
+        blob.to_writer_with(&mut options_dst, &write).unwrap();
+        assert_eq!(options_dst, dst);
+        let read = ReadOptions::new(Endianness::BigEndian).compression(Compression::Lz4);
+        assert_eq!(&Blob::from_reader_with(&mut &dst[..], &read).unwrap(), blob);
+    }
+}
+
+#[test]
+fn corrupt_lz4_streams() {
+    let mut src = Vec::new();
+    level().to_lz4_writer(&mut src, Endianness::BigEndian).unwrap();
+
+    // Every truncation before the end block fails rather than panicking.
+    for len in 0..src.len() - 21 {
+        assert!(Blob::from_lz4_reader(&mut &src[..len], Endianness::BigEndian).is_err());
+    }
+
+    let mut bad_magic = src.clone();
+    bad_magic[0] = b'X';
+    assert!(Blob::from_lz4_reader(&mut &bad_magic[..], Endianness::BigEndian).is_err());
+
+    let mut bad_checksum = src.clone();
+    bad_checksum[17] ^= 1;
+    assert!(Blob::from_lz4_reader(&mut &bad_checksum[..], Endianness::BigEndian).is_err());
+
+    // Flipping payload bytes must not panic, though some flips still decode.
+    for i in 21..src.len() - 21 {
+        let mut corrupt = src.clone();
+        corrupt[i] ^= 0xff;
+        let _ = Blob::from_lz4_reader(&mut &corrupt[..], Endianness::BigEndian);
+    }
+}
+
+#[test]
+fn lz4_region_chunks() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(3, 7, &level(), Compression::Lz4).unwrap();
+    let bytes = region.into_inner().into_inner();
+    assert_eq!(bytes[2 * 4096 + 4], 4);
+
+    let mut region = RegionFile::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(region.read_chunk(3, 7).unwrap(), Some(level()));
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "std")]
+#[macro_use]
+extern crate nbt;
+
+use nbt::MergeStrategy;
+
+fn existing() -> nbt::Value {
+    nbt!({
+        id: "minecraft:bow",
+        Count: 1i8,
+        tag: { Damage: 3i32, Enchantments: [{ id: "power", lvl: 1i16 }] },
+    })
+}
+
+fn other() -> nbt::Value {
+    nbt!({
+        Count: 2i8,
+        tag: { Unbreakable: 1i8, Enchantments: [{ id: "flame", lvl: 1i16 }] },
+    })
+}
+
+#[test]
+fn deep_merge() {
+    let mut value = existing();
+    value.merge(&other(), MergeStrategy::Deep);
+    assert_eq!(value, nbt!({
+        id: "minecraft:bow",
+        Count: 2i8,
+        tag: { Damage: 3i32, Unbreakable: 1i8, Enchantments: [{ id: "flame", lvl: 1i16 }] },
+    }));
+}
+
+#[test]
+fn overwrite_merge() {
+    let mut value = existing();
+    value.merge(&other(), MergeStrategy::Overwrite);
+    assert_eq!(value, nbt!({
+        id: "minecraft:bow",
+        Count: 2i8,
+        tag: { Unbreakable: 1i8, Enchantments: [{ id: "flame", lvl: 1i16 }] },
+    }));
+}
+
+#[test]
+fn keep_existing_merge() {
+    let mut value = existing();
+    value.merge(&other(), MergeStrategy::KeepExisting);
+    assert_eq!(value, nbt!({
+        id: "minecraft:bow",
+        Count: 1i8,
+        tag: { Damage: 3i32, Unbreakable: 1i8, Enchantments: [{ id: "power", lvl: 1i16 }] },
+    }));
+}
+
+#[test]
+fn merge_non_compounds() {
+    let mut value = nbt!(1i8);
+    value.merge(&nbt!({ a: 1i8 }), MergeStrategy::KeepExisting);
+    assert_eq!(value, nbt!(1i8));
+    value.merge(&nbt!({ a: 1i8 }), MergeStrategy::Deep);
+    assert_eq!(value, nbt!({ a: 1i8 }));
+
+    // A Compound replaced by a scalar, or vice versa, is never merged into.
+    let mut value = nbt!({ a: { b: 1i8 } });
+    value.merge(&nbt!({ a: 2i8 }), MergeStrategy::Deep);
+    assert_eq!(value, nbt!({ a: 2i8 }));
+}
+
+#[test]
+fn blob_merge() {
+    let mut blob = blob!("Data", { a: 1i8, b: { c: 2i8 } });
+    blob.merge(&blob!("Other", { b: { d: 3i8 }, e: 4i8 }), MergeStrategy::Deep);
+    assert_eq!(blob, blob!("Data", { a: 1i8, b: { c: 2i8, d: 3i8 }, e: 4i8 }));
+
+    blob.merge(&blob!({ b: { d: 5i8 } }), MergeStrategy::Overwrite);
+    assert_eq!(blob, blob!("Data", { a: 1i8, b: { d: 5i8 }, e: 4i8 }));
+}
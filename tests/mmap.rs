@@ -0,0 +1,57 @@
+//! Tests for reading files through memory maps.
+#![cfg(all(feature = "memmap", unix))]
+
+extern crate nbt;
+
+use std::fs::{self, File};
+
+use nbt::mmap::Mmap;
+use nbt::region::{Compression, RegionFile};
+use nbt::{Blob, Endianness};
+
+#[test]
+fn map_files() {
+    let path = std::env::temp_dir().join(format!("nbt-mmap-test-{}.nbt", std::process::id()));
+    let mut blob = Blob::new();
+    blob.insert("name", "Herobrine").unwrap();
+    blob.insert("health", 20.0f32).unwrap();
+    let mut data = Vec::new();
+    blob.to_writer(&mut data, Endianness::BigEndian).unwrap();
+    fs::write(&path, &data).unwrap();
+
+    let map = unsafe { Mmap::map(&File::open(&path).unwrap()) }.unwrap();
+    assert_eq!(&map[..], &data[..]);
+    assert_eq!(Blob::from_reader(&mut &map[..], Endianness::BigEndian).unwrap(), blob);
+
+    fs::write(&path, b"").unwrap();
+    let map = unsafe { Mmap::map(&File::open(&path).unwrap()) }.unwrap();
+    assert!(map.is_empty());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn mapped_regions() {
+    let dir = std::env::temp_dir().join(format!("nbt-mmap-region-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("r.0.0.mca");
+    {
+        let mut region = RegionFile::create(&path).unwrap();
+        for &(x, z) in &[(0, 0), (4, 9), (31, 31)] {
+            let mut chunk = Blob::new();
+            chunk.insert("xPos", x).unwrap();
+            chunk.insert("zPos", z).unwrap();
+            region.write_chunk(x, z, &chunk, Compression::Zlib).unwrap();
+        }
+    }
+
+    let mut mapped = unsafe { RegionFile::open_mmap(&path) }.unwrap();
+    let mut region = RegionFile::open(&path).unwrap();
+    assert!(mapped.has_chunk(4, 9) && !mapped.has_chunk(1, 1));
+    assert_eq!(mapped.read_chunk(4, 9).unwrap(), region.read_chunk(4, 9).unwrap());
+    let chunks: Vec<_> = mapped.chunks().map(|chunk| chunk.unwrap()).collect();
+    assert_eq!(chunks, region.chunks().map(|chunk| chunk.unwrap()).collect::<Vec<_>>());
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(mapped.get_ref().get_ref().len() as u64, fs::metadata(&path).unwrap().len());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,96 @@
+//! Tests for builds without the `std` feature, where `Value` reads and
+//! writes through the stand-in traits of `nbt::io`. Run them with
+//! `cargo test --no-default-features --test no_std`.
+#![cfg(not(feature = "std"))]
+
+#[macro_use] extern crate nbt;
+
+use nbt::io::{ErrorKind, Read, Write};
+use nbt::raw::{RawReader, RawWriter};
+use nbt::{Endianness, Error, Map, Value};
+
+fn sample() -> Value {
+    nbt!({
+        "name": "\u{10401}\0",
+        "health": 20.0f32,
+        "pos": [1.5f64, 64.0f64, -3.25f64],
+        "uuid": [I; 1, 2, 3, 4],
+        "seen": [L; 1i64 << 40],
+        "inventory": [{ "id": "minecraft:stone", "Count": 64i8 }],
+    })
+}
+
+#[test]
+fn value_roundtrip() {
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let value = sample();
+        let mut dst = Vec::new();
+        value.to_writer(&mut dst, endian).unwrap();
+        let mut src = &dst[..];
+        assert_eq!(Value::from_reader(value.id(), &mut src, endian).unwrap(), value);
+        assert!(src.is_empty());
+    }
+}
+
+#[test]
+fn modified_utf8_strings() {
+    let mut dst = Vec::new();
+    RawWriter::new(&mut dst, Endianness::BigEndian).write_bare_string("\u{10401}\0").unwrap();
+    assert_eq!(dst, [0x00, 0x08, 0xed, 0xa0, 0x81, 0xed, 0xb0, 0x81, 0xc0, 0x80]);
+    let mut src = RawReader::new(&dst[..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_string().unwrap(), "\u{10401}\0");
+
+    let invalid = [0x00, 0x02, 0xed, 0xa0];
+    let mut src = RawReader::new(&invalid[..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_string(), Err(Error::InvalidUtf8));
+}
+
+#[test]
+fn truncated_input() {
+    let mut dst = Vec::new();
+    sample().to_writer(&mut dst, Endianness::BigEndian).unwrap();
+    for len in 0..dst.len() {
+        let mut src = &dst[..len];
+        assert_eq!(Value::from_reader(0x0a, &mut src, Endianness::BigEndian).map_err(Error::without_location),
+                   Err(Error::IncompleteNbtValue));
+    }
+}
+
+/// A source that hands out one byte at a time.
+struct Trickle<'a>(&'a [u8]);
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> nbt::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((&byte, rest)), Some(slot)) => {
+                *slot = byte;
+                self.0 = rest;
+                Ok(1)
+            },
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn custom_io() {
+    let mut dst = Vec::new();
+    sample().to_writer(&mut dst, Endianness::BigEndian).unwrap();
+    let value = Value::from_reader(0x0a, &mut Trickle(&dst), Endianness::BigEndian).unwrap();
+    assert_eq!(value, sample());
+
+    // A full fixed-size buffer fails rather than truncating.
+    let mut buf = [0u8; 8];
+    let mut dst = &mut buf[..];
+    assert!(sample().to_writer(&mut dst, Endianness::BigEndian).is_err());
+    let mut full = &mut [0u8; 0][..];
+    assert_eq!(full.write_all(&[1]).unwrap_err().kind(), ErrorKind::WriteZero);
+}
+
+#[test]
+fn compounds_are_sorted() {
+    let mut map = Map::new();
+    map.insert("b".to_string(), Value::Byte(2));
+    map.insert("a".to_string(), Value::Byte(1));
+    assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b"]);
+}
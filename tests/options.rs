@@ -0,0 +1,245 @@
+//! Tests for `ReadOptions` and `WriteOptions`.
+#![cfg(any(feature = "compression", feature = "rust_backend"))]
+
+extern crate nbt;
+
+use std::fs::File;
+
+use nbt::{Blob, Compression, CompressionLevel, Endianness, Error, ReadOptions, StringEncoding, WriteOptions};
+
+fn player() -> Blob {
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap()
+}
+
+#[test]
+fn roundtrip_with_options() {
+    let blob = player();
+    for &compression in &[Compression::Gzip, Compression::Zlib, Compression::Uncompressed] {
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+            let mut dst = Vec::new();
+            let write = WriteOptions::new(endian).compression(compression);
+            blob.to_writer_with(&mut dst, &write).unwrap();
+            let read = ReadOptions::new(endian).compression(compression);
+            assert_eq!(Blob::from_reader_with(&mut &dst[..], &read).unwrap(), blob);
+        }
+    }
+}
+
+#[test]
+fn options_match_other_methods() {
+    let blob = player();
+
+    let mut expected = Vec::new();
+    blob.to_network_writer(&mut expected, Endianness::BigEndian).unwrap();
+    let mut dst = Vec::new();
+    blob.to_writer_with(&mut dst, &WriteOptions::new(Endianness::BigEndian).unnamed_root(true)).unwrap();
+    assert_eq!(dst, expected);
+
+    let read = ReadOptions::new(Endianness::BigEndian).unnamed_root(true);
+    let expected = Blob::from_network_reader(&mut &dst[..], Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_reader_with(&mut &dst[..], &read).unwrap(), expected);
+
+    let mut expected = Vec::new();
+    blob.to_zlib_writer(&mut expected, Endianness::LittleEndian).unwrap();
+    let read = ReadOptions::new(Endianness::LittleEndian).compression(Compression::Zlib);
+    assert_eq!(Blob::from_reader_with(&mut &expected[..], &read).unwrap(), blob);
+}
+
+#[test]
+fn detect_compression() {
+    let blob = player();
+    for &compression in &[Compression::Gzip, Compression::Zlib, Compression::Uncompressed] {
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian] {
+            let mut dst = Vec::new();
+            blob.to_writer_with(&mut dst, &WriteOptions::new(endian).compression(compression)).unwrap();
+            assert_eq!(Blob::from_reader_auto(&mut &dst[..], endian).unwrap(), blob);
+        }
+    }
+
+    // Short inputs are passed through to the raw parser.
+    assert!(Blob::from_reader_auto(&mut &[][..], Endianness::BigEndian).is_err());
+    let mut dst = Vec::new();
+    Blob::new().to_writer(&mut dst, Endianness::BigEndian).unwrap();
+    assert_eq!(Blob::from_reader_auto(&mut &dst[..], Endianness::BigEndian).unwrap(), Blob::new());
+}
+
+#[test]
+fn compression_levels() {
+    let blob = player();
+    let mut sizes = Vec::new();
+    for &level in &[CompressionLevel::None, CompressionLevel::Fast, CompressionLevel::Best] {
+        let mut dst = Vec::new();
+        let write = WriteOptions::new(Endianness::BigEndian).compression(Compression::Gzip).level(level);
+        blob.to_writer_with(&mut dst, &write).unwrap();
+        let read = ReadOptions::new(Endianness::BigEndian).compression(Compression::Gzip);
+        assert_eq!(Blob::from_reader_with(&mut &dst[..], &read).unwrap(), blob);
+        sizes.push(dst.len());
+    }
+    assert!(sizes[0] > sizes[1]);
+    assert!(sizes[1] >= sizes[2]);
+}
+
+#[test]
+fn sorted_keys_with_options() {
+    let blob = player();
+    let options = WriteOptions::new(Endianness::BigEndian).sort_keys(true);
+    let mut a = Vec::new();
+    blob.to_writer_with(&mut a, &options).unwrap();
+    let mut b = Vec::new();
+    Blob::from_reader(&mut &a[..], Endianness::BigEndian).unwrap()
+        .to_writer_with(&mut b, &options).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn limits_with_options() {
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    let options = ReadOptions::new(Endianness::BigEndian)
+        .compression(Compression::Gzip)
+        .max_depth(1);
+    assert_eq!(Blob::from_reader_with(&mut file, &options).map_err(Error::without_location), Err(Error::DepthLimitExceeded(1)));
+
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    let options = ReadOptions::new(Endianness::BigEndian)
+        .compression(Compression::Gzip)
+        .max_string_len(2);
+    assert_eq!(Blob::from_reader_with(&mut file, &options).map_err(Error::without_location), Err(Error::LengthLimitExceeded(2)));
+}
+
+#[test]
+fn string_encodings() {
+    let mut blob = Blob::new();
+    blob.insert("Name", "Sword \u{1f600}\0").unwrap();
+    let mutf8 = b"Sword \xed\xa0\xbd\xed\xb8\x80\xc0\x80";
+    let utf8 = "Sword \u{1f600}\0".as_bytes();
+
+    let options = WriteOptions::new(Endianness::BigEndian);
+    let mut java = Vec::new();
+    blob.to_writer_with(&mut java, &options).unwrap();
+    assert!(java.windows(mutf8.len()).any(|w| w == &mutf8[..]));
+    assert_eq!(Blob::from_reader(&mut &java[..], Endianness::BigEndian).unwrap(), blob);
+
+    let options = options.string_encoding(StringEncoding::Utf8);
+    let mut bedrock = Vec::new();
+    blob.to_writer_with(&mut bedrock, &options).unwrap();
+    assert!(bedrock.windows(utf8.len()).any(|w| w == utf8));
+    assert_eq!(Blob::from_reader(&mut &bedrock[..], Endianness::BigEndian).unwrap(), blob);
+
+    let strict = ReadOptions::new(Endianness::BigEndian).string_encoding(StringEncoding::Utf8);
+    assert_eq!(Blob::from_reader_with(&mut &bedrock[..], &strict).unwrap(), blob);
+    assert_eq!(Blob::from_reader_with(&mut &java[..], &strict).map_err(Error::without_location), Err(Error::InvalidUtf8));
+}
+
+#[test]
+fn lossy_strings_with_options() {
+    let mut blob = Blob::new();
+    blob.insert("Name", "ab!cd").unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let at = bytes.windows(5).position(|w| w == b"ab!cd").unwrap();
+    bytes[at + 2] = 0xff;
+
+    assert_eq!(Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).map_err(Error::without_location), Err(Error::InvalidUtf8));
+    for &encoding in &[StringEncoding::ModifiedUtf8, StringEncoding::Utf8] {
+        let options = ReadOptions::new(Endianness::BigEndian)
+            .string_encoding(encoding)
+            .lossy_strings(true);
+        let read = Blob::from_reader_with(&mut &bytes[..], &options).unwrap();
+        assert_eq!(read["Name"], nbt::Value::String("ab\u{fffd}cd".to_string()));
+    }
+}
+
+#[test]
+fn read_and_write_paths() {
+    let dir = std::env::temp_dir().join(format!("nbt-path-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let blob = player();
+    assert_eq!(Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap(), blob);
+
+    let gzip = dir.join("player.dat");
+    blob.write_to_path(&gzip, Endianness::BigEndian).unwrap();
+    assert_eq!(&std::fs::read(&gzip).unwrap()[..2], &[0x1f, 0x8b]);
+    assert_eq!(Blob::from_path(&gzip, Endianness::BigEndian).unwrap(), blob);
+
+    let plain = dir.join("level.nbt");
+    let options = WriteOptions::new(Endianness::LittleEndian);
+    blob.write_to_path_with(&plain, &options).unwrap();
+    assert_eq!(std::fs::read(&plain).unwrap()[0], 0x0a);
+    assert_eq!(Blob::from_path(&plain, Endianness::LittleEndian).unwrap(), blob);
+
+    match Blob::from_path(dir.join("missing.dat"), Endianness::BigEndian) {
+        Err(Error::IoError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        other => panic!("unexpected result {:?}", other),
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn consecutive_blobs() {
+    let blobs: Vec<_> = (0i32..3).map(|i| {
+        let mut blob = Blob::named(format!("doc{}", i));
+        blob.insert("i", i).unwrap();
+        blob
+    }).collect();
+    for &unnamed_root in &[false, true] {
+        let write = WriteOptions::new(Endianness::LittleEndian).unnamed_root(unnamed_root);
+        let read = ReadOptions::new(Endianness::LittleEndian).unnamed_root(unnamed_root);
+        let mut bytes = Vec::new();
+        for blob in &blobs {
+            blob.to_writer_with(&mut bytes, &write).unwrap();
+        }
+        let read_back: Vec<_> = Blob::iter_from_reader(&bytes[..], &read).map(Result::unwrap).collect();
+        let values: Vec<_> = read_back.iter().map(|b| b["i"].clone()).collect();
+        assert_eq!(values, [nbt::Value::Int(0), nbt::Value::Int(1), nbt::Value::Int(2)]);
+        if !unnamed_root {
+            assert_eq!(read_back, blobs);
+        }
+    }
+
+    let read = ReadOptions::new(Endianness::BigEndian);
+    let mut bytes = Vec::new();
+    for blob in &blobs {
+        blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    }
+    let len = bytes.len();
+    let mut iter = Blob::iter_from_reader(&bytes[..], &read);
+    assert_eq!(iter.next(), Some(Ok(blobs[0].clone())));
+    assert_eq!(iter.offset(), len as u64 / 3);
+    assert_eq!(iter.count(), 2);
+    assert_eq!(Blob::iter_from_reader(&[][..], &read).count(), 0);
+
+    // A partial document at the end is an error, after which nothing more
+    // is read.
+    let mut results: Vec<_> = Blob::iter_from_reader(&bytes[..len - 2], &read).collect();
+    assert_eq!(results.len(), 3);
+    let err = results.pop().unwrap().unwrap_err();
+    // The read that failed is of the last Int, before the closing TAG_End.
+    assert_eq!(err.offset(), Some(len as u64 - 5));
+    assert_eq!(err.path(), Some("i"));
+    match err {
+        Error::Located(_, _, inner) => assert_eq!(*inner, Error::IncompleteNbtValue),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    bytes.push(0x00);
+    let mut iter = Blob::iter_from_reader(&bytes[..], &read).skip(3);
+    assert_eq!(iter.next().map(|r| r.map_err(Error::without_location)), Some(Err(Error::NoRootCompound)));
+    assert_eq!(iter.next(), None);
+
+    // Decompression applies to the whole stream.
+    let gzip = ReadOptions::new(Endianness::BigEndian).compression(Compression::Gzip);
+    assert!(Blob::iter_from_reader(&bytes[..], &gzip).next().unwrap().is_err());
+    let mut file = File::open("tests/complex_player.dat").unwrap();
+    assert_eq!(Blob::iter_from_reader(&mut file, &gzip).collect::<Result<Vec<_>, _>>(), Ok(vec![player()]));
+}
+
+#[cfg(not(feature = "experimental_lz4"))]
+#[test]
+fn disabled_compression() {
+    let blob = player();
+    let write = WriteOptions::new(Endianness::BigEndian).compression(Compression::Lz4);
+    assert_eq!(blob.to_writer_with(&mut Vec::new(), &write).err(), Some(Error::UnknownChunkCompression(4)));
+    let read = ReadOptions::new(Endianness::BigEndian).compression(Compression::Lz4);
+    assert_eq!(Blob::from_reader_with(&mut &[0x0a, 0x00, 0x00, 0x00][..], &read).err(), Some(Error::UnknownChunkCompression(4)));
+}
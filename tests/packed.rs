@@ -0,0 +1,104 @@
+extern crate nbt;
+
+use nbt::packed::{self, BIOME_MIN_BITS, BLOCK_STATE_MIN_BITS, SECTION_BIOMES, SECTION_BLOCKS};
+use nbt::Error;
+
+#[test]
+fn entries_do_not_span_longs() {
+    let indices: Vec<u16> = (0..16).collect();
+    assert_eq!(packed::pack(&indices, 4).unwrap(), vec![0xfedc_ba98_7654_3210_u64 as i64]);
+
+    // Twelve 5-bit entries fill 60 bits, so the thirteenth starts a new Long.
+    let indices: Vec<u16> = (1..14).collect();
+    let data = packed::pack(&indices, 5).unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(data[0] as u64 >> 60, 0);
+    assert_eq!(data[1], 13);
+    assert_eq!(packed::unpack(&data, 5, 13).unwrap(), indices);
+
+    assert_eq!(packed::packed_len(SECTION_BLOCKS, 5), 342);
+    assert_eq!(packed::packed_len(256, 9), 37);
+    assert_eq!(packed::packed_len(0, 9), 0);
+    assert_eq!(packed::unpack(&[-1], 16, 4).unwrap(), vec![0xffff; 4]);
+}
+
+#[test]
+fn palette_indices() {
+    assert_eq!(packed::bits_per_entry(1, BLOCK_STATE_MIN_BITS), 4);
+    assert_eq!(packed::bits_per_entry(16, BLOCK_STATE_MIN_BITS), 4);
+    assert_eq!(packed::bits_per_entry(17, BLOCK_STATE_MIN_BITS), 5);
+    assert_eq!(packed::bits_per_entry(2, BIOME_MIN_BITS), 1);
+    assert_eq!(packed::bits_per_entry(5, BIOME_MIN_BITS), 3);
+    assert_eq!(packed::bits_per_entry(1, 0), 0);
+
+    let indices: Vec<u16> = (0..SECTION_BLOCKS).map(|i| (i % 40) as u16).collect();
+    let data = packed::pack_indices(&indices, 40, BLOCK_STATE_MIN_BITS).unwrap();
+    assert_eq!(data.len(), packed::packed_len(SECTION_BLOCKS, 6));
+    assert_eq!(packed::unpack_indices(&data, 40, SECTION_BLOCKS, BLOCK_STATE_MIN_BITS).unwrap(), indices);
+
+    let biomes = vec![1u16; SECTION_BIOMES];
+    let data = packed::pack_indices(&biomes, 2, BIOME_MIN_BITS).unwrap();
+    assert_eq!(data, vec![-1]);
+    assert_eq!(packed::unpack_indices(&data, 2, SECTION_BIOMES, BIOME_MIN_BITS).unwrap(), biomes);
+}
+
+#[test]
+fn invalid_arrays() {
+    fn err<T>(msg: &'static str) -> Result<T, Error> {
+        Err(Error::InvalidPackedArray(msg))
+    }
+    assert_eq!(packed::unpack(&[0; 3], 4, 16), err("the array length does not match the number of entries"));
+    assert_eq!(packed::unpack(&[], 0, 16), err("the bits per entry are not between 1 and 16"));
+    assert_eq!(packed::pack(&[16], 4), err("an entry does not fit in the bits per entry"));
+    assert_eq!(packed::pack(&[1], 17), err("the bits per entry are not between 1 and 16"));
+
+    let data = packed::pack(&[0, 1, 2, 3], 4).unwrap();
+    assert_eq!(packed::unpack_indices(&data, 3, 4, BLOCK_STATE_MIN_BITS), err("an index is not in the palette"));
+    assert_eq!(packed::pack_indices(&[0, 3], 3, BLOCK_STATE_MIN_BITS), err("an index is not in the palette"));
+    assert_eq!(packed::unpack_indices(&[], 0, 4, BLOCK_STATE_MIN_BITS),
+               err("the array length does not match the number of entries"));
+}
+
+#[test]
+fn spanning_layout() {
+    // Before 1.16, the fifth 13-bit entry starts at bit 52 and ends in the
+    // second Long.
+    let values = [1u16, 2, 3, 4, 0x1fff, 6];
+    let data = packed::pack_spanning(&values, 13).unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(data[0] as u64 >> 52, 0xfff);
+    assert_eq!(data[1] & 0x1, 1);
+    assert_eq!(data[1] >> 1, 6);
+    assert_eq!(packed::unpack_spanning(&data, 13, 6).unwrap(), values);
+    assert_eq!(packed::unpack_spanning(&data, 13, 10), Err(Error::InvalidPackedArray(
+        "the array length does not match the number of entries")));
+
+    let indices: Vec<u16> = (0..SECTION_BLOCKS).map(|i| (i * 7 % 32) as u16).collect();
+    let data = packed::pack_spanning(&indices, 5).unwrap();
+    assert_eq!(data.len(), 320);
+    assert_eq!(packed::unpack_spanning(&data, 5, SECTION_BLOCKS).unwrap(), indices);
+}
+
+#[test]
+fn heightmaps() {
+    let mut heights = [[0u16; 16]; 16];
+    for (z, row) in heights.iter_mut().enumerate() {
+        for (x, height) in row.iter_mut().enumerate() {
+            *height = (z * 31 + x) as u16;
+        }
+    }
+    let data = packed::pack_heightmap(&heights).unwrap();
+    assert_eq!(data.len(), 37);
+    assert_eq!(data[0] & 0x1ff, 0);
+    assert_eq!(data[0] >> 9 & 0x1ff, 1);
+    assert_eq!(packed::unpack_heightmap(&data).unwrap(), heights);
+
+    let rows: Vec<u16> = heights.iter().flat_map(|row| row.iter().cloned()).collect();
+    let legacy = packed::pack_spanning(&rows, packed::HEIGHTMAP_BITS).unwrap();
+    assert_eq!(legacy.len(), 36);
+    assert_eq!(packed::unpack_heightmap(&legacy).unwrap(), heights);
+
+    assert!(packed::unpack_heightmap(&data[2..]).is_err());
+    heights[3][4] = 512;
+    assert!(packed::pack_heightmap(&heights).is_err());
+}
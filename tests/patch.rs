@@ -0,0 +1,189 @@
+#[macro_use]
+extern crate nbt;
+
+use nbt::{Change, Error, NbtPatch, Value};
+
+#[test]
+fn diff_reports_paths() {
+    let before = nbt!({
+        Health: 20.0f32,
+        Motion: [0.0f64, 0.0f64],
+        Inventory: [
+            { id: "minecraft:stone", Count: 64i8 },
+            { id: "minecraft:dirt", Count: 1i8 },
+        ],
+        "odd key": 1i8,
+    });
+    let after = nbt!({
+        Health: 18.5f32,
+        Inventory: [
+            { id: "minecraft:stone", Count: 64i8, Slot: 0i8 },
+        ],
+        "odd key": 1i16,
+        Pos: [I; 1, 2, 3],
+    });
+
+    let patch = nbt::diff(&before, &after);
+    assert_eq!(patch.changes(), &[
+        Change::Changed { path: "Health".to_string(), from: Value::Float(20.0), to: Value::Float(18.5) },
+        Change::Added { path: "Inventory[0].Slot".to_string(), value: Value::Byte(0) },
+        Change::Removed { path: "Inventory[1]".to_string(), value: nbt!({ id: "minecraft:dirt", Count: 1i8 }) },
+        Change::Removed { path: "Motion".to_string(), value: nbt!([0.0f64, 0.0f64]) },
+        Change::Added { path: "Pos".to_string(), value: Value::IntArray(vec![1, 2, 3]) },
+        // The tag matters, even though the value is the same.
+        Change::Changed { path: "\"odd key\"".to_string(), from: Value::Byte(1), to: Value::Short(1) },
+    ]);
+
+    for change in &patch {
+        match *change {
+            Change::Removed { .. } => assert!(before.get_path(change.path()).is_some()),
+            _ => assert!(after.get_path(change.path()).is_some()),
+        }
+    }
+    assert!(nbt::diff(&after, &after).is_empty());
+}
+
+#[test]
+fn diff_lists() {
+    // Elements removed from the end are listed from the last one.
+    let patch = nbt::diff(&nbt!([1i8, 2i8, 3i8]), &nbt!([1i8]));
+    let paths: Vec<_> = patch.iter().map(Change::path).collect();
+    assert_eq!(paths, vec!["[2]", "[1]"]);
+
+    // Lists whose elements change tag are replaced as a whole.
+    let from = nbt!([1i8, 2i8]);
+    let to = nbt!([1i32, 2i32]);
+    assert_eq!(nbt::diff(&from, &to).changes(), &[
+        Change::Changed { path: String::new(), from: from.clone(), to: to.clone() },
+    ]);
+
+    // So are arrays.
+    let patch = nbt::diff(&nbt!([I; 1, 2]), &nbt!([I; 1, 3]));
+    assert_eq!(patch.len(), 1);
+    assert_eq!(patch.changes()[0].path(), "");
+}
+
+#[test]
+fn display() {
+    let patch = nbt::diff(&nbt!({ a: 1i8, b: 2i8 }), &nbt!({ a: 2i8, c: "x" }));
+    assert_eq!(patch.to_string(), "~ a: 1b -> 2b\n- b\n+ c: \"x\"\n");
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn blob_diff() {
+    use nbt::{Blob, Endianness};
+
+    let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    let before = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    let mut after = before.clone();
+    assert!(before.diff(&after).is_empty());
+
+    *after.get_path_mut("Pos[1]").unwrap() = Value::Double(-64.0);
+    after.insert("Score", 10i32).unwrap();
+    let paths: Vec<_> = before.diff(&after).iter().map(|c| c.path().to_string()).collect();
+    assert_eq!(paths, vec!["Pos[1]", "Score"]);
+}
+
+#[test]
+fn apply_roundtrips_diff() {
+    let before = nbt!({
+        Health: 20.0f32,
+        Motion: [0.0f64, 0.0f64],
+        Inventory: [
+            { id: "minecraft:stone", Count: 64i8 },
+            { id: "minecraft:dirt", Count: 1i8 },
+            { id: "minecraft:sand", Count: 2i8 },
+        ],
+        Tags: ["a"],
+    });
+    let after = nbt!({
+        Health: 18.5f32,
+        Motion: [1i32],
+        Inventory: [
+            { id: "minecraft:stone", Count: 63i8, Slot: 0i8 },
+        ],
+        Tags: ["a", "b", "c"],
+        Pos: [I; 1, 2, 3],
+    });
+
+    let mut patched = before.clone();
+    patched.apply_patch(&nbt::diff(&before, &after)).unwrap();
+    assert_eq!(patched, after);
+
+    let mut reverted = after.clone();
+    reverted.apply_patch(&nbt::diff(&after, &before)).unwrap();
+    assert_eq!(reverted, before);
+
+    // Patches of the root itself.
+    let mut root = nbt!(1i8);
+    root.apply_patch(&nbt::diff(&nbt!(1i8), &nbt!("one"))).unwrap();
+    assert_eq!(root, nbt!("one"));
+}
+
+#[test]
+fn apply_to_another_tree() {
+    let before = nbt!({ a: 1i8, b: { c: 2i8 } });
+    let after = nbt!({ a: 1i8, b: { c: 3i8 } });
+    let patch = nbt::diff(&before, &after);
+
+    // Entries the patch does not touch are left alone.
+    let mut other = nbt!({ b: { c: 2i8, d: 4i8 }, e: 5i8 });
+    other.apply_patch(&patch).unwrap();
+    assert_eq!(other, nbt!({ b: { c: 3i8, d: 4i8 }, e: 5i8 }));
+}
+
+#[test]
+fn apply_conflicts() {
+    let conflict = |path: &str| Err(Error::PatchConflict(path.to_string()));
+    let patch: NbtPatch = vec![
+        Change::Changed { path: "a".to_string(), from: Value::Byte(1), to: Value::Byte(2) },
+        Change::Added { path: "b".to_string(), value: Value::Byte(3) },
+        Change::Removed { path: "c[1]".to_string(), value: Value::Byte(5) },
+    ].into_iter().collect();
+
+    let original = nbt!({ a: 1i8, c: [4i8, 5i8] });
+    let mut target = original.clone();
+    target.apply_patch(&patch).unwrap();
+    assert_eq!(target, nbt!({ a: 2i8, b: 3i8, c: [4i8] }));
+
+    // The old value differs.
+    let mut target = nbt!({ a: 9i8, c: [4i8, 5i8] });
+    assert_eq!(target.apply_patch(&patch), conflict("a"));
+    // The entry to add already exists.
+    let mut target = nbt!({ a: 1i8, b: 3i8, c: [4i8, 5i8] });
+    assert_eq!(target.apply_patch(&patch), conflict("b"));
+    // The element to remove is not at the end, and nothing was applied.
+    let mut target = nbt!({ a: 1i8, c: [4i8, 5i8, 6i8] });
+    assert_eq!(target.apply_patch(&patch), conflict("c[1]"));
+    assert_eq!(target, nbt!({ a: 1i8, c: [4i8, 5i8, 6i8] }));
+    // The parent is missing or has the wrong type.
+    let mut target = nbt!({ a: 1i8, c: { x: 5i8 } });
+    assert_eq!(target.apply_patch(&patch), conflict("c[1]"));
+    let mut target = nbt!([1i8]);
+    assert_eq!(target.apply_patch(&patch), conflict("a"));
+
+    // Lists must stay homogeneous.
+    let patch: NbtPatch = vec![
+        Change::Added { path: "[1]".to_string(), value: Value::Short(1) },
+    ].into_iter().collect();
+    let mut target = nbt!([1i8]);
+    assert_eq!(target.apply_patch(&patch), Err(Error::HeterogeneousList));
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn blob_apply_patch() {
+    use nbt::{Blob, Endianness};
+
+    let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    let before = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    let mut after = before.clone();
+    *after.get_path_mut("Pos[1]").unwrap() = Value::Double(-64.0);
+    after.insert("Score", 10i32).unwrap();
+
+    let mut replica = before.clone();
+    replica.apply_patch(&before.diff(&after)).unwrap();
+    assert_eq!(replica, after);
+    assert_eq!(replica.apply_patch(&before.diff(&after)), Err(Error::PatchConflict("Pos[1]".to_string())));
+}
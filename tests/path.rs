@@ -0,0 +1,272 @@
+#![cfg(feature = "std")]
+#[macro_use]
+extern crate nbt;
+
+use nbt::{Error, Value, VisitAction};
+
+fn chunk() -> Value {
+    nbt!({
+        Level: {
+            Sections: [
+                { Y: 0i8, Palette: [{ Name: "minecraft:air" }] },
+                { Y: 1i8, Palette: [{ Name: "minecraft:stone" }, { Name: "minecraft:dirt" }] },
+            ],
+            "weird.key": { "[0]": 5i32, "say \"hi\"": 6i32 },
+            Heights: [I; 1, 2, 3],
+        },
+    })
+}
+
+#[test]
+fn get_path() {
+    let chunk = chunk();
+    assert_eq!(chunk.get_path(""), Some(&chunk));
+    assert_eq!(chunk.get_path("Level.Sections[1].Palette[1].Name"),
+               Some(&Value::String("minecraft:dirt".to_string())));
+    assert_eq!(chunk.get_path("Level.Sections[0].Y"), Some(&Value::Byte(0)));
+    assert_eq!(chunk.get_path("Level.\"weird.key\".\"[0]\""), Some(&Value::Int(5)));
+    assert_eq!(chunk.get_path(r#"Level."weird.key"."say \"hi\"""#), Some(&Value::Int(6)));
+
+    let list = Value::List(vec![Value::Byte(1), Value::Byte(2)]);
+    assert_eq!(list.get_path("[1]"), Some(&Value::Byte(2)));
+}
+
+#[test]
+fn get_path_misses() {
+    let chunk = chunk();
+    // Missing entries and indices.
+    assert_eq!(chunk.get_path("Level.Entities"), None);
+    assert_eq!(chunk.get_path("Level.Sections[2]"), None);
+    // Values of the wrong type.
+    assert_eq!(chunk.get_path("Level[0]"), None);
+    assert_eq!(chunk.get_path("Level.Sections.Y"), None);
+    assert_eq!(chunk.get_path("Level.Heights[0]"), None);
+    // Malformed paths.
+    assert_eq!(chunk.get_path("Level..Sections"), None);
+    assert_eq!(chunk.get_path(".Level"), None);
+    assert_eq!(chunk.get_path("Level.Sections[x]"), None);
+    assert_eq!(chunk.get_path("Level.Sections[0"), None);
+    assert_eq!(chunk.get_path("Level.Sections[0]Y"), None);
+    assert_eq!(chunk.get_path("Level.\"weird.key"), None);
+}
+
+#[test]
+fn get_path_mut() {
+    let mut chunk = chunk();
+    *chunk.get_path_mut("Level.Sections[0].Palette[0].Name").unwrap() = Value::from("minecraft:water");
+    assert_eq!(chunk.get_path("Level.Sections[0].Palette[0].Name"),
+               Some(&Value::String("minecraft:water".to_string())));
+    assert_eq!(chunk.get_path_mut("Level.Sections[9]"), None);
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn blob_get_path() {
+    use nbt::{Blob, Endianness};
+
+    let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    let mut player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+
+    assert_eq!(player.get_path("Pos"), Some(&player["Pos"]));
+    assert!(player.get_path("Pos[2]").is_some());
+    assert_eq!(player.get_path(""), None);
+    assert_eq!(player.get_path("[0]"), None);
+
+    *player.get_path_mut("Pos[0]").unwrap() = Value::Double(1.5);
+    assert_eq!(player.get_path("Pos[0]"), Some(&Value::Double(1.5)));
+}
+
+fn paths(matches: nbt::Matches) -> Vec<String> {
+    matches.map(|(path, _)| path).collect()
+}
+
+#[test]
+fn select_wildcards() {
+    let chunk = chunk();
+    assert_eq!(paths(chunk.select("Level.Sections[*].Palette[*].Name").unwrap()), vec![
+        "Level.Sections[0].Palette[0].Name",
+        "Level.Sections[1].Palette[0].Name",
+        "Level.Sections[1].Palette[1].Name",
+    ]);
+    assert_eq!(paths(chunk.select("Level.*").unwrap()), vec![
+        "Level.Heights",
+        "Level.Sections",
+        "Level.\"weird.key\"",
+    ]);
+    assert_eq!(paths(chunk.select("Level.\"weird.key\".*").unwrap()), vec![
+        r#"Level."weird.key"."[0]""#,
+        r#"Level."weird.key"."say \"hi\"""#,
+    ]);
+    assert_eq!(paths(chunk.select("").unwrap()), vec![""]);
+
+    // Every path returned leads back to the matched value.
+    for (path, value) in chunk.select("*.*[*].*").unwrap() {
+        assert_eq!(chunk.get_path(&path), Some(value));
+    }
+    // Wildcards of the wrong kind match nothing.
+    assert_eq!(chunk.select("Level[*]").unwrap().count(), 0);
+    assert_eq!(chunk.select("Level.Sections.*").unwrap().count(), 0);
+}
+
+#[test]
+fn select_filters() {
+    let chunk = chunk();
+    assert_eq!(paths(chunk.select("Level.Sections[?Y==1b].Palette[*]").unwrap()), vec![
+        "Level.Sections[1].Palette[0]",
+        "Level.Sections[1].Palette[1]",
+    ]);
+    assert_eq!(paths(chunk.select("Level.Sections[*].Palette[?Name!=\"minecraft:air\"]").unwrap()), vec![
+        "Level.Sections[1].Palette[0]",
+        "Level.Sections[1].Palette[1]",
+    ]);
+    assert_eq!(paths(chunk.select("Level.Sections[?Palette[1]]").unwrap()), vec!["Level.Sections[1]"]);
+    // The tag must match too.
+    assert_eq!(chunk.select("Level.Sections[?Y==1]").unwrap().count(), 0);
+    assert_eq!(chunk.select("Level.Sections[?Missing!=1]").unwrap().count(), 0);
+}
+
+#[test]
+fn select_errors() {
+    let chunk = chunk();
+    let err = |selector: &str| chunk.select(selector).err().unwrap();
+    assert_eq!(err("Level..Sections"), Error::InvalidSelector(6, "expected a key".to_string()));
+    assert_eq!(err("Level[x]"), Error::InvalidSelector(6, "expected an index".to_string()));
+    assert_eq!(err("Level[0"), Error::InvalidSelector(7, "expected ']'".to_string()));
+    assert_eq!(err("Level[*]x"), Error::InvalidSelector(8, "expected '.' or '['".to_string()));
+    assert_eq!(err("Level[?a[*]]"), Error::InvalidSelector(9, "expected an index".to_string()));
+    match err("Level[?a=={b:}]") {
+        Error::InvalidSelector(pos, _) => assert!(pos > 10),
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn blob_select() {
+    use nbt::{Blob, Endianness, Selector};
+
+    let mut file = std::fs::File::open("tests/complex_player.dat").unwrap();
+    let player = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+
+    let selector: Selector = "Inventory[*].id".parse().unwrap();
+    let ids: Vec<_> = player.select("Inventory[*].id").unwrap().collect();
+    assert!(!ids.is_empty());
+    for (path, id) in ids {
+        assert_eq!(player.get_path(&path), Some(id));
+    }
+    assert_eq!(selector, Selector::parse("Inventory[*].id").unwrap());
+    assert_eq!(player.select("").unwrap().count(), 0);
+    assert!(player.select("*").unwrap().count() > 0);
+}
+
+#[test]
+fn walk() {
+    let chunk = chunk();
+    let walked: Vec<(String, &Value)> = chunk.walk().collect();
+    assert_eq!(walked[0], (String::new(), &chunk));
+    assert_eq!(walked.len(), 19);
+    for (path, value) in &walked {
+        assert_eq!(chunk.get_path(path), Some(*value));
+    }
+
+    // Entries are sorted by key unless their order is preserved.
+    #[cfg(not(feature = "preserve_order"))]
+    assert_eq!(walked.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>(), vec![
+        "",
+        "Level",
+        "Level.Heights",
+        "Level.Sections",
+        "Level.Sections[0]",
+        "Level.Sections[0].Palette",
+        "Level.Sections[0].Palette[0]",
+        "Level.Sections[0].Palette[0].Name",
+        "Level.Sections[0].Y",
+        "Level.Sections[1]",
+        "Level.Sections[1].Palette",
+        "Level.Sections[1].Palette[0]",
+        "Level.Sections[1].Palette[0].Name",
+        "Level.Sections[1].Palette[1]",
+        "Level.Sections[1].Palette[1].Name",
+        "Level.Sections[1].Y",
+        "Level.\"weird.key\"",
+        "Level.\"weird.key\".\"[0]\"",
+        "Level.\"weird.key\".\"say \\\"hi\\\"\"",
+    ]);
+
+    let blob = blob!({ a: { b: 1i8 }, c: [2i8] });
+    let paths: Vec<String> = blob.walk().map(|(path, _)| path).collect();
+    assert_eq!(paths, vec!["a", "a.b", "c", "c[0]"]);
+}
+
+#[test]
+fn walk_mut() {
+    let mut list = nbt!({ l: [{ a: 1i8 }, { drop: 1i8 }, { a: 2i8, drop: 1i8 }] });
+    let mut visited = Vec::new();
+    list.walk_mut(|path, value| {
+        visited.push(path.to_string());
+        match *value {
+            Value::Compound(ref map) if map.contains_key("drop") && map.len() == 1 => VisitAction::Remove,
+            Value::Compound(_) | Value::List(_) => VisitAction::Continue,
+            _ if path.ends_with("drop") => VisitAction::Remove,
+            _ => VisitAction::Continue,
+        }
+    });
+    assert_eq!(list, nbt!({ l: [{ a: 1i8 }, { a: 2i8 }] }));
+    // Siblings are visited before the values in them, which have the
+    // indices left after removals.
+    assert_eq!(visited, ["l", "l[0]", "l[1]", "l[2]", "l[0].a", "l[1].a", "l[1].drop"]);
+
+    let mut chunk = chunk();
+    let mut count = 0;
+    chunk.walk_mut(|path, _| {
+        count += 1;
+        if path.ends_with("Sections") { VisitAction::Skip } else { VisitAction::Continue }
+    });
+    assert_eq!(count, 6);
+    assert_eq!(chunk, self::chunk());
+
+    let mut count = 0;
+    chunk.walk_mut(|_, value| {
+        count += 1;
+        *value = Value::Byte(0);
+        VisitAction::Stop
+    });
+    assert_eq!(count, 1);
+    assert_eq!(chunk, nbt!({ Level: 0i8 }));
+
+    let mut blob = blob!({ a: [1i32, 2i32, 3i32], b: { c: 4i32 } });
+    blob.walk_mut(|_, value| match *value {
+        Value::Int(ref mut n) if *n % 2 == 0 => VisitAction::Remove,
+        Value::Int(ref mut n) => {
+            *n *= 10;
+            VisitAction::Continue
+        },
+        _ => VisitAction::Continue,
+    });
+    assert_eq!(blob, blob!({ a: [10i32, 30i32], b: {} }));
+}
+
+#[test]
+fn pointers() {
+    let mut chunk = chunk();
+    assert_eq!(chunk.pointer(""), Some(&chunk));
+    assert_eq!(chunk.pointer("Level/Sections/1/Palette/0/Name"), chunk.get_path("Level.Sections[1].Palette[0].Name"));
+    assert_eq!(chunk.pointer("/Level/weird.key/[0]"), Some(&Value::Int(5)));
+    assert_eq!(chunk.pointer("Level/Sections/01"), None);
+    assert_eq!(chunk.pointer("Level/Sections/-1"), None);
+    assert_eq!(chunk.pointer("Level/Sections/2"), None);
+    assert_eq!(chunk.pointer("Level/Heights/0"), None);
+    assert_eq!(chunk.pointer("Level/Missing"), None);
+
+    let mut escaped = nbt!({ "a/b": { "c~d": 1i8 } });
+    assert_eq!(escaped.pointer("a~1b/c~0d"), Some(&Value::Byte(1)));
+    *escaped.pointer_mut("a~1b/c~0d").unwrap() = Value::Byte(2);
+    assert_eq!(escaped, nbt!({ "a/b": { "c~d": 2i8 } }));
+
+    let section = chunk.pointer_mut("Level/Sections/0").unwrap().take();
+    assert_eq!(section, nbt!({ Y: 0i8, Palette: [{ Name: "minecraft:air" }] }));
+    assert_eq!(chunk.get_path("Level.Sections[0]"), Some(&nbt!({})));
+    let old = chunk.pointer_mut("Level/Sections/0").unwrap().replace(section.clone());
+    assert_eq!(old, nbt!({}));
+    assert_eq!(chunk, self::chunk());
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use std::fs::File;
+
+use nbt::formats::player::{ItemStack, Player, ARMOR_SLOTS};
+use nbt::{Blob, Endianness, Value};
+
+fn legacy_player() -> Value {
+    let mut src = File::open("tests/level.dat").unwrap();
+    let level = Value::from(Blob::from_gzip_reader(&mut src, Endianness::BigEndian).unwrap());
+    level["Data"]["Player"].clone()
+}
+
+#[test]
+fn legacy_inventory() {
+    let player: Player = nbt::from_value(legacy_player()).unwrap();
+    assert_eq!(player.health, 20.0);
+    assert_eq!(player.pos[1], 67.0);
+    assert_eq!(player.rotation.len(), 2);
+    assert_eq!(player.dimension, Some(Value::Int(0)));
+    assert!(player.abilities.may_build);
+    assert_eq!(player.abilities.walk_speed, 0.1);
+    assert!(player.ender_items.is_empty());
+
+    let sword = player.selected_item().unwrap();
+    assert_eq!(sword.id, "minecraft:diamond_sword");
+    assert_eq!(sword.count(), 1);
+    assert_eq!(sword.damage, Some(1196));
+    assert_eq!(sword.tag.as_ref().unwrap()["RepairCost"], Value::Int(9));
+    assert_eq!(player.item_in_slot(15).unwrap().count(), 64);
+    assert_eq!(player.item_in_slot(ARMOR_SLOTS[0]).unwrap().id, "minecraft:diamond_boots");
+}
+
+#[test]
+fn item_stacks() {
+    let modern = nbt!({
+        id: "minecraft:diamond_pickaxe",
+        count: 1i32,
+        Slot: 3i8,
+        components: { "minecraft:damage": 12i32, "minecraft:repair_cost": 1i32 },
+    });
+    let mut stack: ItemStack = nbt::from_value(modern.clone()).unwrap();
+    assert_eq!(stack.count(), 1);
+    assert_eq!(stack.components.as_ref().unwrap()["minecraft:damage"], Value::Int(12));
+    assert_eq!(nbt::to_value(&stack).unwrap(), modern);
+    stack.set_count(2);
+    assert_eq!(stack.count, Some(2));
+
+    let mut legacy: ItemStack = nbt::from_value(nbt!({ id: "minecraft:stone", Count: 5i8 })).unwrap();
+    legacy.set_count(64);
+    assert_eq!(nbt::to_value(&legacy).unwrap(), nbt!({ id: "minecraft:stone", Count: 64i8 }));
+    legacy.set_count(200);
+    assert_eq!(nbt::to_value(&legacy).unwrap(), nbt!({ id: "minecraft:stone", count: 200i32 }));
+
+    let bare: ItemStack = nbt::from_value(nbt!({ id: "minecraft:apple" })).unwrap();
+    assert_eq!(bare.count(), 1);
+}
+
+#[test]
+fn modern_player() {
+    let file = nbt!({
+        DataVersion: 3700i32,
+        Pos: [0.5f64, 64.0f64, 0.5f64],
+        Rotation: [90.0f32, 0.0f32],
+        Dimension: "minecraft:the_nether",
+        Health: 14.5f32,
+        playerGameType: 1i32,
+        SelectedItemSlot: 2i32,
+        Inventory: [{ id: "minecraft:torch", count: 32i32, Slot: 2i8 }],
+        EnderItems: [{ id: "minecraft:elytra", count: 1i32, Slot: 0i8 }],
+        abilities: { flying: 1i8, mayfly: 1i8, flySpeed: 0.05f32 },
+        recipeBook: { isGuiOpen: 0i8 },
+    });
+    let mut player: Player = nbt::from_value(file.clone()).unwrap();
+    assert_eq!(player.dimension, Some(Value::from("minecraft:the_nether")));
+    assert_eq!(player.selected_item().unwrap().count(), 32);
+    assert!(player.abilities.flying);
+
+    player.ender_items.push(ItemStack::new("minecraft:totem_of_undying", 1).in_slot(1));
+    player.health = 20.0;
+    let mut edited = file.clone();
+    player.write_into(&mut edited).unwrap();
+    assert_eq!(edited["recipeBook"], file["recipeBook"]);
+    assert_eq!(edited["Health"], Value::Float(20.0));
+    assert_eq!(edited["EnderItems"][1]["id"], Value::from("minecraft:totem_of_undying"));
+
+    let mut bytes = Vec::new();
+    player.to_gzip_writer(&mut bytes).unwrap();
+    assert_eq!(Player::from_gzip_reader(&bytes[..]).unwrap(), player);
+}
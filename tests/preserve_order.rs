@@ -0,0 +1,44 @@
+//! Tests for the `preserve_order` feature.
+#![cfg(feature = "preserve_order")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::{Blob, Endianness, Value};
+
+fn keys(value: &Value) -> Vec<&str> {
+    match *value {
+        Value::Compound(ref map) => map.keys().map(|k| k.as_str()).collect(),
+        _ => panic!("not a Compound"),
+    }
+}
+
+#[test]
+fn keys_keep_order_through_roundtrip() {
+    let blob = blob!({ data: { zeta: 1i8, alpha: 2i8, mu: { c: 1i8, b: 2i8, a: 3i8 }, beta: 4i8 } });
+
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let read = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(keys(&read["data"]), ["zeta", "alpha", "mu", "beta"]);
+    assert_eq!(keys(read.get_path("data.mu").unwrap()), ["c", "b", "a"]);
+
+    let mut rewritten = Vec::new();
+    read.to_writer(&mut rewritten, Endianness::BigEndian).unwrap();
+    assert_eq!(rewritten, bytes);
+}
+
+#[test]
+fn removal_keeps_order() {
+    let mut value = nbt!({ c: 1i8, b: 2i8, a: 3i8 });
+    let patch = nbt::diff(&value, &nbt!({ c: 1i8, a: 3i8 }));
+    value.apply_patch(&patch).unwrap();
+    assert_eq!(keys(&value), ["c", "a"]);
+}
+
+#[test]
+fn walk_keeps_order() {
+    let value = nbt!({ zeta: 1i8, alpha: { c: 1i8, b: 2i8 }, mu: [3i8] });
+    let paths: Vec<String> = value.walk().map(|(path, _)| path).collect();
+    assert_eq!(paths, ["", "zeta", "alpha", "alpha.c", "alpha.b", "mu", "mu[0]"]);
+}
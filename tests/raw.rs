@@ -0,0 +1,310 @@
+//! Tests for the public low-level `raw` module.
+#![cfg(feature = "std")]
+
+extern crate nbt;
+
+use std::error::Error as StdError;
+
+use nbt::raw::{Endianness, RawReader, RawWriter};
+use nbt::{Blob, Error, Value};
+
+#[test]
+fn write_compound_by_hand() {
+    let mut blob = Blob::named("root");
+    blob.insert("health", 20i16).unwrap();
+    let mut expected = Vec::new();
+    blob.to_writer(&mut expected, Endianness::LittleEndian).unwrap();
+
+    let mut dst = RawWriter::new(Vec::new(), Endianness::LittleEndian);
+    dst.write_header(0x0a, "root").unwrap();
+    dst.begin_compound();
+    assert_eq!(dst.depth(), 1);
+    dst.write_header(0x02, "health").unwrap();
+    dst.write_bare_short(20).unwrap();
+    dst.close_nbt().unwrap();
+    assert_eq!(dst.depth(), 0);
+    assert_eq!(dst.into_inner(), expected);
+}
+
+#[test]
+fn byte_layout_per_endianness() {
+    let mut big = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    big.write_bare_int(0x01020304).unwrap();
+    big.write_bare_string("ab").unwrap();
+    assert_eq!(big.into_inner(), vec![0x01, 0x02, 0x03, 0x04, 0x00, 0x02, 0x61, 0x62]);
+
+    let mut little = RawWriter::new(Vec::new(), Endianness::LittleEndian);
+    little.write_bare_int(0x01020304).unwrap();
+    little.write_bare_string("ab").unwrap();
+    assert_eq!(little.into_inner(), vec![0x04, 0x03, 0x02, 0x01, 0x02, 0x00, 0x61, 0x62]);
+}
+
+#[test]
+fn network_layout() {
+    let mut dst = RawWriter::new(Vec::new(), Endianness::NetworkLittleEndian);
+    dst.write_bare_int(0).unwrap();
+    dst.write_bare_int(-1).unwrap();
+    dst.write_bare_int(150).unwrap();
+    dst.write_bare_int(i32::MIN).unwrap();
+    dst.write_bare_long(-65).unwrap();
+    dst.write_bare_long(i64::MAX).unwrap();
+    dst.write_bare_short(0x0102).unwrap();
+    dst.write_bare_string("ab").unwrap();
+    dst.write_bare_int_array(&[1, -2]).unwrap();
+    let bytes = dst.into_inner();
+    assert_eq!(bytes, vec![
+        0x00,
+        0x01,
+        0xac, 0x02,
+        0xff, 0xff, 0xff, 0xff, 0x0f,
+        0x81, 0x01,
+        0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        0x02, 0x01,
+        0x02, 0x61, 0x62,
+        0x04, 0x02, 0x03,
+    ]);
+
+    let mut src = RawReader::new(&bytes[..], Endianness::NetworkLittleEndian);
+    assert_eq!(src.read_bare_int().unwrap(), 0);
+    assert_eq!(src.read_bare_int().unwrap(), -1);
+    assert_eq!(src.read_bare_int().unwrap(), 150);
+    assert_eq!(src.read_bare_int().unwrap(), i32::MIN);
+    assert_eq!(src.read_bare_long().unwrap(), -65);
+    assert_eq!(src.read_bare_long().unwrap(), i64::MAX);
+    assert_eq!(src.read_bare_short().unwrap(), 0x0102);
+    assert_eq!(src.read_bare_string().unwrap(), "ab");
+    assert_eq!(src.read_bare_int_array().unwrap(), vec![1, -2]);
+    assert!(src.into_inner().is_empty());
+
+    let overlong = [0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+    let mut src = RawReader::new(&overlong[..], Endianness::NetworkLittleEndian);
+    assert_eq!(src.read_bare_int(), Err(Error::InvalidVarInt));
+}
+
+#[test]
+fn large_arrays() {
+    // Long enough to be read in several chunks, with a partial one at the end.
+    let ints: Vec<i32> = (0..10_000).map(|i: i32| i.wrapping_mul(0x0101_0101) - 7).collect();
+    let longs: Vec<i64> = ints.iter().map(|&i| i64::from(i) << 24 | 0xab).collect();
+    let bytes: Vec<i8> = ints.iter().map(|&i| i as i8).collect();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let mut dst = RawWriter::new(Vec::new(), endian);
+        dst.write_bare_int_array(&ints).unwrap();
+        dst.write_bare_long_array(&longs).unwrap();
+        dst.write_bare_byte_array(&bytes).unwrap();
+        let encoded = dst.into_inner();
+
+        let mut src = RawReader::new(&encoded[..], endian);
+        assert_eq!(src.read_bare_int_array().unwrap(), ints);
+        assert_eq!(src.read_bare_long_array().unwrap(), longs);
+        assert_eq!(src.read_bare_byte_array().unwrap(), bytes);
+        assert!(src.into_inner().is_empty());
+
+        let truncated = &encoded[..encoded.len() - 1];
+        let mut src = RawReader::new(truncated, endian);
+        src.read_bare_int_array().unwrap();
+        src.read_bare_long_array().unwrap();
+        assert_eq!(src.read_bare_byte_array(), Err(Error::IncompleteNbtValue));
+    }
+
+    let mut src = RawReader::new(&[0, 0, 0, 2, 1, 2, 3, 4, 0xff, 0xff, 0xff, 0xfe][..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_int_array().unwrap(), vec![0x01020304, -2]);
+}
+
+/// A destination that counts how many writes it receives.
+struct CountingWriter(Vec<u8>, usize);
+
+impl std::io::Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.1 += 1;
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn arrays_are_written_at_once() {
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let mut dst = RawWriter::new(CountingWriter(Vec::new(), 0), endian);
+        dst.write_bare_long_array(&[1 << 40; 1000]).unwrap();
+        dst.write_bare_int_array(&[-1; 1000]).unwrap();
+        dst.write_bare_byte_array(&[7; 1000]).unwrap();
+        let dst = dst.into_inner();
+        // One write for each length, and one for each array's elements.
+        assert_eq!(dst.1, 6);
+
+        let mut src = RawReader::new(&dst.0[..], endian);
+        assert_eq!(src.read_bare_long_array().unwrap(), vec![1 << 40; 1000]);
+        assert_eq!(src.read_bare_int_array().unwrap(), vec![-1; 1000]);
+        assert_eq!(src.read_bare_byte_array().unwrap(), vec![7; 1000]);
+    }
+}
+
+#[test]
+fn error_locations() {
+    let palette = Value::List(vec![Value::String("minecraft:air".into()), Value::String("minecraft:stone".into())]);
+    let section = Value::Compound(vec![("Palette".to_string(), palette)].into_iter().collect());
+    let sections = Value::List(vec![section.clone(), section.clone(), section]);
+    let level = Value::Compound(vec![("Sections".to_string(), sections)].into_iter().collect());
+    let mut blob = Blob::new();
+    blob.insert("Level", level).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    // Cut off in the middle of the last string, which ends 3 bytes before
+    // the document, so that reading its 15 bytes fails.
+    let len = bytes.len() - 8;
+    let err = Blob::from_reader(&mut &bytes[..len], Endianness::BigEndian).unwrap_err();
+    assert_eq!(err.path(), Some("Level.Sections[2].Palette[1]"));
+    assert_eq!(err.offset(), Some(len as u64 - 10));
+    assert_eq!(err.to_string(),
+               format!("data does not represent a complete NbtValue at offset {} in 'Level.Sections[2].Palette[1]'", len - 10));
+    assert_eq!(err.without_location(), Error::IncompleteNbtValue);
+
+    let bytes = [0x0a, 0x00, 0x00, 0x0f, 0x00, 0x01, b'x'];
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    let err = Blob::from_raw_reader(&mut src).unwrap_err();
+    assert_eq!((err.offset(), err.path()), (Some(7), Some("x")));
+    assert!(matches!(err.without_location(), Error::InvalidTypeId(0x0f)));
+
+    let err = Value::from_reader(0x09, &mut &[0x03, 0x00, 0x00, 0x00, 0x02, 0x00][..], Endianness::BigEndian).unwrap_err();
+    assert_eq!((err.offset(), err.path()), (Some(5), Some("[0]")));
+    let inner = err.source().map(ToString::to_string);
+    assert_eq!(inner, Some(Error::IncompleteNbtValue.to_string()));
+    // Located errors only compare equal when they happened in the same place.
+    assert_eq!(err, Error::Located(5, "[0]".to_string(), Box::new(Error::IncompleteNbtValue)));
+    assert_ne!(err, Error::Located(5, "[1]".to_string(), Box::new(Error::IncompleteNbtValue)));
+    assert_ne!(err, Error::IncompleteNbtValue);
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn network_roundtrip() {
+    let mut file = std::fs::File::open("tests/big1.nbt").unwrap();
+    let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+    let mut dst = Vec::new();
+    blob.to_writer(&mut dst, Endianness::NetworkLittleEndian).unwrap();
+    let read = Blob::from_reader(&mut &dst[..], Endianness::NetworkLittleEndian).unwrap();
+    assert_eq!(read, blob);
+}
+
+#[test]
+fn read_value_after_framing() {
+    let bytes = vec![0xff, 0x03, 0x00, 0x01, 0x78, 0x00, 0x00, 0x00, 0x2a];
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_byte().unwrap(), -1);
+    let (tag, name) = src.emit_next_header().unwrap();
+    assert_eq!((tag, &name[..]), (0x03, "x"));
+    assert_eq!(Value::from_raw_reader(tag, &mut src).unwrap(), Value::Int(42));
+    assert!(src.into_inner().is_empty());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "close_nbt() called without a matching begin_compound()")]
+fn unmatched_close_nbt() {
+    let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    dst.write_header(0x0a, "").unwrap();
+    dst.close_nbt().unwrap();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "missing TAG_End")]
+fn missing_tag_end() {
+    let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    dst.write_header(0x0a, "").unwrap();
+    dst.begin_compound();
+    dst.into_inner();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "TAG_End must be written with close_nbt()")]
+fn tag_end_header() {
+    let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    dst.write_header(0x00, "").unwrap();
+}
+
+#[test]
+fn sorted_keys() {
+    let write = |order: &[&str]| {
+        let mut blob = Blob::new();
+        let mut inner = nbt::Map::new();
+        for key in order {
+            blob.insert(*key, 1i8).unwrap();
+            inner.insert(key.to_string(), Value::Byte(2));
+        }
+        blob.insert("z", Value::Compound(inner)).unwrap();
+        let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+        dst.sort_keys(true);
+        assert!(dst.sorts_keys());
+        blob.to_raw_writer(&mut dst).unwrap();
+        dst.into_inner()
+    };
+
+    let bytes = write(&["c", "a", "b"]);
+    assert_eq!(bytes, write(&["b", "c", "a"]));
+    assert_eq!(bytes, vec![
+        0x0a, 0x00, 0x00,
+            0x01, 0x00, 0x01, b'a', 0x01,
+            0x01, 0x00, 0x01, b'b', 0x01,
+            0x01, 0x00, 0x01, b'c', 0x01,
+            0x0a, 0x00, 0x01, b'z',
+                0x01, 0x00, 0x01, b'a', 0x02,
+                0x01, 0x00, 0x01, b'b', 0x02,
+                0x01, 0x00, 0x01, b'c', 0x02,
+            0x00,
+        0x00,
+    ]);
+}
+
+#[test]
+fn string_bytes_and_lossy_strings() {
+    let bytes = [0x00, 0x06, b'a', 0xc0, 0x80, 0xed, 0xa0, b'b'];
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_string_bytes().unwrap(), &bytes[2..]);
+
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    assert_eq!(src.read_bare_string(), Err(Error::InvalidUtf8));
+
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    src.set_lossy_strings(true);
+    assert!(src.lossy_strings());
+    assert_eq!(src.read_bare_string().unwrap(), "a\0\u{fffd}\u{fffd}b");
+}
+
+#[test]
+fn interned_strings() {
+    use std::sync::Arc;
+
+    let mut dst = RawWriter::new(Vec::new(), Endianness::BigEndian);
+    let long = "k".repeat(65);
+    for s in &["id", "Count", "id", &long, &long] {
+        dst.write_bare_string(s).unwrap();
+    }
+    dst.write_bare_string("\u{0}").unwrap();
+    dst.write_bare_string("\u{0}").unwrap();
+    let bytes = dst.into_inner();
+
+    let mut src = RawReader::new(&bytes[..], Endianness::BigEndian);
+    assert!(!src.interns_keys());
+    src.set_intern_keys(true);
+    let id = src.read_interned_string().unwrap();
+    assert_eq!(&*src.read_interned_string().unwrap(), "Count");
+    assert!(Arc::ptr_eq(&id, &src.read_interned_string().unwrap()));
+    // Long strings are not kept.
+    let first = src.read_interned_string().unwrap();
+    let second = src.read_interned_string().unwrap();
+    assert_eq!(first, second);
+    assert!(!Arc::ptr_eq(&first, &second));
+
+    // Modified UTF-8 is decoded, and decoded afresh once the encoding changes.
+    assert_eq!(&*src.read_interned_string().unwrap(), "\u{0}");
+    src.set_lossy_strings(true);
+    src.set_string_encoding(nbt::raw::StringEncoding::Utf8);
+    assert_eq!(&*src.read_interned_string().unwrap(), "\u{fffd}\u{fffd}");
+}
@@ -0,0 +1,337 @@
+//! Tests for reading Anvil region files.
+#![cfg(any(feature = "compression", feature = "rust_backend"))]
+
+extern crate byteorder;
+extern crate nbt;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+use std::io::Cursor;
+
+use byteorder::{BigEndian, WriteBytesExt};
+
+use nbt::region::{Compression, RegionFile};
+use nbt::{Blob, Endianness, Error, Value, WriteOptions};
+
+fn chunk_blob(x: i32, z: i32) -> Blob {
+    let mut blob = Blob::new();
+    blob.insert("xPos", x).unwrap();
+    blob.insert("zPos", z).unwrap();
+    blob.insert("Status", "minecraft:full").unwrap();
+    blob
+}
+
+fn compress(blob: &Blob, compression: Compression) -> Vec<u8> {
+    let mut data = Vec::new();
+    let options = WriteOptions::new(Endianness::BigEndian).compression(compression);
+    blob.to_writer_with(&mut data, &options).unwrap();
+    data
+}
+
+/// Lays out a region file by hand, with each chunk padded to whole sectors.
+fn build_region(chunks: &[(i32, i32, u8, Vec<u8>)]) -> Vec<u8> {
+    let mut locations = vec![0u32; 1024];
+    let mut body = Vec::new();
+    for &(x, z, compression, ref data) in chunks {
+        let offset = 2 + body.len() / 4096;
+        body.write_u32::<BigEndian>(data.len() as u32 + 1).unwrap();
+        body.push(compression);
+        body.extend_from_slice(data);
+        while body.len() % 4096 != 0 {
+            body.push(0);
+        }
+        let sectors = 2 + body.len() / 4096 - offset;
+        locations[(x + z * 32) as usize] = (offset as u32) << 8 | sectors as u32;
+    }
+    let mut file = Vec::new();
+    for location in locations {
+        file.write_u32::<BigEndian>(location).unwrap();
+    }
+    file.extend_from_slice(&[0; 4096]);
+    file.extend_from_slice(&body);
+    file
+}
+
+#[test]
+fn read_chunks_by_coordinate() {
+    let file = build_region(&[
+        (0, 0, 2, compress(&chunk_blob(0, 0), Compression::Zlib)),
+        (5, 3, 1, compress(&chunk_blob(5, 3), Compression::Gzip)),
+        (31, 31, 3, compress(&chunk_blob(31, 31), Compression::Uncompressed)),
+    ]);
+    let mut region = RegionFile::new(Cursor::new(file)).unwrap();
+
+    assert!(region.has_chunk(5, 3));
+    assert!(!region.has_chunk(3, 5));
+    assert_eq!(region.read_chunk(5, 3).unwrap(), Some(chunk_blob(5, 3)));
+    assert_eq!(region.read_chunk(0, 0).unwrap(), Some(chunk_blob(0, 0)));
+    assert_eq!(region.read_chunk(3, 5).unwrap(), None);
+    // World chunk coordinates are reduced modulo 32.
+    assert_eq!(region.read_chunk(-1, 63).unwrap(), Some(chunk_blob(31, 31)));
+}
+
+#[test]
+fn iterate_chunks() {
+    // A chunk spanning several sectors.
+    let mut big = chunk_blob(7, 1);
+    big.insert("Data", Value::LongArray((0..2000).collect())).unwrap();
+    let file = build_region(&[
+        (2, 0, 2, compress(&chunk_blob(2, 0), Compression::Zlib)),
+        (7, 1, 3, compress(&big, Compression::Uncompressed)),
+        (1, 0, 2, compress(&chunk_blob(1, 0), Compression::Zlib)),
+    ]);
+    let mut region = RegionFile::new(Cursor::new(file)).unwrap();
+    let chunks: Vec<_> = region.chunks().map(|c| c.unwrap()).collect();
+    assert_eq!(chunks, vec![
+        (1, 0, chunk_blob(1, 0)),
+        (2, 0, chunk_blob(2, 0)),
+        (7, 1, big),
+    ]);
+}
+
+#[test]
+fn corrupt_regions() {
+    assert!(RegionFile::new(Cursor::new(vec![0; 100])).is_err());
+
+    let mut file = build_region(&[(1, 2, 5, vec![0; 10])]);
+    let mut region = RegionFile::new(Cursor::new(file.clone())).unwrap();
+    assert_eq!(region.read_chunk(1, 2), Err(Error::UnknownChunkCompression(5)));
+
+    // Point the chunk into the header.
+    file[(1 + 2 * 32) * 4 + 2] = 1;
+    let mut region = RegionFile::new(Cursor::new(file.clone())).unwrap();
+    assert_eq!(region.read_chunk(1, 2),
+               Err(Error::CorruptChunk(1, 2, "sector offset points into the header")));
+
+    // Claim a length longer than the allocated sector.
+    let mut file = build_region(&[(1, 2, 2, vec![0; 10])]);
+    file[8192] = 0x7f;
+    let mut region = RegionFile::new(Cursor::new(file.clone())).unwrap();
+    assert_eq!(region.chunks().next().unwrap(),
+               Err(Error::CorruptChunk(1, 2, "chunk is longer than its allocated sectors")));
+
+    // Truncate the file inside the chunk.
+    let file = build_region(&[(1, 2, 2, vec![0; 10])]);
+    let mut region = RegionFile::new(Cursor::new(file[..8200].to_vec())).unwrap();
+    assert_eq!(region.read_chunk(1, 2),
+               Err(Error::CorruptChunk(1, 2, "chunk extends past the end of the file")));
+}
+
+fn location(region: &RegionFile<Cursor<Vec<u8>>>, x: usize, z: usize) -> (u32, u32) {
+    let bytes = &region.get_ref().get_ref()[(x + z * 32) * 4..];
+    let entry = (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32;
+    (entry >> 8, entry & 0xff)
+}
+
+/// A chunk that compresses poorly, occupying roughly `kib` KiB.
+fn large_chunk(kib: usize) -> Blob {
+    let mut blob = Blob::new();
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let data = (0..kib * 128).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as i64
+    });
+    blob.insert("Data", Value::LongArray(data.collect())).unwrap();
+    blob
+}
+
+#[test]
+fn write_and_read_back() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(0, 0, &chunk_blob(0, 0), Compression::Zlib).unwrap();
+    region.write_chunk(4, 9, &chunk_blob(4, 9), Compression::Gzip).unwrap();
+    region.write_chunk(-1, -1, &chunk_blob(31, 31), Compression::Uncompressed).unwrap();
+    assert_eq!(region.get_ref().get_ref().len(), 5 * 4096);
+    assert_eq!(location(&region, 0, 0), (2, 1));
+    assert_eq!(location(&region, 4, 9), (3, 1));
+    assert_eq!(location(&region, 31, 31), (4, 1));
+
+    // Re-open from the written bytes.
+    let bytes = region.into_inner().into_inner();
+    let mut region = RegionFile::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(region.read_chunk(4, 9).unwrap(), Some(chunk_blob(4, 9)));
+    let chunks: Vec<_> = region.chunks().map(|c| c.unwrap()).collect();
+    assert_eq!(chunks, vec![
+        (0, 0, chunk_blob(0, 0)),
+        (4, 9, chunk_blob(4, 9)),
+        (31, 31, chunk_blob(31, 31)),
+    ]);
+}
+
+#[test]
+fn sector_allocation() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(0, 0, &chunk_blob(0, 0), Compression::Zlib).unwrap();
+    region.write_chunk(1, 0, &chunk_blob(1, 0), Compression::Zlib).unwrap();
+
+    // Growing the first chunk moves it to the end of the file.
+    let big = large_chunk(10);
+    region.write_chunk(0, 0, &big, Compression::Zlib).unwrap();
+    assert_eq!(location(&region, 0, 0), (4, 3));
+    assert_eq!(region.get_ref().get_ref().len(), 7 * 4096);
+
+    // Its old sector is reused by a new chunk.
+    region.write_chunk(2, 0, &chunk_blob(2, 0), Compression::Zlib).unwrap();
+    assert_eq!(location(&region, 2, 0), (2, 1));
+
+    // Shrinking it again happens in place.
+    region.write_chunk(0, 0, &chunk_blob(0, 0), Compression::Zlib).unwrap();
+    assert_eq!(location(&region, 0, 0), (4, 1));
+
+    // Removed chunks free their sectors.
+    region.remove_chunk(1, 0).unwrap();
+    assert!(!region.has_chunk(1, 0));
+    assert_eq!(region.read_chunk(1, 0).unwrap(), None);
+    region.write_chunk(3, 0, &chunk_blob(3, 0), Compression::Zlib).unwrap();
+    assert_eq!(location(&region, 3, 0), (3, 1));
+
+    assert_eq!(region.read_chunk(0, 0).unwrap(), Some(chunk_blob(0, 0)));
+    assert_eq!(region.read_chunk(2, 0).unwrap(), Some(chunk_blob(2, 0)));
+    assert_eq!(region.read_chunk(3, 0).unwrap(), Some(chunk_blob(3, 0)));
+}
+
+#[test]
+fn oversized_chunks_are_rejected() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    assert_eq!(region.write_chunk(33, 2, &large_chunk(1100), Compression::Zlib),
+               Err(Error::ChunkTooLarge(1, 2)));
+    assert!(!region.has_chunk(1, 2));
+}
+
+#[test]
+fn open_rw_on_disk() {
+    let path = std::env::temp_dir().join(format!("nbt-region-test-{}.mca", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    {
+        let mut region = RegionFile::open_rw(&path).unwrap();
+        region.write_chunk(5, 5, &chunk_blob(5, 5), Compression::Zlib).unwrap();
+        region.flush().unwrap();
+    }
+    {
+        let mut region = RegionFile::open_rw(&path).unwrap();
+        region.write_chunk(6, 5, &chunk_blob(6, 5), Compression::Zlib).unwrap();
+    }
+    let mut region = RegionFile::open(&path).unwrap();
+    assert_eq!(region.read_chunk(5, 5).unwrap(), Some(chunk_blob(5, 5)));
+    assert_eq!(region.read_chunk(6, 5).unwrap(), Some(chunk_blob(6, 5)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn compact_drops_dead_sectors() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(0, 0, &chunk_blob(0, 0), Compression::Zlib).unwrap();
+    region.write_chunk(1, 0, &large_chunk(10), Compression::Zlib).unwrap();
+    region.write_chunk(2, 0, &chunk_blob(2, 0), Compression::Zlib).unwrap();
+    // Unknown compression schemes are copied verbatim.
+    region.write_chunk(3, 0, &chunk_blob(3, 0), Compression::Gzip).unwrap();
+    let gzip_record = 4096 * location(&region, 3, 0).0 as usize;
+    region.get_mut().get_mut()[gzip_record + 4] = 99;
+    // Leave holes behind: a removed chunk and a chunk that moved.
+    region.remove_chunk(0, 0).unwrap();
+    region.write_chunk(2, 0, &large_chunk(6), Compression::Zlib).unwrap();
+    assert_eq!(region.get_ref().get_ref().len(), 10 * 4096);
+
+    let len = region.compact().unwrap();
+    assert_eq!(len, 8 * 4096);
+    assert_eq!(location(&region, 1, 0), (2, 3));
+    assert_eq!(location(&region, 3, 0), (5, 1));
+    assert_eq!(location(&region, 2, 0), (6, 2));
+
+    let mut bytes = region.into_inner().into_inner();
+    bytes.truncate(len as usize);
+    let mut region = RegionFile::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(region.read_chunk(0, 0).unwrap(), None);
+    assert_eq!(region.read_chunk(1, 0).unwrap(), Some(large_chunk(10)));
+    assert_eq!(region.read_chunk(2, 0).unwrap(), Some(large_chunk(6)));
+    assert_eq!(region.read_chunk(3, 0), Err(Error::UnknownChunkCompression(99)));
+}
+
+#[test]
+fn compact_leaves_corrupt_regions_untouched() {
+    let mut file = build_region(&[(0, 0, 2, vec![0; 10]), (1, 0, 2, vec![0; 10])]);
+    file[4096 * 3] = 0x7f;
+    let mut region = RegionFile::new(Cursor::new(file.clone())).unwrap();
+    assert_eq!(region.compact(),
+               Err(Error::CorruptChunk(1, 0, "chunk is longer than its allocated sectors")));
+    assert_eq!(region.into_inner().into_inner(), file);
+}
+
+#[test]
+fn oversized_chunks_use_mcc_files() {
+    let dir = std::env::temp_dir().join(format!("nbt-region-mcc-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("r.-1.2.mca");
+    let mcc = dir.join("c.-31.66.mcc");
+    let huge = large_chunk(1100);
+    {
+        let mut region = RegionFile::create(&path).unwrap();
+        region.write_chunk(1, 2, &huge, Compression::Zlib).unwrap();
+        region.write_chunk(2, 2, &chunk_blob(2, 2), Compression::Zlib).unwrap();
+    }
+    assert!(mcc.exists());
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 4 * 4096);
+
+    let mut region = RegionFile::open_rw(&path).unwrap();
+    assert_eq!(region.read_chunk(1, 2).unwrap(), Some(huge.clone()));
+    let chunks: Vec<_> = region.chunks().map(|c| c.unwrap()).collect();
+    assert_eq!(chunks, vec![(1, 2, huge.clone()), (2, 2, chunk_blob(2, 2))]);
+
+    // Without knowing where the region lives, the chunk cannot be found.
+    let file = std::fs::File::open(&path).unwrap();
+    let mut anonymous = RegionFile::new(file).unwrap();
+    assert_eq!(anonymous.read_chunk(1, 2), Err(Error::ExternalChunk(1, 2)));
+    let file = std::fs::File::open(&path).unwrap();
+    let mut located = RegionFile::new(file).unwrap().with_external_dir(&dir, -1, 2);
+    assert_eq!(located.read_chunk(1, 2).unwrap(), Some(huge));
+
+    // Rewriting the chunk at a normal size deletes its external file.
+    region.write_chunk(1, 2, &chunk_blob(1, 2), Compression::Zlib).unwrap();
+    assert!(!mcc.exists());
+    assert_eq!(region.read_chunk(1, 2).unwrap(), Some(chunk_blob(1, 2)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn chunk_timestamps() {
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    region.write_chunk(3, 1, &chunk_blob(3, 1), Compression::Zlib).unwrap();
+    region.write_chunk(0, 2, &chunk_blob(0, 2), Compression::Zlib).unwrap();
+    assert!(region.timestamp(3, 1) > 1_600_000_000);
+    assert_eq!(region.timestamp(5, 5), 0);
+
+    region.set_timestamp(3, 1, 1234).unwrap();
+    region.set_timestamp(0, 2, 5678).unwrap();
+    assert_eq!(region.timestamp(35, -31), 1234);
+    assert_eq!(region.timestamps().collect::<Vec<_>>(), vec![(3, 1, 1234), (0, 2, 5678)]);
+
+    // The table is persisted, and survives removal of other chunks.
+    region.remove_chunk(3, 1).unwrap();
+    let region = RegionFile::new(Cursor::new(region.into_inner().into_inner())).unwrap();
+    assert_eq!(region.timestamp(3, 1), 0);
+    assert_eq!(region.timestamps().collect::<Vec<_>>(), vec![(0, 2, 5678)]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn chunks_par_matches_chunks() {
+    use rayon::prelude::*;
+
+    let mut region = RegionFile::create_in(Cursor::new(Vec::new())).unwrap();
+    for i in 0..64 {
+        region.write_chunk(i % 32, i / 32, &chunk_blob(i % 32, i / 32), Compression::Zlib).unwrap();
+    }
+    // Corrupt one chunk's compression scheme.
+    let record = 4096 * location(&region, 7, 1).0 as usize;
+    region.get_mut().get_mut()[record + 4] = 42;
+
+    let mut parallel: Vec<_> = region.chunks_par().collect();
+    let serial: Vec<_> = region.chunks().collect();
+    assert_eq!(parallel.len(), 64);
+    parallel.sort_by_key(|chunk| chunk.as_ref().map(|&(x, z, _)| (z, x)).unwrap_or((1, 7)));
+    assert_eq!(parallel, serial);
+    assert_eq!(parallel[39], Err(Error::UnknownChunkCompression(42)));
+}
@@ -0,0 +1,74 @@
+//! Tests for the pure-Rust gzip and zlib backend.
+#![cfg(feature = "rust_backend")]
+
+extern crate nbt;
+
+mod common;
+
+use std::fs::File;
+use std::io::Read;
+
+use nbt::{Blob, Compression, CompressionLevel, Endianness, ReadOptions, WriteOptions};
+
+fn level() -> Blob {
+    // Written by Minecraft, so this also checks reading zlib's output.
+    let mut file = File::open("tests/level.dat").unwrap();
+    Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap()
+}
+
+#[test]
+fn rust_backend_roundtrip() {
+    let levels = [CompressionLevel::None, CompressionLevel::Fast, CompressionLevel::Default, CompressionLevel::Best];
+    for blob in &[level(), common::large_blob(1), Blob::new()] {
+        for &compression in &[Compression::Gzip, Compression::Zlib] {
+            let mut sizes = Vec::new();
+            for &level in &levels {
+                let write = WriteOptions::new(Endianness::BigEndian).compression(compression).level(level);
+                let mut dst = Vec::new();
+                blob.to_writer_with(&mut dst, &write).unwrap();
+                let read = ReadOptions::new(Endianness::BigEndian).compression(compression);
+                assert_eq!(&Blob::from_reader_with(&mut &dst[..], &read).unwrap(), blob);
+                assert_eq!(&Blob::from_reader_auto(&mut &dst[..], Endianness::BigEndian).unwrap(), blob);
+                sizes.push(dst.len());
+            }
+            assert!(sizes[0] > sizes[1] && sizes[1] >= sizes[3], "{:?}", sizes);
+        }
+    }
+}
+
+#[test]
+fn rust_backend_pull_encoders() {
+    let blob = common::large_blob(1);
+    let mut gzip = Vec::new();
+    blob.gzip_reader(Endianness::BigEndian).read_to_end(&mut gzip).unwrap();
+    let mut written = Vec::new();
+    blob.to_gzip_writer(&mut written, Endianness::BigEndian).unwrap();
+    assert_eq!(gzip, written);
+
+    let mut zlib = Vec::new();
+    blob.zlib_reader(Endianness::BigEndian).read_to_end(&mut zlib).unwrap();
+    assert_eq!(Blob::from_zlib_reader(&mut &zlib[..], Endianness::BigEndian).unwrap(), blob);
+}
+
+#[test]
+fn corrupt_rust_backend_streams() {
+    let mut src = Vec::new();
+    level().to_zlib_writer(&mut src, Endianness::BigEndian).unwrap();
+
+    // The NBT ends before the checksum, which is left unread, so only
+    // truncations before it fail.
+    for len in 0..src.len() - 4 {
+        assert!(Blob::from_zlib_reader(&mut &src[..len], Endianness::BigEndian).is_err());
+    }
+
+    let mut bad_header = src.clone();
+    bad_header[1] ^= 1;
+    assert!(Blob::from_zlib_reader(&mut &bad_header[..], Endianness::BigEndian).is_err());
+
+    // Flipping payload bytes must not panic, though some flips still decode.
+    for i in 2..src.len() - 4 {
+        let mut corrupt = src.clone();
+        corrupt[i] ^= 0xff;
+        let _ = Blob::from_zlib_reader(&mut &corrupt[..], Endianness::BigEndian);
+    }
+}
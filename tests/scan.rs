@@ -0,0 +1,145 @@
+//! Tests for checking documents without building `Value` trees.
+#![cfg(feature = "std")]
+
+#[macro_use] extern crate nbt;
+
+use nbt::{Blob, Endianness, Error, NbtStats, ReadOptions, Value};
+
+fn sample() -> Blob {
+    let mut blob = Blob::new();
+    blob.insert("Level", nbt!({
+        "Status": "full",
+        "Sections": [
+            { "Y": 0i8, "BlockStates": [L; 1, 2, 3, 4] },
+            { "Y": 1i8, "Biomes": [I; 7, 7] },
+        ],
+        "Light": [B; 1, 2, 3],
+        "Empty": [],
+    })).unwrap();
+    blob
+}
+
+#[test]
+fn statistics() {
+    let blob = sample();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let bytes = blob.to_vec(endian).unwrap();
+        let stats = nbt::validate(&bytes[..], &ReadOptions::new(endian)).unwrap();
+        assert_eq!(stats, NbtStats {
+            // The root, Level and its 4 entries, 2 sections of 2 entries each.
+            tags: 1 + 1 + 4 + 2 + 4,
+            // The root's name, 8 other names and one string value.
+            strings: 1 + 9 + 1,
+            array_elements: 4 + 2 + 3,
+            max_depth: 4,
+            bytes: bytes.len() as u64,
+        });
+    }
+
+    let mut unnamed = Vec::new();
+    blob.to_network_writer(&mut unnamed, Endianness::BigEndian).unwrap();
+    let options = ReadOptions::new(Endianness::BigEndian).unnamed_root(true);
+    assert_eq!(nbt::validate(&unnamed[..], &options).unwrap().strings, 10);
+}
+
+#[test]
+fn first_error_with_offset() {
+    let options = ReadOptions::new(Endianness::BigEndian);
+    let bytes = sample().to_vec(Endianness::BigEndian).unwrap();
+
+    let err = nbt::validate(&bytes[..bytes.len() - 3], &options).unwrap_err();
+    // The offset is where the read that failed started.
+    let offset = err.offset().unwrap();
+    assert!(offset > 100 && offset <= bytes.len() as u64 - 3);
+    assert_eq!(err.without_location(), Error::IncompleteNbtValue);
+
+    let mut corrupt = bytes.clone();
+    let at = corrupt.windows(4).position(|w| w == b"full").unwrap();
+    corrupt[at] = 0xff;
+    let err = nbt::validate(&corrupt[..], &options).unwrap_err();
+    let read = Blob::from_reader(&mut &corrupt[..], Endianness::BigEndian).unwrap_err();
+    assert_eq!(read.offset(), err.offset());
+    assert_eq!((err.without_location(), read.without_location()), (Error::InvalidUtf8, Error::InvalidUtf8));
+
+    assert_eq!(nbt::validate(&bytes[..], &options.clone().max_depth(3)).map_err(Error::without_location), Err(Error::DepthLimitExceeded(3)));
+    assert_eq!(nbt::validate(&bytes[..], &options.clone().max_array_len(3)).map_err(Error::without_location), Err(Error::LengthLimitExceeded(3)));
+    assert_eq!(nbt::validate(&[0x08, 0x00, 0x00][..], &options).map_err(Error::without_location), Err(Error::NoRootCompound));
+    assert_eq!(nbt::validate(&[0x0a, 0x00, 0x00, 0x0d, 0x00, 0x00][..], &options).map_err(Error::without_location), Err(Error::InvalidTypeId(0x0d)));
+    // A List of TAG_End is only valid when empty.
+    let list = [0x0a, 0x00, 0x00, 0x09, 0x00, 0x01, b'l', 0x00, 0x00, 0x00, 0x00, 0x01, 0x00];
+    assert_eq!(nbt::validate(&list[..], &options).map_err(Error::without_location), Err(Error::InvalidTypeId(0x00)));
+    assert_eq!(Value::from_reader(0x0a, &mut &list[3..], Endianness::BigEndian).err().map(Error::without_location), Some(Error::InvalidTypeId(0x00)));
+}
+
+#[test]
+fn extract_one_path() {
+    let blob = sample();
+    for &endian in &[Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian] {
+        let bytes = blob.to_vec(endian).unwrap();
+        let options = ReadOptions::new(endian);
+        for path in &["Level.Status", "Level.Sections[1].Y", "Level.Sections[0].BlockStates", "Level.Light", "Level"] {
+            assert_eq!(nbt::extract(&bytes[..], &options, path).unwrap().as_ref(), blob.get_path(path), "{}", path);
+        }
+        for path in &["Level.Missing", "Level.Sections[2]", "Level.Status.Y", "Level[0]", "Level.Empty[0]", "Level..", "Level[x]"] {
+            assert_eq!(nbt::extract(&bytes[..], &options, path), Ok(None), "{}", path);
+        }
+    }
+
+    // Everything after the value is left unread.
+    let bytes = blob.to_vec(Endianness::BigEndian).unwrap();
+    let options = ReadOptions::new(Endianness::BigEndian);
+    let mut src = &bytes[..];
+    let status = nbt::extract(&mut src, &options, "Level.Status").unwrap();
+    assert_eq!(status, Some(Value::String("full".to_string())));
+    assert!(!src.is_empty());
+
+    let err = nbt::extract(&bytes[..20], &options, "Level.Light").unwrap_err();
+    assert!(err.offset().is_some());
+    assert_eq!(err.without_location(), Error::IncompleteNbtValue);
+    assert_eq!(nbt::extract(&[0x08, 0x00, 0x00][..], &options, "a").map_err(Error::without_location), Err(Error::NoRootCompound));
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn extract_from_compressed_files() {
+    let options = ReadOptions::new(Endianness::BigEndian).compression(nbt::Compression::Gzip);
+    let blob = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    for path in &["Inventory[0].id", "Inventory[0]", "Pos[1]", "abilities.flying", "Dimension"] {
+        let file = std::fs::File::open("tests/complex_player.dat").unwrap();
+        assert_eq!(nbt::extract(file, &options, path).unwrap().as_ref(), blob.get_path(path), "{}", path);
+    }
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn transcode_between_byte_orders() {
+    let mut blob = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    blob.insert("Odd", "\0 \u{1f600}").unwrap();
+    blob.insert("Longs", Value::LongArray((0..3000).map(|i| i * -77_777_777).collect())).unwrap();
+    blob.insert("Ints", Value::IntArray((0..3000).collect())).unwrap();
+    blob.insert("Bytes", Value::ByteArray(vec![-1; 5000])).unwrap();
+    blob.insert("Level", sample()["Level"].clone()).unwrap();
+    let endians = [Endianness::BigEndian, Endianness::LittleEndian, Endianness::NetworkLittleEndian];
+    for &from in &endians {
+        let src = blob.to_vec(from).unwrap();
+        for &to in &endians {
+            let mut dst = Vec::new();
+            nbt::transcode(&src[..], &mut dst, from, to).unwrap();
+            assert_eq!(dst, blob.to_vec(to).unwrap());
+        }
+    }
+
+    // Any named root tag is copied.
+    let mut src = Vec::new();
+    Value::List(vec![Value::Short(1), Value::Short(-2)]).to_writer_root(&mut src, "l", Endianness::BigEndian).unwrap();
+    let mut dst = Vec::new();
+    nbt::transcode(&src[..], &mut dst, Endianness::BigEndian, Endianness::LittleEndian).unwrap();
+    assert_eq!(dst, [0x09, 0x01, 0x00, b'l', 0x02, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0xfe, 0xff]);
+
+    let src = sample().to_vec(Endianness::BigEndian).unwrap();
+    let err = nbt::transcode(&src[..src.len() - 1], &mut Vec::new(), Endianness::BigEndian, Endianness::LittleEndian).unwrap_err();
+    assert_eq!((err.offset(), err.without_location()), (Some(src.len() as u64 - 1), Error::IncompleteNbtValue));
+    assert_eq!(nbt::transcode(&[0x00][..], &mut Vec::new(), Endianness::BigEndian, Endianness::LittleEndian)
+                   .map_err(Error::without_location),
+               Err(Error::InvalidTypeId(0)));
+}
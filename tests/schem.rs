@@ -0,0 +1,141 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::formats::schem::Schematic;
+use nbt::formats::structure::BlockState;
+use nbt::{Endianness, Error, Value};
+
+fn read(root: &Value, name: &str) -> nbt::Result<Schematic> {
+    let mut bytes = Vec::new();
+    root.to_writer_root(&mut bytes, name, Endianness::BigEndian).unwrap();
+    Schematic::from_reader(&bytes[..])
+}
+
+fn write(schematic: &Schematic) -> (String, Value) {
+    let mut bytes = Vec::new();
+    schematic.to_writer(&mut bytes).unwrap();
+    Value::from_reader_root(&mut &bytes[..], Endianness::BigEndian).unwrap()
+}
+
+#[test]
+fn version_2() {
+    let file = nbt!({
+        Version: 2i32,
+        DataVersion: 2586i32,
+        Metadata: { Name: "Hut" },
+        Width: 2i16,
+        Height: 1i16,
+        Length: 1i16,
+        Offset: [I; -1, 0, 3],
+        PaletteMax: 2i32,
+        Palette: { "minecraft:air": 0i32, "minecraft:chest[facing=south,type=single]": 1i32 },
+        BlockData: [B; 1, 0],
+        BlockEntities: [{ Pos: [I; 0, 0, 0], Id: "minecraft:chest", Items: [], Lock: "" }],
+        Entities: [{ Pos: [0.5, 1.0, 0.5], Id: "minecraft:cat", Age: 0i32, Owner: [I; 1, 2, 3, 4] }],
+    });
+    let schematic = read(&file, "Schematic").unwrap();
+    assert_eq!(schematic.version, 2);
+    assert_eq!(schematic.size, [2, 1, 1]);
+    assert_eq!(schematic.offset, [-1, 0, 3]);
+    assert_eq!(schematic.metadata.as_ref().unwrap()["Name"], nbt!("Hut"));
+    let chest = BlockState::new("minecraft:chest").with("facing", "south").with("type", "single");
+    assert_eq!(schematic.block_at([0, 0, 0]), Some(&chest));
+    assert_eq!(schematic.block_at([1, 0, 0]), Some(&BlockState::new("minecraft:air")));
+    assert_eq!(schematic.block_at([2, 0, 0]), None);
+    assert_eq!(schematic.block_entities[0].id, "minecraft:chest");
+    assert_eq!(schematic.block_entities[0].data.len(), 2);
+    assert_eq!(schematic.entities[0].pos, [0.5, 1.0, 0.5]);
+    assert_eq!(schematic.entities[0].data["Owner"], Value::IntArray(vec![1, 2, 3, 4]));
+
+    assert_eq!(write(&schematic), ("Schematic".to_string(), file));
+}
+
+#[test]
+fn version_3() {
+    let file = nbt!({
+        Schematic: {
+            Version: 3i32,
+            DataVersion: 3700i32,
+            Width: 1i16,
+            Height: 2i16,
+            Length: 1i16,
+            Offset: [I; 0, 0, 0],
+            Blocks: {
+                Palette: { "minecraft:stone": 0i32, "minecraft:sign[rotation=4]": 1i32 },
+                Data: [B; 0, 1],
+                BlockEntities: [{ Pos: [I; 0, 1, 0], Id: "minecraft:sign", Data: { is_waxed: 1i8 } }],
+            },
+            Entities: [],
+            // Biomes are not read.
+            Biomes: { Palette: { "minecraft:plains": 0i32 }, Data: [B; 0, 0] },
+        },
+    });
+    let schematic = read(&file, "").unwrap();
+    assert_eq!(schematic.version, 3);
+    assert_eq!(schematic.block_at([0, 1, 0]).unwrap().to_string(), "minecraft:sign[rotation=4]");
+    assert_eq!(schematic.block_entities[0].pos, [0, 1, 0]);
+    assert_eq!(schematic.block_entities[0].data["is_waxed"], Value::Byte(1));
+
+    let (name, written) = write(&schematic);
+    assert_eq!(name, "");
+    assert_eq!(written["Schematic"]["Blocks"], file["Schematic"]["Blocks"]);
+    assert_eq!(written["Schematic"].get("Biomes"), None);
+
+    // The two layouts hold the same schematics.
+    let mut v2 = schematic.clone();
+    v2.version = 2;
+    assert_eq!(read(&write(&v2).1, "Schematic").unwrap(), v2);
+}
+
+#[test]
+fn large_palettes() {
+    // Indices past 127 take more than one byte of block data.
+    let mut schematic = Schematic::new([300, 1, 2]);
+    for x in 0..300 {
+        schematic.set_block([x, 0, 1], BlockState::new("minecraft:wool").with("n", x.to_string()));
+    }
+    assert_eq!(schematic.palette.len(), 301);
+    let (_, written) = write(&schematic);
+    match written["Schematic"]["Blocks"]["Data"] {
+        Value::ByteArray(ref data) => assert_eq!(data.len(), 300 + 127 + 173 * 2),
+        ref other => panic!("{:?}", other),
+    }
+    assert_eq!(read(&written, "").unwrap(), schematic);
+}
+
+#[test]
+fn invalid_schematics() {
+    let file = |palette: Value, data: Value| nbt!({
+        Version: 2i32, Width: 1i16, Height: 1i16, Length: 2i16, Palette: palette, BlockData: data,
+    });
+    assert!(read(&file(nbt!({ a: 0i32, b: 1i32 }), nbt!([B; 0, 1])), "Schematic").is_ok());
+    assert_eq!(read(&file(nbt!({ a: 0i32, b: 2i32 }), nbt!([B; 0, 1])), "Schematic").err(),
+               Some(Error::InvalidSchematic("the palette indices are not 0 to its length")));
+    assert_eq!(read(&file(nbt!({ a: 0i32 }), nbt!([B; 0, 1])), "Schematic").err(),
+               Some(Error::InvalidSchematic("a block is not in the palette")));
+    assert_eq!(read(&file(nbt!({ a: 0i32 }), nbt!([B; 0])), "Schematic").err(),
+               Some(Error::InvalidSchematic("the number of blocks does not match the size")));
+    assert_eq!(read(&file(nbt!({ a: 0i32 }), nbt!([B; 0, -128])), "Schematic").err(),
+               Some(Error::InvalidSchematic("the block data ends inside a VarInt")));
+    assert_eq!(read(&file(nbt!({ "a[b": 0i32 }), nbt!([B; 0, 0])), "Schematic").err(),
+               Some(Error::InvalidBlockState("a[b".to_string())));
+    assert_eq!(read(&nbt!({ Version: 4i32 }), "").err(),
+               Some(Error::InvalidSchematic("the version is not supported")));
+
+    let mut schematic = Schematic::new([1, 1, 1]);
+    schematic.blocks.push(0);
+    assert_eq!(schematic.to_writer(&mut Vec::new()).err(),
+               Some(Error::InvalidSchematic("the number of blocks does not match the size")));
+}
+
+#[test]
+fn block_state_syntax() {
+    let state: BlockState = "minecraft:oak_stairs[facing=east,half=bottom]".parse().unwrap();
+    assert_eq!(state, BlockState::new("minecraft:oak_stairs").with("half", "bottom").with("facing", "east"));
+    assert_eq!("stone[]".parse::<BlockState>().unwrap(), BlockState::new("stone"));
+    for invalid in &["", "[a=b]", "stone[a]", "stone[a=b", "stone[a=b=c]", "stone[=b]", "stone[a=b,]"] {
+        assert_eq!(invalid.parse::<BlockState>(), Err(Error::InvalidBlockState(invalid.to_string())));
+    }
+}
@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate nbt;
+
+use nbt::schema::{Schema, Violation};
+
+fn item() -> Schema {
+    Schema::compound()
+        .required("id", Schema::string().len(1, 64))
+        .required("Count", Schema::byte().range(1.0, 64.0))
+        .required("Damage", Schema::short().range(0.0, f64::from(i16::MAX)))
+        .optional("Slot", Schema::byte().range(0.0, 103.0))
+        .optional("tag", Schema::compound().allow_unknown())
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn validate_player_file() {
+    use nbt::{Blob, Endianness};
+
+    let player = Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap();
+    let schema = Schema::compound()
+        .allow_unknown()
+        .required("Health", Schema::short().range(0.0, 20.0))
+        .required("Pos", Schema::list(Schema::double()).len(3, 3))
+        .required("Inventory", Schema::list(item()).len(0, 36))
+        .optional("EnderItems", Schema::list(item()).len(0, 27));
+    assert_eq!(player.validate(&schema), vec![]);
+
+    let strict = Schema::compound()
+        .allow_unknown()
+        .required("Inventory", Schema::list(item().required("tag", Schema::compound())).len(0, 20))
+        .required("Rotation", Schema::list(Schema::double()))
+        .required("XpLevel", Schema::long());
+    assert_eq!(player.validate(&strict), vec![
+        Violation::WrongLength { path: "Inventory".to_string(), len: 25 },
+        Violation::WrongTag { path: "Rotation[0]".to_string(), expected: 0x06, found: 0x05 },
+        Violation::WrongTag { path: "Rotation[1]".to_string(), expected: 0x06, found: 0x05 },
+        Violation::WrongTag { path: "XpLevel".to_string(), expected: 0x04, found: 0x03 },
+    ]);
+    assert!(player.validate(&Schema::compound()).len() > 40);
+    assert_eq!(player.validate(&Schema::compound().allow_unknown().len(0, 10)),
+               vec![Violation::WrongLength { path: String::new(), len: 41 }]);
+    assert_eq!(player.validate(&Schema::list(Schema::any())),
+               vec![Violation::WrongTag { path: String::new(), expected: 0x09, found: 0x0a }]);
+}
+
+#[test]
+fn validate_nested_values() {
+    let schema = Schema::list(item());
+    let items = nbt!([
+        { id: "minecraft:stone", Count: 64i8, Damage: 0i16 },
+        { id: "", Count: 0i8, Damage: 0i16, "odd key": 1i8 },
+        { id: "minecraft:stick", Count: 1i8, Damage: 0i16, tag: { display: { Name: "Wand" } } },
+        { id: 5i8, Count: 1i8 },
+    ]);
+    let violations = schema.validate(&items);
+    assert_eq!(violations, vec![
+        Violation::OutOfRange { path: "[1].Count".to_string(), value: 0.0 },
+        Violation::WrongLength { path: "[1].id".to_string(), len: 0 },
+        Violation::Unexpected { path: "[1].\"odd key\"".to_string() },
+        Violation::Missing { path: "[3].Damage".to_string() },
+        Violation::WrongTag { path: "[3].id".to_string(), expected: 0x08, found: 0x01 },
+    ]);
+    let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    assert_eq!(messages, [
+        "[1].Count: 0 is out of range",
+        "[1].id: length 0 is out of range",
+        "[1].\"odd key\": unexpected entry",
+        "[3].Damage: missing required entry",
+        "[3].id: expected TAG_String, found TAG_Byte",
+    ]);
+
+    // Ranges compare numbers of any tag, and lengths count characters.
+    let any = Schema::any().range(-1.0, 1.0).len(0, 2);
+    assert!(any.validate(&nbt!(1i64)).is_empty());
+    assert!(any.validate(&nbt!(0.5f32)).is_empty());
+    assert!(any.validate(&nbt!("été")).len() == 1);
+    assert!(any.validate(&nbt!([I; 5, 6])).is_empty());
+    assert_eq!(any.validate(&nbt!(-2i16)).first().map(|v| v.path()), Some(""));
+    assert_eq!(any.validate(&nbt!(-2i16))[0].to_string(), "-2 is out of range");
+}
@@ -1,13 +1,17 @@
+#![cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
+extern crate serde_json;
+extern crate serde_bytes;
 
 extern crate nbt;
 
 use std::collections::HashMap;
 
 use nbt::de::from_reader;
-use nbt::Endianness;
+use nbt::{Endianness, Error, Value};
+use nbt::raw::RawReader;
 
 /// Helper function that asserts data of type T can be serialized into and
 /// deserialized from `bytes`. `name` is an optional header for the top-level
@@ -22,6 +26,17 @@ where for <'de> T: serde::Serialize + serde::Deserialize<'de> + PartialEq + std:
 
     let read: T = nbt::de::from_reader(bytes, Endianness::BigEndian).expect("NBT deserialization.");
     assert_eq!(read, nbt);
+
+    let read: T = nbt::de::from_slice(bytes, Endianness::BigEndian).expect("NBT slice deserialization.");
+    assert_eq!(read, nbt);
+
+    let mut reader = RawReader::new(bytes, Endianness::BigEndian);
+    let (id, _) = reader.emit_next_header().unwrap();
+    let value = Value::from_raw_reader(id, &mut reader).unwrap();
+    assert_eq!(nbt::to_value(&nbt).expect("Value serialization."), value);
+
+    let read: T = nbt::from_value(value).expect("Value deserialization.");
+    assert_eq!(read, nbt);
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -473,3 +488,592 @@ fn roundtrip_hashmap() {
 
     assert_roundtrip_eq(nbt, &bytes, None);
 }
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct BorrowedNbt<'a> {
+    name: &'a str,
+    #[serde(with = "borrowed_bytes")]
+    data: &'a [u8],
+}
+
+/// `&[u8]` fields only deserialize from borrowed bytes, which serde's derive
+/// will not ask for on its own.
+mod borrowed_bytes {
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D>(de: D) -> Result<&'de [u8], D::Error>
+        where D: Deserializer<'de>
+    {
+        <&'de [u8]>::deserialize(de)
+    }
+}
+
+#[test]
+fn deserialize_borrowed_from_slice() {
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x08,
+                0x00, 0x04,
+                0x6e, 0x61, 0x6d, 0x65,
+                0x00, 0x09,
+                0x48, 0x65, 0x72, 0x6f, 0x62, 0x72, 0x69, 0x6e, 0x65,
+            0x07,
+                0x00, 0x04,
+                0x64, 0x61, 0x74, 0x61,
+                0x00, 0x00, 0x00, 0x03,
+                0x01, 0x02, 0xff,
+        0x00
+    ];
+
+    let read: BorrowedNbt = nbt::de::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(read, BorrowedNbt { name: "Herobrine", data: &[1, 2, 255] });
+
+    // Both fields point into the input buffer.
+    let range = bytes.as_ptr_range();
+    assert!(range.contains(&read.name.as_ptr()));
+    assert!(range.contains(&read.data.as_ptr()));
+}
+
+#[test]
+fn deserialize_slice_owned_fallback() {
+    // U+1F600 in Modified UTF-8 is a surrogate pair, which is not valid UTF-8
+    // and so cannot be borrowed.
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x08,
+                0x00, 0x04,
+                0x6e, 0x61, 0x6d, 0x65,
+                0x00, 0x06,
+                0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80,
+        0x00
+    ];
+
+    let read: HashMap<String, String> = nbt::de::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(read["name"], "\u{1F600}");
+
+    #[derive(Debug, Deserialize)]
+    struct Name<'a> {
+        #[allow(dead_code)]
+        name: &'a str,
+    }
+
+    let err = nbt::de::from_slice::<Name>(&bytes, Endianness::BigEndian);
+    assert!(err.is_err());
+}
+
+#[test]
+fn deserialize_slice_truncated() {
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x08,
+                0x00, 0x04,
+                0x6e, 0x61, 0x6d, 0x65,
+                0x00, 0x09,
+                0x48, 0x65, 0x72,
+    ];
+
+    let err = nbt::de::from_slice::<HashMap<String, String>>(&bytes, Endianness::BigEndian);
+    assert!(err.is_err());
+}
+
+#[test]
+fn value_bridges_bare_values() {
+    assert_eq!(nbt::to_value(&5i32).unwrap(), Value::Int(5));
+    assert_eq!(nbt::to_value(&true).unwrap(), Value::Byte(1));
+    assert_eq!(nbt::to_value(&vec!["a", "b"]).unwrap(),
+               Value::List(vec![Value::String("a".to_string()), Value::String("b".to_string())]));
+
+    let n: i64 = nbt::from_value(Value::Long(7)).unwrap();
+    assert_eq!(n, 7);
+    let v: Vec<i32> = nbt::from_value(Value::IntArray(vec![1, 2])).unwrap();
+    assert_eq!(v, vec![1, 2]);
+    let b: bool = nbt::from_value(Value::Byte(0)).unwrap();
+    assert!(!b);
+}
+
+#[test]
+fn value_bridges_errors() {
+    assert_eq!(nbt::to_value(&5u32), Err(Error::UnrepresentableType("u32")));
+    assert_eq!(nbt::to_value(&Option::<i8>::None), Err(Error::UnrepresentableType("none")));
+
+    let mut map = HashMap::new();
+    map.insert(1i8, 2i8);
+    assert_eq!(nbt::to_value(&map), Err(Error::NonStringMapKey));
+
+    assert_eq!(nbt::from_value::<bool>(Value::Byte(2)), Err(Error::NonBooleanByte(2)));
+    assert_eq!(nbt::from_value::<bool>(Value::Int(1)), Err(Error::TagMismatch(0x03, 0x01)));
+    assert!(nbt::from_value::<PrimitiveNbt>(Value::Compound(nbt::Map::new())).is_err());
+}
+
+#[test]
+fn roundtrip_named_root() {
+    let nbt = nbt::Named { name: "data".to_string(), value: ByteNbt { data: 100 } };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x04,
+            0x64, 0x61, 0x74, 0x61,
+            0x01,
+                0x00, 0x04,
+                0x64, 0x61, 0x74, 0x61,
+                0x64,
+        0x00
+    ];
+
+    // The name in `Named` wins over the header argument.
+    let mut dst = Vec::new();
+    nbt::to_writer(&mut dst, &nbt, Some("ignored"), Endianness::BigEndian).unwrap();
+    assert_eq!(dst, bytes);
+
+    let read: nbt::Named<ByteNbt> = from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read, nbt);
+    let read: nbt::Named<ByteNbt> = nbt::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(read, nbt);
+
+    // Elsewhere it is an ordinary struct.
+    let value = nbt::to_value(&nbt).unwrap();
+    let read: nbt::Named<ByteNbt> = nbt::from_value(value).unwrap();
+    assert_eq!(read, nbt);
+    let json = serde_json::to_string(&nbt).unwrap();
+    assert_eq!(json, r#"{"name":"data","value":{"data":100}}"#);
+
+    // The root must still be a compound.
+    let err = nbt::to_writer(&mut Vec::new(), &nbt::Named { name: "a".to_string(), value: 1i8 },
+                             None, Endianness::BigEndian);
+    assert_eq!(err, Err(Error::NoRootCompound));
+}
+
+#[test]
+fn named_blob_roundtrip() {
+    // Only strings, since `Value` cannot tell integer widths apart through
+    // serde.
+    let mut blob = nbt::Blob::named("Data");
+    blob.insert("LevelName", "world").unwrap();
+
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    let named: nbt::Named<nbt::Map> = from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(named.name, "Data");
+    assert_eq!(nbt::Blob::from(named.clone()), blob);
+
+    let mut dst = Vec::new();
+    nbt::to_writer(&mut dst, &named, None, Endianness::BigEndian).unwrap();
+    assert_eq!(dst, bytes);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ArrayWrapperNbt {
+    #[serde(with = "nbt::byte_array")]
+    bytes: Vec<i8>,
+    #[serde(with = "nbt::int_array")]
+    uuid: [i32; 4],
+    #[serde(with = "nbt::long_array")]
+    longs: Vec<i64>,
+}
+
+#[test]
+fn roundtrip_array_wrappers() {
+    let nbt = ArrayWrapperNbt { bytes: vec![1, -1], uuid: [1, 2, 3, -4], longs: vec![] };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x07,
+                0x00, 0x05,
+                0x62, 0x79, 0x74, 0x65, 0x73,
+                0x00, 0x00, 0x00, 0x02, // Length.
+                0x01, 0xff,
+            0x0b,
+                0x00, 0x04,
+                0x75, 0x75, 0x69, 0x64,
+                0x00, 0x00, 0x00, 0x04, // Length.
+                0x00, 0x00, 0x00, 0x01,
+                0x00, 0x00, 0x00, 0x02,
+                0x00, 0x00, 0x00, 0x03,
+                0xff, 0xff, 0xff, 0xfc,
+            0x0c,
+                0x00, 0x05,
+                0x6c, 0x6f, 0x6e, 0x67, 0x73,
+                0x00, 0x00, 0x00, 0x00, // Length.
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+
+    // Other serializers see a plain sequence.
+    let nbt = ArrayWrapperNbt { bytes: vec![1], uuid: [1, 2, 3, 4], longs: vec![5] };
+    let json = serde_json::to_string(&nbt).unwrap();
+    assert_eq!(json, r#"{"bytes":[1],"uuid":[1,2,3,4],"longs":[5]}"#);
+    assert_eq!(serde_json::from_str::<ArrayWrapperNbt>(&json).unwrap(), nbt);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct BytesNbt {
+    #[serde(with = "serde_bytes")]
+    light: Vec<u8>,
+    biomes: serde_bytes::ByteBuf,
+}
+
+#[test]
+fn roundtrip_serde_bytes() {
+    let nbt = BytesNbt {
+        light: vec![0x00, 0xff],
+        biomes: serde_bytes::ByteBuf::from(vec![0x80]),
+    };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x07,
+                0x00, 0x05,
+                0x6c, 0x69, 0x67, 0x68, 0x74,
+                0x00, 0x00, 0x00, 0x02, // Length.
+                0x00, 0xff,
+            0x07,
+                0x00, 0x06,
+                0x62, 0x69, 0x6f, 0x6d, 0x65, 0x73,
+                0x00, 0x00, 0x00, 0x01, // Length.
+                0x80,
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        #[serde(borrow)]
+        light: &'a serde_bytes::Bytes,
+    }
+    let read: Borrowed = nbt::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(&read.light[..], &[0x00, 0xff]);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FlattenedInner {
+    byte: i8,
+    #[serde(with = "nbt::byte_bool")]
+    lit: bool,
+    #[serde(with = "nbt::int_array")]
+    ints: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FlattenedNbt {
+    version: i32,
+    #[serde(flatten)]
+    inner: FlattenedInner,
+    #[serde(flatten)]
+    rest: HashMap<String, String>,
+}
+
+#[test]
+fn roundtrip_flatten() {
+    let mut rest = HashMap::new();
+    rest.insert("id".to_string(), "minecraft:chest".to_string());
+    let nbt = FlattenedNbt {
+        version: 1,
+        inner: FlattenedInner { byte: -1, lit: true, ints: vec![2] },
+        rest,
+    };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x03,
+                0x00, 0x07,
+                0x76, 0x65, 0x72, 0x73, 0x69, 0x6f, 0x6e,
+                0x00, 0x00, 0x00, 0x01,
+            0x01,
+                0x00, 0x04,
+                0x62, 0x79, 0x74, 0x65,
+                0xff,
+            0x01,
+                0x00, 0x03,
+                0x6c, 0x69, 0x74,
+                0x01,
+            0x0b,
+                0x00, 0x04,
+                0x69, 0x6e, 0x74, 0x73,
+                0x00, 0x00, 0x00, 0x01,
+                0x00, 0x00, 0x00, 0x02,
+            0x08,
+                0x00, 0x02,
+                0x69, 0x64,
+                0x00, 0x0f,
+                0x6d, 0x69, 0x6e, 0x65, 0x63, 0x72, 0x61, 0x66, 0x74, 0x3a,
+                0x63, 0x68, 0x65, 0x73, 0x74,
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+}
+
+#[test]
+fn serialize_map_keys_separately() {
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+
+    struct KeyThenValue;
+
+    impl Serialize for KeyThenValue {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_key("a")?;
+            map.serialize_value(&1i8)?;
+            map.end()
+        }
+    }
+
+    let mut dst = Vec::new();
+    nbt::to_writer(&mut dst, &KeyThenValue, None, Endianness::BigEndian).unwrap();
+    assert_eq!(dst, vec![0x0a, 0x00, 0x00, 0x01, 0x00, 0x01, 0x61, 0x01, 0x00]);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Gamemode {
+    Survival,
+    Creative,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Point(i8),
+    Rect { w: i8 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Mob {
+    Pig { saddle: i8 },
+    Bat,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "t", content = "c")]
+enum Event {
+    Ping,
+    Move(i8),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct UnitVariantNbt {
+    mode: Gamemode,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ExternalVariantNbt {
+    point: Shape,
+    rect: Shape,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TaggedVariantNbt {
+    mob: Mob,
+    event: Event,
+}
+
+#[test]
+fn roundtrip_unit_variant() {
+    let nbt = UnitVariantNbt { mode: Gamemode::Creative };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x08,
+                0x00, 0x04,
+                0x6d, 0x6f, 0x64, 0x65,
+                0x00, 0x08,
+                0x43, 0x72, 0x65, 0x61, 0x74, 0x69, 0x76, 0x65,
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+}
+
+#[test]
+fn roundtrip_externally_tagged_variants() {
+    let nbt = ExternalVariantNbt { point: Shape::Point(1), rect: Shape::Rect { w: 2 } };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x0a,
+                0x00, 0x05,
+                0x70, 0x6f, 0x69, 0x6e, 0x74,
+                0x01,
+                    0x00, 0x05,
+                    0x50, 0x6f, 0x69, 0x6e, 0x74,
+                    0x01,
+                0x00,
+            0x0a,
+                0x00, 0x04,
+                0x72, 0x65, 0x63, 0x74,
+                0x0a,
+                    0x00, 0x04,
+                    0x52, 0x65, 0x63, 0x74,
+                    0x01,
+                        0x00, 0x01,
+                        0x77,
+                        0x02,
+                    0x00,
+                0x00,
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+
+    // Variants with contents can also form the root compound.
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x0a,
+                0x00, 0x04,
+                0x52, 0x65, 0x63, 0x74,
+                0x01,
+                    0x00, 0x01,
+                    0x77,
+                    0x02,
+                0x00,
+        0x00
+    ];
+
+    assert_roundtrip_eq(Shape::Rect { w: 2 }, &bytes, None);
+}
+
+#[test]
+fn roundtrip_internally_and_adjacently_tagged_variants() {
+    let nbt = TaggedVariantNbt { mob: Mob::Pig { saddle: 1 }, event: Event::Move(3) };
+
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x0a,
+                0x00, 0x03,
+                0x6d, 0x6f, 0x62,
+                0x08,
+                    0x00, 0x04,
+                    0x74, 0x79, 0x70, 0x65,
+                    0x00, 0x03,
+                    0x50, 0x69, 0x67,
+                0x01,
+                    0x00, 0x06,
+                    0x73, 0x61, 0x64, 0x64, 0x6c, 0x65,
+                    0x01,
+                0x00,
+            0x0a,
+                0x00, 0x05,
+                0x65, 0x76, 0x65, 0x6e, 0x74,
+                0x08,
+                    0x00, 0x01,
+                    0x74,
+                    0x00, 0x04,
+                    0x4d, 0x6f, 0x76, 0x65,
+                0x01,
+                    0x00, 0x01,
+                    0x63,
+                    0x03,
+                0x00,
+        0x00
+    ];
+
+    assert_roundtrip_eq(nbt, &bytes, None);
+
+    let nbt = TaggedVariantNbt { mob: Mob::Bat, event: Event::Ping };
+    let mut dst = Vec::new();
+    nbt::to_writer(&mut dst, &nbt, None, Endianness::BigEndian).unwrap();
+    let read: TaggedVariantNbt = nbt::from_reader(&dst[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read, nbt);
+}
+
+#[test]
+fn deserialize_variant_errors() {
+    // A variant compound with more than one entry.
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x01,
+                0x00, 0x05,
+                0x50, 0x6f, 0x69, 0x6e, 0x74,
+                0x01,
+            0x01,
+                0x00, 0x01,
+                0x78,
+                0x02,
+        0x00
+    ];
+
+    let read: Result<Shape, Error> = nbt::from_reader(&bytes[..], Endianness::BigEndian);
+    assert_eq!(read, Err(Error::UnexpectedField("x".to_string())));
+
+    // Unit variants are strings, not numbers.
+    let bytes = vec![
+        0x0a,
+            0x00, 0x00,
+            0x01,
+                0x00, 0x04,
+                0x6d, 0x6f, 0x64, 0x65,
+                0x01,
+        0x00
+    ];
+
+    let read: Result<UnitVariantNbt, Error> = nbt::from_reader(&bytes[..], Endianness::BigEndian);
+    assert_eq!(read, Err(Error::TagMismatch(0x01, 0x08)));
+}
+
+#[test]
+fn value_fields_keep_their_tags() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct BlockEntity {
+        id: String,
+        nbt: Value,
+    }
+
+    let mut map = nbt::Map::new();
+    map.insert("Short".to_string(), Value::Short(5));
+    map.insert("Long".to_string(), Value::Long(5));
+    map.insert("Bytes".to_string(), Value::ByteArray(vec![1, 2]));
+    map.insert("Ints".to_string(), Value::IntArray(vec![1, 2]));
+    map.insert("Longs".to_string(), Value::LongArray(vec![]));
+    map.insert("Arrays".to_string(), Value::List(vec![Value::IntArray(vec![3]), Value::IntArray(vec![])]));
+    map.insert("Empty".to_string(), Value::List(vec![]));
+    let entity = BlockEntity { id: "minecraft:chest".to_string(), nbt: Value::Compound(map) };
+
+    let bytes = nbt::to_vec(&entity, None, Endianness::BigEndian).unwrap();
+    let read: BlockEntity = nbt::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(read, entity);
+    let read: BlockEntity = nbt::from_reader(&bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read, entity);
+    let value = nbt::to_value(&entity).unwrap();
+    assert_eq!(value["nbt"], entity.nbt);
+    let read: BlockEntity = nbt::from_value(value).unwrap();
+    assert_eq!(read, entity);
+
+    // Formats without tags give integers the smallest tag that fits them.
+    let json: Value = serde_json::from_str(r#"{"a": 5, "b": 300, "c": [1.5], "d": "x"}"#).unwrap();
+    assert_eq!(json["a"], Value::Byte(5));
+    assert_eq!(json["b"], Value::Short(300));
+    assert_eq!(json["c"], Value::List(vec![Value::Double(1.5)]));
+    assert_eq!(json["d"], Value::String("x".to_string()));
+}
+
+#[test]
+fn value_rejects_unknown_tags() {
+    // In a Compound, tag 0 ends it, so it is tried as the tag of a List.
+    let entries: Vec<Vec<u8>> = vec![
+        vec![0x09, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x00, 0x01],
+        vec![0x0d, 0x00, 0x01, b'a', 0x00],
+        vec![0xff, 0x00, 0x01, b'a', 0x00],
+    ];
+    for (entry, &tag) in entries.iter().zip(&[0x00, 0x0d, -1i8]) {
+        let mut bytes = vec![0x0a, 0x00, 0x00];
+        bytes.extend_from_slice(entry);
+        bytes.push(0x00);
+        let read: Result<Value, Error> = nbt::from_slice(&bytes, Endianness::BigEndian);
+        assert_eq!(read.map_err(Error::without_location), Err(Error::InvalidTypeId(tag)));
+        let read: Result<Value, Error> = nbt::from_reader(&bytes[..], Endianness::BigEndian);
+        assert_eq!(read.map_err(Error::without_location), Err(Error::InvalidTypeId(tag)));
+    }
+}
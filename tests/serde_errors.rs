@@ -1,3 +1,4 @@
+#![cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
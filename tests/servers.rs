@@ -0,0 +1,42 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::formats::servers::{Server, ServerList};
+use nbt::{Endianness, Value};
+
+#[test]
+fn server_lists() {
+    let file = nbt!({
+        servers: [
+            { name: "Minigames", ip: "mc.example.com", icon: "iVBORw0KGgo=", acceptTextures: 1i8 },
+            { name: "Minecraft Server", ip: "localhost" },
+            { name: "", ip: "10.0.0.5:25566", hidden: 1i8, acceptTextures: 0i8 },
+        ]
+    });
+    let mut bytes = Vec::new();
+    file.to_writer_root(&mut bytes, "", Endianness::BigEndian).unwrap();
+    let mut list = ServerList::from_reader(&bytes[..]).unwrap();
+
+    assert_eq!(list.servers.len(), 3);
+    let first = list.find("mc.example.com").unwrap();
+    assert_eq!(first.icon.as_ref().unwrap(), "iVBORw0KGgo=");
+    assert_eq!(first.accept_textures, Some(true));
+    assert_eq!(list.find("localhost").unwrap().accept_textures, None);
+    assert_eq!(list.servers[2].name, "");
+    assert_eq!(list.servers[2].hidden, Some(true));
+
+    let mut written = Vec::new();
+    list.to_writer(&mut written).unwrap();
+    let (name, value) = Value::from_reader_root(&mut &written[..], Endianness::BigEndian).unwrap();
+    assert_eq!(name, "");
+    assert_eq!(value, file);
+
+    list.find_mut("localhost").unwrap().name = "Local".to_string();
+    list.servers.insert(0, Server::new("New", "play.example.org"));
+    assert_eq!(list.remove("mc.example.com").unwrap().name, "Minigames");
+    assert!(list.remove("mc.example.com").is_none());
+    let names: Vec<&str> = list.servers.iter().map(|server| server.name.as_str()).collect();
+    assert_eq!(names, ["New", "Local", ""]);
+}
@@ -0,0 +1,119 @@
+//! Tests for parsing stringified NBT (SNBT).
+#![cfg(feature = "std")]
+
+extern crate nbt;
+
+use std::io;
+
+use nbt::{Blob, Endianness, Error, Map, Value};
+
+#[test]
+fn parse_numeric_suffixes() {
+    assert_eq!(Value::from_snbt("1b"), Ok(Value::Byte(1)));
+    assert_eq!(Value::from_snbt("-3s"), Ok(Value::Short(-3)));
+    assert_eq!(Value::from_snbt("42"), Ok(Value::Int(42)));
+    assert_eq!(Value::from_snbt("5L"), Ok(Value::Long(5)));
+    assert_eq!(Value::from_snbt("2.5f"), Ok(Value::Float(2.5)));
+    assert_eq!(Value::from_snbt("1e3d"), Ok(Value::Double(1000.0)));
+    assert_eq!(Value::from_snbt("-.5"), Ok(Value::Double(-0.5)));
+    assert_eq!(Value::from_snbt("true"), Ok(Value::Byte(1)));
+    assert_eq!(Value::from_snbt("false"), Ok(Value::Byte(0)));
+    // Out of range or non-numeric literals are strings, as in Minecraft.
+    assert_eq!(Value::from_snbt("300b"), Ok(Value::String("300b".to_string())));
+    assert_eq!(Value::from_snbt("stone"), Ok(Value::String("stone".to_string())));
+    // Colons are not allowed in unquoted strings.
+    assert_eq!(Value::from_snbt("minecraft:stone"),
+               Err(Error::InvalidSnbt(9, "unexpected trailing characters".to_string())));
+}
+
+#[test]
+fn parse_strings() {
+    assert_eq!(Value::from_snbt(r#""say \"hi\"""#), Ok(Value::String("say \"hi\"".to_string())));
+    assert_eq!(Value::from_snbt(r#"'it\'s'"#), Ok(Value::String("it's".to_string())));
+    assert_eq!(Value::from_snbt(r#"'a "quote"'"#), Ok(Value::String("a \"quote\"".to_string())));
+    assert_eq!(Value::from_snbt(r#""é\\""#), Ok(Value::String("\u{e9}\\".to_string())));
+    assert_eq!(Value::from_snbt(r#""open"#), Err(Error::InvalidSnbt(5, "unterminated string".to_string())));
+}
+
+#[test]
+fn parse_arrays_and_lists() {
+    assert_eq!(Value::from_snbt("[B; 1b, -2b]"), Ok(Value::ByteArray(vec![1, -2])));
+    assert_eq!(Value::from_snbt("[I;1,2,3]"), Ok(Value::IntArray(vec![1, 2, 3])));
+    assert_eq!(Value::from_snbt("[L; 1L, 2L]"), Ok(Value::LongArray(vec![1, 2])));
+    assert_eq!(Value::from_snbt("[I;]"), Ok(Value::IntArray(vec![])));
+    assert_eq!(Value::from_snbt("[]"), Ok(Value::List(vec![])));
+    assert_eq!(Value::from_snbt("[ 1s , 2s ]"), Ok(Value::List(vec![Value::Short(1), Value::Short(2)])));
+    assert_eq!(Value::from_snbt("[1, 2s]"), Err(Error::HeterogeneousList));
+    assert_eq!(Value::from_snbt("[I; 1, 2b]"), Err(Error::InvalidSnbt(7, "invalid array element".to_string())));
+}
+
+#[test]
+fn parse_compounds() {
+    let mut inner = Map::new();
+    inner.insert("Count".to_string(), Value::Byte(64));
+    let mut expected = Map::new();
+    expected.insert("item".to_string(), Value::Compound(inner));
+    expected.insert("display name".to_string(), Value::String("x".to_string()));
+    assert_eq!(Value::from_snbt(r#"{item: {Count: 64b}, "display name": x}"#),
+               Ok(Value::Compound(expected)));
+
+    assert_eq!(Value::from_snbt("{a: 1"), Err(Error::InvalidSnbt(5, "expected ',' or '}'".to_string())));
+    assert_eq!(Value::from_snbt("{: 1}"), Err(Error::InvalidSnbt(1, "expected a key".to_string())));
+    assert_eq!(Blob::from_snbt("[1, 2]"), Err(Error::NoRootCompound));
+}
+
+#[test]
+fn data_get_output_roundtrips_to_binary() {
+    let snbt = r#"{Brain: {memories: {}}, HurtByTimestamp: 0, Health: 20.0f,
+        Pos: [-12.5d, 64.0d, 30.5d], Rotation: [90.0f, 0.0f], OnGround: 1b,
+        UUID: [I; 1234, -5678, 9012, -3456], Inventory: [{Slot: 0b,
+        id: "minecraft:diamond_sword", Count: 1b, tag: {Damage: 0}}],
+        XpTotal: 0, LastDeath: 1622547800000L, foodSaturationLevel: 5.0f}"#;
+    let blob = Blob::from_snbt(snbt).unwrap();
+    assert_eq!(blob["UUID"], Value::IntArray(vec![1234, -5678, 9012, -3456]));
+    assert_eq!(blob["LastDeath"], Value::Long(1622547800000));
+
+    let mut dst = Vec::new();
+    blob.to_writer(&mut dst, Endianness::BigEndian).unwrap();
+    let read = Blob::from_reader(&mut io::Cursor::new(dst), Endianness::BigEndian).unwrap();
+    assert_eq!(read, blob);
+}
+
+#[test]
+fn write_compact() {
+    let value = Value::from_snbt(r#"{b: [B; 1b, 2b], a: {}, "key with space": "it's \"x\"",
+        l: [[I; ], [I; 3]], n: [1L, -2L], f: 1.0f, d: -0.25d, s: " "}"#).unwrap();
+    assert_eq!(value.to_snbt(),
+               r#"{a:{},b:[B;1b,2b],d:-0.25d,f:1.0f,"key with space":"it's \"x\"",l:[[I;],[I;3]],n:[1L,-2L],s:" "}"#);
+    assert_eq!(Value::String(r#"say "hi""#.to_string()).to_snbt(), r#"'say "hi"'"#);
+    assert_eq!(Value::String(r"back\slash".to_string()).to_snbt(), r#""back\\slash""#);
+    assert_eq!(Value::Float(1e20).to_snbt(), "1e20f");
+}
+
+#[test]
+fn write_pretty() {
+    let value = Value::from_snbt("{pos: [1, 2], items: [{id: \"a\"}, {}], arr: [L; 1L, 2L]}").unwrap();
+    let expected = "{
+    arr: [L; 1L, 2L],
+    items: [
+        {
+            id: \"a\"
+        },
+        {}
+    ],
+    pos: [1, 2]
+}";
+    assert_eq!(value.to_snbt_pretty(), expected);
+    assert_eq!(Value::from_snbt(expected).unwrap(), value);
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn fixtures_roundtrip_through_snbt() {
+    for path in &["tests/big1.nbt", "tests/complex_player.dat", "tests/level.dat"] {
+        let mut file = std::fs::File::open(path).unwrap();
+        let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+        assert_eq!(Blob::from_snbt(&blob.to_snbt()).unwrap().to_snbt(), blob.to_snbt());
+        assert_eq!(Blob::from_snbt(&blob.to_snbt_pretty()).unwrap().to_snbt(), blob.to_snbt());
+    }
+}
@@ -0,0 +1,217 @@
+//! Tests for the streaming `NbtReader` and `NbtWriter`.
+#![cfg(feature = "std")]
+
+extern crate nbt;
+
+use nbt::{Blob, Endianness, Error, NbtReader, NbtWriter, Token, Value};
+
+/// Rebuilds the `Value` that starts with `first` from the following tokens.
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+fn build<R: std::io::Read>(first: Token, reader: &mut NbtReader<R>) -> Value {
+    match first {
+        Token::Byte(_, v) => Value::Byte(v),
+        Token::Short(_, v) => Value::Short(v),
+        Token::Int(_, v) => Value::Int(v),
+        Token::Long(_, v) => Value::Long(v),
+        Token::Float(_, v) => Value::Float(v),
+        Token::Double(_, v) => Value::Double(v),
+        Token::ByteArray(_, v) => Value::ByteArray(v),
+        Token::String(_, v) => Value::String(v),
+        Token::IntArray(_, v) => Value::IntArray(v),
+        Token::LongArray(_, v) => Value::LongArray(v),
+        Token::ListStart(_, _, len) => {
+            let mut vals = Vec::new();
+            loop {
+                match reader.next_token().unwrap().unwrap() {
+                    Token::End => break,
+                    token => {
+                        assert!(token.name().is_none());
+                        vals.push(build(token, reader));
+                    }
+                }
+            }
+            assert_eq!(vals.len(), len as usize);
+            Value::List(vals)
+        }
+        Token::CompoundStart(_) => {
+            let mut map = nbt::Map::new();
+            loop {
+                match reader.next_token().unwrap().unwrap() {
+                    Token::End => break,
+                    token => {
+                        let name = token.name().unwrap().to_string();
+                        map.insert(name, build(token, reader));
+                    }
+                }
+            }
+            Value::Compound(map)
+        }
+        Token::End => panic!("unexpected End"),
+    }
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn tokens_rebuild_fixtures() {
+    for path in &["tests/big1.nbt", "tests/complex_player.dat", "tests/level.dat"] {
+        let mut file = std::fs::File::open(path).unwrap();
+        let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+        for &endian in &[Endianness::BigEndian, Endianness::NetworkLittleEndian] {
+            let mut bytes = Vec::new();
+            blob.to_writer(&mut bytes, endian).unwrap();
+
+            let mut reader = NbtReader::new(&bytes[..], endian);
+            let first = reader.next_token().unwrap().unwrap();
+            let mut rebuilt = Blob::named(first.name().unwrap());
+            let value = build(first, &mut reader);
+            assert_eq!(reader.depth(), 0);
+            assert_eq!(reader.next_token().unwrap(), None);
+
+            for (name, value) in value.into_compound().unwrap() {
+                rebuilt.insert(name, value).unwrap();
+            }
+            assert_eq!(rebuilt, blob);
+        }
+    }
+}
+
+#[test]
+fn stops_after_root() {
+    let mut blob = Blob::named("root");
+    blob.insert("a", 1i8).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    bytes.extend_from_slice(&[0xde, 0xad]);
+
+    let mut reader = NbtReader::new(&bytes[..], Endianness::BigEndian);
+    assert_eq!(reader.next_token().unwrap(), Some(Token::CompoundStart(Some("root".to_string()))));
+    assert_eq!(reader.next_token().unwrap(), Some(Token::Byte(Some("a".to_string()), 1)));
+    assert_eq!(reader.next_token().unwrap(), Some(Token::End));
+    assert_eq!(reader.next_token().unwrap(), None);
+    assert_eq!(reader.into_inner(), &[0xde, 0xad]);
+}
+
+#[test]
+fn skip_containers() {
+    let blob = Blob::from_snbt("{a: {x: [1, 2], y: {}}, b: [[1b], [2b]], c: 3}").unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+
+    let mut reader = NbtReader::new(&bytes[..], Endianness::BigEndian);
+    let mut seen = Vec::new();
+    reader.next_token().unwrap();
+    while let Some(token) = reader.next_token().unwrap() {
+        match token {
+            Token::CompoundStart(ref name) | Token::ListStart(ref name, _, _) => {
+                seen.push(name.clone().unwrap());
+                reader.skip_container().unwrap();
+            }
+            Token::End => assert_eq!(reader.depth(), 0),
+            other => seen.push(other.name().unwrap().to_string()),
+        }
+    }
+    seen.sort();
+    assert_eq!(seen, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn errors() {
+    // A truncated document.
+    let mut reader = NbtReader::new(&[0x0a, 0x00, 0x00, 0x01, 0x00][..], Endianness::BigEndian);
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+
+    let mut reader = NbtReader::new(&[0x0a, 0x00, 0x00, 0x0d, 0x00, 0x00][..], Endianness::BigEndian);
+    reader.next_token().unwrap();
+    assert_eq!(reader.next_token(), Err(Error::InvalidTypeId(0x0d)));
+
+    // A non-empty list of TAG_End.
+    let bytes = [0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    let mut reader = NbtReader::new(&bytes[..], Endianness::BigEndian);
+    assert_eq!(reader.next_token().unwrap(), Some(Token::ListStart(Some("".to_string()), 0x00, 1)));
+    assert_eq!(reader.next_token(), Err(Error::InvalidTypeId(0x00)));
+
+    let mut reader = NbtReader::new(&[0x00][..], Endianness::BigEndian);
+    assert_eq!(reader.next_token(), Err(Error::NoRootCompound));
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn writer_replays_reader() {
+    for path in &["tests/big1.nbt", "tests/complex_player.dat", "tests/level.dat"] {
+        let mut file = std::fs::File::open(path).unwrap();
+        let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+        let mut bytes = Vec::new();
+        blob.to_writer(&mut bytes, Endianness::LittleEndian).unwrap();
+
+        let mut writer = NbtWriter::new(Vec::new(), Endianness::LittleEndian);
+        for token in NbtReader::new(&bytes[..], Endianness::LittleEndian) {
+            writer.write_token(&token.unwrap()).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), bytes);
+    }
+}
+
+#[test]
+fn writer_typed_methods() {
+    let mut writer = NbtWriter::new(Vec::new(), Endianness::BigEndian);
+    writer.begin_compound("root").unwrap();
+    writer.write_byte("b", 1).unwrap();
+    writer.write_short("s", 2).unwrap();
+    writer.write_long("l", 3).unwrap();
+    writer.write_float("f", 4.0).unwrap();
+    writer.write_double("d", 5.0).unwrap();
+    writer.write_string("str", "six").unwrap();
+    writer.write_byte_array("ba", &[7]).unwrap();
+    writer.write_int_array("ia", &[8]).unwrap();
+    writer.write_long_array("la", &[9]).unwrap();
+    writer.begin_list("empty", 0x00, 0).unwrap();
+    writer.end().unwrap();
+    writer.begin_list("items", 0x0a, 2).unwrap();
+    for i in 0..2 {
+        writer.begin_compound("ignored").unwrap();
+        writer.write_value("id", &Value::String(format!("item{}", i))).unwrap();
+        writer.end().unwrap();
+    }
+    writer.end().unwrap();
+    assert_eq!(writer.depth(), 1);
+    writer.end().unwrap();
+    let bytes = writer.finish().unwrap();
+
+    let expected = Blob::from_snbt(r#"{b: 1b, s: 2s, l: 3L, f: 4.0f, d: 5.0d, str: "six",
+        ba: [B; 7b], ia: [I; 8], la: [L; 9L], empty: [], items: [{id: "item0"}, {id: "item1"}]}"#).unwrap();
+    let read = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read.to_snbt(), expected.to_snbt());
+}
+
+#[test]
+fn writer_validation() {
+    let mut writer = NbtWriter::new(Vec::new(), Endianness::BigEndian);
+    assert_eq!(writer.end(), Err(Error::InvalidNesting("there is no open List or Compound to end")));
+    writer.begin_compound("").unwrap();
+    writer.begin_list("l", 0x03, 2).unwrap();
+    assert_eq!(writer.write_short("", 1), Err(Error::HeterogeneousList));
+    writer.write_int("", 1).unwrap();
+    assert_eq!(writer.end(),
+               Err(Error::InvalidNesting("a List was ended before all of its elements were written")));
+    writer.write_int("", 2).unwrap();
+    assert_eq!(writer.write_int("", 3),
+               Err(Error::InvalidNesting("a List was given more elements than declared")));
+    writer.end().unwrap();
+    assert_eq!(writer.begin_list("bad", 0x0d, 0), Err(Error::InvalidTypeId(0x0d)));
+    assert_eq!(writer.begin_list("bad", 0x00, 1), Err(Error::InvalidTypeId(0x00)));
+    assert_eq!(writer.begin_list("bad", 0x01, -1),
+               Err(Error::InvalidNesting("a List cannot have a negative length")));
+
+    // Failed calls write nothing, so the output is still valid.
+    writer.end().unwrap();
+    assert_eq!(writer.write_int("extra", 1), Err(Error::InvalidNesting("the document is already complete")));
+    let bytes = writer.finish().unwrap();
+    let read = Blob::from_reader(&mut &bytes[..], Endianness::BigEndian).unwrap();
+    assert_eq!(read["l"], Value::List(vec![Value::Int(1), Value::Int(2)]));
+
+    let mut writer = NbtWriter::new(Vec::new(), Endianness::BigEndian);
+    writer.begin_compound("").unwrap();
+    assert!(writer.finish().is_err());
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+
+use nbt::formats::structure::{BlockState, Structure};
+use nbt::{Blob, Endianness, Value};
+
+fn igloo() -> Value {
+    nbt!({
+        DataVersion: 3465i32,
+        size: [2i32, 1i32, 1i32],
+        palette: [
+            { Name: "minecraft:snow_block" },
+            { Name: "minecraft:furnace", Properties: { facing: "west", lit: "false" } },
+        ],
+        blocks: [
+            { state: 0i32, pos: [0i32, 0i32, 0i32] },
+            { state: 1i32, pos: [1i32, 0i32, 0i32], nbt: { id: "minecraft:furnace", BurnTime: 0i16, RecipesUsed: {}, Items: [] } },
+        ],
+        entities: [
+            { pos: [0.5, 0.0, 0.5], blockPos: [0i32, 0i32, 0i32], nbt: { id: "minecraft:villager", Brain: { memories: {} }, Gossips: [{ Target: [I; 1, 2, 3, 4], Value: 5i32 }] } },
+        ],
+    })
+}
+
+#[test]
+fn structure_files() {
+    // Keys a newer version might add are skipped.
+    let mut blob = Blob::from_value(igloo()).unwrap();
+    blob.insert("author", "?").unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let structure = Structure::from_reader(&bytes[..]).unwrap();
+
+    assert_eq!(structure.data_version, 3465);
+    assert_eq!(structure.size, vec![2, 1, 1]);
+    let (block, state) = structure.block_at([1, 0, 0]).unwrap();
+    assert_eq!(state, &BlockState::new("minecraft:furnace").with("facing", "west").with("lit", "false"));
+    assert_eq!(state.to_string(), "minecraft:furnace[facing=west,lit=false]");
+    assert_eq!(block.nbt.as_ref().unwrap()["BurnTime"], Value::Short(0));
+    assert_eq!(structure.block_at([0, 0, 0]).unwrap().0.nbt, None);
+    assert!(structure.block_at([0, 0, 1]).is_none());
+    assert_eq!(structure.entities[0].block_pos, vec![0, 0, 0]);
+
+    // Writing it back gives the same NBT, down to the tags of the entity
+    // data, without the unknown key.
+    let mut written = Vec::new();
+    structure.to_writer(&mut written).unwrap();
+    assert_eq!(Value::from(Blob::from_reader(&mut &written[..], Endianness::BigEndian).unwrap()), igloo());
+}
+
+#[test]
+fn palettes() {
+    let mut structure = Structure::new([1, 1, 1]);
+    structure.palettes.push(vec![BlockState::new("minecraft:oak_planks")]);
+    structure.palettes.push(vec![BlockState::new("minecraft:spruce_planks")]);
+    assert_eq!(structure.default_palette(), &[BlockState::new("minecraft:oak_planks")][..]);
+
+    let mut bytes = Vec::new();
+    structure.to_gzip_writer(&mut bytes).unwrap();
+    let value = Value::from(Blob::from_gzip_reader(&mut &bytes[..], Endianness::BigEndian).unwrap());
+    assert_eq!(value.get("palette"), None);
+    assert_eq!(value["palettes"][1][0]["Name"], nbt!("minecraft:spruce_planks"));
+    assert_eq!(Structure::from_gzip_reader(&bytes[..]).unwrap(), structure);
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "formats")]
+
+#[macro_use]
+extern crate nbt;
+extern crate serde_json;
+
+use nbt::formats::text::{ClickEvent, Component, HoverEvent, Text};
+use nbt::{Endianness, Value};
+
+#[test]
+fn nbt_components() {
+    // How the game writes a List mixing Strings and Compounds.
+    let name = nbt!({
+        text: "",
+        extra: [{ "": "Sword of " }, { text: "Fire", color: "#ff4400", bold: 1i8 }],
+    });
+    let text: Text = nbt::from_value(name).unwrap();
+    assert_eq!(text.to_plain_string(), "Sword of Fire");
+    let component = match text {
+        Text::Component(ref component) => component,
+        Text::Plain(_) => panic!("expected a Compound"),
+    };
+    assert_eq!(component.extra[0], Text::from(Component::text("Sword of ")));
+    match component.extra[1] {
+        Text::Component(ref fire) => {
+            assert_eq!(fire.color.as_ref().unwrap(), "#ff4400");
+            assert_eq!(fire.bold, Some(true));
+            assert_eq!(fire.italic, None);
+        }
+        Text::Plain(_) => panic!("expected a Compound"),
+    }
+
+    let plain: Text = nbt::from_value(Value::from("Stick")).unwrap();
+    assert_eq!(plain, Text::from("Stick"));
+    assert_eq!(nbt::to_value(&plain).unwrap(), Value::from("Stick"));
+
+    let list: Text = nbt::from_value(nbt!([{ text: "a", color: "red" }, { text: "b" }])).unwrap();
+    assert_eq!(list.to_string(), "ab");
+}
+
+#[test]
+fn mixed_lists_are_wrapped() {
+    let mut component = Component::translate("chat.type.text");
+    component.with = vec![Text::from("Alex"), Text::from(Component::text("hello"))];
+    component.extra = vec![Text::from("!"), Text::from("?")];
+    let text = Text::from(component);
+
+    let value = nbt::to_value(&text).unwrap();
+    assert_eq!(value, nbt!({
+        translate: "chat.type.text",
+        with: [{ text: "Alex" }, { text: "hello" }],
+        extra: ["!", "?"],
+    }));
+    let bytes = nbt::to_vec(&text, None, Endianness::BigEndian).unwrap();
+    let read: Text = nbt::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(read.to_plain_string(), "chat.type.text!?");
+}
+
+#[test]
+fn json_components() {
+    let json = r#"{"text":"Click me","underlined":true,
+        "clickEvent":{"action":"open_url","value":"https://example.com"},
+        "hoverEvent":{"action":"show_text","contents":"Opens a link"},
+        "extra":[" now",{"keybind":"key.jump"}]}"#;
+    let text: Text = serde_json::from_str(json).unwrap();
+    let component = match text {
+        Text::Component(ref component) => component,
+        Text::Plain(_) => panic!("expected an object"),
+    };
+    assert_eq!(component.underlined, Some(true));
+    assert_eq!(component.click_event, Some(ClickEvent {
+        action: "open_url".to_string(),
+        value: "https://example.com".to_string(),
+    }));
+    assert_eq!(component.hover_event, Some(HoverEvent::show_text(&Text::from("Opens a link"))));
+    assert_eq!(text.to_plain_string(), "Click me nowkey.jump");
+
+    let written = serde_json::to_string(&text).unwrap();
+    assert!(written.contains(r#""extra":[{"text":" now"},{"keybind":"key.jump"}]"#));
+    assert_eq!(serde_json::from_str::<Text>(&written).unwrap().to_plain_string(), text.to_plain_string());
+}
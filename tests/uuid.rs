@@ -0,0 +1,65 @@
+//! Tests for the UUID conversions.
+#![cfg(feature = "std")]
+
+#[macro_use]
+extern crate nbt;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+
+use nbt::Value;
+
+const UUID: u128 = 0xf81d_4fae_7dec_11d0_a765_00a0_c91e_6bf6;
+
+#[test]
+fn uuid_representations() {
+    let ints = nbt::uuid::to_int_array(UUID);
+    assert_eq!(ints, [0xf81d_4fae_u32 as i32, 0x7dec_11d0, 0xa765_00a0_u32 as i32, 0xc91e_6bf6_u32 as i32]);
+    assert_eq!(nbt::uuid::from_int_array(ints), UUID);
+    let (most, least) = nbt::uuid::to_most_least(UUID);
+    assert_eq!((most, least), (0xf81d_4fae_7dec_11d0_u64 as i64, 0xa765_00a0_c91e_6bf6_u64 as i64));
+    assert_eq!(nbt::uuid::from_most_least(most, least), UUID);
+    assert_eq!(nbt::uuid::from_int_array([-1; 4]), u128::MAX);
+
+    assert_eq!(nbt::uuid::from_value(&nbt::uuid::to_value(UUID)), Some(UUID));
+    assert_eq!(nbt::uuid::from_value(&Value::IntArray(vec![1, 2, 3])), None);
+    assert_eq!(nbt::uuid::from_value(&nbt!([1i32, 2i32, 3i32, 4i32])), None);
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn legacy_pairs_in_files() {
+    use nbt::{Blob, Endianness};
+
+    // This player file predates the array form.
+    let player = Value::from(Blob::from_path("tests/complex_player.dat", Endianness::BigEndian).unwrap());
+    let uuid = nbt::uuid::from_legacy(&player, "UUID").unwrap();
+    let (most, least) = nbt::uuid::to_most_least(uuid);
+    assert_eq!(player["UUIDMost"], Value::Long(most));
+    assert_eq!(player["UUIDLeast"], Value::Long(least));
+
+    assert_eq!(nbt::uuid::from_legacy(&nbt!({ OwnerMost: 1i64, OwnerLeast: 2i64 }), "Owner"), Some(1 << 64 | 2));
+    assert_eq!(nbt::uuid::from_legacy(&nbt!({ OwnerMost: 1i64 }), "Owner"), None);
+    assert_eq!(nbt::uuid::from_legacy(&nbt!({ OwnerMost: 1i64, OwnerLeast: 2i32 }), "Owner"), None);
+    assert_eq!(nbt::uuid::from_legacy(&nbt!(1i64), "Owner"), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_with_module() {
+    use nbt::{Blob, Endianness};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Entity {
+        #[serde(with = "nbt::uuid")]
+        owner: u128,
+    }
+
+    let entity = Entity { owner: UUID };
+    let mut bytes = Vec::new();
+    nbt::to_writer(&mut bytes, &entity, None, Endianness::BigEndian).unwrap();
+    let blob = Blob::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(blob["owner"], nbt::uuid::to_value(UUID));
+    assert_eq!(nbt::from_slice::<Entity>(&bytes, Endianness::BigEndian).unwrap(), entity);
+    assert!(nbt::from_value::<Entity>(nbt!({ owner: [I; 1, 2] })).is_err());
+}
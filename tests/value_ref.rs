@@ -0,0 +1,156 @@
+//! Tests for the borrowed `ValueRef`.
+#![cfg(feature = "std")]
+
+extern crate nbt;
+
+use nbt::{Blob, Endianness, Error, Value, ValueArena, ValueRef};
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn value_ref_matches_blob() {
+    use nbt::raw::RawReader;
+
+    for path in &["tests/big1.nbt", "tests/complex_player.dat", "tests/level.dat"] {
+        let mut file = std::fs::File::open(path).unwrap();
+        let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian,
+                         Endianness::NetworkLittleEndian] {
+            let mut bytes = Vec::new();
+            blob.to_writer(&mut bytes, endian).unwrap();
+
+            let mut reader = RawReader::new(&bytes[..], endian);
+            let (id, _) = reader.emit_next_header().unwrap();
+            let expected = Value::from_raw_reader(id, &mut reader).unwrap();
+
+            let root = ValueRef::from_slice(&bytes, endian).unwrap();
+            assert_eq!(root.to_value(), expected);
+            assert_eq!(Value::from(&root), expected);
+        }
+    }
+}
+
+#[test]
+fn value_ref_borrows() {
+    let blob = Blob::from_snbt(
+        "{name: \"Herobrine\", data: [B; 1b, -2b], ints: [I; 1, -2], longs: [L; 3L], list: [a, b]}"
+    ).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let range = bytes.as_ptr_range();
+
+    let root = ValueRef::from_slice(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(root.id(), 0x0a);
+    assert_eq!(root.get("missing"), None);
+
+    let name = root.get("name").and_then(ValueRef::as_str).unwrap();
+    assert_eq!(name, "Herobrine");
+    assert!(range.contains(&name.as_ptr()));
+
+    match root.get("data") {
+        Some(&ValueRef::ByteArray(data)) => {
+            assert_eq!(data, &[1, -2]);
+            assert!(range.contains(&(data.as_ptr() as *const u8)));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match root.get("ints") {
+        Some(ValueRef::IntArray(ints)) => {
+            assert_eq!(ints.len(), 2);
+            assert_eq!(ints.to_vec(), vec![1, -2]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match root.get("longs") {
+        Some(ValueRef::LongArray(longs)) => assert_eq!(longs.iter().collect::<Vec<_>>(), vec![3]),
+        other => panic!("unexpected {:?}", other),
+    }
+    match root.get("list") {
+        Some(ValueRef::List(list)) => {
+            let items: Vec<_> = list.iter().map(|v| v.as_str().unwrap()).collect();
+            assert_eq!(items, vec!["a", "b"]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn value_ref_errors() {
+    // Not a compound.
+    let err = ValueRef::from_slice(&[0x01, 0x00, 0x00, 0x05], Endianness::BigEndian);
+    assert_eq!(err, Err(Error::NoRootCompound));
+
+    // A byte array running past the end of the input.
+    let err = ValueRef::from_slice(
+        &[0x0a, 0x00, 0x00, 0x07, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x04, 0x01],
+        Endianness::BigEndian,
+    );
+    assert_eq!(err, Err(Error::IncompleteNbtValue));
+
+    // A truncated int array.
+    let err = ValueRef::from_slice(
+        &[0x0a, 0x00, 0x00, 0x0b, 0x00, 0x01, b'a', 0x00, 0x00, 0x00, 0x01, 0x00, 0x00],
+        Endianness::BigEndian,
+    );
+    assert!(err.is_err());
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn arena_matches_blob() {
+    let mut arena = ValueArena::new();
+    for path in &["tests/big1.nbt", "tests/complex_player.dat", "tests/level.dat"] {
+        let mut file = std::fs::File::open(path).unwrap();
+        let blob = Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap();
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian,
+                         Endianness::NetworkLittleEndian] {
+            let mut bytes = Vec::new();
+            blob.to_writer(&mut bytes, endian).unwrap();
+
+            let root = arena.parse(&bytes, endian).unwrap();
+            assert_eq!(root.to_value(), Value::from(blob.clone()));
+            assert_eq!(root.to_value_ref(), ValueRef::from_slice(&bytes, endian).unwrap());
+        }
+    }
+}
+
+#[test]
+fn arena_refs() {
+    let blob = Blob::from_snbt(
+        "{name: \"Herobrine\", odd: \"\\u0000\", data: [B; 1b], list: [{a: 1}, {}], empty: []}"
+    ).unwrap();
+    let mut bytes = Vec::new();
+    blob.to_writer(&mut bytes, Endianness::BigEndian).unwrap();
+    let range = bytes.as_ptr_range();
+
+    let mut arena = ValueArena::new();
+    let root = arena.parse(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(root.id(), 0x0a);
+    assert_eq!(root.name(), "");
+    assert_eq!(root.len(), 5);
+    assert!(root.get("missing").is_none());
+
+    let name = root.get("name").and_then(|v| v.as_str()).unwrap();
+    assert_eq!(name, "Herobrine");
+    assert!(range.contains(&name.as_ptr()));
+    // Modified UTF-8 encodes NUL in two bytes, so it is decoded.
+    assert_eq!(root.get("odd").and_then(|v| v.as_str()), Some("\u{0}"));
+    assert_eq!(root.get("data").unwrap().to_value_ref(), ValueRef::ByteArray(&[1]));
+
+    let list = root.get("list").unwrap();
+    assert_eq!(list.iter().len(), 2);
+    let first = list.iter().next().unwrap();
+    assert_eq!(first.name(), "");
+    assert_eq!(first.get("a").unwrap().to_value_ref(), ValueRef::Int(1));
+    assert!(list.iter().nth(1).unwrap().is_empty());
+    assert!(root.get("empty").unwrap().is_empty());
+
+    let names: Vec<_> = root.iter().map(|entry| entry.name()).collect();
+    assert_eq!(names.len(), 5);
+    assert!(names.contains(&"list"));
+
+    // The arena can be reused after an error.
+    let err = arena.parse(&bytes[..bytes.len() - 1], Endianness::BigEndian).unwrap_err();
+    assert_eq!(err, Error::IncompleteNbtValue);
+    let root = arena.parse(&bytes, Endianness::BigEndian).unwrap();
+    assert_eq!(root.to_value(), Value::from(blob));
+}
@@ -0,0 +1,81 @@
+//! Tests for Zstandard compression.
+#![cfg(feature = "experimental_zstd")]
+
+extern crate nbt;
+
+mod common;
+
+use std::fs::File;
+
+use nbt::{Blob, Compression, Endianness, Error, ReadOptions, WriteOptions};
+
+/// The level.dat fixture, as compressed by the reference encoder.
+fn level() -> Blob {
+    let mut file = File::open("tests/level_zstd.dat").unwrap();
+    Blob::from_zstd_reader(&mut file, Endianness::BigEndian).unwrap()
+}
+
+#[cfg(any(feature = "compression", feature = "rust_backend"))]
+#[test]
+fn read_reference_frame() {
+    // Compressed by the reference encoder, with Huffman-coded literals.
+    let mut file = File::open("tests/level.dat").unwrap();
+    assert_eq!(level(), Blob::from_gzip_reader(&mut file, Endianness::BigEndian).unwrap());
+}
+
+#[test]
+fn zstd_roundtrip() {
+    // Zstandard blocks are 128 KiB, twice the size of LZ4's.
+    for blob in &[level(), common::large_blob(2), Blob::new()] {
+        for &endian in &[Endianness::BigEndian, Endianness::LittleEndian] {
+            let mut dst = Vec::new();
+            blob.to_zstd_writer(&mut dst, endian).unwrap();
+            assert_eq!(&Blob::from_zstd_reader(&mut &dst[..], endian).unwrap(), blob);
+
+            let mut raw = Vec::new();
+            blob.to_writer(&mut raw, endian).unwrap();
+            assert!(dst.len() < raw.len() || raw.len() < 100);
+        }
+    }
+}
+
+#[test]
+fn zstd_with_options() {
+    let blob = level();
+    let write = WriteOptions::new(Endianness::LittleEndian).compression(Compression::Zstd);
+    let mut dst = Vec::new();
+    blob.to_writer_with(&mut dst, &write).unwrap();
+    assert_eq!(&dst[..4], &[0x28, 0xb5, 0x2f, 0xfd]);
+    let read = ReadOptions::new(Endianness::LittleEndian).compression(Compression::Zstd);
+    assert_eq!(Blob::from_reader_with(&mut &dst[..], &read).unwrap(), blob);
+    #[cfg(any(feature = "compression", feature = "rust_backend"))]
+    assert_eq!(Blob::from_reader_auto(&mut &dst[..], Endianness::LittleEndian).unwrap(), blob);
+
+    let mut region = nbt::region::RegionFile::create_in(std::io::Cursor::new(Vec::new())).unwrap();
+    assert_eq!(region.write_chunk(0, 0, &blob, Compression::Zstd).err(), Some(Error::UnknownChunkCompression(127)));
+}
+
+#[test]
+fn corrupt_zstd_frames() {
+    let src = std::fs::read("tests/level_zstd.dat").unwrap();
+
+    for len in 0..src.len() {
+        assert!(Blob::from_zstd_reader(&mut &src[..len], Endianness::BigEndian).is_err());
+    }
+
+    let mut bad_magic = src.clone();
+    bad_magic[0] ^= 1;
+    assert!(Blob::from_zstd_reader(&mut &bad_magic[..], Endianness::BigEndian).is_err());
+
+    let mut bad_checksum = src.clone();
+    let last = bad_checksum.len() - 1;
+    bad_checksum[last] ^= 1;
+    assert!(Blob::from_zstd_reader(&mut &bad_checksum[..], Endianness::BigEndian).is_err());
+
+    // Flipping bytes must not panic; the checksum catches what decodes.
+    for i in 0..src.len() {
+        let mut corrupt = src.clone();
+        corrupt[i] ^= 0x5a;
+        assert!(Blob::from_zstd_reader(&mut &corrupt[..], Endianness::BigEndian).is_err());
+    }
+}